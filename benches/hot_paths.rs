@@ -0,0 +1,66 @@
+//! The innermost loops of a render, run with `cargo bench -p ray-tracer`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ray_tracer::prelude::*;
+
+fn transform() -> Matrix4 {
+    Matrix4::translation(1., -2., 3.)
+        * Matrix4::rotation_y(0.7)
+        * Matrix4::rotation_x(-0.3)
+        * Matrix4::scaling(2., 0.5, 1.5)
+}
+
+fn matrices(c: &mut Criterion) {
+    let a = transform();
+    let b = Matrix4::rotation_z(1.2) * Matrix4::translation(0., 1., 0.);
+    let point = Tuple::point(1., 2., 3.);
+
+    c.bench_function("matrix4 inverse", |bench| {
+        bench.iter(|| black_box(a).inverse())
+    });
+    c.bench_function("matrix4 cofactor inverse", |bench| {
+        bench.iter(|| black_box(a).cofactor_inverse())
+    });
+    c.bench_function("matrix4 multiply", |bench| {
+        bench.iter(|| black_box(a) * black_box(b))
+    });
+    c.bench_function("matrix4 times point", |bench| {
+        bench.iter(|| black_box(a) * black_box(point))
+    });
+}
+
+fn intersections(c: &mut Criterion) {
+    let mut sphere = Object::sphere();
+    sphere.set_transform(transform());
+    let hit = Ray::new(Point3::new(1., -2., -10.), Vec3::new(0., 0., 1.));
+    let miss = Ray::new(Point3::new(10., 10., -10.), Vec3::new(0., 0., 1.));
+
+    c.bench_function("ray sphere hit", |bench| {
+        bench.iter(|| black_box(&sphere).intersect(black_box(hit)).len())
+    });
+    c.bench_function("ray sphere miss", |bench| {
+        bench.iter(|| black_box(&sphere).intersect(black_box(miss)).len())
+    });
+}
+
+fn refraction(c: &mut Criterion) {
+    let mut world = World::new();
+    for shell in 1..=3 {
+        let scale = shell as f64;
+        let mut sphere = Object::sphere();
+        sphere.set_transform(Matrix4::scaling(scale, scale, scale));
+        let mut material = Material::new();
+        material.transparency = 1.0;
+        material.refractive_index = 1. + scale / 10.;
+        sphere.set_material(material);
+        world.add_object(sphere);
+    }
+    let ray = Ray::new(Point3::new(0., 0., -10.), Vec3::new(0., 0., 1.));
+
+    c.bench_function("color through nested glass", |bench| {
+        bench.iter(|| world.color_at_with_depth(black_box(ray), 1))
+    });
+}
+
+criterion_group!(benches, matrices, intersections, refraction);
+criterion_main!(benches);
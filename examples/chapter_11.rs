@@ -1,8 +1,4 @@
-use examples;
-use ray_tracer::{
-    camera::Camera, color::Color, light::Light, material::Material, math::matrix4::Matrix4,
-    math::transformations, math::tuple::Tuple, pattern::Pattern, shape::Object, world::World,
-};
+use ray_tracer::prelude::*;
 
 pub fn scene(width: usize, height: usize) -> (Camera, World) {
     let mut world = World::new();
@@ -23,7 +19,7 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
 
     /* Walls */
     let mut floor = Object::plane();
-    floor.transform = Matrix4::rotation_y(0.31415);
+    floor.set_transform(Matrix4::rotation_y(0.31415));
     let mut material = Material::with_pattern(Pattern::checkered(
         Color::new(0.35, 0.35, 0.35),
         Color::new(0.65, 0.65, 0.65),
@@ -34,7 +30,7 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
     world.add_object(floor);
 
     let mut ceiling = Object::plane();
-    ceiling.transform = Matrix4::translation(0., 5., 0.);
+    ceiling.set_transform(Matrix4::translation(0., 5., 0.));
     let mut material = Material::new();
     material.color = Color::new(0.8, 0.8, 0.8);
     material.ambient = 0.3;
@@ -43,36 +39,40 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
     world.add_object(ceiling);
 
     let mut west_wall = Object::plane();
-    west_wall.transform = Matrix4::translation(-5., 0., 0.)
-        * Matrix4::rotation_z(1.5708)
-        * Matrix4::rotation_y(1.5708);
+    west_wall.set_transform(
+        Matrix4::translation(-5., 0., 0.)
+            * Matrix4::rotation_z(1.5708)
+            * Matrix4::rotation_y(1.5708),
+    );
     material = wall_material;
     west_wall.set_material(material);
     world.add_object(west_wall);
 
     let mut east_wall = Object::plane();
-    east_wall.transform = Matrix4::translation(5., 0., 0.)
-        * Matrix4::rotation_z(1.5708)
-        * Matrix4::rotation_y(1.5708);
+    east_wall.set_transform(
+        Matrix4::translation(5., 0., 0.)
+            * Matrix4::rotation_z(1.5708)
+            * Matrix4::rotation_y(1.5708),
+    );
     material = wall_material;
     east_wall.set_material(material);
     world.add_object(east_wall);
 
     let mut north_wall = Object::plane();
-    north_wall.transform = Matrix4::translation(0., 0., 5.) * Matrix4::rotation_x(1.5708);
+    north_wall.set_transform(Matrix4::translation(0., 0., 5.) * Matrix4::rotation_x(1.5708));
     material = wall_material;
     north_wall.set_material(material);
     world.add_object(north_wall);
 
     let mut south_wall = Object::plane();
-    south_wall.transform = Matrix4::translation(0., 0., -5.) * Matrix4::rotation_x(1.5708);
+    south_wall.set_transform(Matrix4::translation(0., 0., -5.) * Matrix4::rotation_x(1.5708));
     material = wall_material;
     south_wall.set_material(material);
     world.add_object(south_wall);
 
     /* Background balls */
     let mut bg1 = Object::sphere();
-    bg1.transform = Matrix4::translation(4.6, 0.4, 1.) * Matrix4::scaling(0.4, 0.4, 0.4);
+    bg1.set_transform(Matrix4::translation(4.6, 0.4, 1.) * Matrix4::scaling(0.4, 0.4, 0.4));
     let mut material = Material::new();
     material.color = Color::new(0.8, 0.5, 0.3);
     material.shininess = 50.;
@@ -80,7 +80,7 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
     world.add_object(bg1);
 
     let mut bg2 = Object::sphere();
-    bg2.transform = Matrix4::translation(4.7, 0.3, 0.4) * Matrix4::scaling(0.3, 0.3, 0.3);
+    bg2.set_transform(Matrix4::translation(4.7, 0.3, 0.4) * Matrix4::scaling(0.3, 0.3, 0.3));
     let mut material = Material::new();
     material.color = Color::new(0.9, 0.4, 0.5);
     material.shininess = 50.;
@@ -88,7 +88,7 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
     world.add_object(bg2);
 
     let mut bg3 = Object::sphere();
-    bg3.transform = Matrix4::translation(-1., 0.5, 4.5) * Matrix4::scaling(0.5, 0.5, 0.5);
+    bg3.set_transform(Matrix4::translation(-1., 0.5, 4.5) * Matrix4::scaling(0.5, 0.5, 0.5));
     let mut material = Material::new();
     material.color = Color::new(0.4, 0.9, 0.6);
     material.shininess = 50.;
@@ -96,7 +96,7 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
     world.add_object(bg3);
 
     let mut bg4 = Object::sphere();
-    bg4.transform = Matrix4::translation(-1.7, 0.3, 4.7) * Matrix4::scaling(0.3, 0.3, 0.3);
+    bg4.set_transform(Matrix4::translation(-1.7, 0.3, 4.7) * Matrix4::scaling(0.3, 0.3, 0.3));
     let mut material = Material::new();
     material.color = Color::new(0.4, 0.6, 0.9);
     material.shininess = 50.;
@@ -105,7 +105,7 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
 
     /* Foreground balls */
     let mut red_ball = Object::sphere();
-    red_ball.transform = Matrix4::translation(-0.6, 1., 0.6);
+    red_ball.set_transform(Matrix4::translation(-0.6, 1., 0.6));
     let mut material = Material::new();
     material.color = Color::new(1., 0.3, 0.2);
     material.shininess = 5.;
@@ -114,8 +114,8 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
     world.add_object(red_ball);
 
     let mut blue_glass_ball = Object::sphere();
-    blue_glass_ball.transform =
-        Matrix4::translation(0.6, 0.7, -0.6) * Matrix4::scaling(0.7, 0.7, 0.7);
+    blue_glass_ball
+        .set_transform(Matrix4::translation(0.6, 0.7, -0.6) * Matrix4::scaling(0.7, 0.7, 0.7));
     let mut material = Material::new();
     material.color = Color::new(0., 0., 0.2);
     material.ambient = 0.;
@@ -136,18 +136,18 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
     material.transparency = 0.9;
     material.refractive_index = 1.5;
     let mut green_glass_ball = Object::sphere();
-    green_glass_ball.transform =
-        Matrix4::translation(-0.7, 0.5, -0.8) * Matrix4::scaling(0.5, 0.5, 0.5);
+    green_glass_ball
+        .set_transform(Matrix4::translation(-0.7, 0.5, -0.8) * Matrix4::scaling(0.5, 0.5, 0.5));
     green_glass_ball.set_material(material);
     world.add_object(green_glass_ball);
 
     world.add_light(Light::point_light(
-        Tuple::point(-4.9, 4.9, -1.),
+        Point3::new(-4.9, 4.9, -1.),
         Color::white(),
     ));
 
-    let mut camera = Camera::new(width as i32, height as i32, 1.152);
-    camera.transform = transformations::view_transform(
+    let mut camera = Camera::new(width as i32, height as i32, Angle::radians(1.152));
+    camera.transform = view_transform(
         Tuple::point(-2.6, 1.5, -3.9),
         Tuple::point(-0.6, 1., -0.8),
         Tuple::vector(0., 1., 0.),
@@ -165,3 +165,35 @@ pub fn main() {
     let (camera, world) = scene(WIDTH, HEIGHT);
     examples::run_and_save_scene("chapter_11", camera, world);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ray_tracer::scene::Scene;
+
+    #[test]
+    fn the_scene_file_describes_the_same_scene() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../scenes/reflect-refract.yml");
+        let mut loaded = Scene::from_file(path).unwrap();
+        loaded.camera.hsize = 40;
+        loaded.camera.vsize = 20;
+        let (camera, world) = scene(40, 20);
+
+        let expected = camera.render_with_progress(&world, |_, _| {});
+        let image = loaded.camera.render_with_progress(&loaded.world, |_, _| {});
+
+        for y in 0..20 {
+            for x in 0..40 {
+                let (a, b) = (image.pixel_at(x, y), expected.pixel_at(x, y));
+                assert!(
+                    (a.red - b.red).abs() < 1e-6
+                        && (a.green - b.green).abs() < 1e-6
+                        && (a.blue - b.blue).abs() < 1e-6,
+                    "pixel {}, {}",
+                    x,
+                    y
+                );
+            }
+        }
+    }
+}
@@ -1,20 +1,16 @@
-use examples;
-use ray_tracer::{
-    camera::Camera, color::Color, light::Light, material::Material, math::matrix4::Matrix4,
-    math::transformations, math::tuple::Tuple, pattern::Pattern, shape::Object, world::World,
-};
+use ray_tracer::prelude::*;
 
 pub fn scene(width: usize, height: usize) -> (Camera, World) {
     let mut world = World::new();
 
     world.add_light(Light::point_light(
-        Tuple::point(0., 6.9, -5.),
+        Point3::new(0., 6.9, -5.),
         Color::new(1., 1., 0.9),
     ));
 
     /* The floor / ceiling */
     let mut floor = Object::cube();
-    floor.transform = Matrix4::scaling(20., 7., 20.) * Matrix4::translation(0., 1., 0.);
+    floor.set_transform(Matrix4::scaling(20., 7., 20.) * Matrix4::translation(0., 1., 0.));
     let mut pattern = Pattern::checkered(Color::new(0., 0., 0.), Color::new(0.25, 0.25, 0.25));
     *pattern.transform_mut() = Matrix4::scaling(0.07, 0.07, 0.07);
     let mut material = Material::with_pattern(pattern);
@@ -28,7 +24,7 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
 
     /* Walls */
     let mut walls = Object::cube();
-    walls.transform = Matrix4::scaling(10., 10., 10.);
+    walls.set_transform(Matrix4::scaling(10., 10., 10.));
     let mut pattern = Pattern::checkered(
         Color::new(0.4863, 0.3765, 0.2941),
         Color::new(0.3725, 0.2902, 0.2275),
@@ -45,7 +41,7 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
 
     /* Table top */
     let mut table_top = Object::cube();
-    table_top.transform = Matrix4::translation(0., 3.1, 0.) * Matrix4::scaling(3., 0.1, 2.);
+    table_top.set_transform(Matrix4::translation(0., 3.1, 0.) * Matrix4::scaling(3., 0.1, 2.));
     let mut pattern = Pattern::striped(
         Color::new(0.5529, 0.4235, 0.3255),
         Color::new(0.6588, 0.5098, 0.4000),
@@ -62,7 +58,7 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
 
     /* Leg 1 */
     let mut leg1 = Object::cube();
-    leg1.transform = Matrix4::translation(2.7, 1.5, -1.7) * Matrix4::scaling(0.1, 1.5, 0.1);
+    leg1.set_transform(Matrix4::translation(2.7, 1.5, -1.7) * Matrix4::scaling(0.1, 1.5, 0.1));
     let mut material = Material::new();
     material.color = Color::new(0.5529, 0.4235, 0.3255);
     material.ambient = 0.2;
@@ -72,7 +68,7 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
 
     /* Leg 2 */
     let mut leg2 = Object::cube();
-    leg2.transform = Matrix4::translation(2.7, 1.5, 1.7) * Matrix4::scaling(0.1, 1.5, 0.1);
+    leg2.set_transform(Matrix4::translation(2.7, 1.5, 1.7) * Matrix4::scaling(0.1, 1.5, 0.1));
     let mut material = Material::new();
     material.color = Color::new(0.5529, 0.4235, 0.3255);
     material.ambient = 0.2;
@@ -82,7 +78,7 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
 
     /* Leg 3 */
     let mut leg3 = Object::cube();
-    leg3.transform = Matrix4::translation(-2.7, 1.5, -1.7) * Matrix4::scaling(0.1, 1.5, 0.1);
+    leg3.set_transform(Matrix4::translation(-2.7, 1.5, -1.7) * Matrix4::scaling(0.1, 1.5, 0.1));
     let mut material = Material::new();
     material.color = Color::new(0.5529, 0.4235, 0.3255);
     material.ambient = 0.2;
@@ -92,7 +88,7 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
 
     /* Leg 4 */
     let mut leg4 = Object::cube();
-    leg4.transform = Matrix4::translation(-2.7, 1.5, 1.7) * Matrix4::scaling(0.1, 1.5, 0.1);
+    leg4.set_transform(Matrix4::translation(-2.7, 1.5, 1.7) * Matrix4::scaling(0.1, 1.5, 0.1));
     let mut material = Material::new();
     material.color = Color::new(0.5529, 0.4235, 0.3255);
     material.ambient = 0.2;
@@ -104,9 +100,11 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
 
     /* Glass cube */
     let mut glass_cube = Object::cube();
-    glass_cube.transform = Matrix4::translation(0., 3.45001, 0.)
-        * Matrix4::rotation_y(0.2)
-        * Matrix4::scaling(0.25, 0.25, 0.25);
+    glass_cube.set_transform(
+        Matrix4::translation(0., 3.45001, 0.)
+            * Matrix4::rotation_y(0.2)
+            * Matrix4::scaling(0.25, 0.25, 0.25),
+    );
     let mut material = Material::new();
     material.casts_shadows = false;
     material.color = Color::new(1., 1., 0.8);
@@ -122,9 +120,11 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
 
     /* Little cube 1 */
     let mut lil_cube1 = Object::cube();
-    lil_cube1.transform = Matrix4::translation(1., 3.35, -0.9)
-        * Matrix4::rotation_y(-0.4)
-        * Matrix4::scaling(0.15, 0.15, 0.15);
+    lil_cube1.set_transform(
+        Matrix4::translation(1., 3.35, -0.9)
+            * Matrix4::rotation_y(-0.4)
+            * Matrix4::scaling(0.15, 0.15, 0.15),
+    );
     let mut material = Material::new();
     material.color = Color::new(1., 0.5, 0.5);
     material.reflective = 0.6;
@@ -134,9 +134,11 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
 
     /* Little cube 2 */
     let mut lil_cube2 = Object::cube();
-    lil_cube2.transform = Matrix4::translation(-1.5, 3.27, 0.3)
-        * Matrix4::rotation_y(0.4)
-        * Matrix4::scaling(0.15, 0.07, 0.15);
+    lil_cube2.set_transform(
+        Matrix4::translation(-1.5, 3.27, 0.3)
+            * Matrix4::rotation_y(0.4)
+            * Matrix4::scaling(0.15, 0.07, 0.15),
+    );
     let mut material = Material::new();
     material.color = Color::new(1., 1., 0.5);
     lil_cube2.set_material(material);
@@ -144,9 +146,11 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
 
     /* Little cube 3 */
     let mut lil_cube3 = Object::cube();
-    lil_cube3.transform = Matrix4::translation(0., 3.25, 1.)
-        * Matrix4::rotation_y(0.4)
-        * Matrix4::scaling(0.2, 0.05, 0.05);
+    lil_cube3.set_transform(
+        Matrix4::translation(0., 3.25, 1.)
+            * Matrix4::rotation_y(0.4)
+            * Matrix4::scaling(0.2, 0.05, 0.05),
+    );
     let mut material = Material::new();
     material.color = Color::new(0.5, 1., 0.5);
     lil_cube3.set_material(material);
@@ -154,9 +158,11 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
 
     /* Little cube 4 */
     let mut lil_cube4 = Object::cube();
-    lil_cube4.transform = Matrix4::translation(-0.6, 3.4, -1.)
-        * Matrix4::rotation_y(0.8)
-        * Matrix4::scaling(0.05, 0.2, 0.05);
+    lil_cube4.set_transform(
+        Matrix4::translation(-0.6, 3.4, -1.)
+            * Matrix4::rotation_y(0.8)
+            * Matrix4::scaling(0.05, 0.2, 0.05),
+    );
     let mut material = Material::new();
     material.color = Color::new(0.5, 0.5, 1.);
     lil_cube4.set_material(material);
@@ -164,9 +170,11 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
 
     /* Little cube 5 */
     let mut lil_cube5 = Object::cube();
-    lil_cube5.transform = Matrix4::translation(2., 3.4, 1.)
-        * Matrix4::rotation_y(0.8)
-        * Matrix4::scaling(0.05, 0.2, 0.05);
+    lil_cube5.set_transform(
+        Matrix4::translation(2., 3.4, 1.)
+            * Matrix4::rotation_y(0.8)
+            * Matrix4::scaling(0.05, 0.2, 0.05),
+    );
     let mut material = Material::new();
     material.color = Color::new(0.5, 1., 1.);
     lil_cube5.set_material(material);
@@ -176,7 +184,7 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
 
     /* Frame 1 */
     let mut frame1 = Object::cube();
-    frame1.transform = Matrix4::translation(-10., 4., 1.) * Matrix4::scaling(0.05, 1., 1.);
+    frame1.set_transform(Matrix4::translation(-10., 4., 1.) * Matrix4::scaling(0.05, 1., 1.));
     let mut material = Material::new();
     material.color = Color::new(0.7098, 0.2471, 0.2196);
     material.diffuse = 0.6;
@@ -185,7 +193,7 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
 
     /* Frame 2 */
     let mut frame2 = Object::cube();
-    frame2.transform = Matrix4::translation(-10., 3.4, 2.7) * Matrix4::scaling(0.05, 0.4, 0.4);
+    frame2.set_transform(Matrix4::translation(-10., 3.4, 2.7) * Matrix4::scaling(0.05, 0.4, 0.4));
     let mut material = Material::new();
     material.color = Color::new(0.2667, 0.2706, 0.6902);
     material.diffuse = 0.6;
@@ -194,7 +202,7 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
 
     /* Frame 3 */
     let mut frame3 = Object::cube();
-    frame3.transform = Matrix4::translation(-10., 4.6, 2.7) * Matrix4::scaling(0.05, 0.4, 0.4);
+    frame3.set_transform(Matrix4::translation(-10., 4.6, 2.7) * Matrix4::scaling(0.05, 0.4, 0.4));
     let mut material = Material::new();
     material.color = Color::new(0.3098, 0.5961, 0.3098);
     material.diffuse = 0.6;
@@ -205,7 +213,7 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
 
     /* Mirror */
     let mut mirror = Object::cube();
-    mirror.transform = Matrix4::translation(-2., 3.5, 9.95) * Matrix4::scaling(4.8, 1.4, 0.06);
+    mirror.set_transform(Matrix4::translation(-2., 3.5, 9.95) * Matrix4::scaling(4.8, 1.4, 0.06));
     let mut material = Material::new();
     material.color = Color::new(0., 0., 0.);
     material.diffuse = 0.;
@@ -216,8 +224,8 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
     mirror.set_material(material);
     world.add_object(mirror);
 
-    let mut camera = Camera::new(width as i32, height as i32, 0.785);
-    camera.transform = transformations::view_transform(
+    let mut camera = Camera::new(width as i32, height as i32, Angle::radians(0.785));
+    camera.transform = view_transform(
         Tuple::point(8., 6., -8.),
         Tuple::point(0., 3., 0.),
         Tuple::vector(0., 1., 0.),
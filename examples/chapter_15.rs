@@ -1,9 +1,4 @@
-use examples;
-use ray_tracer::{
-    camera::Camera, color::Color, light::Light, material::Material, math::matrix4::Matrix4,
-    math::transformations, math::tuple::Tuple, obj::WavefrontObj, pattern::Pattern, shape::Object,
-    world::World,
-};
+use ray_tracer::prelude::*;
 use std::f64::{
     self,
     consts::{FRAC_PI_2, PI},
@@ -12,9 +7,9 @@ use std::f64::{
 pub fn scene(width: usize, height: usize) -> (Camera, World) {
     let mut world = World::new();
 
-    let light1 = Light::point_light(Tuple::point(50., 100., 20.), Color::new(0.5, 0.5, 0.5));
+    let light1 = Light::point_light(Point3::new(50., 100., 20.), Color::new(0.5, 0.5, 0.5));
     world.add_light(light1);
-    let light2 = Light::point_light(Tuple::point(2., 50., 100.), Color::new(0.5, 0.5, 0.5));
+    let light2 = Light::point_light(Point3::new(2., 50., 100.), Color::new(0.5, 0.5, 0.5));
     world.add_light(light2);
 
     /* ----------------------------- */
@@ -32,7 +27,7 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
     world.add_object(p);
 
     let mut p2 = Object::plane();
-    p2.transform = Matrix4::translation(0., 0., -10.) * Matrix4::rotation_x(FRAC_PI_2);
+    p2.set_transform(Matrix4::translation(0., 0., -10.) * Matrix4::rotation_x(FRAC_PI_2));
     p2.set_material(planes_material);
     world.add_object(p2);
 
@@ -42,18 +37,22 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
     low_poly_material.specular = 0.4;
 
     let mut teapot = WavefrontObj::from_file("./resources/teapot-low.obj").unwrap();
-    teapot.transform = Matrix4::translation(7., 0., 3.)
-        * Matrix4::rotation_y(PI * 23. / 22.)
-        * Matrix4::rotation_x(-PI / 2.)
-        * Matrix4::scaling(0.3, 0.3, 0.3);
+    teapot.set_transform(
+        Matrix4::translation(7., 0., 3.)
+            * Matrix4::rotation_y(PI * 23. / 22.)
+            * Matrix4::rotation_x(-PI / 2.)
+            * Matrix4::scaling(0.3, 0.3, 0.3),
+    );
     teapot.set_material(low_poly_material);
     world.add_object(teapot);
 
     let mut teapot2 = WavefrontObj::from_file("./resources/teapot-lowtri.obj").unwrap();
-    teapot2.transform = Matrix4::translation(-7., 0., 3.)
-        * Matrix4::rotation_y(-PI * 46. / 22.)
-        * Matrix4::rotation_x(-PI / 2.)
-        * Matrix4::scaling(0.3, 0.3, 0.3);
+    teapot2.set_transform(
+        Matrix4::translation(-7., 0., 3.)
+            * Matrix4::rotation_y(-PI * 46. / 22.)
+            * Matrix4::rotation_x(-PI / 2.)
+            * Matrix4::scaling(0.3, 0.3, 0.3),
+    );
     teapot2.set_material(low_poly_material);
     world.add_object(teapot2);
 
@@ -64,15 +63,17 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
     high_poly_material.reflective = 0.5;
 
     let mut teapot3 = WavefrontObj::from_file("./resources/teapot.obj").unwrap();
-    teapot3.transform = Matrix4::translation(0., 0., -5.)
-        * Matrix4::rotation_y(-PI)
-        * Matrix4::rotation_x(-PI / 2.)
-        * Matrix4::scaling(0.4, 0.4, 0.4);
+    teapot3.set_transform(
+        Matrix4::translation(0., 0., -5.)
+            * Matrix4::rotation_y(-PI)
+            * Matrix4::rotation_x(-PI / 2.)
+            * Matrix4::scaling(0.4, 0.4, 0.4),
+    );
     teapot3.set_material(high_poly_material);
     world.add_object(teapot3);
 
     let mut camera = Camera::new(width as i32, height as i32, FRAC_PI_2);
-    camera.transform = transformations::view_transform(
+    camera.transform = view_transform(
         Tuple::point(0., 7., 13.),
         Tuple::point(0., 1., 0.),
         Tuple::vector(0., 1., 0.),
@@ -2,9 +2,7 @@ use std::fs::File;
 use std::io::Write;
 
 use examples::output_file_path;
-use ray_tracer::canvas::Canvas;
-use ray_tracer::color::Color;
-use ray_tracer::math::tuple::Tuple;
+use ray_tracer::prelude::*;
 
 #[derive(Clone, Copy, Debug)]
 struct Projectile {
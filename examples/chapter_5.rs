@@ -1,8 +1,5 @@
 use examples::output_file_path;
-use ray_tracer::{
-    canvas::Canvas, color::Color, intersection::Intersection, math::tuple::Tuple, ray::Ray,
-    shape::Object,
-};
+use ray_tracer::prelude::*;
 use std::{fs::File, io::Write};
 
 pub fn scene(width: usize, height: usize) -> Canvas {
@@ -10,7 +7,7 @@ pub fn scene(width: usize, height: usize) -> Canvas {
     let sphere = Object::sphere();
     let red = Color::red();
 
-    let camera_origin = Tuple::point(0., 0., -5.);
+    let camera_origin = Point3::new(0., 0., -5.);
 
     let wall_distance = 10.;
     let wall_size = 7.;
@@ -20,12 +17,12 @@ pub fn scene(width: usize, height: usize) -> Canvas {
         let world_y = (wall_size / 2.) - pixel_size * y as f64;
         for x in 0..canvas.width() {
             let world_x = -(wall_size / 2.) + pixel_size * x as f64;
-            let position = Tuple::point(world_x, world_y, wall_distance);
+            let position = Point3::new(world_x, world_y, wall_distance);
             let r = Ray::new(camera_origin, (position - camera_origin).normalize());
 
             let intersections = sphere.intersect(r);
 
-            if let Some(_) = Intersection::hit(&intersections) {
+            if Intersection::hit(&intersections).is_some() {
                 canvas.write_pixel(x as i32, y as i32, red);
             }
         }
@@ -34,7 +31,7 @@ pub fn scene(width: usize, height: usize) -> Canvas {
     canvas
 }
 
-const ASPECT: f64 = 1. / 1.;
+const ASPECT: f64 = 1.;
 
 const WIDTH: usize = 300;
 const HEIGHT: usize = (WIDTH as f64 / ASPECT) as usize;
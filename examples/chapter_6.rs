@@ -1,14 +1,5 @@
 use examples::output_file_path;
-use ray_tracer::{
-    canvas::Canvas,
-    color::Color,
-    intersection::Intersection,
-    light::Light,
-    material::{self, Material},
-    math::tuple::Tuple,
-    ray::Ray,
-    shape::Object,
-};
+use ray_tracer::{material, prelude::*};
 use std::{fs::File, io::Write};
 
 pub fn scene(width: usize, height: usize) -> Canvas {
@@ -17,9 +8,9 @@ pub fn scene(width: usize, height: usize) -> Canvas {
     let mut material = Material::new();
     material.color = Color::new(1., 0.2, 1.);
 
-    let light = Light::point_light(Tuple::point(-10., 10., -10.), Color::white());
+    let light = Light::point_light(Point3::new(-10., 10., -10.), Color::white());
 
-    let camera_origin = Tuple::point(0., 0., -5.);
+    let camera_origin = Point3::new(0., 0., -5.);
     let wall_distance = 10.;
     let wall_size = 7.;
     let pixel_size = wall_size / canvas.width() as f64;
@@ -28,7 +19,7 @@ pub fn scene(width: usize, height: usize) -> Canvas {
         let world_y = (wall_size / 2.) - pixel_size * y as f64;
         for x in 0..canvas.width() {
             let world_x = -(wall_size / 2.) + pixel_size * x as f64;
-            let position = Tuple::point(world_x, world_y, wall_distance);
+            let position = Point3::new(world_x, world_y, wall_distance);
             let r = Ray::new(camera_origin, (position - camera_origin).normalize());
 
             let intersections = sphere.intersect(r);
@@ -45,7 +36,7 @@ pub fn scene(width: usize, height: usize) -> Canvas {
                     hit_point,
                     eye,
                     hit_normal_vector,
-                    false,
+                    Color::white(),
                 );
 
                 canvas.write_pixel(x as i32, y as i32, pixel_color);
@@ -56,7 +47,7 @@ pub fn scene(width: usize, height: usize) -> Canvas {
     canvas
 }
 
-const ASPECT: f64 = 1. / 1.;
+const ASPECT: f64 = 1.;
 
 const WIDTH: usize = 300;
 const HEIGHT: usize = (WIDTH as f64 / ASPECT) as usize;
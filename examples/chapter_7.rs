@@ -1,16 +1,12 @@
 use std::f64::consts::PI;
 
-use examples;
-use ray_tracer::{
-    camera::Camera, color::Color, light::Light, material::Material, math::matrix4::Matrix4,
-    math::transformations, math::tuple::Tuple, shape::Object, world::World,
-};
+use ray_tracer::prelude::*;
 
 pub fn scene(width: usize, height: usize) -> (Camera, World) {
     let mut world = World::new();
 
     let mut floor = Object::sphere();
-    floor.transform = Matrix4::scaling(10., 0.01, 10.);
+    floor.set_transform(Matrix4::scaling(10., 0.01, 10.));
     let mut floor_material = Material::new();
     floor_material.casts_shadows = false;
     floor_material.color = Color::new(1., 0.9, 0.9);
@@ -19,23 +15,27 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
     world.add_object(floor);
 
     let mut left_wall = Object::sphere();
-    left_wall.transform = Matrix4::translation(0., 0., 5.)
-        * Matrix4::rotation_y(-PI / 4.)
-        * Matrix4::rotation_x(PI / 2.)
-        * Matrix4::scaling(10., 0.01, 10.);
+    left_wall.set_transform(
+        Matrix4::translation(0., 0., 5.)
+            * Matrix4::rotation_y(-PI / 4.)
+            * Matrix4::rotation_x(PI / 2.)
+            * Matrix4::scaling(10., 0.01, 10.),
+    );
     left_wall.set_material(floor_material);
     world.add_object(left_wall);
 
     let mut right_wall = Object::sphere();
-    right_wall.transform = Matrix4::translation(0., 0., 5.)
-        * Matrix4::rotation_y(PI / 4.)
-        * Matrix4::rotation_x(PI / 2.)
-        * Matrix4::scaling(10., 0.01, 10.);
+    right_wall.set_transform(
+        Matrix4::translation(0., 0., 5.)
+            * Matrix4::rotation_y(PI / 4.)
+            * Matrix4::rotation_x(PI / 2.)
+            * Matrix4::scaling(10., 0.01, 10.),
+    );
     right_wall.set_material(floor_material);
     world.add_object(right_wall);
 
     let mut middle = Object::sphere();
-    middle.transform = Matrix4::translation(-0.5, 1., 0.5);
+    middle.set_transform(Matrix4::translation(-0.5, 1., 0.5));
     let mut middle_material = Material::new();
     middle_material.casts_shadows = false;
     middle_material.color = Color::new(0.1, 1., 0.5);
@@ -45,7 +45,7 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
     world.add_object(middle);
 
     let mut right = Object::sphere();
-    right.transform = Matrix4::translation(1.5, 0.5, -0.5) * Matrix4::scaling(0.5, 0.5, 0.5);
+    right.set_transform(Matrix4::translation(1.5, 0.5, -0.5) * Matrix4::scaling(0.5, 0.5, 0.5));
     let mut right_material = Material::new();
     right_material.casts_shadows = false;
     right_material.color = Color::new(0.5, 1., 0.1);
@@ -55,7 +55,9 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
     world.add_object(right);
 
     let mut left = Object::sphere();
-    left.transform = Matrix4::translation(-1.5, 0.33, -0.75) * Matrix4::scaling(0.33, 0.33, 0.33);
+    left.set_transform(
+        Matrix4::translation(-1.5, 0.33, -0.75) * Matrix4::scaling(0.33, 0.33, 0.33),
+    );
     let mut left_material = Material::new();
     left_material.casts_shadows = false;
     left_material.color = Color::new(1., 0.8, 0.1);
@@ -64,11 +66,11 @@ pub fn scene(width: usize, height: usize) -> (Camera, World) {
     left.set_material(left_material);
     world.add_object(left);
 
-    let light = Light::point_light(Tuple::point(-10., 10., -10.), Color::white());
+    let light = Light::point_light(Point3::new(-10., 10., -10.), Color::white());
     world.add_light(light);
 
     let mut camera = Camera::new(width as i32, height as i32, PI / 3.);
-    camera.transform = transformations::view_transform(
+    camera.transform = view_transform(
         Tuple::point(0., 1.5, -5.),
         Tuple::point(0., 1., 0.),
         Tuple::vector(0., 1., 0.),
@@ -1,17 +1,26 @@
 use std::{fs::File, io::Write};
 
-use ray_tracer::{camera::Camera, world::World};
+use ray_tracer::prelude::*;
 
 pub fn output_file_path(example_name: &str) -> String {
     format!("./output/{}.ppm", example_name)
 }
 
+/// Renders the scene coarse to fine, writing the image again after every pass,
+/// so it can be watched sharpening in an image viewer while tuning the scene.
 pub fn run_and_save_scene(example_name: &str, camera: Camera, world: World) {
     let file_name = output_file_path(example_name);
     println!("Writing scene to: {}", file_name);
 
-    let ppm = camera.render(&world).to_ppm();
+    let image = camera.render_coarse_to_fine(&world, &RenderSettings::default(), |preview| {
+        save(&file_name, preview)
+    });
 
-    let mut f = File::create(&file_name).expect("Unable to create file");
-    f.write_all(ppm.as_bytes()).expect("Unable to write data");
+    save(&file_name, &image);
+}
+
+fn save(file_name: &str, canvas: &Canvas) {
+    let mut f = File::create(file_name).expect("Unable to create file");
+    f.write_all(canvas.to_ppm().as_bytes())
+        .expect("Unable to write data");
 }
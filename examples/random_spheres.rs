@@ -0,0 +1,20 @@
+use std::time::Instant;
+
+use ray_tracer::{random_scene::RandomSpheres, sampler::Sampler};
+
+const WIDTH: usize = 600;
+const HEIGHT: usize = 400;
+
+/// Renders the random spheres scene, seeded by the first argument, and reports how long it took.
+pub fn main() {
+    let seed = std::env::args()
+        .nth(1)
+        .map(|seed| seed.parse().expect("the seed must be a number"))
+        .unwrap_or(0);
+    let mut scene = RandomSpheres::new(seed).scene(WIDTH, HEIGHT);
+    scene.camera.sampler = Sampler::new(4);
+
+    let start = Instant::now();
+    examples::run_and_save_scene("random_spheres", scene.camera, scene.world);
+    println!("\nRendered in {:.2?}", start.elapsed());
+}
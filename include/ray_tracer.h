@@ -0,0 +1,81 @@
+/*
+ * C interface to the ray tracer, implemented in src/ffi.rs.
+ *
+ * Build the library with `cargo build --release -p ray-tracer`,
+ * then link against target/release/libray_tracer.{so,dylib,a}.
+ *
+ * This header is maintained by hand: change it along with src/ffi.rs. A test there
+ * checks that every exported function and error code is declared here.
+ */
+
+#ifndef RAY_TRACER_H
+#define RAY_TRACER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define RT_OK 0
+#define RT_ERROR_NULL_POINTER -1
+#define RT_ERROR_NO_SUCH_OBJECT -2
+#define RT_ERROR_BUFFER_TOO_SMALL -3
+#define RT_ERROR_NOT_INVERTIBLE -4
+#define RT_ERROR_INVALID_SIZE -5
+#define RT_ERROR_RENDER_FAILED -6
+
+typedef struct RtWorld RtWorld;
+
+typedef struct RtMaterial {
+    double color[3];
+    double ambient;
+    double diffuse;
+    double specular;
+    double shininess;
+    double reflective;
+    double transparency;
+    double refractive_index;
+} RtMaterial;
+
+typedef struct RtCamera {
+    uint32_t width;
+    uint32_t height;
+    double field_of_view;
+    double from[3];
+    double to[3];
+    double up[3];
+} RtCamera;
+
+RtWorld *rt_world_new(void);
+void rt_world_free(RtWorld *world);
+
+int32_t rt_world_add_light(RtWorld *world, const double (*position)[3], const double (*intensity)[3]);
+
+/* Return the index of the new object, or a negative error code. */
+int64_t rt_world_add_sphere(RtWorld *world);
+int64_t rt_world_add_plane(RtWorld *world);
+int64_t rt_world_add_cube(RtWorld *world);
+
+/* `matrix` holds 16 numbers in row-major order. */
+int32_t rt_object_set_transform(RtWorld *world, size_t object, const double (*matrix)[16]);
+
+RtMaterial rt_material_default(void);
+int32_t rt_object_set_material(RtWorld *world, size_t object, const RtMaterial *material);
+
+/* Writes width * height RGBA pixels, row by row. Cameras larger than INT32_MAX either way
+   are refused with RT_ERROR_INVALID_SIZE, and ones whose `up` points along the view with
+   RT_ERROR_NOT_INVERTIBLE. Renders that fail anyway return RT_ERROR_RENDER_FAILED. */
+int32_t rt_render(const RtWorld *world, const RtCamera *camera, uint8_t *buffer, size_t buffer_length);
+
+/* Writes the pixels from (x0, y0) up to but not including (x1, y1), row by row, the way
+   they come out in the whole image. Corners outside the image are moved into it. */
+int32_t rt_render_region(const RtWorld *world, const RtCamera *camera, uint32_t x0, uint32_t y0,
+                         uint32_t x1, uint32_t y1, uint8_t *buffer, size_t buffer_length);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
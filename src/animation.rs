@@ -0,0 +1,479 @@
+//! Scenes changing over time: values keyed at a few moments and blended in between,
+//! rendered frame by frame.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    camera::Camera,
+    color::Color,
+    error::{RayTracerError, Result},
+    math::{matrix4::Matrix4, point3::Point3, transformations::view_transform, tuple::Tuple},
+    misc::EPSILON,
+    noise::{Noise, Octaves},
+    render_settings::RenderSettings,
+    world::World,
+};
+
+/// Values that can be blended, `t` of the way from `self` to `other`.
+pub trait Interpolate: Copy {
+    fn interpolate(self, other: Self, t: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn interpolate(self, other: Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Tuple {
+    fn interpolate(self, other: Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Point3 {
+    fn interpolate(self, other: Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(self, other: Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+/// A value at a few moments of the timeline, blended linearly in between and held
+/// before the first and after the last.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keyframes<T> {
+    keys: Vec<(f64, T)>,
+}
+
+impl<T> Default for Keyframes<T> {
+    fn default() -> Self {
+        Self { keys: vec![] }
+    }
+}
+
+impl<T: Interpolate> Keyframes<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keys `value` at `time`, replacing whatever was keyed at the same time.
+    pub fn key(mut self, time: f64, value: T) -> Self {
+        let index = self.keys.partition_point(|(key_time, _)| *key_time < time);
+
+        match self.keys.get_mut(index) {
+            Some((key_time, key_value)) if *key_time == time => *key_value = value,
+            _ => self.keys.insert(index, (time, value)),
+        }
+
+        self
+    }
+
+    /// The value at `time`, `None` if nothing was keyed.
+    pub fn at(&self, time: f64) -> Option<T> {
+        let next = self.keys.partition_point(|(key_time, _)| *key_time <= time);
+
+        match (
+            next.checked_sub(1).map(|index| self.keys[index]),
+            self.keys.get(next),
+        ) {
+            (Some((from_time, from)), Some(&(to_time, to))) => {
+                Some(from.interpolate(to, (time - from_time) / (to_time - from_time)))
+            }
+            (Some((_, value)), None) | (None, Some(&(_, value))) => Some(value),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Where an object is, keyed in parts so turns stay turns in between keys, which
+/// blending transform matrices wouldn't.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pose {
+    pub translation: Tuple,
+    /// Radians around the x, y and z axes, turned in that order.
+    pub rotation: Tuple,
+    pub scale: Tuple,
+}
+
+impl Pose {
+    pub fn at(translation: Tuple) -> Self {
+        Self {
+            translation,
+            rotation: Tuple::vector(0., 0., 0.),
+            scale: Tuple::vector(1., 1., 1.),
+        }
+    }
+
+    /// Scales, then turns, then moves into place.
+    pub fn transform(self) -> Matrix4 {
+        let Tuple { x, y, z, .. } = self.translation;
+
+        Matrix4::translation(x, y, z)
+            * Matrix4::rotation_z(self.rotation.z)
+            * Matrix4::rotation_y(self.rotation.y)
+            * Matrix4::rotation_x(self.rotation.x)
+            * Matrix4::scaling(self.scale.x, self.scale.y, self.scale.z)
+    }
+}
+
+impl Interpolate for Pose {
+    fn interpolate(self, other: Self, t: f64) -> Self {
+        Self {
+            translation: self.translation.interpolate(other.translation, t),
+            rotation: self.rotation.interpolate(other.rotation, t),
+            scale: self.scale.interpolate(other.scale, t),
+        }
+    }
+}
+
+/// Where the camera looks from and at, as for `view_transform`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct View {
+    pub from: Tuple,
+    pub to: Tuple,
+    pub up: Tuple,
+}
+
+impl Interpolate for View {
+    fn interpolate(self, other: Self, t: f64) -> Self {
+        Self {
+            from: self.from.interpolate(other.from, t),
+            to: self.to.interpolate(other.to, t),
+            up: self.up.interpolate(other.up, t),
+        }
+    }
+}
+
+/// Something in the scene changing over time.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Track {
+    /// The transform of the object at this index in `World::objects`.
+    Object(usize, Keyframes<Pose>),
+    /// The position of the light at this index, in the order lights were added to the world.
+    Light(usize, Keyframes<Point3>),
+    CameraView(Keyframes<View>),
+    FieldOfView(Keyframes<f64>),
+    Aperture(Keyframes<f64>),
+    FocalDistance(Keyframes<f64>),
+}
+
+/// The camera wobbling like it's held by hand, turning and moving by smooth noise over time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shake {
+    /// Different seeds shake differently, the same one the same way every render.
+    pub seed: u64,
+    /// Radians the camera turns away at most around each of its axes.
+    pub rotation: f64,
+    /// How far the camera moves away at most along each of its axes.
+    pub translation: f64,
+    /// About how many times a second the shake changes direction.
+    pub frequency: f64,
+}
+
+impl Shake {
+    pub fn new(seed: u64, rotation: f64, translation: f64, frequency: f64) -> Self {
+        Self {
+            seed,
+            rotation,
+            translation,
+            frequency,
+        }
+    }
+
+    /// The camera shaken as it is at `time`, in its own space, so it turns about
+    /// itself instead of around the origin of the world.
+    pub fn apply(self, mut camera: Camera, time: f64) -> Camera {
+        let [pitch, yaw, roll, x, y, z] =
+            [0, 1, 2, 3, 4, 5].map(|channel| self.wobble(channel, time));
+        let jitter = Matrix4::translation(
+            x * self.translation,
+            y * self.translation,
+            z * self.translation,
+        ) * Matrix4::rotation_z(roll * self.rotation)
+            * Matrix4::rotation_y(yaw * self.rotation)
+            * Matrix4::rotation_x(pitch * self.rotation);
+
+        camera.transform = jitter * camera.transform;
+        camera
+    }
+
+    /// Noise between -1 and 1 over time, unrelated for every `channel`.
+    fn wobble(self, channel: u64, time: f64) -> f64 {
+        let noise = Noise::perlin(self.seed.wrapping_mul(6).wrapping_add(channel * 1009));
+
+        // Off the lattice across, where Perlin noise would always be 0.
+        Octaves::new(noise, 3).fbm(Tuple::point(time * self.frequency, 0.31, 0.67))
+    }
+}
+
+/// A world and a camera, and how they change over time.
+pub struct Animation {
+    pub world: World,
+    pub camera: Camera,
+    pub tracks: Vec<Track>,
+    /// Shakes the camera on top of its tracks, `None` for a steady one.
+    pub shake: Option<Shake>,
+    /// How every frame is rendered. Cancelling its `cancel` token stops the whole sequence.
+    pub settings: RenderSettings,
+}
+
+impl Animation {
+    pub fn new(world: World, camera: Camera) -> Self {
+        Self {
+            world,
+            camera,
+            tracks: vec![],
+            shake: None,
+            settings: RenderSettings::default(),
+        }
+    }
+
+    pub fn add_track(&mut self, track: Track) {
+        self.tracks.push(track)
+    }
+
+    /// The world and camera as they are at `time`. Things without keys, or tracks
+    /// pointing at objects or lights that aren't there, are left as they were.
+    pub fn frame(&self, time: f64) -> (World, Camera) {
+        let mut world = self.world.clone();
+        let mut camera = self.camera;
+        camera.time = time;
+
+        for track in &self.tracks {
+            match track {
+                Track::Object(index, keys) => {
+                    if let (Some(object), Some(pose)) =
+                        (world.objects.get_mut(*index), keys.at(time))
+                    {
+                        object.set_transform(pose.transform());
+                    }
+                }
+                Track::Light(index, keys) => {
+                    if let (Some(light), Some(position)) =
+                        (world.lights.get_mut(*index), keys.at(time))
+                    {
+                        light.position = position;
+                    }
+                }
+                Track::CameraView(keys) => {
+                    if let Some(View { from, to, up }) = keys.at(time) {
+                        camera.transform = view_transform(from, to, up);
+                    }
+                }
+                Track::FieldOfView(keys) => {
+                    camera.field_of_view = keys.at(time).unwrap_or(camera.field_of_view)
+                }
+                Track::Aperture(keys) => camera.aperture = keys.at(time).unwrap_or(camera.aperture),
+                Track::FocalDistance(keys) => {
+                    camera.focal_distance = keys.at(time).unwrap_or(camera.focal_distance)
+                }
+            }
+        }
+        if let Some(shake) = self.shake {
+            camera = shake.apply(camera, time);
+        }
+
+        (world, camera)
+    }
+
+    /// The times of the frames from `start` to `end`, both included, `fps` frames a second.
+    pub fn frame_times(start: f64, end: f64, fps: f64) -> Vec<f64> {
+        if fps <= 0. || end < start {
+            return vec![start];
+        }
+        let frames = ((end - start) * fps + EPSILON).floor() as usize + 1;
+
+        (0..frames)
+            .map(|frame| start + frame as f64 / fps)
+            .collect()
+    }
+
+    /// Renders every frame of `frame_times` to a PPM file in `dir`, `frame_0000.ppm` onwards,
+    /// creating it if needed. Returns the paths written, in order.
+    pub fn render_sequence(
+        &self,
+        start: f64,
+        end: f64,
+        fps: f64,
+        dir: impl AsRef<Path>,
+    ) -> Result<Vec<PathBuf>> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+
+        Self::frame_times(start, end, fps)
+            .into_iter()
+            .enumerate()
+            .map(|(index, time)| {
+                let (world, camera) = self.frame(time);
+                let canvas = camera.try_render_with_settings(&world, &self.settings, |_, _| {})?;
+                let path = dir.join(format!("frame_{:04}.ppm", index));
+                let file = std::fs::File::create(&path).map_err(RayTracerError::from)?;
+                canvas.write_ppm(std::io::BufWriter::new(file))?;
+
+                Ok(path)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{light::Light, shape::Object};
+    use std::f64::consts::PI;
+
+    #[test]
+    fn keyframes_blend_between_keys_and_hold_outside_them() {
+        let keys = Keyframes::new().key(2., 10.).key(0., 0.).key(4., 0.);
+
+        assert_eq!(keys.at(-1.), Some(0.));
+        assert_eq!(keys.at(1.), Some(5.));
+        assert_eq!(keys.at(2.), Some(10.));
+        assert_eq!(keys.at(3.5), Some(2.5));
+        assert_eq!(keys.at(9.), Some(0.));
+        assert_eq!(Keyframes::<f64>::new().at(1.), None);
+    }
+
+    #[test]
+    fn keying_the_same_time_again_replaces_the_key() {
+        let keys = Keyframes::new().key(1., 3.).key(1., 4.);
+
+        assert_eq!(keys, Keyframes::new().key(1., 4.));
+    }
+
+    #[test]
+    fn poses_turn_in_between_keys() {
+        let mut turned = Pose::at(Tuple::point(0., 0., 0.));
+        turned.rotation = Tuple::vector(0., PI, 0.);
+        let halfway = Pose::at(Tuple::point(0., 0., 0.))
+            .interpolate(turned, 0.5)
+            .transform();
+
+        assert_eq!(
+            halfway * Tuple::point(1., 0., 0.),
+            Tuple::point(0., 0., -1.)
+        );
+    }
+
+    fn animation() -> Animation {
+        let mut world = World::new();
+        world.add_object(Object::sphere());
+        world.add_light(Light::point_light(
+            Point3::new(-10., 10., -10.),
+            Color::white(),
+        ));
+        let mut animation = Animation::new(world, Camera::new(11, 11, PI / 3.));
+        animation.add_track(Track::Object(
+            0,
+            Keyframes::new()
+                .key(0., Pose::at(Tuple::point(0., 0., 0.)))
+                .key(1., Pose::at(Tuple::point(2., 0., 0.))),
+        ));
+        animation.add_track(Track::Light(
+            0,
+            Keyframes::new().key(1., Point3::new(10., 10., -10.)),
+        ));
+        animation.add_track(Track::CameraView(Keyframes::new().key(
+            0.,
+            View {
+                from: Tuple::point(0., 0., -5.),
+                to: Tuple::point(0., 0., 0.),
+                up: Tuple::vector(0., 1., 0.),
+            },
+        )));
+        animation.add_track(Track::FieldOfView(
+            Keyframes::new().key(0., PI / 2.).key(1., PI / 4.),
+        ));
+        animation.settings.threads = Some(1);
+
+        animation
+    }
+
+    #[test]
+    fn frames_show_the_world_and_camera_at_their_time() {
+        let (world, camera) = animation().frame(0.5);
+
+        assert_eq!(
+            world.objects[0].transform(),
+            Matrix4::translation(1., 0., 0.)
+        );
+        assert_eq!(world.lights[0].position, Point3::new(10., 10., -10.));
+        assert_eq!(camera.field_of_view, 3. * PI / 8.);
+        assert_eq!(camera.time, 0.5);
+        assert_eq!(
+            camera.transform,
+            view_transform(
+                Tuple::point(0., 0., -5.),
+                Tuple::point(0., 0., 0.),
+                Tuple::vector(0., 1., 0.)
+            )
+        );
+    }
+
+    #[test]
+    fn shaking_moves_the_camera_a_little_differently_every_frame() {
+        let mut animation = animation();
+        let (_, steady) = animation.frame(0.5);
+        animation.shake = Some(Shake::new(3, 0.02, 0.1, 2.));
+
+        let eye = |camera: Camera| camera.transform.inverse().unwrap() * Tuple::point(0., 0., 0.);
+        let shaken: Vec<Tuple> = [0.5, 0.75, 1.5]
+            .map(|time| eye(animation.frame(time).1))
+            .into();
+
+        assert_eq!(eye(animation.frame(0.5).1), shaken[0]);
+        assert_ne!(shaken[0], shaken[1]);
+        assert_ne!(shaken[0], eye(steady));
+        assert!((shaken[0] - eye(steady)).magnitude() <= 0.1 * 3f64.sqrt());
+
+        animation.shake = Some(Shake::new(4, 0.02, 0.1, 2.));
+        assert_ne!(eye(animation.frame(0.5).1), shaken[0]);
+    }
+
+    #[test]
+    fn shaking_without_amplitude_keeps_the_camera_still() {
+        let camera = Camera::new(10, 10, PI / 3.);
+
+        assert_eq!(
+            Shake::new(1, 0., 0., 5.).apply(camera, 0.3).transform,
+            camera.transform
+        );
+    }
+
+    #[test]
+    fn frame_times_include_both_ends() {
+        assert_eq!(
+            Animation::frame_times(0., 1., 4.),
+            [0., 0.25, 0.5, 0.75, 1.]
+        );
+        assert_eq!(Animation::frame_times(1., 1.1, 4.), [1.]);
+        assert_eq!(Animation::frame_times(0., 1., 0.), [0.]);
+    }
+
+    #[test]
+    fn rendering_a_sequence_writes_numbered_frames() {
+        let dir = std::env::temp_dir().join(format!("animation-{}", std::process::id()));
+        let animation = animation();
+
+        let paths = animation.render_sequence(0., 1., 2., &dir).unwrap();
+
+        assert_eq!(
+            paths,
+            ["frame_0000.ppm", "frame_0001.ppm", "frame_0002.ppm"].map(|name| dir.join(name))
+        );
+        let (world, camera) = animation.frame(1.);
+        let mut last = vec![];
+        camera
+            .render_with_settings(&world, &animation.settings, |_, _| {})
+            .write_ppm(&mut last)
+            .unwrap();
+        assert_eq!(std::fs::read(&paths[2]).unwrap(), last);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
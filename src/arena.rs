@@ -0,0 +1,114 @@
+//! Memory for the intersections of rays, kept around by every thread and reused from one ray
+//! to the next, so tracing a pixel doesn't go through the allocator once buffers have grown.
+//!
+//! It's a pool of `Vec`s rather than a bump arena: every list owns its own buffer and hands
+//! it back when dropped, there's nothing to reset per pixel or tile.
+
+use std::{
+    cell::RefCell,
+    ops::{Deref, DerefMut},
+};
+
+use crate::intersection::Intersection;
+
+/// How many unused buffers a thread keeps, more than a ray ever holds at once
+/// through its reflections and refractions.
+const POOL_SIZE: usize = 64;
+
+thread_local! {
+    static POOL: RefCell<Vec<Vec<Intersection<'static>>>> = const { RefCell::new(vec![]) };
+}
+
+/// A list of intersections whose memory goes back to the thread's pool when dropped.
+#[derive(Debug, Default)]
+pub(crate) struct Intersections<'a> {
+    list: Vec<Intersection<'a>>,
+}
+
+impl<'a> Intersections<'a> {
+    /// An empty list, reusing a buffer from the pool if there's one.
+    pub(crate) fn new() -> Self {
+        let list = POOL
+            .with(|pool| pool.borrow_mut().pop())
+            .map(recycle)
+            .unwrap_or_default();
+
+        Self { list }
+    }
+}
+
+impl Drop for Intersections<'_> {
+    fn drop(&mut self) {
+        let list = recycle(std::mem::take(&mut self.list));
+
+        if list.capacity() > 0 {
+            // Threads being torn down have no pool left, their buffers are just freed.
+            let _ = POOL.try_with(|pool| {
+                let mut pool = pool.borrow_mut();
+                if pool.len() < POOL_SIZE {
+                    pool.push(list);
+                }
+            });
+        }
+    }
+}
+
+impl<'a> Deref for Intersections<'a> {
+    type Target = Vec<Intersection<'a>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.list
+    }
+}
+
+impl DerefMut for Intersections<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.list
+    }
+}
+
+impl<'b, 'a> IntoIterator for &'b Intersections<'a> {
+    type Item = &'b Intersection<'a>;
+    type IntoIter = std::slice::Iter<'b, Intersection<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.iter()
+    }
+}
+
+/// Empties the buffer so it can hold intersections with objects of any lifetime,
+/// keeping its allocation.
+fn recycle<'a, 'b>(mut list: Vec<Intersection<'a>>) -> Vec<Intersection<'b>> {
+    list.clear();
+    let mut list = std::mem::ManuallyDrop::new(list);
+
+    // SAFETY: the pointer and capacity come from a live `Vec` that is never dropped, and
+    // `Intersection<'a>` and `Intersection<'b>` only differ in lifetime, so they have the
+    // same size and alignment. The new `Vec` is empty, so nothing borrowing for `'a` can
+    // be reached through it.
+    unsafe { Vec::from_raw_parts(list.as_mut_ptr().cast(), 0, list.capacity()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{point3::Point3, vec3::Vec3};
+    use crate::{ray::Ray, shape::Object};
+
+    #[test]
+    fn dropped_lists_give_their_memory_to_the_next_ones() {
+        let sphere = Object::sphere();
+        let ray = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
+
+        let mut xs = Intersections::new();
+        sphere.intersect_into(ray, &mut xs);
+        assert_eq!(xs.len(), 2);
+        let buffer = xs.as_ptr() as usize;
+        drop(xs);
+
+        let ys: Intersections = Intersections::new();
+        assert!(ys.is_empty());
+        assert_eq!(ys.as_ptr() as usize, buffer);
+        assert!(ys.capacity() >= 2);
+    }
+}
@@ -0,0 +1,345 @@
+//! Bounding volume hierarchies: nesting the children of groups into subgroups of nearby ones,
+//! so rays can skip most of them through their bounding boxes.
+//!
+//! Hierarchies are built over the indices of the children, which lets them be written
+//! to a cache file and reused for the same mesh instead of being built again.
+
+use crate::error::Result;
+
+use crate::{
+    bytes::{invalid_data, Reader},
+    obj::WavefrontObj,
+    shape::{BoundingBox, Object, ShapeOrGroup},
+};
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Node {
+    /// The child at this index.
+    Leaf(usize),
+    Group(Vec<Node>),
+}
+
+/// Nests the objects with the given bounds so that no group has more than `threshold`
+/// children, unless they can't be told apart by splitting their bounds in half.
+pub(crate) fn build(boxes: &[BoundingBox], threshold: usize) -> Vec<Node> {
+    build_over(boxes, (0..boxes.len()).collect(), threshold)
+}
+
+fn build_over(boxes: &[BoundingBox], indices: Vec<usize>, threshold: usize) -> Vec<Node> {
+    let leaves = |indices: Vec<usize>| indices.into_iter().map(Node::Leaf).collect();
+
+    if indices.len() <= threshold {
+        return leaves(indices);
+    }
+
+    let (finite, mut rest): (Vec<usize>, Vec<usize>) =
+        indices.iter().partition(|&&index| boxes[index].is_finite());
+    let bounds = finite
+        .iter()
+        .map(|&index| boxes[index].clone())
+        .reduce(|box1, box2| box1.union(&box2));
+    let (left_box, right_box) = match bounds {
+        Some(bounds) => bounds.split(),
+        None => return leaves(indices),
+    };
+
+    let (mut left, mut right) = (vec![], vec![]);
+    for index in finite {
+        if left_box.contains_box(&boxes[index]) {
+            left.push(index);
+        } else if right_box.contains_box(&boxes[index]) {
+            right.push(index);
+        } else {
+            rest.push(index);
+        }
+    }
+
+    // Nothing would change but the depth of the hierarchy.
+    if left.len() == indices.len() || right.len() == indices.len() {
+        return leaves(indices);
+    }
+
+    rest.sort_unstable();
+    let mut nodes: Vec<Node> = leaves(rest);
+    for part in [left, right] {
+        match part.len() {
+            0 => {}
+            1 => nodes.push(Node::Leaf(part[0])),
+            _ => nodes.push(Node::Group(build_over(boxes, part, threshold))),
+        }
+    }
+
+    nodes
+}
+
+/// Puts `objects` into the groups described by `nodes`, which must use each of them once.
+pub(crate) fn assemble(nodes: &[Node], objects: Vec<Object>) -> Result<Vec<Object>> {
+    let mut objects: Vec<Option<Object>> = objects.into_iter().map(Some).collect();
+    let assembled = assemble_from(nodes, &mut objects)?;
+
+    if objects.iter().any(Option::is_some) {
+        return Err(invalid_data("the hierarchy leaves objects out"));
+    }
+
+    Ok(assembled)
+}
+
+fn assemble_from(nodes: &[Node], objects: &mut [Option<Object>]) -> Result<Vec<Object>> {
+    nodes
+        .iter()
+        .map(|node| match node {
+            Node::Leaf(index) => objects
+                .get_mut(*index)
+                .and_then(Option::take)
+                .ok_or_else(|| invalid_data("the hierarchy uses an object twice or not at all")),
+            Node::Group(children) => Ok(Object::group(assemble_from(children, objects)?)),
+        })
+        .collect()
+}
+
+const MAGIC: &[u8; 8] = b"RTBVH\x00\x00\x01";
+/// Groups can nest this deep in a cache file, deeper ones are taken as corrupt.
+const MAX_DEPTH: usize = 256;
+
+/// Loads a mesh from an OBJ file with its triangles in a hierarchy,
+/// reusing the one in `cache_path` if it was built for the same file and threshold,
+/// and writing it there otherwise, if it can.
+///
+/// The groups of the OBJ file are left out, all its triangles end up in the same hierarchy.
+pub fn load_obj(obj_path: &str, cache_path: &str, threshold: usize) -> Result<Object> {
+    let contents = std::fs::read_to_string(obj_path)?;
+    let key = cache_key(contents.as_bytes(), threshold);
+    let triangles = leaves(WavefrontObj::from_file_contents(&contents)?.to_group());
+
+    let cached = std::fs::read(cache_path)
+        .ok()
+        .and_then(|bytes| read_cache(&bytes, key, triangles.len()).ok());
+    let nodes = match cached {
+        Some(nodes) => nodes,
+        None => {
+            let boxes: Vec<BoundingBox> = triangles.iter().map(Object::bounding_box).collect();
+            let nodes = build(&boxes, threshold);
+            // The cache only saves time, the mesh loads fine without it being written.
+            let _ = std::fs::write(cache_path, write_cache(&nodes, key, triangles.len()));
+
+            nodes
+        }
+    };
+
+    Ok(Object::group(assemble(&nodes, triangles)?))
+}
+
+/// The shapes inside the object, depth first.
+fn leaves(object: Object) -> Vec<Object> {
+    match object.shape {
+        ShapeOrGroup::Group(children) => children.into_iter().flat_map(leaves).collect(),
+        ShapeOrGroup::Shape { .. } => vec![object],
+    }
+}
+
+/// FNV-1a over the mesh and the threshold, anything else building the same hierarchy.
+fn cache_key(mesh: &[u8], threshold: usize) -> u64 {
+    mesh.iter()
+        .chain(&(threshold as u64).to_le_bytes())
+        .fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01B3)
+        })
+}
+
+fn write_cache(nodes: &[Node], key: u64, leaf_count: usize) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&key.to_le_bytes());
+    bytes.extend_from_slice(&(leaf_count as u32).to_le_bytes());
+    write_nodes(&mut bytes, nodes);
+
+    bytes
+}
+
+fn write_nodes(bytes: &mut Vec<u8>, nodes: &[Node]) {
+    bytes.extend_from_slice(&(nodes.len() as u32).to_le_bytes());
+
+    for node in nodes {
+        match node {
+            Node::Leaf(index) => {
+                bytes.push(0);
+                bytes.extend_from_slice(&(*index as u32).to_le_bytes());
+            }
+            Node::Group(children) => {
+                bytes.push(1);
+                write_nodes(bytes, children);
+            }
+        }
+    }
+}
+
+fn read_cache(bytes: &[u8], key: u64, leaf_count: usize) -> Result<Vec<Node>> {
+    let mut reader = Reader::new(bytes);
+
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(invalid_data("not a hierarchy cache"));
+    }
+    if reader.u64()? != key || reader.u32()? as usize != leaf_count {
+        return Err(invalid_data("the cache was built for another mesh"));
+    }
+
+    let nodes = read_nodes(&mut reader, leaf_count, 0)?;
+    if reader.remaining() != 0 {
+        return Err(invalid_data("trailing bytes after the hierarchy"));
+    }
+
+    Ok(nodes)
+}
+
+fn read_nodes(reader: &mut Reader, leaf_count: usize, depth: usize) -> Result<Vec<Node>> {
+    if depth > MAX_DEPTH {
+        return Err(invalid_data("the hierarchy is nested too deeply"));
+    }
+
+    // Every node takes at least five bytes.
+    let count = reader.count(5)?;
+
+    (0..count)
+        .map(|_| match reader.u8()? {
+            0 => match reader.u32()? as usize {
+                index if index < leaf_count => Ok(Node::Leaf(index)),
+                _ => Err(invalid_data("leaf index out of range")),
+            },
+            1 => Ok(Node::Group(read_nodes(reader, leaf_count, depth + 1)?)),
+            _ => Err(invalid_data("unknown node")),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{point3::Point3, vec3::Vec3};
+    use crate::{math::matrix4::Matrix4, ray::Ray};
+
+    fn sphere_at(x: f64) -> Object {
+        let mut sphere = Object::sphere();
+        sphere.set_transform(Matrix4::translation(x, 0., 0.));
+
+        sphere
+    }
+
+    fn leaf_indices(nodes: &[Node]) -> Vec<usize> {
+        let mut indices: Vec<usize> = nodes
+            .iter()
+            .flat_map(|node| match node {
+                Node::Leaf(index) => vec![*index],
+                Node::Group(children) => leaf_indices(children),
+            })
+            .collect();
+        indices.sort_unstable();
+
+        indices
+    }
+
+    #[test]
+    fn building_a_hierarchy_splits_objects_by_their_bounds() {
+        let spheres: Vec<Object> = [-6., -3., 3., 6.].map(sphere_at).into();
+        let boxes: Vec<BoundingBox> = spheres.iter().map(Object::bounding_box).collect();
+
+        assert_eq!(
+            build(&boxes, 2),
+            vec![
+                Node::Group(vec![Node::Leaf(0), Node::Leaf(1)]),
+                Node::Group(vec![Node::Leaf(2), Node::Leaf(3)]),
+            ]
+        );
+        assert_eq!(leaf_indices(&build(&boxes, 1)), vec![0, 1, 2, 3]);
+        assert_eq!(build(&boxes, 4).len(), 4);
+    }
+
+    #[test]
+    fn objects_that_cant_be_split_stay_together() {
+        let mut boxes: Vec<BoundingBox> = [0., 0., 0.].map(|x| sphere_at(x).bounding_box()).into();
+        boxes.push(Object::plane().bounding_box());
+
+        assert_eq!(build(&boxes, 1).len(), 4);
+    }
+
+    #[test]
+    fn hierarchies_go_through_the_cache_unchanged() {
+        let spheres: Vec<Object> = (0..20).map(|i| sphere_at(i as f64 * 2.5)).collect();
+        let boxes: Vec<BoundingBox> = spheres.iter().map(Object::bounding_box).collect();
+        let nodes = build(&boxes, 3);
+        let bytes = write_cache(&nodes, 42, 20);
+
+        assert_eq!(read_cache(&bytes, 42, 20).unwrap(), nodes);
+        assert!(read_cache(&bytes, 43, 20).is_err());
+        assert!(read_cache(&bytes[..bytes.len() - 1], 42, 20).is_err());
+        assert!(read_cache(&bytes, 42, 10).is_err());
+    }
+
+    #[test]
+    fn caches_nested_too_deeply_are_rejected() {
+        let mut nodes = vec![Node::Leaf(0)];
+        for _ in 0..=MAX_DEPTH {
+            nodes = vec![Node::Group(nodes)];
+        }
+        let bytes = write_cache(&nodes, 42, 1);
+
+        assert!(read_cache(&bytes, 42, 1).is_err());
+
+        let mut bytes = write_cache(&[], 42, 1);
+        bytes.truncate(bytes.len() - 4);
+        for _ in 0..100_000 {
+            bytes.extend_from_slice(&1u32.to_le_bytes());
+            bytes.push(1);
+        }
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+
+        assert!(read_cache(&bytes, 42, 1).is_err());
+    }
+
+    #[test]
+    fn assembling_a_hierarchy_uses_every_object_once() {
+        let nodes = vec![Node::Leaf(1), Node::Group(vec![Node::Leaf(0)])];
+        let group = assemble(&nodes, vec![sphere_at(0.), sphere_at(5.)]).unwrap();
+
+        assert_eq!(group[0].transform(), Matrix4::translation(5., 0., 0.));
+        assert!(assemble(&[Node::Leaf(0), Node::Leaf(0)], vec![sphere_at(0.)]).is_err());
+        assert!(assemble(&[Node::Leaf(0)], vec![sphere_at(0.), sphere_at(1.)]).is_err());
+    }
+
+    #[test]
+    fn loading_a_mesh_writes_its_hierarchy_and_then_reuses_it() {
+        let directory = std::env::temp_dir().join(format!("bvh-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let obj_path = directory.join("strip.obj");
+        let cache_path = directory.join("strip.bvh");
+        let (obj_path, cache_path) = (obj_path.to_str().unwrap(), cache_path.to_str().unwrap());
+
+        let mut obj = String::new();
+        for i in 0..10 {
+            let x = i as f64 * 2.;
+            obj += &format!("v {} 0 0\nv {} 0 0\nv {} 1 0\n", x, x + 1., x);
+        }
+        for i in 0..10 {
+            obj += &format!("f {} {} {}\n", 3 * i + 1, 3 * i + 2, 3 * i + 3);
+        }
+        std::fs::write(obj_path, &obj).unwrap();
+
+        let built = load_obj(obj_path, cache_path, 2).unwrap();
+        let cache = std::fs::read(cache_path).unwrap();
+        let loaded = load_obj(obj_path, cache_path, 2).unwrap();
+
+        assert_eq!(loaded, built);
+        assert_eq!(std::fs::read(cache_path).unwrap(), cache);
+        let r = Ray::new(Point3::new(4.1, 0.1, -5.), Vec3::new(0., 0., 1.));
+        assert_eq!(loaded.intersect(r).len(), 1);
+
+        // A different threshold can't use the same hierarchy.
+        load_obj(obj_path, cache_path, 4).unwrap();
+        assert_ne!(std::fs::read(cache_path).unwrap(), cache);
+
+        // Nor does a cache that can't be written stop the mesh from loading.
+        let unwritable = directory.join("missing").join("strip.bvh");
+        let loaded = load_obj(obj_path, unwritable.to_str().unwrap(), 2).unwrap();
+        assert_eq!(loaded, built);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,60 @@
+//! Reading the little endian binary files the renderer writes, like caches and snapshots.
+
+use crate::error::{RayTracerError, Result};
+
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub(crate) fn take(&mut self, count: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < count {
+            return Err(invalid_data("the file is truncated"));
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+
+        Ok(taken)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    #[cfg(feature = "snapshot")]
+    pub(crate) fn f64(&mut self) -> Result<f64> {
+        Ok(f64::from_bits(self.u64()?))
+    }
+
+    /// A count of things that take at least `min_size` bytes each,
+    /// checked against what's left so corrupt files can't make us allocate too much.
+    pub(crate) fn count(&mut self, min_size: usize) -> Result<usize> {
+        let count = self.u32()? as usize;
+
+        if count > self.remaining() / min_size.max(1) {
+            return Err(invalid_data("the file is truncated"));
+        }
+
+        Ok(count)
+    }
+}
+
+pub(crate) fn invalid_data(message: &str) -> RayTracerError {
+    RayTracerError::Parse(message.to_string())
+}
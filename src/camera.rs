@@ -1,25 +1,158 @@
-use std::io::Write;
+use std::{
+    f64::consts::PI,
+    io::Write,
+    iter::StepBy,
+    num::NonZeroUsize,
+    ops::Range,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
 
-use crate::{canvas::Canvas, math::matrix4::Matrix4, math::tuple::Tuple, ray::Ray, world::World};
+#[cfg(feature = "bidirectional")]
+use crate::integrator::Integrator;
+use crate::{
+    canvas::Canvas,
+    color::Color,
+    error::{RayTracerError, Result},
+    math::angle::Angle,
+    math::matrix4::Matrix4,
+    math::point3::Point3,
+    math::vec3::Vec3,
+    misc::EPSILON,
+    ray::Ray,
+    render_settings::{PixelContext, RenderSettings},
+    sampler::{AdaptiveSampling, ImportanceMap, Sampler},
+    shape::BoundingBox,
+    world::World,
+};
 
 #[derive(Clone, Copy)]
 pub struct Camera {
     pub hsize: i32,
     pub vsize: i32,
+    /// In radians, `new` takes anything that converts into an `Angle`.
     pub field_of_view: f64,
     pub transform: Matrix4,
+    pub sampler: Sampler,
+    /// The moment the picture is taken, frames of an animation are rendered at different times.
+    pub time: f64,
+    /// Renders onto a canvas with an alpha channel, leaving what the rays miss transparent
+    /// and shadow catchers as translucent shadows, to composite over other backdrops.
+    pub transparent_background: bool,
+    /// Diameter of the lens, 0 for a pinhole camera with everything in focus.
+    /// Wider lenses blur what is away from `focal_distance` more, given enough samples per pixel.
+    pub aperture: f64,
+    /// How far in front of the camera things are in focus.
+    pub focal_distance: f64,
+    /// How the lens bends the picture, none by default.
+    pub distortion: LensDistortion,
+    /// How pixels map to the directions rays go in, through a flat picture in front of the
+    /// camera by default.
+    pub projection: Projection,
 }
 
+/// How pixels map to the directions rays go in from the camera.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Projection {
+    /// Through a picture in front of the camera, `field_of_view` wide.
+    #[default]
+    Perspective,
+    /// All the way around, as an equirectangular panorama: 360° from the left edge of the
+    /// image to the right, the middle looking straight ahead, and 180° from straight up at
+    /// the top to straight down at the bottom, to use as an environment map or in VR viewers.
+    /// The field of view, the lens and its distortion are left out.
+    ///
+    /// With an `eye_separation` over 0, the image is a stereo pair with the panorama of the
+    /// left eye over the one of the right eye, eyes going around a circle that wide as they
+    /// look around, the way VR viewers expect omni-directional stereo.
+    Panorama { eye_separation: f64 },
+}
+
+/// Radial lens distortion, as in the Brown-Conrady model camera trackers use: a point `r` away
+/// from the middle of the view, on the image plane one unit in front of the camera, shows up
+/// `1 + k1 r² + k2 r⁴` times as far. Negative `k1` bulges the picture out like a wide lens
+/// (barrel), and positive `k1` pinches it in (pincushion).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LensDistortion {
+    pub k1: f64,
+    pub k2: f64,
+}
+
+impl LensDistortion {
+    pub fn new(k1: f64, k2: f64) -> Self {
+        Self { k1, k2 }
+    }
+
+    fn scale(self, (x, y): (f64, f64)) -> f64 {
+        let r2 = x * x + y * y;
+
+        1. + self.k1 * r2 + self.k2 * r2 * r2
+    }
+
+    /// Where the point `(x, y)` of the image plane shows up through the lens.
+    pub fn distort(self, (x, y): (f64, f64)) -> (f64, f64) {
+        let scale = self.scale((x, y));
+
+        (x * scale, y * scale)
+    }
+
+    /// The point of the image plane that shows up at `(x, y)` through the lens, undoing
+    /// `distort`. It's found by iterating, which settles for distortion that keeps points
+    /// in the same order going out from the middle, like any real lens does over its view.
+    pub fn undistort(self, (x, y): (f64, f64)) -> (f64, f64) {
+        if self == Self::default() {
+            return (x, y);
+        }
+
+        let mut point = (x, y);
+        for _ in 0..UNDISTORT_ITERATIONS {
+            let scale = self.scale(point);
+            point = (x / scale, y / scale);
+        }
+
+        point
+    }
+}
+
+const UNDISTORT_ITERATIONS: usize = 32;
+
 impl Camera {
-    pub fn new(hsize: i32, vsize: i32, field_of_view: f64) -> Self {
+    pub fn new(hsize: i32, vsize: i32, field_of_view: impl Into<Angle>) -> Self {
         Self {
             hsize,
             vsize,
-            field_of_view,
+            field_of_view: field_of_view.into().to_radians(),
             transform: Matrix4::identity(),
+            sampler: Sampler::default(),
+            time: 0.,
+            transparent_background: false,
+            aperture: 0.,
+            focal_distance: 1.,
+            distortion: LensDistortion::default(),
+            projection: Projection::Perspective,
         }
     }
 
+    /// A camera rendering an equirectangular panorama `width` pixels wide, as tall as it
+    /// takes to keep pixels square, twice that with `eye_separation` for a stereo pair.
+    /// See `Projection::Panorama`.
+    pub fn panorama(width: i32, eye_separation: f64) -> Self {
+        let eyes = if eye_separation > 0. { 2 } else { 1 };
+        let mut camera = Self::new(width, eyes * width / 2, PI);
+        camera.projection = Projection::Panorama { eye_separation };
+
+        camera
+    }
+
+    /// Anti-aliases the render by averaging `samples` rays per pixel, jittered over it
+    /// (see `Sampler`). 1 sends a single ray through the center of each pixel.
+    pub fn set_samples_per_pixel(&mut self, samples: usize) {
+        self.sampler.samples_per_pixel = samples.max(1);
+    }
+
     fn half_extents(self) -> (f64, f64) {
         let half_view = (self.field_of_view / 2.).tan();
         let aspect = self.hsize as f64 / self.vsize as f64;
@@ -39,56 +172,722 @@ impl Camera {
         2. * half_width / self.hsize as f64
     }
 
-    pub fn ray_for_pixel(self, px: i32, py: i32) -> Ray {
-        let x_offset = (px as f64 + 0.5) * self.pixel_size();
-        let y_offset = (py as f64 + 0.5) * self.pixel_size();
+    /// The point of the image plane at `(x, y)` in pixels, from the top left corner of the
+    /// picture, with the picture undistorted.
+    fn pixel_to_plane(self, (x, y): (f64, f64)) -> (f64, f64) {
+        let (half_width, half_height) = self.half_extents();
 
+        (
+            half_width - x * self.pixel_size(),
+            half_height - y * self.pixel_size(),
+        )
+    }
+
+    fn plane_to_pixel(self, (x, y): (f64, f64)) -> (f64, f64) {
         let (half_width, half_height) = self.half_extents();
-        let world_x = half_width - x_offset;
-        let world_y = half_height - y_offset;
+
+        (
+            (half_width - x) / self.pixel_size(),
+            (half_height - y) / self.pixel_size(),
+        )
+    }
+
+    /// Where what's at `(x, y)` in pixels of a render without `distortion` shows up in one
+    /// with it, counting from the top left corner, to draw annotations made on undistorted
+    /// pictures over distorted renders.
+    pub fn distort_pixel(self, x: f64, y: f64) -> (f64, f64) {
+        self.plane_to_pixel(self.distortion.distort(self.pixel_to_plane((x, y))))
+    }
+
+    /// Where what's at `(x, y)` in pixels of a render with `distortion` shows up in one
+    /// without it, undoing `distort_pixel`.
+    pub fn undistort_pixel(self, x: f64, y: f64) -> (f64, f64) {
+        self.plane_to_pixel(self.distortion.undistort(self.pixel_to_plane((x, y))))
+    }
+
+    pub fn ray_for_pixel(self, px: i32, py: i32) -> Ray {
+        self.ray_through(px, py, (0.5, 0.5), (0., 0.))
+    }
+
+    /// Like `ray_for_pixel`, but going through `offset` within the pixel instead of its center,
+    /// from `lens`, a point on the unit disk scaled to the aperture, instead of the middle of it.
+    fn ray_through(self, px: i32, py: i32, (dx, dy): (f64, f64), lens: (f64, f64)) -> Ray {
+        if let Projection::Panorama { eye_separation } = self.projection {
+            return self.panorama_ray((px as f64 + dx, py as f64 + dy), eye_separation);
+        }
+        let (world_x, world_y) = self
+            .distortion
+            .undistort(self.pixel_to_plane((px as f64 + dx, py as f64 + dy)));
 
         let inverse_transform = self.transform.inverse().unwrap();
-        let pixel = inverse_transform * Tuple::point(world_x, world_y, -1.);
-        let origin = inverse_transform * Tuple::point(0., 0., 0.);
+        // Rays through the pixel from anywhere on the lens meet at the focal distance.
+        let distance = self.focal_distance;
+        let focus =
+            inverse_transform * Point3::new(world_x * distance, world_y * distance, -distance);
+        let radius = self.aperture / 2.;
+        let origin = inverse_transform * Point3::new(lens.0 * radius, lens.1 * radius, 0.);
 
-        let direction = (pixel - origin).normalize();
+        let direction = (focus - origin).normalize();
 
-        Ray::new(origin, direction)
+        Ray::new(origin, direction).at_time(self.time)
     }
 
-    pub fn render(self, world: &World) -> Canvas {
-        let mut canvas = Canvas::new(self.hsize as usize, self.vsize as usize);
-        let total_pixels = self.vsize * self.hsize;
+    /// The ray through `(x, y)` in pixels of an equirectangular panorama, see
+    /// `Projection::Panorama`.
+    fn panorama_ray(self, (x, y): (f64, f64), eye_separation: f64) -> Ray {
+        let stereo = eye_separation > 0.;
+        let eye_height = if stereo {
+            self.vsize as f64 / 2.
+        } else {
+            self.vsize as f64
+        };
+        // The right eye's panorama goes under the left eye's.
+        let (y, eye_offset) = if stereo && y >= eye_height {
+            (y - eye_height, -eye_separation / 2.)
+        } else {
+            (y, eye_separation / 2.)
+        };
 
-        let mut total_done = 0;
-        for y in 0..self.vsize {
-            for x in 0..self.hsize {
-                let ray = self.ray_for_pixel(x, y);
-                let color = world.color_at(ray);
+        // Cameras look towards -z with +x to the left of the picture.
+        let longitude = (0.5 - x / self.hsize as f64) * 2. * PI;
+        let latitude = (0.5 - y / eye_height) * PI;
+        let direction = Vec3::new(
+            latitude.cos() * longitude.sin(),
+            latitude.sin(),
+            -latitude.cos() * longitude.cos(),
+        );
+        // Eyes sit on either side of the way they look, level with the ground.
+        let eye = Point3::new(
+            eye_offset * longitude.cos(),
+            0.,
+            eye_offset * longitude.sin(),
+        );
 
-                canvas.write_pixel(x, y, color);
-            }
-            total_done += self.hsize;
+        let inverse_transform = self.transform.inverse().unwrap();
+        let direction = (inverse_transform * direction).normalize();
+
+        Ray::new(inverse_transform * eye, direction).at_time(self.time)
+    }
+
+    pub fn render(self, world: &World) -> Canvas {
+        self.render_with_progress(world, |done, total| {
             print!(
                 "Computed: {}/{} ({}%) pixels.\r",
-                total_done,
-                total_pixels,
-                (100. * (total_done as f64 / total_pixels as f64)).round()
+                done,
+                total,
+                (100. * (done as f64 / total as f64)).round()
             );
             std::io::stdout().flush().unwrap();
+        })
+    }
+
+    /// The pixel `point` shows up in, and how much of the light coming from it straight to the
+    /// camera counts towards that pixel, for light traced from the lights. Pixels away from
+    /// the middle cover less of the view, so light through them counts for more.
+    /// The camera is taken to be a pinhole.
+    #[cfg(feature = "bidirectional")]
+    pub(crate) fn pixel_showing(self, point: Point3) -> Option<(usize, usize, f64)> {
+        if self.projection != Projection::Perspective {
+            return None;
+        }
+        let local = self.transform * point;
+        if local.z > -EPSILON {
+            return None;
+        }
+        let pixel_size = self.pixel_size();
+        let (x, y) = self.plane_to_pixel(
+            self.distortion
+                .distort((local.x / -local.z, local.y / -local.z)),
+        );
+        if x < 0. || y < 0. || x >= self.hsize as f64 || y >= self.vsize as f64 {
+            return None;
+        }
+        let cosine = -local.z / (local - Point3::origin()).magnitude();
+
+        Some((
+            x as usize,
+            y as usize,
+            1. / (pixel_size.powi(2) * cosine.powi(3)),
+        ))
+    }
+
+    /// Renders the world, calling `progress` with the pixels done and the total after every row.
+    pub fn render_with_progress(self, world: &World, progress: impl FnMut(usize, usize)) -> Canvas {
+        self.render_with_settings(world, &RenderSettings::default(), progress)
+    }
+
+    /// Renders the world the way the settings say, calling `progress` like `render_with_progress`.
+    /// Rows left when the settings' `cancel` token stops the render are left black.
+    pub fn render_with_settings(
+        self,
+        world: &World,
+        settings: &RenderSettings,
+        progress: impl FnMut(usize, usize),
+    ) -> Canvas {
+        self.render_counted(world, settings, progress).0
+    }
+
+    /// Like `render_with_settings`, but failing with `RayTracerError::Cancelled` instead of
+    /// returning an unfinished image when the settings' `cancel` token stops the render.
+    pub fn try_render_with_settings(
+        self,
+        world: &World,
+        settings: &RenderSettings,
+        progress: impl FnMut(usize, usize),
+    ) -> Result<Canvas> {
+        match self.render_counted(world, settings, progress) {
+            (canvas, true) => Ok(canvas),
+            (_, false) => Err(RayTracerError::Cancelled),
+        }
+    }
+
+    /// Renders the world reporting progress, and whether every pixel got rendered.
+    fn render_counted(
+        self,
+        world: &World,
+        settings: &RenderSettings,
+        mut progress: impl FnMut(usize, usize),
+    ) -> (Canvas, bool) {
+        let width = self.hsize.max(0) as usize;
+        let total_pixels = width * self.rows(settings).len();
+        let mut total_done = 0;
+
+        let canvas = self.render_rows(world, settings, |_, _| {
+            total_done += width;
+            progress(total_done, total_pixels);
+        });
+
+        (canvas, total_done == total_pixels)
+    }
+
+    /// Renders the world, calling `preview` with a rough version of the image every time
+    /// there are twice as many rows done, rows still left copying the closest one above them.
+    ///
+    /// Rows are rendered spread out over the whole image instead of from top to bottom,
+    /// so the previews sharpen everywhere at once.
+    pub fn render_progressively(
+        self,
+        world: &World,
+        settings: &RenderSettings,
+        mut preview: impl FnMut(&Canvas),
+    ) -> Canvas {
+        let height = self.rows(settings).len();
+        let passes = interleaved_rows(height);
+        let mut done = vec![false; height];
+        let mut pass = 0;
+        let mut pass_row = 0;
+
+        self.render_rows(world, settings, |canvas, y| {
+            done[y] = true;
+
+            // Rows can finish out of order, a pass is over once all of its rows are in.
+            while pass < passes.len() && done[passes[pass][pass_row]] {
+                pass_row += 1;
+                if pass_row == passes[pass].len() {
+                    let step = passes.get(pass + 1).map_or(1, |rows| rows[0] * 2);
+                    preview(&filled_in(canvas, 1, step));
+                    pass += 1;
+                    pass_row = 0;
+                }
+            }
+        })
+    }
+
+    /// Renders the world starting from every 8th pixel across and down, then filling in
+    /// the pixels halfway between the ones done, down to every pixel, calling `preview`
+    /// after each of those passes with the image where pixels still left copy the closest
+    /// one done above and to the left of them. The first preview comes after 1/64th of
+    /// the work, and every pixel is only rendered once.
+    pub fn render_coarse_to_fine(
+        self,
+        world: &World,
+        settings: &RenderSettings,
+        mut preview: impl FnMut(&Canvas),
+    ) -> Canvas {
+        let tile = self.rows(settings);
+        let columns = 0..self.hsize.max(0) as usize;
+        let mut spans = vec![];
+        let mut pass_of_span = vec![];
+        let mut left = [0; COARSE_STEPS.len()];
+        for (pass, step) in COARSE_STEPS.into_iter().enumerate() {
+            for y in (0..tile.len()).step_by(step) {
+                // Rows done by the pass before already have every other pixel of this one.
+                let done_before = pass > 0 && y % (2 * step) == 0;
+                spans.push(Span {
+                    y: tile.start + y,
+                    start: if done_before { step } else { 0 },
+                    step: if done_before { 2 * step } else { step },
+                });
+                pass_of_span.push(pass);
+                left[pass] += 1;
+            }
+        }
+        let mut pass = 0;
+
+        self.render_spans(world, settings, columns, tile, spans, |canvas, index| {
+            left[pass_of_span[index]] -= 1;
+            // Spans can finish out of order, a pass is over once all of its spans are in.
+            while pass < COARSE_STEPS.len() && left[pass] == 0 {
+                let step = COARSE_STEPS[pass];
+                preview(&filled_in(canvas, step, step));
+                pass += 1;
+            }
+        })
+    }
+
+    /// The rows of the image the settings ask for, all of them unless they name a `Tile`.
+    fn rows(self, settings: &RenderSettings) -> Range<usize> {
+        let height = self.vsize.max(0) as usize;
+
+        settings.tile.map_or(0..height, |tile| tile.rows(height))
+    }
+
+    /// Renders only the pixels from `(x0, y0)` up to but not including `(x1, y1)`, into a canvas
+    /// that size, the way they come out in the whole image. Much quicker to render again and
+    /// again while tweaking what a small part of the image shows.
+    ///
+    /// Corners outside the image are moved into it.
+    pub fn render_region(self, world: &World, x0: i32, y0: i32, x1: i32, y1: i32) -> Canvas {
+        let columns = clamped(x0, x1, self.hsize);
+        let rows = clamped(y0, y1, self.vsize);
+
+        self.render_area(world, &RenderSettings::default(), columns, rows, |_, _| {})
+    }
+
+    /// The color of a single pixel, the way it comes out in the whole image.
+    pub fn render_pixel(self, world: &World, x: i32, y: i32) -> Color {
+        self.pixel_color(world, &RenderSettings::default(), world.max_depth, x, y)
+            .0
+    }
+
+    /// Renders every row of the settings' tile, see `render_area`.
+    fn render_rows(
+        self,
+        world: &World,
+        settings: &RenderSettings,
+        row_done: impl FnMut(&mut Canvas, usize),
+    ) -> Canvas {
+        let columns = 0..self.hsize.max(0) as usize;
+
+        self.render_area(world, settings, columns, self.rows(settings), row_done)
+    }
+
+    /// Renders the `columns` of every one of the `tile` rows, in the order of `interleaved_rows`,
+    /// calling `row_done` with the canvas and the row in it after writing each of them.
+    fn render_area(
+        self,
+        world: &World,
+        settings: &RenderSettings,
+        columns: Range<usize>,
+        tile: Range<usize>,
+        mut row_done: impl FnMut(&mut Canvas, usize),
+    ) -> Canvas {
+        let rows = interleaved_rows(tile.len()).concat();
+        let spans = rows
+            .iter()
+            .map(|y| Span {
+                y: tile.start + y,
+                start: columns.start,
+                step: 1,
+            })
+            .collect();
+
+        self.render_spans(world, settings, columns, tile, spans, |canvas, index| {
+            row_done(canvas, rows[index])
+        })
+    }
+
+    /// Renders the spans, all within the `columns` of the `tile` rows, on as many threads as
+    /// the settings say, in order, into a canvas as big as the columns and rows, calling
+    /// `span_done` with the canvas and the index of the span after writing each of them.
+    /// Threads stop taking spans once the settings' `cancel` token is cancelled.
+    fn render_spans(
+        self,
+        world: &World,
+        settings: &RenderSettings,
+        columns: Range<usize>,
+        tile: Range<usize>,
+        spans: Vec<Span>,
+        mut span_done: impl FnMut(&mut Canvas, usize),
+    ) -> Canvas {
+        let (width, height) = (columns.len(), tile.len());
+        let mut canvas = if self.transparent_background {
+            Canvas::with_alpha(width, height)
+        } else {
+            Canvas::new(width, height)
+        };
+        let threads = settings
+            .threads
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZeroUsize::get))
+            .clamp(1, spans.len().max(1));
+        let overridden = settings.world_for(world);
+        let world = overridden.as_ref().unwrap_or(world);
+        let camera = self.with_sampling(settings);
+        let adaptive = settings.adaptive.map(|adaptive| {
+            let importance = camera.importance_map(world, settings, adaptive.prepass_scale);
+
+            (adaptive, importance)
+        });
+        let depths = camera.tile_depths(world, settings.max_depth.unwrap_or(world.max_depth));
+
+        // Threads take the next span left whenever they finish one, so rows that take longer,
+        // like the ones full of reflections, don't hold the others up.
+        let next_span = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel();
+
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                let (next_span, spans, columns, depths, adaptive, sender) = (
+                    &next_span,
+                    &spans,
+                    &columns,
+                    &depths,
+                    &adaptive,
+                    sender.clone(),
+                );
+
+                scope.spawn(move || loop {
+                    let index = next_span.fetch_add(1, Ordering::Relaxed);
+                    let Some(&span) = spans.get(index) else {
+                        break;
+                    };
+                    if settings.is_cancelled() {
+                        break;
+                    }
+                    let y = span.y;
+                    let row: Vec<(Color, f64)> = span
+                        .columns(columns.end)
+                        .map(|x| {
+                            let depth = depths.at(x, y);
+                            let x = x as i32;
+                            camera
+                                .adapted(adaptive.as_ref(), x, y)
+                                .pixel_color(world, settings, depth, x, y as i32)
+                        })
+                        .collect();
+                    if sender.send((index, row)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(sender);
+
+            for (index, row) in receiver {
+                let span = spans[index];
+                let y = span.y - tile.start;
+                for (x, (color, alpha)) in span.columns(columns.end).zip(row) {
+                    let x = x - columns.start;
+                    canvas.write_pixel_with_alpha(x as i32, y as i32, color, alpha);
+                }
+                span_done(&mut canvas, index);
+            }
+        });
+
+        #[cfg(feature = "bidirectional")]
+        if let Integrator::Bidirectional { samples, .. } = world.integrator {
+            // Traced over the whole image, so tiles get the same light as the rest of it.
+            let image_width = self.hsize.max(0) as usize;
+            let paths = samples.max(1) * image_width * self.vsize.max(0) as usize;
+            let caustics = world.caustics(camera, paths, threads, || settings.is_cancelled());
+
+            for (index, light) in caustics.into_iter().flatten().enumerate() {
+                let (x, y) = (index % image_width, index / image_width);
+                if !columns.contains(&x) || !tile.contains(&y) {
+                    continue;
+                }
+                let (x, y) = ((x - columns.start) as i32, (y - tile.start) as i32);
+                let (color, alpha) = (canvas.pixel_at(x, y), canvas.alpha_at(x, y));
+                canvas.write_pixel_with_alpha(x, y, color + light, alpha);
+            }
         }
 
         canvas
     }
+
+    /// How many times rays through every tile of the image bounce at most: tiles none of the
+    /// `World::bouncing_bounds` show up in only need one bounce, since camera rays through
+    /// them never hit anything sending rays on.
+    ///
+    /// Rays from a lens start all over it, so with an aperture every tile keeps `depth`, and
+    /// so does every tile with distortion or a panorama, which bend straight lines.
+    fn tile_depths(self, world: &World, depth: i32) -> TileDepths {
+        let tiles = |size: i32| (size.max(0) as usize).div_ceil(DEPTH_TILE_SIZE);
+        let (columns, rows) = (tiles(self.hsize), tiles(self.vsize));
+        let mut tile_depths = TileDepths {
+            depth,
+            columns,
+            bouncing: vec![false; columns * rows],
+        };
+        if self.aperture > 0.
+            || self.distortion != LensDistortion::default()
+            || self.projection != Projection::Perspective
+        {
+            tile_depths.bouncing.fill(true);
+            return tile_depths;
+        }
+
+        for bounds in world.bouncing_bounds() {
+            let Some((x0, y0, x1, y1)) = self.pixel_extent(&bounds) else {
+                tile_depths.bouncing.fill(true);
+                break;
+            };
+            // Past the image, or a pixel around it for the rays through its edges.
+            let tile = |pixel: f64, tiles: usize| {
+                ((pixel.max(0.) as usize) / DEPTH_TILE_SIZE).min(tiles.saturating_sub(1))
+            };
+            if x1 < -1. || y1 < -1. || x0 > self.hsize as f64 + 1. || y0 > self.vsize as f64 + 1. {
+                continue;
+            }
+            for row in tile(y0 - 1., rows)..=tile(y1 + 1., rows) {
+                for column in tile(x0 - 1., columns)..=tile(x1 + 1., columns) {
+                    tile_depths.bouncing[row * columns + column] = true;
+                }
+            }
+        }
+
+        tile_depths
+    }
+
+    /// From where to where, in pixels, the box shows up in the image: the smallest and largest
+    /// `x` and `y` of its corners seen through the camera. `None` if the box reaches beside
+    /// or behind the camera, where it could show up anywhere.
+    fn pixel_extent(self, bounds: &BoundingBox) -> Option<(f64, f64, f64, f64)> {
+        if !bounds.is_finite() {
+            return None;
+        }
+
+        bounds.points().into_iter().try_fold(
+            (
+                f64::INFINITY,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::NEG_INFINITY,
+            ),
+            |(x0, y0, x1, y1), corner| {
+                let corner = self.transform * corner;
+                if corner.z > -EPSILON {
+                    return None;
+                }
+                let (x, y) = self.plane_to_pixel((corner.x / -corner.z, corner.y / -corner.z));
+
+                Some((x0.min(x), y0.min(y), x1.max(x), y1.max(y)))
+            },
+        )
+    }
+
+    /// The camera with the sampling the settings ask for in place of its own.
+    fn with_sampling(mut self, settings: &RenderSettings) -> Self {
+        if let Some(samples) = settings.samples_per_pixel {
+            self.sampler.samples_per_pixel = samples.max(1);
+        }
+        if let Some(light_sampling) = settings.light_sampling {
+            self.sampler.light_sampling = light_sampling;
+        }
+        if let Some(seed) = settings.seed {
+            self.sampler.seed = seed;
+        }
+
+        self
+    }
+
+    /// Renders the image `scale` times smaller with a sample per pixel and without the pixel
+    /// hook, to see where `AdaptiveSampling` should spend samples. The whole image even when
+    /// rendering a tile, so tiles sample their rows the same as a render of all of them would.
+    fn importance_map(
+        self,
+        world: &World,
+        settings: &RenderSettings,
+        scale: usize,
+    ) -> ImportanceMap {
+        let scale = scale.max(1) as i32;
+        let mut prepass = self;
+        prepass.hsize = ((self.hsize + scale - 1) / scale).max(1);
+        prepass.vsize = ((self.vsize + scale - 1) / scale).max(1);
+        prepass.sampler.samples_per_pixel = 1;
+        let prepass_settings = RenderSettings {
+            threads: settings.threads,
+            max_depth: settings.max_depth,
+            cancel: settings.cancel.clone(),
+            ..Default::default()
+        };
+
+        ImportanceMap::from_canvas(&prepass.render_rows(world, &prepass_settings, |_, _| {}))
+    }
+
+    /// The camera taking as many samples for pixel `(x, y)` as the importance map says,
+    /// or the camera as is without adaptive sampling.
+    fn adapted(
+        mut self,
+        adaptive: Option<&(AdaptiveSampling, ImportanceMap)>,
+        x: i32,
+        y: usize,
+    ) -> Self {
+        if let Some((adaptive, importance)) = adaptive {
+            let importance = importance.at(
+                (x as f64 + 0.5) / self.hsize as f64,
+                (y as f64 + 0.5) / self.vsize as f64,
+            );
+            self.sampler.samples_per_pixel =
+                adaptive.samples(importance, self.sampler.samples_per_pixel);
+        }
+
+        self
+    }
+
+    /// Averages all the samples the sampler takes for the pixel, weighting colors by their alpha.
+    fn pixel_color(
+        self,
+        world: &World,
+        settings: &RenderSettings,
+        depth: i32,
+        x: i32,
+        y: i32,
+    ) -> (Color, f64) {
+        let mut pixel = self.sampler.pixel(x, y, world.light_count());
+        let samples = pixel.samples();
+
+        let (total, total_alpha) = (0..samples)
+            .map(|index| {
+                let offset = pixel.offset(index);
+                let lens = if self.aperture > 0. {
+                    pixel.lens()
+                } else {
+                    (0., 0.)
+                };
+                let ray = self.ray_through(x, y, offset, lens);
+                let hooked = settings
+                    .pixel_hook
+                    .as_ref()
+                    .and_then(|hook| hook(&PixelContext::new(world, x, y, ray)));
+
+                if let Some(color) = hooked {
+                    (color, 1.)
+                } else if self.transparent_background {
+                    world.color_and_alpha_at_sampled(ray, depth, pixel.light(index))
+                } else {
+                    (
+                        world.color_at_with_lights(ray, depth, pixel.light(index)),
+                        1.,
+                    )
+                }
+            })
+            .fold((Color::black(), 0.), |(c1, a1), (c2, a2)| {
+                (c1 + c2 * a2, a1 + a2)
+            });
+
+        if total_alpha > 0. {
+            (total * (1. / total_alpha), total_alpha / samples as f64)
+        } else {
+            (Color::black(), 0.)
+        }
+    }
+}
+
+/// The rows of an image of `height` rows in passes, each one halving the gaps between
+/// the rows of the ones before: the first row, then the middle one, then the quarters...
+fn interleaved_rows(height: usize) -> Vec<Vec<usize>> {
+    if height == 0 {
+        return vec![];
+    }
+
+    let mut passes = vec![vec![0]];
+    let mut step = height.next_power_of_two() / 2;
+    while step > 0 {
+        passes.push((step..height).step_by(2 * step).collect());
+        step /= 2;
+    }
+
+    passes
+}
+
+/// The pixels from `start` up to `end` that are within `size` pixels.
+fn clamped(start: i32, end: i32, size: i32) -> Range<usize> {
+    let clamp = |edge: i32| edge.clamp(0, size.max(0)) as usize;
+
+    clamp(start)..clamp(end).max(clamp(start))
+}
+
+/// A copy of the canvas where only every `step_x`th pixel of every `step_y`th row is done,
+/// the rest copying the closest done pixel above and to the left of them.
+fn filled_in(canvas: &Canvas, step_x: usize, step_y: usize) -> Canvas {
+    let mut preview = canvas.clone();
+
+    for y in 0..canvas.height() as i32 {
+        let source_y = y - y % step_y as i32;
+        for x in 0..canvas.width() as i32 {
+            let source_x = x - x % step_x as i32;
+            preview.write_pixel_with_alpha(
+                x,
+                y,
+                canvas.pixel_at(source_x, source_y),
+                canvas.alpha_at(source_x, source_y),
+            );
+        }
+    }
+
+    preview
+}
+
+/// Every how many pixels across and down `render_coarse_to_fine` renders in each pass.
+const COARSE_STEPS: [usize; 4] = [8, 4, 2, 1];
+
+/// Every `step`th pixel of row `y` of the image, from column `start` on.
+/// Side of the square tiles, in pixels, that `Camera::tile_depths` caps the depth of.
+const DEPTH_TILE_SIZE: usize = 16;
+
+/// How many times rays through each `DEPTH_TILE_SIZE` tile of an image bounce at most,
+/// see `Camera::tile_depths`.
+struct TileDepths {
+    depth: i32,
+    columns: usize,
+    /// Whether anything sending rays on shows up in each tile, row by row.
+    bouncing: Vec<bool>,
+}
+
+impl TileDepths {
+    /// The depth of rays through pixel `(x, y)`.
+    fn at(&self, x: usize, y: usize) -> i32 {
+        let tile = (y / DEPTH_TILE_SIZE) * self.columns + x / DEPTH_TILE_SIZE;
+
+        if self.bouncing.get(tile).copied().unwrap_or(true) {
+            self.depth
+        } else {
+            self.depth.min(1)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Span {
+    y: usize,
+    start: usize,
+    step: usize,
+}
+
+impl Span {
+    /// The columns of the span, up to `end`.
+    fn columns(self, end: usize) -> StepBy<Range<usize>> {
+        (self.start..end).step_by(self.step)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::math::tuple::Tuple;
     use crate::{
-        color::Color, math::transformations::view_transform, misc::approx_equal, world::World,
+        color::Color,
+        material::Material,
+        math::transformations::view_transform,
+        misc::approx_equal,
+        render_settings::{CancelToken, Tile},
+        shape::Object,
+        world::{World, DEFAULT_ALLOWED_DEPTH},
+    };
+    use std::{
+        f64::consts::PI,
+        sync::{Arc, Mutex},
     };
-    use std::f64::consts::PI;
 
     #[test]
     fn constructing_a_camera() {
@@ -122,8 +921,17 @@ mod tests {
         let c = Camera::new(201, 101, PI / 2.);
         let r = c.ray_for_pixel(100, 50);
 
-        assert_eq!(r.origin, Tuple::point(0., 0., 0.));
-        assert_eq!(r.direction, Tuple::vector(0., 0., -1.));
+        assert_eq!(r.origin, Point3::new(0., 0., 0.));
+        assert_eq!(r.direction, Vec3::new(0., 0., -1.));
+    }
+
+    #[test]
+    fn rays_are_cast_at_the_time_of_the_camera() {
+        let mut c = Camera::new(201, 101, PI / 2.);
+        assert_eq!(c.ray_for_pixel(100, 50).time, 0.);
+
+        c.time = 2.5;
+        assert_eq!(c.ray_for_pixel(100, 50).time, 2.5);
     }
 
     #[test]
@@ -131,8 +939,8 @@ mod tests {
         let c = Camera::new(201, 101, PI / 2.);
         let r = c.ray_for_pixel(0, 0);
 
-        assert_eq!(r.origin, Tuple::point(0., 0., 0.));
-        assert_eq!(r.direction, Tuple::vector(0.66519, 0.33259, -0.66851));
+        assert_eq!(r.origin, Point3::new(0., 0., 0.));
+        assert_eq!(r.direction, Vec3::new(0.66519, 0.33259, -0.66851));
     }
 
     #[test]
@@ -142,13 +950,101 @@ mod tests {
 
         let r = c.ray_for_pixel(100, 50);
 
-        assert_eq!(r.origin, Tuple::point(0., 2., -5.));
+        assert_eq!(r.origin, Point3::new(0., 2., -5.));
         assert_eq!(
             r.direction,
-            Tuple::vector(2_f64.sqrt() / 2., 0., -2_f64.sqrt() / 2.)
+            Vec3::new(2_f64.sqrt() / 2., 0., -2_f64.sqrt() / 2.)
+        );
+    }
+
+    #[test]
+    fn barrel_distortion_widens_the_view_towards_the_edges() {
+        let mut c = Camera::new(201, 101, PI / 2.);
+        let undistorted = c.ray_for_pixel(0, 50);
+        c.distortion = LensDistortion::new(-0.2, 0.02);
+
+        // The middle of the picture stays put.
+        assert_eq!(c.ray_for_pixel(100, 50).direction, Vec3::new(0., 0., -1.));
+        let distorted = c.ray_for_pixel(0, 50);
+        assert!(distorted.direction.x > undistorted.direction.x);
+        assert!(approx_equal(distorted.direction.y, 0.));
+    }
+
+    #[test]
+    fn undistorting_undoes_distorting() {
+        let mut c = Camera::new(160, 120, PI / 2.);
+        c.distortion = LensDistortion::new(0.1, -0.01);
+
+        for &(x, y) in &[(0., 0.), (80., 60.), (13.5, 101.25), (160., 120.)] {
+            let (dx, dy) = c.distort_pixel(x, y);
+            let (ux, uy) = c.undistort_pixel(dx, dy);
+
+            assert!(approx_equal(ux, x) && approx_equal(uy, y));
+        }
+        let (x, y) = c.distort_pixel(80., 60.);
+        assert!(approx_equal(x, 80.) && approx_equal(y, 60.));
+        // Pincushion distortion pushes corners out.
+        let (x, y) = c.distort_pixel(0., 0.);
+        assert!(x < 0. && y < 0.);
+    }
+
+    #[test]
+    fn panoramas_look_all_the_way_around() {
+        let c = Camera::panorama(200, 0.);
+        let looking = |x: f64, y: f64| c.panorama_ray((x, y), 0.).direction;
+
+        assert_eq!((c.hsize, c.vsize), (200, 100));
+        assert_eq!(c.ray_for_pixel(100, 50).origin, Point3::new(0., 0., 0.));
+        assert_eq!(looking(100., 50.), Vec3::new(0., 0., -1.));
+        assert_eq!(looking(50., 50.), Vec3::new(1., 0., 0.));
+        assert_eq!(looking(0., 50.), Vec3::new(0., 0., 1.));
+        assert_eq!(looking(100., 0.), Vec3::new(0., 1., 0.));
+        assert_eq!(looking(100., 100.), Vec3::new(0., -1., 0.));
+    }
+
+    #[test]
+    fn stereo_panoramas_put_the_left_eye_over_the_right_one() {
+        let mut c = Camera::panorama(200, 0.1);
+        c.transform = Matrix4::translation(0., -1., 0.);
+
+        let left = c.panorama_ray((100., 50.), 0.1);
+        let right = c.panorama_ray((100., 150.), 0.1);
+
+        assert_eq!((c.hsize, c.vsize), (200, 200));
+        assert_eq!(left.origin, Point3::new(0.05, 1., 0.));
+        assert_eq!(right.origin, Point3::new(-0.05, 1., 0.));
+        assert_eq!(left.direction, right.direction);
+        // Looking to the left, the left eye is behind the right one.
+        assert_eq!(
+            c.panorama_ray((50., 50.), 0.1).origin,
+            Point3::new(0., 1., 0.05)
         );
     }
 
+    #[cfg(feature = "bidirectional")]
+    #[test]
+    fn points_show_up_in_the_pixels_whose_rays_go_through_them() {
+        let mut c = Camera::new(201, 101, PI / 2.);
+        c.transform = Matrix4::rotation_y(PI / 4.) * Matrix4::translation(0., -2., 5.);
+
+        for (x, y) in [(100, 50), (0, 0), (200, 100), (37, 81)] {
+            let ray = c.ray_for_pixel(x, y);
+            let (px, py, _) = c.pixel_showing(ray.position(7.)).unwrap();
+
+            assert_eq!((px as i32, py as i32), (x, y));
+        }
+        // Behind the camera.
+        let ray = c.ray_for_pixel(100, 50);
+        assert_eq!(c.pixel_showing(ray.position(-1.)), None);
+        // Pixels in the corners cover less of the view than the one in the middle.
+        let importance = |x, y| {
+            c.pixel_showing(c.ray_for_pixel(x, y).position(1.))
+                .unwrap()
+                .2
+        };
+        assert!(importance(0, 0) > importance(100, 50));
+    }
+
     #[test]
     fn rendering_a_world_with_a_camera() {
         let w = World::default();
@@ -164,4 +1060,372 @@ mod tests {
 
         assert_eq!(image.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
     }
+
+    #[test]
+    fn rendering_on_any_number_of_threads_gives_the_same_image() {
+        let w = World::default();
+        let mut c = Camera::new(15, 9, PI / 2.);
+        c.transform = view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        );
+        c.sampler = Sampler::new(4);
+        let render = |threads| {
+            let settings = RenderSettings {
+                threads: Some(threads),
+                ..Default::default()
+            };
+            let mut reported = vec![];
+            let image = c.render_with_settings(&w, &settings, |done, total| {
+                reported.push((done, total));
+            });
+
+            assert_eq!(reported.len(), 9);
+            assert_eq!(reported.last(), Some(&(15 * 9, 15 * 9)));
+            image.to_ppm()
+        };
+
+        assert_eq!(render(1), render(4));
+        assert_eq!(render(1), render(100));
+    }
+
+    #[test]
+    fn tiles_render_their_rows_of_the_whole_image() {
+        let w = World::default();
+        let mut c = Camera::new(7, 10, PI / 2.);
+        c.transform = view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        );
+        c.sampler = Sampler::new(4);
+        let whole = c.render_with_settings(&w, &RenderSettings::default(), |_, _| {});
+
+        for index in 1..=3 {
+            let tile = Tile::new(index, 3).unwrap();
+            let settings = RenderSettings {
+                tile: Some(tile),
+                ..Default::default()
+            };
+            let mut reported = vec![];
+            let image = c.render_with_settings(&w, &settings, |done, total| {
+                reported.push((done, total));
+            });
+            let rows = tile.rows(10);
+
+            assert_eq!(image.height(), rows.len());
+            assert_eq!(reported.last(), Some(&(7 * rows.len(), 7 * rows.len())));
+            for (y, whole_y) in rows.enumerate() {
+                for x in 0..7 {
+                    assert_eq!(
+                        image.pixel_at(x, y as i32),
+                        whole.pixel_at(x, whole_y as i32)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn regions_and_pixels_come_out_as_they_do_in_the_whole_image() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.transform = view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        );
+        c.sampler = Sampler::new(4);
+        let whole = c.render_with_settings(&w, &RenderSettings::default(), |_, _| {});
+
+        let region = c.render_region(&w, 3, 4, 8, 6);
+
+        assert_eq!((region.width(), region.height()), (5, 2));
+        for y in 0..2 {
+            for x in 0..5 {
+                assert_eq!(region.pixel_at(x, y), whole.pixel_at(x + 3, y + 4));
+            }
+        }
+        assert_eq!(c.render_pixel(&w, 5, 5), whole.pixel_at(5, 5));
+        assert_eq!(c.render_region(&w, 9, -2, 20, 1).width(), 2);
+        assert_eq!(c.render_region(&w, 5, 5, 2, 2).height(), 0);
+    }
+
+    #[test]
+    fn cancelling_a_render_stops_it_before_the_next_rows() {
+        let w = World::default();
+        let c = Camera::new(11, 11, PI / 2.);
+        let cancel = CancelToken::new();
+        let in_render = cancel.clone();
+        let settings = RenderSettings {
+            threads: Some(1),
+            cancel: Some(cancel.clone()),
+            // Cancels the render halfway through the first row.
+            pixel_hook: Some(Box::new(move |context| {
+                if context.x == 5 {
+                    in_render.cancel();
+                }
+                None
+            })),
+            ..Default::default()
+        };
+
+        let mut reported = vec![];
+        let result = c.try_render_with_settings(&w, &settings, |done, total| {
+            reported.push((done, total));
+        });
+
+        assert!(matches!(result, Err(RayTracerError::Cancelled)));
+        assert_eq!(reported, vec![(11, 11 * 11)]);
+
+        // Without the error, the rows left are black.
+        let image = c.render_with_settings(&w, &settings, |_, _| {});
+        assert_eq!(image.pixel_at(5, 5), Color::black());
+    }
+
+    #[test]
+    fn finished_renders_are_not_cancelled() {
+        let w = World::default();
+        let c = Camera::new(3, 3, PI / 2.);
+        let settings = RenderSettings {
+            cancel: Some(CancelToken::new()),
+            ..Default::default()
+        };
+
+        assert!(c.try_render_with_settings(&w, &settings, |_, _| {}).is_ok());
+    }
+
+    #[test]
+    fn only_tiles_showing_reflective_or_transparent_objects_bounce_more_than_once() {
+        let mut w = World::default();
+        let mut mirror = Object::sphere();
+        mirror.set_transform(Matrix4::translation(0., 3., 0.) * Matrix4::scaling(0.5, 0.5, 0.5));
+        mirror.set_material({
+            let mut material = Material::new();
+            material.reflective = 1.;
+            material
+        });
+        w.add_object(mirror);
+        let mut c = Camera::new(64, 64, PI / 2.);
+        c.transform = view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        );
+
+        // The mirror is up in the middle of the image, in the top two rows of tiles.
+        let depths = c.tile_depths(&w, 5);
+        let depth_per_tile: Vec<Vec<i32>> = (0..4)
+            .map(|row| {
+                (0..4)
+                    .map(|column| depths.at(column * 16, row * 16))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            depth_per_tile,
+            vec![
+                vec![1, 5, 5, 1],
+                vec![1, 5, 5, 1],
+                vec![1, 1, 1, 1],
+                vec![1, 1, 1, 1],
+            ]
+        );
+
+        // The matte tiles come out the same as they would with every bounce.
+        let image = c.render_with_progress(&w, |_, _| {});
+        for (x, y) in [(0, 0), (40, 40), (8, 60), (30, 10)] {
+            assert_eq!(image.pixel_at(x, y), c.render_pixel(&w, x, y));
+        }
+
+        c.aperture = 0.1;
+        assert_eq!(c.tile_depths(&w, 5).at(8, 60), 5);
+    }
+
+    #[test]
+    fn rows_are_rendered_spread_out_over_the_image() {
+        assert_eq!(
+            interleaved_rows(6),
+            vec![vec![0], vec![4], vec![2], vec![1, 3, 5]]
+        );
+        assert_eq!(interleaved_rows(1), vec![vec![0]]);
+
+        let mut all: Vec<usize> = interleaved_rows(100).concat();
+        all.sort();
+        assert_eq!(all, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn progressive_previews_fill_in_the_rows_left() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.transform = view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        );
+        let settings = RenderSettings {
+            threads: Some(3),
+            ..Default::default()
+        };
+
+        let mut previews = vec![];
+        let image = c.render_progressively(&w, &settings, |preview| previews.push(preview.clone()));
+
+        // Rows 0, 8, 4, then 2 and 6, then the odd ones.
+        assert_eq!(previews.len(), 5);
+        assert_eq!(previews[2].pixel_at(5, 5), image.pixel_at(5, 4));
+        assert_eq!(previews[3].pixel_at(5, 7), image.pixel_at(5, 6));
+        assert_eq!(previews[4].to_ppm(), image.to_ppm());
+        assert_eq!(image.to_ppm(), c.render(&w).to_ppm());
+    }
+
+    #[test]
+    fn coarse_previews_fill_in_every_eighth_pixel_first() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.transform = view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        );
+        let settings = RenderSettings {
+            threads: Some(3),
+            ..Default::default()
+        };
+
+        let mut previews = vec![];
+        let image =
+            c.render_coarse_to_fine(&w, &settings, |preview| previews.push(preview.clone()));
+
+        assert_eq!(previews.len(), 4);
+        assert_eq!(previews[0].pixel_at(5, 5), image.pixel_at(0, 0));
+        assert_eq!(previews[0].pixel_at(9, 3), image.pixel_at(8, 0));
+        assert_eq!(previews[1].pixel_at(5, 5), image.pixel_at(4, 4));
+        assert_eq!(previews[2].pixel_at(5, 5), image.pixel_at(4, 4));
+        assert_eq!(previews[2].pixel_at(7, 3), image.pixel_at(6, 2));
+        assert_eq!(previews[3].to_ppm(), image.to_ppm());
+        assert_eq!(image.to_ppm(), c.render(&w).to_ppm());
+
+        // Every pixel is rendered once.
+        let rendered = Arc::new(AtomicUsize::new(0));
+        let counter = rendered.clone();
+        let counting = RenderSettings {
+            pixel_hook: Some(Box::new(move |_| {
+                counter.fetch_add(1, Ordering::Relaxed);
+                None
+            })),
+            ..Default::default()
+        };
+        c.render_coarse_to_fine(&w, &counting, |_| {});
+        assert_eq!(rendered.load(Ordering::Relaxed), 11 * 11);
+    }
+
+    #[test]
+    fn multiple_samples_blend_the_edges_of_shapes() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.transform = view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        );
+        // The edge of the sphere goes through this pixel, but not through its center.
+        let (single, _) =
+            c.pixel_color(&w, &RenderSettings::default(), DEFAULT_ALLOWED_DEPTH, 6, 5);
+
+        c.set_samples_per_pixel(16);
+        let (blended, _) =
+            c.pixel_color(&w, &RenderSettings::default(), DEFAULT_ALLOWED_DEPTH, 6, 5);
+
+        assert!(blended.green > 0.);
+        assert!(blended.green < single.green);
+    }
+
+    #[test]
+    fn adaptive_sampling_spends_samples_on_edges() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.transform = view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        );
+        c.set_samples_per_pixel(16);
+        let samples = Arc::new(Mutex::new(vec![0; 11 * 11]));
+        let counted = samples.clone();
+        let settings = RenderSettings {
+            adaptive: Some(AdaptiveSampling::new(1, 1)),
+            pixel_hook: Some(Box::new(move |context: &PixelContext| {
+                counted.lock().unwrap()[(context.y * 11 + context.x) as usize] += 1;
+                None
+            })),
+            ..Default::default()
+        };
+
+        c.render_with_settings(&w, &settings, |_, _| {});
+
+        let samples = samples.lock().unwrap();
+        // The background all around the corner is the same black.
+        assert_eq!(samples[0], 1);
+        // The edge of the sphere goes through this pixel.
+        assert_eq!(samples[5 * 11 + 6], 16);
+    }
+
+    #[test]
+    fn rendering_with_a_transparent_background() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.transform = view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        );
+        c.transparent_background = true;
+
+        let image = c.render(&w);
+
+        assert_eq!(image.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
+        assert_eq!(image.alpha_at(5, 5), 1.);
+        assert_eq!(image.alpha_at(0, 0), 0.);
+    }
+
+    #[test]
+    fn a_lens_keeps_what_is_at_the_focal_distance_in_focus() {
+        let mut c = Camera::new(201, 101, PI / 2.);
+        c.transform = Matrix4::translation(0., 0., 5.);
+        c.aperture = 0.5;
+        c.focal_distance = 4.;
+
+        let center = c.ray_for_pixel(100, 50);
+        let edge = c.ray_through(100, 50, (0.5, 0.5), (1., 0.));
+        let focus = center.position(4.);
+        let t = (focus - edge.origin).magnitude();
+
+        assert_eq!(edge.origin, Point3::new(0.25, 0., -5.));
+        assert_eq!(edge.position(t), focus);
+    }
+
+    #[test]
+    fn wide_apertures_blur_what_is_out_of_focus() {
+        let w = World::default();
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.transform = view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        );
+        c.set_samples_per_pixel(16);
+        let (sharp, _) = c.pixel_color(&w, &RenderSettings::default(), DEFAULT_ALLOWED_DEPTH, 3, 5);
+
+        // Focused far behind the spheres, some rays from the lens reach them from the edge.
+        c.aperture = 4.;
+        c.focal_distance = 20.;
+        let (blurred, _) =
+            c.pixel_color(&w, &RenderSettings::default(), DEFAULT_ALLOWED_DEPTH, 3, 5);
+
+        assert_eq!(sharp, Color::black());
+        assert!(blurred.green > 0.);
+    }
 }
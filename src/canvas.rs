@@ -1,9 +1,18 @@
-use crate::color::Color;
-
+use std::io::Write;
+
+use crate::{
+    color::Color,
+    color_buffer::ColorBuffer,
+    error::{RayTracerError, Result},
+    lut::Lut,
+    png::{self, ColorType},
+    tone::ToneMap,
+};
+
+/// An image to export, encoding the `ColorBuffer` it wraps as PPM, PNG or Radiance HDR.
+#[derive(Clone)]
 pub struct Canvas {
-    width: usize,
-    height: usize,
-    pixels: Vec<Color>,
+    buffer: ColorBuffer,
 }
 
 const MAX_COLOR_VALUE: i32 = 255;
@@ -11,51 +20,278 @@ const MAX_PPM_LINE_LENGTH: usize = 70;
 
 impl Canvas {
     pub fn new(width: usize, height: usize) -> Self {
-        let pixels = vec![Color::new(0., 0., 0.); width * height];
+        ColorBuffer::new(width, height).into()
+    }
 
-        Self {
-            width,
-            height,
-            pixels,
-        }
+    /// A canvas that also keeps how opaque each pixel is, starting out fully transparent.
+    pub fn with_alpha(width: usize, height: usize) -> Self {
+        ColorBuffer::with_alpha(width, height).into()
     }
 
     pub fn write_pixel(&mut self, x: i32, y: i32, color: Color) {
-        if let Some(index) = self.get_index(x, y) {
-            self.pixels[index] = color;
-        }
+        self.buffer.write_pixel(x, y, color);
+    }
+
+    /// Panics if the pixel is outside the canvas.
+    pub fn pixel_at(&self, x: i32, y: i32) -> Color {
+        self.buffer.pixel_at(x, y)
+    }
+
+    /// Like `write_pixel`, also setting how opaque the pixel is if the canvas keeps track of it.
+    pub fn write_pixel_with_alpha(&mut self, x: i32, y: i32, color: Color, alpha: f64) {
+        self.buffer.write_pixel_with_alpha(x, y, color, alpha);
+    }
+
+    /// How opaque the pixel is, pixels of canvases without an alpha channel being opaque.
+    pub fn alpha_at(&self, x: i32, y: i32) -> f64 {
+        self.buffer.alpha_at(x, y)
     }
 
     pub fn width(&self) -> usize {
-        self.width
+        self.buffer.width()
     }
 
     pub fn height(&self) -> usize {
-        self.height
+        self.buffer.height()
     }
 
-    fn get_index(&self, x: i32, y: i32) -> Option<usize> {
-        let in_bounds = 0 <= x && x < self.width as i32 && 0 <= y && y < self.height as i32;
+    /// The pixels of the canvas, as floating point colors.
+    pub fn buffer(&self) -> &ColorBuffer {
+        &self.buffer
+    }
+
+    pub fn buffer_mut(&mut self) -> &mut ColorBuffer {
+        &mut self.buffer
+    }
 
-        if in_bounds {
-            Some(x as usize + y as usize * self.width)
-        } else {
-            None
+    pub fn into_buffer(self) -> ColorBuffer {
+        self.buffer
+    }
+
+    /// Reads a plain (P3) PPM image, like the ones `to_ppm` writes.
+    pub fn from_ppm(source: &str) -> Result<Canvas> {
+        let mut tokens = source
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default())
+            .flat_map(str::split_whitespace);
+
+        if tokens.next() != Some("P3") {
+            return Err(invalid_data("only plain PPM images (P3) are supported"));
         }
+        let mut number = || -> Result<f64> {
+            tokens
+                .next()
+                .and_then(|token| token.parse::<u32>().ok())
+                .map(f64::from)
+                .ok_or_else(|| invalid_data("expected a number"))
+        };
+        let width = number()? as usize;
+        let height = number()? as usize;
+        let max_value = number()?.max(1.);
+
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                let (red, green, blue) = (number()?, number()?, number()?);
+                canvas.write_pixel(x, y, Color::new(red, green, blue) * (1. / max_value));
+            }
+        }
+
+        Ok(canvas)
     }
 
     pub fn to_ppm(&self) -> String {
-        let ppm_header = format!("P3\n{} {}\n{}", self.width, self.height, MAX_COLOR_VALUE);
+        let ppm_header = format!(
+            "P3\n{} {}\n{}",
+            self.width(),
+            self.height(),
+            MAX_COLOR_VALUE
+        );
 
         let ppm_body: String = self
-            .pixels
-            .chunks(self.width)
+            .buffer
+            .pixels()
+            .chunks(self.width())
             .map(process_row)
             .collect::<Vec<_>>()
             .join("\n");
 
         ppm_header + "\n" + &ppm_body + "\n"
     }
+
+    /// Streams the canvas as a binary (P6) PPM image, a row at a time, which is much smaller
+    /// and faster to write than the plain one `to_ppm` builds for big renders.
+    /// Wrap files in a `BufWriter`, rows are written as they are.
+    pub fn write_ppm<W: Write>(&self, mut writer: W) -> Result<()> {
+        write!(
+            writer,
+            "P6\n{} {}\n{}\n",
+            self.width(),
+            self.height(),
+            MAX_COLOR_VALUE
+        )?;
+
+        let mut bytes = Vec::with_capacity(3 * self.width());
+        for row in self.buffer.pixels().chunks(self.width().max(1)) {
+            bytes.clear();
+            bytes.extend(row.iter().flat_map(|pixel| {
+                let [red, green, blue, _] = to_bytes(*pixel, 1.);
+
+                [red, green, blue]
+            }));
+            writer.write_all(&bytes)?;
+        }
+
+        Ok(writer.flush()?)
+    }
+
+    /// A copy of the canvas with every pixel looked up in the table, to export graded renders.
+    pub fn graded(&self, lut: &Lut) -> Canvas {
+        self.buffer.map(|pixel| lut.apply(pixel)).into()
+    }
+
+    /// A copy of the canvas with every pixel tone mapped, to export renders with highlights
+    /// brighter than white without blowing them out.
+    pub fn tone_mapped(&self, tone_map: ToneMap) -> Canvas {
+        self.buffer.map(|pixel| tone_map.apply(pixel)).into()
+    }
+
+    /// Encodes the canvas as a Radiance `.hdr` image, which keeps colors over 1 as they are
+    /// for tone mapping or compositing elsewhere. Negative channels are cut off at 0.
+    pub fn to_hdr(&self) -> Vec<u8> {
+        let mut hdr = format!(
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            self.height(),
+            self.width()
+        )
+        .into_bytes();
+
+        for row in self.buffer.pixels().chunks(self.width().max(1)) {
+            let pixels: Vec<[u8; 4]> = row.iter().map(|pixel| to_rgbe(*pixel)).collect();
+
+            // Rows this short or long can't be run length encoded, and go as they are.
+            if !(8..=0x7fff).contains(&self.width()) {
+                hdr.extend(pixels.iter().flatten());
+                continue;
+            }
+            // Run length encoded rows hold each channel after the other, here in literal
+            // runs only, which readers take as the start of a row can't be mistaken for
+            // a pixel.
+            hdr.extend_from_slice(&[2, 2, (self.width() >> 8) as u8, self.width() as u8]);
+            for channel in 0..4 {
+                let bytes: Vec<u8> = pixels.iter().map(|pixel| pixel[channel]).collect();
+                for run in bytes.chunks(128) {
+                    hdr.push(run.len() as u8);
+                    hdr.extend_from_slice(run);
+                }
+            }
+        }
+
+        hdr
+    }
+
+    /// Encodes the canvas as a 16 bit PNG, with smoother gradients than 8 bits can keep,
+    /// clamped the same way and without alpha.
+    pub fn to_png_16(&self) -> Vec<u8> {
+        let rgb: Vec<u8> = self
+            .buffer
+            .pixels()
+            .iter()
+            .flat_map(|pixel| [pixel.red, pixel.green, pixel.blue])
+            .flat_map(|component| {
+                let value = (component.clamp(0., 1.) * u16::MAX as f64).round() as u16;
+
+                value.to_be_bytes()
+            })
+            .collect();
+
+        png::encode(self.width(), self.height(), ColorType::Rgb16, &rgb)
+    }
+
+    /// Encodes the canvas as a PNG, with an alpha channel if the canvas has one.
+    pub fn to_png(&self) -> Vec<u8> {
+        if self.buffer.has_alpha() {
+            return png::encode(
+                self.width(),
+                self.height(),
+                ColorType::Rgba,
+                &self.to_rgba(),
+            );
+        }
+
+        let rgb: Vec<u8> = self
+            .buffer
+            .pixels()
+            .iter()
+            .flat_map(|pixel| {
+                let [red, green, blue, _] = to_bytes(*pixel, 1.);
+
+                [red, green, blue]
+            })
+            .collect();
+
+        png::encode(self.width(), self.height(), ColorType::Rgb, &rgb)
+    }
+
+    /// The pixels row by row, four bytes each, opaque unless the canvas has an alpha channel.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.buffer
+            .pixels()
+            .iter()
+            .enumerate()
+            .flat_map(|(index, pixel)| {
+                let alpha = self.buffer.alphas().map_or(1., |alphas| alphas[index]);
+
+                to_bytes(*pixel, alpha)
+            })
+            .collect()
+    }
+}
+
+impl From<ColorBuffer> for Canvas {
+    fn from(buffer: ColorBuffer) -> Self {
+        Self { buffer }
+    }
+}
+
+fn to_bytes(pixel: Color, alpha: f64) -> [u8; 4] {
+    let scaled_pixel = pixel * (MAX_COLOR_VALUE as f64);
+    let byte = |component: f64| component.clamp(0., MAX_COLOR_VALUE as f64).round() as u8;
+
+    [
+        byte(scaled_pixel.red),
+        byte(scaled_pixel.green),
+        byte(scaled_pixel.blue),
+        byte(alpha * MAX_COLOR_VALUE as f64),
+    ]
+}
+
+/// A color as Radiance stores it: a byte for each channel, sharing the exponent in the fourth.
+fn to_rgbe(pixel: Color) -> [u8; 4] {
+    let (red, green, blue) = (pixel.red.max(0.), pixel.green.max(0.), pixel.blue.max(0.));
+    let brightest = red.max(green).max(blue);
+    if brightest < 1e-32 {
+        return [0; 4];
+    }
+
+    // The exponent that brings the brightest channel between 0.5 and 1.
+    let mut exponent = brightest.log2().floor() as i32 + 1;
+    if brightest / 2_f64.powi(exponent) >= 1. {
+        exponent += 1;
+    }
+    let scale = 256. / 2_f64.powi(exponent);
+    let byte = |component: f64| (component * scale).min(255.) as u8;
+
+    [
+        byte(red),
+        byte(green),
+        byte(blue),
+        (exponent + 128).clamp(0, 255) as u8,
+    ]
+}
+
+fn invalid_data(message: &str) -> RayTracerError {
+    RayTracerError::Parse(message.to_string())
 }
 
 fn process_row(row: &[Color]) -> String {
@@ -85,7 +321,7 @@ fn process_pixel(
             result_string += " ";
             char_count += 1;
         }
-        result_string += &component;
+        result_string += component;
         char_count += component.len();
     }
 
@@ -102,25 +338,17 @@ fn format_scaled_color(color_component: f64) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    impl Canvas {
-        pub fn pixel_at(&self, x: i32, y: i32) -> Color {
-            if let Some(index) = self.get_index(x, y) {
-                self.pixels[index]
-            } else {
-                panic!("trying to get a pixel outside bounds")
-            }
-        }
-    }
+    use crate::tone::ToneOperator;
 
     #[test]
     fn creating_a_canvas() {
         let c = Canvas::new(10, 20);
 
-        assert_eq!(c.width, 10);
-        assert_eq!(c.height, 20);
+        assert_eq!(c.width(), 10);
+        assert_eq!(c.height(), 20);
         assert!(c
-            .pixels
+            .buffer
+            .pixels()
             .iter()
             .all(|pixel| *pixel == Color::new(0., 0., 0.)))
     }
@@ -171,7 +399,7 @@ mod tests {
     fn splitting_long_lines_in_ppm_files() {
         let mut c = Canvas::new(10, 2);
 
-        c.pixels.fill(Color::new(1., 0.8, 0.6));
+        c.buffer_mut().pixels_mut().fill(Color::new(1., 0.8, 0.6));
 
         let ppm = c.to_ppm();
 
@@ -192,6 +420,116 @@ mod tests {
         assert_eq!(ppm.chars().last().unwrap(), '\n');
     }
 
+    #[test]
+    fn writing_a_binary_ppm() {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(0, 0, Color::new(1.5, 0., 0.));
+        c.write_pixel(1, 1, Color::new(0., 0.5, -0.5));
+
+        let mut ppm = vec![];
+        c.write_ppm(&mut ppm).unwrap();
+
+        assert_eq!(&ppm[..11], b"P6\n2 2\n255\n");
+        assert_eq!(&ppm[11..], &[255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 128, 0]);
+    }
+
+    #[test]
+    fn png_pixels_are_clamped_like_ppm_ones() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(1.5, 0.5, -0.5));
+        let png = c.to_png();
+
+        let idat = png.windows(4).position(|w| w == b"IDAT").unwrap();
+        assert_eq!(&png[idat + 11..idat + 15], &[0, 255, 128, 0]);
+    }
+
+    #[test]
+    fn reading_a_ppm_file() {
+        let ppm = "P3\n# made by hand\n2 1\n10\n10 0 5  0 10 0\n";
+        let c = Canvas::from_ppm(ppm).unwrap();
+
+        assert_eq!(c.width(), 2);
+        assert_eq!(c.height(), 1);
+        assert_eq!(c.pixel_at(0, 0), Color::new(1., 0., 0.5));
+        assert_eq!(c.pixel_at(1, 0), Color::new(0., 1., 0.));
+
+        let written = Canvas::from_ppm(&c.to_ppm()).unwrap();
+        assert_eq!(written.pixel_at(0, 0), Color::new(1., 0., 0.50196));
+        assert!(Canvas::from_ppm("P6\n1 1\n255\n").is_err());
+        assert!(Canvas::from_ppm("P3\n2 1\n255\n0 0 0").is_err());
+    }
+
+    #[test]
+    fn canvases_with_alpha_export_transparent_pngs() {
+        let mut c = Canvas::with_alpha(2, 1);
+        c.write_pixel_with_alpha(0, 0, Color::new(1., 0., 0.), 0.5);
+
+        assert_eq!(c.alpha_at(0, 0), 0.5);
+        assert_eq!(c.alpha_at(1, 0), 0.);
+        assert_eq!(Canvas::new(2, 1).alpha_at(1, 0), 1.);
+
+        let png = c.to_png();
+        let idat = png.windows(4).position(|w| w == b"IDAT").unwrap();
+        assert_eq!(&png[idat + 11..idat + 20], &[0, 255, 0, 0, 128, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn grading_a_canvas_with_a_lookup_table() {
+        let lut = Lut::from_cube("LUT_1D_SIZE 2\n1 1 1\n0 0 0\n").unwrap();
+        let mut c = Canvas::with_alpha(2, 1);
+        c.write_pixel_with_alpha(0, 0, Color::new(1., 0.25, 0.), 0.5);
+
+        let graded = c.graded(&lut);
+
+        assert_eq!(graded.pixel_at(0, 0), Color::new(0., 0.75, 1.));
+        assert_eq!(graded.pixel_at(1, 0), Color::white());
+        assert_eq!(graded.alpha_at(0, 0), 0.5);
+    }
+
+    #[test]
+    fn exposing_and_tone_mapping_a_canvas() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(3., 1., 0.5));
+
+        let mapped = c.tone_mapped(ToneMap::new(ToneOperator::Reinhard, -1.));
+
+        assert_eq!(mapped.pixel_at(0, 0), Color::new(0.6, 1. / 3., 0.2));
+        assert_eq!(mapped.pixel_at(1, 0), Color::black());
+    }
+
+    #[test]
+    fn writing_a_radiance_hdr() {
+        let mut c = Canvas::new(1, 1);
+        c.write_pixel(0, 0, Color::new(1., 0.5, 0.25));
+        let hdr = c.to_hdr();
+        let header = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 1\n";
+
+        assert_eq!(&hdr[..header.len()], header);
+        assert_eq!(&hdr[header.len()..], &[128, 64, 32, 129]);
+
+        // Wider rows are run length encoded, a channel at a time.
+        let mut c = Canvas::new(8, 1);
+        c.write_pixel(0, 0, Color::new(6., 0., 0.));
+        let hdr = c.to_hdr();
+        let row = &hdr[hdr.len() - 4 - 4 * 9..];
+
+        assert_eq!(&row[..4], &[2, 2, 0, 8]);
+        assert_eq!(&row[4..6], &[8, 192]);
+        assert_eq!(&row[4 + 9 * 3..4 + 9 * 3 + 2], &[8, 131]);
+    }
+
+    #[test]
+    fn writing_a_16_bit_png() {
+        let mut c = Canvas::new(1, 1);
+        c.write_pixel(0, 0, Color::new(1.5, 0.5, -0.5));
+        let png = c.to_png_16();
+
+        // Bit depth and color type.
+        assert_eq!(&png[24..26], &[16, 2]);
+        let idat = png.windows(4).position(|w| w == b"IDAT").unwrap();
+        assert_eq!(&png[idat + 11..idat + 18], &[0, 255, 255, 128, 0, 0, 0]);
+    }
+
     /// Returns the lines in the range [start, end] (inclusive!!!)
     fn get_lines(s: &str, start: usize, end: usize) -> String {
         s.split_inclusive("\n")
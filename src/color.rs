@@ -9,11 +9,11 @@ pub struct Color {
 }
 
 impl Color {
-    pub fn new(red: f64, green: f64, blue: f64) -> Self {
+    pub const fn new(red: f64, green: f64, blue: f64) -> Self {
         Self { red, green, blue }
     }
 
-    pub fn black() -> Self {
+    pub const fn black() -> Self {
         Self {
             red: 0.,
             green: 0.,
@@ -21,7 +21,7 @@ impl Color {
         }
     }
 
-    pub fn white() -> Self {
+    pub const fn white() -> Self {
         Self {
             red: 1.,
             green: 1.,
@@ -29,8 +29,13 @@ impl Color {
         }
     }
 
+    /// How bright the color looks, weighting green the most and blue the least the way eyes do.
+    pub fn luminance(self) -> f64 {
+        0.2126 * self.red + 0.7152 * self.green + 0.0722 * self.blue
+    }
+
     #[allow(dead_code)]
-    pub fn red() -> Self {
+    pub const fn red() -> Self {
         Self {
             red: 1.,
             green: 0.,
@@ -39,7 +44,7 @@ impl Color {
     }
 
     #[allow(dead_code)]
-    pub fn green() -> Self {
+    pub const fn green() -> Self {
         Self {
             red: 0.,
             green: 1.,
@@ -48,7 +53,7 @@ impl Color {
     }
 
     #[allow(dead_code)]
-    pub fn blue() -> Self {
+    pub const fn blue() -> Self {
         Self {
             red: 0.,
             green: 0.,
@@ -0,0 +1,186 @@
+use crate::color::Color;
+
+/// The pixels of an image as floating point colors, row by row from the top left,
+/// without anything to do with encoding them. `Canvas` wraps one to export it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorBuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    /// How opaque each pixel is, from 0 to 1, for buffers meant to be composited.
+    alpha: Option<Vec<f64>>,
+}
+
+impl ColorBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Color::black(); width * height],
+            alpha: None,
+        }
+    }
+
+    /// A buffer that also keeps how opaque each pixel is, starting out fully transparent.
+    pub fn with_alpha(width: usize, height: usize) -> Self {
+        Self {
+            alpha: Some(vec![0.; width * height]),
+            ..Self::new(width, height)
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        self.alpha.is_some()
+    }
+
+    /// Every pixel, row by row.
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [Color] {
+        &mut self.pixels
+    }
+
+    /// How opaque every pixel is, row by row, if the buffer keeps track of it.
+    pub fn alphas(&self) -> Option<&[f64]> {
+        self.alpha.as_deref()
+    }
+
+    /// Writing outside the buffer does nothing.
+    pub fn write_pixel(&mut self, x: i32, y: i32, color: Color) {
+        if let Some(index) = self.index(x, y) {
+            self.pixels[index] = color;
+        }
+    }
+
+    /// Panics if the pixel is outside the buffer.
+    pub fn pixel_at(&self, x: i32, y: i32) -> Color {
+        match self.index(x, y) {
+            Some(index) => self.pixels[index],
+            None => panic!("trying to get a pixel outside bounds"),
+        }
+    }
+
+    /// Like `write_pixel`, also setting how opaque the pixel is if the buffer keeps track of it.
+    pub fn write_pixel_with_alpha(&mut self, x: i32, y: i32, color: Color, alpha: f64) {
+        if let Some(index) = self.index(x, y) {
+            self.pixels[index] = color;
+
+            if let Some(ref mut alphas) = self.alpha {
+                alphas[index] = alpha;
+            }
+        }
+    }
+
+    /// How opaque the pixel is, pixels of buffers without an alpha channel being opaque.
+    pub fn alpha_at(&self, x: i32, y: i32) -> f64 {
+        match (&self.alpha, self.index(x, y)) {
+            (Some(alphas), Some(index)) => alphas[index],
+            _ => 1.,
+        }
+    }
+
+    /// Adds `color` to the pixel, to sum up samples over several passes before `scale`
+    /// averages them.
+    pub fn add_to_pixel(&mut self, x: i32, y: i32, color: Color) {
+        if let Some(index) = self.index(x, y) {
+            self.pixels[index] = self.pixels[index] + color;
+        }
+    }
+
+    /// Multiplies every pixel by `factor`, leaving alpha as it is.
+    pub fn scale(&mut self, factor: f64) {
+        for pixel in &mut self.pixels {
+            *pixel = *pixel * factor;
+        }
+    }
+
+    /// Adds a row at the bottom, growing the buffer a pixel taller, with opaque pixels if
+    /// it keeps alpha.
+    ///
+    /// Panics if the row isn't as wide as the buffer.
+    pub fn push_row(&mut self, row: &[Color]) {
+        assert_eq!(row.len(), self.width, "rows must be as wide as the buffer");
+
+        self.pixels.extend_from_slice(row);
+        if let Some(ref mut alphas) = self.alpha {
+            alphas.resize(alphas.len() + row.len(), 1.);
+        }
+        self.height += 1;
+    }
+
+    /// A copy of the buffer with `f` applied to every pixel.
+    pub fn map(&self, f: impl Fn(Color) -> Color) -> Self {
+        Self {
+            pixels: self.pixels.iter().map(|pixel| f(*pixel)).collect(),
+            ..self.clone()
+        }
+    }
+
+    /// The channels of every pixel one after the other, red, green, blue, and alpha if
+    /// the buffer keeps it, to hand over to code that only deals in numbers.
+    pub fn to_f64s(&self) -> Vec<f64> {
+        match &self.alpha {
+            Some(alphas) => self
+                .pixels
+                .iter()
+                .zip(alphas)
+                .flat_map(|(pixel, alpha)| [pixel.red, pixel.green, pixel.blue, *alpha])
+                .collect(),
+            None => self
+                .pixels
+                .iter()
+                .flat_map(|pixel| [pixel.red, pixel.green, pixel.blue])
+                .collect(),
+        }
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        let in_bounds = 0 <= x && x < self.width as i32 && 0 <= y && y < self.height as i32;
+
+        if in_bounds {
+            Some(x as usize + y as usize * self.width)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffers_hand_out_their_channels_as_numbers() {
+        let mut buffer = ColorBuffer::new(2, 1);
+        buffer.write_pixel(1, 0, Color::new(0.25, 1.5, -0.5));
+
+        assert_eq!(buffer.to_f64s(), vec![0., 0., 0., 0.25, 1.5, -0.5]);
+
+        let mut transparent = ColorBuffer::with_alpha(1, 1);
+        transparent.write_pixel_with_alpha(0, 0, Color::white(), 0.5);
+        assert_eq!(transparent.to_f64s(), vec![1., 1., 1., 0.5]);
+    }
+
+    #[test]
+    fn accumulating_and_growing_a_buffer() {
+        let mut buffer = ColorBuffer::with_alpha(2, 0);
+        buffer.push_row(&[Color::new(0.2, 0.4, 0.6), Color::black()]);
+        buffer.add_to_pixel(0, 0, Color::new(0.2, 0.4, 0.6));
+        buffer.add_to_pixel(0, 1, Color::white());
+        buffer.scale(0.5);
+
+        assert_eq!(buffer.height(), 1);
+        assert_eq!(buffer.pixel_at(0, 0), Color::new(0.2, 0.4, 0.6));
+        assert_eq!(buffer.alpha_at(1, 0), 1.);
+    }
+}
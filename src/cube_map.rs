@@ -0,0 +1,258 @@
+use crate::{
+    canvas::Canvas,
+    color::Color,
+    error::{RayTracerError, Result},
+    math::vec3::Vec3,
+};
+
+/// An environment surrounding the whole world, six images on the inside of a cube,
+/// looked up by the direction of the rays that miss everything else.
+///
+/// Faces are seen from inside the cube, with `+y` up on the sides.
+#[derive(Clone)]
+pub struct CubeMap {
+    faces: [Canvas; 6],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Face {
+    Right,
+    Left,
+    Up,
+    Down,
+    Front,
+    Back,
+}
+
+impl CubeMap {
+    /// Faces go in the order of `Face`: `+x`, `-x`, `+y`, `-y`, `+z` and `-z`.
+    pub fn new(faces: [Canvas; 6]) -> Self {
+        Self { faces }
+    }
+
+    /// Slices an image laid out as a horizontal cross, four faces wide and three tall:
+    ///
+    /// ```text
+    ///         +y
+    ///     -x  +z  +x  -z
+    ///         -y
+    /// ```
+    pub fn from_cross(image: &Canvas) -> Result<Self> {
+        let size = image.width() / 4;
+
+        if size == 0 || image.width() != 4 * size || image.height() != 3 * size {
+            return Err(RayTracerError::Parse(
+                "cross images are four square faces wide and three tall".to_string(),
+            ));
+        }
+
+        let face = |column: usize, row: usize| {
+            let mut face = Canvas::new(size, size);
+            for y in 0..size {
+                for x in 0..size {
+                    let color = image.pixel_at((column * size + x) as i32, (row * size + y) as i32);
+                    face.write_pixel(x as i32, y as i32, color);
+                }
+            }
+
+            face
+        };
+
+        Ok(Self::new([
+            face(2, 1),
+            face(0, 1),
+            face(1, 0),
+            face(1, 2),
+            face(1, 1),
+            face(3, 1),
+        ]))
+    }
+
+    /// Faces of `size` by `size` pixels, each colored by `color` with the direction through it.
+    pub fn from_fn(size: usize, color: impl Fn(Vec3) -> Color) -> Self {
+        let face = |face: Face| {
+            let mut canvas = Canvas::new(size, size);
+            for y in 0..size {
+                for x in 0..size {
+                    let u = 2. * (x as f64 + 0.5) / size as f64 - 1.;
+                    let v = 1. - 2. * (y as f64 + 0.5) / size as f64;
+                    let direction = match face {
+                        Face::Right => Vec3::new(1., v, -u),
+                        Face::Left => Vec3::new(-1., v, u),
+                        Face::Up => Vec3::new(u, 1., -v),
+                        Face::Down => Vec3::new(u, -1., v),
+                        Face::Front => Vec3::new(u, v, 1.),
+                        Face::Back => Vec3::new(-u, v, -1.),
+                    };
+
+                    canvas.write_pixel(x as i32, y as i32, color(direction.normalize()));
+                }
+            }
+
+            canvas
+        };
+
+        Self::new(
+            [
+                Face::Right,
+                Face::Left,
+                Face::Up,
+                Face::Down,
+                Face::Front,
+                Face::Back,
+            ]
+            .map(face),
+        )
+    }
+
+    pub fn face(&self, face: Face) -> &Canvas {
+        &self.faces[face as usize]
+    }
+
+    /// The color seen looking towards `direction`.
+    pub fn color_at(&self, direction: Vec3) -> Color {
+        let (face, u, v) = face_uv(direction);
+        let canvas = self.face(face);
+        let pixel = |coordinate: f64, size: usize| {
+            ((coordinate * size as f64) as i32).clamp(0, size as i32 - 1)
+        };
+
+        canvas.pixel_at(pixel(u, canvas.width()), pixel(1. - v, canvas.height()))
+    }
+}
+
+/// The face the direction points at, and where on it, from 0 to 1 rightwards and upwards.
+fn face_uv(direction: Vec3) -> (Face, f64, f64) {
+    let Vec3 { x, y, z } = direction;
+    let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+    let coordinate = |numerator: f64, denominator: f64| (numerator / denominator + 1.) / 2.;
+
+    if ax >= ay && ax >= az {
+        if x > 0. {
+            (Face::Right, coordinate(-z, ax), coordinate(y, ax))
+        } else {
+            (Face::Left, coordinate(z, ax), coordinate(y, ax))
+        }
+    } else if ay >= az {
+        if y > 0. {
+            (Face::Up, coordinate(x, ay), coordinate(-z, ay))
+        } else {
+            (Face::Down, coordinate(x, ay), coordinate(z, ay))
+        }
+    } else if z > 0. {
+        (Face::Front, coordinate(x, az), coordinate(y, az))
+    } else {
+        (Face::Back, coordinate(-x, az), coordinate(y, az))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::misc::approx_equal;
+
+    /// A cross image where every face is a different shade of gray, a tenth per face.
+    fn cross(size: usize) -> Canvas {
+        let mut image = Canvas::new(4 * size, 3 * size);
+        let faces = [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)];
+
+        for (index, (column, row)) in faces.iter().enumerate() {
+            let shade = (index + 1) as f64 / 10.;
+            for y in 0..size {
+                for x in 0..size {
+                    image.write_pixel(
+                        (column * size + x) as i32,
+                        (row * size + y) as i32,
+                        Color::new(shade, shade, shade),
+                    );
+                }
+            }
+        }
+
+        image
+    }
+
+    #[test]
+    fn directions_look_at_the_face_they_point_to() {
+        let cases = [
+            (Vec3::new(-1., 0.5, -0.1), Face::Left, 0.45, 0.75),
+            (Vec3::new(1.1, 0.5, -0.1), Face::Right, 0.54545, 0.72727),
+            (Vec3::new(0.1, 1., 0.9), Face::Up, 0.55, 0.05),
+            (Vec3::new(-0.5, -1., 0.1), Face::Down, 0.25, 0.55),
+            (Vec3::new(-0.5, 0.5, 1.), Face::Front, 0.25, 0.75),
+            (Vec3::new(0.5, -0.5, -1.), Face::Back, 0.25, 0.25),
+        ];
+
+        for (direction, face, u, v) in cases {
+            let (f, fu, fv) = face_uv(direction);
+            assert_eq!(f, face);
+            assert!(approx_equal(fu, u), "{:?}", direction);
+            assert!(approx_equal(fv, v), "{:?}", direction);
+        }
+    }
+
+    #[test]
+    fn slicing_a_cross_image_into_faces() {
+        let map = CubeMap::from_cross(&cross(2)).unwrap();
+
+        assert_eq!(map.face(Face::Back).width(), 2);
+        assert_eq!(
+            map.color_at(Vec3::new(0., 0., -1.)),
+            Color::new(0.6, 0.6, 0.6)
+        );
+        assert_eq!(
+            map.color_at(Vec3::new(0., 1., 0.)),
+            Color::new(0.3, 0.3, 0.3)
+        );
+        assert_eq!(
+            map.color_at(Vec3::new(-1., 0., 0.)),
+            Color::new(0.2, 0.2, 0.2)
+        );
+        assert!(CubeMap::from_cross(&Canvas::new(8, 5)).is_err());
+    }
+
+    #[test]
+    fn faces_are_seen_from_inside_the_cube() {
+        let mut front = Canvas::new(2, 2);
+        front.write_pixel(1, 0, Color::white());
+        let mut faces = [0; 6].map(|_| Canvas::new(2, 2));
+        faces[Face::Front as usize] = front;
+        let map = CubeMap::new(faces);
+
+        // Looking forward, the top right pixel is up and to the right.
+        assert_eq!(map.color_at(Vec3::new(0.5, 0.5, 1.)), Color::white());
+        assert_eq!(map.color_at(Vec3::new(-0.5, 0.5, 1.)), Color::black());
+    }
+
+    #[test]
+    fn cube_maps_from_a_function_of_the_direction() {
+        let color = |direction: Vec3| {
+            Color::new(
+                (direction.x + 1.) / 2.,
+                (direction.y + 1.) / 2.,
+                (direction.z + 1.) / 2.,
+            )
+        };
+        let map = CubeMap::from_fn(16, color);
+
+        for direction in [
+            Vec3::new(1., 0.1, 0.2),
+            Vec3::new(-1., -0.4, 0.3),
+            Vec3::new(-0.3, 1., 0.1),
+            Vec3::new(0.6, -1., -0.2),
+            Vec3::new(0.2, -0.1, 1.),
+            Vec3::new(-0.1, 0.2, -1.),
+        ] {
+            let (expected, actual) = (color(direction.normalize()), map.color_at(direction));
+            let difference = expected - actual;
+
+            assert!(
+                [difference.red, difference.green, difference.blue]
+                    .iter()
+                    .all(|channel| channel.abs() < 0.05),
+                "{:?}",
+                direction
+            );
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{color::Color, cube_map::CubeMap, math::vec3::Vec3};
+
+/// What rays that miss every object see, and what reflections of the sky show.
+#[derive(Clone)]
+pub enum Environment {
+    /// A sky blending from `horizon` up to `zenith` straight overhead, and from `horizon`
+    /// down to `ground` straight below, smooth however close it's looked at.
+    Gradient {
+        zenith: Color,
+        horizon: Color,
+        ground: Color,
+    },
+    /// Images all around, see `CubeMap`.
+    CubeMap(Box<CubeMap>),
+}
+
+impl Environment {
+    /// A gradient sky with the same color all the way down from the horizon.
+    pub fn sky(zenith: Color, horizon: Color) -> Self {
+        Self::Gradient {
+            zenith,
+            horizon,
+            ground: horizon,
+        }
+    }
+
+    /// The color seen looking towards `direction`.
+    pub fn color_at(&self, direction: Vec3) -> Color {
+        match self {
+            Self::Gradient {
+                zenith,
+                horizon,
+                ground,
+            } => {
+                let height = direction.normalize().y;
+                let (end, t) = if height >= 0. {
+                    (*zenith, height)
+                } else {
+                    (*ground, -height)
+                };
+
+                *horizon + (end - *horizon) * t
+            }
+            Self::CubeMap(cube_map) => cube_map.color_at(direction),
+        }
+    }
+}
+
+impl From<CubeMap> for Environment {
+    fn from(cube_map: CubeMap) -> Self {
+        Self::CubeMap(Box::new(cube_map))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gradient_skies_blend_from_the_horizon() {
+        let sky = Environment::Gradient {
+            zenith: Color::new(0., 0., 1.),
+            horizon: Color::white(),
+            ground: Color::black(),
+        };
+
+        assert_eq!(sky.color_at(Vec3::new(0., 1., 0.)), Color::new(0., 0., 1.));
+        assert_eq!(sky.color_at(Vec3::new(1., 0., 0.)), Color::white());
+        assert_eq!(sky.color_at(Vec3::new(0., -1., 0.)), Color::black());
+        assert_eq!(
+            sky.color_at(Vec3::new(0., 1., 1.)),
+            Color::new(0.29289, 0.29289, 1.)
+        );
+    }
+}
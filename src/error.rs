@@ -0,0 +1,53 @@
+use std::{fmt, io};
+
+/// Everything that can go wrong loading scenes, meshes and images, or setting them up.
+#[derive(Debug)]
+pub enum RayTracerError {
+    /// Reading or writing a file failed.
+    Io(io::Error),
+    /// A file isn't in the format it should be, with what's wrong with it.
+    Parse(String),
+    /// A transform that can't be inverted, so rays can't be taken into the space of the object.
+    DegenerateTransform,
+    /// Render settings that don't make sense, with what's wrong with them.
+    InvalidSettings(String),
+    /// Shape parameters that don't make sense, with what's wrong with them.
+    InvalidShape(String),
+    /// A render stopped with `CancelToken::cancel` before it was done.
+    Cancelled,
+    /// Something a file format has no way of describing, with what it is.
+    Unsupported(String),
+}
+
+pub type Result<T> = std::result::Result<T, RayTracerError>;
+
+impl fmt::Display for RayTracerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RayTracerError::Io(error) => write!(f, "{}", error),
+            RayTracerError::Parse(message) => write!(f, "{}", message),
+            RayTracerError::DegenerateTransform => write!(f, "transforms must be invertible"),
+            RayTracerError::InvalidSettings(message) => {
+                write!(f, "invalid render settings: {}", message)
+            }
+            RayTracerError::InvalidShape(message) => write!(f, "invalid shape: {}", message),
+            RayTracerError::Cancelled => write!(f, "the render was cancelled"),
+            RayTracerError::Unsupported(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for RayTracerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RayTracerError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RayTracerError {
+    fn from(error: io::Error) -> Self {
+        RayTracerError::Io(error)
+    }
+}
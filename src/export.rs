@@ -0,0 +1,237 @@
+//! Writing the geometry of whole worlds to files other tools can open, turning every object
+//! into triangles with `Object::tessellate`.
+
+use std::path::Path;
+
+use crate::{
+    error::Result,
+    material::Material,
+    math::matrix4::Matrix4,
+    mesh::{self, Mesh},
+    shape::{Object, Shape, ShapeOrGroup},
+    world::World,
+};
+
+/// An OBJ file of the world and the MTL file of its materials, which the OBJ file loads
+/// from `mtl_name`. Every object is a group named after its index in `World::objects`,
+/// `object_2`, and children of groups after their index in it, `object_2_0`.
+pub fn world_to_obj(world: &World, mtl_name: &str, resolution: usize) -> (String, String) {
+    let mut exporter = ObjExporter {
+        obj: format!("mtllib {}\n", mtl_name),
+        materials: vec![],
+        vertices: 0,
+        resolution,
+    };
+    for (index, object) in world.objects.iter().enumerate() {
+        exporter.object(object, Matrix4::identity(), &format!("object_{}", index));
+    }
+
+    let mtl = exporter
+        .materials
+        .iter()
+        .enumerate()
+        .map(|(index, properties)| format!("newmtl material_{}\n{}", index, properties))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    (exporter.obj, mtl)
+}
+
+/// Writes `world_to_obj` to `path`, with the materials next to it in a file of the same
+/// name and an `.mtl` extension.
+pub fn export_obj(world: &World, path: &str, resolution: usize) -> Result<()> {
+    let mtl_path = Path::new(path).with_extension("mtl");
+    let mtl_name = mtl_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let (obj, mtl) = world_to_obj(world, &mtl_name, resolution);
+
+    std::fs::write(path, obj)?;
+    std::fs::write(mtl_path, mtl)?;
+
+    Ok(())
+}
+
+/// An ASCII STL file of the whole world as a single solid, STL having no groups or materials.
+pub fn world_to_stl(world: &World, name: &str, resolution: usize) -> String {
+    let mut mesh = Mesh::default();
+    for object in &world.objects {
+        mesh.extend(object.tessellate(resolution));
+    }
+
+    mesh.to_stl(name)
+}
+
+pub fn export_stl(world: &World, path: &str, resolution: usize) -> Result<()> {
+    let name = Path::new(path)
+        .file_stem()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    Ok(std::fs::write(
+        path,
+        world_to_stl(world, &name, resolution),
+    )?)
+}
+
+struct ObjExporter {
+    obj: String,
+    /// The MTL properties of every material used so far, written as `material_{index}`.
+    /// Materials only differing in what MTL files can't tell share one.
+    materials: Vec<String>,
+    /// How many vertices were written so far, since faces count them across the whole file.
+    vertices: usize,
+    resolution: usize,
+}
+
+impl ObjExporter {
+    /// Writes the object, `transform` taking it from the space of its parent to world space.
+    fn object(&mut self, object: &Object, transform: Matrix4, name: &str) {
+        let to_world = transform * object.transform();
+
+        match object.shape() {
+            ShapeOrGroup::Group(children) => {
+                for (index, child) in children.iter().enumerate() {
+                    self.object(child, to_world, &format!("{}_{}", name, index));
+                }
+            }
+            ShapeOrGroup::Shape {
+                shape: Shape::Lod(lod),
+                ..
+            } => self.object(&lod.levels[0].1, to_world, name),
+            ShapeOrGroup::Shape {
+                shape: Shape::Csg(csg),
+                ..
+            } => {
+                let (left, right) = mesh::csg_parts(csg, self.resolution.max(3));
+                let (left_material, right_material) = csg.surface_materials();
+
+                self.faces(name, left.transformed(to_world), left_material);
+                self.faces(name, right.transformed(to_world), right_material);
+            }
+            ShapeOrGroup::Shape { material, .. } => {
+                let mesh = object.tessellate(self.resolution).transformed(transform);
+
+                self.faces(name, mesh, *material);
+            }
+        }
+    }
+
+    fn faces(&mut self, name: &str, mesh: Mesh, material: Material) {
+        if mesh.is_empty() {
+            return;
+        }
+        let properties = mtl_properties(&material);
+        let index = self
+            .materials
+            .iter()
+            .position(|known| *known == properties)
+            .unwrap_or_else(|| {
+                self.materials.push(properties);
+                self.materials.len() - 1
+            });
+        let (lines, vertices) = mesh.obj_lines(self.vertices + 1);
+
+        self.obj += &format!("g {}\nusemtl material_{}\n", name, index);
+        self.obj += &lines;
+        self.vertices += vertices;
+    }
+}
+
+/// The material in MTL terms, patterns standing in with their plain color.
+fn mtl_properties(material: &Material) -> String {
+    let color = material.color;
+    let scaled = |factor: f64| {
+        format!(
+            "{} {} {}",
+            color.red * factor,
+            color.green * factor,
+            color.blue * factor
+        )
+    };
+
+    format!(
+        "Ka {}\nKd {}\nKs {s} {s} {s}\nNs {}\nd {}\nNi {}\n",
+        scaled(material.ambient),
+        scaled(material.diffuse),
+        material.shininess,
+        1. - material.transparency,
+        material.refractive_index,
+        s = material.specular,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, obj::WavefrontObj};
+
+    fn world() -> World {
+        let mut world = World::new();
+        let mut red = Material::new();
+        red.color = Color::new(1., 0., 0.);
+        let mut cube = Object::cube();
+        cube.set_material(red);
+        let mut group = Object::group(vec![Object::sphere(), cube]);
+        group.set_transform(Matrix4::translation(0., 3., 0.));
+        world.add_object(group);
+        world.add_object(Object::sphere());
+        world.add_object(Object::plane());
+
+        world
+    }
+
+    #[test]
+    fn exporting_a_world_to_obj_keeps_its_groups_and_materials() {
+        let (obj, mtl) = world_to_obj(&world(), "scene.mtl", 8);
+
+        assert!(obj.starts_with("mtllib scene.mtl\ng object_0_0\nusemtl material_0\n"));
+        assert!(obj.contains("g object_0_1\nusemtl material_1\n"));
+        // Both spheres share the default material, the plane is left out.
+        assert!(obj.contains("g object_1\nusemtl material_0\n"));
+        assert!(!obj.contains("object_2"));
+        assert_eq!(mtl.matches("newmtl").count(), 2);
+        assert!(mtl.contains("newmtl material_1\nKa 0.1 0 0\nKd 0.9 0 0\nKs 0.9 0.9 0.9\n"));
+
+        let read = WavefrontObj::from_file_contents(&obj).unwrap();
+        let triangles = |name: &str| read.groups[name].len();
+        assert_eq!(
+            triangles("object_0_0"),
+            Object::sphere().tessellate(8).len()
+        );
+        assert_eq!(triangles("object_0_1"), Object::cube().tessellate(8).len());
+        assert_eq!(triangles("object_1"), Object::sphere().tessellate(8).len());
+        // Groups are moved into place.
+        assert!(read.groups["object_0_1"]
+            .iter()
+            .all(|triangle| triangle.p1.y >= 2.));
+    }
+
+    #[test]
+    fn exporting_csg_gives_each_operand_its_material() {
+        let mut world = World::new();
+        let mut glass = Material::new();
+        glass.transparency = 0.5;
+        let mut hole = Object::sphere();
+        hole.set_material(glass);
+        hole.set_transform(Matrix4::translation(1., 1., 1.));
+        world.add_object(Object::difference(Object::cube(), hole));
+
+        let (obj, mtl) = world_to_obj(&world, "scene.mtl", 8);
+
+        assert!(obj.contains("g object_0\nusemtl material_0\n"));
+        assert!(obj.contains("g object_0\nusemtl material_1\n"));
+        assert!(mtl.contains("d 0.5\n"));
+    }
+
+    #[test]
+    fn exporting_a_world_to_stl() {
+        let stl = world_to_stl(&world(), "scene", 8);
+        let triangles =
+            2 * Object::sphere().tessellate(8).len() + Object::cube().tessellate(8).len();
+
+        assert!(stl.starts_with("solid scene\n"));
+        assert_eq!(stl.matches("facet normal").count(), triangles);
+    }
+}
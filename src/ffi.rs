@@ -0,0 +1,534 @@
+//! C bindings to embed the renderer, declared in `include/ray_tracer.h`.
+//!
+//! Worlds are opaque pointers owned by the caller, created with `rt_world_new`
+//! and released with `rt_world_free`. Objects are referred to by the index
+//! returned when adding them. Functions that can fail return 0 on success
+//! and a negative `RT_ERROR_*` code otherwise, rather than unwinding into the caller.
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    slice,
+};
+
+use crate::{
+    camera::Camera,
+    color::Color,
+    light::Light,
+    material::Material,
+    math::{matrix4::Matrix4, point3::Point3, transformations::view_transform, tuple::Tuple},
+    shape::Object,
+    world::World,
+};
+
+pub const RT_OK: i32 = 0;
+pub const RT_ERROR_NULL_POINTER: i32 = -1;
+pub const RT_ERROR_NO_SUCH_OBJECT: i32 = -2;
+pub const RT_ERROR_BUFFER_TOO_SMALL: i32 = -3;
+pub const RT_ERROR_NOT_INVERTIBLE: i32 = -4;
+pub const RT_ERROR_INVALID_SIZE: i32 = -5;
+pub const RT_ERROR_RENDER_FAILED: i32 = -6;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RtMaterial {
+    pub color: [f64; 3],
+    pub ambient: f64,
+    pub diffuse: f64,
+    pub specular: f64,
+    pub shininess: f64,
+    pub reflective: f64,
+    pub transparency: f64,
+    pub refractive_index: f64,
+}
+
+impl From<RtMaterial> for Material {
+    fn from(material: RtMaterial) -> Self {
+        let [red, green, blue] = material.color;
+        let mut result = Material::new();
+        result.color = Color::new(red, green, blue);
+        result.ambient = material.ambient;
+        result.diffuse = material.diffuse;
+        result.specular = material.specular;
+        result.shininess = material.shininess;
+        result.reflective = material.reflective;
+        result.transparency = material.transparency;
+        result.refractive_index = material.refractive_index;
+
+        result
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RtCamera {
+    pub width: u32,
+    pub height: u32,
+    pub field_of_view: f64,
+    pub from: [f64; 3],
+    pub to: [f64; 3],
+    pub up: [f64; 3],
+}
+
+#[no_mangle]
+pub extern "C" fn rt_world_new() -> *mut World {
+    Box::into_raw(Box::new(World::new()))
+}
+
+/// # Safety
+///
+/// `world` must come from `rt_world_new` and not have been freed already, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_world_free(world: *mut World) {
+    if !world.is_null() {
+        drop(Box::from_raw(world));
+    }
+}
+
+/// # Safety
+///
+/// `world` must be a live world or null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_world_add_light(
+    world: *mut World,
+    position: *const [f64; 3],
+    intensity: *const [f64; 3],
+) -> i32 {
+    let (Some(world), Some(position), Some(intensity)) =
+        (world.as_mut(), position.as_ref(), intensity.as_ref())
+    else {
+        return RT_ERROR_NULL_POINTER;
+    };
+
+    let [x, y, z] = *position;
+    let [red, green, blue] = *intensity;
+    world.add_light(Light::point_light(
+        Point3::new(x, y, z),
+        Color::new(red, green, blue),
+    ));
+
+    RT_OK
+}
+
+/// Adds a unit sphere, returning its index or a negative error code.
+///
+/// # Safety
+///
+/// `world` must be a live world or null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_world_add_sphere(world: *mut World) -> i64 {
+    add_object(world, Object::sphere())
+}
+
+/// Adds an xz plane, returning its index or a negative error code.
+///
+/// # Safety
+///
+/// `world` must be a live world or null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_world_add_plane(world: *mut World) -> i64 {
+    add_object(world, Object::plane())
+}
+
+/// Adds a cube from -1 to 1, returning its index or a negative error code.
+///
+/// # Safety
+///
+/// `world` must be a live world or null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_world_add_cube(world: *mut World) -> i64 {
+    add_object(world, Object::cube())
+}
+
+unsafe fn add_object(world: *mut World, object: Object) -> i64 {
+    match world.as_mut() {
+        Some(world) => world.add_object(object) as i64,
+        None => RT_ERROR_NULL_POINTER as i64,
+    }
+}
+
+/// Sets the transform of an object, given as 16 numbers in row-major order.
+///
+/// # Safety
+///
+/// `world` must be a live world or null, and `matrix` must point to 16 numbers or be null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_object_set_transform(
+    world: *mut World,
+    object: usize,
+    matrix: *const [f64; 16],
+) -> i32 {
+    let (Some(world), Some(matrix)) = (world.as_mut(), matrix.as_ref()) else {
+        return RT_ERROR_NULL_POINTER;
+    };
+    let Some(object) = world.objects.get_mut(object) else {
+        return RT_ERROR_NO_SUCH_OBJECT;
+    };
+
+    let mut rows = [[0.; 4]; 4];
+    for (index, value) in matrix.iter().enumerate() {
+        rows[index / 4][index % 4] = *value;
+    }
+    let transform = Matrix4::from_rows(rows);
+
+    match object.try_set_transform(transform) {
+        Ok(()) => RT_OK,
+        Err(_) => RT_ERROR_NOT_INVERTIBLE,
+    }
+}
+
+/// The material every object starts with.
+#[no_mangle]
+pub extern "C" fn rt_material_default() -> RtMaterial {
+    let material = Material::new();
+    let Color { red, green, blue } = material.color;
+
+    RtMaterial {
+        color: [red, green, blue],
+        ambient: material.ambient,
+        diffuse: material.diffuse,
+        specular: material.specular,
+        shininess: material.shininess,
+        reflective: material.reflective,
+        transparency: material.transparency,
+        refractive_index: material.refractive_index,
+    }
+}
+
+/// # Safety
+///
+/// `world` must be a live world or null, and `material` must be valid or null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_object_set_material(
+    world: *mut World,
+    object: usize,
+    material: *const RtMaterial,
+) -> i32 {
+    let (Some(world), Some(material)) = (world.as_mut(), material.as_ref()) else {
+        return RT_ERROR_NULL_POINTER;
+    };
+    let Some(object) = world.objects.get_mut(object) else {
+        return RT_ERROR_NO_SUCH_OBJECT;
+    };
+
+    object.set_material((*material).into());
+
+    RT_OK
+}
+
+/// Renders the world into `buffer`, which holds `width * height` RGBA pixels row by row.
+/// The camera can be at most `INT32_MAX` pixels across and down, and its `up` can't point
+/// along the view from `from` to `to`.
+///
+/// # Safety
+///
+/// `world` must be a live world or null, `camera` must be valid or null,
+/// and `buffer` must point to `buffer_length` writable bytes or be null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_render(
+    world: *const World,
+    camera: *const RtCamera,
+    buffer: *mut u8,
+    buffer_length: usize,
+) -> i32 {
+    let (Some(world), Some(camera)) = (world.as_ref(), camera.as_ref()) else {
+        return RT_ERROR_NULL_POINTER;
+    };
+    if buffer.is_null() {
+        return RT_ERROR_NULL_POINTER;
+    }
+    let renderer = match renderer(camera) {
+        Ok(renderer) => renderer,
+        Err(error) => return error,
+    };
+    let Some(length) = rgba_length(camera.width as usize, camera.height as usize) else {
+        return RT_ERROR_INVALID_SIZE;
+    };
+    if buffer_length < length {
+        return RT_ERROR_BUFFER_TOO_SMALL;
+    }
+
+    render_into(buffer, || {
+        renderer.render_with_progress(world, |_, _| {}).to_rgba()
+    })
+}
+
+/// Renders only the pixels from `(x0, y0)` up to but not including `(x1, y1)` into `buffer`,
+/// which holds `(x1 - x0) * (y1 - y0)` RGBA pixels row by row, the way they come out in the
+/// whole image. Corners outside the image are moved into it, and the buffer only needs to
+/// hold what is left.
+///
+/// # Safety
+///
+/// The same as for `rt_render`.
+#[no_mangle]
+pub unsafe extern "C" fn rt_render_region(
+    world: *const World,
+    camera: *const RtCamera,
+    x0: u32,
+    y0: u32,
+    x1: u32,
+    y1: u32,
+    buffer: *mut u8,
+    buffer_length: usize,
+) -> i32 {
+    let (Some(world), Some(camera)) = (world.as_ref(), camera.as_ref()) else {
+        return RT_ERROR_NULL_POINTER;
+    };
+    if buffer.is_null() {
+        return RT_ERROR_NULL_POINTER;
+    }
+    let renderer = match renderer(camera) {
+        Ok(renderer) => renderer,
+        Err(error) => return error,
+    };
+    let size = |start: u32, end: u32, size: u32| end.min(size).saturating_sub(start) as usize;
+    let (width, height) = (size(x0, x1, camera.width), size(y0, y1, camera.height));
+    let Some(length) = rgba_length(width, height) else {
+        return RT_ERROR_INVALID_SIZE;
+    };
+    if buffer_length < length {
+        return RT_ERROR_BUFFER_TOO_SMALL;
+    }
+
+    let coordinate = |c: u32| c.min(i32::MAX as u32) as i32;
+    render_into(buffer, || {
+        renderer
+            .render_region(
+                world,
+                coordinate(x0),
+                coordinate(y0),
+                coordinate(x1),
+                coordinate(y1),
+            )
+            .to_rgba()
+    })
+}
+
+/// Copies the pixels `render` comes up with into `buffer`, or reports that it panicked, which
+/// would abort the caller if it unwound out of a C function.
+///
+/// # Safety
+///
+/// `buffer` must have room for what `render` comes up with.
+unsafe fn render_into(buffer: *mut u8, render: impl FnOnce() -> Vec<u8>) -> i32 {
+    match panic::catch_unwind(AssertUnwindSafe(render)) {
+        Ok(rgba) => {
+            slice::from_raw_parts_mut(buffer, rgba.len()).copy_from_slice(&rgba);
+
+            RT_OK
+        }
+        Err(_) => RT_ERROR_RENDER_FAILED,
+    }
+}
+
+/// Bytes taken by `width * height` RGBA pixels, `None` if they don't fit in memory.
+fn rgba_length(width: usize, height: usize) -> Option<usize> {
+    4usize.checked_mul(width)?.checked_mul(height)
+}
+
+/// The camera to render with, or the error code of what makes it unable to render: a size
+/// that doesn't fit the `i32`s cameras take, or a view that can't be inverted.
+fn renderer(camera: &RtCamera) -> Result<Camera, i32> {
+    let [x, y, z] = camera.from;
+    let from = Tuple::point(x, y, z);
+    let [x, y, z] = camera.to;
+    let to = Tuple::point(x, y, z);
+    let [x, y, z] = camera.up;
+    let up = Tuple::vector(x, y, z);
+
+    let (Ok(width), Ok(height)) = (i32::try_from(camera.width), i32::try_from(camera.height))
+    else {
+        return Err(RT_ERROR_INVALID_SIZE);
+    };
+    // Without a direction to look in or an up, the view can't even be worked out.
+    if (to - from).magnitude() == 0. || up.magnitude() == 0. {
+        return Err(RT_ERROR_NOT_INVERTIBLE);
+    }
+    let mut renderer = Camera::new(width, height, camera.field_of_view);
+    renderer.transform = view_transform(from, to, up);
+    if renderer.transform.inverse().is_none() {
+        return Err(RT_ERROR_NOT_INVERTIBLE);
+    }
+
+    Ok(renderer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ptr;
+
+    fn camera(width: u32, height: u32) -> RtCamera {
+        RtCamera {
+            width,
+            height,
+            field_of_view: std::f64::consts::PI / 3.,
+            from: [0., 0., -5.],
+            to: [0., 0., 0.],
+            up: [0., 1., 0.],
+        }
+    }
+
+    #[test]
+    fn rendering_a_red_sphere_through_the_c_api() {
+        unsafe {
+            let world = rt_world_new();
+            assert_eq!(
+                rt_world_add_light(world, &[-10., 10., -10.], &[1., 1., 1.]),
+                RT_OK
+            );
+            let sphere = rt_world_add_sphere(world);
+            assert_eq!(sphere, 0);
+
+            let mut material = rt_material_default();
+            material.color = [1., 0., 0.];
+            assert_eq!(
+                rt_object_set_material(world, sphere as usize, &material),
+                RT_OK
+            );
+
+            let mut buffer = vec![0; 4 * 5 * 5];
+            let result = rt_render(world, &camera(5, 5), buffer.as_mut_ptr(), buffer.len());
+            rt_world_free(world);
+
+            assert_eq!(result, RT_OK);
+            let center = &buffer[4 * (2 * 5 + 2)..4 * (2 * 5 + 3)];
+            assert!(center[0] > 0 && center[1] == 0 && center[2] == 0);
+            assert_eq!(center[3], 255);
+            // The corners miss the sphere.
+            assert_eq!(&buffer[..4], &[0, 0, 0, 255]);
+        }
+    }
+
+    #[test]
+    fn rendering_a_region_through_the_c_api() {
+        unsafe {
+            let world = rt_world_new();
+            rt_world_add_light(world, &[-10., 10., -10.], &[1., 1., 1.]);
+            rt_world_add_sphere(world);
+            let mut whole = vec![0; 4 * 5 * 5];
+            rt_render(world, &camera(5, 5), whole.as_mut_ptr(), whole.len());
+
+            let mut region = vec![0; 4 * 2 * 2];
+            let too_small =
+                rt_render_region(world, &camera(5, 5), 1, 1, 4, 3, region.as_mut_ptr(), 8);
+            let result = rt_render_region(
+                world,
+                &camera(5, 5),
+                3,
+                2,
+                9,
+                4,
+                region.as_mut_ptr(),
+                region.len(),
+            );
+            rt_world_free(world);
+
+            assert_eq!(too_small, RT_ERROR_BUFFER_TOO_SMALL);
+            assert_eq!(result, RT_OK);
+            // Up to the right edge of the image.
+            assert_eq!(&region[..8], &whole[4 * (2 * 5 + 3)..4 * (2 * 5 + 5)]);
+            assert_eq!(&region[8..], &whole[4 * (3 * 5 + 3)..4 * (3 * 5 + 5)]);
+        }
+    }
+
+    #[test]
+    fn invalid_arguments_are_reported() {
+        unsafe {
+            let world = rt_world_new();
+            let material = rt_material_default();
+            let mut buffer = vec![0; 10];
+
+            assert_eq!(rt_world_add_sphere(ptr::null_mut()), -1);
+            assert_eq!(
+                rt_object_set_material(world, 0, &material),
+                RT_ERROR_NO_SUCH_OBJECT
+            );
+            assert_eq!(
+                rt_object_set_transform(world, 0, ptr::null()),
+                RT_ERROR_NULL_POINTER
+            );
+            assert_eq!(
+                rt_render(world, &camera(2, 2), buffer.as_mut_ptr(), buffer.len()),
+                RT_ERROR_BUFFER_TOO_SMALL
+            );
+
+            for (width, height) in [(u32::MAX, 1), (1, i32::MAX as u32 + 1)] {
+                assert_eq!(
+                    rt_render(
+                        world,
+                        &camera(width, height),
+                        buffer.as_mut_ptr(),
+                        buffer.len()
+                    ),
+                    RT_ERROR_INVALID_SIZE
+                );
+                assert_eq!(
+                    rt_render_region(
+                        world,
+                        &camera(width, height),
+                        0,
+                        0,
+                        1,
+                        1,
+                        buffer.as_mut_ptr(),
+                        buffer.len()
+                    ),
+                    RT_ERROR_INVALID_SIZE
+                );
+            }
+
+            let mut looking_up = camera(2, 2);
+            looking_up.up = [0., 0., 1.];
+            let mut looking_nowhere = camera(2, 2);
+            looking_nowhere.to = looking_nowhere.from;
+            let mut without_up = camera(2, 2);
+            without_up.up = [0.; 3];
+            for camera in [looking_up, looking_nowhere, without_up] {
+                assert_eq!(
+                    rt_render(world, &camera, buffer.as_mut_ptr(), buffer.len()),
+                    RT_ERROR_NOT_INVERTIBLE
+                );
+                assert_eq!(
+                    rt_render_region(world, &camera, 0, 0, 1, 1, buffer.as_mut_ptr(), 4),
+                    RT_ERROR_NOT_INVERTIBLE
+                );
+            }
+
+            let cube = rt_world_add_cube(world) as usize;
+            assert_eq!(
+                rt_object_set_transform(world, cube, &[0.; 16]),
+                RT_ERROR_NOT_INVERTIBLE
+            );
+
+            rt_world_free(world);
+        }
+    }
+
+    #[test]
+    fn the_header_declares_every_exported_function() {
+        let header = include_str!("../include/ray_tracer.h");
+        let source = include_str!("ffi.rs");
+
+        let exported = source
+            .lines()
+            .filter_map(|line| line.split("extern \"C\" fn ").nth(1))
+            .filter_map(|rest| rest.split('(').next());
+
+        for name in exported {
+            assert!(header.contains(&format!("{}(", name)), "{}", name);
+        }
+        for name in ["RtMaterial", "RtCamera"] {
+            assert!(header.contains(name), "{}", name);
+        }
+
+        let constants = source
+            .lines()
+            .filter_map(|line| line.strip_prefix("pub const "))
+            .filter_map(|rest| rest.split_once(": i32 = "));
+        for (name, value) in constants {
+            let define = format!("#define {} {}", name, value.trim_end_matches(';'));
+            assert!(header.contains(&define), "{}", define);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{color::Color, sampler::Rng};
+
+/// How far fog is marched through at most, where it lets through a thousandth of the light.
+const OPAQUE_TRANSMITTANCE: f64 = 1e-3;
+
+/// Fog of the same density all through the world, dimming what is seen through it and
+/// glowing where light falls on it, which shows the shafts of light objects cast shadows in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fog {
+    /// How much of the light the fog catches per unit of distance: after `1 / density`
+    /// units, a little over a third of it is left.
+    pub density: f64,
+    /// What the fog turns the light it scatters towards the eye into.
+    pub color: Color,
+    /// How many points along each ray look for the light falling on the fog.
+    /// More smooth out light shafts, at the cost of a shadow ray per light for each.
+    pub steps: usize,
+}
+
+impl Fog {
+    pub fn new(density: f64, color: Color) -> Self {
+        Self {
+            density,
+            color,
+            steps: 16,
+        }
+    }
+
+    /// How much of the light coming from `distance` away makes it through.
+    pub fn transmittance(&self, distance: f64) -> f64 {
+        (-self.density * distance).exp()
+    }
+
+    /// The light the fog scatters towards the eye from up to `distance` away, `light_at`
+    /// telling how much light falls on the fog at any distance along the way.
+    ///
+    /// The light is looked up at a random point of each of `steps` stretches, weighted by
+    /// how much the fog in it scatters and how much of that makes it back, past which the
+    /// fog lets nothing through anyway.
+    pub(crate) fn in_scattered(
+        &self,
+        distance: f64,
+        rng: &mut Rng,
+        mut light_at: impl FnMut(f64) -> Color,
+    ) -> Color {
+        if self.density <= 0. || distance <= 0. {
+            return Color::black();
+        }
+        let steps = self.steps.max(1);
+        let reach = -OPAQUE_TRANSMITTANCE.ln() / self.density;
+        let step = distance.min(reach) / steps as f64;
+
+        let mut light = Color::black();
+        for i in 0..steps {
+            let start = i as f64 * step;
+            // The last stretch goes on for however much fog is left, the little light
+            // from past `reach` being lit like the end of the march.
+            let end = if i + 1 == steps {
+                distance
+            } else {
+                start + step
+            };
+            let scattered = self.transmittance(start) - self.transmittance(end);
+
+            light = light + light_at(start + rng.next_f64() * step) * scattered;
+        }
+
+        light * self.color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evenly_lit_fog_glows_as_much_as_it_hides() {
+        let fog = Fog::new(0.5, Color::new(1., 0.5, 0.25));
+        let mut rng = Rng::new(0);
+
+        let glow = fog.in_scattered(2., &mut rng, |_| Color::white());
+
+        assert_eq!(fog.transmittance(2.), (-1_f64).exp());
+        assert_eq!(glow, Color::new(1., 0.5, 0.25) * (1. - (-1_f64).exp()));
+        assert_eq!(
+            fog.in_scattered(f64::INFINITY, &mut rng, |_| Color::white()),
+            Color::new(1., 0.5, 0.25)
+        );
+    }
+
+    #[test]
+    fn only_lit_fog_glows() {
+        let fog = Fog::new(1., Color::white());
+        let mut rng = Rng::new(0);
+
+        // Light only past 1 unit away, where stretches start too.
+        let glow = fog.in_scattered(2., &mut rng, |distance| {
+            if distance > 1. {
+                Color::white()
+            } else {
+                Color::black()
+            }
+        });
+
+        assert_eq!(glow, Color::white() * ((-1_f64).exp() - (-2_f64).exp()));
+        assert_eq!(
+            Fog::new(0., Color::white()).in_scattered(1., &mut rng, |_| Color::white()),
+            Color::black()
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! Randomized scenes checking invariants every shape should uphold,
+//! to catch the edge cases that the book's handpicked examples miss.
+//!
+//! Every case is generated from its own seed, so failures report the seed to reproduce them.
+
+use crate::{
+    math::{matrix4::Matrix4, point3::Point3, tuple::Tuple, vec3::Vec3},
+    ray::Ray,
+    sampler::Rng,
+    shape::{
+        cone::Cone,
+        cube::Cube,
+        curve::Curve,
+        cylinder::Cylinder,
+        plane::{Disc, Quad},
+        sphere::Sphere,
+        triangle::Triangle,
+        Object, Shape,
+    },
+    world::World,
+};
+
+const CASES: u64 = 500;
+
+impl Rng {
+    fn bool(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+
+    fn point(&mut self, extent: f64) -> Tuple {
+        Tuple::point(
+            self.range(-extent, extent),
+            self.range(-extent, extent),
+            self.range(-extent, extent),
+        )
+    }
+
+    fn direction(&mut self) -> Vec3 {
+        loop {
+            let Tuple { x, y, z, .. } = self.point(1.);
+            let v = Vec3::new(x, y, z);
+            let length = v.magnitude();
+
+            if length > 0.1 && length <= 1. {
+                return v.normalize();
+            }
+        }
+    }
+
+    fn transform(&mut self) -> Matrix4 {
+        let Tuple { x, y, z, .. } = self.point(3.);
+
+        Matrix4::translation(x, y, z)
+            * Matrix4::rotation_x(self.range(0., 6.3))
+            * Matrix4::rotation_y(self.range(0., 6.3))
+            * Matrix4::rotation_z(self.range(0., 6.3))
+            * Matrix4::scaling(
+                self.range(0.3, 2.),
+                self.range(0.3, 2.),
+                self.range(0.3, 2.),
+            )
+    }
+
+    /// A bounded shape, so that it has a finite bounding box.
+    fn shape(&mut self) -> Shape {
+        let minimum = self.range(-2., 1.);
+        let maximum = minimum + self.range(0.1, 2.);
+
+        match self.next_u64() % 8 {
+            0 => Shape::primitive(Sphere {}),
+            1 => Shape::primitive(Cube),
+            2 => Shape::primitive(Cylinder::truncated(minimum, maximum, self.bool()).unwrap()),
+            3 => Shape::primitive(Cone::truncated(minimum, maximum, self.bool()).unwrap()),
+            4 => Shape::primitive(Triangle::new(
+                self.point(1.),
+                self.point(1.),
+                self.point(1.),
+            )),
+            5 => Shape::primitive(Disc {
+                radius: self.range(0.1, 2.),
+            }),
+            6 => Shape::primitive(Quad {
+                width: self.range(0.1, 2.),
+                depth: self.range(0.1, 2.),
+            }),
+            _ => Shape::primitive(Curve::bezier(
+                self.point(1.),
+                self.point(1.),
+                self.point(1.),
+                self.point(1.),
+                self.range(0.05, 0.5),
+            )),
+        }
+    }
+
+    /// A ray from afar, aimed somewhere near the object.
+    fn ray_towards(&mut self, object: &Object) -> Ray {
+        let Tuple { x, y, z, .. } = object.transform() * self.point(1.5);
+        let target = Point3::new(x, y, z);
+        let origin = target + self.direction() * self.range(5., 20.);
+
+        Ray::new(origin, (target - origin).normalize())
+    }
+}
+
+fn random_world(rng: &mut Rng) -> World {
+    let mut object = Object::new(rng.shape());
+    object.set_transform(rng.transform());
+
+    let mut world = World::new();
+    world.add_object(object);
+
+    world
+}
+
+#[test]
+fn intersections_are_finite_and_ordered() {
+    for seed in 0..CASES {
+        let mut rng = Rng::new(seed);
+        let world = random_world(&mut rng);
+        let ray = rng.ray_towards(&world.objects[0]);
+
+        let xs = world.intersect(ray);
+
+        assert!(xs.iter().all(|i| i.t.is_finite()), "seed {}", seed);
+        assert!(xs.windows(2).all(|w| w[0].t <= w[1].t), "seed {}", seed);
+    }
+}
+
+#[test]
+fn normals_are_unit_length_and_face_the_eye() {
+    for seed in 0..CASES {
+        let mut rng = Rng::new(seed);
+        let world = random_world(&mut rng);
+        let ray = rng.ray_towards(&world.objects[0]);
+
+        let xs = world.intersect(ray);
+
+        for i in &xs {
+            let comps = i.prepare_computations(ray, &xs);
+            let normal = comps.normal_vector;
+
+            assert!((normal.magnitude() - 1.).abs() < 1e-6, "seed {}", seed);
+            assert!(normal.dot(comps.eye_vector) >= 0., "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn bounding_boxes_contain_the_surface() {
+    for seed in 0..CASES {
+        let mut rng = Rng::new(seed);
+        let world = random_world(&mut rng);
+        let object = &world.objects[0];
+        let bounds = object.bounding_box().padded(1e-6);
+
+        // Every point a ray hits is on the surface.
+        for _ in 0..10 {
+            let ray = rng.ray_towards(object);
+
+            for i in object.intersect(ray) {
+                assert!(
+                    bounds.contains_point(ray.position(i.t).into()),
+                    "seed {}",
+                    seed
+                );
+            }
+        }
+    }
+}
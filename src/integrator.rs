@@ -0,0 +1,116 @@
+//! How much of the light bouncing around a world gets rendered, see `World::integrator`.
+
+/// Ways to gather light at the points camera rays hit, from the cheapest to the most faithful,
+/// but for `OcclusionOnly` and the experimental `Bidirectional`. Every one of them renders everything the one before does, so scenes can be set up with
+/// `Direct` and rendered for good with more, without looking different in anything but the
+/// light in the shade.
+///
+/// Light bouncing off surfaces is gathered off their diffuse color only, reflections and
+/// refractions are still only followed from camera rays.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Integrator {
+    /// Light straight from the lights, with the ambient term of materials standing in for
+    /// everything else. Shade is flat, but it's the fastest.
+    #[default]
+    Direct,
+    /// Like `Direct`, with the ambient term darkened by how much of what's around a point
+    /// within `distance` hides it, for soft contact shadows in corners and under objects.
+    AmbientOcclusion { samples: usize, distance: f64 },
+    /// Like `Direct`, plus the light bounced once off the surfaces around a point:
+    /// colors bleed onto their neighbors, and shadows fill in with light from lit surfaces.
+    OneBounce { samples: usize },
+    /// Light bounced around up to `bounces` times, following a single ray after the first.
+    /// Enclosed spaces brighten up the way real ones do, and it's noisy with few samples.
+    PathTrace { samples: usize, bounces: usize },
+    /// Not lighting at all: every surface is white, darkened only by how much of what's around
+    /// it within `distance` hides it, whatever its material and wherever the lights are.
+    /// The clay render modelers check shapes and composition with, and fast since nothing
+    /// bounces and no shadow rays go to lights.
+    OcclusionOnly { samples: usize, distance: f64 },
+    /// Experimental, for scenes lit mostly through glass and mirrors: paths bouncing up to
+    /// `bounces` times are traced from the points camera rays hit and from the lights, then
+    /// joined up, so the light glass focuses onto what's behind it shows up, as caustics.
+    ///
+    /// Surfaces are lit as if they were perfectly matte, without ambient light or highlights,
+    /// and transparent objects cast shadows as dark as opaque ones, apart from what they focus.
+    /// Lights are as bright at any distance, as with the other integrators.
+    /// Caustics are traced as if the camera were a pinhole, and caustics seen in mirrors or
+    /// through glass are left out.
+    #[cfg(feature = "bidirectional")]
+    Bidirectional { samples: usize, bounces: usize },
+}
+
+/// The names `Integrator::named` knows.
+#[cfg(not(feature = "bidirectional"))]
+pub const INTEGRATORS: [&str; 5] = [
+    "direct",
+    "ambient_occlusion",
+    "one_bounce",
+    "path_trace",
+    "occlusion_only",
+];
+
+/// The names `Integrator::named` knows.
+#[cfg(feature = "bidirectional")]
+pub const INTEGRATORS: [&str; 6] = [
+    "direct",
+    "ambient_occlusion",
+    "one_bounce",
+    "path_trace",
+    "occlusion_only",
+    "bidirectional",
+];
+
+impl Integrator {
+    /// The integrator called `name`, one of `INTEGRATORS`, with its usual settings.
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "direct" => Some(Integrator::Direct),
+            "ambient_occlusion" => Some(Integrator::AmbientOcclusion {
+                samples: 16,
+                distance: 1.,
+            }),
+            "one_bounce" => Some(Integrator::OneBounce { samples: 16 }),
+            "path_trace" => Some(Integrator::PathTrace {
+                samples: 16,
+                bounces: 4,
+            }),
+            "occlusion_only" => Some(Integrator::OcclusionOnly {
+                samples: 16,
+                distance: 1.,
+            }),
+            #[cfg(feature = "bidirectional")]
+            "bidirectional" => Some(Integrator::Bidirectional {
+                samples: 16,
+                bounces: 4,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Integrator::Direct => "direct",
+            Integrator::AmbientOcclusion { .. } => "ambient_occlusion",
+            Integrator::OneBounce { .. } => "one_bounce",
+            Integrator::PathTrace { .. } => "path_trace",
+            Integrator::OcclusionOnly { .. } => "occlusion_only",
+            #[cfg(feature = "bidirectional")]
+            Integrator::Bidirectional { .. } => "bidirectional",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integrators_are_found_by_their_name() {
+        for name in INTEGRATORS {
+            assert_eq!(Integrator::named(name).unwrap().name(), name);
+        }
+        assert_eq!(Integrator::named("photon_mapping"), None);
+        assert_eq!(Integrator::default(), Integrator::Direct);
+    }
+}
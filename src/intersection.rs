@@ -1,10 +1,15 @@
-use crate::math::tuple::Tuple;
-use crate::misc::{approx_equal, EPSILON};
+use crate::math::{point3::Point3, vec3::Vec3};
+use crate::misc::{epsilon_at, nearly_equal};
 use crate::ray::Ray;
 use crate::shape::triangle::UVT;
-use crate::shape::SimpleObject;
-
-pub(crate) enum TorUVT {
+use crate::shape::{primitive::Primitive, SimpleObject};
+use std::collections::HashMap;
+
+/// Where a ray hits a shape: just how far along the ray for most shapes, and also the
+/// coordinates of the hit on the surface for the ones that have them, like triangles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum TorUVT {
     JustT { t: f64 },
     UVT { uvt: UVT },
 }
@@ -17,14 +22,14 @@ pub struct Intersection<'a> {
 }
 
 impl<'a> Intersection<'a> {
-    pub(crate) fn new(t_or_uvt: &TorUVT, object: SimpleObject<'a>) -> Self {
-        match t_or_uvt {
-            &TorUVT::JustT { t } => Self {
+    pub fn new(t_or_uvt: &TorUVT, object: SimpleObject<'a>) -> Self {
+        match *t_or_uvt {
+            TorUVT::JustT { t } => Self {
                 t,
                 uv: None,
                 object,
             },
-            &TorUVT::UVT { uvt } => Self {
+            TorUVT::UVT { uvt } => Self {
                 t: uvt.t,
                 uv: Some((uvt.u, uvt.v)),
                 object,
@@ -32,11 +37,16 @@ impl<'a> Intersection<'a> {
         }
     }
 
+    /// Where the ray hits, for shapes that know their coordinates, like triangles.
+    pub fn uv(&self) -> Option<(f64, f64)> {
+        self.uv
+    }
+
     /// Returns the closest intersection (the one with the smallest non-negative t value.)
     pub fn hit(intersections: &[Self]) -> Option<&Self> {
         intersections
             .iter()
-            .filter(|i| (**i).t >= 0.)
+            .filter(|i| i.t >= 0.)
             .min_by(|i1, i2| i1.t.partial_cmp(&i2.t).unwrap())
     }
 
@@ -44,37 +54,56 @@ impl<'a> Intersection<'a> {
         &self,
         ray: Ray,
         all_intersections: &[Intersection],
-    ) -> ComputedIntersection {
+    ) -> ComputedIntersection<'a> {
         let object = self.object;
         let _t = self.t;
         let point = ray.position(self.t);
         let eye_vector = -ray.direction;
 
         let tentative_normal = self.object.normal_at(*self, point);
+        let tentative_normal = match self.object.material().waves {
+            Some(waves) => waves.perturb(tentative_normal, point, ray.time),
+            None => tentative_normal,
+        };
+        let tentative_normal = match self.object.material().bump {
+            Some(bump) => bump.perturb(self.object, tentative_normal, point, ray.time),
+            None => tentative_normal,
+        };
+        let geometric_normal = self.object.geometric_normal_at(*self, point);
 
-        let (_inside, normal_vector) = if tentative_normal.dot(eye_vector) < 0. {
-            (true, -tentative_normal)
+        // Which side the ray comes from is up to the surface itself, the shading normal
+        // is flipped along with it even if it leans the other way.
+        let (inside, normal_vector, geometric_normal) = if geometric_normal.dot(eye_vector) < 0. {
+            (true, -tentative_normal, -geometric_normal)
         } else {
-            (false, tentative_normal)
+            (false, tentative_normal, geometric_normal)
         };
 
         let reflect_vector = ray.direction.reflect(normal_vector);
-        let over_point = point + normal_vector * EPSILON;
-        let under_point = point - normal_vector * EPSILON;
+        // Where the ray hits is only as precise as the largest coordinates that went into
+        // working it out, so the step off of the surface grows with them.
+        let offset = epsilon_at(
+            ray.origin
+                .largest_coordinate()
+                .max(point.largest_coordinate()),
+        );
+        let over_point = point + geometric_normal * offset;
+        let under_point = point - geometric_normal * offset;
 
         let (n1, n2) = self.compute_refractive_indices(all_intersections);
 
         ComputedIntersection {
             eye_vector,
             normal_vector,
+            geometric_normal,
             reflect_vector,
             over_point,
             under_point,
-            n1: n1,
-            n2: n2,
+            n1,
+            n2,
             object,
-            #[cfg(test)]
-            inside: _inside,
+            time: ray.time,
+            inside,
             #[cfg(test)]
             t: _t,
             #[cfg(test)]
@@ -82,78 +111,159 @@ impl<'a> Intersection<'a> {
         }
     }
 
-    fn compute_refractive_indices<'b>(
-        &'a self,
-        all_intersections: &[Intersection<'a>],
-    ) -> (f64, f64)
-    where
-        'a: 'b,
-    {
-        let mut containers: Vec<SimpleObject<'b>> = vec![];
+    /// Walks the intersections up to this one keeping track of the objects the ray is inside of.
+    fn compute_refractive_indices(&self, all_intersections: &[Intersection]) -> (f64, f64) {
+        let mut containers = Containers::new();
         let mut n1 = 1.0;
-        let mut n2 = 1.0;
 
-        for &i in all_intersections {
-            // Bad phrasing by the author, check this:
-            // https://forum.raytracerchallenge.com/post/103/thread
+        for (entry, &i) in all_intersections.iter().enumerate() {
             let is_hit = i == *self;
 
             if is_hit {
-                if let Some(last) = containers.last() {
-                    n1 = last.material().refractive_index;
-                } else {
-                    n1 = 1.0;
-                }
+                n1 = containers.top_refractive_index();
             }
 
-            let position = containers.iter().position(|o| *o == i.object);
-
-            if let Some(index) = position {
-                containers.remove(index);
-            } else {
-                containers.push(i.object);
-            }
+            containers.cross(
+                object_id(&i.object),
+                entry,
+                i.object.material.refractive_index,
+            );
 
             if is_hit {
-                if let Some(last) = containers.last() {
-                    n2 = last.material().refractive_index;
-                } else {
-                    n2 = 1.0;
-                }
-                break;
+                return (n1, containers.top_refractive_index());
             }
         }
 
-        (n1, n2)
+        (n1, 1.0)
     }
 
-    pub(crate) fn uvt(&self) -> Option<UVT> {
+    /// How far along the ray and where on the surface the hit is, for shapes with coordinates.
+    pub fn uvt(&self) -> Option<UVT> {
         self.uv.map(|(u, v)| UVT { t: self.t, u, v })
     }
 }
 
+/// Shapes are told apart by where they are, and the points of a cloud, which share theirs,
+/// by their transforms.
+type ObjectId = (usize, [u64; 16]);
+
+fn object_id(object: &SimpleObject) -> ObjectId {
+    let mut transform = [0; 16];
+    for (id, value) in transform
+        .iter_mut()
+        .zip(object.transform.matrix().rows().iter().flatten())
+    {
+        *id = value.to_bits();
+    }
+
+    (
+        object.primitive as *const dyn Primitive as *const () as usize,
+        transform,
+    )
+}
+
+/// How many nested objects `Containers` keeps track of without allocating.
+const INLINE_CONTAINERS: usize = 8;
+
+/// The objects a ray is inside of, innermost last. The few nested objects nearly every scene
+/// has are kept inline and searched in place; past that they spill over into a stack, with
+/// the objects still inside of kept in a map, so no step has to search the stack.
+struct Containers {
+    inline: [(ObjectId, f64); INLINE_CONTAINERS],
+    len: usize,
+    spilled: Option<Spilled>,
+}
+
+#[derive(Default)]
+struct Spilled {
+    /// When each object the ray is inside of was entered, telling stale stack entries apart.
+    inside: HashMap<ObjectId, usize>,
+    /// Objects left are only forgotten from `inside`, and dropped once they reach the top.
+    stack: Vec<(ObjectId, usize, f64)>,
+}
+
+impl Containers {
+    fn new() -> Self {
+        Self {
+            inline: [Default::default(); INLINE_CONTAINERS],
+            len: 0,
+            spilled: None,
+        }
+    }
+
+    /// Enters the object, or leaves it if the ray was already inside of it.
+    fn cross(&mut self, id: ObjectId, entry: usize, refractive_index: f64) {
+        if let Some(spilled) = &mut self.spilled {
+            if spilled.inside.remove(&id).is_none() {
+                spilled.inside.insert(id, entry);
+                spilled.stack.push((id, entry, refractive_index));
+            }
+            return;
+        }
+
+        let containers = &mut self.inline[..self.len];
+        if let Some(position) = containers.iter().position(|&(other, _)| other == id) {
+            containers[position..].rotate_left(1);
+            self.len -= 1;
+        } else if self.len < INLINE_CONTAINERS {
+            self.inline[self.len] = (id, refractive_index);
+            self.len += 1;
+        } else {
+            let mut spilled = Spilled::default();
+            // Any later entry is numbered past these, so they can all share this one's.
+            for &(id, refractive_index) in self.inline.iter().chain([&(id, refractive_index)]) {
+                spilled.inside.insert(id, entry);
+                spilled.stack.push((id, entry, refractive_index));
+            }
+            self.len = 0;
+            self.spilled = Some(spilled);
+        }
+    }
+
+    /// The refractive index of the innermost object the ray is still inside of, 1 outside of any.
+    fn top_refractive_index(&mut self) -> f64 {
+        let Some(spilled) = &mut self.spilled else {
+            return self.inline[..self.len].last().map_or(1.0, |&(_, n)| n);
+        };
+
+        while let Some(&(id, entry, refractive_index)) = spilled.stack.last() {
+            if spilled.inside.get(&id) == Some(&entry) {
+                return refractive_index;
+            }
+            spilled.stack.pop();
+        }
+
+        1.0
+    }
+}
+
 impl<'a> PartialEq for Intersection<'a> {
     fn eq(&self, other: &Self) -> bool {
-        approx_equal(self.t, other.t) && self.object == other.object
+        nearly_equal(self.t, other.t) && self.object == other.object
     }
 }
 
 #[derive(Copy, Clone, Debug)]
 pub(crate) struct ComputedIntersection<'a> {
     pub object: SimpleObject<'a>,
-    pub eye_vector: Tuple,
-    pub normal_vector: Tuple,
-    pub reflect_vector: Tuple,
-    pub over_point: Tuple,
-    pub under_point: Tuple,
+    pub eye_vector: Vec3,
+    /// The normal to shade with, which may be smoothed or perturbed, facing the eye.
+    pub normal_vector: Vec3,
+    /// The normal of the surface itself, facing the eye, which `over_point` and
+    /// `under_point` are offset along.
+    pub geometric_normal: Vec3,
+    pub reflect_vector: Vec3,
+    pub over_point: Point3,
+    pub under_point: Point3,
     pub n1: f64,
     pub n2: f64,
+    pub time: f64,
+    /// Whether the ray hits the surface from inside the object.
+    pub inside: bool,
     #[cfg(test)]
     t: f64,
     #[cfg(test)]
-    point: Tuple,
-    #[cfg(test)]
-    inside: bool,
+    point: Point3,
 }
 
 impl<'a> ComputedIntersection<'a> {
@@ -177,13 +287,19 @@ impl<'a> ComputedIntersection<'a> {
         }
         let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
 
-        return r0 + (1. - r0) * (1. - cos).powi(5);
+        r0 + (1. - r0) * (1. - cos).powi(5)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{material::Material, math::matrix4::Matrix4, shape::Object};
+
+    use crate::{
+        material::Material,
+        math::matrix4::Matrix4,
+        misc::{approx_equal, EPSILON},
+        shape::Object,
+    };
 
     use super::*;
 
@@ -255,7 +371,7 @@ mod tests {
 
     #[test]
     fn precomputing_the_state_of_an_intersection() {
-        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
         let object = Object::sphere();
         let shape = SimpleObject::from_object(&object).unwrap();
         let intersection = Intersection::new(&TorUVT::JustT { t: 4. }, shape);
@@ -264,14 +380,14 @@ mod tests {
 
         assert!(approx_equal(comps.t, intersection.t));
         assert_eq!(comps.object, intersection.object);
-        assert_eq!(comps.point, Tuple::point(0., 0., -1.));
-        assert_eq!(comps.eye_vector, Tuple::vector(0., 0., -1.));
-        assert_eq!(comps.normal_vector, Tuple::vector(0., 0., -1.));
+        assert_eq!(comps.point, Point3::new(0., 0., -1.));
+        assert_eq!(comps.eye_vector, Vec3::new(0., 0., -1.));
+        assert_eq!(comps.normal_vector, Vec3::new(0., 0., -1.));
     }
 
     #[test]
     fn the_hit_when_an_intersection_occurs_on_the_outside() {
-        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
         let object = Object::sphere();
         let shape = SimpleObject::from_object(&object).unwrap();
         let i = Intersection::new(&TorUVT::JustT { t: 4. }, shape);
@@ -282,24 +398,45 @@ mod tests {
 
     #[test]
     fn the_hit_when_an_intersection_occurs_on_the_inside() {
-        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., 1.));
         let object = Object::sphere();
         let shape = SimpleObject::from_object(&object).unwrap();
         let i = Intersection::new(&TorUVT::JustT { t: 1. }, shape);
         let comps = i.prepare_computations(r, &[i]);
 
-        assert_eq!(comps.point, Tuple::point(0., 0., 1.));
-        assert_eq!(comps.eye_vector, Tuple::vector(0., 0., -1.));
+        assert_eq!(comps.point, Point3::new(0., 0., 1.));
+        assert_eq!(comps.eye_vector, Vec3::new(0., 0., -1.));
         assert!(comps.inside);
         // Normal would have been (0., 0., 1.), but is inverted!
-        assert_eq!(comps.normal_vector, Tuple::vector(0., 0., -1.));
+        assert_eq!(comps.normal_vector, Vec3::new(0., 0., -1.));
+    }
+
+    #[test]
+    fn hits_far_from_where_rays_start_are_stepped_further_off() {
+        let object = Object::sphere();
+        let eye = Point3::new(10_000., 3_100., -7_000.);
+
+        for i in 0..100 {
+            let target = Point3::new((i % 10) as f64 / 15. - 0.3, (i / 10) as f64 / 15. - 0.3, 0.);
+            let r = Ray::new(eye, (target - eye).normalize());
+            let xs = object.intersect(r);
+            let comps = Intersection::hit(&xs).unwrap().prepare_computations(r, &xs);
+
+            // Towards a light just above the horizon of the surface.
+            let normal = comps.geometric_normal;
+            let along = Vec3::new(1., 0., 0.3);
+            let tangent = (along - normal * along.dot(normal)).normalize();
+            let to_light = (normal * 0.001 + tangent).normalize();
+            let shadow = object.intersect(Ray::new(comps.over_point, to_light));
+            assert!(shadow.iter().all(|i| i.t <= 0.));
+        }
     }
 
     #[test]
     fn the_hit_should_offset_the_point() {
-        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
         let mut object = Object::sphere();
-        object.transform = Matrix4::translation(0., 0., 1.);
+        object.set_transform(Matrix4::translation(0., 0., 1.));
         let shape = SimpleObject::from_object(&object).unwrap();
         let i = Intersection::new(&TorUVT::JustT { t: 5. }, shape);
         let comps = i.prepare_computations(r, &[i]);
@@ -313,39 +450,39 @@ mod tests {
         let object = Object::plane();
         let shape = SimpleObject::from_object(&object).unwrap();
         let r = Ray::new(
-            Tuple::point(0., 1., -1.),
-            Tuple::vector(0., -2_f64.sqrt() / 2_f64, 2_f64.sqrt() / 2_f64),
+            Point3::new(0., 1., -1.),
+            Vec3::new(0., -2_f64.sqrt() / 2_f64, 2_f64.sqrt() / 2_f64),
         );
         let i = Intersection::new(&TorUVT::JustT { t: 2_f64.sqrt() }, shape);
         let comps = i.prepare_computations(r, &[i]);
 
         assert_eq!(
             comps.reflect_vector,
-            Tuple::vector(0., 2_f64.sqrt() / 2_f64, 2_f64.sqrt() / 2_f64)
+            Vec3::new(0., 2_f64.sqrt() / 2_f64, 2_f64.sqrt() / 2_f64)
         );
     }
 
     #[test]
     fn finding_n1_and_n2_at_various_intersections() {
         let mut a = Object::glass_sphere();
-        a.transform = Matrix4::scaling(2., 2., 2.);
+        a.set_transform(Matrix4::scaling(2., 2., 2.));
         let mut material = Material::new();
         material.refractive_index = 1.5;
         a.set_material(material);
 
         let mut b = Object::glass_sphere();
-        b.transform = Matrix4::translation(0., 0., -0.25);
+        b.set_transform(Matrix4::translation(0., 0., -0.25));
         let mut material = Material::new();
         material.refractive_index = 2.0;
         b.set_material(material);
 
         let mut c = Object::glass_sphere();
-        c.transform = Matrix4::translation(0., 0., 0.25);
+        c.set_transform(Matrix4::translation(0., 0., 0.25));
         let mut material = Material::new();
         material.refractive_index = 2.5;
         c.set_material(material);
 
-        let ray = Ray::new(Tuple::point(0., 0., -4.), Tuple::vector(0., 0., 1.));
+        let ray = Ray::new(Point3::new(0., 0., -4.), Vec3::new(0., 0., 1.));
         let intersections_with_expected_indices = [
             (
                 Intersection::new(
@@ -413,11 +550,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn finding_n1_and_n2_through_many_nested_shells() {
+        let shell_count = 12;
+        let refractive_index = |shell: usize| match shell {
+            0 => 1.,
+            _ => 1. + shell as f64 / 10.,
+        };
+        let shells: Vec<Object> = (1..=shell_count)
+            .map(|shell| {
+                let mut sphere = Object::glass_sphere();
+                let scale = shell as f64;
+                sphere.set_transform(Matrix4::scaling(scale, scale, scale));
+                let mut material = Material::new();
+                material.refractive_index = refractive_index(shell);
+                sphere.set_material(material);
+
+                sphere
+            })
+            .collect();
+        let ray = Ray::new(Point3::new(0., 0., -20.), Vec3::new(0., 0., 1.));
+        let mut xs: Vec<Intersection> = shells.iter().flat_map(|s| s.intersect(ray)).collect();
+        xs.sort_by(|i1, i2| i1.t.total_cmp(&i2.t));
+
+        // Going in from the outermost shell, then out from the innermost one.
+        let going_in = (1..=shell_count).rev().map(|shell| (shell + 1, shell));
+        let going_out = (1..=shell_count).map(|shell| (shell, shell + 1));
+        for (i, (from, to)) in xs.iter().zip(going_in.chain(going_out)) {
+            let comps = i.prepare_computations(ray, &xs);
+            let index = |shell| refractive_index(if shell > shell_count { 0 } else { shell });
+
+            assert!(approx_equal(comps.n1, index(from)));
+            assert!(approx_equal(comps.n2, index(to)));
+        }
+    }
+
     #[test]
     fn the_under_point_is_offset_below_the_surface() {
-        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
         let mut object = Object::glass_sphere();
-        object.transform = Matrix4::translation(0., 0., 1.);
+        object.set_transform(Matrix4::translation(0., 0., 1.));
         let shape = SimpleObject::from_object(&object).unwrap();
         let i = Intersection::new(&TorUVT::JustT { t: 5. }, shape);
         let comps = i.prepare_computations(r, &[i]);
@@ -430,8 +602,8 @@ mod tests {
     fn the_schlick_approximation_under_total_internal_reflection() {
         let object = Object::glass_sphere();
         let r = Ray::new(
-            Tuple::point(0., 0., 2_f64.sqrt() / 2.),
-            Tuple::vector(0., 1., 0.),
+            Point3::new(0., 0., 2_f64.sqrt() / 2.),
+            Vec3::new(0., 1., 0.),
         );
         let shape = SimpleObject::from_object(&object).unwrap();
         let xs = [
@@ -457,7 +629,7 @@ mod tests {
     #[test]
     fn the_schlick_approximation_with_a_perpendicular_viewing_angle() {
         let object = Object::glass_sphere();
-        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 1., 0.));
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 1., 0.));
         let shape = SimpleObject::from_object(&object).unwrap();
         let xs = [
             Intersection::new(&TorUVT::JustT { t: -1. }, shape),
@@ -473,7 +645,7 @@ mod tests {
     fn the_schlick_approximation_with_small_angle_and_n2_greater_than_n1() {
         let object = Object::glass_sphere();
         let shape = SimpleObject::from_object(&object).unwrap();
-        let r = Ray::new(Tuple::point(0., 0.99, -2.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 0.99, -2.), Vec3::new(0., 0., 1.));
         let xs = [Intersection::new(&TorUVT::JustT { t: 1.8589 }, shape)];
         let comps = xs[0].prepare_computations(r, &xs);
         let reflectance = comps.schlick();
@@ -1,14 +1,51 @@
+pub mod animation;
+mod arena;
+pub mod bvh;
+mod bytes;
 pub mod camera;
 pub mod canvas;
 pub mod color;
+pub mod color_buffer;
+pub mod cube_map;
+pub mod environment;
+pub mod error;
+pub mod export;
+pub mod ffi;
+pub mod fog;
+#[cfg(test)]
+mod fuzz;
+pub mod integrator;
 pub mod intersection;
 pub mod light;
+pub mod lut;
 pub mod material;
 pub mod math;
+pub mod mesh;
 pub mod misc;
+pub mod noise;
 pub mod pattern;
+mod png;
+pub mod prelude;
+pub mod random_scene;
 pub mod ray;
+pub mod render_settings;
+pub mod sampler;
+pub mod scatter;
+pub mod scene;
+#[cfg(feature = "server")]
+pub mod server;
 pub mod shape;
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+pub mod static_scene;
+pub mod sun;
+pub mod tone;
+pub mod water;
+mod winding;
 pub mod world;
+pub mod yaml;
 // use examples::{chapter_11, chapter_12, chapter_13, chapter_14};
 pub mod obj;
+pub mod ply;
+#[cfg(feature = "python")]
+mod python;
@@ -1,32 +1,254 @@
 use crate::color::Color;
-use crate::math::tuple::Tuple;
+use crate::math::angle::Angle;
+use crate::math::matrix4::Matrix4;
+use crate::math::point3::Point3;
+use crate::math::vec3::Vec3;
+use crate::misc::EPSILON;
+use crate::ray::Ray;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Light {
-    pub position: Tuple,
+    pub position: Point3,
     pub intensity: Color,
+    /// Something to show where the light is, glowing with its intensity.
+    pub fixture: Option<Fixture>,
+    pub kind: LightKind,
+    /// Index in `World::objects` of the object an attached light came with, which doesn't
+    /// shadow its own light, so bulbs and lamp shades can be built around it.
+    pub(crate) attached_to: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightKind {
+    /// All the light comes from `position`.
+    Point,
+    /// The light comes from `position`, only within a cone.
+    Spot(Spot),
+    /// The light comes from the whole sky, `position` is left unused.
+    Dome(Dome),
+}
+
+/// A cone of light, like a flashlight or a stage light.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spot {
+    /// Where the cone points, normalized.
+    pub direction: Vec3,
+    /// Angle between the direction and the edge of the fully lit cone, in radians.
+    pub cone_angle: f64,
+    /// Angle past the edge of the cone over which the light fades out, in radians.
+    pub fade_angle: f64,
+}
+
+/// A hemisphere of light over the world, giving soft light to whatever the sky can see.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dome {
+    pub sky: Sky,
+    /// How many directions towards the sky every point is shaded with.
+    pub samples: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sky {
+    /// The same light from every direction, the intensity of the light.
+    Uniform,
+    /// The light of `World::environment` in each direction, scaled by the intensity.
+    Environment,
+}
+
+/// Visible geometry around a light, which always follows it and never casts shadows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fixture {
+    Sphere {
+        radius: f64,
+    },
+    /// A horizontal square, like a ceiling panel.
+    Quad {
+        size: f64,
+    },
 }
 
 impl Light {
-    pub fn point_light(position: Tuple, intensity: Color) -> Self {
+    pub const fn point_light(position: Point3, intensity: Color) -> Self {
         Self {
             position,
             intensity,
+            fixture: None,
+            kind: LightKind::Point,
+            attached_to: None,
+        }
+    }
+
+    /// A light shining from `position` towards `direction`, fully within `cone_angle` of it,
+    /// then fading out smoothly over `fade_angle` more.
+    pub fn spot_light(
+        position: Point3,
+        direction: Vec3,
+        cone_angle: impl Into<Angle>,
+        fade_angle: impl Into<Angle>,
+        intensity: Color,
+    ) -> Self {
+        Self {
+            kind: LightKind::Spot(Spot {
+                direction: direction.normalize(),
+                cone_angle: cone_angle.into().to_radians(),
+                fade_angle: fade_angle.into().to_radians().max(0.),
+            }),
+            ..Self::point_light(position, intensity)
+        }
+    }
+
+    /// The intensity of the light falling on `point`, which is less than `intensity`
+    /// outside the cone of a spotlight.
+    pub fn intensity_at(&self, point: Point3) -> Color {
+        self.intensity * self.spot_falloff(point)
+    }
+
+    /// How much of the light is left at `point` by the cone of a spotlight, from 0 to 1.
+    pub(crate) fn spot_falloff(&self, point: Point3) -> f64 {
+        let spot = match self.kind {
+            LightKind::Spot(spot) => spot,
+            _ => return 1.,
+        };
+        let to_point = point - self.position;
+        if to_point.magnitude() < EPSILON {
+            return 1.;
+        }
+        let to_point = to_point.normalize();
+        let angle = to_point.dot(spot.direction).clamp(-1., 1.).acos();
+        let fade = if spot.fade_angle > 0. {
+            ((spot.cone_angle + spot.fade_angle - angle) / spot.fade_angle).clamp(0., 1.)
+        } else if angle <= spot.cone_angle {
+            1.
+        } else {
+            0.
+        };
+
+        fade * fade * (3. - 2. * fade)
+    }
+
+    /// A light coming from the upper half of the sky, `intensity` scaling the light of the sky.
+    pub fn dome(sky: Sky, intensity: Color) -> Self {
+        Self {
+            kind: LightKind::Dome(Dome { sky, samples: 32 }),
+            ..Self::point_light(Point3::origin(), intensity)
+        }
+    }
+
+    /// The light moved by `transform`, spotlights turning along. Fixtures keep their size.
+    pub fn transformed(self, transform: Matrix4) -> Self {
+        let kind = match self.kind {
+            LightKind::Spot(spot) => LightKind::Spot(Spot {
+                direction: (transform * spot.direction).normalize(),
+                ..spot
+            }),
+            kind => kind,
+        };
+
+        Self {
+            position: transform * self.position,
+            kind,
+            ..self
+        }
+    }
+
+    pub fn with_fixture(self, fixture: Fixture) -> Self {
+        Self {
+            fixture: Some(fixture),
+            ..self
+        }
+    }
+
+    /// The closest point where the ray hits the fixture of the light, if it has one.
+    pub(crate) fn fixture_intersection(&self, ray: Ray) -> Option<f64> {
+        let center = self.position;
+
+        if let LightKind::Dome(_) = self.kind {
+            return None;
+        }
+
+        match self.fixture? {
+            Fixture::Sphere { radius } => {
+                let center_to_ray = ray.origin - center;
+                let a = ray.direction.dot(ray.direction);
+                let b = 2. * ray.direction.dot(center_to_ray);
+                let c = center_to_ray.dot(center_to_ray) - radius.powi(2);
+                let discriminant = b.powi(2) - 4. * a * c;
+
+                if discriminant < 0. {
+                    return None;
+                }
+                let t1 = (-b - discriminant.sqrt()) / (2. * a);
+                let t2 = (-b + discriminant.sqrt()) / (2. * a);
+
+                [t1, t2].into_iter().find(|t| *t >= 0.)
+            }
+            Fixture::Quad { size } => {
+                if ray.direction.y.abs() < EPSILON {
+                    return None;
+                }
+                let t = (center.y - ray.origin.y) / ray.direction.y;
+                let point = ray.position(t);
+                let inside = (point.x - center.x).abs() <= size / 2.
+                    && (point.z - center.z).abs() <= size / 2.;
+
+                (t >= 0. && inside).then_some(t)
+            }
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+
     use super::*;
+    use crate::misc::approx_equal;
+    use std::f64::consts::PI;
 
     #[test]
     fn a_point_light_has_a_position_and_intensity() {
         let intensity = Color::new(1., 1., 1.);
-        let position = Tuple::point(0., 0., 0.);
+        let position = Point3::new(0., 0., 0.);
         let light = Light::point_light(position, intensity);
 
         assert_eq!(light.position, position);
         assert_eq!(light.intensity, intensity);
     }
+
+    #[test]
+    fn spotlights_fade_out_past_their_cone() {
+        let light = Light::spot_light(
+            Point3::new(0., 0., 0.),
+            Vec3::new(0., -2., 0.),
+            PI / 6.,
+            PI / 6.,
+            Color::white(),
+        );
+        let at_angle = |angle: f64| Point3::new(angle.sin(), -angle.cos(), 0.);
+
+        assert_eq!(light.intensity_at(at_angle(0.)), Color::white());
+        assert_eq!(light.intensity_at(at_angle(PI / 6.)), Color::white());
+        assert_eq!(
+            light.intensity_at(at_angle(PI / 4.)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(light.intensity_at(at_angle(PI / 3.)), Color::black());
+        assert_eq!(light.intensity_at(Point3::new(0., 5., 0.)), Color::black());
+    }
+
+    #[test]
+    fn fixtures_are_hit_around_their_light() {
+        let light = Light::point_light(Point3::new(0., 5., 0.), Color::white());
+        let down = Ray::new(Point3::new(0.2, 10., 0.3), Vec3::new(0., -1., 0.));
+
+        assert_eq!(light.fixture_intersection(down), None);
+
+        let sphere = light.with_fixture(Fixture::Sphere { radius: 1. });
+        let t = sphere.fixture_intersection(down).unwrap();
+        assert!(approx_equal(t, 5. - 0.87_f64.sqrt()));
+
+        let quad = light.with_fixture(Fixture::Quad { size: 1. });
+        assert_eq!(quad.fixture_intersection(down), Some(5.));
+        let beside = Ray::new(Point3::new(0.6, 10., 0.), Vec3::new(0., -1., 0.));
+        assert_eq!(quad.fixture_intersection(beside), None);
+    }
 }
@@ -0,0 +1,238 @@
+//! Color lookup tables in the `.cube` format, to grade renders like footage from a camera
+//! or give them a look, applied when exporting them with `Canvas::graded`.
+
+use crate::{
+    color::Color,
+    error::{RayTracerError, Result},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lut {
+    /// The colors the table goes from and to, mapped to its first and last entries.
+    domain_min: Color,
+    domain_max: Color,
+    table: Table,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Table {
+    /// Curves for each channel on its own.
+    OneD(Vec<Color>),
+    /// A cube of colors, red changing fastest, then green, then blue.
+    ThreeD { size: usize, entries: Vec<Color> },
+}
+
+impl Lut {
+    /// Reads a 1D or 3D table in Adobe's `.cube` format.
+    pub fn from_cube(source: &str) -> Result<Lut> {
+        let mut one_d_size = None;
+        let mut three_d_size = None;
+        let mut domain_min = Color::black();
+        let mut domain_max = Color::white();
+        let mut entries = vec![];
+
+        for line in source.lines().map(str::trim) {
+            let mut words = line.split_whitespace();
+            let keyword = match words.next() {
+                None => continue,
+                Some(word) if word.starts_with('#') => continue,
+                Some(word) => word,
+            };
+            let rest: Vec<&str> = words.collect();
+
+            match keyword {
+                "TITLE" => {}
+                "LUT_1D_SIZE" => one_d_size = Some(parse_size(&rest)?),
+                "LUT_3D_SIZE" => three_d_size = Some(parse_size(&rest)?),
+                "DOMAIN_MIN" => domain_min = parse_color(&rest)?,
+                "DOMAIN_MAX" => domain_max = parse_color(&rest)?,
+                first => {
+                    let mut values = vec![first];
+                    values.extend(rest);
+                    entries.push(parse_color(&values)?);
+                }
+            }
+        }
+
+        let table = match (one_d_size, three_d_size) {
+            (Some(size), None) if entries.len() == size => Table::OneD(entries),
+            (None, Some(size)) if Some(entries.len()) == size.checked_pow(3) => {
+                Table::ThreeD { size, entries }
+            }
+            (Some(_), None) | (None, Some(_)) => {
+                return Err(invalid_data(
+                    "the table doesn't have as many entries as its size",
+                ))
+            }
+            _ => return Err(invalid_data("tables have either a 1D or a 3D size")),
+        };
+
+        let (min, max) = (channels(domain_min), channels(domain_max));
+        if !(0..3).all(|channel| max[channel] > min[channel]) {
+            return Err(invalid_data(
+                "the domain has to go upwards on every channel",
+            ));
+        }
+
+        Ok(Lut {
+            domain_min,
+            domain_max,
+            table,
+        })
+    }
+
+    /// Looks the color up, interpolating between the entries around it.
+    /// Colors outside the domain get the entries at its edges.
+    pub fn apply(&self, color: Color) -> Color {
+        let (min, max) = (channels(self.domain_min), channels(self.domain_max));
+        let [red, green, blue] = channels(color);
+        let position = |value: f64, channel: usize| {
+            ((value - min[channel]) / (max[channel] - min[channel])).clamp(0., 1.)
+        };
+        let (red, green, blue) = (position(red, 0), position(green, 1), position(blue, 2));
+
+        match &self.table {
+            Table::OneD(entries) => {
+                let curve = |value: f64, channel: usize| {
+                    let (low, high, fraction) = neighbors(value, entries.len());
+
+                    channels(entries[low])[channel] * (1. - fraction)
+                        + channels(entries[high])[channel] * fraction
+                };
+
+                Color::new(curve(red, 0), curve(green, 1), curve(blue, 2))
+            }
+            Table::ThreeD { size, entries } => {
+                let size = *size;
+                let (r0, r1, rf) = neighbors(red, size);
+                let (g0, g1, gf) = neighbors(green, size);
+                let (b0, b1, bf) = neighbors(blue, size);
+                let entry = |r: usize, g: usize, b: usize| entries[r + size * (g + size * b)];
+                let mix =
+                    |c0: Color, c1: Color, fraction: f64| c0 * (1. - fraction) + c1 * fraction;
+                let plane = |b: usize| {
+                    mix(
+                        mix(entry(r0, g0, b), entry(r1, g0, b), rf),
+                        mix(entry(r0, g1, b), entry(r1, g1, b), rf),
+                        gf,
+                    )
+                };
+
+                mix(plane(b0), plane(b1), bf)
+            }
+        }
+    }
+}
+
+fn channels(color: Color) -> [f64; 3] {
+    [color.red, color.green, color.blue]
+}
+
+/// The entries on either side of a position from 0 to 1 along a table of `size` entries,
+/// and how far it is from the first.
+fn neighbors(position: f64, size: usize) -> (usize, usize, f64) {
+    let position = position * (size - 1) as f64;
+    let low = position.floor();
+
+    (low as usize, position.ceil() as usize, position - low)
+}
+
+fn parse_size(words: &[&str]) -> Result<usize> {
+    match words {
+        [size] => match size.parse() {
+            Ok(size) if size >= 2 => Ok(size),
+            _ => Err(invalid_data(
+                "tables have at least two entries along each side",
+            )),
+        },
+        _ => Err(invalid_data("sizes are a single number")),
+    }
+}
+
+fn parse_color(words: &[&str]) -> Result<Color> {
+    let values: Vec<f64> = words
+        .iter()
+        .map(|word| word.parse().map_err(|_| invalid_data("expected a number")))
+        .collect::<Result<_>>()?;
+
+    match values.as_slice() {
+        [red, green, blue] => Ok(Color::new(*red, *green, *blue)),
+        _ => Err(invalid_data("colors are three numbers")),
+    }
+}
+
+fn invalid_data(message: &str) -> RayTracerError {
+    RayTracerError::Parse(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_1d_table_is_a_curve_for_each_channel() {
+        let lut = Lut::from_cube(
+            "TITLE \"invert red\"\n\
+             # red goes down, green and blue stay\n\
+             LUT_1D_SIZE 3\n\
+             1 0 0\n\
+             0.5 0.5 0.5\n\
+             0 1 1\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            lut.apply(Color::new(0.25, 0.25, 1.)),
+            Color::new(0.75, 0.25, 1.)
+        );
+        assert_eq!(lut.apply(Color::new(2., -1., 0.5)), Color::new(0., 0., 0.5));
+    }
+
+    #[test]
+    fn a_3d_table_is_interpolated_between_its_corners() {
+        // Red and blue swapped, the cube going through red fastest.
+        let mut source = String::from("LUT_3D_SIZE 2\n");
+        for blue in 0..2 {
+            for green in 0..2 {
+                for red in 0..2 {
+                    source += &format!("{} {} {}\n", blue, green, red);
+                }
+            }
+        }
+        let lut = Lut::from_cube(&source).unwrap();
+
+        assert_eq!(
+            lut.apply(Color::new(0.2, 0.4, 0.9)),
+            Color::new(0.9, 0.4, 0.2)
+        );
+    }
+
+    #[test]
+    fn the_domain_is_stretched_over_the_table() {
+        let lut =
+            Lut::from_cube("DOMAIN_MIN 0 0 0\nDOMAIN_MAX 2 2 2\nLUT_1D_SIZE 2\n0 0 0\n1 1 1\n")
+                .unwrap();
+
+        assert_eq!(
+            lut.apply(Color::new(1., 2., 0.5)),
+            Color::new(0.5, 1., 0.25)
+        );
+    }
+
+    #[test]
+    fn invalid_tables() {
+        let invalid = [
+            "0 0 0\n1 1 1\n",
+            "LUT_1D_SIZE 3\n0 0 0\n1 1 1\n",
+            "LUT_3D_SIZE 2\n0 0 0\n1 1 1\n",
+            "LUT_1D_SIZE 2\n0 0\n1 1 1\n",
+            "LUT_1D_SIZE 2\nLUT_3D_SIZE 2\n0 0 0\n1 1 1\n",
+            "LUT_1D_SIZE 1\n0 0 0\n",
+            "DOMAIN_MIN 1 0 0\nDOMAIN_MAX 1 1 1\nLUT_1D_SIZE 2\n0 0 0\n1 1 1\n",
+        ];
+
+        for source in invalid {
+            assert!(Lut::from_cube(source).is_err(), "{}", source);
+        }
+    }
+}
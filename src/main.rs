@@ -0,0 +1,312 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    process::exit,
+};
+
+use clap::{builder::PossibleValuesParser, error::ErrorKind, CommandFactory, Parser, Subcommand};
+use ray_tracer::{
+    camera::Camera,
+    error::RayTracerError,
+    render_settings::{RenderSettings, Tile, PRESETS},
+    sampler::AdaptiveSampling,
+    scene::Scene,
+    tone::{ToneOperator, TONE_OPERATORS},
+    world::World,
+};
+
+#[derive(Parser)]
+#[command(name = "raytracer", about = "Renders scenes described in YAML files.")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Serves renders over HTTP.
+    #[cfg(feature = "server")]
+    Serve {
+        /// The address to listen on, only this machine unless told otherwise.
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
+    /// Renders a scene to an image, or to a directory of frames.
+    Render(Box<RenderArgs>),
+}
+
+#[derive(clap::Args)]
+struct RenderArgs {
+    /// The YAML file describing the scene.
+    scene: String,
+    /// Written as a PNG if it ends in .png, as a Radiance HDR image keeping colors over 1 if
+    /// it ends in .hdr, and otherwise as a PPM. With any --frame option, a directory the
+    /// frames go to as frame_NNNN.ppm, or frame_NNNN_tile_I_of_N.ppm for tiles.
+    output: String,
+    #[arg(long, value_parser = PossibleValuesParser::new(PRESETS))]
+    preset: Option<String>,
+    /// Render settings to start from, in place of a preset.
+    #[arg(long, conflicts_with = "preset")]
+    settings: Option<String>,
+    /// Overrides the width of the scene's camera, keeping its shape if --height isn't given.
+    #[arg(long)]
+    width: Option<usize>,
+    /// Overrides the height of the scene's camera, keeping its shape if --width isn't given.
+    #[arg(long)]
+    height: Option<usize>,
+    /// Samples per pixel, over what the settings say.
+    #[arg(long)]
+    samples: Option<usize>,
+    /// Samples parts of the image a quick prepass shows little going on in less, down to
+    /// this many samples per pixel, and the rest up to --samples.
+    #[arg(long)]
+    min_samples: Option<usize>,
+    /// Threads to render with, over what the settings say.
+    #[arg(long)]
+    threads: Option<usize>,
+    /// How many times rays bounce, over what the settings say.
+    #[arg(long)]
+    max_depth: Option<usize>,
+    /// Renders the Ith of N bands of rows, written I/N and counting from 1 at the top.
+    #[arg(long, value_parser = tile)]
+    tile: Option<Tile>,
+    /// Brings colors over 1 into PNGs and PPMs.
+    #[arg(long, value_parser = PossibleValuesParser::new(TONE_OPERATORS))]
+    tone_map: Option<String>,
+    /// Stops to brighten colors by before tone mapping them.
+    #[arg(long, value_parser = finite)]
+    exposure: Option<f64>,
+    /// Bits per channel of PNGs.
+    #[arg(long, value_parser = ["8", "16"])]
+    bit_depth: Option<String>,
+    /// The first frame of an animation to render, 0 unless given.
+    #[arg(long)]
+    frame_start: Option<usize>,
+    /// The last frame to render, the first unless given.
+    #[arg(long)]
+    frame_end: Option<usize>,
+    /// Renders every this many frames.
+    #[arg(long)]
+    frame_step: Option<usize>,
+    /// Frames per second, every frame rendered at the time of its number over these.
+    #[arg(long, default_value_t = 24., value_parser = positive)]
+    fps: f64,
+}
+
+fn tile(value: &str) -> Result<Tile, String> {
+    Tile::parse(value).map_err(|error| error.to_string())
+}
+
+fn finite(value: &str) -> Result<f64, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|value: &f64| value.is_finite())
+        .ok_or_else(|| "must be a number".to_string())
+}
+
+fn positive(value: &str) -> Result<f64, String> {
+    Some(finite(value)?)
+        .filter(|value| *value > 0.)
+        .ok_or_else(|| "must be positive".to_string())
+}
+
+fn main() {
+    match Cli::parse().command {
+        #[cfg(feature = "server")]
+        Command::Serve { host, port } => serve(&host, port),
+        Command::Render(args) => {
+            let (scene, output) = (args.scene.clone(), args.output.clone());
+            match RenderOptions::new(*args) {
+                Ok(options) => render(&scene, &output, options),
+                Err(message) => {
+                    let mut command = Cli::command();
+                    let render = command.find_subcommand_mut("render").unwrap();
+                    render.error(ErrorKind::ValueValidation, message).exit()
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+fn serve(host: &str, port: u16) {
+    if let Err(error) = ray_tracer::server::serve((host, port)) {
+        fail(&error.to_string());
+    }
+}
+
+/// What to render of a scene, and how.
+struct RenderOptions {
+    settings: RenderSettings,
+    /// Pixels across and down in place of the camera's own.
+    width: Option<usize>,
+    height: Option<usize>,
+    /// The numbers of the frames to render, or `None` for a single image.
+    frames: Option<Vec<usize>>,
+    fps: f64,
+    /// Writes 16 bit PNGs instead of 8 bit ones.
+    sixteen_bit: bool,
+}
+
+impl RenderOptions {
+    fn new(args: RenderArgs) -> Result<Self, String> {
+        let mut settings = match (&args.preset, &args.settings) {
+            (Some(preset), _) => RenderSettings::preset(preset).unwrap(),
+            (None, Some(path)) => {
+                RenderSettings::from_file(path).map_err(|error| error.to_string())?
+            }
+            (None, None) => RenderSettings::default(),
+        };
+        let max_depth = args
+            .max_depth
+            .map(|depth| {
+                i32::try_from(depth).map_err(|_| format!("`--max-depth` of {} is too deep", depth))
+            })
+            .transpose()?;
+
+        // Given on their own, these override whatever the preset or settings file says.
+        settings.samples_per_pixel = args.samples.or(settings.samples_per_pixel);
+        settings.threads = args.threads.or(settings.threads);
+        settings.max_depth = max_depth.or(settings.max_depth);
+        settings.tile = args.tile.or(settings.tile);
+        if let Some(min_samples) = args.min_samples {
+            let prepass_scale = settings.adaptive.unwrap_or_default().prepass_scale;
+            settings.adaptive = Some(AdaptiveSampling::new(min_samples, prepass_scale));
+        }
+        let operator = args
+            .tone_map
+            .as_deref()
+            .map(|name| ToneOperator::named(name).unwrap());
+        if operator.is_some() || args.exposure.is_some() {
+            let tone_map = settings.tone_map.get_or_insert_with(Default::default);
+            tone_map.operator = operator.unwrap_or(tone_map.operator);
+            tone_map.exposure = args.exposure.unwrap_or(tone_map.exposure);
+        }
+        if args.width == Some(0) || args.height == Some(0) {
+            return Err("images must be at least a pixel wide and tall".to_string());
+        }
+        let frames = match (args.frame_start, args.frame_end, args.frame_step) {
+            (None, None, None) => None,
+            (_, _, Some(0)) => return Err("`--frame-step` must be at least 1".to_string()),
+            (start, end, step) => {
+                let start = start.unwrap_or(0);
+                let end = end.unwrap_or(start);
+                if end < start {
+                    return Err(format!(
+                        "frames end at {} before they start at {}",
+                        end, start
+                    ));
+                }
+
+                Some((start..=end).step_by(step.unwrap_or(1)).collect())
+            }
+        };
+
+        Ok(Self {
+            settings,
+            width: args.width,
+            height: args.height,
+            frames,
+            fps: args.fps,
+            sixteen_bit: args.bit_depth.as_deref() == Some("16"),
+        })
+    }
+}
+
+fn render(scene: &str, output: &str, options: RenderOptions) {
+    let mut scene = Scene::from_file(scene).unwrap_or_else(|error| fail(&error.to_string()));
+    if let Err(error) = resize(&mut scene.camera, options.width, options.height) {
+        fail(&error);
+    }
+    let frames = match &options.frames {
+        Some(frames) => frames,
+        None => {
+            let output = Path::new(output);
+            render_image(scene.camera, &scene.world, &options, output);
+            return;
+        }
+    };
+
+    if let Err(error) = std::fs::create_dir_all(output) {
+        fail(&error.to_string());
+    }
+    for &frame in frames {
+        let name = match options.settings.tile {
+            Some(tile) => format!(
+                "frame_{:04}_tile_{}_of_{}.ppm",
+                frame, tile.index, tile.count
+            ),
+            None => format!("frame_{:04}.ppm", frame),
+        };
+        println!("Frame {}:", frame);
+
+        let mut camera = scene.camera;
+        camera.time = frame as f64 / options.fps;
+        let output = Path::new(output).join(name);
+        render_image(camera, &scene.world, &options, &output);
+    }
+}
+
+/// Changes the size of the image the camera takes, working out the side left out from
+/// the shape of the image it took before.
+fn resize(camera: &mut Camera, width: Option<usize>, height: Option<usize>) -> Result<(), String> {
+    let aspect = camera.hsize as f64 / camera.vsize.max(1) as f64;
+    let (width, height) = match (width, height) {
+        (None, None) => return Ok(()),
+        (Some(width), Some(height)) => (width, height),
+        (Some(width), None) => (width, ((width as f64 / aspect).round() as usize).max(1)),
+        (None, Some(height)) => (((height as f64 * aspect).round() as usize).max(1), height),
+    };
+
+    camera.hsize =
+        i32::try_from(width).map_err(|_| format!("images can't be {} pixels wide", width))?;
+    camera.vsize =
+        i32::try_from(height).map_err(|_| format!("images can't be {} pixels tall", height))?;
+
+    Ok(())
+}
+
+fn render_image(camera: Camera, world: &World, options: &RenderOptions, output: &Path) {
+    let settings = &options.settings;
+    let canvas = camera.render_with_settings(world, settings, |done, total| {
+        print!("Computed: {}/{} pixels.\r", done, total);
+        std::io::stdout().flush().unwrap();
+    });
+    println!();
+
+    let extension = output.extension().and_then(|extension| extension.to_str());
+    if extension == Some("hdr") {
+        return write_file(output, &canvas.to_hdr());
+    }
+    let canvas = match settings.tone_map {
+        Some(tone_map) => canvas.tone_mapped(tone_map),
+        None => canvas,
+    };
+    match extension {
+        Some("png") if options.sixteen_bit => write_file(output, &canvas.to_png_16()),
+        Some("png") => write_file(output, &canvas.to_png()),
+        _ => {
+            let written = File::create(output)
+                .map_err(RayTracerError::from)
+                .and_then(|file| canvas.write_ppm(BufWriter::new(file)));
+            if let Err(error) = written {
+                fail(&error.to_string());
+            }
+        }
+    }
+}
+
+fn write_file(output: &Path, bytes: &[u8]) {
+    if let Err(error) = std::fs::write(output, bytes) {
+        fail(&error.to_string());
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    exit(1)
+}
@@ -1,9 +1,12 @@
 use crate::color::Color;
 use crate::light::Light;
-use crate::math::tuple::Tuple;
-use crate::misc::approx_equal;
+use crate::math::matrix4::Matrix4;
+use crate::math::point3::Point3;
+use crate::math::vec3::Vec3;
+use crate::misc::{approx_equal, EPSILON};
 use crate::pattern::Pattern;
 use crate::shape::SimpleObject;
+use crate::water::Waves;
 
 #[derive(Clone, Copy, Debug)]
 pub struct Material {
@@ -13,14 +16,73 @@ pub struct Material {
     pub specular: f64,
     pub shininess: f64,
     pub reflective: f64,
-    pattern: Option<Pattern>,
+    pub(crate) pattern: Option<Pattern>,
     pub transparency: f64,
     pub refractive_index: f64,
+    /// How much of each color transparent materials soak up per unit light travels inside them,
+    /// black for none. Light going `d` units through keeps `e^(-absorption * d)` of itself,
+    /// so thick glass looks more tinted than thin glass.
+    pub absorption: Color,
     pub casts_shadows: bool,
+    /// Ripples bending the normals of the surface, see `water_material`.
+    pub waves: Option<Waves>,
+    /// Bumps bending the normals of the surface as if it were raised by a pattern.
+    pub bump: Option<Bump>,
+    /// Only shows the shadows falling on it, over whatever is behind it.
+    pub shadow_catcher: bool,
+    /// Sends rays on from another place instead of shading the surface.
+    pub portal: Option<Portal>,
+    /// How the diffuse light of every light changes with how squarely it falls on the
+    /// surface, looked up along x from 0 (grazing) to 1 (straight on) in the space of the
+    /// pattern, in place of the usual falloff. Stepped gradients give toon shading, and
+    /// colored ones tint the light as it turns away, see `Pattern::ramp_at`.
+    pub ramp: Option<Pattern>,
+    /// Which of the world's material slots the surface takes its look from,
+    /// see `World::set_material_slot`.
+    pub slot: Option<usize>,
+    /// Given to objects made without a material, for the world's default material to take
+    /// over, see `World::set_default_material`.
+    pub(crate) unset: bool,
+}
+
+/// A surface raised where `pattern` is bright and sunk where it's dark, by `strength`
+/// units per unit of brightness, shading it as if it had bumps without moving it.
+#[derive(Clone, Copy, Debug)]
+pub struct Bump {
+    pub pattern: Pattern,
+    pub strength: f64,
+}
+
+impl Bump {
+    pub fn new(pattern: Pattern, strength: f64) -> Self {
+        Self { pattern, strength }
+    }
+
+    /// Tilts `normal` away from where the surface of the object rises around `point` at `time`.
+    pub(crate) fn perturb(
+        self,
+        object: SimpleObject,
+        normal: Vec3,
+        point: Point3,
+        time: f64,
+    ) -> Vec3 {
+        let gradient = self.pattern.gradient_at_object_time(object, point, time);
+        // Only the part of the slope along the surface tilts it.
+        let along_surface = gradient - normal * gradient.dot(normal);
+
+        (normal - along_surface * self.strength).normalize()
+    }
+}
+
+/// Where rays going into a portal come out: they go through `exit`, the transform of the
+/// paired portal, as if they had gone through the object they hit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Portal {
+    pub exit: Matrix4,
 }
 
 impl Material {
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
         Self {
             color: Color::white(),
             ambient: 0.1,
@@ -31,7 +93,15 @@ impl Material {
             pattern: None,
             transparency: 0.,
             refractive_index: 1.,
+            absorption: Color::black(),
             casts_shadows: true,
+            waves: None,
+            bump: None,
+            shadow_catcher: false,
+            portal: None,
+            ramp: None,
+            slot: None,
+            unset: false,
         }
     }
 
@@ -41,6 +111,47 @@ impl Material {
             ..Self::new()
         }
     }
+
+    /// An invisible material that still darkens with the shadows it receives, to ground
+    /// objects over any background.
+    pub fn shadow_catcher() -> Self {
+        Self {
+            shadow_catcher: true,
+            casts_shadows: false,
+            ..Self::new()
+        }
+    }
+
+    /// Whether rays hitting the material go on, reflected, refracted or through a portal.
+    pub(crate) fn sends_rays_on(&self) -> bool {
+        self.reflective > 0. || self.transparency > 0. || self.portal.is_some()
+    }
+
+    pub fn portal(exit: Matrix4) -> Self {
+        Self {
+            portal: Some(Portal { exit }),
+            casts_shadows: false,
+            ..Self::new()
+        }
+    }
+
+    /// The material as it is at `time`, with its pattern replaced by its color at the point.
+    pub(crate) fn at_time(self, object: SimpleObject, point: Point3, time: f64) -> Self {
+        match self.pattern {
+            Some(pattern) => Self {
+                color: pattern.pattern_at_object_time(object, point, time),
+                pattern: None,
+                ..self
+            },
+            None => self,
+        }
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PartialEq for Material {
@@ -57,10 +168,11 @@ pub fn lighting(
     material: Material,
     object: SimpleObject,
     light: Light,
-    point: Tuple,
-    eye_vector: Tuple,
-    normal_vector: Tuple,
-    in_shadow: bool,
+    point: Point3,
+    eye_vector: Vec3,
+    normal_vector: Vec3,
+    // How much of the light reaches the point, black if it's completely in shadow.
+    light_visibility: Color,
 ) -> Color {
     let color = if let Some(pattern) = material.pattern {
         pattern.pattern_at_object(object, point)
@@ -68,10 +180,17 @@ pub fn lighting(
         material.color
     };
 
-    // combine the surface color with the light's color/intensity
-    let effective_color = color * light.intensity;
-    // find the direction to the light source
-    let light_vector = (light.position - point).normalize();
+    // combine the surface color with the light's color/intensity,
+    // dimmer outside the cone of a spotlight
+    let intensity = light.intensity_at(point);
+    let effective_color = color * intensity;
+    // find the direction to the light source, taking points right at it as facing it
+    let to_light = light.position - point;
+    let light_vector = if to_light.magnitude() < EPSILON {
+        normal_vector
+    } else {
+        to_light.normalize()
+    };
     // compute the ambient contribution
     let ambient = effective_color * material.ambient;
 
@@ -84,7 +203,11 @@ pub fn lighting(
         (Color::black(), Color::black())
     } else {
         // compute the diffuse contribution
-        let diffuse = effective_color * material.diffuse * light_dot_normal;
+        let falloff = match material.ramp {
+            Some(ramp) => ramp.ramp_at(light_dot_normal),
+            None => Color::white() * light_dot_normal,
+        };
+        let diffuse = effective_color * material.diffuse * falloff;
         // reflect_dot_eye represents the cosine of the angle between the
         // reflection vector and the eye vector. A negative number means the
         // light reflects away from the eye.
@@ -96,20 +219,22 @@ pub fn lighting(
         } else {
             // compute the specular contribution
             let factor = reflect_dot_eye.powf(material.shininess);
-            let specular = light.intensity * material.specular * factor;
+            let specular = intensity * material.specular * factor;
             (diffuse, specular)
         }
     };
 
-    if in_shadow {
-        ambient
-    } else {
-        ambient + diffuse + specular
-    }
+    ambient + (diffuse + specular) * light_visibility
 }
 #[cfg(test)]
 mod tests {
-    use crate::{misc::approx_equal, shape::Object};
+
+    use crate::{
+        misc::approx_equal,
+        pattern::{Gradient, GradientBlend},
+        ray::Ray,
+        shape::Object,
+    };
 
     use super::*;
 
@@ -130,11 +255,40 @@ mod tests {
         let m = Material::new();
         let o = Object::sphere();
         let s = SimpleObject::from_object(&o).unwrap();
-        let position = Tuple::point(0., 0., 0.);
-        let eye_vector = Tuple::vector(0., 0., -1.);
-        let normal_vector = Tuple::vector(0., 0., -1.);
-        let light = Light::point_light(Tuple::point(0., 0., -10.), Color::new(1., 1., 1.));
-        let result = lighting(m, s, light, position, eye_vector, normal_vector, false);
+        let position = Point3::new(0., 0., 0.);
+        let eye_vector = Vec3::new(0., 0., -1.);
+        let normal_vector = Vec3::new(0., 0., -1.);
+        let light = Light::point_light(Point3::new(0., 0., -10.), Color::new(1., 1., 1.));
+        let result = lighting(
+            m,
+            s,
+            light,
+            position,
+            eye_vector,
+            normal_vector,
+            Color::white(),
+        );
+        assert_eq!(result, Color::new(1.9, 1.9, 1.9));
+    }
+
+    #[test]
+    fn lighting_with_the_light_right_at_the_surface() {
+        let m = Material::new();
+        let o = Object::sphere();
+        let s = SimpleObject::from_object(&o).unwrap();
+        let position = Point3::new(0., 0., 0.);
+        let eye_vector = Vec3::new(0., 0., -1.);
+        let normal_vector = Vec3::new(0., 0., -1.);
+        let light = Light::point_light(position, Color::new(1., 1., 1.));
+        let result = lighting(
+            m,
+            s,
+            light,
+            position,
+            eye_vector,
+            normal_vector,
+            Color::white(),
+        );
         assert_eq!(result, Color::new(1.9, 1.9, 1.9));
     }
 
@@ -143,11 +297,19 @@ mod tests {
         let m = Material::new();
         let o = Object::sphere();
         let s = SimpleObject::from_object(&o).unwrap();
-        let position = Tuple::point(0., 0., 0.);
-        let eye_vector = Tuple::vector(0., 2_f64.sqrt() / 2., -2_f64.sqrt() / 2.);
-        let normal_vector = Tuple::vector(0., 0., -1.);
-        let light = Light::point_light(Tuple::point(0., 0., -10.), Color::new(1., 1., 1.));
-        let result = lighting(m, s, light, position, eye_vector, normal_vector, false);
+        let position = Point3::new(0., 0., 0.);
+        let eye_vector = Vec3::new(0., 2_f64.sqrt() / 2., -2_f64.sqrt() / 2.);
+        let normal_vector = Vec3::new(0., 0., -1.);
+        let light = Light::point_light(Point3::new(0., 0., -10.), Color::new(1., 1., 1.));
+        let result = lighting(
+            m,
+            s,
+            light,
+            position,
+            eye_vector,
+            normal_vector,
+            Color::white(),
+        );
         assert_eq!(result, Color::new(1.0, 1.0, 1.0));
     }
 
@@ -156,11 +318,19 @@ mod tests {
         let m = Material::new();
         let o = Object::sphere();
         let s = SimpleObject::from_object(&o).unwrap();
-        let position = Tuple::point(0., 0., 0.);
-        let eye_vector = Tuple::vector(0., 0., -1.);
-        let normal_vector = Tuple::vector(0., 0., -1.);
-        let light = Light::point_light(Tuple::point(0., 10., -10.), Color::new(1., 1., 1.));
-        let result = lighting(m, s, light, position, eye_vector, normal_vector, false);
+        let position = Point3::new(0., 0., 0.);
+        let eye_vector = Vec3::new(0., 0., -1.);
+        let normal_vector = Vec3::new(0., 0., -1.);
+        let light = Light::point_light(Point3::new(0., 10., -10.), Color::new(1., 1., 1.));
+        let result = lighting(
+            m,
+            s,
+            light,
+            position,
+            eye_vector,
+            normal_vector,
+            Color::white(),
+        );
         assert_eq!(result, Color::new(0.7364, 0.7364, 0.7364));
     }
 
@@ -169,11 +339,19 @@ mod tests {
         let m = Material::new();
         let o = Object::sphere();
         let s = SimpleObject::from_object(&o).unwrap();
-        let position = Tuple::point(0., 0., 0.);
-        let eye_vector = Tuple::vector(0., -2_f64.sqrt() / 2., -2_f64.sqrt() / 2.);
-        let normal_vector = Tuple::vector(0., 0., -1.);
-        let light = Light::point_light(Tuple::point(0., 10., -10.), Color::new(1., 1., 1.));
-        let result = lighting(m, s, light, position, eye_vector, normal_vector, false);
+        let position = Point3::new(0., 0., 0.);
+        let eye_vector = Vec3::new(0., -2_f64.sqrt() / 2., -2_f64.sqrt() / 2.);
+        let normal_vector = Vec3::new(0., 0., -1.);
+        let light = Light::point_light(Point3::new(0., 10., -10.), Color::new(1., 1., 1.));
+        let result = lighting(
+            m,
+            s,
+            light,
+            position,
+            eye_vector,
+            normal_vector,
+            Color::white(),
+        );
         assert_eq!(result, Color::new(1.6364, 1.6364, 1.6364));
     }
 
@@ -182,11 +360,19 @@ mod tests {
         let m = Material::new();
         let o = Object::sphere();
         let s = SimpleObject::from_object(&o).unwrap();
-        let position = Tuple::point(0., 0., 0.);
-        let eye_vector = Tuple::vector(0., 0., -1.);
-        let normal_vector = Tuple::vector(0., 0., -1.);
-        let light = Light::point_light(Tuple::point(0., 0., 10.), Color::new(1., 1., 1.));
-        let result = lighting(m, s, light, position, eye_vector, normal_vector, false);
+        let position = Point3::new(0., 0., 0.);
+        let eye_vector = Vec3::new(0., 0., -1.);
+        let normal_vector = Vec3::new(0., 0., -1.);
+        let light = Light::point_light(Point3::new(0., 0., 10.), Color::new(1., 1., 1.));
+        let result = lighting(
+            m,
+            s,
+            light,
+            position,
+            eye_vector,
+            normal_vector,
+            Color::white(),
+        );
         assert_eq!(result, Color::new(0.1, 0.1, 0.1));
     }
 
@@ -194,20 +380,102 @@ mod tests {
     fn lighting_with_the_surface_in_shadow() {
         let m = Material::new();
         let o = Object::sphere();
-        let eye_vector = Tuple::vector(0., 0., -1.);
+        let eye_vector = Vec3::new(0., 0., -1.);
         let s = SimpleObject::from_object(&o).unwrap();
-        let position = Tuple::point(0., 0., 0.);
-        let normal_vector = Tuple::vector(0., 0., -1.);
-        let light = Light::point_light(Tuple::point(0., 0., -10.), Color::new(1., 1., 1.));
-        let in_shadow = true;
-        let result = lighting(m, s, light, position, eye_vector, normal_vector, in_shadow);
+        let position = Point3::new(0., 0., 0.);
+        let normal_vector = Vec3::new(0., 0., -1.);
+        let light = Light::point_light(Point3::new(0., 0., -10.), Color::new(1., 1., 1.));
+        let light_visibility = Color::black();
+        let result = lighting(
+            m,
+            s,
+            light,
+            position,
+            eye_vector,
+            normal_vector,
+            light_visibility,
+        );
         assert_eq!(result, Color::new(0.1, 0.1, 0.1));
     }
 
+    #[test]
+    fn lighting_through_a_colored_occluder() {
+        let m = Material::new();
+        let o = Object::sphere();
+        let s = SimpleObject::from_object(&o).unwrap();
+        let eye_vector = Vec3::new(0., 0., -1.);
+        let position = Point3::new(0., 0., 0.);
+        let normal_vector = Vec3::new(0., 0., -1.);
+        let light = Light::point_light(Point3::new(0., 0., -10.), Color::new(1., 1., 1.));
+        let light_visibility = Color::new(0., 0., 0.5);
+        let result = lighting(
+            m,
+            s,
+            light,
+            position,
+            eye_vector,
+            normal_vector,
+            light_visibility,
+        );
+
+        assert_eq!(result, Color::new(0.1, 0.1, 1.));
+    }
+
     #[test]
     fn transparency_and_refractive_index_for_the_default_material() {
         let m = Material::new();
         assert!(approx_equal(m.transparency, 0.0));
         assert!(approx_equal(m.refractive_index, 1.0));
     }
+
+    #[test]
+    fn bumps_tilt_normals_away_from_where_the_surface_rises() {
+        let mut plane = Object::plane();
+        let mut material = Material::new();
+        material.bump = Some(Bump::new(
+            Pattern::gradient(Color::black(), Color::white()),
+            0.1,
+        ));
+        plane.set_material(material);
+        let r = Ray::new(Point3::new(0.5, 1., 0.), Vec3::new(0., -1., 0.));
+
+        let xs = plane.intersect(r);
+        let comps = xs[0].prepare_computations(r, &xs);
+
+        assert_eq!(comps.normal_vector, Vec3::new(-0.1, 1., 0.).normalize());
+    }
+
+    #[test]
+    fn ramps_replace_the_falloff_of_diffuse_light() {
+        let mut m = Material::new();
+        m.ambient = 0.;
+        m.specular = 0.;
+        m.diffuse = 1.;
+        let mut toon = Gradient::new(vec![(0., Color::new(0.2, 0.2, 0.2)), (0.5, Color::white())]);
+        toon.blend = GradientBlend::Steps;
+        m.ramp = Some(Pattern::gradient_with_stops(toon));
+        let o = Object::sphere();
+        let s = SimpleObject::from_object(&o).unwrap();
+        let position = Point3::new(0., 0., 0.);
+        let eye_vector = Vec3::new(0., 0., -1.);
+        let normal_vector = Vec3::new(0., 0., -1.);
+        let shade = |light_position| {
+            let light = Light::point_light(light_position, Color::white());
+            lighting(
+                m,
+                s,
+                light,
+                position,
+                eye_vector,
+                normal_vector,
+                Color::white(),
+            )
+        };
+
+        // N·L is about 0.8 and 0.3, each in its own band.
+        assert_eq!(shade(Point3::new(0., 7.5, -10.)), Color::new(1., 1., 1.));
+        assert_eq!(shade(Point3::new(0., 30., -10.)), Color::new(0.2, 0.2, 0.2));
+        // Light from behind stays dark.
+        assert_eq!(shade(Point3::new(0., 0., 10.)), Color::black());
+    }
 }
@@ -0,0 +1,49 @@
+use std::f64::consts::PI;
+
+/// An angle, made from either degrees or radians so it's always clear which one a number is.
+///
+/// Rotations, camera fields of view and spotlight cones take anything that converts into an
+/// angle. Plain numbers still do, as radians.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Angle(f64);
+
+impl Angle {
+    pub const fn radians(radians: f64) -> Self {
+        Self(radians)
+    }
+
+    pub fn degrees(degrees: f64) -> Self {
+        Self(degrees * PI / 180.)
+    }
+
+    pub const fn to_radians(self) -> f64 {
+        self.0
+    }
+
+    pub fn to_degrees(self) -> f64 {
+        self.0 * 180. / PI
+    }
+}
+
+impl From<f64> for Angle {
+    fn from(radians: f64) -> Self {
+        Self::radians(radians)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{math::matrix4::Matrix4, misc::approx_equal};
+
+    #[test]
+    fn degrees_and_radians_are_the_same_angles() {
+        assert!(approx_equal(Angle::degrees(90.).to_radians(), PI / 2.));
+        assert!(approx_equal(Angle::radians(PI).to_degrees(), 180.));
+        assert_eq!(Angle::from(0.5), Angle::radians(0.5));
+        assert_eq!(
+            Matrix4::rotation_y(Angle::degrees(180.)),
+            Matrix4::rotation_y(PI)
+        );
+    }
+}
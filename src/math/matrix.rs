@@ -0,0 +1,313 @@
+use std::ops::Mul;
+
+use crate::misc::{self, approx_equal};
+
+/// A square matrix of `N` rows and columns.
+///
+/// Operations that work for every size are implemented generically,
+/// the ones that need a smaller matrix (submatrices, determinants by cofactors)
+/// are implemented for each concrete size, since stable Rust can't express `N - 1`.
+#[derive(Debug, Clone, Copy)]
+pub struct Matrix<const N: usize> {
+    rows: [[f64; N]; N],
+}
+
+impl<const N: usize> Matrix<N> {
+    pub const fn from_rows(rows: [[f64; N]; N]) -> Self {
+        Self { rows }
+    }
+
+    pub const fn zeroes() -> Self {
+        Self { rows: [[0.; N]; N] }
+    }
+
+    pub const fn identity() -> Self {
+        let mut zeroes = Self::zeroes();
+
+        let mut index = 0;
+        while index < N {
+            zeroes.rows[index][index] = 1.;
+            index += 1;
+        }
+
+        zeroes
+    }
+
+    /// The same as `self * other`, for transforms built in constants.
+    pub const fn multiply(self, other: Self) -> Self {
+        let mut result = Self::zeroes();
+
+        let mut row = 0;
+        while row < N {
+            let mut col = 0;
+            while col < N {
+                let mut k = 0;
+                while k < N {
+                    result.rows[row][col] += self.rows[row][k] * other.rows[k][col];
+                    k += 1;
+                }
+                col += 1;
+            }
+            row += 1;
+        }
+
+        result
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut result = Self::zeroes();
+
+        for row in 0..N {
+            for col in 0..N {
+                *result.get_mut(col, row) = self.get(row, col);
+            }
+        }
+
+        result
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> f64 {
+        self.rows[row][col]
+    }
+
+    pub fn get_mut(&mut self, row: usize, col: usize) -> &mut f64 {
+        &mut self.rows[row][col]
+    }
+
+    pub(crate) fn rows(&self) -> &[[f64; N]; N] {
+        &self.rows
+    }
+
+    /// The inverse by Gauss-Jordan elimination, `None` if there is none.
+    ///
+    /// Rows are swapped to divide by the largest value left in each column, which keeps
+    /// rounding errors down. A matrix is taken to have no inverse when one of those values is
+    /// within rounding errors of 0, next to the largest entry of the matrix rather than to 1,
+    /// so small scales still have one (and matrices with infinities or NaNs don't).
+    pub fn inverse(&self) -> Option<Self> {
+        let mut left = self.rows;
+        let mut right = Self::identity().rows;
+        if left.iter().flatten().any(|entry| !entry.is_finite()) {
+            return None;
+        }
+        let largest = left
+            .iter()
+            .flatten()
+            .fold(0., |largest: f64, entry| largest.max(entry.abs()));
+        let tolerance = N as f64 * f64::EPSILON * largest;
+
+        for col in 0..N {
+            let pivot_row = (col..N)
+                .max_by(|&a, &b| left[a][col].abs().total_cmp(&left[b][col].abs()))
+                .unwrap_or(col);
+            if pivot_row != col {
+                left.swap(pivot_row, col);
+                right.swap(pivot_row, col);
+            }
+
+            let pivot = left[col][col];
+            if pivot.abs() <= tolerance {
+                return None;
+            }
+            for k in 0..N {
+                left[col][k] /= pivot;
+                right[col][k] /= pivot;
+            }
+
+            for row in 0..N {
+                let factor = left[row][col];
+                if row == col || factor == 0. {
+                    continue;
+                }
+                for k in 0..N {
+                    left[row][k] -= factor * left[col][k];
+                    right[row][k] -= factor * right[col][k];
+                }
+            }
+        }
+
+        Some(Self::from_rows(right))
+    }
+
+    /// Copies everything but the given row and column, `M` has to be `N - 1`.
+    fn without<const M: usize>(&self, row_to_delete: usize, col_to_delete: usize) -> Matrix<M> {
+        debug_assert_eq!(M + 1, N);
+        let mut result = Matrix::zeroes();
+
+        for row in 0..N {
+            for col in 0..N {
+                if let Some((offset_row, offset_col)) =
+                    misc::cmp_to_offset(row.cmp(&row_to_delete), col.cmp(&col_to_delete))
+                {
+                    let actual_row = (row as i32 + offset_row) as usize;
+                    let actual_col = (col as i32 + offset_col) as usize;
+
+                    *result.get_mut(actual_row, actual_col) = self.get(row, col);
+                }
+            }
+        }
+
+        result
+    }
+}
+
+impl Matrix<1> {
+    pub fn determinant(&self) -> f64 {
+        self.get(0, 0)
+    }
+}
+
+macro_rules! impl_cofactor_expansion {
+    ($n:literal, $m:literal) => {
+        impl Matrix<$n> {
+            pub fn determinant(&self) -> f64 {
+                (0..$n)
+                    .map(|col| {
+                        let element = self.get(0, col);
+
+                        element * self.cofactor(0, col)
+                    })
+                    .sum()
+            }
+
+            pub fn submatrix(&self, row_to_delete: usize, col_to_delete: usize) -> Matrix<$m> {
+                self.without(row_to_delete, col_to_delete)
+            }
+
+            pub fn minor(&self, row_to_delete: usize, col_to_delete: usize) -> f64 {
+                self.submatrix(row_to_delete, col_to_delete).determinant()
+            }
+
+            pub fn cofactor(&self, row_to_delete: usize, col_to_delete: usize) -> f64 {
+                let sign = if (row_to_delete + col_to_delete).is_multiple_of(2) {
+                    1.
+                } else {
+                    -1.
+                };
+
+                sign * self.minor(row_to_delete, col_to_delete)
+            }
+
+            /// The inverse as the adjugate over the determinant, the way the book works
+            /// it out. Over twice as slow as `inverse` for a `Matrix4`, kept to check it against.
+            pub fn cofactor_inverse(&self) -> Option<Self> {
+                let det = self.determinant();
+
+                if approx_equal(det, 0.) {
+                    None
+                } else {
+                    let mut result = Self::zeroes();
+
+                    for row in 0..$n {
+                        for col in 0..$n {
+                            let cofactor = self.cofactor(row, col);
+
+                            *result.get_mut(col, row) = cofactor / det;
+                        }
+                    }
+
+                    Some(result)
+                }
+            }
+        }
+    };
+}
+
+impl_cofactor_expansion!(2, 1);
+impl_cofactor_expansion!(3, 2);
+impl_cofactor_expansion!(4, 3);
+
+impl<const N: usize> Mul for Matrix<N> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.multiply(rhs)
+    }
+}
+
+impl<const N: usize> Mul<f64> for Matrix<N> {
+    type Output = Self;
+
+    fn mul(mut self, rhs: f64) -> Self::Output {
+        self.rows
+            .iter_mut()
+            .flatten()
+            .for_each(|element| *element *= rhs);
+
+        self
+    }
+}
+
+impl<const N: usize> PartialEq for Matrix<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rows
+            .iter()
+            .zip(other.rows.iter())
+            .all(|(row_a, row_b)| {
+                row_a
+                    .iter()
+                    .zip(row_b.iter())
+                    .all(|(a, b)| approx_equal(*a, *b))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_size_shares_the_same_operations() {
+        let a = Matrix::from_rows([[1., 2.], [3., 4.]]);
+
+        assert_eq!(a * Matrix::identity(), a);
+        assert_eq!(a.transpose(), Matrix::from_rows([[1., 3.], [2., 4.]]));
+        assert_eq!(a * 2., Matrix::from_rows([[2., 4.], [6., 8.]]));
+        assert_eq!(a.inverse().unwrap() * a, Matrix::identity());
+    }
+
+    #[test]
+    fn elimination_inverts_like_cofactors_do() {
+        let a = Matrix::from_rows([
+            [0., 9., 3., 9.],
+            [-5., -2., -6., -3.],
+            [-4., 9., 6., 4.],
+            [-7., 6., 6., 2.],
+        ]);
+        // A 0 where the first pivot would go without swapping rows.
+        let b = Matrix::from_rows([[0., 1., 2.], [3., 0., 5.], [1., 4., 0.]]);
+        let singular = Matrix::from_rows([[1., 2.], [2., 4.]]);
+
+        assert_eq!(a.inverse(), a.cofactor_inverse());
+        assert_eq!(b.inverse(), b.cofactor_inverse());
+        assert_eq!(singular.inverse(), None);
+        assert_eq!(singular.cofactor_inverse(), None);
+    }
+
+    #[test]
+    fn small_scales_have_an_inverse_and_rounding_errors_do_not() {
+        let small = Matrix::from_rows([[0.02, 0., 0.], [0., 0.02, 0.], [0., 0., 1.]]);
+        // Singular, but for the rounding of 0.1 + 0.2.
+        let rounded = Matrix::from_rows([[0.1 + 0.2, 1.], [0.3, 1.]]);
+        let nan = Matrix::from_rows([[f64::NAN, 0.], [0., 1.]]);
+
+        assert_eq!(
+            small.inverse(),
+            Some(Matrix::from_rows([
+                [50., 0., 0.],
+                [0., 50., 0.],
+                [0., 0., 1.]
+            ]))
+        );
+        assert_eq!(rounded.inverse(), None);
+        assert_eq!(nan.inverse(), None);
+    }
+
+    #[test]
+    fn a_submatrix_drops_a_row_and_a_column() {
+        let a = Matrix::from_rows([[1., 5., 0.], [-3., 2., 7.], [0., 6., -3.]]);
+
+        assert_eq!(a.submatrix(1, 1), Matrix::from_rows([[1., 0.], [0., -3.]]));
+        assert_eq!(a.submatrix(1, 1).submatrix(0, 1).determinant(), 0.);
+    }
+}
@@ -1,5 +1,10 @@
+pub mod angle;
+pub mod matrix;
 pub mod matrix2;
 pub mod matrix3;
 pub mod matrix4;
+pub mod point3;
+pub mod transform;
 pub mod transformations;
 pub mod tuple;
+pub mod vec3;
@@ -0,0 +1,111 @@
+use std::ops::{Add, Sub};
+
+use super::{tuple::Tuple, vec3::Vec3};
+use crate::misc::approx_equal;
+
+/// A position, which unlike a `Tuple` can't be mixed up with a direction:
+/// points and vectors only combine the ways that make sense geometrically.
+///
+/// ```compile_fail
+/// use ray_tracer::math::point3::Point3;
+///
+/// let p = Point3::new(1., 2., 3.);
+/// let _ = p + p;
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Point3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Point3 {
+    pub const fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    pub const fn origin() -> Self {
+        Self::new(0., 0., 0.)
+    }
+
+    /// The largest of `x`, `y` and `z` ignoring their signs, how far out the point is along
+    /// any one axis.
+    pub fn largest_coordinate(self) -> f64 {
+        self.x.abs().max(self.y.abs()).max(self.z.abs())
+    }
+}
+
+impl From<Point3> for Tuple {
+    fn from(point: Point3) -> Self {
+        Tuple::point(point.x, point.y, point.z)
+    }
+}
+
+impl TryFrom<Tuple> for Point3 {
+    /// The tuple, which was a vector or something else.
+    type Error = Tuple;
+
+    fn try_from(tuple: Tuple) -> Result<Self, Self::Error> {
+        if tuple.is_point() {
+            Ok(Self::new(tuple.x, tuple.y, tuple.z))
+        } else {
+            Err(tuple)
+        }
+    }
+}
+
+impl Add<Vec3> for Point3 {
+    type Output = Self;
+
+    fn add(self, vector: Vec3) -> Self {
+        Self::new(self.x + vector.x, self.y + vector.y, self.z + vector.z)
+    }
+}
+
+impl Sub<Vec3> for Point3 {
+    type Output = Self;
+
+    fn sub(self, vector: Vec3) -> Self {
+        self + -vector
+    }
+}
+
+impl Sub for Point3 {
+    type Output = Vec3;
+
+    fn sub(self, other: Self) -> Vec3 {
+        Vec3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl PartialEq for Point3 {
+    fn eq(&self, other: &Self) -> bool {
+        approx_equal(self.x, other.x)
+            && approx_equal(self.y, other.y)
+            && approx_equal(self.z, other.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subtracting_two_points_gives_the_vector_between_them() {
+        let p1 = Point3::new(3., 2., 1.);
+        let p2 = Point3::new(5., 6., 7.);
+
+        assert_eq!(p1 - p2, Vec3::new(-2., -4., -6.));
+        assert_eq!(p2 + (p1 - p2), p1);
+        assert_eq!(p1 - Vec3::new(5., 6., 7.), Point3::new(-2., -4., -6.));
+    }
+
+    #[test]
+    fn points_convert_to_and_from_tuples() {
+        let p = Point3::new(4., -4., 3.);
+
+        assert_eq!(Tuple::from(p), Tuple::point(4., -4., 3.));
+        assert_eq!(Point3::try_from(Tuple::point(4., -4., 3.)), Ok(p));
+        assert!(Point3::try_from(Tuple::vector(4., -4., 3.)).is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use std::ops::Mul;
+
+use super::matrix4::Matrix4;
+
+/// A transformation matrix along with its inverse, inverted once when it's made instead
+/// of every time a ray or a normal goes through it. Composing two of them multiplies
+/// their inverses the other way around, which is much cheaper than inverting again.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+}
+
+impl Transform {
+    /// `None` if the matrix can't be inverted.
+    pub fn new(matrix: Matrix4) -> Option<Self> {
+        let inverse = matrix.inverse()?;
+
+        Some(Self { matrix, inverse })
+    }
+
+    pub fn identity() -> Self {
+        Self {
+            matrix: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+        }
+    }
+
+    pub fn translation(x: f64, y: f64, z: f64) -> Self {
+        Self {
+            matrix: Matrix4::translation(x, y, z),
+            inverse: Matrix4::translation(-x, -y, -z),
+        }
+    }
+
+    /// Factors of 0 can't be undone, and leave infinities in the inverse.
+    pub fn scaling(x: f64, y: f64, z: f64) -> Self {
+        Self {
+            matrix: Matrix4::scaling(x, y, z),
+            inverse: Matrix4::scaling(1. / x, 1. / y, 1. / z),
+        }
+    }
+
+    pub fn matrix(self) -> Matrix4 {
+        self.matrix
+    }
+
+    pub fn inverse(self) -> Matrix4 {
+        self.inverse
+    }
+
+    /// What takes normals through the transform.
+    pub fn inverse_transpose(self) -> Matrix4 {
+        self.inverse.transpose()
+    }
+}
+
+impl Mul for Transform {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            matrix: self.matrix * rhs.matrix,
+            inverse: rhs.inverse * self.inverse,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn composed_transforms_keep_their_inverse() {
+        let rotation = Transform::new(Matrix4::rotation_y(0.5)).unwrap();
+        let scaling = Transform::scaling(2., 3., 4.);
+
+        let composed = rotation * scaling;
+
+        assert_eq!(
+            composed.matrix(),
+            Matrix4::rotation_y(0.5) * Matrix4::scaling(2., 3., 4.)
+        );
+        assert_eq!(composed.inverse(), composed.matrix().inverse().unwrap());
+        assert_eq!(
+            Transform::translation(1., 2., 3.).inverse(),
+            Matrix4::translation(1., 2., 3.).inverse().unwrap()
+        );
+        assert!(Transform::new(Matrix4::scaling(1., 0., 1.)).is_none());
+    }
+}
@@ -10,14 +10,14 @@ pub struct Tuple {
 }
 
 impl Tuple {
-    pub fn new(x: f64, y: f64, z: f64, w: f64) -> Self {
+    pub const fn new(x: f64, y: f64, z: f64, w: f64) -> Self {
         Self { x, y, z, w }
     }
 
-    pub fn point(x: f64, y: f64, z: f64) -> Self {
+    pub const fn point(x: f64, y: f64, z: f64) -> Self {
         Self::new(x, y, z, 1.0)
     }
-    pub fn vector(x: f64, y: f64, z: f64) -> Self {
+    pub const fn vector(x: f64, y: f64, z: f64) -> Self {
         Self::new(x, y, z, 0.0)
     }
 
@@ -31,7 +31,7 @@ impl Tuple {
 
     pub fn magnitude(self) -> f64 {
         let Self { x, y, z, .. } = self;
-        assert!(self.is_vector());
+        debug_assert!(self.is_vector());
 
         (x.powi(2) + y.powi(2) + z.powi(2)).sqrt()
     }
@@ -49,11 +49,10 @@ impl Tuple {
     }
 
     pub fn cross(self, other: Self) -> Self {
-        // It's basically a bug if this is not the case.
-        // Adding these is probably stupid,
-        // I just don't trust myself not to make this error, for now.
-        assert!(self.is_vector());
-        assert!(other.is_vector());
+        // Only checked in debug builds: rays, normals and lighting use `Vec3`, where the
+        // compiler rules this out, and what's left on tuples is mesh geometry.
+        debug_assert!(self.is_vector());
+        debug_assert!(other.is_vector());
 
         Self::vector(
             self.y * other.z - self.z * other.y,
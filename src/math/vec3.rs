@@ -0,0 +1,159 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use super::tuple::Tuple;
+use crate::misc::approx_equal;
+
+/// A direction or displacement, the vector counterpart of `Point3`.
+/// Everything that only makes sense for vectors, like `cross`, is only defined here.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Vec3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Vec3 {
+    pub const fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn magnitude(self) -> f64 {
+        self.magnitude_squared().sqrt()
+    }
+
+    pub fn magnitude_squared(self) -> f64 {
+        self.dot(self)
+    }
+
+    pub fn normalize(self) -> Self {
+        self / self.magnitude()
+    }
+
+    pub fn dot(self, other: Self) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(self, other: Self) -> Self {
+        Self::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn reflect(self, normal: Self) -> Self {
+        self - normal * 2. * self.dot(normal)
+    }
+}
+
+impl From<Vec3> for Tuple {
+    fn from(vector: Vec3) -> Self {
+        Tuple::vector(vector.x, vector.y, vector.z)
+    }
+}
+
+impl TryFrom<Tuple> for Vec3 {
+    /// The tuple, which was a point or something else.
+    type Error = Tuple;
+
+    fn try_from(tuple: Tuple) -> Result<Self, Self::Error> {
+        if tuple.is_vector() {
+            Ok(Self::new(tuple.x, tuple.y, tuple.z))
+        } else {
+            Err(tuple)
+        }
+    }
+}
+
+impl Add for Vec3 {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl Neg for Vec3 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl Mul<f64> for Vec3 {
+    type Output = Self;
+
+    fn mul(self, scalar: f64) -> Self {
+        Self::new(self.x * scalar, self.y * scalar, self.z * scalar)
+    }
+}
+
+impl Mul<Vec3> for f64 {
+    type Output = Vec3;
+
+    fn mul(self, vector: Vec3) -> Vec3 {
+        vector * self
+    }
+}
+
+impl Div<f64> for Vec3 {
+    type Output = Self;
+
+    fn div(self, scalar: f64) -> Self {
+        self * (1. / scalar)
+    }
+}
+
+impl PartialEq for Vec3 {
+    fn eq(&self, other: &Self) -> bool {
+        approx_equal(self.x, other.x)
+            && approx_equal(self.y, other.y)
+            && approx_equal(self.z, other.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{matrix4::Matrix4, point3::Point3};
+    use std::f64::consts::PI;
+
+    #[test]
+    fn vectors_do_what_tuple_vectors_do() {
+        let a = Vec3::new(1., 2., 3.);
+        let b = Vec3::new(2., 3., 4.);
+
+        assert_eq!(a.cross(b), Vec3::new(-1., 2., -1.));
+        assert!(approx_equal(a.dot(b), 20.));
+        assert!(approx_equal(a.normalize().magnitude(), 1.));
+        assert_eq!(
+            Tuple::from(a.cross(b)),
+            Tuple::from(a).cross(Tuple::from(b))
+        );
+        assert_eq!(
+            Vec3::new(1., -1., 0.).reflect(Vec3::new(0., 1., 0.)),
+            Vec3::new(1., 1., 0.)
+        );
+    }
+
+    #[test]
+    fn transforms_move_points_but_not_vectors() {
+        let transform = Matrix4::translation(5., -3., 2.) * Matrix4::rotation_z(PI / 2.);
+
+        assert_eq!(
+            transform * Point3::new(1., 0., 0.),
+            Point3::new(5., -2., 2.)
+        );
+        assert_eq!(transform * Vec3::new(1., 0., 0.), Vec3::new(0., 1., 0.));
+        assert!(Vec3::try_from(Tuple::point(1., 0., 0.)).is_err());
+    }
+}
@@ -0,0 +1,604 @@
+//! Turning objects into triangles, to export them to other tools, see `Object::tessellate`.
+
+use std::{collections::HashMap, f64::consts::PI};
+
+use crate::{
+    math::{matrix4::Matrix4, point3::Point3, tuple::Tuple, vec3::Vec3},
+    misc::EPSILON,
+    ray::Ray,
+    sampler::Rng,
+    shape::{
+        cone::Cone,
+        csg::{Csg, CsgOp},
+        cylinder::Cylinder,
+        triangle::Triangle,
+        Object, Shape, ShapeOrGroup,
+    },
+};
+
+/// Triangles approximating the surface of an object, wound the way `Triangle` expects
+/// for their normals to point out of it, like OBJ files of closed meshes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+    triangles: Vec<Triangle>,
+}
+
+impl Mesh {
+    /// The mesh of `triangles`, which have to be wound with their normals pointing out of it
+    /// for OBJ and STL files to be right.
+    pub fn new(triangles: Vec<Triangle>) -> Self {
+        Self { triangles }
+    }
+
+    /// The corners of every triangle.
+    pub fn triangles(&self) -> impl Iterator<Item = [Tuple; 3]> + '_ {
+        self.triangles
+            .iter()
+            .map(|triangle| [triangle.p1, triangle.p2, triangle.p3])
+    }
+
+    pub fn len(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    /// A group of the triangles, to render the mesh like any other object.
+    pub fn to_group(&self) -> Object {
+        Object::group(
+            self.triangles
+                .iter()
+                .map(|&triangle| Object::primitive(triangle))
+                .collect(),
+        )
+    }
+
+    /// An OBJ file of the mesh, with corners shared by triangles written once.
+    pub fn to_obj(&self) -> String {
+        self.obj_lines(1).0
+    }
+
+    /// The vertices and faces of the mesh in an OBJ file, numbering vertices from
+    /// `first_vertex` on, and how many vertices there are.
+    pub(crate) fn obj_lines(&self, first_vertex: usize) -> (String, usize) {
+        let mut vertices = String::new();
+        let mut faces = String::new();
+        let mut indices: HashMap<[u64; 3], usize> = HashMap::new();
+
+        for [p1, p2, p3] in self.triangles() {
+            let [i1, i2, i3] = [p1, p2, p3].map(|point| {
+                let key = [point.x, point.y, point.z].map(f64::to_bits);
+                let next = first_vertex + indices.len();
+
+                *indices.entry(key).or_insert_with(|| {
+                    vertices += &format!("v {} {} {}\n", point.x, point.y, point.z);
+                    next
+                })
+            });
+            faces += &format!("f {} {} {}\n", i1, i2, i3);
+        }
+
+        (vertices + &faces, indices.len())
+    }
+
+    /// An ASCII STL file of the mesh, as the solid called `name`.
+    pub fn to_stl(&self, name: &str) -> String {
+        let mut stl = format!("solid {}\n", name);
+
+        for triangle in &self.triangles {
+            let normal = triangle.normal();
+            stl += &format!(
+                "  facet normal {} {} {}\n    outer loop\n",
+                normal.x, normal.y, normal.z
+            );
+            for point in [triangle.p1, triangle.p2, triangle.p3] {
+                stl += &format!("      vertex {} {} {}\n", point.x, point.y, point.z);
+            }
+            stl += "    endloop\n  endfacet\n";
+        }
+
+        stl + &format!("endsolid {}\n", name)
+    }
+
+    pub(crate) fn transformed(self, transform: Matrix4) -> Self {
+        let triangles = self
+            .triangles
+            .into_iter()
+            .map(|Triangle { p1, p2, p3, .. }| {
+                let triangle = Triangle::new(transform * p1, transform * p2, transform * p3);
+
+                // Mirroring transforms turn the windings inside out.
+                if transform.determinant() < 0. {
+                    triangle.flipped()
+                } else {
+                    triangle
+                }
+            })
+            .collect();
+
+        Self { triangles }
+    }
+
+    /// The total area of the triangles.
+    pub fn area(&self) -> f64 {
+        self.triangles.iter().map(Triangle::area).sum()
+    }
+
+    /// `count` points spread uniformly over the surface of the mesh, with the normals of
+    /// the triangles they're on. Empty if the mesh has no area.
+    pub fn sample_surface(&self, count: usize, rng: &mut Rng) -> Vec<(Point3, Vec3)> {
+        let mut total = 0.;
+        let running_areas: Vec<f64> = self
+            .triangles
+            .iter()
+            .map(|triangle| {
+                total += triangle.area();
+                total
+            })
+            .collect();
+        if total <= 0. {
+            return vec![];
+        }
+
+        (0..count)
+            .map(|_| {
+                // Bigger triangles are picked more often, so every bit of area is as likely.
+                let target = rng.next_f64() * total;
+                let index = running_areas
+                    .partition_point(|&area| area <= target)
+                    .min(self.triangles.len() - 1);
+
+                self.triangles[index].sample_surface(rng)
+            })
+            .collect()
+    }
+
+    pub(crate) fn extend(&mut self, other: Mesh) {
+        self.triangles.extend(other.triangles);
+    }
+}
+
+/// The mesh of `object`, in the space `Object::bounding_box` is in.
+pub(crate) fn tessellate(object: &Object, resolution: usize) -> Mesh {
+    let resolution = resolution.max(3);
+    let local = match object.shape() {
+        ShapeOrGroup::Group(children) => {
+            let mut mesh = Mesh::default();
+            for child in children {
+                mesh.extend(tessellate(child, resolution));
+            }
+
+            mesh
+        }
+        ShapeOrGroup::Shape { shape, .. } => match shape {
+            Shape::Primitive(primitive) => primitive.tessellate(resolution),
+            Shape::Csg(csg) => csg_mesh(csg, resolution),
+            Shape::Lod(lod) => tessellate(&lod.levels[0].1, resolution),
+            Shape::PointCloud(cloud) => {
+                let mut mesh = Mesh::default();
+                for point in cloud.points() {
+                    mesh.extend(sphere(resolution).transformed(point.transform().matrix()));
+                }
+
+                mesh
+            }
+        },
+    };
+
+    local.transformed(object.transform())
+}
+
+/// A triangle facing `outward`, however its corners are given.
+fn facing(p1: Tuple, p2: Tuple, p3: Tuple, outward: Tuple) -> Option<Triangle> {
+    let triangle = Triangle::new(p1, p2, p3);
+    let normal = (p3 - p1).cross(p2 - p1);
+
+    if normal.magnitude() < EPSILON * EPSILON {
+        // Corners meeting at a pole or an apex.
+        None
+    } else if normal.dot(outward) < 0. {
+        Some(triangle.flipped())
+    } else {
+        Some(triangle)
+    }
+}
+
+/// Two triangles covering the quad going around `p1`, `p2`, `p3`, `p4`.
+fn quad(corners: [Tuple; 4], outward: Tuple) -> impl Iterator<Item = Triangle> {
+    let [p1, p2, p3, p4] = corners;
+
+    facing(p1, p2, p3, outward)
+        .into_iter()
+        .chain(facing(p1, p3, p4, outward))
+}
+
+fn around(resolution: usize) -> impl Iterator<Item = (f64, f64)> {
+    (0..resolution).map(move |step| {
+        let angle = |step: usize| 2. * PI * step as f64 / resolution as f64;
+
+        (angle(step), angle(step + 1))
+    })
+}
+
+pub(crate) fn sphere(resolution: usize) -> Mesh {
+    let stacks = (resolution / 2).max(2);
+    let at = |polar: f64, azimuth: f64| {
+        Tuple::point(
+            polar.sin() * azimuth.cos(),
+            polar.cos(),
+            polar.sin() * azimuth.sin(),
+        )
+    };
+    let mut triangles = vec![];
+
+    for stack in 0..stacks {
+        let top = PI * stack as f64 / stacks as f64;
+        let bottom = PI * (stack + 1) as f64 / stacks as f64;
+
+        for (start, end) in around(resolution) {
+            let corners = [
+                at(top, start),
+                at(bottom, start),
+                at(bottom, end),
+                at(top, end),
+            ];
+            let outward = at((top + bottom) / 2., (start + end) / 2.) - Tuple::point(0., 0., 0.);
+
+            triangles.extend(quad(corners, outward));
+        }
+    }
+
+    Mesh { triangles }
+}
+
+/// A fan of triangles around the centre, with `resolution` of them.
+pub(crate) fn disc(radius: f64, resolution: usize) -> Mesh {
+    let at = |angle: f64| Tuple::point(radius * angle.cos(), 0., radius * angle.sin());
+    let triangles = around(resolution)
+        .filter_map(|(start, end)| {
+            facing(
+                Tuple::point(0., 0., 0.),
+                at(start),
+                at(end),
+                Tuple::vector(0., 1., 0.),
+            )
+        })
+        .collect();
+
+    Mesh { triangles }
+}
+
+/// Two triangles, `width` along x and `depth` along z.
+pub(crate) fn quad_mesh(width: f64, depth: f64) -> Mesh {
+    let (x, z) = (width / 2., depth / 2.);
+    let corners = [
+        Tuple::point(-x, 0., -z),
+        Tuple::point(x, 0., -z),
+        Tuple::point(x, 0., z),
+        Tuple::point(-x, 0., z),
+    ];
+
+    Mesh {
+        triangles: quad(corners, Tuple::vector(0., 1., 0.)).collect(),
+    }
+}
+
+/// Every face split in a grid of a quarter of `resolution` squares a side,
+/// so they come out about as fine as spheres do.
+pub(crate) fn cube(resolution: usize) -> Mesh {
+    let cells = (resolution / 4).max(1);
+    let step = 2. / cells as f64;
+    let mut triangles = vec![];
+
+    for axis in 0..3 {
+        for side in [-1., 1.] {
+            let point = |u: f64, v: f64| {
+                let mut coordinates = [0.; 3];
+                coordinates[axis] = side;
+                coordinates[(axis + 1) % 3] = u;
+                coordinates[(axis + 2) % 3] = v;
+
+                Tuple::point(coordinates[0], coordinates[1], coordinates[2])
+            };
+            let outward = point(0., 0.) - Tuple::point(0., 0., 0.);
+
+            for i in 0..cells {
+                for j in 0..cells {
+                    let (u, v) = (-1. + i as f64 * step, -1. + j as f64 * step);
+                    let corners = [
+                        point(u, v),
+                        point(u + step, v),
+                        point(u + step, v + step),
+                        point(u, v + step),
+                    ];
+
+                    triangles.extend(quad(corners, outward));
+                }
+            }
+        }
+    }
+
+    Mesh { triangles }
+}
+
+/// Sides going around from `bottom` to `top`, each a circle of the radius at its height
+/// stretched by `radii` along x and z, with caps on the ends `caps` say. Infinite ones are left out.
+fn lathe(
+    bottom: f64,
+    top: f64,
+    caps: (bool, bool),
+    resolution: usize,
+    (radius_x, radius_z): (f64, f64),
+    radius: impl Fn(f64) -> f64,
+    outward: impl Fn(f64, Tuple) -> Tuple,
+) -> Mesh {
+    if !bottom.is_finite() || !top.is_finite() {
+        return Mesh::default();
+    }
+    let at = |angle: f64, y: f64| {
+        let radius = radius(y);
+
+        Tuple::point(
+            radius * radius_x * angle.cos(),
+            y,
+            radius * radius_z * angle.sin(),
+        )
+    };
+    let mut triangles = vec![];
+
+    for (start, end) in around(resolution) {
+        let corners = [
+            at(start, bottom),
+            at(end, bottom),
+            at(end, top),
+            at(start, top),
+        ];
+        let middle = (start + end) / 2.;
+        let direction = Tuple::vector(middle.cos() / radius_x, 0., middle.sin() / radius_z);
+
+        triangles.extend(quad(corners, outward((bottom + top) / 2., direction)));
+
+        for (y, up, capped) in [(bottom, -1., caps.0), (top, 1., caps.1)] {
+            if capped {
+                let center = Tuple::point(0., y, 0.);
+                triangles.extend(facing(
+                    center,
+                    at(start, y),
+                    at(end, y),
+                    Tuple::vector(0., up, 0.),
+                ));
+            }
+        }
+    }
+
+    Mesh { triangles }
+}
+
+pub(crate) fn cylinder_mesh(cylinder: Cylinder, resolution: usize) -> Mesh {
+    lathe(
+        cylinder.minimum(),
+        cylinder.maximum(),
+        (cylinder.closed(), cylinder.closed()),
+        resolution,
+        (cylinder.radius_x(), cylinder.radius_z()),
+        |_| 1.,
+        |_, direction| direction,
+    )
+}
+
+/// Cones going through their apex are split there, into the two halves around it.
+pub(crate) fn cone_mesh(cone: Cone, resolution: usize) -> Mesh {
+    let (minimum, maximum, closed) = (cone.minimum(), cone.maximum(), cone.closed());
+    let half = |bottom: f64, top: f64, caps: (bool, bool)| {
+        let radii = (cone.radius_x(), cone.radius_z());
+        lathe(
+            bottom,
+            top,
+            caps,
+            resolution,
+            radii,
+            f64::abs,
+            |y, direction| direction - Tuple::vector(0., y.signum(), 0.),
+        )
+    };
+
+    if minimum < 0. && 0. < maximum {
+        let mut mesh = half(minimum, 0., (closed, false));
+        mesh.extend(half(0., maximum, (false, closed)));
+
+        mesh
+    } else {
+        half(minimum, maximum, (closed, closed))
+    }
+}
+
+/// The surfaces of the operands the CSG keeps, judged by the middle of each triangle,
+/// so cuts are as jagged as the triangles are coarse.
+fn csg_mesh(csg: &Csg, resolution: usize) -> Mesh {
+    let (mut left, right) = csg_parts(csg, resolution);
+    left.extend(right);
+
+    left
+}
+
+/// The triangles `csg_mesh` keeps from each operand, in the space of the CSG.
+pub(crate) fn csg_parts(csg: &Csg, resolution: usize) -> (Mesh, Mesh) {
+    let left = tessellate(&csg.left, resolution);
+    let right = tessellate(&csg.right, resolution);
+    let keep = |mesh: Mesh, other: &Object, inside: bool, flip: bool| {
+        mesh.triangles
+            .into_iter()
+            .filter(|triangle| contains(other, centroid(triangle)) == inside)
+            .map(|triangle| if flip { triangle.flipped() } else { triangle })
+            .collect::<Vec<_>>()
+    };
+
+    let (left, right) = match csg.op {
+        CsgOp::Union => (
+            keep(left, &csg.right, false, false),
+            keep(right, &csg.left, false, false),
+        ),
+        CsgOp::Intersection => (
+            keep(left, &csg.right, true, false),
+            keep(right, &csg.left, true, false),
+        ),
+        // What's cut out of the left operand is lined with the inside of the right one.
+        CsgOp::Difference => (
+            keep(left, &csg.right, false, false),
+            keep(right, &csg.left, true, true),
+        ),
+    };
+
+    (Mesh { triangles: left }, Mesh { triangles: right })
+}
+
+fn centroid(triangle: &Triangle) -> Tuple {
+    let sum = triangle.p1 + (triangle.p2 - Tuple::point(0., 0., 0.)) + triangle.p3;
+
+    Tuple::point(sum.x / 3., sum.y / 3., sum.z / 3.)
+}
+
+/// Whether the point is inside the object, going by how many of its surfaces a ray leaving
+/// the point crosses. The ray goes out askew, so it doesn't slip along edges of boxes.
+fn contains(object: &Object, point: Tuple) -> bool {
+    let ray = Ray::new(
+        Point3::new(point.x, point.y, point.z),
+        Vec3::new(0.5377, 0.4121, 0.7357).normalize(),
+    );
+    let crossings = object
+        .intersect(ray)
+        .iter()
+        .filter(|intersection| intersection.t > EPSILON)
+        .count();
+
+    crossings % 2 == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{misc::approx_equal, shape::cylinder::Cylinder};
+
+    fn faces_outward(mesh: &Mesh, center: Tuple) -> bool {
+        mesh.triangles
+            .iter()
+            .all(|triangle| Tuple::from(triangle.normal()).dot(centroid(triangle) - center) > 0.)
+    }
+
+    #[test]
+    fn tessellating_a_sphere() {
+        let mut object = Object::sphere();
+        object.set_transform(Matrix4::translation(0., 2., 0.));
+
+        let mesh = object.tessellate(8);
+
+        // 8 slices of 4 stacks, the ones touching a pole being single triangles.
+        assert_eq!(mesh.len(), 8 * 2 * 2 + 8 * 2);
+        for [p1, p2, p3] in mesh.triangles() {
+            for point in [p1, p2, p3] {
+                let distance = (point - Tuple::point(0., 2., 0.)).magnitude();
+                assert!((distance - 1.).abs() < EPSILON);
+            }
+        }
+        assert!(faces_outward(&mesh, Tuple::point(0., 2., 0.)));
+    }
+
+    #[test]
+    fn tessellating_cubes_cylinders_and_cones() {
+        assert_eq!(Object::cube().tessellate(8).len(), 6 * 2 * 2 * 2);
+        assert!(faces_outward(
+            &Object::cube().tessellate(8),
+            Tuple::point(0., 0., 0.)
+        ));
+
+        let cylinder = Object::primitive(Cylinder::truncated(-1., 1., true).unwrap());
+        let mesh = cylinder.tessellate(8);
+        assert_eq!(mesh.len(), 8 * 2 + 8 * 2);
+        assert!(faces_outward(&mesh, Tuple::point(0., 0., 0.)));
+
+        let cone = Object::primitive(Cone::truncated(-1., 1., true).unwrap());
+        let mesh = cone.tessellate(8);
+        // The sides come to a point at the apex.
+        assert_eq!(mesh.len(), 8 * 2 + 8 * 2);
+
+        // Infinite shapes can't be turned into triangles.
+        assert!(Object::plane().tessellate(8).is_empty());
+        assert!(Object::cylinder().tessellate(8).is_empty());
+    }
+
+    #[test]
+    fn tessellating_a_difference_keeps_the_cut() {
+        let mut hole = Object::sphere();
+        hole.set_transform(Matrix4::translation(1., 1., 1.));
+        let object = Object::difference(Object::cube(), hole);
+
+        let mesh = object.tessellate(16);
+        let cube = Object::cube().tessellate(16);
+
+        // Squares of the cube at the corner went away, and part of the sphere lines the hole.
+        let on_side = |mesh: &Mesh| mesh.triangles().filter(|[p1, ..]| p1.x == 1.).count();
+        assert!(on_side(&mesh) < on_side(&cube));
+        assert!(mesh
+            .triangles()
+            .any(|[p1, ..]| (p1 - Tuple::point(1., 1., 1.)).magnitude() < 1. + EPSILON));
+        assert!(faces_outward(
+            &Object::union(Object::cube(), Object::sphere()).tessellate(16),
+            Tuple::point(0., 0., 0.)
+        ));
+    }
+
+    #[test]
+    fn exporting_a_mesh() {
+        let mesh = Mesh {
+            triangles: vec![
+                Triangle::new(
+                    Tuple::point(0., 0., 0.),
+                    Tuple::point(0., 1., 0.),
+                    Tuple::point(1., 0., 0.),
+                ),
+                Triangle::new(
+                    Tuple::point(0., 1., 0.),
+                    Tuple::point(1., 1., 0.),
+                    Tuple::point(1., 0., 0.),
+                ),
+            ],
+        };
+
+        assert_eq!(
+            mesh.to_obj(),
+            "v 0 0 0\nv 0 1 0\nv 1 0 0\nv 1 1 0\nf 1 2 3\nf 2 4 3\n"
+        );
+        let stl = mesh.to_stl("square");
+        assert!(stl.starts_with("solid square\n  facet normal 0 0 1\n    outer loop\n"));
+        assert!(stl.contains("      vertex 1 1 0\n"));
+        assert!(stl.ends_with("endfacet\nendsolid square\n"));
+        assert_eq!(stl.matches("facet normal").count(), 2);
+    }
+
+    #[test]
+    fn sampling_the_surface_of_a_mesh_spreads_points_by_area() {
+        let mut cube = Object::cube();
+        cube.set_transform(Matrix4::scaling(2., 1., 1.));
+        let mesh = cube.tessellate(4);
+        let samples = mesh.sample_surface(6000, &mut Rng::new(3));
+
+        assert!(approx_equal(mesh.area(), 40.));
+        assert_eq!(samples.len(), 6000);
+        // The ends are half as big as the top and bottom, and as the sides.
+        let on = |normal: Vec3| samples.iter().filter(|(_, n)| *n == normal).count();
+        let ends = on(Vec3::new(1., 0., 0.)) + on(Vec3::new(-1., 0., 0.));
+        let top_and_bottom = on(Vec3::new(0., 1., 0.)) + on(Vec3::new(0., -1., 0.));
+        assert!((ends as f64 / 6000. - 0.2).abs() < 0.03);
+        assert!((top_and_bottom as f64 / 6000. - 0.4).abs() < 0.03);
+        for (point, normal) in samples {
+            let on_face = point.x * normal.x / 2. + point.y * normal.y + point.z * normal.z;
+            assert!(approx_equal(on_face, 1.));
+        }
+
+        assert!(Mesh::default()
+            .sample_surface(3, &mut Rng::new(1))
+            .is_empty());
+    }
+}
@@ -1,15 +1,48 @@
 use std::cmp::Ordering;
 
+/// How far apart numbers around 1 can be and still be taken as the same, and how far points
+/// are stepped off of surfaces near the origin.
 pub const EPSILON: f64 = 1e-8;
 
+/// How far apart numbers can be as a fraction of how large they are, for numbers large
+/// enough that rounding errors alone take them further apart than `EPSILON`.
+pub const RELATIVE_EPSILON: f64 = 1e-10;
+
 /// Compare floats with a hardcoded precision of
 /// 5 significant digits.
 pub fn approx_equal(a: f64, b: f64) -> bool {
     let dp = 5;
-    let p = 10f64.powi(-(dp as i32));
+    let p = 10f64.powi(-dp);
     (a - b).abs() < p
 }
 
+/// Whether `a` and `b` are within `absolute` of each other, or within `relative` of the
+/// larger of them, so numbers far from 0 aren't told apart by their rounding errors.
+/// Infinities are only equal to themselves.
+pub fn approx_equal_within(a: f64, b: f64, absolute: f64, relative: f64) -> bool {
+    if a == b {
+        return true;
+    }
+    if a.is_infinite() || b.is_infinite() {
+        return false;
+    }
+    let difference = (a - b).abs();
+
+    difference <= absolute || difference <= relative * a.abs().max(b.abs())
+}
+
+/// `approx_equal_within` `EPSILON` or `RELATIVE_EPSILON`, for numbers that should come
+/// out the same but for the rounding errors of working them out, like where a ray hits.
+pub fn nearly_equal(a: f64, b: f64) -> bool {
+    approx_equal_within(a, b, EPSILON, RELATIVE_EPSILON)
+}
+
+/// How far to step off of a surface `scale` units from the origin, to get past the rounding
+/// errors of working out where it is, which grow with how far out it is.
+pub fn epsilon_at(scale: f64) -> f64 {
+    EPSILON.max(scale.abs() * RELATIVE_EPSILON)
+}
+
 /// Weird function only used for computing MatrixN::submatrix
 pub fn cmp_to_offset(ordering_row: Ordering, ordering_col: Ordering) -> Option<(i32, i32)> {
     match (ordering_row, ordering_col) {
@@ -21,3 +54,21 @@ pub fn cmp_to_offset(ordering_row: Ordering, ordering_col: Ordering) -> Option<(
         (Ordering::Less, Ordering::Less) => Some((0, 0)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tolerances_grow_with_the_numbers_compared() {
+        assert!(nearly_equal(1., 1. + EPSILON / 2.));
+        assert!(!nearly_equal(1., 1. + 1e-6));
+        assert!(nearly_equal(1e6, 1e6 + 1e-5));
+        assert!(!nearly_equal(1e6, 1e6 + 1e-3));
+        assert!(nearly_equal(f64::INFINITY, f64::INFINITY));
+        assert!(!nearly_equal(f64::INFINITY, 1e300));
+
+        assert_eq!(epsilon_at(1.), EPSILON);
+        assert!(approx_equal_within(epsilon_at(-1e6), 1e-4, 0., 1e-12));
+    }
+}
@@ -0,0 +1,294 @@
+//! Smooth pseudo-random functions of space, for anything that should look irregular but
+//! stay the same from render to render, like marble, waves or a shaking camera.
+//!
+//! Nothing is stored but the seed: lattice values are hashed from their coordinates,
+//! so noise is cheap to copy around in patterns and materials.
+
+use crate::math::tuple::Tuple;
+
+/// How the values between lattice points are made up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoiseBasis {
+    /// Random slopes at every lattice point, which makes for rounded blobs with no
+    /// visible grid. Always 0 at lattice points.
+    Perlin,
+    /// Random values at every lattice point smoothly blended, blockier than Perlin noise.
+    Value,
+}
+
+/// Noise with features about a unit apart, between -1 and 1.
+/// The same seed always gives the same noise, different seeds unrelated ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Noise {
+    pub basis: NoiseBasis,
+    pub seed: u64,
+}
+
+impl Noise {
+    pub const fn perlin(seed: u64) -> Self {
+        Self {
+            basis: NoiseBasis::Perlin,
+            seed,
+        }
+    }
+
+    pub const fn value(seed: u64) -> Self {
+        Self {
+            basis: NoiseBasis::Value,
+            seed,
+        }
+    }
+
+    /// The noise at `point`, between -1 and 1. Use a single coordinate for noise over time.
+    pub fn at(self, point: Tuple) -> f64 {
+        let cell = [point.x, point.y, point.z].map(f64::floor);
+        let offset = [point.x - cell[0], point.y - cell[1], point.z - cell[2]];
+        let cell = cell.map(|coordinate| coordinate as i64);
+        let [u, v, w] = offset.map(fade);
+
+        let corner = |dx: i64, dy: i64, dz: i64| {
+            let hash = hash(self.seed, cell[0] + dx, cell[1] + dy, cell[2] + dz);
+
+            match self.basis {
+                NoiseBasis::Perlin => gradient(
+                    hash,
+                    offset[0] - dx as f64,
+                    offset[1] - dy as f64,
+                    offset[2] - dz as f64,
+                ),
+                NoiseBasis::Value => (hash >> 11) as f64 / (1u64 << 52) as f64 - 1.,
+            }
+        };
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, corner(0, 0, 0), corner(1, 0, 0)),
+                lerp(u, corner(0, 1, 0), corner(1, 1, 0)),
+            ),
+            lerp(
+                v,
+                lerp(u, corner(0, 0, 1), corner(1, 0, 1)),
+                lerp(u, corner(0, 1, 1), corner(1, 1, 1)),
+            ),
+        )
+        .clamp(-1., 1.)
+    }
+}
+
+/// Copies of the same noise added up, each `lacunarity` times finer and `gain` times
+/// fainter than the one before, for detail at every scale.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Octaves {
+    pub noise: Noise,
+    pub octaves: u32,
+    pub lacunarity: f64,
+    pub gain: f64,
+}
+
+impl Octaves {
+    /// Each octave twice as fine and half as strong as the one before.
+    pub const fn new(noise: Noise, octaves: u32) -> Self {
+        Self {
+            noise,
+            octaves,
+            lacunarity: 2.,
+            gain: 0.5,
+        }
+    }
+
+    /// Fractional Brownian motion: the octaves as they are, between -1 and 1.
+    pub fn fbm(self, point: Tuple) -> f64 {
+        self.sum(point, |noise| noise)
+    }
+
+    /// The octaves folded over at 0, between 0 and 1, for billowy, cloud-like shapes
+    /// and the veins of marble.
+    pub fn turbulence(self, point: Tuple) -> f64 {
+        self.sum(point, f64::abs)
+    }
+
+    /// Turbulence upside down and sharpened, between 0 and 1, for sharp crests like
+    /// mountain ridges.
+    pub fn ridged(self, point: Tuple) -> f64 {
+        self.sum(point, |noise| (1. - noise.abs()).powi(2))
+    }
+
+    /// The octaves, each shaped by `shape`, weighted so the result stays in the range of `shape`.
+    fn sum(self, point: Tuple, shape: impl Fn(f64) -> f64) -> f64 {
+        let mut total = 0.;
+        let mut total_amplitude = 0.;
+        let mut amplitude = 1.;
+        let mut frequency = 1.;
+
+        for octave in 0..self.octaves.max(1) {
+            // Octaves are seeded apart, or they'd all be 0 at the origin.
+            let noise = Noise {
+                seed: self.noise.seed.wrapping_add(octave as u64),
+                ..self.noise
+            };
+            let scaled = Tuple::point(
+                point.x * frequency,
+                point.y * frequency,
+                point.z * frequency,
+            );
+
+            total += amplitude * shape(noise.at(scaled));
+            total_amplitude += amplitude;
+            amplitude *= self.gain;
+            frequency *= self.lacunarity;
+        }
+
+        total / total_amplitude
+    }
+}
+
+/// Smooths out the blend between lattice points, flat at both ends so the noise has no creases.
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+/// The slope of Perlin noise at a lattice point, one of the 12 directions to the edges of
+/// a cube, dotted with the offset from it.
+fn gradient(hash: u64, x: f64, y: f64, z: f64) -> f64 {
+    match hash >> 60 {
+        0 | 12 => x + y,
+        1 | 13 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x + z,
+        5 => -x + z,
+        6 => x - z,
+        7 => -x - z,
+        8 => y + z,
+        9 | 14 => -y + z,
+        10 => y - z,
+        _ => -y - z,
+    }
+}
+
+/// Mixes the coordinates of a lattice point into bits with no pattern to them,
+/// with the finalizer of SplitMix64.
+fn hash(seed: u64, x: i64, y: i64, z: i64) -> u64 {
+    let mut hash = seed
+        ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+        ^ (z as u64).wrapping_mul(0x1656_67B1_9E37_79F9);
+
+    hash ^= hash >> 30;
+    hash = hash.wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    hash ^= hash >> 27;
+    hash = hash.wrapping_mul(0x94D0_49BB_1331_11EB);
+
+    hash ^ (hash >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::Rng;
+
+    /// Points spread over a big cube, away from the lattice.
+    fn points(count: usize) -> Vec<Tuple> {
+        let mut rng = Rng::new(7);
+
+        (0..count)
+            .map(|_| {
+                Tuple::point(
+                    rng.range(-50., 50.),
+                    rng.range(-50., 50.),
+                    rng.range(-50., 50.),
+                )
+            })
+            .collect()
+    }
+
+    fn mean(values: impl Iterator<Item = f64>) -> f64 {
+        let values: Vec<f64> = values.collect();
+
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+
+    #[test]
+    fn noise_is_the_same_for_the_same_seed_only() {
+        let point = Tuple::point(1.3, -2.7, 0.4);
+
+        for noise in [Noise::perlin(3), Noise::value(3)] {
+            assert_eq!(noise.at(point), noise.at(point));
+            let other = Noise { seed: 4, ..noise };
+            assert_ne!(noise.at(point), other.at(point));
+        }
+    }
+
+    #[test]
+    fn perlin_noise_is_zero_at_lattice_points() {
+        let noise = Noise::perlin(1);
+
+        assert_eq!(noise.at(Tuple::point(0., 0., 0.)), 0.);
+        assert_eq!(noise.at(Tuple::point(3., -2., 7.)), 0.);
+        assert_ne!(noise.at(Tuple::point(3.3, -2.6, 7.2)), 0.);
+    }
+
+    #[test]
+    fn noise_averages_out_to_zero_within_its_range() {
+        let points = points(20_000);
+
+        for noise in [Noise::perlin(11), Noise::value(11)] {
+            let values: Vec<f64> = points.iter().map(|point| noise.at(*point)).collect();
+
+            assert!(values.iter().all(|value| (-1. ..=1.).contains(value)));
+            assert!(mean(values.iter().copied()).abs() < 0.02);
+            // Not stuck near 0 either.
+            assert!(mean(values.iter().map(|value| value.abs())) > 0.1);
+        }
+    }
+
+    #[test]
+    fn noise_is_continuous() {
+        let noise = Noise::perlin(5);
+
+        for point in points(1_000) {
+            let nearby = point + Tuple::vector(1e-4, -1e-4, 1e-4);
+
+            assert!((noise.at(point) - noise.at(nearby)).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn octaves_stay_within_their_ranges() {
+        let octaves = Octaves::new(Noise::perlin(2), 5);
+        let points = points(5_000);
+
+        let fbm: Vec<f64> = points.iter().map(|point| octaves.fbm(*point)).collect();
+        assert!(fbm.iter().all(|value| (-1. ..=1.).contains(value)));
+        assert!(mean(fbm.iter().copied()).abs() < 0.02);
+
+        for value in points.iter().map(|point| octaves.turbulence(*point)) {
+            assert!((0. ..=1.).contains(&value));
+        }
+        let ridged: Vec<f64> = points.iter().map(|point| octaves.ridged(*point)).collect();
+        assert!(ridged.iter().all(|value| (0. ..=1.).contains(value)));
+        // Ridges are where the noise crosses 0, which it mostly stays close to.
+        assert!(mean(ridged.into_iter()) > 0.5);
+    }
+
+    #[test]
+    fn more_octaves_add_finer_detail() {
+        let coarse = Octaves::new(Noise::perlin(9), 1);
+        let fine = Octaves::new(Noise::perlin(9), 6);
+        let roughness = |octaves: Octaves| {
+            mean(points(2_000).into_iter().map(|point| {
+                let nearby = point + Tuple::vector(0.05, 0., 0.);
+
+                (octaves.fbm(point) - octaves.fbm(nearby)).abs()
+            }))
+        };
+
+        assert!(roughness(fine) > roughness(coarse));
+    }
+}
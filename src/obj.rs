@@ -1,110 +1,110 @@
-use std::{
-    collections::{hash_map::DefaultHasher, HashMap},
-    hash::BuildHasherDefault,
-};
+use std::{collections::HashMap, fmt, ops::Index};
 
 use crate::{
+    error::{RayTracerError, Result},
     math::tuple::Tuple,
-    shape::{triangle::Triangle, Object, Shape},
+    shape::{triangle::Triangle, Object},
+    winding,
 };
 
 pub struct WavefrontObj {
-    groups: HashMap<String, Vec<Triangle>, BuildHasherDefault<DefaultHasher>>,
+    pub(crate) groups: Groups,
     #[cfg(test)]
     vertices: Vec<Tuple>,
     #[cfg(test)]
     normals: Vec<Tuple>,
+    #[cfg(test)]
+    textures: Vec<(f64, f64)>,
 }
 
 impl WavefrontObj {
+    /// The names of the groups, in the order they first show up in the file,
+    /// starting with `default` for faces before any `g` line.
+    pub fn group_names(&self) -> impl Iterator<Item = &str> {
+        self.groups.groups.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// A group with a child group named after every group of the file, in the order of
+    /// `group_names`, with their triangles in the order their faces were written.
     pub fn to_group(self) -> Object {
         Object::group(
             self.groups
+                .groups
                 .into_iter()
-                .map(|(_, triangles)| {
-                    let triangles = triangles
-                        .into_iter()
-                        .map(|triangle| Object::new(Shape::Triangle(triangle)))
-                        .collect();
+                .map(|(name, triangles)| {
+                    let triangles = triangles.into_iter().map(Object::primitive).collect();
 
-                    Object::group(triangles)
+                    Object::group(triangles).named(name)
                 })
                 .collect(),
         )
     }
 
-    pub fn from_file(file_path: &str) -> std::io::Result<Object> {
+    /// Flips triangles for all of them to be wound the same way as their neighbors,
+    /// groups included, and if `outward` is set, for closed meshes to face out.
+    pub fn orient_faces(&mut self, outward: bool) {
+        let triangles: Vec<Triangle> = self.groups.values().flatten().copied().collect();
+        let flip = winding::triangles_to_flip(&triangles, outward);
+
+        for (triangle, flip) in self.groups.values_mut().flatten().zip(flip) {
+            if flip {
+                *triangle = triangle.flipped();
+            }
+        }
+    }
+
+    pub fn from_file(file_path: &str) -> Result<Object> {
         let file_contents = std::fs::read_to_string(file_path)?;
         let obj = WavefrontObj::from_file_contents(&file_contents)?;
         Ok(obj.to_group())
     }
 
-    pub fn from_file_contents(file_contents: &str) -> std::io::Result<WavefrontObj> {
+    /// Parses an OBJ file, failing on the first line that's wrong.
+    pub fn from_file_contents(file_contents: &str) -> std::result::Result<Self, ObjError> {
+        Self::parse(file_contents, false).map(|(obj, _)| obj)
+    }
+
+    /// Like `from_file_contents`, leaving faces that are wrong out instead of failing,
+    /// and telling what was wrong with them. Broken vertices and normals still fail,
+    /// since skipping them would throw off the indices of every face after them.
+    pub fn from_file_contents_lenient(
+        file_contents: &str,
+    ) -> std::result::Result<(Self, Vec<ObjError>), ObjError> {
+        Self::parse(file_contents, true)
+    }
+
+    fn parse(
+        file_contents: &str,
+        lenient: bool,
+    ) -> std::result::Result<(Self, Vec<ObjError>), ObjError> {
         let mut vertices = vec![];
         let mut normals = vec![];
+        let mut textures = vec![];
+        let mut skipped = vec![];
 
         let mut current_group = "default";
 
-        let map_hasher = BuildHasherDefault::<DefaultHasher>::default();
-        let mut groups: HashMap<String, Vec<Triangle>, _> = HashMap::with_hasher(map_hasher);
+        let mut groups = Groups::default();
+
+        for (index, line) in file_contents.lines().enumerate() {
+            let number = index + 1;
 
-        for line in file_contents.lines() {
             if let Some((node_type, rest)) = line.split_once(" ") {
                 match node_type {
                     "v" => {
-                        let mut rest = rest.split_ascii_whitespace();
-                        let x = rest.next().unwrap().parse::<f64>().unwrap();
-                        let y = rest.next().unwrap().parse::<f64>().unwrap();
-                        let z = rest.next().unwrap().parse::<f64>().unwrap();
-
+                        let (x, y, z) = coordinates(rest, number)?;
                         vertices.push(Tuple::point(x, y, z));
                     }
                     "vn" => {
-                        let mut rest = rest.split_ascii_whitespace();
-                        let x = rest.next().unwrap().parse::<f64>().unwrap();
-                        let y = rest.next().unwrap().parse::<f64>().unwrap();
-                        let z = rest.next().unwrap().parse::<f64>().unwrap();
-
+                        let (x, y, z) = coordinates(rest, number)?;
                         normals.push(Tuple::vector(x, y, z));
                     }
-                    "f" => {
-                        // "1//3 2//4 3//5"
-                        let rest = rest.split_ascii_whitespace();
-                        // ["1//3", "2//4", "3//5"]
-                        let mut indices = rest.map(|attr| {
-                            let mut it = attr.split('/').map(|i| i.parse::<usize>().ok());
-
-                            let vertex = it.next().unwrap().unwrap() - 1;
-                            let texture = it.next().flatten().map(|t| t - 1);
-                            let normal = it.next().flatten().map(|t| t - 1);
-
-                            (vertex, texture, normal)
-                        });
-
-                        let (start_index, _, normal1) = indices.next().unwrap();
-                        for window in indices.collect::<Vec<_>>().windows(2) {
-                            if let [(index2, _, normal2), (index3, _, normal3)] = window {
-                                let entry = groups.entry(current_group.to_owned());
-                                let triangle = match (normal1, normal2, normal3) {
-                                    (Some(n1), Some(n2), Some(n3)) => Triangle::smooth(
-                                        vertices[start_index],
-                                        vertices[*index2],
-                                        vertices[*index3],
-                                        normals[n1],
-                                        normals[*n2],
-                                        normals[*n3],
-                                    ),
-                                    _ => Triangle::new(
-                                        vertices[start_index],
-                                        vertices[*index2],
-                                        vertices[*index3],
-                                    ),
-                                };
-
-                                entry.or_insert(vec![]).push(triangle);
-                            }
-                        }
-                    }
+                    "vt" => textures.push(texture_coordinates(rest, number)?),
+                    "f" => match face(rest, number, &vertices, &normals, &textures) {
+                        Ok(triangles) => groups.entry(current_group).extend(triangles),
+                        Err(error) if lenient => skipped.push(error),
+                        Err(error) => return Err(error),
+                    },
                     "g" => {
                         current_group = rest;
                     }
@@ -113,14 +113,229 @@ impl WavefrontObj {
             }
         }
 
-        Ok(WavefrontObj {
+        let obj = WavefrontObj {
             groups,
             #[cfg(test)]
             vertices,
             #[cfg(test)]
             normals,
+            #[cfg(test)]
+            textures,
+        };
+
+        Ok((obj, skipped))
+    }
+}
+
+/// The triangles of every group, in the order the groups first show up in the file so
+/// loading the same file always gives the same object tree.
+#[derive(Default)]
+pub(crate) struct Groups {
+    groups: Vec<(String, Vec<Triangle>)>,
+    /// Where in `groups` each group is, so faces don't have to look for theirs.
+    indices: HashMap<String, usize>,
+}
+
+impl Groups {
+    /// The triangles of the group named `name`, added at the end if it's new.
+    fn entry(&mut self, name: &str) -> &mut Vec<Triangle> {
+        let index = match self.indices.get(name) {
+            Some(&index) => index,
+            None => {
+                self.indices.insert(name.to_owned(), self.groups.len());
+                self.groups.push((name.to_owned(), vec![]));
+                self.groups.len() - 1
+            }
+        };
+
+        &mut self.groups[index].1
+    }
+
+    pub(crate) fn values(&self) -> impl Iterator<Item = &Vec<Triangle>> {
+        self.groups.iter().map(|(_, triangles)| triangles)
+    }
+
+    fn values_mut(&mut self) -> impl Iterator<Item = &mut Vec<Triangle>> {
+        self.groups.iter_mut().map(|(_, triangles)| triangles)
+    }
+}
+
+impl Index<&str> for Groups {
+    type Output = Vec<Triangle>;
+
+    /// Panics if there's no group named `name`.
+    fn index(&self, name: &str) -> &Vec<Triangle> {
+        match self.indices.get(name) {
+            Some(&index) => &self.groups[index].1,
+            None => panic!("no group named `{}`", name),
+        }
+    }
+}
+
+/// What's wrong with an OBJ file, on which line counting from 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ObjError {
+    /// A vertex or normal without three numbers.
+    InvalidCoordinates { line: usize },
+    /// A face with less than three vertices, or with something other than indices.
+    InvalidFace { line: usize },
+    /// A face referring to a vertex that isn't defined before it.
+    MissingVertex { line: usize, index: isize },
+    /// A face referring to a normal that isn't defined before it.
+    MissingNormal { line: usize, index: isize },
+    /// Texture coordinates without a number for `u`.
+    InvalidTextureCoordinates { line: usize },
+    /// A face referring to texture coordinates that aren't defined before it.
+    MissingTextureCoordinates { line: usize, index: isize },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::InvalidCoordinates { line } => {
+                write!(f, "line {}: expected three coordinates", line)
+            }
+            ObjError::InvalidFace { line } => {
+                write!(f, "line {}: expected three or more vertex indices", line)
+            }
+            ObjError::MissingVertex { line, index } => {
+                write!(f, "line {}: there is no vertex {}", line, index)
+            }
+            ObjError::MissingNormal { line, index } => {
+                write!(f, "line {}: there is no normal {}", line, index)
+            }
+            ObjError::InvalidTextureCoordinates { line } => {
+                write!(
+                    f,
+                    "line {}: expected one to three texture coordinates",
+                    line
+                )
+            }
+            ObjError::MissingTextureCoordinates { line, index } => {
+                write!(
+                    f,
+                    "line {}: there are no texture coordinates {}",
+                    line, index
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+impl From<ObjError> for RayTracerError {
+    fn from(error: ObjError) -> Self {
+        RayTracerError::Parse(format!("invalid obj file: {}", error))
+    }
+}
+
+fn coordinates(rest: &str, line: usize) -> std::result::Result<(f64, f64, f64), ObjError> {
+    let mut numbers = rest.split_ascii_whitespace().map(str::parse::<f64>);
+
+    match (numbers.next(), numbers.next(), numbers.next()) {
+        (Some(Ok(x)), Some(Ok(y)), Some(Ok(z))) => Ok((x, y, z)),
+        _ => Err(ObjError::InvalidCoordinates { line }),
+    }
+}
+
+/// The `u` and `v` of a "vt" line, `v` being 0 if it's left out. A third `w` is ignored.
+fn texture_coordinates(rest: &str, line: usize) -> std::result::Result<(f64, f64), ObjError> {
+    let mut numbers = rest.split_ascii_whitespace().map(str::parse::<f64>);
+
+    match (numbers.next(), numbers.next()) {
+        (Some(Ok(u)), None) => Ok((u, 0.)),
+        (Some(Ok(u)), Some(Ok(v))) => Ok((u, v)),
+        _ => Err(ObjError::InvalidTextureCoordinates { line }),
+    }
+}
+
+/// The triangles of a face like "1/1/3 2/2/4 3/3/5", fanned out from its first vertex,
+/// with texture coordinates if every corner has some.
+fn face(
+    rest: &str,
+    line: usize,
+    vertices: &[Tuple],
+    normals: &[Tuple],
+    textures: &[(f64, f64)],
+) -> std::result::Result<Vec<Triangle>, ObjError> {
+    let corners = rest
+        .split_ascii_whitespace()
+        .map(|attr| {
+            let mut it = attr.split('/');
+            let invalid = ObjError::InvalidFace { line };
+
+            let vertex_index: isize = it.next().unwrap().parse().map_err(|_| invalid.clone())?;
+            let vertex = resolve(vertex_index, vertices).ok_or(ObjError::MissingVertex {
+                line,
+                index: vertex_index,
+            })?;
+            // Files without any texture coordinates sometimes have indices for them anyway.
+            let texture = match it
+                .next()
+                .filter(|texture| !texture.is_empty() && !textures.is_empty())
+            {
+                Some(texture) => {
+                    let texture_index: isize = texture.parse().map_err(|_| invalid.clone())?;
+                    let texture = resolve(texture_index, textures).ok_or(
+                        ObjError::MissingTextureCoordinates {
+                            line,
+                            index: texture_index,
+                        },
+                    )?;
+
+                    Some(texture)
+                }
+                None => None,
+            };
+            let normal = match it.next().filter(|normal| !normal.is_empty()) {
+                Some(normal) => {
+                    let normal_index: isize = normal.parse().map_err(|_| invalid)?;
+                    let normal = resolve(normal_index, normals).ok_or(ObjError::MissingNormal {
+                        line,
+                        index: normal_index,
+                    })?;
+
+                    Some(normal)
+                }
+                None => None,
+            };
+
+            Ok((vertex, normal, texture))
         })
+        .collect::<std::result::Result<Vec<_>, ObjError>>()?;
+
+    if corners.len() < 3 {
+        return Err(ObjError::InvalidFace { line });
     }
+
+    let (p1, n1, t1) = corners[0];
+    Ok(corners[1..]
+        .windows(2)
+        .map(|window| {
+            let [(p2, n2, t2), (p3, n3, t3)] = [window[0], window[1]];
+
+            let triangle = match (n1, n2, n3) {
+                (Some(n1), Some(n2), Some(n3)) => Triangle::smooth(p1, p2, p3, n1, n2, n3),
+                _ => Triangle::new(p1, p2, p3),
+            };
+            match (t1, t2, t3) {
+                (Some(t1), Some(t2), Some(t3)) => triangle.with_texture([t1, t2, t3]),
+                _ => triangle,
+            }
+        })
+        .collect())
+}
+
+/// Indices count from 1, or back from the last one defined so far when negative.
+fn resolve<T: Copy>(index: isize, defined: &[T]) -> Option<T> {
+    let position = if index < 0 {
+        defined.len().checked_sub(index.unsigned_abs())?
+    } else {
+        (index as usize).checked_sub(1)?
+    };
+
+    defined.get(position).copied()
 }
 
 #[cfg(test)]
@@ -243,16 +458,45 @@ f 1 3 4
             panic!("Didn't get a group back from obj file!")
         };
 
-        // The order of the triangles in this test is a bit arbitrary
-        // because of iteration order in a HashMap
+        assert_eq!(
+            group_objects[0],
+            Object::group(vec![Object::primitive(t1)]).named("FirstGroup")
+        );
         assert_eq!(
             group_objects[1],
-            Object::group(vec![Object::new(Shape::Triangle(t2))])
+            Object::group(vec![Object::primitive(t2)]).named("SecondGroup")
         );
+    }
+
+    #[test]
+    fn groups_keep_the_order_of_the_file() {
+        let file_contents = r#"
+v -1 1 0
+v -1 0 0
+v 1 0 0
+f 1 2 3
+g Zebra
+f 1 2 3
+g Apple
+f 1 2 3
+g Mango
+f 1 2 3
+g Zebra
+f 1 2 3
+"#;
+        let obj = WavefrontObj::from_file_contents(file_contents).unwrap();
+
         assert_eq!(
-            group_objects[0],
-            Object::group(vec![Object::new(Shape::Triangle(t1))])
+            obj.group_names().collect::<Vec<_>>(),
+            vec!["default", "Zebra", "Apple", "Mango"]
         );
+        assert_eq!(obj.groups["Zebra"].len(), 2);
+        let g = obj.to_group();
+        assert!(g.find("Apple").is_some());
+        let ShapeOrGroup::Group(children) = g.shape else {
+            panic!("Didn't get a group back from obj file!")
+        };
+        assert_eq!(children[3].name.as_deref(), Some("Mango"));
     }
 
     #[test]
@@ -295,4 +539,127 @@ f 1/1/3 2/102/1 3/14/2
         assert_eq!(t1.normals().2, obj.normals[2 - 1]);
         assert_eq!(t2, t1);
     }
+
+    #[test]
+    fn orienting_faces_across_groups() {
+        let file_contents = r#"
+v 0 0 0
+v 1 0 0
+v 0 1 0
+v 0 0 1
+g Bottom
+f 1 2 3
+g Sides
+f 1 2 4
+f 1 4 3
+f 2 3 4
+"#;
+
+        let mut obj = WavefrontObj::from_file_contents(file_contents).unwrap();
+        obj.orient_faces(true);
+        let center = Tuple::point(0.25, 0.25, 0.25);
+
+        for triangle in obj.groups.values().flatten() {
+            assert!(Tuple::from(triangle.normal()).dot(triangle.p1 - center) > 0.);
+        }
+    }
+
+    #[test]
+    fn faces_can_count_back_from_the_last_vertex() {
+        let file_contents = r#"
+v -1 1 0
+v -1 0 0
+v 1 0 0
+vn 0 0 1
+f -3//-1 -2//-1 -1//-1
+"#;
+
+        let obj = WavefrontObj::from_file_contents(file_contents).unwrap();
+        let t = obj.groups["default"][0];
+
+        assert_eq!(t.p1, obj.vertices[0]);
+        assert_eq!(t.p3, obj.vertices[2]);
+        assert_eq!(t.normals().0, obj.normals[0]);
+    }
+
+    #[test]
+    fn faces_keep_their_texture_coordinates() {
+        let file_contents = r#"
+v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+vt 0 1
+vt 0 0 0
+vt 1
+vt 1 1
+vn 0 0 1
+f 1/1 2/2 3/3 4/4
+f -4/-4/-1 -3/-3/-1 -2/-2/-1
+f 1 2 3
+"#;
+
+        let obj = WavefrontObj::from_file_contents(file_contents).unwrap();
+        let triangles = &obj.groups["default"];
+
+        assert_eq!(obj.textures, vec![(0., 1.), (0., 0.), (1., 0.), (1., 1.)]);
+        assert_eq!(triangles[0].texture, Some([(0., 1.), (0., 0.), (1., 0.)]));
+        assert_eq!(triangles[1].texture, Some([(0., 1.), (1., 0.), (1., 1.)]));
+        assert_eq!(triangles[2].texture, triangles[0].texture);
+        assert_eq!(triangles[2].normals().0, obj.normals[0]);
+        assert_eq!(triangles[3].texture, None);
+    }
+
+    #[test]
+    fn malformed_lines_are_reported_with_their_number() {
+        let vertices = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+        let cases = [
+            ("v 1 x 0", ObjError::InvalidCoordinates { line: 1 }),
+            ("vn 1 0", ObjError::InvalidCoordinates { line: 1 }),
+            ("f 1 2", ObjError::InvalidFace { line: 4 }),
+            ("f 1 two 3", ObjError::InvalidFace { line: 4 }),
+            ("f 1 2 4", ObjError::MissingVertex { line: 4, index: 4 }),
+            ("f 0 1 2", ObjError::MissingVertex { line: 4, index: 0 }),
+            ("f 1 2 -4", ObjError::MissingVertex { line: 4, index: -4 }),
+            (
+                "f 1//1 2//1 3//1",
+                ObjError::MissingNormal { line: 4, index: 1 },
+            ),
+            ("vt x", ObjError::InvalidTextureCoordinates { line: 1 }),
+            (
+                "vt 0 0\nf 1/1 2/1 3/-2",
+                ObjError::MissingTextureCoordinates { line: 5, index: -2 },
+            ),
+        ];
+
+        for (line, expected) in cases {
+            let file_contents = if line.contains("f ") {
+                format!("{}{}", vertices, line)
+            } else {
+                line.to_string()
+            };
+
+            assert_eq!(
+                WavefrontObj::from_file_contents(&file_contents).err(),
+                Some(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn lenient_parsing_skips_bad_faces() {
+        let file_contents = r#"v 0 0 0
+v 1 0 0
+v 0 1 0
+f 1 2 3
+f 1 2 9
+f 3 2 1
+"#;
+
+        let (obj, skipped) = WavefrontObj::from_file_contents_lenient(file_contents).unwrap();
+
+        assert_eq!(obj.groups["default"].len(), 2);
+        assert_eq!(skipped, vec![ObjError::MissingVertex { line: 5, index: 9 }]);
+        assert!(WavefrontObj::from_file_contents_lenient("v 0 0").is_err());
+    }
 }
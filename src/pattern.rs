@@ -1,17 +1,75 @@
-use crate::{color::Color, math::matrix4::Matrix4, math::tuple::Tuple, shape::SimpleObject};
+use std::f64::consts::PI;
+
+use crate::{
+    canvas::Canvas,
+    color::Color,
+    math::{matrix4::Matrix4, point3::Point3, tuple::Tuple, vec3::Vec3},
+    shape::SimpleObject,
+};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Pattern {
     pub transform: Matrix4,
-    pattern_type: PatternType,
+    pub space: PatternSpace,
+    /// How the pattern moves over time, on top of its transform.
+    pub motion: PatternMotion,
+    pub(crate) pattern_type: PatternType,
+}
+
+/// Constant speeds at which a pattern moves, in the space it's evaluated in.
+/// Still by default, which is what renders outside of animations expect.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PatternMotion {
+    /// Distance travelled per unit of time.
+    pub velocity: Tuple,
+    /// Radians turned around the y axis per unit of time.
+    pub angular_velocity: f64,
+}
+
+impl PatternMotion {
+    pub fn still() -> Self {
+        Self {
+            velocity: Tuple::vector(0., 0., 0.),
+            angular_velocity: 0.,
+        }
+    }
+
+    fn at(&self, time: f64) -> Matrix4 {
+        let Tuple { x, y, z, .. } = self.velocity * time;
+
+        Matrix4::translation(x, y, z) * Matrix4::rotation_y(self.angular_velocity * time)
+    }
+}
+
+impl Default for PatternMotion {
+    fn default() -> Self {
+        Self::still()
+    }
+}
+
+/// The coordinate space the pattern is evaluated in, before applying its own transform.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PatternSpace {
+    /// Moves along with each shape, as in the book.
+    #[default]
+    Object,
+    /// Ignores the transforms of the shape and its groups.
+    World,
+    /// Moves along with the outermost group containing the shape,
+    /// so every child of a group shares the same pattern.
+    /// Shapes outside of groups behave like `Object`.
+    GroupRoot,
 }
 
 #[derive(Clone, Copy, Debug)]
-enum PatternType {
+pub(crate) enum PatternType {
     Striped(StripePattern),
     Gradient(GradientPattern),
+    Stops(StopsPattern),
+    Blend(BlendPattern),
     Ring(RingPattern),
     Checkered(CheckeredPattern),
+    Image(ImagePattern),
     #[cfg(test)]
     TestPattern,
 }
@@ -20,6 +78,8 @@ impl Pattern {
     fn new(pattern_type: PatternType) -> Self {
         Self {
             transform: Matrix4::identity(),
+            space: PatternSpace::Object,
+            motion: PatternMotion::still(),
             pattern_type,
         }
     }
@@ -28,111 +88,383 @@ impl Pattern {
         &mut self.transform
     }
 
-    pub fn striped(a: Color, b: Color) -> Self {
+    pub fn striped(a: impl Into<Fill>, b: impl Into<Fill>) -> Self {
         Self::new(PatternType::Striped(StripePattern::new(a, b)))
     }
 
     #[allow(dead_code)]
-    pub fn gradient(a: Color, b: Color) -> Self {
+    pub fn gradient(a: impl Into<Fill>, b: impl Into<Fill>) -> Self {
         Self::new(PatternType::Gradient(GradientPattern::new(a, b)))
     }
 
+    /// The average of what fills `a` and `b`, like two patterns laid over each other.
+    pub fn blend(a: impl Into<Fill>, b: impl Into<Fill>) -> Self {
+        Self::new(PatternType::Blend(BlendPattern {
+            a: a.into(),
+            b: b.into(),
+        }))
+    }
+
+    /// A gradient through every stop of `gradient`.
+    ///
+    /// The stops are kept for the rest of the program, like the images of `Pattern::image`.
+    ///
+    /// Panics if there are no stops.
+    pub fn gradient_with_stops(gradient: Gradient) -> Self {
+        assert!(!gradient.stops.is_empty(), "gradients need at least a stop");
+        let mut stops = gradient.stops;
+        stops.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+        Self::new(PatternType::Stops(StopsPattern {
+            stops: Box::leak(stops.into_boxed_slice()),
+            blend: gradient.blend,
+            shape: gradient.shape,
+            repeating: gradient.repeating,
+        }))
+    }
+
     #[allow(dead_code)]
-    pub fn ring(a: Color, b: Color) -> Self {
+    pub fn ring(a: impl Into<Fill>, b: impl Into<Fill>) -> Self {
         Self::new(PatternType::Ring(RingPattern::new(a, b)))
     }
 
-    pub fn checkered(a: Color, b: Color) -> Self {
+    pub fn checkered(a: impl Into<Fill>, b: impl Into<Fill>) -> Self {
         Self::new(PatternType::Checkered(CheckeredPattern::new(a, b)))
     }
 
+    /// An image wrapped around shapes, like a map of the earth around a sphere.
+    ///
+    /// The image is kept for the rest of the program, so patterns using it stay cheap to copy
+    /// like every other: load it once and copy the pattern for every shape it goes on.
+    ///
+    /// Panics if the image has no pixels.
+    pub fn image(canvas: Canvas, mapping: UvMapping, filter: ImageFilter) -> Self {
+        assert!(
+            canvas.width() > 0 && canvas.height() > 0,
+            "images need at least a pixel"
+        );
+        Self::new(PatternType::Image(ImagePattern {
+            canvas: Box::leak(Box::new(canvas)),
+            mapping,
+            filter,
+        }))
+    }
+
     fn pattern_at(&self, point: Tuple) -> Color {
         match self.pattern_type {
             PatternType::Striped(pattern_type) => pattern_type.pattern_at(point),
             PatternType::Gradient(pattern_type) => pattern_type.pattern_at(point),
+            PatternType::Stops(pattern_type) => pattern_type.pattern_at(point),
+            PatternType::Blend(pattern_type) => pattern_type.pattern_at(point),
             PatternType::Ring(pattern_type) => pattern_type.pattern_at(point),
             PatternType::Checkered(pattern_type) => pattern_type.pattern_at(point),
+            PatternType::Image(pattern_type) => pattern_type.pattern_at(point),
             #[cfg(test)]
             PatternType::TestPattern => tests::TestPattern::pattern_at(point),
         }
     }
 
-    pub(crate) fn pattern_at_object(self, object: SimpleObject, world_point: Tuple) -> Color {
-        let object_point = object.transform.inverse().unwrap() * world_point;
-        let pattern_point = self.transform.inverse().unwrap() * object_point;
+    pub(crate) fn pattern_at_object(self, object: SimpleObject, world_point: Point3) -> Color {
+        self.pattern_at_object_time(object, world_point, 0.)
+    }
+
+    /// Like `pattern_at_object`, with the pattern moved to where it is at `time`.
+    pub(crate) fn pattern_at_object_time(
+        self,
+        object: SimpleObject,
+        world_point: Point3,
+        time: f64,
+    ) -> Color {
+        self.pattern_at(self.world_to_pattern(object, time) * Tuple::from(world_point))
+    }
+
+    /// The pattern as a ramp for `Material::ramp`: its color at `light_dot_normal`
+    /// along x, through its own transform.
+    pub fn ramp_at(&self, light_dot_normal: f64) -> Color {
+        self.pattern_at(self.transform.inverse().unwrap() * Tuple::point(light_dot_normal, 0., 0.))
+    }
+
+    /// Which way the brightness of the pattern goes up around `point`, in the space of
+    /// the pattern, as steep as it changes per unit. Patterns with sharp edges, like
+    /// stripes, are flat everywhere off their edges.
+    ///
+    /// Samples are taken on both sides of the point, as far apart as suits the size of
+    /// its coordinates, so the result doesn't depend on where the pattern is.
+    pub fn pattern_gradient_at(&self, point: Tuple) -> Tuple {
+        let brightness = |point: Tuple| {
+            let color = self.pattern_at(point);
+
+            (color.red + color.green + color.blue) / 3.
+        };
+        let partial = |axis: Tuple, coordinate: f64| {
+            let step = GRADIENT_STEP * coordinate.abs().max(1.);
+
+            (brightness(point + axis * step) - brightness(point - axis * step)) / (2. * step)
+        };
+
+        Tuple::vector(
+            partial(Tuple::vector(1., 0., 0.), point.x),
+            partial(Tuple::vector(0., 1., 0.), point.y),
+            partial(Tuple::vector(0., 0., 1.), point.z),
+        )
+    }
+
+    /// Like `pattern_gradient_at`, at a point of the object in world space at `time`,
+    /// in world space.
+    pub(crate) fn gradient_at_object_time(
+        self,
+        object: SimpleObject,
+        world_point: Point3,
+        time: f64,
+    ) -> Vec3 {
+        let to_pattern = self.world_to_pattern(object, time);
+        let gradient = to_pattern.transpose()
+            * self.pattern_gradient_at(to_pattern * Tuple::from(world_point));
+
+        Vec3::new(gradient.x, gradient.y, gradient.z)
+    }
+
+    /// Takes points on the object from world space to the space of the pattern at `time`.
+    fn world_to_pattern(self, object: SimpleObject, time: f64) -> Matrix4 {
+        let to_space = match self.space {
+            PatternSpace::Object => object.transform.inverse(),
+            PatternSpace::World => Matrix4::identity(),
+            PatternSpace::GroupRoot => object.root_transform.inverse(),
+        };
+        let pattern_transform = self.motion.at(time) * self.transform;
+
+        pattern_transform.inverse().unwrap() * to_space
+    }
+}
+
+/// How far apart `pattern_gradient_at` samples patterns, relative to the size of the
+/// coordinates: the cube root of the machine epsilon, which for central differences
+/// balances rounding errors against how much the pattern curves in between samples.
+const GRADIENT_STEP: f64 = 6.055454452393343e-6;
+
+/// What fills one of the two slots of a pattern, like the stripes of stripes:
+/// a plain color, or another pattern.
+///
+/// Patterns in slots are kept for the rest of the program, like the images of `Pattern::image`.
+/// They're evaluated at the point in the space of the pattern they're in, through their
+/// own transform, their space and motion being those of the outer pattern.
+#[derive(Clone, Copy, Debug)]
+pub enum Fill {
+    Color(Color),
+    Pattern(&'static Pattern),
+}
+
+impl Fill {
+    fn at(self, point: Tuple) -> Color {
+        match self {
+            Fill::Color(color) => color,
+            Fill::Pattern(pattern) => {
+                pattern.pattern_at(pattern.transform.inverse().unwrap() * point)
+            }
+        }
+    }
+}
+
+impl From<Color> for Fill {
+    fn from(color: Color) -> Self {
+        Fill::Color(color)
+    }
+}
+
+impl From<Pattern> for Fill {
+    fn from(pattern: Pattern) -> Self {
+        Fill::Pattern(Box::leak(Box::new(pattern)))
+    }
+}
 
-        self.pattern_at(pattern_point)
+impl PartialEq<Color> for Fill {
+    fn eq(&self, color: &Color) -> bool {
+        matches!(self, Fill::Color(own) if own == color)
     }
 }
+
 #[derive(Clone, Copy, Debug)]
-struct StripePattern {
-    a: Color,
-    b: Color,
+pub(crate) struct StripePattern {
+    pub(crate) a: Fill,
+    pub(crate) b: Fill,
 }
 
 impl StripePattern {
-    pub fn new(a: Color, b: Color) -> Self {
-        Self { a, b }
+    pub fn new(a: impl Into<Fill>, b: impl Into<Fill>) -> Self {
+        Self {
+            a: a.into(),
+            b: b.into(),
+        }
     }
 
     pub fn pattern_at(&self, point: Tuple) -> Color {
         if point.x.floor() as i32 % 2 == 0 {
-            self.a
+            self.a.at(point)
         } else {
-            self.b
+            self.b.at(point)
         }
     }
 }
 
 #[derive(Clone, Copy, Debug)]
-struct GradientPattern {
-    a: Color,
-    b: Color,
+pub(crate) struct GradientPattern {
+    pub(crate) a: Fill,
+    pub(crate) b: Fill,
 }
 
 impl GradientPattern {
-    pub fn new(a: Color, b: Color) -> Self {
-        Self { a, b }
+    pub fn new(a: impl Into<Fill>, b: impl Into<Fill>) -> Self {
+        Self {
+            a: a.into(),
+            b: b.into(),
+        }
     }
 
     pub fn pattern_at(&self, point: Tuple) -> Color {
         let t = point.x - point.x.floor();
+        let (a, b) = (self.a.at(point), self.b.at(point));
+
+        a + (b - a) * t
+    }
+}
+
+/// How colors blend in between the stops of a gradient.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GradientBlend {
+    #[default]
+    Linear,
+    /// Easing out of every stop and into the next, so colors don't change abruptly at stops.
+    Smooth,
+    /// Keeping the color of every stop up to the next, in bands, like the ramps of toon shading.
+    Steps,
+}
+
+/// Which way a gradient with stops runs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GradientShape {
+    /// Along x, like `Pattern::gradient`.
+    #[default]
+    Axial,
+    /// Outward from the y axis, like rings.
+    Radial,
+    /// Outward from the origin.
+    Spherical,
+}
+
+/// A gradient through any number of colors, each at its position along the gradient,
+/// for things like sunsets or the grain of wood.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    pub stops: Vec<(f64, Color)>,
+    pub blend: GradientBlend,
+    pub shape: GradientShape,
+    /// Starts over every unit, instead of keeping the color of the first stop before it
+    /// and of the last one after it. Stops should then be between 0 and 1.
+    pub repeating: bool,
+}
+
+impl Gradient {
+    /// A linear, axial gradient through `stops`, not repeating.
+    pub fn new(stops: Vec<(f64, Color)>) -> Self {
+        Self {
+            stops,
+            blend: GradientBlend::Linear,
+            shape: GradientShape::Axial,
+            repeating: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct BlendPattern {
+    pub(crate) a: Fill,
+    pub(crate) b: Fill,
+}
+
+impl BlendPattern {
+    pub fn pattern_at(&self, point: Tuple) -> Color {
+        (self.a.at(point) + self.b.at(point)) * 0.5
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct StopsPattern {
+    /// Sorted by position, never empty.
+    pub(crate) stops: &'static [(f64, Color)],
+    pub(crate) blend: GradientBlend,
+    pub(crate) shape: GradientShape,
+    pub(crate) repeating: bool,
+}
 
-        self.a + (self.b - self.a) * t
+impl StopsPattern {
+    pub fn pattern_at(&self, point: Tuple) -> Color {
+        let position = match self.shape {
+            GradientShape::Axial => point.x,
+            GradientShape::Radial => point.x.hypot(point.z),
+            GradientShape::Spherical => Tuple::vector(point.x, point.y, point.z).magnitude(),
+        };
+        let position = if self.repeating {
+            position.rem_euclid(1.)
+        } else {
+            position
+        };
+        let next = self.stops.partition_point(|(stop, _)| *stop <= position);
+
+        match (next.checked_sub(1), self.stops.get(next)) {
+            (Some(previous), Some(&(to, b))) => {
+                let (from, a) = self.stops[previous];
+                let t = (position - from) / (to - from);
+                let t = match self.blend {
+                    GradientBlend::Linear => t,
+                    GradientBlend::Smooth => t * t * (3. - 2. * t),
+                    GradientBlend::Steps => 0.,
+                };
+
+                a + (b - a) * t
+            }
+            (Some(last), None) => self.stops[last].1,
+            (None, _) => self.stops[0].1,
+        }
     }
 }
 
 #[derive(Debug, Clone, Copy)]
-struct RingPattern {
-    a: Color,
-    b: Color,
+pub(crate) struct RingPattern {
+    pub(crate) a: Fill,
+    pub(crate) b: Fill,
 }
 
 impl RingPattern {
-    pub fn new(a: Color, b: Color) -> Self {
-        Self { a, b }
+    pub fn new(a: impl Into<Fill>, b: impl Into<Fill>) -> Self {
+        Self {
+            a: a.into(),
+            b: b.into(),
+        }
     }
 
     pub fn pattern_at(&self, point: Tuple) -> Color {
         let p = (point.x.powi(2) + point.z.powi(2)).floor() as i32 % 2 == 0;
 
         if p {
-            self.a
+            self.a.at(point)
         } else {
-            self.b
+            self.b.at(point)
         }
     }
 }
 
 #[derive(Debug, Clone, Copy)]
-struct CheckeredPattern {
-    a: Color,
-    b: Color,
+pub(crate) struct CheckeredPattern {
+    pub(crate) a: Fill,
+    pub(crate) b: Fill,
 }
 
 impl CheckeredPattern {
-    pub fn new(a: Color, b: Color) -> Self {
-        Self { a, b }
+    pub fn new(a: impl Into<Fill>, b: impl Into<Fill>) -> Self {
+        Self {
+            a: a.into(),
+            b: b.into(),
+        }
     }
 
     pub fn pattern_at(&self, point: Tuple) -> Color {
@@ -140,9 +472,91 @@ impl CheckeredPattern {
         let predicate = sum_floors as i32 % 2 == 0;
 
         if predicate {
-            self.a
+            self.a.at(point)
         } else {
-            self.b
+            self.b.at(point)
+        }
+    }
+}
+
+/// How the points of a shape map to the (u, v) coordinates of an image, both from 0 to 1,
+/// with v going up from the bottom of the image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UvMapping {
+    /// Longitude and latitude around the origin, like a map around a globe.
+    Spherical,
+    /// The x and z coordinates, the image repeating every unit.
+    Planar,
+    /// Around the y axis, repeating every unit along it.
+    Cylindrical,
+}
+
+impl UvMapping {
+    pub(crate) fn uv(self, point: Tuple) -> (f64, f64) {
+        match self {
+            UvMapping::Spherical => {
+                let theta = point.x.atan2(point.z);
+                let radius = Tuple::vector(point.x, point.y, point.z).magnitude();
+                let phi = (point.y / radius).acos();
+
+                (1. - (theta / (2. * PI) + 0.5), 1. - phi / PI)
+            }
+            UvMapping::Planar => (point.x.rem_euclid(1.), point.z.rem_euclid(1.)),
+            UvMapping::Cylindrical => {
+                let theta = point.x.atan2(point.z);
+
+                (1. - (theta / (2. * PI) + 0.5), point.y.rem_euclid(1.))
+            }
+        }
+    }
+}
+
+/// How colors are picked between the pixels of an image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFilter {
+    /// The closest pixel, blocky up close.
+    Nearest,
+    /// A blend of the four closest pixels.
+    Bilinear,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct ImagePattern {
+    pub(crate) canvas: &'static Canvas,
+    pub(crate) mapping: UvMapping,
+    pub(crate) filter: ImageFilter,
+}
+
+impl std::fmt::Debug for ImagePattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImagePattern")
+            .field("width", &self.canvas.width())
+            .field("height", &self.canvas.height())
+            .field("mapping", &self.mapping)
+            .field("filter", &self.filter)
+            .finish()
+    }
+}
+
+impl ImagePattern {
+    pub fn pattern_at(&self, point: Tuple) -> Color {
+        let (u, v) = self.mapping.uv(point);
+        let x = u.clamp(0., 1.) * (self.canvas.width() as f64 - 1.);
+        let y = (1. - v.clamp(0., 1.)) * (self.canvas.height() as f64 - 1.);
+
+        match self.filter {
+            ImageFilter::Nearest => self.canvas.pixel_at(x.round() as i32, y.round() as i32),
+            ImageFilter::Bilinear => {
+                let (x0, y0) = (x.floor(), y.floor());
+                let (x1, y1) = (x.ceil() as i32, y.ceil() as i32);
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i32, y0 as i32);
+                let row = |y: i32| {
+                    self.canvas.pixel_at(x0, y) * (1. - fx) + self.canvas.pixel_at(x1, y) * fx
+                };
+
+                row(y0) * (1. - fy) + row(y1) * fy
+            }
         }
     }
 }
@@ -150,7 +564,13 @@ impl CheckeredPattern {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::shape::{Object, SimpleObject};
+    use crate::{
+        intersection::Intersection,
+        misc::approx_equal,
+        ray::Ray,
+        shape::{cylinder::Cylinder, Object, SimpleObject},
+    };
+    use std::f64::consts::PI;
 
     impl Pattern {
         pub fn test() -> Self {
@@ -216,14 +636,168 @@ mod tests {
         );
     }
 
+    fn sunset() -> Gradient {
+        Gradient::new(vec![
+            (1., Color::new(0., 0., 1.)),
+            (0., Color::new(1., 0., 0.)),
+            (0.5, Color::new(1., 1., 0.)),
+        ])
+    }
+
+    #[test]
+    fn a_gradient_with_stops_blends_between_neighbouring_stops() {
+        let pattern = Pattern::gradient_with_stops(sunset());
+
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(0.25, 0., 0.)),
+            Color::new(1., 0.5, 0.)
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(0.5, 3., 2.)),
+            Color::new(1., 1., 0.)
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(0.75, 0., 0.)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        // The end stops are held outside of them.
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(-2., 0., 0.)),
+            Color::new(1., 0., 0.)
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(7., 0., 0.)),
+            Color::new(0., 0., 1.)
+        );
+    }
+
+    #[test]
+    fn smooth_gradients_ease_in_and_out_of_stops() {
+        let mut gradient = sunset();
+        gradient.blend = GradientBlend::Smooth;
+        let pattern = Pattern::gradient_with_stops(gradient);
+
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(0.125, 0., 0.)),
+            Color::new(1., 0.15625, 0.)
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(0.25, 0., 0.)),
+            Color::new(1., 0.5, 0.)
+        );
+    }
+
+    #[test]
+    fn radial_and_spherical_gradients_grow_outward() {
+        let mut gradient = sunset();
+        gradient.shape = GradientShape::Radial;
+        let radial = Pattern::gradient_with_stops(gradient.clone());
+        gradient.shape = GradientShape::Spherical;
+        let spherical = Pattern::gradient_with_stops(gradient);
+
+        assert_eq!(
+            radial.pattern_at(Tuple::point(0.3, 5., 0.4)),
+            Color::new(1., 1., 0.)
+        );
+        assert_eq!(
+            spherical.pattern_at(Tuple::point(0.3, 0., 0.4)),
+            Color::new(1., 1., 0.)
+        );
+        assert_eq!(
+            spherical.pattern_at(Tuple::point(0.3, 5., 0.4)),
+            Color::new(0., 0., 1.)
+        );
+    }
+
+    #[test]
+    fn repeating_gradients_start_over_every_unit() {
+        let mut gradient = sunset();
+        gradient.repeating = true;
+        let pattern = Pattern::gradient_with_stops(gradient);
+
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(2.25, 0., 0.)),
+            Color::new(1., 0.5, 0.)
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(-0.75, 0., 0.)),
+            Color::new(1., 0.5, 0.)
+        );
+    }
+
+    #[test]
+    fn the_gradient_of_a_pattern_points_to_where_it_gets_brighter() {
+        let pattern = Pattern::gradient(Color::black(), Color::white());
+
+        let gradient = pattern.pattern_gradient_at(Tuple::point(0.3, 2., -1.));
+        assert!(approx_equal(gradient.x, 1.));
+        assert!(approx_equal(gradient.y, 0.));
+        assert!(approx_equal(gradient.z, 0.));
+
+        // As precise far from the origin as near it.
+        let far = pattern.pattern_gradient_at(Tuple::point(1000.3, 2., -1.));
+        assert!(approx_equal(far.x, 1.));
+    }
+
+    #[test]
+    fn gradients_of_patterns_on_objects_are_in_world_space() {
+        let mut object = Object::sphere();
+        object.set_transform(Matrix4::scaling(2., 2., 2.));
+        let s = SimpleObject::from_object(&object).unwrap();
+        let mut pattern = Pattern::gradient(Color::black(), Color::white());
+        pattern.transform = Matrix4::rotation_z(PI / 2.);
+
+        // Along the y axis of the world, half as steep on a sphere twice as big.
+        let gradient = pattern.gradient_at_object_time(s, Point3::new(0.5, -0.6, 0.), 0.);
+
+        assert!(approx_equal(gradient.x, 0.));
+        assert!(approx_equal(gradient.y, 0.5));
+    }
+
+    #[test]
+    fn patterns_can_fill_the_slots_of_other_patterns() {
+        let mut across = Pattern::striped(Color::new(1., 0., 0.), Color::new(0., 0., 1.));
+        across.transform = Matrix4::rotation_y(PI / 2.);
+        let pattern = Pattern::striped(across, Color::white());
+
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(0.5, 0., -0.5)),
+            Color::new(1., 0., 0.)
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(0.5, 0., 0.5)),
+            Color::new(0., 0., 1.)
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(1.5, 0., 0.5)),
+            Color::white()
+        );
+    }
+
+    #[test]
+    fn blended_patterns_average_each_other() {
+        let stripes = Pattern::striped(Color::white(), Color::black());
+        let gradient = Pattern::gradient(Color::new(1., 0., 0.), Color::new(0., 0., 1.));
+        let pattern = Pattern::blend(stripes, gradient);
+
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(0.5, 0., 0.)),
+            Color::new(0.75, 0.5, 0.75)
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::point(1.5, 0., 0.)),
+            Color::new(0.25, 0., 0.25)
+        );
+    }
+
     #[test]
     fn stripes_with_an_object_transformation() {
         let mut object = Object::sphere();
-        object.transform = Matrix4::scaling(2., 2., 2.);
+        object.set_transform(Matrix4::scaling(2., 2., 2.));
         let s = SimpleObject::from_object(&object).unwrap();
 
         let pattern = Pattern::striped(Color::white(), Color::black());
-        let c = pattern.pattern_at_object(s, Tuple::point(1.5, 0., 0.));
+        let c = pattern.pattern_at_object(s, Point3::new(1.5, 0., 0.));
 
         assert_eq!(c, Color::white());
     }
@@ -235,7 +809,7 @@ mod tests {
         *pattern.transform_mut() = Matrix4::scaling(2., 2., 2.);
         let s = SimpleObject::from_object(&object).unwrap();
 
-        let c = pattern.pattern_at_object(s, Tuple::point(1.5, 0., 0.));
+        let c = pattern.pattern_at_object(s, Point3::new(1.5, 0., 0.));
 
         assert_eq!(c, Color::white());
     }
@@ -243,17 +817,55 @@ mod tests {
     #[test]
     fn stripes_with_both_an_object_and_a_pattern_transformation() {
         let mut object = Object::sphere();
-        object.transform = Matrix4::scaling(2., 2., 2.);
+        object.set_transform(Matrix4::scaling(2., 2., 2.));
 
         let mut pattern = Pattern::striped(Color::white(), Color::black());
         *pattern.transform_mut() = Matrix4::translation(0.5, 0., 0.);
         let s = SimpleObject::from_object(&object).unwrap();
 
-        let c = pattern.pattern_at_object(s, Tuple::point(2.5, 0., 0.));
+        let c = pattern.pattern_at_object(s, Point3::new(2.5, 0., 0.));
 
         assert_eq!(c, Color::white());
     }
 
+    #[test]
+    fn patterns_are_still_by_default() {
+        let pattern = Pattern::striped(Color::white(), Color::black());
+        let object = Object::sphere();
+        let s = SimpleObject::from_object(&object).unwrap();
+
+        assert_eq!(pattern.motion, PatternMotion::still());
+        assert_eq!(
+            pattern.pattern_at_object_time(s, Point3::new(0.5, 0., 0.), 3.),
+            Color::white()
+        );
+    }
+
+    #[test]
+    fn a_moving_pattern_is_displaced_over_time() {
+        let mut pattern = Pattern::striped(Color::white(), Color::black());
+        pattern.motion.velocity = Tuple::vector(1., 0., 0.);
+        let object = Object::sphere();
+        let s = SimpleObject::from_object(&object).unwrap();
+        let point = Point3::new(0.5, 0., 0.);
+
+        assert_eq!(pattern.pattern_at_object_time(s, point, 0.), Color::white());
+        assert_eq!(pattern.pattern_at_object_time(s, point, 1.), Color::black());
+        assert_eq!(pattern.pattern_at_object_time(s, point, 2.), Color::white());
+    }
+
+    #[test]
+    fn a_spinning_pattern_turns_around_the_y_axis_over_time() {
+        let mut pattern = Pattern::striped(Color::white(), Color::black());
+        pattern.motion.angular_velocity = PI / 2.;
+        let object = Object::sphere();
+        let s = SimpleObject::from_object(&object).unwrap();
+        let point = Point3::new(0., 0., 0.5);
+
+        assert_eq!(pattern.pattern_at_object_time(s, point, 0.), Color::white());
+        assert_eq!(pattern.pattern_at_object_time(s, point, 1.), Color::black());
+    }
+
     #[test]
     fn the_default_pattern_transformation() {
         let pattern = Pattern::test();
@@ -273,10 +885,10 @@ mod tests {
     #[test]
     fn a_pattern_with_an_object_transformation() {
         let mut object = Object::sphere();
-        object.transform = Matrix4::scaling(2., 2., 2.);
+        object.set_transform(Matrix4::scaling(2., 2., 2.));
         let pattern = Pattern::test();
         let shape = SimpleObject::from_object(&object).unwrap();
-        let c = pattern.pattern_at_object(shape, Tuple::point(2., 3., 4.));
+        let c = pattern.pattern_at_object(shape, Point3::new(2., 3., 4.));
 
         assert_eq!(c, Color::new(1., 1.5, 2.));
     }
@@ -287,7 +899,7 @@ mod tests {
         let mut pattern = Pattern::test();
         *pattern.transform_mut() = Matrix4::scaling(2., 2., 2.);
         let shape = SimpleObject::from_object(&object).unwrap();
-        let c = pattern.pattern_at_object(shape, Tuple::point(2., 3., 4.));
+        let c = pattern.pattern_at_object(shape, Point3::new(2., 3., 4.));
 
         assert_eq!(c, Color::new(1., 1.5, 2.));
     }
@@ -295,12 +907,12 @@ mod tests {
     #[test]
     fn a_pattern_with_both_an_object_and_a_pattern_transformation() {
         let mut object = Object::sphere();
-        object.transform = Matrix4::scaling(2., 2., 2.);
+        object.set_transform(Matrix4::scaling(2., 2., 2.));
         let mut pattern = Pattern::test();
         *pattern.transform_mut() = Matrix4::translation(0.5, 1., 1.5);
         let shape = SimpleObject::from_object(&object).unwrap();
 
-        let c = pattern.pattern_at_object(shape, Tuple::point(2.5, 3., 3.5));
+        let c = pattern.pattern_at_object(shape, Point3::new(2.5, 3., 3.5));
 
         assert_eq!(c, Color::new(0.75, 0.5, 0.25));
     }
@@ -379,4 +991,145 @@ mod tests {
             Color::black()
         );
     }
+
+    /// Same leg as in chapter 14's hexagon.
+    fn hexagon_leg() -> Object {
+        let mut s = Object::sphere();
+        s.set_transform(Matrix4::translation(0., 0., -1.) * Matrix4::scaling(0.25, 0.25, 0.25));
+
+        let mut cylinder = Object::primitive(Cylinder::truncated(0., 1., false).unwrap());
+        cylinder.set_transform(
+            Matrix4::translation(0., 0., -1.)
+                * Matrix4::rotation_y(-PI / 6.)
+                * Matrix4::rotation_z(-PI / 2.)
+                * Matrix4::scaling(0.25, 1., 0.25),
+        );
+
+        Object::group(vec![s, cylinder])
+    }
+
+    fn color_on_top_of_the_joint(space: PatternSpace) -> Color {
+        let mut hexagon = Object::group(vec![hexagon_leg()]);
+        hexagon.set_transform(Matrix4::translation(0., 1., 0.) * Matrix4::rotation_y(PI / 3.));
+        let joint = hexagon.transform() * Point3::new(0., 0., -1.);
+        let r = Ray::new(joint + Vec3::new(0., 5., 0.), Vec3::new(0., -1., 0.));
+        let mut pattern = Pattern::test();
+        pattern.space = space;
+
+        let xs = hexagon.intersect(r);
+        let hit = Intersection::hit(&xs).unwrap();
+
+        pattern.pattern_at_object(hit.object, r.position(hit.t))
+    }
+
+    #[test]
+    fn patterns_in_object_space_follow_each_shape() {
+        let c = color_on_top_of_the_joint(PatternSpace::Object);
+
+        assert_eq!(c, Color::new(0., 1., 0.));
+    }
+
+    #[test]
+    fn patterns_in_world_space_ignore_every_transform() {
+        let c = color_on_top_of_the_joint(PatternSpace::World);
+        let joint = Matrix4::translation(0., 1., 0.)
+            * Matrix4::rotation_y(PI / 3.)
+            * Tuple::point(0., 0.25, -1.);
+
+        assert_eq!(c, Color::new(joint.x, joint.y, joint.z));
+    }
+
+    #[test]
+    fn patterns_in_group_root_space_follow_the_outermost_group() {
+        let c = color_on_top_of_the_joint(PatternSpace::GroupRoot);
+
+        assert_eq!(c, Color::new(0., 0.25, -1.));
+    }
+
+    #[test]
+    fn patterns_in_group_root_space_are_shared_by_every_child() {
+        let mut hexagon = Object::group(vec![hexagon_leg()]);
+        hexagon.set_transform(Matrix4::rotation_y(PI / 3.));
+        let mut pattern = Pattern::striped(Color::white(), Color::black());
+        pattern.space = PatternSpace::GroupRoot;
+
+        // Evaluating the same point through either child gives the same color.
+        let point = hexagon.transform() * Point3::new(0., 0., -1.);
+        let sphere_ray = Ray::new(
+            hexagon.transform() * Point3::new(-0.1, 5., -1.),
+            Vec3::new(0., -1., 0.),
+        );
+        let xs = hexagon.intersect(sphere_ray);
+        let sphere = Intersection::hit(&xs).unwrap().object;
+        let cylinder_ray = Ray::new(
+            hexagon.transform() * Point3::new(0.5, 5., -0.8),
+            Vec3::new(0., -1., 0.),
+        );
+        let xs = hexagon.intersect(cylinder_ray);
+        let cylinder = Intersection::hit(&xs).unwrap().object;
+
+        assert_ne!(sphere.transform, cylinder.transform);
+        assert_eq!(sphere.root_transform, cylinder.root_transform);
+        assert_eq!(
+            pattern.pattern_at_object(sphere, point),
+            pattern.pattern_at_object(cylinder, point)
+        );
+    }
+
+    #[test]
+    fn using_a_spherical_mapping_on_a_3d_point() {
+        let examples = [
+            (Tuple::point(0., 0., -1.), (0., 0.5)),
+            (Tuple::point(1., 0., 0.), (0.25, 0.5)),
+            (Tuple::point(0., 0., 1.), (0.5, 0.5)),
+            (Tuple::point(-1., 0., 0.), (0.75, 0.5)),
+            (Tuple::point(0., 1., 0.), (0.5, 1.)),
+            (Tuple::point(0., -1., 0.), (0.5, 0.)),
+            (
+                Tuple::point(2_f64.sqrt() / 2., 2_f64.sqrt() / 2., 0.),
+                (0.25, 0.75),
+            ),
+        ];
+
+        for (point, (u, v)) in examples {
+            let uv = UvMapping::Spherical.uv(point);
+
+            assert!(
+                approx_equal(uv.0, u) && approx_equal(uv.1, v),
+                "{:?}",
+                point
+            );
+        }
+    }
+
+    #[test]
+    fn image_patterns_sample_the_image_at_the_uv_of_the_point() {
+        // Black and red on the top row, green and white on the bottom one.
+        let image = Canvas::from_ppm("P3\n2 2\n255\n0 0 0 255 0 0\n0 255 0 255 255 255\n").unwrap();
+        let nearest = Pattern::image(image.clone(), UvMapping::Planar, ImageFilter::Nearest);
+        let bilinear = Pattern::image(image, UvMapping::Planar, ImageFilter::Bilinear);
+
+        assert_eq!(
+            nearest.pattern_at(Tuple::point(0.1, 0., 0.9)),
+            Color::black()
+        );
+        assert_eq!(nearest.pattern_at(Tuple::point(0.9, 0., 0.9)), Color::red());
+        assert_eq!(
+            nearest.pattern_at(Tuple::point(0.1, 0., 0.1)),
+            Color::green()
+        );
+        // Repeating every unit.
+        assert_eq!(
+            nearest.pattern_at(Tuple::point(1.9, 0., -0.9)),
+            Color::white()
+        );
+        assert_eq!(
+            bilinear.pattern_at(Tuple::point(0.5, 0., 0.5)),
+            Color::new(0.5, 0.5, 0.25)
+        );
+        assert_eq!(
+            bilinear.pattern_at(Tuple::point(0.25, 0., 0.5)),
+            Color::new(0.25, 0.5, 0.125)
+        );
+    }
 }
@@ -0,0 +1,826 @@
+use crate::{
+    color::Color,
+    error::{RayTracerError, Result},
+    material::Material,
+    math::tuple::Tuple,
+    shape::{point_cloud::CloudPoint, triangle::Triangle, Object},
+};
+
+/// A mesh (or point set) read from a Stanford PLY file.
+///
+/// Supports the `ascii`, `binary_little_endian` and `binary_big_endian` encodings.
+/// Only the `vertex` and `face` elements are interpreted, any other element is skipped.
+pub struct Ply {
+    vertices: Vec<Tuple>,
+    normals: Option<Vec<Tuple>>,
+    colors: Option<Vec<Color>>,
+    radii: Option<Vec<f64>>,
+    faces: Vec<Vec<usize>>,
+}
+
+impl Ply {
+    /// Builds a group with one triangle per (fan-triangulated) face.
+    ///
+    /// Faces are smooth if the file has vertex normals, and take the average
+    /// of their vertex colors as material color if the file has vertex colors.
+    pub fn to_group(self) -> Object {
+        let mut triangles = vec![];
+
+        for face in &self.faces {
+            let start_index = face[0];
+
+            for window in face[1..].windows(2) {
+                let indices = [start_index, window[0], window[1]];
+                let [p1, p2, p3] = indices.map(|index| self.vertices[index]);
+
+                let triangle = match &self.normals {
+                    Some(normals) => {
+                        let [n1, n2, n3] = indices.map(|index| normals[index]);
+
+                        Triangle::smooth(p1, p2, p3, n1, n2, n3)
+                    }
+                    None => Triangle::new(p1, p2, p3),
+                };
+                let mut object = Object::primitive(triangle);
+
+                if let Some(colors) = &self.colors {
+                    let [c1, c2, c3] = indices.map(|index| colors[index]);
+                    let mut material = Material::new();
+                    material.color = (c1 + c2 + c3) * (1. / 3.);
+                    object.set_material(material);
+                }
+
+                triangles.push(object);
+            }
+        }
+
+        Object::group(triangles)
+    }
+
+    /// Builds a point cloud out of the vertices, ignoring faces.
+    ///
+    /// Points use the `radius` vertex property if the file has one, `default_radius` otherwise.
+    pub fn to_point_cloud(self, default_radius: f64) -> Object {
+        let points = self
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(index, position)| CloudPoint {
+                position: *position,
+                radius: self
+                    .radii
+                    .as_ref()
+                    .map_or(default_radius, |radii| radii[index]),
+                color: self.colors.as_ref().map(|colors| colors[index]),
+            })
+            .collect();
+
+        Object::point_cloud(points)
+    }
+
+    pub fn from_file(file_path: &str) -> Result<Object> {
+        let bytes = std::fs::read(file_path)?;
+        let ply = Ply::from_bytes(&bytes)?;
+        Ok(ply.to_group())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Ply> {
+        let (header, body) = Header::parse(bytes)?;
+        let mut reader: Box<dyn BodyReader> = match header.format {
+            Format::Ascii => {
+                let body = std::str::from_utf8(body).map_err(|_| invalid_data("non utf8 body"))?;
+
+                Box::new(AsciiReader { rest: body })
+            }
+            Format::BinaryLittleEndian => Box::new(BinaryReader {
+                bytes: body,
+                position: 0,
+                big_endian: false,
+            }),
+            Format::BinaryBigEndian => Box::new(BinaryReader {
+                bytes: body,
+                position: 0,
+                big_endian: true,
+            }),
+        };
+
+        let mut vertices = vec![];
+        let mut normals = vec![];
+        let mut colors = vec![];
+        let mut radii = vec![];
+        let mut faces = vec![];
+        let mut has_normals = false;
+        let mut has_colors = false;
+        let mut has_radii = false;
+
+        for element in &header.elements {
+            let is_vertex = element.name == "vertex";
+            let is_face = element.name == "face";
+            has_normals |= is_vertex && element.has_properties(&["nx", "ny", "nz"]);
+            has_colors |= is_vertex && element.has_properties(&["red", "green", "blue"]);
+            has_radii |= is_vertex && element.has_properties(&["radius"]);
+
+            // Records without properties take nothing from the body, which can't bound them.
+            if element.properties.is_empty() {
+                if (is_vertex || is_face) && element.count > 0 {
+                    return Err(invalid_data("vertices and faces need properties"));
+                }
+                continue;
+            }
+            if element.count > reader.records_left(&element.properties) {
+                return Err(invalid_data("more elements than the body holds"));
+            }
+            if is_vertex {
+                vertices.reserve(element.count);
+                normals.reserve(element.count);
+                colors.reserve(element.count);
+                radii.reserve(element.count);
+            }
+
+            for _ in 0..element.count {
+                let mut position = Tuple::point(0., 0., 0.);
+                let mut normal = Tuple::vector(0., 0., 0.);
+                let mut color = Color::black();
+                let mut radius = 0.;
+
+                for property in &element.properties {
+                    match property.kind {
+                        PropertyKind::Scalar(scalar) => {
+                            let value = reader.read(scalar)?;
+
+                            if is_vertex {
+                                // Integer colors go from 0 to 255, float ones from 0 to 1.
+                                let color_value = if scalar.is_integer() {
+                                    value / 255.
+                                } else {
+                                    value
+                                };
+
+                                match property.name.as_str() {
+                                    "x" => position.x = value,
+                                    "y" => position.y = value,
+                                    "z" => position.z = value,
+                                    "nx" => normal.x = value,
+                                    "ny" => normal.y = value,
+                                    "nz" => normal.z = value,
+                                    "red" => color.red = color_value,
+                                    "green" => color.green = color_value,
+                                    "blue" => color.blue = color_value,
+                                    "radius" => radius = value,
+                                    _ => {}
+                                }
+                            }
+                        }
+                        PropertyKind::List { count, item } => {
+                            // Not reserved up front, the count could be anything: the
+                            // body running out is what stops a bogus one.
+                            let length = reader.read(count)? as usize;
+                            let mut values = vec![];
+
+                            for _ in 0..length {
+                                values.push(reader.read(item)?);
+                            }
+
+                            let is_face_indices = property.name == "vertex_indices"
+                                || property.name == "vertex_index";
+                            if is_face && is_face_indices {
+                                let indices = values
+                                    .into_iter()
+                                    .map(vertex_index)
+                                    .collect::<Result<Vec<_>>>()?;
+                                faces.push(indices);
+                            }
+                        }
+                    }
+                }
+
+                if is_vertex {
+                    vertices.push(position);
+                    normals.push(normal);
+                    colors.push(color);
+                    radii.push(radius);
+                }
+            }
+        }
+
+        for face in &faces {
+            if face.len() < 3 {
+                return Err(invalid_data("face with less than 3 vertices"));
+            }
+            if face.iter().any(|index| *index >= vertices.len()) {
+                return Err(invalid_data("face references a missing vertex"));
+            }
+        }
+
+        Ok(Ply {
+            vertices,
+            normals: has_normals.then_some(normals),
+            colors: has_colors.then_some(colors),
+            radii: has_radii.then_some(radii),
+            faces,
+        })
+    }
+}
+
+fn vertex_index(value: f64) -> Result<usize> {
+    if value >= 0. && value.fract() == 0. {
+        Ok(value as usize)
+    } else {
+        Err(invalid_data(
+            "vertex index that isn't a whole, non negative number",
+        ))
+    }
+}
+
+fn invalid_data(message: &str) -> RayTracerError {
+    RayTracerError::Parse(format!("invalid ply file: {}", message))
+}
+
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ScalarType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Result<Self> {
+        match name {
+            "char" | "int8" => Ok(ScalarType::Int8),
+            "uchar" | "uint8" => Ok(ScalarType::UInt8),
+            "short" | "int16" => Ok(ScalarType::Int16),
+            "ushort" | "uint16" => Ok(ScalarType::UInt16),
+            "int" | "int32" => Ok(ScalarType::Int32),
+            "uint" | "uint32" => Ok(ScalarType::UInt32),
+            "float" | "float32" => Ok(ScalarType::Float32),
+            "double" | "float64" => Ok(ScalarType::Float64),
+            _ => Err(invalid_data("unknown property type")),
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ScalarType::Int8 | ScalarType::UInt8 => 1,
+            ScalarType::Int16 | ScalarType::UInt16 => 2,
+            ScalarType::Int32 | ScalarType::UInt32 | ScalarType::Float32 => 4,
+            ScalarType::Float64 => 8,
+        }
+    }
+
+    fn is_integer(self) -> bool {
+        !matches!(self, ScalarType::Float32 | ScalarType::Float64)
+    }
+}
+
+enum PropertyKind {
+    Scalar(ScalarType),
+    List { count: ScalarType, item: ScalarType },
+}
+
+struct Property {
+    name: String,
+    kind: PropertyKind,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    fn has_properties(&self, names: &[&str]) -> bool {
+        names
+            .iter()
+            .all(|name| self.properties.iter().any(|p| p.name == *name))
+    }
+}
+
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+}
+
+impl Header {
+    /// Parses the header, returning it along with the remaining bytes (the body).
+    fn parse(bytes: &[u8]) -> Result<(Header, &[u8])> {
+        const END_HEADER: &[u8] = b"end_header";
+
+        // `end_header` has to be a line of its own, it could be in a comment otherwise.
+        let mut end = 0;
+        let body_start = loop {
+            let line_end = bytes[end..]
+                .iter()
+                .position(|byte| *byte == b'\n')
+                .map(|offset| end + offset);
+            let line = &bytes[end..line_end.unwrap_or(bytes.len())];
+
+            if line.trim_ascii() == END_HEADER {
+                break line_end.map_or(bytes.len(), |line_end| line_end + 1);
+            }
+            end = line_end.ok_or_else(|| invalid_data("missing end_header"))? + 1;
+        };
+
+        let header =
+            std::str::from_utf8(&bytes[..end]).map_err(|_| invalid_data("non utf8 header"))?;
+        let mut lines = header.lines().map(str::trim);
+
+        if lines.next() != Some("ply") {
+            return Err(invalid_data("missing magic number"));
+        }
+
+        let mut format = None;
+        let mut elements: Vec<Element> = vec![];
+
+        for line in lines {
+            let mut words = line.split_ascii_whitespace();
+
+            match words.next() {
+                Some("format") => {
+                    format = match words.next() {
+                        Some("ascii") => Some(Format::Ascii),
+                        Some("binary_little_endian") => Some(Format::BinaryLittleEndian),
+                        Some("binary_big_endian") => Some(Format::BinaryBigEndian),
+                        _ => return Err(invalid_data("unknown format")),
+                    }
+                }
+                Some("element") => {
+                    let name = words.next().ok_or_else(|| invalid_data("element name"))?;
+                    let count = words
+                        .next()
+                        .and_then(|count| count.parse().ok())
+                        .ok_or_else(|| invalid_data("element count"))?;
+
+                    elements.push(Element {
+                        name: name.to_owned(),
+                        count,
+                        properties: vec![],
+                    });
+                }
+                Some("property") => {
+                    let element = elements
+                        .last_mut()
+                        .ok_or_else(|| invalid_data("property outside of an element"))?;
+                    let words: Vec<&str> = words.collect();
+
+                    let property = match words[..] {
+                        ["list", count, item, name] => Property {
+                            name: name.to_owned(),
+                            kind: PropertyKind::List {
+                                count: ScalarType::parse(count)?,
+                                item: ScalarType::parse(item)?,
+                            },
+                        },
+                        [scalar, name] => Property {
+                            name: name.to_owned(),
+                            kind: PropertyKind::Scalar(ScalarType::parse(scalar)?),
+                        },
+                        _ => return Err(invalid_data("malformed property")),
+                    };
+
+                    element.properties.push(property);
+                }
+                _ => {}
+            }
+        }
+
+        let format = format.ok_or_else(|| invalid_data("missing format"))?;
+
+        Ok((Header { format, elements }, &bytes[body_start..]))
+    }
+}
+
+trait BodyReader {
+    fn read(&mut self, scalar: ScalarType) -> Result<f64>;
+
+    /// At most how many records with (some) `properties` are left, going by the least room
+    /// each of them takes in the body.
+    fn records_left(&self, properties: &[Property]) -> usize;
+}
+
+struct AsciiReader<'a> {
+    rest: &'a str,
+}
+
+impl<'a> BodyReader for AsciiReader<'a> {
+    fn read(&mut self, _: ScalarType) -> Result<f64> {
+        let rest = self
+            .rest
+            .trim_start_matches(|c: char| c.is_ascii_whitespace());
+        let end = rest
+            .find(|c: char| c.is_ascii_whitespace())
+            .unwrap_or(rest.len());
+        let (token, rest) = rest.split_at(end);
+        self.rest = rest;
+
+        token
+            .parse()
+            .map_err(|_| invalid_data("missing or malformed value"))
+    }
+
+    fn records_left(&self, properties: &[Property]) -> usize {
+        // Every value takes a character and the whitespace after it, but the last one.
+        let values = self.rest.len().div_ceil(2);
+
+        values / properties.len()
+    }
+}
+
+struct BinaryReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    big_endian: bool,
+}
+
+impl<'a> BodyReader for BinaryReader<'a> {
+    fn read(&mut self, scalar: ScalarType) -> Result<f64> {
+        let size = scalar.size();
+        let mut buffer = [0; 8];
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + size)
+            .ok_or_else(|| invalid_data("unexpected end of body"))?;
+
+        buffer[..size].copy_from_slice(bytes);
+        if self.big_endian {
+            buffer[..size].reverse();
+        }
+        self.position += size;
+
+        let value = match scalar {
+            ScalarType::Int8 => i8::from_le_bytes([buffer[0]]) as f64,
+            ScalarType::UInt8 => buffer[0] as f64,
+            ScalarType::Int16 => i16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+            ScalarType::UInt16 => u16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+            ScalarType::Int32 => {
+                i32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64
+            }
+            ScalarType::UInt32 => {
+                u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64
+            }
+            ScalarType::Float32 => {
+                f32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64
+            }
+            ScalarType::Float64 => f64::from_le_bytes(buffer),
+        };
+
+        Ok(value)
+    }
+
+    fn records_left(&self, properties: &[Property]) -> usize {
+        let size: usize = properties
+            .iter()
+            .map(|property| match property.kind {
+                PropertyKind::Scalar(scalar) => scalar.size(),
+                PropertyKind::List { count, .. } => count.size(),
+            })
+            .sum();
+
+        (self.bytes.len() - self.position) / size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shape::{Shape, ShapeOrGroup};
+
+    use super::*;
+
+    fn group_children(object: Object) -> Vec<Object> {
+        if let ShapeOrGroup::Group(group) = object.shape {
+            group
+        } else {
+            panic!("Didn't get a group back from ply file!")
+        }
+    }
+
+    #[test]
+    fn parsing_an_ascii_ply_file() {
+        let file_contents = b"ply
+format ascii 1.0
+comment a unit square
+element vertex 4
+property float x
+property float y
+property float z
+element face 2
+property list uchar int vertex_indices
+end_header
+0 0 0
+1 0 0
+1 1 0
+0 1 0
+3 0 1 2
+3 0 2 3
+";
+        let ply = Ply::from_bytes(file_contents).unwrap();
+
+        assert_eq!(ply.vertices.len(), 4);
+        assert_eq!(ply.vertices[2], Tuple::point(1., 1., 0.));
+        assert_eq!(ply.faces, vec![vec![0, 1, 2], vec![0, 2, 3]]);
+        assert!(ply.normals.is_none());
+        assert!(ply.colors.is_none());
+    }
+
+    #[test]
+    fn triangulating_polygons() {
+        let file_contents = b"ply
+format ascii 1.0
+element vertex 5
+property float x
+property float y
+property float z
+element face 1
+property list uchar int vertex_indices
+end_header
+-1 1 0
+-1 0 0
+1 0 0
+1 1 0
+0 2 0
+5 0 1 2 3 4
+";
+        let ply = Ply::from_bytes(file_contents).unwrap();
+        let triangles = group_children(ply.to_group());
+
+        assert_eq!(triangles.len(), 3);
+        assert_eq!(
+            triangles[2],
+            Object::primitive(Triangle::new(
+                Tuple::point(-1., 1., 0.),
+                Tuple::point(1., 1., 0.),
+                Tuple::point(0., 2., 0.),
+            ))
+        );
+    }
+
+    #[test]
+    fn parsing_a_binary_little_endian_ply_file() {
+        let mut file_contents = b"ply
+format binary_little_endian 1.0
+element vertex 3
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+"
+        .to_vec();
+        let vertices = [
+            ([0f32, 1., 0.], [255u8, 0, 0]),
+            ([-1., 0., 0.], [0, 255, 0]),
+            ([1., 0., 0.], [0, 0, 255]),
+        ];
+        for (position, color) in vertices {
+            for coordinate in position {
+                file_contents.extend_from_slice(&coordinate.to_le_bytes());
+            }
+            file_contents.extend_from_slice(&color);
+        }
+        file_contents.push(3);
+        for index in [0i32, 1, 2] {
+            file_contents.extend_from_slice(&index.to_le_bytes());
+        }
+
+        let ply = Ply::from_bytes(&file_contents).unwrap();
+
+        assert_eq!(ply.vertices[1], Tuple::point(-1., 0., 0.));
+        assert_eq!(ply.colors.as_ref().unwrap()[2], Color::blue());
+        assert_eq!(ply.faces, vec![vec![0, 1, 2]]);
+
+        let triangles = group_children(ply.to_group());
+        let material = match &triangles[0].shape {
+            ShapeOrGroup::Shape { material, .. } => *material,
+            ShapeOrGroup::Group(_) => panic!("Expected a triangle"),
+        };
+        assert_eq!(material.color, Color::new(1. / 3., 1. / 3., 1. / 3.));
+    }
+
+    #[test]
+    fn vertex_normals_produce_smooth_triangles() {
+        let file_contents = b"ply
+format ascii 1.0
+element vertex 3
+property double x
+property double y
+property double z
+property double nx
+property double ny
+property double nz
+element face 1
+property list uchar uint vertex_index
+end_header
+0 1 0 0 1 0
+-1 0 0 -1 0 0
+1 0 0 1 0 0
+3 0 1 2
+";
+        let ply = Ply::from_bytes(file_contents).unwrap();
+        let triangles = group_children(ply.to_group());
+
+        assert_eq!(
+            triangles[0],
+            Object::primitive(Triangle::smooth(
+                Tuple::point(0., 1., 0.),
+                Tuple::point(-1., 0., 0.),
+                Tuple::point(1., 0., 0.),
+                Tuple::vector(0., 1., 0.),
+                Tuple::vector(-1., 0., 0.),
+                Tuple::vector(1., 0., 0.),
+            ))
+        );
+    }
+
+    #[test]
+    fn converting_vertices_to_a_point_cloud() {
+        let file_contents = b"ply
+format ascii 1.0
+element vertex 2
+property float x
+property float y
+property float z
+property float radius
+property uchar red
+property uchar green
+property uchar blue
+end_header
+0 0 0 0.5 255 0 0
+2 0 0 0.25 0 0 255
+";
+        let ply = Ply::from_bytes(file_contents).unwrap();
+        let object = ply.to_point_cloud(1.);
+
+        let cloud = match &object.shape {
+            ShapeOrGroup::Shape {
+                shape: Shape::PointCloud(cloud),
+                ..
+            } => cloud,
+            _ => panic!("Expected a point cloud"),
+        };
+        let mut points = cloud.points().to_vec();
+        points.sort_by(|p1, p2| p1.position.x.partial_cmp(&p2.position.x).unwrap());
+
+        assert_eq!(points[0].radius, 0.5);
+        assert_eq!(points[0].color, Some(Color::red()));
+        assert_eq!(points[1].position, Tuple::point(2., 0., 0.));
+        assert_eq!(points[1].radius, 0.25);
+        assert_eq!(points[1].color, Some(Color::blue()));
+    }
+
+    #[test]
+    fn unknown_elements_are_skipped() {
+        let file_contents = b"ply
+format ascii 1.0
+element vertex 1
+property float x
+property float y
+property float z
+element edge 1
+property int vertex1
+property int vertex2
+end_header
+1 2 3
+0 0
+";
+        let ply = Ply::from_bytes(file_contents).unwrap();
+
+        assert_eq!(ply.vertices, vec![Tuple::point(1., 2., 3.)]);
+        assert!(ply.faces.is_empty());
+    }
+
+    #[test]
+    fn malformed_files_are_rejected() {
+        let not_a_ply = b"v 1 2 3\nend_header\n";
+        let missing_vertex = b"ply
+format ascii 1.0
+element vertex 1
+property float x
+property float y
+property float z
+element face 1
+property list uchar int vertex_indices
+end_header
+1 2 3
+3 0 1 2
+";
+        let truncated_binary = b"ply
+format binary_little_endian 1.0
+element vertex 1
+property float x
+end_header
+\x00\x00";
+        let face = |indices: &str| {
+            format!(
+                "ply
+format ascii 1.0
+element vertex 3
+property float x
+property float y
+property float z
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0
+1 0 0
+0 1 0
+{}
+",
+                indices
+            )
+        };
+        let negative_index = face("3 0 1 -1");
+        let fractional_index = face("3 0 1 1.5");
+        let mut endless_list = b"ply
+format binary_little_endian 1.0
+element face 1
+property list uint int vertex_indices
+end_header
+"
+        .to_vec();
+        endless_list.extend_from_slice(&u32::MAX.to_le_bytes());
+        endless_list.extend_from_slice(&0i32.to_le_bytes());
+
+        let vertices_without_properties =
+            b"ply\nformat ascii 1.0\nelement vertex 300000000\nend_header\n";
+        let too_many_vertices = b"ply
+format ascii 1.0
+element vertex 300000000
+property float x
+end_header
+1 2 3
+";
+        let mut too_many_faces = b"ply
+format binary_big_endian 1.0
+element face 300000000
+property list uchar int vertex_indices
+end_header
+"
+        .to_vec();
+        too_many_faces.extend_from_slice(&[0; 64]);
+
+        for contents in [
+            &not_a_ply[..],
+            &missing_vertex[..],
+            &truncated_binary[..],
+            &vertices_without_properties[..],
+            &too_many_vertices[..],
+            &too_many_faces[..],
+            negative_index.as_bytes(),
+            fractional_index.as_bytes(),
+            &endless_list[..],
+        ] {
+            let error = Ply::from_bytes(contents).err().unwrap();
+
+            assert!(matches!(error, RayTracerError::Parse(_)));
+        }
+    }
+
+    #[test]
+    fn elements_without_properties_are_skipped() {
+        let file_contents = b"ply
+format ascii 1.0
+element marker 18446744073709551615
+element vertex 1
+property float x
+property float y
+property float z
+end_header
+1 2 3
+";
+        let ply = Ply::from_bytes(file_contents).unwrap();
+
+        assert_eq!(ply.vertices, vec![Tuple::point(1., 2., 3.)]);
+    }
+
+    #[test]
+    fn end_header_only_ends_the_header_on_its_own_line() {
+        let file_contents = b"ply
+format ascii 1.0
+comment no end_header here
+element vertex 1
+property float x
+property float y
+property float z
+end_header 
+1 2 3
+";
+        let ply = Ply::from_bytes(file_contents).unwrap();
+
+        assert_eq!(ply.vertices, vec![Tuple::point(1., 2., 3.)]);
+    }
+}
@@ -0,0 +1,169 @@
+//! A minimal PNG encoder: 8 bit RGB or RGBA or 16 bit RGB, no filtering and uncompressed
+//! deflate blocks.
+//! Files come out about as big as the raw pixels, but any viewer can open them.
+
+const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+const MAX_STORED_BLOCK: usize = 65535;
+
+#[derive(Clone, Copy)]
+pub(crate) enum ColorType {
+    Rgb,
+    Rgba,
+    /// Two bytes per channel, most significant first.
+    Rgb16,
+}
+
+impl ColorType {
+    fn bytes_per_pixel(self) -> usize {
+        match self {
+            ColorType::Rgb => 3,
+            ColorType::Rgba => 4,
+            ColorType::Rgb16 => 6,
+        }
+    }
+
+    fn bit_depth(self) -> u8 {
+        match self {
+            ColorType::Rgb | ColorType::Rgba => 8,
+            ColorType::Rgb16 => 16,
+        }
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            ColorType::Rgb | ColorType::Rgb16 => 2,
+            ColorType::Rgba => 6,
+        }
+    }
+}
+
+/// Encodes `pixels`, row by row in the layout of `color_type`, as a PNG image.
+pub(crate) fn encode(width: usize, height: usize, color_type: ColorType, pixels: &[u8]) -> Vec<u8> {
+    let mut header = vec![];
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // Bit depth, color type, compression, filter and interlace methods.
+    header.extend_from_slice(&[color_type.bit_depth(), color_type.code(), 0, 0, 0]);
+
+    // Every scanline starts with its filter type, 0 meaning none.
+    let row_length = color_type.bytes_per_pixel() * width;
+    let mut scanlines = Vec::with_capacity(height * (1 + row_length));
+    for row in pixels.chunks(row_length.max(1)) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+    write_chunk(&mut png, b"IEND", &[]);
+
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wraps `data` in a zlib stream made of stored (uncompressed) deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut stream = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = if data.is_empty() {
+        vec![&[]]
+    } else {
+        data.chunks(MAX_STORED_BLOCK).collect()
+    };
+
+    for (index, block) in blocks.iter().enumerate() {
+        let is_last = index + 1 == blocks.len();
+        let length = block.len() as u16;
+
+        stream.push(is_last as u8);
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+
+    stream
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    const MODULO: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+
+    for byte in bytes {
+        a = (a + *byte as u32) % MODULO;
+        b = (b + a) % MODULO;
+    }
+
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums_match_known_values() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn encoding_a_small_image() {
+        let png = encode(2, 1, ColorType::Rgb, &[255, 0, 0, 0, 0, 255]);
+
+        assert_eq!(png[..8], SIGNATURE);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], &[0, 0, 0, 2, 0, 0, 0, 1]);
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+
+        // The image data is stored as is, after the filter byte of the only row.
+        let idat = png.windows(4).position(|w| w == b"IDAT").unwrap();
+        assert_eq!(&png[idat + 11..idat + 18], &[0, 255, 0, 0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn images_with_transparency_have_four_channels() {
+        let png = encode(1, 1, ColorType::Rgba, &[255, 0, 0, 128]);
+
+        assert_eq!(png[25], 6);
+        let idat = png.windows(4).position(|w| w == b"IDAT").unwrap();
+        assert_eq!(&png[idat + 11..idat + 16], &[0, 255, 0, 0, 128]);
+    }
+
+    #[test]
+    fn large_images_are_split_into_several_blocks() {
+        let data = vec![7; MAX_STORED_BLOCK + 10];
+        let stream = zlib_stored(&data);
+
+        // Header, two block headers, the data and the checksum.
+        assert_eq!(stream.len(), 2 + 2 * 5 + data.len() + 4);
+        assert_eq!(stream[2], 0);
+        assert_eq!(stream[2 + 5 + MAX_STORED_BLOCK], 1);
+    }
+}
@@ -0,0 +1,34 @@
+//! The types most scenes are built from, to bring them all in with a single
+//! `use ray_tracer::prelude::*;`.
+//!
+//! Everything here is also where it always was, in the module it comes from.
+
+pub use crate::{
+    animation::Animation,
+    camera::{Camera, LensDistortion, Projection},
+    canvas::Canvas,
+    color::Color,
+    color_buffer::ColorBuffer,
+    environment::Environment,
+    error::RayTracerError,
+    fog::Fog,
+    integrator::Integrator,
+    intersection::{Intersection, TorUVT},
+    light::Light,
+    material::Material,
+    math::{
+        angle::Angle, matrix4::Matrix4, point3::Point3, transform::Transform,
+        transformations::view_transform, tuple::Tuple, vec3::Vec3,
+    },
+    obj::WavefrontObj,
+    pattern::Pattern,
+    ray::Ray,
+    render_settings::RenderSettings,
+    sampler::Sampler,
+    scene::Scene,
+    shape::{
+        cone::Cone, curve::Curve, cylinder::Cylinder, hits::Hits, primitive::Primitive,
+        triangle::Triangle, triangle::UVT, BoundingBox, Object, ObjectMut, Shape, SimpleObject,
+    },
+    world::World,
+};
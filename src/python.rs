@@ -0,0 +1,276 @@
+//! Python bindings, built as the `ray_tracer` extension module.
+//!
+//! ```python
+//! import ray_tracer as rt
+//!
+//! world = rt.World()
+//! world.add_light((-10, 10, -10), (1, 1, 1))
+//! world.add(rt.Object.sphere().with_material(rt.Material(color=(1, 0.2, 1))))
+//!
+//! camera = rt.Camera(200, 100, 1.0).look_at((0, 1.5, -5), (0, 1, 0), (0, 1, 0))
+//! image = camera.render(world)  # numpy array of shape (100, 200, 4)
+//! ```
+
+// The code generated by `#[pymethods]` trips this lint on every fallible method.
+#![allow(clippy::useless_conversion)]
+
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyBytes};
+
+use crate::{
+    camera::Camera,
+    color::Color,
+    light::Light,
+    material::Material,
+    math::{matrix4::Matrix4, point3::Point3, transformations::view_transform, tuple::Tuple},
+    sampler::Sampler,
+    shape::{cone::Cone, cylinder::Cylinder, Object},
+    world::World,
+};
+
+type Triple = (f64, f64, f64);
+
+fn color((red, green, blue): Triple) -> Color {
+    Color::new(red, green, blue)
+}
+
+fn point((x, y, z): Triple) -> Tuple {
+    Tuple::point(x, y, z)
+}
+
+#[pyclass(name = "Material")]
+#[derive(Clone)]
+struct PyMaterial(Material);
+
+#[pymethods]
+impl PyMaterial {
+    #[new]
+    #[pyo3(signature = (
+        color = None,
+        ambient = None,
+        diffuse = None,
+        specular = None,
+        shininess = None,
+        reflective = None,
+        transparency = None,
+        refractive_index = None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        color: Option<Triple>,
+        ambient: Option<f64>,
+        diffuse: Option<f64>,
+        specular: Option<f64>,
+        shininess: Option<f64>,
+        reflective: Option<f64>,
+        transparency: Option<f64>,
+        refractive_index: Option<f64>,
+    ) -> Self {
+        let mut material = Material::new();
+        material.color = color.map(self::color).unwrap_or(material.color);
+        material.ambient = ambient.unwrap_or(material.ambient);
+        material.diffuse = diffuse.unwrap_or(material.diffuse);
+        material.specular = specular.unwrap_or(material.specular);
+        material.shininess = shininess.unwrap_or(material.shininess);
+        material.reflective = reflective.unwrap_or(material.reflective);
+        material.transparency = transparency.unwrap_or(material.transparency);
+        material.refractive_index = refractive_index.unwrap_or(material.refractive_index);
+
+        Self(material)
+    }
+}
+
+/// Objects are immutable from Python: transforms and materials return new objects,
+/// so they can be chained like `Object.cube().scale(1, 2, 1).translate(0, 2, 0)`.
+#[pyclass(name = "Object")]
+#[derive(Clone)]
+struct PyShape(Object);
+
+impl PyShape {
+    fn transformed(&self, transform: Matrix4) -> PyResult<Self> {
+        let mut object = self.0.clone();
+        object
+            .try_set_transform(transform * self.0.transform())
+            .map_err(|error| PyValueError::new_err(error.to_string()))?;
+
+        Ok(Self(object))
+    }
+}
+
+#[pymethods]
+impl PyShape {
+    #[staticmethod]
+    fn sphere() -> Self {
+        Self(Object::sphere())
+    }
+
+    #[staticmethod]
+    fn plane() -> Self {
+        Self(Object::plane())
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (radius = 1.))]
+    fn disc(radius: f64) -> Self {
+        Self(Object::disc(radius))
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (width = 2., depth = 2.))]
+    fn quad(width: f64, depth: f64) -> Self {
+        Self(Object::quad(width, depth))
+    }
+
+    #[staticmethod]
+    fn cube() -> Self {
+        Self(Object::cube())
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (minimum = f64::NEG_INFINITY, maximum = f64::INFINITY, closed = false, radius = 1.))]
+    fn cylinder(minimum: f64, maximum: f64, closed: bool, radius: f64) -> PyResult<Self> {
+        let cylinder = Cylinder::truncated(minimum, maximum, closed)
+            .and_then(|cylinder| cylinder.with_radius(radius))
+            .map_err(|error| PyValueError::new_err(error.to_string()))?;
+
+        Ok(Self(Object::primitive(cylinder)))
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (minimum = f64::NEG_INFINITY, maximum = f64::INFINITY, closed = false, radius = 1.))]
+    fn cone(minimum: f64, maximum: f64, closed: bool, radius: f64) -> PyResult<Self> {
+        let cone = Cone::truncated(minimum, maximum, closed)
+            .and_then(|cone| cone.with_radius(radius))
+            .map_err(|error| PyValueError::new_err(error.to_string()))?;
+
+        Ok(Self(Object::primitive(cone)))
+    }
+
+    #[staticmethod]
+    fn group(children: Vec<PyShape>) -> Self {
+        Self(Object::group(
+            children.into_iter().map(|child| child.0).collect(),
+        ))
+    }
+
+    fn translate(&self, x: f64, y: f64, z: f64) -> PyResult<Self> {
+        self.transformed(Matrix4::translation(x, y, z))
+    }
+
+    fn scale(&self, x: f64, y: f64, z: f64) -> PyResult<Self> {
+        self.transformed(Matrix4::scaling(x, y, z))
+    }
+
+    fn rotate_x(&self, angle: f64) -> PyResult<Self> {
+        self.transformed(Matrix4::rotation_x(angle))
+    }
+
+    fn rotate_y(&self, angle: f64) -> PyResult<Self> {
+        self.transformed(Matrix4::rotation_y(angle))
+    }
+
+    fn rotate_z(&self, angle: f64) -> PyResult<Self> {
+        self.transformed(Matrix4::rotation_z(angle))
+    }
+
+    fn with_material(&self, material: PyMaterial) -> Self {
+        let mut object = self.0.clone();
+        object.set_material(material.0);
+
+        Self(object)
+    }
+}
+
+#[pyclass(name = "World")]
+struct PyWorld(World);
+
+#[pymethods]
+impl PyWorld {
+    #[new]
+    fn new() -> Self {
+        Self(World::new())
+    }
+
+    fn add_light(&mut self, position: Triple, intensity: Triple) {
+        let (x, y, z) = position;
+        self.0
+            .add_light(Light::point_light(Point3::new(x, y, z), color(intensity)));
+    }
+
+    fn add(&mut self, object: PyShape) {
+        self.0.add_object(object.0);
+    }
+}
+
+#[pyclass(name = "Camera")]
+#[derive(Clone)]
+struct PyCamera(Camera);
+
+#[pymethods]
+impl PyCamera {
+    #[new]
+    #[pyo3(signature = (width, height, field_of_view, samples_per_pixel = 1))]
+    fn new(
+        width: i32,
+        height: i32,
+        field_of_view: f64,
+        samples_per_pixel: usize,
+    ) -> PyResult<Self> {
+        if width <= 0 || height <= 0 {
+            return Err(PyValueError::new_err("the image must have some pixels"));
+        }
+
+        let mut camera = Camera::new(width, height, field_of_view);
+        camera.sampler = Sampler::new(samples_per_pixel);
+
+        Ok(Self(camera))
+    }
+
+    fn look_at(&self, from: Triple, to: Triple, up: Triple) -> Self {
+        let (x, y, z) = up;
+        let mut camera = self.0;
+        camera.transform = view_transform(point(from), point(to), Tuple::vector(x, y, z));
+
+        Self(camera)
+    }
+
+    /// Renders the world as a `(height, width, 4)` numpy array of RGBA bytes.
+    fn render<'py>(&self, py: Python<'py>, world: &PyWorld) -> PyResult<Bound<'py, PyAny>> {
+        let camera = self.0;
+        let world = &world.0;
+        let rgba = py.allow_threads(|| camera.render_with_progress(world, |_, _| {}).to_rgba());
+
+        py.import_bound("numpy")?
+            .call_method1("frombuffer", (PyBytes::new_bound(py, &rgba), "uint8"))?
+            .call_method1("reshape", ((camera.vsize, camera.hsize, 4),))
+    }
+
+    /// Renders the world as a `(height, width, 3)` numpy array of float colors, as they
+    /// are before being brought into bytes, colors over 1 included.
+    fn render_float<'py>(&self, py: Python<'py>, world: &PyWorld) -> PyResult<Bound<'py, PyAny>> {
+        let camera = self.0;
+        let world = &world.0;
+        let bytes: Vec<u8> = py.allow_threads(|| {
+            let buffer = camera.render_with_progress(world, |_, _| {}).into_buffer();
+
+            buffer
+                .to_f64s()
+                .into_iter()
+                .flat_map(f64::to_ne_bytes)
+                .collect()
+        });
+
+        py.import_bound("numpy")?
+            .call_method1("frombuffer", (PyBytes::new_bound(py, &bytes), "float64"))?
+            .call_method1("reshape", ((camera.vsize, camera.hsize, 3),))
+    }
+}
+
+#[pymodule]
+fn ray_tracer(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyMaterial>()?;
+    module.add_class::<PyShape>()?;
+    module.add_class::<PyWorld>()?;
+    module.add_class::<PyCamera>()?;
+
+    Ok(())
+}
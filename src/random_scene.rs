@@ -0,0 +1,188 @@
+use std::f64::consts::PI;
+
+use crate::{
+    camera::Camera,
+    color::Color,
+    light::Light,
+    material::Material,
+    math::{matrix4::Matrix4, point3::Point3, transformations::view_transform, tuple::Tuple},
+    sampler::Rng,
+    scene::Scene,
+    shape::Object,
+    world::World,
+};
+
+/// The cover of Ray Tracing in One Weekend: three big spheres over a field of small random ones.
+///
+/// The same seed always gives the same scene, so renders of it can be compared as benchmarks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RandomSpheres {
+    /// Small spheres go on a grid from `-grid_size` to `grid_size` along x and z.
+    pub grid_size: i32,
+    pub seed: u64,
+}
+
+const SMALL_RADIUS: f64 = 0.2;
+/// Where the glass, diffuse and metal big spheres go, along x.
+const BIG_SPHERES: [f64; 3] = [0., -4., 4.];
+
+impl RandomSpheres {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            grid_size: 11,
+            seed,
+        }
+    }
+
+    pub fn scene(self, width: usize, height: usize) -> Scene {
+        let mut rng = Rng::new(self.seed);
+        let mut world = World::new();
+
+        world.add_light(Light::point_light(
+            Point3::new(-10., 20., 10.),
+            Color::white(),
+        ));
+
+        let mut ground = Object::plane();
+        let mut material = Material::new();
+        material.color = Color::new(0.5, 0.5, 0.5);
+        material.specular = 0.;
+        ground.set_material(material);
+        world.add_object(ground);
+
+        let [glass, diffuse, metal] = BIG_SPHERES.map(|x| Tuple::point(x, 1., 0.));
+        world.add_object(sphere(glass, 1., glass_material()));
+        world.add_object(sphere(
+            diffuse,
+            1.,
+            diffuse_material(Color::new(0.4, 0.2, 0.1)),
+        ));
+        world.add_object(sphere(metal, 1., metal_material(Color::new(0.7, 0.6, 0.5))));
+
+        // Every row is a group, so rays only look at the spheres of the rows they go through.
+        for a in -self.grid_size..self.grid_size {
+            let mut row = vec![];
+
+            for b in -self.grid_size..self.grid_size {
+                let center = Tuple::point(
+                    a as f64 + 0.9 * rng.next_f64(),
+                    SMALL_RADIUS,
+                    b as f64 + 0.9 * rng.next_f64(),
+                );
+                let choice = rng.next_f64();
+                let material = if choice < 0.8 {
+                    diffuse_material(random_color(&mut rng) * random_color(&mut rng))
+                } else if choice < 0.95 {
+                    let color = random_color(&mut rng) * 0.5 + Color::new(0.5, 0.5, 0.5);
+                    metal_material(color)
+                } else {
+                    glass_material()
+                };
+
+                let overlaps_a_big_sphere = BIG_SPHERES
+                    .iter()
+                    .any(|x| (center - Tuple::point(*x, SMALL_RADIUS, 0.)).magnitude() < 1.2);
+                if !overlaps_a_big_sphere {
+                    row.push(sphere(center, SMALL_RADIUS, material));
+                }
+            }
+
+            world.add_object(Object::group(row));
+        }
+
+        // The book's 20 degrees are vertical, ours go along the longer side of the image.
+        let aspect = (width as f64 / height as f64).max(1.);
+        let field_of_view = 2. * ((PI / 18.).tan() * aspect).atan();
+        let mut camera = Camera::new(width as i32, height as i32, field_of_view);
+        camera.transform = view_transform(
+            Tuple::point(13., 2., 3.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        );
+
+        Scene { camera, world }
+    }
+}
+
+fn random_color(rng: &mut Rng) -> Color {
+    Color::new(rng.next_f64(), rng.next_f64(), rng.next_f64())
+}
+
+fn sphere(center: Tuple, radius: f64, material: Material) -> Object {
+    let mut sphere = Object::sphere();
+    sphere.set_transform(
+        Matrix4::translation(center.x, center.y, center.z)
+            * Matrix4::scaling(radius, radius, radius),
+    );
+    sphere.set_material(material);
+
+    sphere
+}
+
+fn diffuse_material(color: Color) -> Material {
+    let mut material = Material::new();
+    material.color = color;
+    material.specular = 0.1;
+
+    material
+}
+
+fn metal_material(color: Color) -> Material {
+    let mut material = Material::new();
+    material.color = color;
+    material.diffuse = 0.3;
+    material.specular = 1.;
+    material.shininess = 300.;
+    material.reflective = 0.7;
+
+    material
+}
+
+fn glass_material() -> Material {
+    let mut material = Material::new();
+    material.color = Color::black();
+    material.ambient = 0.;
+    material.diffuse = 0.;
+    material.specular = 1.;
+    material.shininess = 300.;
+    material.reflective = 0.9;
+    material.transparency = 0.9;
+    material.refractive_index = 1.5;
+
+    material
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(spheres: RandomSpheres) -> String {
+        let scene = spheres.scene(8, 6);
+
+        scene
+            .camera
+            .render_with_progress(&scene.world, |_, _| {})
+            .to_ppm()
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_scene() {
+        let mut spheres = RandomSpheres::new(7);
+        spheres.grid_size = 3;
+        let mut other = spheres;
+        other.seed = 8;
+
+        assert_eq!(render(spheres), render(spheres));
+        assert_ne!(render(spheres), render(other));
+        // The ground, the three big spheres and a group per row.
+        assert_eq!(spheres.scene(8, 6).world.objects.len(), 4 + 6);
+    }
+
+    #[test]
+    fn the_big_spheres_are_in_front_of_the_camera() {
+        let scene = RandomSpheres::new(1).scene(30, 20);
+        let ray = scene.camera.ray_for_pixel(15, 10);
+
+        assert!(!scene.world.intersect(ray).is_empty());
+    }
+}
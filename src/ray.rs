@@ -1,18 +1,27 @@
-use crate::math::matrix4::Matrix4;
-use crate::math::tuple::Tuple;
+use crate::math::{matrix4::Matrix4, point3::Point3, vec3::Vec3};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Ray {
-    pub origin: Tuple,
-    pub direction: Tuple,
+    pub origin: Point3,
+    pub direction: Vec3,
+    /// When the ray is cast, for anything that changes over time, like animated patterns.
+    pub time: f64,
 }
 
 impl Ray {
-    pub fn new(origin: Tuple, direction: Tuple) -> Self {
-        Self { origin, direction }
+    pub fn new(origin: Point3, direction: Vec3) -> Self {
+        Self {
+            origin,
+            direction,
+            time: 0.,
+        }
     }
 
-    pub fn position(self, t: f64) -> Tuple {
+    pub fn at_time(self, time: f64) -> Self {
+        Self { time, ..self }
+    }
+
+    pub fn position(self, t: f64) -> Point3 {
         self.origin + self.direction * t
     }
 
@@ -20,20 +29,22 @@ impl Ray {
         Self {
             origin: matrix * self.origin,
             direction: matrix * self.direction,
+            time: self.time,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+
     use super::*;
     use crate::misc::approx_equal;
     use crate::shape::{Object, SimpleObject};
 
     #[test]
     fn creating_and_querying_a_ray() {
-        let origin = Tuple::point(1., 2., 3.);
-        let direction = Tuple::vector(4., 5., 6.);
+        let origin = Point3::new(1., 2., 3.);
+        let direction = Vec3::new(4., 5., 6.);
 
         let r = Ray::new(origin, direction);
 
@@ -43,17 +54,17 @@ mod tests {
 
     #[test]
     fn computing_a_point_from_a_distance() {
-        let r = Ray::new(Tuple::point(2., 3., 4.), Tuple::vector(1., 0., 0.));
+        let r = Ray::new(Point3::new(2., 3., 4.), Vec3::new(1., 0., 0.));
 
-        assert_eq!(r.position(0.), Tuple::point(2., 3., 4.));
-        assert_eq!(r.position(1.), Tuple::point(3., 3., 4.));
-        assert_eq!(r.position(-1.), Tuple::point(1., 3., 4.));
-        assert_eq!(r.position(2.5), Tuple::point(4.5, 3., 4.));
+        assert_eq!(r.position(0.), Point3::new(2., 3., 4.));
+        assert_eq!(r.position(1.), Point3::new(3., 3., 4.));
+        assert_eq!(r.position(-1.), Point3::new(1., 3., 4.));
+        assert_eq!(r.position(2.5), Point3::new(4.5, 3., 4.));
     }
 
     #[test]
     fn a_ray_intersects_a_sphere_at_two_points() {
-        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
         let s = Object::sphere();
 
         let xs = s.intersect(r);
@@ -65,7 +76,7 @@ mod tests {
 
     #[test]
     fn a_ray_intersects_a_sphere_at_a_tangent() {
-        let r = Ray::new(Tuple::point(0., 1., -5.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 1., -5.), Vec3::new(0., 0., 1.));
         let s = Object::sphere();
 
         let xs = s.intersect(r);
@@ -77,7 +88,7 @@ mod tests {
 
     #[test]
     fn a_ray_misses_a_sphere() {
-        let r = Ray::new(Tuple::point(0., 2., -5.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 2., -5.), Vec3::new(0., 0., 1.));
         let s = Object::sphere();
         let xs = s.intersect(r);
 
@@ -86,7 +97,7 @@ mod tests {
 
     #[test]
     fn a_ray_originates_inside_a_sphere() {
-        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., 1.));
         let s = Object::sphere();
 
         let xs = s.intersect(r);
@@ -98,7 +109,7 @@ mod tests {
 
     #[test]
     fn a_sphere_is_behind_a_ray() {
-        let r = Ray::new(Tuple::point(0., 0., 5.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 0., 5.), Vec3::new(0., 0., 1.));
         let s = Object::sphere();
 
         let xs = s.intersect(r);
@@ -110,7 +121,7 @@ mod tests {
 
     #[test]
     fn intersect_sets_the_object_on_the_intersection() {
-        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
         let object = Object::sphere();
         let s = SimpleObject::from_object(&object).unwrap();
         let xs = object.intersect(r);
@@ -122,31 +133,31 @@ mod tests {
 
     #[test]
     fn translating_a_ray_() {
-        let r = Ray::new(Tuple::point(1., 2., 3.), Tuple::vector(0., 1., 0.));
+        let r = Ray::new(Point3::new(1., 2., 3.), Vec3::new(0., 1., 0.));
         let m = Matrix4::translation(3., 4., 5.);
 
         let r2 = r.transform(m);
 
-        assert_eq!(r2.origin, Tuple::point(4., 6., 8.));
-        assert_eq!(r2.direction, Tuple::vector(0., 1., 0.));
+        assert_eq!(r2.origin, Point3::new(4., 6., 8.));
+        assert_eq!(r2.direction, Vec3::new(0., 1., 0.));
     }
 
     #[test]
     fn scaling_a_ray_() {
-        let r = Ray::new(Tuple::point(1., 2., 3.), Tuple::vector(0., 1., 0.));
+        let r = Ray::new(Point3::new(1., 2., 3.), Vec3::new(0., 1., 0.));
         let m = Matrix4::scaling(2., 3., 4.);
 
         let r2 = r.transform(m);
 
-        assert_eq!(r2.origin, Tuple::point(2., 6., 12.));
-        assert_eq!(r2.direction, Tuple::vector(0., 3., 0.));
+        assert_eq!(r2.origin, Point3::new(2., 6., 12.));
+        assert_eq!(r2.direction, Vec3::new(0., 3., 0.));
     }
 
     #[test]
     fn intersecting_a_scaled_sphere_with_a_ray() {
-        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
         let mut object = Object::sphere();
-        object.transform = Matrix4::scaling(2., 2., 2.);
+        object.set_transform(Matrix4::scaling(2., 2., 2.));
         let s = SimpleObject::from_object(&object).unwrap();
 
         let xs = s.intersect(r);
@@ -158,9 +169,9 @@ mod tests {
 
     #[test]
     fn intersecting_a_translated_sphere_with_a_ray() {
-        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
         let mut object = Object::sphere();
-        object.transform = Matrix4::translation(5., 0., 0.);
+        object.set_transform(Matrix4::translation(5., 0., 0.));
         let s = SimpleObject::from_object(&object).unwrap();
 
         let xs = s.intersect(r);
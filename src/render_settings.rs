@@ -0,0 +1,749 @@
+use std::{
+    fmt,
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crate::{
+    color::Color,
+    error::{RayTracerError, Result},
+    integrator::{Integrator, INTEGRATORS},
+    intersection::Intersection,
+    material::Material,
+    math::{point3::Point3, vec3::Vec3},
+    ray::Ray,
+    sampler::{AdaptiveSampling, LightSampling},
+    shape::SimpleObject,
+    tone::{ToneMap, ToneOperator, TONE_OPERATORS},
+    world::World,
+    yaml::Yaml,
+};
+
+/// Decides `Color`s for camera rays, or `None` to leave them to the usual shading.
+pub type PixelHook = Box<dyn Fn(&PixelContext) -> Option<Color> + Sync>;
+
+/// Stops a render from another thread, or from its progress callback, see `RenderSettings::cancel`.
+/// Clones share the same flag, so one can go in the settings and the other cancel the render.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops renders using the token after the rows they are rendering.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// How a camera renders a world, beyond where it looks from.
+#[derive(Default)]
+pub struct RenderSettings {
+    /// Called for every camera ray before it's shaded, to render something else instead,
+    /// like texture coordinates or which object is where.
+    pub pixel_hook: Option<PixelHook>,
+    /// How many threads render rows of the image, as many as the machine can run at once if `None`.
+    pub threads: Option<usize>,
+    /// Renders every object with a stand-in material instead of its own.
+    pub material_override: Option<MaterialOverride>,
+    /// Samples per pixel in place of the camera's own, see `Sampler`.
+    pub samples_per_pixel: Option<usize>,
+    /// Light sampling in place of the camera's own, see `Sampler`.
+    pub light_sampling: Option<LightSampling>,
+    /// Fewer samples for pixels a quick prepass shows little going on in, see `AdaptiveSampling`.
+    pub adaptive: Option<AdaptiveSampling>,
+    /// How many times rays bounce off or go through surfaces, the world's own if `None`,
+    /// see `World::set_max_depth`.
+    pub max_depth: Option<i32>,
+    /// Integrator in place of the world's own, see `World::integrator`.
+    pub integrator: Option<Integrator>,
+    /// Stops the render once cancelled, see `Camera::try_render_with_settings`.
+    pub cancel: Option<CancelToken>,
+    /// Seed in place of the camera's `Sampler::seed` and of `World::seed`, to get
+    /// different noise, or the same noise every time.
+    pub seed: Option<u64>,
+    /// Renders only this band of rows, into a canvas as tall as the band, see `Tile`.
+    pub tile: Option<Tile>,
+    /// How the render is brought into what 8 bit images can show when it's exported,
+    /// see `Canvas::tone_mapped`. Renders themselves keep colors over 1.
+    pub tone_map: Option<ToneMap>,
+}
+
+/// The `index`th of `count` bands of rows splitting the image as evenly as they can,
+/// counting from 1 at the top, so separate renders can each take a part of an image.
+/// Pixels come out the same as they do in the whole image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tile {
+    pub index: usize,
+    pub count: usize,
+}
+
+impl Tile {
+    pub fn new(index: usize, count: usize) -> Result<Self> {
+        if index == 0 || index > count {
+            return Err(invalid_settings(&format!(
+                "tiles go from 1 to their count, not {}/{}",
+                index, count
+            )));
+        }
+
+        Ok(Self { index, count })
+    }
+
+    /// Reads tiles written `index/count`, like `2/4`.
+    pub fn parse(text: &str) -> Result<Self> {
+        let invalid = || invalid_settings(&format!("tiles are written `i/N`, not `{}`", text));
+        let (index, count) = text.split_once('/').ok_or_else(invalid)?;
+
+        Self::new(
+            index.trim().parse().map_err(|_| invalid())?,
+            count.trim().parse().map_err(|_| invalid())?,
+        )
+    }
+
+    /// The rows of an image `height` pixels tall that are in the tile.
+    pub fn rows(self, height: usize) -> Range<usize> {
+        let edge = |index: usize| index * height / self.count;
+
+        edge(self.index - 1)..edge(self.index)
+    }
+}
+
+impl fmt::Display for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+/// The names `RenderSettings::preset` knows.
+pub const PRESETS: [&str; 3] = ["draft", "preview", "production"];
+
+impl RenderSettings {
+    /// As fast as it gets, to check where things are: a sample per pixel, each shaded by
+    /// a single light, and rays bouncing twice at most.
+    pub fn draft() -> Self {
+        Self {
+            samples_per_pixel: Some(1),
+            light_sampling: Some(LightSampling::OnePerSample),
+            max_depth: Some(2),
+            ..Default::default()
+        }
+    }
+
+    /// Good enough to judge the look of a scene: 4 samples per pixel shaded by every light,
+    /// and rays bouncing 4 times at most.
+    pub fn preview() -> Self {
+        Self {
+            samples_per_pixel: Some(4),
+            light_sampling: Some(LightSampling::All),
+            max_depth: Some(4),
+            ..Default::default()
+        }
+    }
+
+    /// For the final image: 16 samples per pixel shaded by every light,
+    /// and rays bouncing 8 times at most.
+    pub fn production() -> Self {
+        Self {
+            samples_per_pixel: Some(16),
+            light_sampling: Some(LightSampling::All),
+            max_depth: Some(8),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelToken::is_cancelled)
+    }
+
+    /// The preset called `name`, one of `PRESETS`.
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "draft" => Some(Self::draft()),
+            "preview" => Some(Self::preview()),
+            "production" => Some(Self::production()),
+            _ => None,
+        }
+    }
+
+    pub fn from_file(file_path: &str) -> Result<Self> {
+        let source = std::fs::read_to_string(file_path)?;
+
+        Self::from_yaml(&source)
+    }
+
+    /// Reads settings saved by `to_yaml`: a mapping of the fields to set, leaving out
+    /// the ones to keep as they are. A `preset` key starts from that preset instead of
+    /// the defaults. Pixel hooks can't be saved.
+    pub fn from_yaml(source: &str) -> Result<Self> {
+        let document = Yaml::parse(source)?;
+        let entries = match &document {
+            Yaml::Null => &[][..],
+            document => document
+                .as_mapping()
+                .ok_or_else(|| invalid_settings("render settings must be a mapping"))?,
+        };
+
+        let mut settings = match document.get("preset") {
+            Some(name) => {
+                let name = name
+                    .as_str()
+                    .ok_or_else(|| invalid_settings("`preset` must be a name"))?;
+
+                Self::preset(name)
+                    .ok_or_else(|| invalid_settings(&format!("unknown preset `{}`", name)))?
+            }
+            None => Self::default(),
+        };
+
+        for (key, value) in entries {
+            match key.as_str() {
+                "preset" => {}
+                "threads" => settings.threads = Some(count(key, value)?),
+                "samples_per_pixel" => settings.samples_per_pixel = Some(count(key, value)?),
+                "max_depth" => settings.max_depth = Some(count(key, value)? as i32),
+                "adaptive_min_samples" => {
+                    let adaptive = settings.adaptive.get_or_insert_with(Default::default);
+                    *adaptive = AdaptiveSampling::new(count(key, value)?, adaptive.prepass_scale);
+                }
+                "prepass_scale" => {
+                    let adaptive = settings.adaptive.get_or_insert_with(Default::default);
+                    *adaptive = AdaptiveSampling::new(adaptive.min_samples, count(key, value)?);
+                }
+                "seed" => settings.seed = Some(count(key, value)? as u64),
+                "light_sampling" => {
+                    settings.light_sampling = Some(match value.as_str() {
+                        Some("all") => LightSampling::All,
+                        Some("one_per_sample") => LightSampling::OnePerSample,
+                        _ => return Err(invalid_value(key)),
+                    })
+                }
+                "integrator" => {
+                    settings.integrator =
+                        Some(value.as_str().and_then(Integrator::named).ok_or_else(|| {
+                            invalid_settings(&format!(
+                                "`integrator` must be one of: {}",
+                                INTEGRATORS.join(", ")
+                            ))
+                        })?)
+                }
+                "tile" => {
+                    settings.tile = Some(
+                        value
+                            .as_str()
+                            .ok_or_else(|| invalid_value(key))
+                            .and_then(Tile::parse)?,
+                    )
+                }
+                "tone_map" => {
+                    let operator =
+                        value
+                            .as_str()
+                            .and_then(ToneOperator::named)
+                            .ok_or_else(|| {
+                                invalid_settings(&format!(
+                                    "`tone_map` must be one of: {}",
+                                    TONE_OPERATORS.join(", ")
+                                ))
+                            })?;
+                    settings
+                        .tone_map
+                        .get_or_insert_with(ToneMap::default)
+                        .operator = operator;
+                }
+                "exposure" => {
+                    settings
+                        .tone_map
+                        .get_or_insert_with(ToneMap::default)
+                        .exposure = value
+                        .as_f64()
+                        .filter(|exposure| exposure.is_finite())
+                        .ok_or_else(|| invalid_value(key))?
+                }
+                "material_override" => {
+                    settings.material_override = Some(match value.as_str() {
+                        Some("clay") => MaterialOverride::Clay,
+                        Some("by_object") => MaterialOverride::ByObject,
+                        _ => return Err(invalid_value(key)),
+                    })
+                }
+                _ => return Err(invalid_settings(&format!("unknown setting `{}`", key))),
+            }
+        }
+
+        Ok(settings)
+    }
+
+    /// Writes the settings `from_yaml` reads back, all but the pixel hook.
+    /// Integrators are saved by name, with their usual settings.
+    pub fn to_yaml(&self) -> String {
+        let mut yaml = String::new();
+
+        if let Some(threads) = self.threads {
+            yaml += &format!("threads: {}\n", threads);
+        }
+        if let Some(samples) = self.samples_per_pixel {
+            yaml += &format!("samples_per_pixel: {}\n", samples);
+        }
+        if let Some(depth) = self.max_depth {
+            yaml += &format!("max_depth: {}\n", depth);
+        }
+        if let Some(adaptive) = self.adaptive {
+            yaml += &format!("adaptive_min_samples: {}\n", adaptive.min_samples);
+            yaml += &format!("prepass_scale: {}\n", adaptive.prepass_scale);
+        }
+        if let Some(seed) = self.seed {
+            yaml += &format!("seed: {}\n", seed);
+        }
+        if let Some(light_sampling) = self.light_sampling {
+            let name = match light_sampling {
+                LightSampling::All => "all",
+                LightSampling::OnePerSample => "one_per_sample",
+            };
+            yaml += &format!("light_sampling: {}\n", name);
+        }
+        if let Some(integrator) = self.integrator {
+            yaml += &format!("integrator: {}\n", integrator.name());
+        }
+        if let Some(material_override) = self.material_override {
+            let name = match material_override {
+                MaterialOverride::Clay => "clay",
+                MaterialOverride::ByObject => "by_object",
+            };
+            yaml += &format!("material_override: {}\n", name);
+        }
+        if let Some(tile) = self.tile {
+            yaml += &format!("tile: {}\n", tile);
+        }
+        if let Some(tone_map) = self.tone_map {
+            yaml += &format!("tone_map: {}\n", tone_map.operator.name());
+            yaml += &format!("exposure: {}\n", tone_map.exposure);
+        }
+
+        yaml
+    }
+
+    /// A copy of the world changed the way the settings say, if they change anything, with
+    /// the boxes of `World::debug_draw_bounds` added to it.
+    pub(crate) fn world_for(&self, world: &World) -> Option<World> {
+        let integrator = self
+            .integrator
+            .filter(|integrator| *integrator != world.integrator);
+        let seed = self.seed.filter(|seed| *seed != world.seed);
+        if self.material_override.is_none()
+            && integrator.is_none()
+            && seed.is_none()
+            && !world.draws_bounds
+        {
+            return None;
+        }
+
+        let mut world = match self.material_override {
+            Some(material_override) => material_override.apply(world),
+            None => world.clone(),
+        };
+        if let Some(integrator) = integrator {
+            world.integrator = integrator;
+        }
+        if let Some(seed) = seed {
+            world.seed = seed;
+        }
+        if world.draws_bounds {
+            let boxes = world.bounds_objects();
+            world.objects.extend(boxes);
+        }
+
+        Some(world)
+    }
+}
+
+fn count(key: &str, value: &Yaml) -> Result<usize> {
+    match value.as_f64() {
+        Some(number) if number >= 0. && number.fract() == 0. => Ok(number as usize),
+        _ => Err(invalid_value(key)),
+    }
+}
+
+fn invalid_value(key: &str) -> RayTracerError {
+    invalid_settings(&format!("invalid value for `{}`", key))
+}
+
+fn invalid_settings(message: &str) -> RayTracerError {
+    RayTracerError::InvalidSettings(message.to_string())
+}
+
+/// Materials to render a whole world with, to look at parts of it without editing every material.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaterialOverride {
+    /// Everything matte gray, to judge the lighting on its own.
+    Clay,
+    /// Every object in `World::objects` a flat color of its own, groups included as a whole,
+    /// to tell which is which.
+    ByObject,
+}
+
+impl MaterialOverride {
+    /// A copy of the world with the materials replaced.
+    pub(crate) fn apply(self, world: &World) -> World {
+        let mut world = world.clone();
+
+        for (index, object) in world.objects.iter_mut().enumerate() {
+            object.set_material(match self {
+                MaterialOverride::Clay => {
+                    let mut material = Material::new();
+                    material.color = Color::new(0.8, 0.8, 0.8);
+                    material.specular = 0.;
+
+                    material
+                }
+                MaterialOverride::ByObject => {
+                    let mut material = Material::new();
+                    material.color = object_color(index);
+                    material.ambient = 1.;
+                    material.diffuse = 0.;
+                    material.specular = 0.;
+
+                    material
+                }
+            });
+        }
+
+        world
+    }
+}
+
+/// Bright colors with hues spread around the color wheel by the golden ratio,
+/// so objects next to each other in the list look nothing alike.
+fn object_color(index: usize) -> Color {
+    let hue = (index as f64 * 0.618_033_988_75).fract() * 6.;
+    let ramp = |offset: f64| (((hue + offset) % 6. - 3.).abs() - 1.).clamp(0., 1.);
+
+    Color::new(ramp(0.), ramp(4.), ramp(2.))
+}
+
+/// What a camera ray sees, for `RenderSettings::pixel_hook`.
+pub struct PixelContext<'a> {
+    pub x: i32,
+    pub y: i32,
+    pub ray: Ray,
+    /// How many times the ray crosses a surface, hidden ones included.
+    pub intersection_count: usize,
+    /// The closest thing the ray hits, if any.
+    pub hit: Option<PixelHit<'a>>,
+}
+
+pub struct PixelHit<'a> {
+    pub t: f64,
+    pub point: Point3,
+    /// Facing the ray, like the normals used for shading.
+    pub normal: Vec3,
+    /// The normal of the surface itself, without the smoothing of smooth triangles
+    /// or the ripples of water, also facing the ray.
+    pub geometric_normal: Vec3,
+    pub object: SimpleObject<'a>,
+    /// Index in `World::objects` of the object that was hit, or of the group it's in.
+    pub object_index: usize,
+    /// Where the ray hits, for shapes that know their coordinates, like triangles.
+    pub uv: Option<(f64, f64)>,
+}
+
+impl<'a> PixelContext<'a> {
+    pub(crate) fn new(world: &'a World, x: i32, y: i32, ray: Ray) -> Self {
+        let intersections = world.intersect(ray);
+        let hit = Intersection::hit(&intersections).map(|hit| {
+            let comps = hit.prepare_computations(ray, &intersections);
+
+            PixelHit {
+                t: hit.t,
+                point: ray.position(hit.t),
+                normal: comps.normal_vector,
+                geometric_normal: comps.geometric_normal,
+                object: hit.object,
+                object_index: world
+                    .objects
+                    .iter()
+                    .position(|object| object.includes(hit.object))
+                    .expect("hits come from the objects of the world"),
+                uv: hit.uv(),
+            }
+        });
+
+        Self {
+            x,
+            y,
+            ray,
+            intersection_count: intersections.len(),
+            hit,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::tuple::Tuple;
+    use crate::{
+        camera::Camera,
+        light::Light,
+        math::{matrix4::Matrix4, transformations::view_transform},
+        shape::Object,
+    };
+    use std::f64::consts::PI;
+
+    fn camera() -> Camera {
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.transform = view_transform(
+            Tuple::point(0., 0., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        );
+
+        c
+    }
+
+    fn quality(settings: &RenderSettings) -> (Option<usize>, Option<LightSampling>, Option<i32>) {
+        (
+            settings.samples_per_pixel,
+            settings.light_sampling,
+            settings.max_depth,
+        )
+    }
+
+    #[test]
+    fn what_each_preset_means() {
+        assert_eq!(
+            quality(&RenderSettings::draft()),
+            (Some(1), Some(LightSampling::OnePerSample), Some(2))
+        );
+        assert_eq!(
+            quality(&RenderSettings::preview()),
+            (Some(4), Some(LightSampling::All), Some(4))
+        );
+        assert_eq!(
+            quality(&RenderSettings::production()),
+            (Some(16), Some(LightSampling::All), Some(8))
+        );
+
+        for name in PRESETS {
+            let preset = RenderSettings::preset(name).unwrap();
+            assert_eq!(preset.threads, None);
+            assert_eq!(preset.material_override, None);
+            assert_eq!(preset.integrator, None);
+        }
+        assert!(RenderSettings::preset("ultra").is_none());
+    }
+
+    #[test]
+    fn settings_are_read_back_as_they_were_saved() {
+        let settings = RenderSettings {
+            threads: Some(3),
+            material_override: Some(MaterialOverride::ByObject),
+            integrator: Integrator::named("path_trace"),
+            seed: Some(42),
+            tile: Some(Tile::new(2, 3).unwrap()),
+            tone_map: Some(ToneMap::new(ToneOperator::Aces, -0.5)),
+            adaptive: Some(AdaptiveSampling::new(2, 3)),
+            ..RenderSettings::draft()
+        };
+
+        let read = RenderSettings::from_yaml(&settings.to_yaml()).unwrap();
+
+        assert_eq!(quality(&read), quality(&settings));
+        assert_eq!(read.threads, Some(3));
+        assert_eq!(read.material_override, Some(MaterialOverride::ByObject));
+        assert_eq!(read.integrator, settings.integrator);
+        assert_eq!(read.seed, Some(42));
+        assert_eq!(read.tile, settings.tile);
+        assert_eq!(read.tone_map, settings.tone_map);
+        assert_eq!(read.adaptive, settings.adaptive);
+    }
+
+    #[test]
+    fn tiles_split_the_rows_between_them() {
+        let tiles: Vec<_> = (1..=4)
+            .map(|index| Tile::parse(&format!("{}/4", index)).unwrap().rows(10))
+            .collect();
+
+        assert_eq!(tiles, [0..2, 2..5, 5..7, 7..10]);
+        // More tiles than rows leave some of them empty.
+        assert_eq!(Tile::new(1, 3).unwrap().rows(2), 0..0);
+        for text in ["0/4", "5/4", "1/0", "2", "a/b", "1/2/3"] {
+            assert!(Tile::parse(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn saved_settings_can_start_from_a_preset() {
+        let read = RenderSettings::from_yaml("preset: production\nmax_depth: 3\n").unwrap();
+
+        assert_eq!(
+            quality(&read),
+            (Some(16), Some(LightSampling::All), Some(3))
+        );
+        assert!(RenderSettings::from_yaml("preset: ultra\n").is_err());
+        assert!(RenderSettings::from_yaml("samples_per_pixel: -1\n").is_err());
+        assert!(RenderSettings::from_yaml("samples: 4\n").is_err());
+    }
+
+    #[test]
+    fn settings_take_over_from_the_sampler_of_the_camera() {
+        let w = World::default();
+        let mut c = camera();
+        c.set_samples_per_pixel(4);
+        let settings = RenderSettings {
+            samples_per_pixel: Some(1),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            c.render_with_settings(&w, &settings, |_, _| {})
+                .pixel_at(6, 4),
+            camera().render(&w).pixel_at(6, 4)
+        );
+        assert_ne!(
+            c.render(&w).pixel_at(6, 4),
+            camera().render(&w).pixel_at(6, 4)
+        );
+    }
+
+    #[test]
+    fn the_seed_decides_the_noise_of_a_render() {
+        let mut w = World::new();
+        w.add_light(Light::point_light(
+            Point3::new(-10., 10., -10.),
+            Color::white(),
+        ));
+        let mut floor = Object::plane();
+        floor.set_transform(Matrix4::translation(0., -1., 0.));
+        w.add_object(floor);
+        w.add_object(Object::sphere());
+        w.integrator = Integrator::AmbientOcclusion {
+            samples: 3,
+            distance: 2.,
+        };
+        let mut c = camera();
+        c.set_samples_per_pixel(4);
+        let render = |seed| {
+            let settings = RenderSettings {
+                seed: Some(seed),
+                ..Default::default()
+            };
+
+            c.render_with_settings(&w, &settings, |_, _| {}).to_ppm()
+        };
+
+        assert_eq!(render(1), render(1));
+        assert_ne!(render(1), render(2));
+    }
+
+    #[test]
+    fn settings_limit_how_many_times_rays_bounce() {
+        let mut w = World::default();
+        let mut floor = Object::plane();
+        floor.set_transform(Matrix4::translation(0., -1., 0.));
+        floor.set_material({
+            let mut mirror = Material::new();
+            mirror.reflective = 0.5;
+            mirror
+        });
+        w.add_object(floor);
+        let settings = RenderSettings {
+            max_depth: Some(0),
+            ..Default::default()
+        };
+
+        let no_bounces = camera().render_with_settings(&w, &settings, |_, _| {});
+        let bounces = camera().render_with_settings(&w, &RenderSettings::default(), |_, _| {});
+
+        assert_ne!(no_bounces.pixel_at(5, 8), bounces.pixel_at(5, 8));
+    }
+
+    #[test]
+    fn the_pixel_hook_sees_what_camera_rays_hit() {
+        let w = World::default();
+        let settings = RenderSettings {
+            pixel_hook: Some(Box::new(|context: &PixelContext| {
+                let hit = context.hit.as_ref()?;
+
+                Some(Color::new(
+                    hit.object_index as f64,
+                    context.intersection_count as f64,
+                    hit.normal.z,
+                ))
+            })),
+            ..Default::default()
+        };
+
+        let image = camera().render_with_settings(&w, &settings, |_, _| {});
+
+        assert_eq!(image.pixel_at(5, 5), Color::new(0., 4., -1.));
+    }
+
+    #[test]
+    fn pixels_the_hook_passes_on_are_shaded_as_usual() {
+        let w = World::default();
+        let settings = RenderSettings {
+            pixel_hook: Some(Box::new(|context: &PixelContext| {
+                (context.x == 0).then(Color::white)
+            })),
+            ..Default::default()
+        };
+
+        let image = camera().render_with_settings(&w, &settings, |_, _| {});
+
+        assert_eq!(image.pixel_at(0, 5), Color::white());
+        assert_eq!(image.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn material_overrides_replace_every_material() {
+        let w = World::default();
+        let render = |material_override| {
+            let settings = RenderSettings {
+                material_override: Some(material_override),
+                ..Default::default()
+            };
+
+            camera().render_with_settings(&w, &settings, |_, _| {})
+        };
+
+        let clay = render(MaterialOverride::Clay);
+        let pixel = clay.pixel_at(5, 5);
+        assert!(pixel.red == pixel.green && pixel.green == pixel.blue);
+        assert!(pixel.red > 0.1);
+
+        let by_object = render(MaterialOverride::ByObject);
+        assert_eq!(by_object.pixel_at(5, 5), object_color(0));
+        assert_eq!(by_object.pixel_at(0, 0), Color::black());
+
+        // The world itself is left as it was.
+        assert_eq!(
+            w.objects[0].intersect(camera().ray_for_pixel(5, 5))[0]
+                .object
+                .material
+                .color,
+            Color::new(0.8, 1.0, 0.6)
+        );
+    }
+
+    #[test]
+    fn objects_get_colors_of_their_own() {
+        let colors: Vec<Color> = (0..8).map(object_color).collect();
+
+        for (i, a) in colors.iter().enumerate() {
+            for b in &colors[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+        assert_eq!(object_color(0), Color::red());
+    }
+}
@@ -0,0 +1,443 @@
+use crate::canvas::Canvas;
+
+/// Xorshift64*, a small seedable random number generator.
+#[derive(Clone, Copy, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift gets stuck at 0, and nearby seeds should still diverge quickly.
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform in [0, 1).
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in [min, max).
+    pub fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + self.next_f64() * (max - min)
+    }
+
+    /// Uniform in [0, n).
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_f64() * n as f64) as usize
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+
+    /// `count` points in the unit square, spread out more evenly than uniform ones so
+    /// averages over them are less noisy: one in each cell of a grid when `count` is square,
+    /// otherwise one in each column and each row of a `count` by `count` grid, rows shuffled.
+    pub fn stratified(&mut self, count: usize) -> Vec<(f64, f64)> {
+        let strata = (count as f64).sqrt().round() as usize;
+
+        if strata * strata == count {
+            let cell_size = 1. / strata as f64;
+
+            (0..count)
+                .map(|index| {
+                    let (column, row) = ((index % strata) as f64, (index / strata) as f64);
+
+                    (
+                        (column + self.next_f64()) * cell_size,
+                        (row + self.next_f64()) * cell_size,
+                    )
+                })
+                .collect()
+        } else {
+            let cell_size = 1. / count as f64;
+            let mut rows: Vec<usize> = (0..count).collect();
+            self.shuffle(&mut rows);
+
+            rows.into_iter()
+                .enumerate()
+                .map(|(column, row)| {
+                    (
+                        (column as f64 + self.next_f64()) * cell_size,
+                        (row as f64 + self.next_f64()) * cell_size,
+                    )
+                })
+                .collect()
+        }
+    }
+}
+
+/// How the lights of the world are used by each sample of a pixel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LightSampling {
+    /// Every sample is shaded by every light.
+    All,
+    /// Every sample is shaded by a single light, scaled by the amount of lights.
+    /// Cheaper with many lights, at the cost of noise that goes away with more samples.
+    OnePerSample,
+}
+
+/// Decides where the samples of each pixel go, and which lights shade them.
+///
+/// Everything is derived from the seed and the pixel coordinates,
+/// so renders are reproducible and independent of the order pixels are computed in.
+/// Random directions picked while shading go by `World::seed` instead, which
+/// `RenderSettings::seed` sets along with this one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sampler {
+    pub samples_per_pixel: usize,
+    pub light_sampling: LightSampling,
+    pub seed: u64,
+}
+
+impl Sampler {
+    pub fn new(samples_per_pixel: usize) -> Self {
+        Self {
+            samples_per_pixel: samples_per_pixel.max(1),
+            light_sampling: LightSampling::All,
+            seed: 0,
+        }
+    }
+
+    pub(crate) fn pixel(&self, x: i32, y: i32, light_count: usize) -> PixelSampler {
+        self.pixel_with_samples(x, y, light_count, self.samples_per_pixel)
+    }
+
+    /// Like `pixel`, taking `samples` samples instead of `samples_per_pixel`.
+    pub(crate) fn pixel_with_samples(
+        &self,
+        x: i32,
+        y: i32,
+        light_count: usize,
+        samples: usize,
+    ) -> PixelSampler {
+        let samples = samples.max(1);
+        let pixel_seed = self.seed ^ ((x as u64) << 32 | (y as u32) as u64);
+        let mut rng = Rng::new(pixel_seed);
+
+        let light_order = match self.light_sampling {
+            LightSampling::OnePerSample if light_count > 1 => {
+                let mut order: Vec<usize> = (0..light_count).collect();
+                rng.shuffle(&mut order);
+                order
+            }
+            _ => vec![],
+        };
+
+        PixelSampler {
+            rng,
+            samples,
+            strata: (samples as f64).sqrt().ceil() as usize,
+            light_order,
+        }
+    }
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+/// Spends the samples of each pixel where they're needed, going by an `ImportanceMap` of a
+/// quick render at a fraction of the size: pixels where it's flat take `min_samples`,
+/// and the ones on its edges and details up to the usual samples per pixel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdaptiveSampling {
+    pub min_samples: usize,
+    /// How many times smaller the quick render is on each side, 1 rendering it full size.
+    pub prepass_scale: usize,
+}
+
+impl AdaptiveSampling {
+    pub fn new(min_samples: usize, prepass_scale: usize) -> Self {
+        Self {
+            min_samples: min_samples.max(1),
+            prepass_scale: prepass_scale.max(1),
+        }
+    }
+
+    /// How many of `max_samples` a pixel with `importance` takes.
+    pub(crate) fn samples(self, importance: f64, max_samples: usize) -> usize {
+        let min_samples = self.min_samples.min(max_samples);
+        let extra = (importance * (max_samples - min_samples) as f64).round() as usize;
+
+        (min_samples + extra).min(max_samples)
+    }
+}
+
+impl Default for AdaptiveSampling {
+    fn default() -> Self {
+        Self::new(1, 4)
+    }
+}
+
+/// How much every part of an image needs more samples, from 0 where it's flat to 1 on
+/// the sharpest edges, going by how much the luminance of each pixel stands out from its
+/// neighbours relative to how bright they are, so dark edges count as much as bright ones.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportanceMap {
+    width: usize,
+    height: usize,
+    values: Vec<f64>,
+}
+
+impl ImportanceMap {
+    pub fn from_canvas(canvas: &Canvas) -> Self {
+        let (width, height) = (canvas.width(), canvas.height());
+        let luminance =
+            |x: usize, y: usize| canvas.pixel_at(x as i32, y as i32).luminance().max(0.);
+
+        let mut values = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let center = luminance(x, y);
+                let mut contrast: f64 = 0.;
+                for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                    for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                        let neighbour = luminance(nx, ny);
+                        if center + neighbour > 0. {
+                            contrast =
+                                contrast.max((center - neighbour).abs() / (center + neighbour));
+                        }
+                    }
+                }
+                values.push(contrast);
+            }
+        }
+
+        Self {
+            width,
+            height,
+            values,
+        }
+    }
+
+    /// The importance at `(u, v)`, from 0 to 1 across and down the image, whatever its size.
+    pub fn at(&self, u: f64, v: f64) -> f64 {
+        if self.values.is_empty() {
+            return 1.;
+        }
+        let x = ((u * self.width as f64) as usize).min(self.width - 1);
+        let y = ((v * self.height as f64) as usize).min(self.height - 1);
+
+        self.values[y * self.width + x]
+    }
+}
+
+/// Which lights shade a single sample.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LightSelection {
+    All,
+    /// Only the light at this index, standing in for all of them.
+    One(usize),
+}
+
+pub(crate) struct PixelSampler {
+    rng: Rng,
+    samples: usize,
+    strata: usize,
+    /// A shuffled permutation of the lights, empty if every sample uses all of them.
+    light_order: Vec<usize>,
+}
+
+impl PixelSampler {
+    pub(crate) fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Offset of the `index`th sample within the pixel, with each coordinate in [0, 1).
+    ///
+    /// A single sample goes through the center, more are jittered within a grid of strata.
+    pub(crate) fn offset(&mut self, index: usize) -> (f64, f64) {
+        if self.samples == 1 {
+            return (0.5, 0.5);
+        }
+
+        let cell_size = 1. / self.strata as f64;
+        let column = (index % self.strata) as f64;
+        let row = (index / self.strata % self.strata) as f64;
+
+        (
+            (column + self.rng.next_f64()) * cell_size,
+            (row + self.rng.next_f64()) * cell_size,
+        )
+    }
+
+    /// A point on the unit disk for a sample to start from, for lenses.
+    pub(crate) fn lens(&mut self) -> (f64, f64) {
+        let angle = 2. * std::f64::consts::PI * self.rng.next_f64();
+        let radius = self.rng.next_f64().sqrt();
+
+        (radius * angle.cos(), radius * angle.sin())
+    }
+
+    /// The light shading the `index`th sample.
+    ///
+    /// Walking through the pixel's own permutation means every light is used
+    /// once before any is repeated, whatever the sample count,
+    /// while the shuffle keeps neighbouring pixels from lining up.
+    pub(crate) fn light(&self, index: usize) -> LightSelection {
+        if self.light_order.is_empty() {
+            LightSelection::All
+        } else {
+            LightSelection::One(self.light_order[index % self.light_order.len()])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn the_same_seed_gives_the_same_numbers() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let mut c = Rng::new(43);
+
+        let xs: Vec<u64> = (0..10).map(|_| a.next_u64()).collect();
+        let ys: Vec<u64> = (0..10).map(|_| b.next_u64()).collect();
+        let zs: Vec<u64> = (0..10).map(|_| c.next_u64()).collect();
+
+        assert_eq!(xs, ys);
+        assert_ne!(xs, zs);
+    }
+
+    #[test]
+    fn a_single_sample_goes_through_the_center_of_the_pixel() {
+        let sampler = Sampler::new(1);
+        let mut pixel = sampler.pixel(3, 4, 5);
+
+        assert_eq!(pixel.samples(), 1);
+        assert_eq!(pixel.offset(0), (0.5, 0.5));
+        assert_eq!(pixel.light(0), LightSelection::All);
+    }
+
+    #[test]
+    fn samples_are_spread_over_one_stratum_each() {
+        let sampler = Sampler::new(4);
+        let mut pixel = sampler.pixel(0, 0, 1);
+        let offsets: Vec<(f64, f64)> = (0..4).map(|i| pixel.offset(i)).collect();
+
+        assert!(offsets[0].0 < 0.5 && offsets[0].1 < 0.5);
+        assert!(offsets[1].0 >= 0.5 && offsets[1].1 < 0.5);
+        assert!(offsets[2].0 < 0.5 && offsets[2].1 >= 0.5);
+        assert!(offsets[3].0 >= 0.5 && offsets[3].1 >= 0.5);
+    }
+
+    #[test]
+    fn importance_is_highest_on_edges() {
+        let mut canvas = Canvas::new(4, 2);
+        for y in 0..2 {
+            canvas.write_pixel(2, y, Color::white());
+            canvas.write_pixel(3, y, Color::white());
+        }
+
+        let importance = ImportanceMap::from_canvas(&canvas);
+
+        assert_eq!(importance.at(0., 0.), 0.);
+        assert_eq!(importance.at(0.3, 0.9), 1.);
+        assert_eq!(importance.at(0.6, 0.5), 1.);
+        assert_eq!(importance.at(1., 1.), 0.);
+
+        let adaptive = AdaptiveSampling::new(2, 4);
+        assert_eq!(adaptive.samples(0., 16), 2);
+        assert_eq!(adaptive.samples(0.5, 16), 9);
+        assert_eq!(adaptive.samples(1., 16), 16);
+        assert_eq!(adaptive.samples(1., 1), 1);
+    }
+
+    #[test]
+    fn every_light_is_used_before_any_is_repeated() {
+        let mut sampler = Sampler::new(8);
+        sampler.light_sampling = LightSampling::OnePerSample;
+        let pixel = sampler.pixel(7, 2, 4);
+
+        let mut first: Vec<LightSelection> = (0..4).map(|i| pixel.light(i)).collect();
+        let second: Vec<LightSelection> = (4..8).map(|i| pixel.light(i)).collect();
+        assert_eq!(first, second);
+
+        first.sort_by_key(|selection| match selection {
+            LightSelection::One(index) => *index,
+            LightSelection::All => usize::MAX,
+        });
+        assert_eq!(
+            first,
+            (0..4)
+                .map(LightSelection::One)
+                .collect::<Vec<LightSelection>>()
+        );
+    }
+
+    #[test]
+    fn neighbouring_pixels_shuffle_lights_differently() {
+        let mut sampler = Sampler::new(1);
+        sampler.light_sampling = LightSampling::OnePerSample;
+
+        let first_lights: Vec<LightSelection> =
+            (0..16).map(|x| sampler.pixel(x, 0, 8).light(0)).collect();
+
+        assert!(first_lights.iter().any(|light| *light != first_lights[0]));
+    }
+
+    #[test]
+    fn a_single_light_is_never_sampled() {
+        let mut sampler = Sampler::new(4);
+        sampler.light_sampling = LightSampling::OnePerSample;
+
+        assert_eq!(sampler.pixel(0, 0, 1).light(0), LightSelection::All);
+    }
+
+    #[test]
+    fn stratified_points_fill_every_cell() {
+        let mut rng = Rng::new(5);
+        let distinct = |values: Vec<(usize, usize)>| {
+            values
+                .into_iter()
+                .collect::<std::collections::BTreeSet<_>>()
+                .len()
+        };
+
+        for count in [1, 4, 9] {
+            let strata = (count as f64).sqrt();
+            let cells = rng
+                .stratified(count)
+                .into_iter()
+                .map(|(x, y)| ((x * strata) as usize, (y * strata) as usize))
+                .collect();
+
+            assert_eq!(distinct(cells), count);
+        }
+
+        for count in [2, 5, 12] {
+            let points = rng.stratified(count);
+            let cell = |value: f64| (value * count as f64) as usize;
+
+            assert_eq!(points.len(), count);
+            assert_eq!(
+                distinct(points.iter().map(|&(x, _)| (cell(x), 0)).collect()),
+                count
+            );
+            assert_eq!(
+                distinct(points.iter().map(|&(_, y)| (0, cell(y))).collect()),
+                count
+            );
+        }
+    }
+
+    #[test]
+    fn stratified_points_depend_on_the_seed_only() {
+        assert_eq!(Rng::new(8).stratified(6), Rng::new(8).stratified(6));
+        assert_ne!(Rng::new(8).stratified(6), Rng::new(9).stratified(6));
+    }
+}
@@ -0,0 +1,143 @@
+//! Placing many copies of an object over the surface of another, like pebbles on the ground
+//! or moss on a rock.
+
+use std::f64::consts::PI;
+
+use crate::{
+    math::{matrix4::Matrix4, vec3::Vec3},
+    sampler::Rng,
+    shape::Object,
+};
+
+/// How finely curved targets are approximated to find points on them.
+const SCATTER_RESOLUTION: usize = 32;
+
+/// A group of `count` copies of `prototype` standing on the surface of `target`, spread
+/// evenly over its area, their y axis along the normal of the surface and turned by a
+/// random angle around it. Each copy is scaled by a random factor up to `scale_jitter`
+/// away from 1, and the same `seed` always places them the same way.
+///
+/// The group is in the same space as `target`, so it goes next to it in the world.
+/// Targets `Object::tessellate` leaves out, like planes, get no copies.
+pub fn scatter_on_surface(
+    target: &Object,
+    prototype: &Object,
+    count: usize,
+    seed: u64,
+    scale_jitter: f64,
+) -> Object {
+    let mut rng = Rng::new(seed);
+    let samples = target
+        .tessellate(SCATTER_RESOLUTION)
+        .sample_surface(count, &mut rng);
+
+    let copies = samples
+        .into_iter()
+        .map(|(point, normal)| {
+            let scale = 1. + rng.range(-scale_jitter, scale_jitter);
+            let spin = rng.range(0., 2. * PI);
+            let mut copy = prototype.clone();
+            copy.set_transform(
+                Matrix4::translation(point.x, point.y, point.z)
+                    * standing_on(normal)
+                    * Matrix4::rotation_y(spin)
+                    * Matrix4::scaling(scale, scale, scale)
+                    * prototype.transform(),
+            );
+
+            copy
+        })
+        .collect();
+
+    Object::group(copies)
+}
+
+/// A rotation taking the y axis to `normal`.
+fn standing_on(normal: Vec3) -> Matrix4 {
+    let helper = if normal.x.abs() > 0.9 {
+        Vec3::new(0., 0., 1.)
+    } else {
+        Vec3::new(1., 0., 0.)
+    };
+    let z = helper.cross(normal).normalize();
+    let x = normal.cross(z);
+
+    Matrix4::from_rows([
+        [x.x, normal.x, z.x, 0.],
+        [x.y, normal.y, z.y, 0.],
+        [x.z, normal.z, z.z, 0.],
+        [0., 0., 0., 1.],
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::tuple::Tuple;
+    use crate::{misc::approx_equal, shape::ShapeOrGroup};
+
+    fn pebbles(seed: u64) -> Object {
+        let mut pebble = Object::sphere();
+        pebble.set_transform(Matrix4::scaling(0.1, 0.05, 0.1));
+        let mut ground = Object::cube();
+        ground.set_transform(Matrix4::scaling(5., 1., 5.));
+
+        scatter_on_surface(&ground, &pebble, 50, seed, 0.2)
+    }
+
+    fn copies(group: &Object) -> &[Object] {
+        match group.shape() {
+            ShapeOrGroup::Group(children) => children,
+            _ => panic!("scattering makes groups"),
+        }
+    }
+
+    #[test]
+    fn copies_stand_on_the_surface_of_the_target() {
+        let group = pebbles(1);
+
+        assert_eq!(copies(&group).len(), 50);
+        for copy in copies(&group) {
+            let center = copy.transform() * Tuple::point(0., 0., 0.);
+            let up = copy.transform() * Tuple::vector(0., 1., 0.);
+            let normal = up.normalize();
+            // The faces of the ground are 5 units from its center, the top and bottom 1.
+            let extent = if approx_equal(normal.y.abs(), 1.) {
+                1.
+            } else {
+                5.
+            };
+
+            assert!(approx_equal(center.dot(normal), extent));
+            assert!((0.04 - 1e-9..=0.06 + 1e-9).contains(&up.magnitude()));
+        }
+    }
+
+    #[test]
+    fn the_same_seed_scatters_the_same_way() {
+        let transforms = |group: &Object| {
+            copies(group)
+                .iter()
+                .map(|copy| copy.transform())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(transforms(&pebbles(4)), transforms(&pebbles(4)));
+        assert_ne!(transforms(&pebbles(4)), transforms(&pebbles(5)));
+    }
+
+    #[test]
+    fn standing_on_a_normal_turns_the_y_axis_onto_it() {
+        for normal in [
+            Vec3::new(0., 1., 0.),
+            Vec3::new(1., 0., 0.),
+            Vec3::new(0., 0., -1.),
+            Vec3::new(1., 1., 1.).normalize(),
+        ] {
+            let rotation = standing_on(normal);
+
+            assert_eq!(rotation * Vec3::new(0., 1., 0.), normal);
+            assert!(approx_equal(rotation.determinant(), 1.));
+        }
+    }
+}
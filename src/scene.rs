@@ -0,0 +1,1421 @@
+use std::collections::HashMap;
+
+use crate::{
+    camera::Camera,
+    canvas::Canvas,
+    color::Color,
+    environment::Environment,
+    error::{RayTracerError, Result},
+    fog::Fog,
+    light::Light,
+    material::{Bump, Material},
+    math::{
+        matrix4::Matrix4, point3::Point3, transformations::view_transform, tuple::Tuple, vec3::Vec3,
+    },
+    obj::WavefrontObj,
+    pattern::{Fill, Gradient, GradientBlend, GradientShape, ImageFilter, Pattern, UvMapping},
+    shape::{cone::Cone, cylinder::Cylinder, Object},
+    world::World,
+    yaml::Yaml,
+};
+
+mod writer;
+
+/// A camera and the world it looks at, as described by a scene file.
+///
+/// Scene files use the YAML dialect of the book's bonus scenes:
+/// a list of items that either `add` a camera, light, environment, fog or shape,
+/// or `define` a named value (materials, patterns and transforms) that later items refer to.
+/// Materials can `extends` another material, setting only the keys they change.
+/// `to_yaml` writes scenes back out.
+pub struct Scene {
+    pub camera: Camera,
+    pub world: World,
+}
+
+impl Scene {
+    pub fn from_file(file_path: &str) -> Result<Scene> {
+        let source = std::fs::read_to_string(file_path)?;
+
+        Self::from_yaml(&source)
+    }
+
+    pub fn from_yaml(source: &str) -> Result<Scene> {
+        Self::load(source, true)
+    }
+
+    /// Like `from_yaml`, but refusing items that read files (OBJ meshes and image patterns),
+    /// for scenes from wherever files shouldn't be read from, like the render server.
+    pub fn from_yaml_without_files(source: &str) -> Result<Scene> {
+        Self::load(source, false)
+    }
+
+    fn load(source: &str, reads_files: bool) -> Result<Scene> {
+        let document = Yaml::parse(source)?;
+        let items = document
+            .as_sequence()
+            .ok_or_else(|| invalid_data("a scene is a list of items"))?;
+
+        let mut loader = Loader {
+            defines: HashMap::new(),
+            reads_files,
+        };
+        let mut camera = None;
+        let mut world = World::new();
+
+        for item in items {
+            if let Some(name) = item.get("define") {
+                let name = name
+                    .as_str()
+                    .ok_or_else(|| invalid_data("define names must be strings"))?;
+                loader.define(name, item)?;
+            } else if let Some(kind) = item.get("add") {
+                match kind.as_str() {
+                    Some("camera") => camera = Some(loader.camera(item)?),
+                    Some("light") => {
+                        for light in loader.lights(item)? {
+                            world.add_light(light);
+                        }
+                    }
+                    Some("environment") => world.environment = Some(environment(item)?),
+                    Some("fog") => world.fog = Some(fog(item)?),
+                    _ => {
+                        world.add_object(loader.object(item)?);
+                    }
+                }
+            } else {
+                return Err(invalid_data("items must either `add` or `define`"));
+            }
+        }
+
+        let camera = camera.ok_or_else(|| invalid_data("the scene has no camera"))?;
+
+        Ok(Scene { camera, world })
+    }
+}
+
+struct Loader {
+    defines: HashMap<String, Yaml>,
+    reads_files: bool,
+}
+
+impl Loader {
+    fn define(&mut self, name: &str, item: &Yaml) -> Result<()> {
+        let value = field(item, "value")?.clone();
+
+        let value = match item.get("extend") {
+            Some(parent) => {
+                let parent = self.lookup(parent)?;
+
+                merge(parent, &value)
+            }
+            None => value,
+        };
+
+        self.defines.insert(name.to_string(), value);
+
+        Ok(())
+    }
+
+    /// The path in the `file` of `item`, if files can be read.
+    fn file<'a>(&self, item: &'a Yaml) -> Result<&'a str> {
+        if !self.reads_files {
+            return Err(invalid_data("this scene can't read files"));
+        }
+
+        field(item, "file")?
+            .as_str()
+            .ok_or_else(|| invalid_data("`file` must be a path"))
+    }
+
+    /// Resolves references to defined values, returning anything else as is.
+    fn lookup<'a>(&'a self, value: &'a Yaml) -> Result<&'a Yaml> {
+        match value {
+            Yaml::String(name) => self
+                .defines
+                .get(name)
+                .ok_or_else(|| invalid_data(&format!("`{}` was never defined", name))),
+            _ => Ok(value),
+        }
+    }
+
+    /// Cameras with `projection: panorama` render equirectangular panoramas, with an optional
+    /// `eye-separation` for stereo pairs, as tall as they need unless they set a `height`, and
+    /// without a field of view.
+    fn camera(&self, item: &Yaml) -> Result<Camera> {
+        let size = |key: &str| match number(item, key)? {
+            size if size >= 1. && size.fract() == 0. && size <= i32::MAX as f64 => Ok(size as i32),
+            _ => Err(invalid_data(&format!(
+                "`{}` must be a whole number of pixels",
+                key
+            ))),
+        };
+        let width = size("width")?;
+        let mut camera = match item.get("projection").map(|projection| projection.as_str()) {
+            None | Some(Some("perspective")) => {
+                Camera::new(width, size("height")?, number(item, "field-of-view")?)
+            }
+            Some(Some("panorama")) => {
+                let eye_separation = optional_number(item, "eye-separation")?.unwrap_or(0.);
+                let mut camera = Camera::panorama(width, eye_separation);
+                if item.get("height").is_some() {
+                    camera.vsize = size("height")?;
+                } else if camera.vsize < 1 {
+                    return Err(invalid_data(
+                        "the panorama is too narrow to be a pixel tall",
+                    ));
+                }
+
+                camera
+            }
+            _ => {
+                return Err(invalid_data(
+                    "`projection` must be `perspective` or `panorama`",
+                ))
+            }
+        };
+        let (from, to) = (point(field(item, "from")?)?, point(field(item, "to")?)?);
+        let up = vector(field(item, "up")?)?;
+        if (to - from).magnitude() == 0. {
+            return Err(invalid_data(
+                "the camera must look `from` somewhere else than `to`",
+            ));
+        }
+        if up.magnitude() == 0. {
+            return Err(invalid_data("`up` must point somewhere"));
+        }
+        camera.transform = view_transform(from, to, up);
+        if camera.transform.inverse().is_none() {
+            return Err(invalid_data(
+                "`up` can't point along the view from `from` to `to`",
+            ));
+        }
+
+        Ok(camera)
+    }
+
+    /// A point light `at` somewhere, or an area light as the book's bonus scenes describe
+    /// them: a `corner` and the `uvec` and `vvec` sides from it, split in `usteps` by
+    /// `vsteps` cells. Area lights become a point light in the middle of every cell sharing
+    /// the intensity, and `jitter` is accepted but leaves them there.
+    fn lights(&self, item: &Yaml) -> Result<Vec<Light>> {
+        let intensity = color(field(item, "intensity")?)?;
+        let corner = match item.get("corner") {
+            Some(corner) => point3(corner)?,
+            None => {
+                return Ok(vec![Light::point_light(
+                    point3(field(item, "at")?)?,
+                    intensity,
+                )])
+            }
+        };
+        let steps = |key: &str| match number(item, key)? {
+            steps if steps >= 1. && steps.fract() == 0. => Ok(steps as usize),
+            _ => Err(invalid_data(&format!(
+                "`{}` must be a whole number of cells",
+                key
+            ))),
+        };
+        let (usteps, vsteps) = (steps("usteps")?, steps("vsteps")?);
+        let uvec = vec3(field(item, "uvec")?)? / usteps as f64;
+        let vvec = vec3(field(item, "vvec")?)? / vsteps as f64;
+        optional_bool(item, "jitter")?;
+
+        let cell_intensity = intensity * (1. / (usteps * vsteps) as f64);
+        Ok((0..vsteps)
+            .flat_map(|v| (0..usteps).map(move |u| (u, v)))
+            .map(|(u, v)| {
+                let position = corner + uvec * (u as f64 + 0.5) + vvec * (v as f64 + 0.5);
+
+                Light::point_light(position, cell_intensity)
+            })
+            .collect())
+    }
+
+    fn object(&self, item: &Yaml) -> Result<Object> {
+        let kind = field(item, "add")?.as_str().unwrap_or_default();
+
+        let mut object = match kind {
+            "sphere" => Object::sphere(),
+            "plane" => Object::plane(),
+            "disc" => Object::disc(optional_size(item, "radius", 1.)?),
+            "quad" => Object::quad(
+                optional_size(item, "width", 2.)?,
+                optional_size(item, "depth", 2.)?,
+            ),
+            "cube" => Object::cube(),
+            "cylinder" => {
+                let (radius_x, radius_z) = radii(item)?;
+                let cylinder = Cylinder::truncated(
+                    optional_number(item, "min")?.unwrap_or(f64::NEG_INFINITY),
+                    optional_number(item, "max")?.unwrap_or(f64::INFINITY),
+                    optional_bool(item, "closed")?.unwrap_or(false),
+                )?
+                .with_radii(radius_x, radius_z)?;
+
+                Object::primitive(cylinder)
+            }
+            "cone" => {
+                let (radius_x, radius_z) = radii(item)?;
+                let cone = Cone::truncated(
+                    optional_number(item, "min")?.unwrap_or(f64::NEG_INFINITY),
+                    optional_number(item, "max")?.unwrap_or(f64::INFINITY),
+                    optional_bool(item, "closed")?.unwrap_or(false),
+                )?
+                .with_radii(radius_x, radius_z)?;
+
+                Object::primitive(cone)
+            }
+            "group" => {
+                let children = match item.get("children") {
+                    Some(children) => children
+                        .as_sequence()
+                        .ok_or_else(|| invalid_data("`children` must be a list"))?
+                        .iter()
+                        .map(|child| self.object(child))
+                        .collect::<Result<Vec<Object>>>()?,
+                    None => vec![],
+                };
+
+                Object::group(children)
+            }
+            "obj" => WavefrontObj::from_file(self.file(item)?)?,
+            _ => return Err(invalid_data(&format!("unknown shape `{}`", kind))),
+        };
+
+        if let Some(material) = item.get("material") {
+            let mut material = self.material(material)?;
+
+            if let Some(shadow) = optional_bool(item, "shadow")? {
+                material.casts_shadows = shadow;
+            }
+
+            object.set_material(material);
+        }
+
+        if let Some(transform) = item.get("transform") {
+            object.try_set_transform(self.transform(transform)?)?;
+        }
+
+        if let Some(name) = item.get("name") {
+            let name = name
+                .as_str()
+                .ok_or_else(|| invalid_data("`name` must be a string"))?;
+            object.name = Some(name.to_string());
+        }
+
+        Ok(object)
+    }
+
+    fn material(&self, value: &Yaml) -> Result<Material> {
+        let value = self.material_keys(value, &mut vec![])?;
+        let entries = value
+            .as_mapping()
+            .ok_or_else(|| invalid_data("materials must be mappings"))?;
+
+        let mut material = match value.get("pattern") {
+            Some(pattern) => Material::with_pattern(self.pattern(pattern)?),
+            None => Material::new(),
+        };
+
+        for (key, value) in entries {
+            let number = || {
+                value
+                    .as_f64()
+                    .ok_or_else(|| invalid_data(&format!("`{}` must be a number", key)))
+            };
+
+            match key.as_str() {
+                "color" => material.color = color(value)?,
+                "ambient" => material.ambient = number()?,
+                "diffuse" => material.diffuse = number()?,
+                "specular" => material.specular = number()?,
+                "shininess" => material.shininess = number()?,
+                "reflective" => material.reflective = number()?,
+                "transparency" => material.transparency = number()?,
+                "refractive-index" => material.refractive_index = number()?,
+                "absorption" => material.absorption = color(value)?,
+                "bump" => {
+                    material.bump = Some(Bump::new(
+                        self.pattern(field(value, "pattern")?)?,
+                        self::number(value, "strength")?,
+                    ))
+                }
+                "ramp" => material.ramp = Some(self.pattern(value)?),
+                "slot" => {
+                    material.slot = match number()? {
+                        slot if slot >= 0. && slot.fract() == 0. => Some(slot as usize),
+                        _ => return Err(invalid_data("`slot` must be a whole number")),
+                    }
+                }
+                "pattern" => {}
+                _ => return Err(invalid_data(&format!("unknown material key `{}`", key))),
+            }
+        }
+
+        Ok(material)
+    }
+
+    /// The keys of a material, with those of the materials it `extends` underneath.
+    /// Parents are looked up when the material is used rather than when it is defined,
+    /// so redefining a parent changes every material extending it from then on.
+    fn material_keys(&self, value: &Yaml, extended: &mut Vec<String>) -> Result<Yaml> {
+        if let Yaml::String(name) = value {
+            if extended.contains(name) {
+                return Err(invalid_data(&format!("`{}` extends itself", name)));
+            }
+            extended.push(name.clone());
+        }
+
+        let value = self.lookup(value)?;
+        let entries = value
+            .as_mapping()
+            .ok_or_else(|| invalid_data("materials must be mappings"))?;
+
+        match value.get("extends") {
+            Some(parent) => {
+                let parent = self.material_keys(parent, extended)?;
+                let own = entries
+                    .iter()
+                    .filter(|(key, _)| key != "extends")
+                    .cloned()
+                    .collect();
+
+                Ok(merge(&parent, &Yaml::Mapping(own)))
+            }
+            None => Ok(value.clone()),
+        }
+    }
+
+    fn pattern(&self, value: &Yaml) -> Result<Pattern> {
+        let value = self.lookup(value)?;
+        if field(value, "type")?.as_str() == Some("image") {
+            return self.image_pattern(value);
+        }
+        if value.get("stops").is_some() {
+            return self.gradient_pattern(value);
+        }
+
+        let colors = field(value, "colors")?
+            .as_sequence()
+            .filter(|colors| colors.len() == 2)
+            .ok_or_else(|| invalid_data("patterns need a list of two `colors`"))?;
+        let a = self.fill(&colors[0])?;
+        let b = self.fill(&colors[1])?;
+
+        let mut pattern = match field(value, "type")?.as_str() {
+            Some("stripes") => Pattern::striped(a, b),
+            Some("gradient") => Pattern::gradient(a, b),
+            Some("rings") => Pattern::ring(a, b),
+            Some("checkers") => Pattern::checkered(a, b),
+            Some("blend") => Pattern::blend(a, b),
+            _ => return Err(invalid_data("unknown pattern type")),
+        };
+
+        if let Some(transform) = value.get("transform") {
+            pattern.transform = self.transform(transform)?;
+        }
+
+        Ok(pattern)
+    }
+
+    /// Images from PPM files, like `{ type: image, file: earth.ppm, mapping: spherical }`,
+    /// optionally with `filter: bilinear`.
+    fn image_pattern(&self, value: &Yaml) -> Result<Pattern> {
+        let image = Canvas::from_ppm(&std::fs::read_to_string(self.file(value)?)?)?;
+        if image.width() == 0 || image.height() == 0 {
+            return Err(invalid_data("images need at least a pixel"));
+        }
+        let mapping = match field(value, "mapping")?.as_str() {
+            Some("spherical") => UvMapping::Spherical,
+            Some("planar") => UvMapping::Planar,
+            Some("cylindrical") => UvMapping::Cylindrical,
+            _ => return Err(invalid_data("unknown uv mapping")),
+        };
+        let filter = match value.get("filter").map(Yaml::as_str) {
+            None | Some(Some("nearest")) => ImageFilter::Nearest,
+            Some(Some("bilinear")) => ImageFilter::Bilinear,
+            _ => return Err(invalid_data("unknown image filter")),
+        };
+
+        let mut pattern = Pattern::image(image, mapping, filter);
+        if let Some(transform) = value.get("transform") {
+            pattern.transform = self.transform(transform)?;
+        }
+
+        Ok(pattern)
+    }
+
+    /// The `colors` of patterns are colors, or patterns themselves filling their place.
+    fn fill(&self, value: &Yaml) -> Result<Fill> {
+        match value {
+            Yaml::Sequence(_) => Ok(Fill::Color(color(value)?)),
+            _ => Ok(self.pattern(value)?.into()),
+        }
+    }
+
+    /// Gradients through any number of colors, like
+    /// `{ type: gradient, stops: [ [0, [1, 0, 0]], [0.5, [1, 1, 0]] ] }`, optionally with
+    /// `blend: smooth` or `steps`, `shape: radial` or `spherical`, and `repeat: true`.
+    fn gradient_pattern(&self, value: &Yaml) -> Result<Pattern> {
+        if field(value, "type")?.as_str() != Some("gradient") {
+            return Err(invalid_data("only gradients have `stops`"));
+        }
+        let stops = field(value, "stops")?
+            .as_sequence()
+            .filter(|stops| !stops.is_empty())
+            .ok_or_else(|| invalid_data("`stops` must be a list of at least one stop"))?
+            .iter()
+            .map(|stop| match stop.as_sequence() {
+                Some([position, stop_color]) => Ok((
+                    position
+                        .as_f64()
+                        .ok_or_else(|| invalid_data("stop positions must be numbers"))?,
+                    color(stop_color)?,
+                )),
+                _ => Err(invalid_data("stops must be a position and a color")),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let blend = match value.get("blend").map(Yaml::as_str) {
+            None | Some(Some("linear")) => GradientBlend::Linear,
+            Some(Some("smooth")) => GradientBlend::Smooth,
+            Some(Some("steps")) => GradientBlend::Steps,
+            _ => return Err(invalid_data("unknown gradient blend")),
+        };
+        let shape = match value.get("shape").map(Yaml::as_str) {
+            None | Some(Some("axial")) => GradientShape::Axial,
+            Some(Some("radial")) => GradientShape::Radial,
+            Some(Some("spherical")) => GradientShape::Spherical,
+            _ => return Err(invalid_data("unknown gradient shape")),
+        };
+
+        let mut pattern = Pattern::gradient_with_stops(Gradient {
+            stops,
+            blend,
+            shape,
+            repeating: optional_bool(value, "repeat")?.unwrap_or(false),
+        });
+        if let Some(transform) = value.get("transform") {
+            pattern.transform = self.transform(transform)?;
+        }
+
+        Ok(pattern)
+    }
+
+    /// Transforms are lists of operations applied in order,
+    /// or names of defined lists that get spliced in.
+    fn transform(&self, value: &Yaml) -> Result<Matrix4> {
+        let operations = self
+            .lookup(value)?
+            .as_sequence()
+            .ok_or_else(|| invalid_data("transforms must be lists"))?;
+
+        operations
+            .iter()
+            .try_fold(Matrix4::identity(), |transform, operation| {
+                let matrix = match operation {
+                    Yaml::String(_) => self.transform(operation)?,
+                    _ => transformation(operation)?,
+                };
+
+                Ok(matrix * transform)
+            })
+    }
+}
+
+fn transformation(operation: &Yaml) -> Result<Matrix4> {
+    let operation = operation
+        .as_sequence()
+        .filter(|operation| !operation.is_empty())
+        .ok_or_else(|| invalid_data("transformations look like `[ name, arguments... ]`"))?;
+    let name = operation[0].as_str().unwrap_or_default();
+    let arguments = operation[1..]
+        .iter()
+        .map(|argument| {
+            argument
+                .as_f64()
+                .ok_or_else(|| invalid_data("transformation arguments must be numbers"))
+        })
+        .collect::<Result<Vec<f64>>>()?;
+
+    match (name, arguments.as_slice()) {
+        ("translate", &[x, y, z]) => Ok(Matrix4::translation(x, y, z)),
+        ("scale", &[x, y, z]) => Ok(Matrix4::scaling(x, y, z)),
+        ("rotate-x", &[angle]) => Ok(Matrix4::rotation_x(angle)),
+        ("rotate-y", &[angle]) => Ok(Matrix4::rotation_y(angle)),
+        ("rotate-z", &[angle]) => Ok(Matrix4::rotation_z(angle)),
+        ("shear", &[xy, xz, yx, yz, zx, zy]) => Ok(Matrix4::shearing(xy, xz, yx, yz, zx, zy)),
+        ("matrix", numbers) if numbers.len() == 16 => {
+            let mut rows = [[0.; 4]; 4];
+            for (index, number) in numbers.iter().enumerate() {
+                rows[index / 4][index % 4] = *number;
+            }
+
+            Ok(Matrix4::from_rows(rows))
+        }
+        _ => Err(invalid_data(&format!("invalid transformation `{}`", name))),
+    }
+}
+
+/// Overrides the entries of `parent` with the ones in `child`.
+fn merge(parent: &Yaml, child: &Yaml) -> Yaml {
+    match (parent, child) {
+        (Yaml::Mapping(parent), Yaml::Mapping(child)) => {
+            let mut entries = parent.clone();
+
+            for (key, value) in child {
+                match entries.iter_mut().find(|(parent_key, _)| parent_key == key) {
+                    Some(entry) => entry.1 = value.clone(),
+                    None => entries.push((key.clone(), value.clone())),
+                }
+            }
+
+            Yaml::Mapping(entries)
+        }
+        _ => child.clone(),
+    }
+}
+
+/// A gradient sky from `horizon` up to `zenith`, and down to `ground` if it's given.
+fn environment(item: &Yaml) -> Result<Environment> {
+    let horizon = color(field(item, "horizon")?)?;
+
+    Ok(Environment::Gradient {
+        zenith: color(field(item, "zenith")?)?,
+        horizon,
+        ground: item
+            .get("ground")
+            .map(color)
+            .transpose()?
+            .unwrap_or(horizon),
+    })
+}
+
+/// A `density` and a `color`, and how many `steps` to march through it in, 16 if left out.
+fn fog(item: &Yaml) -> Result<Fog> {
+    let mut fog = Fog::new(number(item, "density")?, color(field(item, "color")?)?);
+    if fog.density < 0. {
+        return Err(invalid_data("fog can't have a negative `density`"));
+    }
+    if let Some(steps) = optional_number(item, "steps")? {
+        fog.steps = match steps {
+            steps if steps >= 1. && steps.fract() == 0. => steps as usize,
+            _ => return Err(invalid_data("`steps` must be a whole number of steps")),
+        };
+    }
+
+    Ok(fog)
+}
+
+fn field<'a>(item: &'a Yaml, key: &str) -> Result<&'a Yaml> {
+    item.get(key)
+        .ok_or_else(|| invalid_data(&format!("missing `{}`", key)))
+}
+
+fn number(item: &Yaml, key: &str) -> Result<f64> {
+    field(item, key)?
+        .as_f64()
+        .ok_or_else(|| invalid_data(&format!("`{}` must be a number", key)))
+}
+
+fn optional_number(item: &Yaml, key: &str) -> Result<Option<f64>> {
+    item.get(key).map(|_| number(item, key)).transpose()
+}
+
+/// A positive number, `default` if the item leaves it out.
+fn optional_size(item: &Yaml, key: &str, default: f64) -> Result<f64> {
+    match optional_number(item, key)? {
+        None => Ok(default),
+        Some(size) if size > 0. && size.is_finite() => Ok(size),
+        Some(_) => Err(invalid_data(&format!(
+            "`{}` must be a positive number",
+            key
+        ))),
+    }
+}
+
+/// The `radius-x` and `radius-z` of cylinders and cones, both `radius` if left out.
+fn radii(item: &Yaml) -> Result<(f64, f64)> {
+    let radius = optional_size(item, "radius", 1.)?;
+
+    Ok((
+        optional_size(item, "radius-x", radius)?,
+        optional_size(item, "radius-z", radius)?,
+    ))
+}
+
+fn optional_bool(item: &Yaml, key: &str) -> Result<Option<bool>> {
+    item.get(key)
+        .map(|value| {
+            value
+                .as_bool()
+                .ok_or_else(|| invalid_data(&format!("`{}` must be true or false", key)))
+        })
+        .transpose()
+}
+
+fn triple(value: &Yaml) -> Result<(f64, f64, f64)> {
+    match value.as_sequence() {
+        Some([x, y, z]) => match (x.as_f64(), y.as_f64(), z.as_f64()) {
+            (Some(x), Some(y), Some(z)) => Ok((x, y, z)),
+            _ => Err(invalid_data("expected three numbers")),
+        },
+        _ => Err(invalid_data("expected three numbers")),
+    }
+}
+
+fn point(value: &Yaml) -> Result<Tuple> {
+    let (x, y, z) = triple(value)?;
+
+    Ok(Tuple::point(x, y, z))
+}
+
+fn vector(value: &Yaml) -> Result<Tuple> {
+    let (x, y, z) = triple(value)?;
+
+    Ok(Tuple::vector(x, y, z))
+}
+
+fn point3(value: &Yaml) -> Result<Point3> {
+    let (x, y, z) = triple(value)?;
+
+    Ok(Point3::new(x, y, z))
+}
+
+fn vec3(value: &Yaml) -> Result<Vec3> {
+    let (x, y, z) = triple(value)?;
+
+    Ok(Vec3::new(x, y, z))
+}
+
+fn color(value: &Yaml) -> Result<Color> {
+    let (red, green, blue) = triple(value)?;
+
+    Ok(Color::new(red, green, blue))
+}
+
+fn invalid_data(message: &str) -> RayTracerError {
+    RayTracerError::Parse(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        camera::Projection,
+        misc::approx_equal,
+        ray::Ray,
+        shape::{
+            plane::{Disc, Quad},
+            Shape, ShapeOrGroup,
+        },
+    };
+    use std::f64::consts::PI;
+
+    const SCENE: &str = "
+- add: camera
+  width: 100
+  height: 50
+  field-of-view: 1.0471975512
+  from: [ 0, 1.5, -5 ]
+  to: [ 0, 1, 0 ]
+  up: [ 0, 1, 0 ]
+
+- add: light
+  at: [ -10, 10, -10 ]
+  intensity: [ 1, 1, 1 ]
+
+- define: white-material
+  value:
+    color: [ 1, 1, 1 ]
+    diffuse: 0.7
+    ambient: 0.1
+
+- define: blue-material
+  extend: white-material
+  value:
+    color: [ 0.537, 0.831, 0.914 ]
+
+- define: standard-transform
+  value:
+    - [ translate, 1, -1, 1 ]
+    - [ scale, 0.5, 0.5, 0.5 ]
+
+- add: plane
+  material:
+    pattern:
+      type: checkers
+      colors:
+        - [ 1, 1, 1 ]
+        - [ 0, 0, 0 ]
+
+- add: sphere
+  material: blue-material
+  transform:
+    - standard-transform
+    - [ translate, 0, 1, 0 ]
+";
+
+    #[test]
+    fn loading_the_camera_and_lights() {
+        let scene = Scene::from_yaml(SCENE).unwrap();
+
+        assert_eq!(scene.camera.hsize, 100);
+        assert_eq!(scene.camera.vsize, 50);
+        assert!(approx_equal(scene.camera.field_of_view, PI / 3.));
+        assert_eq!(scene.world.light_count(), 1);
+        assert_eq!(scene.world.objects.len(), 2);
+    }
+
+    #[test]
+    fn defined_materials_can_extend_each_other() {
+        let scene = Scene::from_yaml(SCENE).unwrap();
+        let r = Ray::new(Point3::new(0.5, 5., 0.5), Vec3::new(0., -1., 0.));
+        let xs = scene.world.objects[1].intersect(r);
+        let material = xs[0].object.material;
+
+        assert_eq!(material.color, Color::new(0.537, 0.831, 0.914));
+        assert!(approx_equal(material.diffuse, 0.7));
+    }
+
+    #[test]
+    fn materials_override_what_they_extend() {
+        let source = "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [ 0, 0, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+- define: stripes
+  value:
+    type: stripes
+    colors:
+      - [ 1, 0, 0 ]
+      - [ 0, 0, 1 ]
+- define: glass
+  value:
+    transparency: 0.9
+    refractive-index: 1.5
+    reflective: 0.9
+- define: tinted-glass
+  value:
+    extends: glass
+    color: [ 0, 0.2, 0 ]
+- define: glass
+  value:
+    transparency: 1
+    refractive-index: 1.5
+- add: sphere
+  material:
+    extends: tinted-glass
+    reflective: 0.5
+- add: cube
+  material:
+    pattern: stripes
+";
+        let scene = Scene::from_yaml(source).unwrap();
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
+        let material = scene.world.objects[0].intersect(r)[0].object.material;
+
+        assert_eq!(material.color, Color::new(0., 0.2, 0.));
+        assert!(approx_equal(material.reflective, 0.5));
+        assert!(approx_equal(material.refractive_index, 1.5));
+        // The last definition of a parent is the one materials extend.
+        assert!(approx_equal(material.transparency, 1.));
+
+        let xs = scene.world.objects[1].intersect(r);
+        let comps = xs[0].prepare_computations(r, &xs);
+        let striped = comps
+            .object
+            .material
+            .at_time(comps.object, Point3::new(0.5, 0., -1.), 0.);
+        assert_eq!(striped.color, Color::new(1., 0., 0.));
+    }
+
+    #[test]
+    fn gradients_can_have_any_number_of_stops() {
+        let scene = Scene::from_yaml(
+            "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [ 0, 0, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+- add: plane
+  material:
+    pattern:
+      type: gradient
+      stops:
+        - [ 0, [ 1, 0, 0 ] ]
+        - [ 0.5, [ 1, 1, 0 ] ]
+        - [ 1, [ 0, 0, 1 ] ]
+      shape: radial
+      blend: smooth
+      repeat: true
+",
+        )
+        .unwrap();
+
+        let r = Ray::new(Point3::new(0., 5., 2.5), Vec3::new(0., -1., 0.));
+        let xs = scene.world.objects[0].intersect(r);
+        let comps = xs[0].prepare_computations(r, &xs);
+        let material = comps
+            .object
+            .material
+            .at_time(comps.object, Point3::new(0., 0., 2.5), 0.);
+        assert_eq!(material.color, Color::new(1., 1., 0.));
+
+        let unknown = Scene::from_yaml(
+            "
+- add: plane
+  material:
+    pattern:
+      type: gradient
+      stops: [ [ 0, [ 1, 0, 0 ] ] ]
+      shape: conical
+",
+        );
+        assert!(unknown.is_err());
+    }
+
+    #[test]
+    fn loading_toon_ramps() {
+        let scene = Scene::from_yaml(
+            "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [ 0, 0, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+- add: sphere
+  material:
+    ramp:
+      type: gradient
+      stops:
+        - [ 0, [ 0.2, 0.2, 0.2 ] ]
+        - [ 0.5, [ 1, 1, 1 ] ]
+      blend: steps
+",
+        )
+        .unwrap();
+
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
+        let xs = scene.world.objects[0].intersect(r);
+        let comps = xs[0].prepare_computations(r, &xs);
+        let ramp = comps.object.material.ramp.unwrap();
+        assert_eq!(ramp.ramp_at(0.3), Color::new(0.2, 0.2, 0.2));
+        assert_eq!(ramp.ramp_at(0.8), Color::white());
+    }
+
+    #[test]
+    fn loading_a_sky() {
+        let scene = Scene::from_yaml(
+            "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [ 0, 0, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+- add: environment
+  zenith: [ 0, 0, 1 ]
+  horizon: [ 1, 1, 1 ]
+",
+        )
+        .unwrap();
+
+        let up = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 1., 0.));
+        let down = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., -1., 0.));
+        assert_eq!(scene.world.color_at(up), Color::new(0., 0., 1.));
+        assert_eq!(scene.world.color_at(down), Color::white());
+    }
+
+    #[test]
+    fn loading_a_stereo_panorama() {
+        let scene = Scene::from_yaml(
+            "
+- add: camera
+  projection: panorama
+  width: 64
+  eye-separation: 0.065
+  from: [ 0, 1, 0 ]
+  to: [ 0, 1, -1 ]
+  up: [ 0, 1, 0 ]
+",
+        )
+        .unwrap();
+
+        assert_eq!((scene.camera.hsize, scene.camera.vsize), (64, 64));
+        assert_eq!(
+            scene.camera.projection,
+            Projection::Panorama {
+                eye_separation: 0.065
+            }
+        );
+    }
+
+    #[test]
+    fn loading_fog() {
+        let scene = Scene::from_yaml(
+            "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [ 0, 0, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+- add: fog
+  density: 0.1
+  color: [ 0.5, 0.5, 0.5 ]
+  steps: 4
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            scene.world.fog,
+            Some(Fog {
+                density: 0.1,
+                color: Color::new(0.5, 0.5, 0.5),
+                steps: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn patterns_can_be_made_of_other_patterns() {
+        let scene = Scene::from_yaml(
+            "
+- define: red-and-blue
+  value:
+    type: stripes
+    colors: [ [ 1, 0, 0 ], [ 0, 0, 1 ] ]
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [ 0, 0, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+- add: plane
+  material:
+    pattern:
+      type: blend
+      colors:
+        - red-and-blue
+        - type: checkers
+          colors: [ [ 1, 1, 1 ], red-and-blue ]
+",
+        )
+        .unwrap();
+
+        let color_at = |x: f64, z: f64| {
+            let r = Ray::new(Point3::new(x, 5., z), Vec3::new(0., -1., 0.));
+            let xs = scene.world.objects[0].intersect(r);
+            let comps = xs[0].prepare_computations(r, &xs);
+
+            comps
+                .object
+                .material
+                .at_time(comps.object, Point3::new(x, 0., z), 0.)
+                .color
+        };
+        assert_eq!(color_at(0.5, 0.5), Color::new(1., 0.5, 0.5));
+        assert_eq!(color_at(1.5, 0.5), Color::new(0., 0., 1.));
+    }
+
+    #[test]
+    fn materials_can_be_bumped_by_patterns() {
+        let scene = Scene::from_yaml(
+            "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [ 0, 0, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+- add: plane
+  material:
+    bump:
+      pattern:
+        type: gradient
+        colors: [ [ 0, 0, 0 ], [ 1, 1, 1 ] ]
+      strength: 0.1
+",
+        )
+        .unwrap();
+
+        let r = Ray::new(Point3::new(0.5, 1., 0.), Vec3::new(0., -1., 0.));
+        let xs = scene.world.objects[0].intersect(r);
+        let comps = xs[0].prepare_computations(r, &xs);
+        assert_eq!(comps.normal_vector, Vec3::new(-0.1, 1., 0.).normalize());
+    }
+
+    #[test]
+    fn image_patterns_are_loaded_from_ppm_files() {
+        let path = std::env::temp_dir().join(format!("scene-image-{}.ppm", std::process::id()));
+        std::fs::write(&path, "P3\n2 1\n255\n255 0 0 0 0 255\n").unwrap();
+        let source = format!(
+            "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [ 0, 0, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+- add: plane
+  material:
+    pattern:
+      type: image
+      file: {}
+      mapping: planar
+      filter: bilinear
+",
+            path.to_str().unwrap()
+        );
+        let scene = Scene::from_yaml(&source).unwrap();
+        let without_files = Scene::from_yaml_without_files(&source);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(without_files, Err(RayTracerError::Parse(_))));
+
+        let r = Ray::new(Point3::new(0.5, 5., 0.5), Vec3::new(0., -1., 0.));
+        let xs = scene.world.objects[0].intersect(r);
+        let comps = xs[0].prepare_computations(r, &xs);
+        let material = comps
+            .object
+            .material
+            .at_time(comps.object, Point3::new(0.5, 0., 0.5), 0.);
+        assert_eq!(material.color, Color::new(0.5, 0., 0.5));
+    }
+
+    #[test]
+    fn transforms_are_applied_in_order() {
+        let scene = Scene::from_yaml(SCENE).unwrap();
+
+        assert_eq!(
+            scene.world.objects[1].transform(),
+            Matrix4::translation(0., 1., 0.)
+                * Matrix4::scaling(0.5, 0.5, 0.5)
+                * Matrix4::translation(1., -1., 1.)
+        );
+    }
+
+    #[test]
+    fn loading_groups_and_bounded_shapes() {
+        let source = "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [ 0, 0, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+- add: group
+  transform:
+    - [ rotate-y, 0.5 ]
+  children:
+    - add: cylinder
+      min: 0
+      max: 1
+      closed: true
+    - add: cube
+      name: box
+      material:
+        color: [ 1, 0, 0 ]
+      shadow: false
+";
+        let scene = Scene::from_yaml(source).unwrap();
+
+        assert_eq!(scene.world.objects.len(), 1);
+        assert_eq!(scene.world.objects[0].transform(), Matrix4::rotation_y(0.5));
+        assert!(scene.world.find_object("box").is_some());
+    }
+
+    #[test]
+    fn area_lights_are_split_into_a_light_per_cell() {
+        let source = "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [ 0, 0, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+- add: light
+  corner: [ -1, 2, 4 ]
+  uvec: [ 2, 0, 0 ]
+  usteps: 2
+  vvec: [ 0, 2, 0 ]
+  vsteps: 1
+  jitter: true
+  intensity: [ 1.5, 1.5, 1.5 ]
+";
+        let scene = Scene::from_yaml(source).unwrap();
+        let lights: Vec<_> = scene
+            .world
+            .lights
+            .iter()
+            .map(|light| (light.position, light.intensity))
+            .collect();
+
+        assert_eq!(
+            lights,
+            [
+                (Point3::new(-0.5, 3., 4.), Color::new(0.75, 0.75, 0.75)),
+                (Point3::new(0.5, 3., 4.), Color::new(0.75, 0.75, 0.75)),
+            ]
+        );
+        assert!(Scene::from_yaml(&source.replace("usteps: 2", "usteps: 0")).is_err());
+    }
+
+    #[test]
+    fn loading_discs_and_quads() {
+        let source = "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [ 0, 0, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+- add: disc
+  radius: 0.5
+- add: quad
+  width: 3
+- add: disc
+";
+        let scene = Scene::from_yaml(source).unwrap();
+        let shapes: Vec<&Shape> = scene
+            .world
+            .objects
+            .iter()
+            .map(|object| match object.shape() {
+                ShapeOrGroup::Shape { shape, .. } => shape,
+                ShapeOrGroup::Group(_) => unreachable!(),
+            })
+            .collect();
+
+        assert_eq!(
+            shapes,
+            [
+                &Shape::primitive(Disc { radius: 0.5 }),
+                &Shape::primitive(Quad {
+                    width: 3.,
+                    depth: 2.
+                }),
+                &Shape::primitive(Disc { radius: 1. }),
+            ]
+        );
+        let flat = "- add: quad\n  depth: 0";
+        assert!(matches!(
+            Scene::from_yaml(flat),
+            Err(RayTracerError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_scenes_are_rejected() {
+        let missing_camera = "- add: sphere";
+        let unknown_shape = "- add: teapot";
+        let undefined_material = "- add: sphere\n  material: nothing";
+        let bad_transform = "- add: sphere\n  transform:\n    - [ translate, 1 ]";
+        let circular_material =
+            "- define: a\n  value:\n    extends: a\n- add: sphere\n  material: a";
+
+        for source in [
+            missing_camera,
+            unknown_shape,
+            undefined_material,
+            bad_transform,
+            circular_material,
+        ] {
+            let error = Scene::from_yaml(source).err().unwrap();
+            assert!(matches!(error, RayTracerError::Parse(_)));
+        }
+
+        let flattened = "- add: sphere\n  transform:\n    - [ scale, 1, 0, 1 ]";
+        assert!(matches!(
+            Scene::from_yaml(flattened),
+            Err(RayTracerError::DegenerateTransform)
+        ));
+    }
+
+    #[test]
+    fn scenes_without_files_can_not_load_meshes() {
+        let source = format!("{}\n- add: obj\n  file: /etc/passwd", SCENE);
+
+        assert!(matches!(
+            Scene::from_yaml_without_files(&source),
+            Err(RayTracerError::Parse(_))
+        ));
+        assert!(Scene::from_yaml_without_files(SCENE).is_ok());
+    }
+
+    #[test]
+    fn cameras_that_can_not_render_are_rejected() {
+        for (from, to) in [
+            ("width: 100", "width: 0"),
+            ("height: 50", "height: -50"),
+            ("width: 100", "width: 100.5"),
+            ("up: [ 0, 1, 0 ]", "up: [ 0, -1, 10 ]"),
+            ("to: [ 0, 1, 0 ]", "to: [ 0, 1.5, -5 ]"),
+            ("up: [ 0, 1, 0 ]", "up: [ 0, 0, 0 ]"),
+        ] {
+            let source = SCENE.replace(from, to);
+            assert!(
+                matches!(Scene::from_yaml(&source), Err(RayTracerError::Parse(_))),
+                "{}",
+                to
+            );
+        }
+    }
+
+    #[test]
+    fn scenes_are_written_back_out_as_they_were_read() {
+        let source = "
+- add: camera
+  width: 16
+  height: 12
+  field-of-view: 0.9
+  from: [ 1, 2, -6 ]
+  to: [ 0, 0.5, 0 ]
+  up: [ 0, 1, 0 ]
+- add: light
+  at: [ -10, 10, -10 ]
+  intensity: [ 1, 0.9, 0.8 ]
+- add: environment
+  zenith: [ 0, 0, 1 ]
+  horizon: [ 1, 1, 1 ]
+- add: fog
+  density: 0.02
+  color: [ 0.5, 0.5, 0.5 ]
+  steps: 2
+- add: plane
+  material:
+    pattern:
+      type: checkers
+      colors:
+        - [ 1, 1, 1 ]
+        - type: stripes
+          colors: [ [ 1, 0, 0 ], [ 0, 0, 1 ] ]
+          transform:
+            - [ scale, 0.25, 0.25, 0.25 ]
+- add: group
+  name: pillars
+  children:
+    - add: cylinder
+      min: 0
+      max: 2
+      closed: true
+      radius-x: 0.5
+      radius-z: 0.25
+      name: left
+      shadow: false
+      material:
+        color: [ 0.2, 0.8, 0.2 ]
+        reflective: 0.3
+      transform:
+        - [ translate, -1.5, 0, 0 ]
+    - add: sphere
+      material:
+        transparency: 0.9
+        refractive-index: 1.5
+        ramp:
+          type: gradient
+          stops:
+            - [ 0, [ 0.1, 0.1, 0.1 ] ]
+            - [ 0.5, [ 1, 1, 1 ] ]
+          blend: steps
+      transform:
+        - [ rotate-z, 0.5 ]
+        - [ translate, 1, 1, 0 ]
+  transform:
+    - [ rotate-y, 0.3 ]
+- add: disc
+  radius: 0.5
+  material: {}
+  transform:
+    - [ translate, 0, 3, 0 ]
+";
+        let mut scene = Scene::from_yaml(source).unwrap();
+
+        let written = scene.to_yaml().unwrap();
+        let mut reread = Scene::from_yaml(&written).unwrap();
+
+        // Working out where the camera looks from rounds a little differently each time.
+        let without_camera = |yaml: &str| yaml.split_once("- add: light").unwrap().1.to_string();
+        assert_eq!(
+            without_camera(&reread.to_yaml().unwrap()),
+            without_camera(&written)
+        );
+        assert_eq!(reread.camera.transform, scene.camera.transform);
+        assert_eq!(reread.world.objects[1].name.as_deref(), Some("pillars"));
+        let ShapeOrGroup::Shape {
+            shape: Shape::Primitive(left),
+            ..
+        } = reread.world.objects[1].find("left").unwrap().shape()
+        else {
+            panic!("expected a cylinder");
+        };
+        let left = left.downcast_ref::<Cylinder>().unwrap();
+        assert_eq!((left.radius_x(), left.radius_z()), (0.5, 0.25));
+
+        // Fog scatters light in at random, so leave it out of the pictures compared.
+        reread.world.fog = None;
+        scene.world.fog = None;
+        assert_eq!(
+            reread.camera.render(&reread.world).buffer(),
+            scene.camera.render(&scene.world).buffer()
+        );
+    }
+
+    #[test]
+    fn scenes_made_in_code_are_written_out() {
+        let mut camera = Camera::new(8, 8, PI / 3.);
+        camera.transform = view_transform(
+            Tuple::point(0., 1., -5.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(1., 1., 0.),
+        );
+        let mut sphere = Object::sphere();
+        sphere.set_transform(Matrix4::rotation_x(0.3) * Matrix4::scaling(1., 2., 1.));
+        let mut world = World::new();
+        world.objects = vec![sphere];
+        world.lights = vec![Light::point_light(
+            Point3::new(-10., 10., -10.),
+            Color::white(),
+        )];
+        let scene = Scene { camera, world };
+
+        let reread = Scene::from_yaml(&scene.to_yaml().unwrap()).unwrap();
+
+        assert_eq!(reread.camera.transform, scene.camera.transform);
+        assert_eq!(
+            reread.world.objects[0].transform(),
+            scene.world.objects[0].transform()
+        );
+
+        let mut spotlit = scene;
+        spotlit.world.lights = vec![Light::spot_light(
+            Point3::new(0., 5., 0.),
+            Vec3::new(0., -1., 0.),
+            0.5,
+            0.1,
+            Color::white(),
+        )];
+        assert!(matches!(
+            spotlit.to_yaml(),
+            Err(RayTracerError::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,444 @@
+//! Writing scenes back out in the format scene files are read from.
+
+use super::Scene;
+use crate::{
+    camera::{Camera, Projection},
+    color::Color,
+    environment::Environment,
+    error::{RayTracerError, Result},
+    light::{Light, LightKind},
+    material::Material,
+    math::{matrix4::Matrix4, point3::Point3, transformations::view_transform, tuple::Tuple},
+    pattern::{
+        BlendPattern, CheckeredPattern, Fill, GradientBlend, GradientPattern, GradientShape,
+        Pattern, PatternMotion, PatternSpace, PatternType, RingPattern, StopsPattern,
+        StripePattern,
+    },
+    shape::{
+        cone::Cone,
+        cube::Cube,
+        curve::Curve,
+        cylinder::Cylinder,
+        plane::{Disc, Plane, Quad},
+        primitive::Primitive,
+        sphere::Sphere,
+        triangle::Triangle,
+        Object, Shape, ShapeOrGroup,
+    },
+};
+
+impl Scene {
+    /// Writes the scene as a scene file `from_yaml` reads back, so scenes put together in
+    /// code can be saved, shared and rendered from the command line.
+    ///
+    /// Cameras are written without their lens, time and sampling, and worlds without their
+    /// integrator, seed and depth, which renders set with `RenderSettings`. Anything else scene
+    /// files can't describe, like meshes, spotlights or image patterns, fails with
+    /// `RayTracerError::Unsupported`.
+    pub fn to_yaml(&self) -> Result<String> {
+        let world = &self.world;
+        let mut yaml = camera(&self.camera)?;
+
+        if !world.attached_lights.is_empty() {
+            return Err(unsupported("lights attached to objects"));
+        }
+        for light in &world.lights {
+            yaml += &self::light(light)?;
+        }
+        match &world.environment {
+            None => {}
+            Some(Environment::Gradient {
+                zenith,
+                horizon,
+                ground,
+            }) => {
+                yaml += "- add: environment\n";
+                yaml += &format!("  zenith: {}\n", color(*zenith));
+                yaml += &format!("  horizon: {}\n", color(*horizon));
+                yaml += &format!("  ground: {}\n", color(*ground));
+            }
+            Some(Environment::CubeMap(_)) => return Err(unsupported("cube map environments")),
+        }
+        if let Some(fog) = world.fog {
+            yaml += "- add: fog\n";
+            yaml += &format!("  density: {}\n", fog.density);
+            yaml += &format!("  color: {}\n", color(fog.color));
+            yaml += &format!("  steps: {}\n", fog.steps);
+        }
+        for object in &world.objects {
+            yaml += &self::object(object, "")?;
+        }
+
+        Ok(yaml)
+    }
+
+    /// Writes the scene to a file, see `to_yaml`.
+    pub fn save(&self, file_path: &str) -> Result<()> {
+        std::fs::write(file_path, self.to_yaml()?)?;
+
+        Ok(())
+    }
+}
+
+/// The camera, looking from `from` to `to` with `up` worked out so `view_transform` gives
+/// back its transform, which can't be done for transforms that scale or skew.
+fn camera(camera: &Camera) -> Result<String> {
+    let transform = camera.transform;
+    let inverse = transform
+        .inverse()
+        .ok_or(RayTracerError::DegenerateTransform)?;
+    let row = |index: usize| {
+        Tuple::vector(
+            transform.get(index, 0),
+            transform.get(index, 1),
+            transform.get(index, 2),
+        )
+    };
+    let from = inverse * Tuple::point(0., 0., 0.);
+    let forward = -row(2);
+    // `view_transform` crosses `forward` with the normalized `up`, so an `up` leaning
+    // towards `forward` scales the sides of the picture down as much as `left` is short.
+    let sine = row(0).magnitude();
+    let up = row(1).normalize() * sine + forward * (1. - sine * sine).max(0.).sqrt();
+    let to = from + forward;
+    if view_transform(from, to, up) != transform {
+        return Err(unsupported("cameras with transforms that scale or skew"));
+    }
+
+    let mut yaml = String::from("- add: camera\n");
+    match camera.projection {
+        Projection::Perspective => {
+            yaml += &format!("  width: {}\n", camera.hsize);
+            yaml += &format!("  height: {}\n", camera.vsize);
+            yaml += &format!("  field-of-view: {}\n", camera.field_of_view);
+        }
+        Projection::Panorama { eye_separation } => {
+            yaml += "  projection: panorama\n";
+            yaml += &format!("  width: {}\n", camera.hsize);
+            yaml += &format!("  height: {}\n", camera.vsize);
+            yaml += &format!("  eye-separation: {}\n", eye_separation);
+        }
+    }
+    yaml += &format!("  from: {}\n", triple(from.x, from.y, from.z));
+    yaml += &format!("  to: {}\n", triple(to.x, to.y, to.z));
+    yaml += &format!("  up: {}\n", triple(up.x, up.y, up.z));
+
+    Ok(yaml)
+}
+
+fn light(light: &Light) -> Result<String> {
+    if !matches!(light.kind, LightKind::Point) {
+        return Err(unsupported("lights other than point lights"));
+    }
+    if light.fixture.is_some() {
+        return Err(unsupported("light fixtures"));
+    }
+    let Point3 { x, y, z } = light.position;
+
+    Ok(format!(
+        "- add: light\n  at: {}\n  intensity: {}\n",
+        triple(x, y, z),
+        color(light.intensity)
+    ))
+}
+
+/// The object as an item of a list indented by `indent`, children of groups included.
+fn object(object: &Object, indent: &str) -> Result<String> {
+    let kind = match object.shape() {
+        ShapeOrGroup::Group(_) => "group",
+        ShapeOrGroup::Shape { shape, .. } => match shape {
+            Shape::Primitive(primitive) => primitive_kind(primitive.as_ref())?,
+            Shape::Csg(_) => return Err(unsupported("constructive solid geometry")),
+            Shape::Lod(_) => return Err(unsupported("levels of detail")),
+            Shape::PointCloud(_) => return Err(unsupported("point clouds")),
+        },
+    };
+    let mut entries = vec![];
+    if let Some(name) = &object.name {
+        entries.push(format!("name: {:?}", name));
+    }
+
+    match object.shape() {
+        ShapeOrGroup::Shape { shape, material } => {
+            if let Shape::Primitive(primitive) = shape {
+                if let Some(Disc { radius }) = primitive.downcast_ref() {
+                    entries.push(format!("radius: {}", radius));
+                } else if let Some(Quad { width, depth }) = primitive.downcast_ref() {
+                    entries.push(format!("width: {}", width));
+                    entries.push(format!("depth: {}", depth));
+                } else if let Some(cylinder) = primitive.downcast_ref::<Cylinder>() {
+                    entries.extend(truncation(
+                        cylinder.minimum(),
+                        cylinder.maximum(),
+                        cylinder.closed(),
+                    ));
+                    entries.extend(radii(cylinder.radius_x(), cylinder.radius_z()));
+                } else if let Some(cone) = primitive.downcast_ref::<Cone>() {
+                    entries.extend(truncation(cone.minimum(), cone.maximum(), cone.closed()));
+                    entries.extend(radii(cone.radius_x(), cone.radius_z()));
+                }
+            }
+            if !material.unset {
+                let keys = self::material(material)?;
+                if keys.is_empty() {
+                    entries.push("material: {}".to_string());
+                } else {
+                    entries.push("material:".to_string());
+                    entries.extend(keys.into_iter().map(|key| format!("  {}", key)));
+                }
+                if !material.casts_shadows {
+                    entries.push("shadow: false".to_string());
+                }
+            }
+        }
+        ShapeOrGroup::Group(children) => {
+            entries.push("children:".to_string());
+            for child in children {
+                entries.extend(self::object(child, "  ")?.lines().map(str::to_string));
+            }
+        }
+    }
+    if let Some(transform) = transform(object.transform()) {
+        entries.push(format!("transform: {}", transform));
+    }
+
+    let mut yaml = format!("{}- add: {}\n", indent, kind);
+    for entry in entries {
+        yaml += &format!("{}  {}\n", indent, entry);
+    }
+
+    Ok(yaml)
+}
+
+/// The keys of cylinders and cones cut short, infinite ones leaving them out.
+fn truncation(minimum: f64, maximum: f64, closed: bool) -> Vec<String> {
+    let mut entries = vec![];
+    if minimum.is_finite() {
+        entries.push(format!("min: {}", minimum));
+    }
+    if maximum.is_finite() {
+        entries.push(format!("max: {}", maximum));
+    }
+    if closed {
+        entries.push("closed: true".to_string());
+    }
+
+    entries
+}
+
+/// The radii of cylinders and cones, left out for round ones of radius 1.
+fn radii(radius_x: f64, radius_z: f64) -> Vec<String> {
+    if radius_x != radius_z {
+        vec![
+            format!("radius-x: {}", radius_x),
+            format!("radius-z: {}", radius_z),
+        ]
+    } else if radius_x != 1. {
+        vec![format!("radius: {}", radius_x)]
+    } else {
+        vec![]
+    }
+}
+
+/// The `key: value` lines of what the material changes from `Material::new`.
+fn material(material: &Material) -> Result<Vec<String>> {
+    if material.waves.is_some() {
+        return Err(unsupported("water"));
+    }
+    if material.shadow_catcher {
+        return Err(unsupported("shadow catchers"));
+    }
+    if material.portal.is_some() {
+        return Err(unsupported("portals"));
+    }
+
+    let default = Material::new();
+    let mut keys = vec![];
+    let numbers = [
+        ("ambient", material.ambient, default.ambient),
+        ("diffuse", material.diffuse, default.diffuse),
+        ("specular", material.specular, default.specular),
+        ("shininess", material.shininess, default.shininess),
+        ("reflective", material.reflective, default.reflective),
+        ("transparency", material.transparency, default.transparency),
+        (
+            "refractive-index",
+            material.refractive_index,
+            default.refractive_index,
+        ),
+    ];
+
+    if material.color != default.color {
+        keys.push(format!("color: {}", color(material.color)));
+    }
+    for (key, value, default) in numbers {
+        if value != default {
+            keys.push(format!("{}: {}", key, value));
+        }
+    }
+    if material.absorption != default.absorption {
+        keys.push(format!("absorption: {}", color(material.absorption)));
+    }
+    if let Some(pattern) = &material.pattern {
+        keys.push(format!("pattern: {}", self::pattern(pattern)?));
+    }
+    if let Some(bump) = &material.bump {
+        keys.push(format!(
+            "bump: {{ pattern: {}, strength: {} }}",
+            self::pattern(&bump.pattern)?,
+            bump.strength
+        ));
+    }
+    if let Some(ramp) = &material.ramp {
+        keys.push(format!("ramp: {}", self::pattern(ramp)?));
+    }
+    if let Some(slot) = material.slot {
+        keys.push(format!("slot: {}", slot));
+    }
+
+    Ok(keys)
+}
+
+/// The pattern as a flow mapping, patterns filling it in included.
+fn pattern(pattern: &Pattern) -> Result<String> {
+    if pattern.space != PatternSpace::Object {
+        return Err(unsupported("patterns outside of the space of their object"));
+    }
+    if pattern.motion != PatternMotion::still() {
+        return Err(unsupported("moving patterns"));
+    }
+
+    let (kind, a, b) = match &pattern.pattern_type {
+        PatternType::Striped(StripePattern { a, b }) => ("stripes", a, b),
+        PatternType::Gradient(GradientPattern { a, b }) => ("gradient", a, b),
+        PatternType::Ring(RingPattern { a, b }) => ("rings", a, b),
+        PatternType::Checkered(CheckeredPattern { a, b }) => ("checkers", a, b),
+        PatternType::Blend(BlendPattern { a, b }) => ("blend", a, b),
+        PatternType::Stops(gradient) => return stops(gradient, pattern.transform),
+        PatternType::Image(_) => return Err(unsupported("image patterns")),
+        #[cfg(test)]
+        PatternType::TestPattern => return Err(unsupported("test patterns")),
+    };
+    let mut yaml = format!(
+        "{{ type: {}, colors: [ {}, {} ]",
+        kind,
+        fill(*a)?,
+        fill(*b)?
+    );
+    if let Some(transform) = transform(pattern.transform) {
+        yaml += &format!(", transform: {}", transform);
+    }
+
+    Ok(yaml + " }")
+}
+
+/// Gradients through any number of colors, written with their `stops`.
+fn stops(gradient: &StopsPattern, transform: Matrix4) -> Result<String> {
+    let stops = gradient
+        .stops
+        .iter()
+        .map(|(position, stop)| format!("[ {}, {} ]", position, color(*stop)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut yaml = format!("{{ type: gradient, stops: [ {} ]", stops);
+    match gradient.blend {
+        GradientBlend::Linear => {}
+        GradientBlend::Smooth => yaml += ", blend: smooth",
+        GradientBlend::Steps => yaml += ", blend: steps",
+    }
+    match gradient.shape {
+        GradientShape::Axial => {}
+        GradientShape::Radial => yaml += ", shape: radial",
+        GradientShape::Spherical => yaml += ", shape: spherical",
+    }
+    if gradient.repeating {
+        yaml += ", repeat: true";
+    }
+    if let Some(transform) = self::transform(transform) {
+        yaml += &format!(", transform: {}", transform);
+    }
+
+    Ok(yaml + " }")
+}
+
+fn fill(fill: Fill) -> Result<String> {
+    match fill {
+        Fill::Color(fill) => Ok(color(fill)),
+        Fill::Pattern(fill) => pattern(fill),
+    }
+}
+
+/// The transform as a list of operations, scaling and translating when that's all it does,
+/// `None` for the identity.
+fn transform(matrix: Matrix4) -> Option<String> {
+    let get = |row, col| matrix.get(row, col);
+    let scales_and_translates = (0..4)
+        .flat_map(|row| (0..3).map(move |col| (row, col)))
+        .all(|(row, col)| row == col || get(row, col) == 0.)
+        && get(3, 3) == 1.;
+
+    let mut operations = vec![];
+    if scales_and_translates {
+        let scale = (get(0, 0), get(1, 1), get(2, 2));
+        if scale != (1., 1., 1.) {
+            operations.push(format!("[ scale, {}, {}, {} ]", scale.0, scale.1, scale.2));
+        }
+        let translation = (get(0, 3), get(1, 3), get(2, 3));
+        if translation != (0., 0., 0.) {
+            operations.push(format!(
+                "[ translate, {}, {}, {} ]",
+                translation.0, translation.1, translation.2
+            ));
+        }
+    } else {
+        let numbers = (0..16)
+            .map(|index| get(index / 4, index % 4).to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        operations.push(format!("[ matrix, {} ]", numbers));
+    }
+
+    if operations.is_empty() {
+        None
+    } else {
+        Some(format!("[ {} ]", operations.join(", ")))
+    }
+}
+
+fn triple(x: f64, y: f64, z: f64) -> String {
+    format!("[ {}, {}, {} ]", x, y, z)
+}
+
+fn color(color: Color) -> String {
+    triple(color.red, color.green, color.blue)
+}
+
+/// The item scene files add `primitive` with, for the shapes they can describe.
+fn primitive_kind(primitive: &dyn Primitive) -> Result<&'static str> {
+    Ok(if primitive.downcast_ref::<Sphere>().is_some() {
+        "sphere"
+    } else if primitive.downcast_ref::<Plane>().is_some() {
+        "plane"
+    } else if primitive.downcast_ref::<Disc>().is_some() {
+        "disc"
+    } else if primitive.downcast_ref::<Quad>().is_some() {
+        "quad"
+    } else if primitive.downcast_ref::<Cube>().is_some() {
+        "cube"
+    } else if primitive.downcast_ref::<Cylinder>().is_some() {
+        "cylinder"
+    } else if primitive.downcast_ref::<Cone>().is_some() {
+        "cone"
+    } else if primitive.downcast_ref::<Triangle>().is_some() {
+        return Err(unsupported("triangles"));
+    } else if primitive.downcast_ref::<Curve>().is_some() {
+        return Err(unsupported("curves"));
+    } else {
+        return Err(unsupported("shapes defined outside the crate"));
+    })
+}
+
+fn unsupported(what: &str) -> RayTracerError {
+    RayTracerError::Unsupported(format!("scene files can't describe {}", what))
+}
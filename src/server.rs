@@ -0,0 +1,550 @@
+//! A small HTTP server rendering scenes in the background.
+//!
+//! - `POST /renders` takes a YAML scene (see [`Scene`]) and answers with the id of its render.
+//!   Scenes can't read files, so OBJ meshes and image patterns are turned away.
+//! - `GET /renders/{id}` reports the progress of a render.
+//! - `GET /renders/{id}/image.png` fetches the finished image.
+//!
+//! Renders that panic are reported as failed instead of taking the server down with them.
+//!
+//! Only a few renders run at once, and only the latest finished ones are kept around.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write as _,
+    io::{BufRead, BufReader, Error, ErrorKind, Read, Result, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::scene::Scene;
+
+/// Largest scene accepted, in bytes.
+const MAX_BODY_SIZE: usize = 1 << 20;
+/// Longest request or header line accepted, in bytes.
+const MAX_LINE_LENGTH: usize = 8 << 10;
+/// Most headers read from a request.
+const MAX_HEADERS: usize = 100;
+/// How long a connection can take to send a request or read a response before it's dropped.
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// Largest width and height a scene can ask for.
+const MAX_IMAGE_SIZE: i32 = 4096;
+/// Renders running at the same time, more are turned away until one finishes.
+const MAX_RENDERS_IN_PROGRESS: usize = 4;
+/// Finished renders kept for fetching, the oldest are forgotten past this.
+const MAX_FINISHED_RENDERS: usize = 32;
+
+/// Accepts connections on `address` (say `("127.0.0.1", 8080)`) forever,
+/// one thread per connection.
+pub fn serve(address: impl ToSocketAddrs) -> Result<()> {
+    let listener = TcpListener::bind(address)?;
+    let renders = Renders::default();
+
+    println!("Listening on {}", listener.local_addr()?);
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let renders = renders.clone();
+
+        thread::spawn(move || {
+            if let Err(error) = handle_connection(stream, &renders) {
+                eprintln!("Connection failed: {}", error);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Default)]
+struct Renders(Arc<Mutex<RenderTable>>);
+
+#[derive(Default)]
+struct RenderTable {
+    next_id: usize,
+    statuses: HashMap<usize, RenderStatus>,
+    /// Ids of the finished (or failed) renders, oldest first.
+    finished: VecDeque<usize>,
+}
+
+impl RenderTable {
+    fn in_progress(&self) -> usize {
+        self.statuses.len() - self.finished.len()
+    }
+
+    fn add(&mut self, status: RenderStatus) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.statuses.insert(id, status);
+
+        id
+    }
+
+    /// Stops counting the render as in progress, recording `status` as how it ended.
+    fn finish(&mut self, id: usize, status: RenderStatus) {
+        self.statuses.insert(id, status);
+        self.finished.push_back(id);
+
+        while self.finished.len() > MAX_FINISHED_RENDERS {
+            let oldest = self.finished.pop_front().unwrap();
+            self.statuses.remove(&oldest);
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum RenderStatus {
+    Rendering { done: usize, total: usize },
+    Done(Vec<u8>),
+    Failed,
+}
+
+impl Renders {
+    /// Starts rendering the scene in its own thread, returning its id,
+    /// or `None` if too many renders are running already.
+    fn start(&self, scene: Scene) -> Option<usize> {
+        let total = scene.camera.hsize as usize * scene.camera.vsize as usize;
+        let id = {
+            let mut renders = self.0.lock().unwrap();
+            if renders.in_progress() >= MAX_RENDERS_IN_PROGRESS {
+                return None;
+            }
+            renders.add(RenderStatus::Rendering { done: 0, total })
+        };
+
+        let renders = self.clone();
+        thread::spawn(move || {
+            let png = panic::catch_unwind(AssertUnwindSafe(|| {
+                scene
+                    .camera
+                    .render_with_progress(&scene.world, |done, total| {
+                        renders.set(id, RenderStatus::Rendering { done, total })
+                    })
+                    .to_png()
+            }));
+            let status = png.map_or(RenderStatus::Failed, RenderStatus::Done);
+
+            renders.0.lock().unwrap().finish(id, status);
+        });
+
+        Some(id)
+    }
+
+    fn set(&self, id: usize, status: RenderStatus) {
+        self.0.lock().unwrap().statuses.insert(id, status);
+    }
+
+    fn get(&self, id: usize) -> Option<RenderStatus> {
+        self.0.lock().unwrap().statuses.get(&id).cloned()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn json(status: u16, body: String) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: body.into_bytes(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::json(status, format!("{{\"error\":{}}}", json_string(message)))
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            202 => "Accepted",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            409 => "Conflict",
+            413 => "Payload Too Large",
+            431 => "Request Header Fields Too Large",
+            500 => "Internal Server Error",
+            503 => "Service Unavailable",
+            _ => "",
+        }
+    }
+}
+
+fn handle(request: &Request, renders: &Renders) -> Response {
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("POST", ["renders"]) => {
+            let scene = std::str::from_utf8(&request.body)
+                .map_err(|_| "the scene is not valid UTF-8".to_string())
+                .and_then(|source| {
+                    Scene::from_yaml_without_files(source).map_err(|error| error.to_string())
+                });
+
+            match scene {
+                Ok(scene)
+                    if scene.camera.hsize > MAX_IMAGE_SIZE
+                        || scene.camera.vsize > MAX_IMAGE_SIZE =>
+                {
+                    Response::error(
+                        400,
+                        &format!("the image can't be larger than {0}x{0}", MAX_IMAGE_SIZE),
+                    )
+                }
+                Ok(scene) => match renders.start(scene) {
+                    Some(id) => Response::json(202, format!("{{\"id\":{}}}", id)),
+                    None => Response::error(503, "too many renders in progress, try again later"),
+                },
+                Err(message) => Response::error(400, &message),
+            }
+        }
+        ("GET", ["renders", id]) => match id.parse().ok().and_then(|id| renders.get(id)) {
+            Some(RenderStatus::Rendering { done, total }) => Response::json(
+                200,
+                format!(
+                    "{{\"status\":\"rendering\",\"done\":{},\"total\":{}}}",
+                    done, total
+                ),
+            ),
+            Some(RenderStatus::Done(_)) => Response::json(200, "{\"status\":\"done\"}".to_string()),
+            Some(RenderStatus::Failed) => {
+                Response::json(200, "{\"status\":\"failed\"}".to_string())
+            }
+            None => Response::error(404, "no such render"),
+        },
+        ("GET", ["renders", id, "image.png"]) => {
+            match id.parse().ok().and_then(|id| renders.get(id)) {
+                Some(RenderStatus::Done(png)) => Response {
+                    status: 200,
+                    content_type: "image/png",
+                    body: png,
+                },
+                Some(RenderStatus::Rendering { .. }) => {
+                    Response::error(409, "the render is not done yet")
+                }
+                Some(RenderStatus::Failed) => Response::error(500, "the render failed"),
+                None => Response::error(404, "no such render"),
+            }
+        }
+        (_, ["renders"]) | (_, ["renders", _]) | (_, ["renders", _, "image.png"]) => {
+            Response::error(405, "method not allowed")
+        }
+        _ => Response::error(404, "not found"),
+    }
+}
+
+/// Quotes `string` as a JSON string.
+fn json_string(string: &str) -> String {
+    let mut quoted = String::with_capacity(string.len() + 2);
+    quoted.push('"');
+
+    for c in string.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+fn handle_connection(stream: TcpStream, renders: &Renders) -> Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    let mut reader = BufReader::new(stream);
+    let response = match read_request(&mut reader)? {
+        Ok(request) => handle(&request, renders),
+        Err(response) => response,
+    };
+
+    let mut stream = reader.into_inner();
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.reason(),
+        response.content_type,
+        response.body.len()
+    )?;
+    stream.write_all(&response.body)?;
+    stream.flush()
+}
+
+/// Reads a request, or the response turning it away if its lines are over
+/// [`MAX_LINE_LENGTH`], it has more than [`MAX_HEADERS`] or its body is over [`MAX_BODY_SIZE`]
+/// (what's left of it is left unread).
+fn read_request(reader: &mut impl BufRead) -> Result<std::result::Result<Request, Response>> {
+    let too_large = || Response::error(431, "the request's header is too large");
+    let Some(request_line) = read_line(reader)? else {
+        return Ok(Err(too_large()));
+    };
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut content_length = 0;
+    for headers in 0.. {
+        let header = match read_line(reader)? {
+            Some(header) if headers <= MAX_HEADERS => header,
+            _ => return Ok(Err(too_large())),
+        };
+        if header.trim().is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    if content_length > MAX_BODY_SIZE {
+        return Ok(Err(Response::error(413, "the scene is too large")));
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    Ok(Ok(Request { method, path, body }))
+}
+
+/// Reads a line, or `None` if it goes on past [`MAX_LINE_LENGTH`].
+fn read_line(reader: &mut impl BufRead) -> Result<Option<String>> {
+    let mut line = Vec::new();
+    reader
+        .take(MAX_LINE_LENGTH as u64)
+        .read_until(b'\n', &mut line)?;
+    if line.len() == MAX_LINE_LENGTH && !line.ends_with(b"\n") {
+        return Ok(None);
+    }
+
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|error| Error::new(ErrorKind::InvalidData, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::matrix4::Matrix4;
+    use std::time::Duration;
+
+    const SCENE: &str = "
+- add: camera
+  width: 4
+  height: 3
+  field-of-view: 1
+  from: [ 0, 0, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+- add: light
+  at: [ -10, 10, -10 ]
+  intensity: [ 1, 1, 1 ]
+- add: sphere
+";
+
+    fn request(method: &str, path: &str, body: &str) -> Request {
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn parsing_a_request() {
+        let raw = "POST /renders HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\n\r\nhello";
+        let request = read_request(&mut raw.as_bytes()).unwrap().unwrap();
+
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/renders");
+        assert_eq!(request.body, b"hello");
+    }
+
+    #[test]
+    fn bodies_that_are_too_large_are_not_read() {
+        let raw = format!(
+            "POST /renders HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            usize::MAX
+        );
+
+        let response = read_request(&mut raw.as_bytes()).unwrap().unwrap_err();
+        assert_eq!(response.status, 413);
+    }
+
+    #[test]
+    fn headers_that_are_too_large_are_not_read() {
+        let long_line = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE_LENGTH));
+        let many_headers = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "Host: localhost\r\n".repeat(MAX_HEADERS + 1)
+        );
+
+        for raw in [long_line, many_headers] {
+            let response = read_request(&mut raw.as_bytes()).unwrap().unwrap_err();
+            assert_eq!(response.status, 431);
+        }
+    }
+
+    #[test]
+    fn errors_are_valid_json_strings() {
+        let response = Response::error(400, "bad \"key\" \\ at line 1\n\u{1}");
+
+        assert_eq!(
+            response.body,
+            br#"{"error":"bad \"key\" \\ at line 1\n\u0001"}"#
+        );
+    }
+
+    #[test]
+    fn submitting_and_fetching_a_render() {
+        let renders = Renders::default();
+
+        let response = handle(&request("POST", "/renders", SCENE), &renders);
+        assert_eq!(response.status, 202);
+        assert_eq!(response.body, b"{\"id\":0}");
+
+        while let Some(RenderStatus::Rendering { .. }) = renders.get(0) {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        let response = handle(&request("GET", "/renders/0", ""), &renders);
+        assert_eq!(response.body, b"{\"status\":\"done\"}");
+
+        let response = handle(&request("GET", "/renders/0/image.png", ""), &renders);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.content_type, "image/png");
+        assert_eq!(&response.body[1..4], b"PNG");
+    }
+
+    #[test]
+    fn polling_the_progress_of_a_render() {
+        let renders = Renders::default();
+        renders
+            .0
+            .lock()
+            .unwrap()
+            .add(RenderStatus::Rendering { done: 4, total: 12 });
+
+        let response = handle(&request("GET", "/renders/0", ""), &renders);
+        assert_eq!(
+            response.body,
+            b"{\"status\":\"rendering\",\"done\":4,\"total\":12}"
+        );
+
+        let response = handle(&request("GET", "/renders/0/image.png", ""), &renders);
+        assert_eq!(response.status, 409);
+    }
+
+    #[test]
+    fn only_a_few_renders_run_at_once() {
+        let renders = Renders::default();
+        for _ in 0..MAX_RENDERS_IN_PROGRESS {
+            renders
+                .0
+                .lock()
+                .unwrap()
+                .add(RenderStatus::Rendering { done: 0, total: 12 });
+        }
+
+        let response = handle(&request("POST", "/renders", SCENE), &renders);
+        assert_eq!(response.status, 503);
+
+        renders
+            .0
+            .lock()
+            .unwrap()
+            .finish(0, RenderStatus::Done(vec![]));
+        let response = handle(&request("POST", "/renders", SCENE), &renders);
+        assert_eq!(response.status, 202);
+    }
+
+    #[test]
+    fn renders_that_panic_fail_and_free_their_slot() {
+        let renders = Renders::default();
+        let mut scene = Scene::from_yaml(SCENE).unwrap();
+        scene.camera.transform = Matrix4::scaling(0., 1., 1.);
+
+        let id = renders.start(scene).unwrap();
+        while let Some(RenderStatus::Rendering { .. }) = renders.get(id) {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(renders.get(id), Some(RenderStatus::Failed));
+        assert_eq!(renders.0.lock().unwrap().in_progress(), 0);
+        let response = handle(&request("GET", "/renders/0", ""), &renders);
+        assert_eq!(response.body, b"{\"status\":\"failed\"}");
+        let response = handle(&request("GET", "/renders/0/image.png", ""), &renders);
+        assert_eq!(response.status, 500);
+    }
+
+    #[test]
+    fn only_the_latest_finished_renders_are_kept() {
+        let renders = Renders::default();
+        for _ in 0..MAX_FINISHED_RENDERS + 1 {
+            let mut table = renders.0.lock().unwrap();
+            let id = table.add(RenderStatus::Rendering { done: 0, total: 12 });
+            table.finish(id, RenderStatus::Done(vec![]));
+        }
+
+        assert_eq!(renders.get(0), None);
+        assert_eq!(renders.get(1), Some(RenderStatus::Done(vec![])));
+        assert_eq!(
+            handle(&request("GET", "/renders/0", ""), &renders).status,
+            404
+        );
+    }
+
+    #[test]
+    fn invalid_requests_are_rejected() {
+        let renders = Renders::default();
+
+        let statuses: Vec<u16> = [
+            request("POST", "/renders", "- add: teapot"),
+            request(
+                "POST",
+                "/renders",
+                &SCENE.replace("width: 4", "width: 100000"),
+            ),
+            request("POST", "/renders", &SCENE.replace("width: 4", "width: 0")),
+            request(
+                "POST",
+                "/renders",
+                &format!("{}- add: obj\n  file: /etc/passwd\n", SCENE),
+            ),
+            request("GET", "/renders/3", ""),
+            request("GET", "/renders/nope/image.png", ""),
+            request("DELETE", "/renders/0", ""),
+            request("GET", "/", ""),
+        ]
+        .iter()
+        .map(|request| handle(request, &renders).status)
+        .collect();
+
+        assert_eq!(statuses, vec![400, 400, 400, 400, 404, 404, 405, 404]);
+    }
+}
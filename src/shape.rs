@@ -1,35 +1,158 @@
+use crate::bvh;
 use crate::color::Color;
+use crate::error::{RayTracerError, Result};
 use crate::intersection::Intersection;
 use crate::intersection::TorUVT;
 use crate::material::Material;
 use crate::math::matrix4::Matrix4;
+use crate::math::point3::Point3;
+use crate::math::transform::Transform;
 use crate::math::tuple::Tuple;
+use crate::math::vec3::Vec3;
+use crate::mesh::{self, Mesh};
 use crate::misc::EPSILON;
 use crate::ray::Ray;
+use crate::sampler::Rng;
+use crate::water::{water_material, Waves};
+use crate::world::World;
 pub mod cone;
 pub mod csg;
 pub mod cube;
+pub mod curve;
 pub mod cylinder;
+pub mod hits;
+pub mod lod;
 pub mod plane;
+pub mod point_cloud;
+pub mod primitive;
 pub mod sphere;
 pub mod triangle;
 use cone::Cone;
 use cube::Cube;
+use curve::Curve;
 use cylinder::Cylinder;
-use plane::Plane;
+use lod::Lod;
+use plane::{Disc, Plane, Quad};
+use point_cloud::{CloudPoint, PointCloud};
+use primitive::Primitive;
 use sphere::Sphere;
 use triangle::Triangle;
 
-use self::csg::Csg;
+use self::csg::{Csg, CsgMaterial};
+use std::sync::Arc;
 
 #[derive(Clone, Debug, PartialEq)]
 // #[cfg_attr(test, derive(PartialEq))]
 pub struct Object {
-    pub transform: Matrix4,
-    pub shape: ShapeOrGroup,
+    /// Keeps its inverse, which is why it can only change through `set_transform`.
+    transform: Transform,
+    /// World space bounds, cached for the same reason.
+    bounds: BoundingBox,
+    pub(crate) shape: ShapeOrGroup,
+    /// What to look the object up by, see `World::find_object`.
+    pub name: Option<String>,
 }
 
 impl Object {
+    pub(crate) fn from_parts(transform: Matrix4, shape: ShapeOrGroup) -> Self {
+        let mut object = Self {
+            transform: Transform::identity(),
+            bounds: BoundingBox::infinite(),
+            shape,
+            name: None,
+        };
+        object.set_transform(transform);
+
+        object
+    }
+
+    pub fn transform(&self) -> Matrix4 {
+        self.transform.matrix()
+    }
+
+    /// Changes the transform, updating everything derived from it.
+    ///
+    /// Panics if the transform can't be inverted, see `try_set_transform`.
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.try_set_transform(transform)
+            .expect("object transforms must be invertible");
+    }
+
+    /// Like `set_transform`, leaving the object as it was if the transform can't be inverted.
+    pub fn try_set_transform(&mut self, transform: Matrix4) -> Result<()> {
+        self.transform = Transform::new(transform).ok_or(RayTracerError::DegenerateTransform)?;
+        self.bounds = self.local_bounding_box().transform(transform);
+
+        Ok(())
+    }
+
+    pub fn shape(&self) -> &ShapeOrGroup {
+        &self.shape
+    }
+
+    /// The object with `name` for looking it up by, see `World::find_object`.
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// The indices of the children to go through, one group after the other, to get from
+    /// the object to the first one inside it called `name`, itself included.
+    fn path_to(&self, name: &str) -> Option<Vec<usize>> {
+        if self.name.as_deref() == Some(name) {
+            return Some(vec![]);
+        }
+
+        match &self.shape {
+            ShapeOrGroup::Group(group) => group.iter().enumerate().find_map(|(index, child)| {
+                let mut path = child.path_to(name)?;
+                path.insert(0, index);
+
+                Some(path)
+            }),
+            ShapeOrGroup::Shape { .. } => None,
+        }
+    }
+
+    /// The first object called `name` in this one, itself included, going through groups.
+    pub fn find(&self, name: &str) -> Option<&Object> {
+        let path = self.path_to(name)?;
+
+        Some(self.descendant(&path))
+    }
+
+    fn descendant(&self, path: &[usize]) -> &Object {
+        let Some((&index, rest)) = path.split_first() else {
+            return self;
+        };
+
+        match &self.shape {
+            ShapeOrGroup::Group(group) => group[index].descendant(rest),
+            ShapeOrGroup::Shape { .. } => unreachable!("paths only go through groups"),
+        }
+    }
+
+    fn descendant_mut(&mut self, path: &[usize]) -> &mut Object {
+        let Some((&index, rest)) = path.split_first() else {
+            return self;
+        };
+
+        match &mut self.shape {
+            ShapeOrGroup::Group(group) => group[index].descendant_mut(rest),
+            ShapeOrGroup::Shape { .. } => unreachable!("paths only go through groups"),
+        }
+    }
+
+    /// Updates the bounds of the groups along `path`, after what's at the end of it changed.
+    fn refresh_bounds_along(&mut self, path: &[usize]) {
+        if let (Some((&index, rest)), ShapeOrGroup::Group(group)) =
+            (path.split_first(), &mut self.shape)
+        {
+            group[index].refresh_bounds_along(rest);
+            self.bounds = self.local_bounding_box().transform(self.transform.matrix());
+        }
+    }
+
     pub(crate) fn includes(&self, object: SimpleObject) -> bool {
         match &self.shape {
             ShapeOrGroup::Group(group) => group.iter().any(|o| o.includes(object)),
@@ -37,38 +160,85 @@ impl Object {
                 shape: Shape::Csg(csg),
                 ..
             } => csg.includes(object),
-            ShapeOrGroup::Shape { .. } => {
-                let o = SimpleObject::from_object(self).unwrap();
-
-                o == object
-            }
+            ShapeOrGroup::Shape {
+                shape: Shape::Lod(lod),
+                ..
+            } => lod.includes(object),
+            ShapeOrGroup::Shape {
+                shape: Shape::PointCloud(cloud),
+                ..
+            } => cloud.includes(self.transform, object),
+            // The material is left out, a CSG can shade its operands with a different one.
+            ShapeOrGroup::Shape {
+                shape: Shape::Primitive(primitive),
+                ..
+            } => **primitive == *object.primitive && self.transform == object.transform,
         }
     }
 
+    /// Bounds of the object in world space, or in its parent's space if it's in a group.
     pub fn bounding_box(&self) -> BoundingBox {
-        let inner_bb = match &self.shape {
+        self.bounds.clone()
+    }
+
+    fn local_bounding_box(&self) -> BoundingBox {
+        match &self.shape {
             ShapeOrGroup::Shape { shape, .. } => shape.bounding_box(),
             ShapeOrGroup::Group(ref group) => group
                 .iter()
                 .map(|object| object.bounding_box())
                 .reduce(|box1, box2| BoundingBox::union(&box1, &box2))
-                .unwrap(),
-        };
+                .unwrap_or_else(BoundingBox::empty),
+        }
+    }
 
-        let new_points = inner_bb.points().map(|point| self.transform * point);
+    pub fn group(objects: Vec<Object>) -> Self {
+        Self::from_parts(Matrix4::identity(), ShapeOrGroup::Group(objects))
+    }
 
-        BoundingBox::from_points(&new_points)
+    /// Every object in the world as a group, to place a scene modelled on its own, like a
+    /// table, as one object, as many times as needed. Its lights are left out, see
+    /// `World::merge` to bring them along.
+    pub fn from_world(world: &World) -> Self {
+        Self::group(world.objects.clone())
     }
 
-    pub fn group(objects: Vec<Object>) -> Self {
-        Object {
-            transform: Matrix4::identity(),
-            shape: ShapeOrGroup::Group(objects),
+    /// Nests the children of groups into subgroups of nearby ones, so that no group
+    /// has more than `threshold` children that can be told apart by their bounds.
+    pub fn divide(&mut self, threshold: usize) {
+        if let ShapeOrGroup::Shape {
+            shape: Shape::Lod(ref mut lod),
+            ..
+        } = self.shape
+        {
+            for (_, level) in lod.levels.iter_mut() {
+                level.divide(threshold);
+            }
+        }
+
+        if let ShapeOrGroup::Group(ref mut children) = self.shape {
+            for child in children.iter_mut() {
+                child.divide(threshold);
+            }
+
+            let boxes: Vec<BoundingBox> = children.iter().map(Object::bounding_box).collect();
+            let nodes = bvh::build(&boxes, threshold);
+            *children = bvh::assemble(&nodes, std::mem::take(children))
+                .expect("hierarchies use every child once");
         }
     }
 
-    pub fn set_material(&mut self, material: Material) {
+    pub fn set_material(&mut self, mut material: Material) {
+        material.unset = false;
         match self.shape {
+            ShapeOrGroup::Shape {
+                shape: Shape::Lod(ref mut lod),
+                ..
+            } => {
+                for (_, level) in lod.levels.iter_mut() {
+                    level.set_material(material);
+                }
+            }
             ShapeOrGroup::Shape {
                 material: ref mut mat,
                 ..
@@ -83,92 +253,337 @@ impl Object {
         }
     }
 
-    pub fn intersect(&self, ray: Ray) -> Vec<Intersection> {
-        let bb = self.bounding_box();
+    /// Replaces every material surfaces of the object can be shaded with by what `f` makes of it.
+    pub(crate) fn map_materials(&mut self, f: &impl Fn(Material) -> Material) {
+        match &mut self.shape {
+            ShapeOrGroup::Group(group) => {
+                for object in group.iter_mut() {
+                    object.map_materials(f);
+                }
+            }
+            ShapeOrGroup::Shape {
+                shape: Shape::Lod(lod),
+                ..
+            } => {
+                for (_, level) in lod.levels.iter_mut() {
+                    level.map_materials(f);
+                }
+            }
+            ShapeOrGroup::Shape {
+                shape: Shape::Csg(csg),
+                material,
+            } => {
+                *material = f(*material);
+                csg.left.map_materials(f);
+                csg.right.map_materials(f);
+                for operand_material in [&mut csg.left_material, &mut csg.right_material] {
+                    if let CsgMaterial::Custom(material) = operand_material {
+                        **material = f(**material);
+                    }
+                }
+            }
+            ShapeOrGroup::Shape { material, .. } => *material = f(*material),
+        }
+    }
+
+    /// Whether `test` holds for any of the materials surfaces of the object can be shaded with.
+    pub(crate) fn any_material(&self, test: &impl Fn(&Material) -> bool) -> bool {
+        match &self.shape {
+            ShapeOrGroup::Group(group) => group.iter().any(|object| object.any_material(test)),
+            ShapeOrGroup::Shape {
+                shape: Shape::Lod(lod),
+                ..
+            } => lod.levels.iter().any(|(_, level)| level.any_material(test)),
+            ShapeOrGroup::Shape {
+                shape: Shape::Csg(csg),
+                material,
+            } => {
+                let custom = |operand_material: &CsgMaterial| match operand_material {
+                    CsgMaterial::Custom(material) => test(material),
+                    _ => false,
+                };
+
+                test(material)
+                    || csg.left.any_material(test)
+                    || csg.right.any_material(test)
+                    || custom(&csg.left_material)
+                    || custom(&csg.right_material)
+            }
+            ShapeOrGroup::Shape { material, .. } => test(material),
+        }
+    }
+
+    /// Triangles approximating the surface of the object, in the same space as its bounding box,
+    /// with curves split in `resolution` segments all the way around.
+    ///
+    /// CSG keeps the triangles of its operands on the surface of the result, so cuts are as
+    /// jagged as the resolution is coarse. Planes, curves, infinite cylinders and cones, and
+    /// primitives without a `Primitive::tessellate` are left out, and level of detail shapes
+    /// use their most detailed model.
+    pub fn tessellate(&self, resolution: usize) -> Mesh {
+        mesh::tessellate(self, resolution)
+    }
+
+    /// A point on the surface of the object in the same space as its bounding box, the normal
+    /// there, and the probability density of picking it per unit of area in that space,
+    /// for area lights and the like. `None` if there's nothing in it `Primitive::sample_surface`
+    /// can pick points on.
+    ///
+    /// Groups pick their children by area, so meshes are sampled uniformly, and so are shapes
+    /// scaled the same in every direction. Other shapes are more likely to be picked where
+    /// they're squashed, which the density accounts for.
+    pub fn sample_surface(&self, rng: &mut Rng) -> Option<(Point3, Vec3, f64)> {
+        let (point, normal, density) = match &self.shape {
+            ShapeOrGroup::Shape { shape, .. } => shape.sample_surface(rng)?,
+            ShapeOrGroup::Group(group) => {
+                let areas: Vec<f64> = group.iter().map(Object::sampled_area).collect();
+                let total: f64 = areas.iter().sum();
+                if total <= 0. {
+                    return None;
+                }
+
+                let mut target = rng.next_f64() * total;
+                let index = areas
+                    .iter()
+                    .position(|area| {
+                        target -= area;
+                        target < 0.
+                    })
+                    .or_else(|| areas.iter().rposition(|&area| area > 0.))?;
+                let (point, normal, density) = group[index].sample_surface(rng)?;
+
+                (point, normal, density * areas[index] / total)
+            }
+        };
+
+        let world_normal = self.transform.inverse_transpose() * normal;
+        // How much the transform stretches the surface around the point.
+        let stretch = self.transform.matrix().determinant().abs() * world_normal.magnitude();
+
+        Some((
+            self.transform.matrix() * point,
+            world_normal.normalize(),
+            density / stretch,
+        ))
+    }
+
+    /// Roughly the area `sample_surface` picks points on, in the same space: exact for
+    /// triangles and shapes scaled the same in every direction, otherwise estimated from
+    /// how much the transform scales volumes.
+    fn sampled_area(&self) -> f64 {
+        let transform = self.transform.matrix();
+        let scale = transform.determinant().abs().powf(2. / 3.);
+
+        match &self.shape {
+            ShapeOrGroup::Shape { shape, .. } => match shape {
+                Shape::Primitive(primitive) => match primitive.downcast_ref::<Triangle>() {
+                    Some(triangle) => Triangle::new(
+                        transform * triangle.p1,
+                        transform * triangle.p2,
+                        transform * triangle.p3,
+                    )
+                    .area(),
+                    None => primitive.area().unwrap_or(0.) * scale,
+                },
+                _ => 0.,
+            },
+            ShapeOrGroup::Group(group) => {
+                group.iter().map(Object::sampled_area).sum::<f64>() * scale
+            }
+        }
+    }
+
+    pub fn intersect(&self, ray: Ray) -> Vec<Intersection<'_>> {
+        let mut intersections = vec![];
+        self.intersect_into(ray, &mut intersections);
+
+        intersections
+    }
+
+    /// Like `intersect`, adding the intersections to `intersections` instead of a new list,
+    /// so a whole world can be intersected into the same one.
+    pub(crate) fn intersect_into<'a>(
+        &'a self,
+        ray: Ray,
+        intersections: &mut Vec<Intersection<'a>>,
+    ) {
+        let bb = self.bounding_box().padded(BOUNDING_BOX_PADDING);
         // This is a bit different from the book, it looks like?
         // They seem to do the AABB check in the local intersect function
         // But that doesn't seem to make sense because we compute the bounding box in world space.
         let intersects_box = bb.intersect(ray);
 
         if intersects_box {
-            let local_ray = ray.transform(self.transform.inverse().unwrap());
+            let local_ray = ray.transform(self.transform.inverse());
 
-            self.local_intersect(local_ray)
-        } else {
-            vec![]
+            self.local_intersect(local_ray, intersections)
         }
     }
 
-    fn local_intersect<'a>(&'a self, local_ray: Ray) -> Vec<Intersection<'a>> {
-        match self.shape {
-            ShapeOrGroup::Shape {
-                shape: Shape::Csg(ref csg),
-                ..
-            } => csg
-                .local_intersect(local_ray)
-                .into_iter()
-                .map(|mut i| {
-                    i.object.transform = self.transform * i.object.transform;
-                    i
-                })
-                .collect(),
-            ShapeOrGroup::Group(ref group) => group
-                .iter()
-                .flat_map(|object| object.intersect(local_ray))
-                .map(|mut i| {
+    fn local_intersect<'a>(&'a self, local_ray: Ray, intersections: &mut Vec<Intersection<'a>>) {
+        let (shape, material) = match &self.shape {
+            ShapeOrGroup::Shape { shape, material } => (shape, material),
+            ShapeOrGroup::Group(group) => {
+                let start = intersections.len();
+                for object in group {
+                    object.intersect_into(local_ray, intersections);
+                }
+
+                for i in &mut intersections[start..] {
                     i.object.transform = self.transform * i.object.transform;
-                    i
-                })
-                .collect(),
+                    i.object.root_transform = self.transform;
+                }
+                return;
+            }
+        };
 
-            ShapeOrGroup::Shape {
-                ref shape,
-                ref material,
-            } => shape
-                .local_intersect(local_ray)
-                .into_iter()
-                .map(|t| {
+        match shape {
+            Shape::Primitive(primitive) => {
+                intersections.extend(primitive.local_intersect(local_ray).into_iter().map(|t| {
                     Intersection::new(
                         &t,
                         SimpleObject {
                             material: *material,
                             transform: self.transform,
-                            shape: &shape,
+                            root_transform: self.transform,
+                            primitive: primitive.as_ref(),
                         },
                     )
-                })
-                .collect(),
+                }))
+            }
+            Shape::Csg(csg) => {
+                intersections.extend(csg.local_intersect(local_ray).into_iter().map(|mut i| {
+                    i.object.transform = self.transform * i.object.transform;
+                    // A CSG is a single shape, not a group of them.
+                    i.object.root_transform = self.transform * i.object.root_transform;
+                    i
+                }))
+            }
+            Shape::Lod(lod) => {
+                let start = intersections.len();
+                lod.level_for(local_ray)
+                    .intersect_into(local_ray, intersections);
+
+                // Like a group with only the level in it.
+                for i in &mut intersections[start..] {
+                    i.object.transform = self.transform * i.object.transform;
+                    i.object.root_transform = self.transform;
+                }
+            }
+            Shape::PointCloud(cloud) => {
+                intersections.extend(cloud.local_intersect(local_ray).into_iter().map(
+                    |(t, point)| {
+                        Intersection::new(
+                            &TorUVT::JustT { t },
+                            SimpleObject {
+                                material: point.material(*material),
+                                transform: self.transform * point.transform(),
+                                root_transform: self.transform,
+                                primitive: &point_cloud::UNIT_SPHERE,
+                            },
+                        )
+                    },
+                ))
+            }
         }
     }
 
     pub fn new(shape: Shape) -> Self {
-        Self {
-            transform: Matrix4::identity(),
-            shape: ShapeOrGroup::Shape {
-                material: Material::new(),
+        Self::from_parts(
+            Matrix4::identity(),
+            ShapeOrGroup::Shape {
+                material: Material {
+                    unset: true,
+                    ..Material::new()
+                },
                 shape,
             },
-        }
+        )
     }
 
     pub fn sphere() -> Self {
-        Self::new(Shape::Sphere)
+        Self::primitive(Sphere {})
     }
 
     pub fn plane() -> Self {
-        Self::new(Shape::Plane)
+        Self::primitive(Plane {})
+    }
+
+    /// The part of the xz plane within `radius` of the origin, facing up.
+    pub fn disc(radius: f64) -> Self {
+        Self::primitive(Disc { radius })
+    }
+
+    /// A `width` by `depth` rectangle on the xz plane centred on the origin, facing up.
+    pub fn quad(width: f64, depth: f64) -> Self {
+        Self::primitive(Quad { width, depth })
+    }
+
+    /// A plane of clear water rippling with `waves` as time goes by.
+    pub fn water(waves: Waves) -> Self {
+        let mut water = Self::plane();
+        water.set_material(water_material(waves));
+
+        water
+    }
+
+    /// A floor that is invisible except for the shadows falling on it.
+    pub fn shadow_catcher() -> Self {
+        let mut floor = Self::plane();
+        floor.set_material(Material::shadow_catcher());
+
+        floor
+    }
+
+    /// Two squares from -1 to 1 on their xy planes, each one showing what's past the other,
+    /// so that rays going into one come out of the other.
+    pub fn portals(transform: Matrix4, other_transform: Matrix4) -> (Self, Self) {
+        let portal = |transform: Matrix4, exit: Matrix4| {
+            let corners = [
+                Tuple::point(-1., -1., 0.),
+                Tuple::point(1., -1., 0.),
+                Tuple::point(1., 1., 0.),
+                Tuple::point(-1., 1., 0.),
+            ];
+            let mut square = Self::group(vec![
+                Self::primitive(Triangle::new(corners[0], corners[1], corners[2])),
+                Self::primitive(Triangle::new(corners[0], corners[2], corners[3])),
+            ]);
+            square.set_transform(transform);
+            square.set_material(Material::portal(exit));
+
+            square
+        };
+
+        (
+            portal(transform, other_transform),
+            portal(other_transform, transform),
+        )
     }
 
     pub fn cube() -> Self {
-        Self::new(Shape::Cube)
+        Self::primitive(Cube)
     }
 
     pub fn cylinder() -> Self {
-        Self::new(Shape::Cylinder(Cylinder::new()))
+        Self::primitive(Cylinder::new())
     }
 
     pub fn cone() -> Self {
-        Self::new(Shape::Cone(Cone::new()))
+        Self::primitive(Cone::new())
+    }
+
+    pub fn point_cloud(points: Vec<CloudPoint>) -> Self {
+        Self::new(Shape::PointCloud(PointCloud::new(points)))
+    }
+
+    pub fn curve(curve: Curve) -> Self {
+        Self::primitive(curve)
+    }
+
+    /// An object of any shape rays hit directly, built-in or not, see `Primitive`.
+    pub fn primitive(primitive: impl Primitive) -> Self {
+        Self::new(Shape::primitive(primitive))
     }
 
     pub fn union(left: Object, right: Object) -> Self {
@@ -179,12 +594,65 @@ impl Object {
         Self::new(Shape::Csg(Csg::intersection(left, right)))
     }
 
+    /// Gives access to the CSG operation, if the object is one.
+    pub fn csg_mut(&mut self) -> Option<&mut Csg> {
+        match self.shape {
+            ShapeOrGroup::Shape {
+                shape: Shape::Csg(ref mut csg),
+                ..
+            } => Some(csg),
+            _ => None,
+        }
+    }
+
+    /// Levels of detail of a model, see `Lod`.
+    pub fn lod(detailed: Object, levels: Vec<(f64, Object)>) -> Self {
+        Self::new(Shape::Lod(Lod::new(detailed, levels)))
+    }
+
     pub fn difference(left: Object, right: Object) -> Self {
         Self::new(Shape::Csg(Csg::difference(left, right)))
     }
 }
 
+/// An object found by name somewhere in a group, for changing it in place, see
+/// `World::find_object_mut`. Groups keep the bounds of what's in them, so the ones it's in
+/// update theirs when it's dropped.
+pub struct ObjectMut<'a> {
+    root: &'a mut Object,
+    path: Vec<usize>,
+}
+
+impl<'a> ObjectMut<'a> {
+    pub(crate) fn find(root: &'a mut Object, name: &str) -> Option<Self> {
+        let path = root.path_to(name)?;
+
+        Some(Self { root, path })
+    }
+}
+
+impl std::ops::Deref for ObjectMut<'_> {
+    type Target = Object;
+
+    fn deref(&self) -> &Object {
+        self.root.descendant(&self.path)
+    }
+}
+
+impl std::ops::DerefMut for ObjectMut<'_> {
+    fn deref_mut(&mut self) -> &mut Object {
+        self.root.descendant_mut(&self.path)
+    }
+}
+
+impl Drop for ObjectMut<'_> {
+    fn drop(&mut self) {
+        self.root.refresh_bounds_along(&self.path);
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
+#[allow(clippy::large_enum_variant)]
 pub enum ShapeOrGroup {
     Shape { material: Material, shape: Shape },
     Group(Vec<Object>),
@@ -193,38 +661,87 @@ pub enum ShapeOrGroup {
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct SimpleObject<'a> {
     pub material: Material,
-    pub transform: Matrix4,
-    pub shape: &'a Shape,
+    pub transform: Transform,
+    /// Transform of the outermost group containing the object,
+    /// or its own transform if it isn't part of one.
+    pub root_transform: Transform,
+    pub primitive: &'a dyn Primitive,
 }
 
-#[derive(Debug)]
+/// Relative padding added to bounding boxes before testing rays against them.
+/// Boxes themselves are exact, shapes don't need to pad them.
+pub const BOUNDING_BOX_PADDING: f64 = EPSILON;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct BoundingBox {
     min: Tuple,
     max: Tuple,
 }
 
 impl BoundingBox {
-    #[allow(dead_code)]
-    pub fn to_object(&self) -> Object {
-        let Tuple {
-            x: w, y: h, z: d, ..
-        } = dbg!(self.max - self.min);
+    /// The box from the corner at `min` to the one at `max`, which can be infinitely far.
+    pub fn new(min: Tuple, max: Tuple) -> Self {
+        Self { min, max }
+    }
 
-        let mut object = Object::cube();
-        let pos = self.min + Tuple::vector(w / 2., h / 2., d / 2.);
+    /// The corner with the smallest coordinates.
+    pub fn min(&self) -> Tuple {
+        self.min
+    }
+
+    /// The corner with the largest coordinates.
+    pub fn max(&self) -> Tuple {
+        self.max
+    }
 
-        object.transform =
-            Matrix4::translation(pos.x, pos.y, pos.z) * Matrix4::scaling(w / 2., h / 2., d / 2.);
+    /// A translucent cube filling the box, to see where it is, `None` for boxes without an
+    /// end or with nothing in them. Sides with no thickness, like the bounds of a quad, are
+    /// given a little.
+    pub fn to_object(&self) -> Option<Object> {
+        if !self.is_finite() {
+            return None;
+        }
+        let half = (self.max - self.min) * 0.5;
+        let center = self.min + half;
+        let thickness = |side: f64| side.max(1e-3);
+
+        let mut object = Object::cube();
+        object
+            .try_set_transform(
+                Matrix4::translation(center.x, center.y, center.z)
+                    * Matrix4::scaling(thickness(half.x), thickness(half.y), thickness(half.z)),
+            )
+            .ok()?;
         let mut material = Material::new();
         material.color = Color::new(0.5, 0., 0.5);
         material.transparency = 0.925;
+        material.casts_shadows = false;
         object.set_material(material);
 
-        object
+        Some(object)
     }
 
     fn intersect(&self, world_ray: Ray) -> bool {
-        cube::local_intersect(self.min, self.max, world_ray).len() > 0
+        !cube::local_intersect(self.min, self.max, world_ray).is_empty()
+    }
+
+    /// Grows the box by `relative` times the magnitude of its coordinates (at least 1 unit),
+    /// so that rounding errors in transforms and in the slab test can't cull rays
+    /// grazing the surface, like rays hitting a flat triangle on its edge.
+    pub fn padded(&self, relative: f64) -> BoundingBox {
+        let magnitude = [self.min, self.max]
+            .iter()
+            .flat_map(|corner| [corner.x, corner.y, corner.z])
+            .map(f64::abs)
+            .filter(|coordinate| coordinate.is_finite())
+            .fold(1., f64::max);
+        let padding = relative * magnitude;
+        let padding = Tuple::vector(padding, padding, padding);
+
+        BoundingBox {
+            min: self.min - padding,
+            max: self.max + padding,
+        }
     }
 
     pub(crate) fn from_points(points: &[Tuple]) -> BoundingBox {
@@ -242,7 +759,32 @@ impl BoundingBox {
         }
     }
 
-    fn points(&self) -> [Tuple; 8] {
+    fn infinite() -> BoundingBox {
+        BoundingBox {
+            min: Tuple::point(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            max: Tuple::point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        }
+    }
+
+    /// A box that nothing hits, and that leaves others unchanged in a union.
+    pub(crate) fn empty() -> BoundingBox {
+        BoundingBox::from_points(&[])
+    }
+
+    /// The box containing this one once transformed, which is larger than it needs to be
+    /// for rotations. Boxes without an end stay without one.
+    pub fn transform(&self, transform: Matrix4) -> BoundingBox {
+        if self.is_finite() {
+            BoundingBox::from_points(&self.points().map(|point| transform * point))
+        } else if self.min.x > self.max.x {
+            self.clone()
+        } else {
+            // Transforming infinite corners gives NaNs, stay on the safe side.
+            BoundingBox::infinite()
+        }
+    }
+
+    pub(crate) fn points(&self) -> [Tuple; 8] {
         let Tuple {
             x: x_min,
             y: y_min,
@@ -268,7 +810,88 @@ impl BoundingBox {
         ]
     }
 
-    fn union(&self, other: &BoundingBox) -> BoundingBox {
+    /// The center of the box and the radius of the sphere through its corners.
+    #[cfg(feature = "bidirectional")]
+    pub(crate) fn bounding_sphere(&self) -> (Point3, f64) {
+        let half_diagonal = (self.max - self.min) * 0.5;
+        let center = self.min + half_diagonal;
+
+        (
+            Point3::new(center.x, center.y, center.z),
+            half_diagonal.magnitude(),
+        )
+    }
+
+    /// Whether every side of the box ends, which the sides of empty boxes don't.
+    pub fn is_finite(&self) -> bool {
+        [self.min, self.max]
+            .iter()
+            .all(|corner| corner.x.is_finite() && corner.y.is_finite() && corner.z.is_finite())
+    }
+
+    /// Whether `point` is inside the box or on its sides.
+    pub fn contains_point(&self, point: Tuple) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x)
+            && (self.min.y..=self.max.y).contains(&point.y)
+            && (self.min.z..=self.max.z).contains(&point.z)
+    }
+
+    /// Whether `other` fits inside this box, empty boxes fitting nowhere.
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        let is_empty = other.min.x > other.max.x;
+
+        !is_empty
+            && self.min.x <= other.min.x
+            && self.min.y <= other.min.y
+            && self.min.z <= other.min.z
+            && other.max.x <= self.max.x
+            && other.max.y <= self.max.y
+            && other.max.z <= self.max.z
+    }
+
+    /// Halves the box across its longest side. Sides without end are only split if no other
+    /// side has any length, at 0 or as near it as they go, since they have no middle.
+    pub fn split(&self) -> (BoundingBox, BoundingBox) {
+        let (min, max) = (self.min, self.max);
+        let sides = [(min.x, max.x), (min.y, max.y), (min.z, max.z)];
+        let length = |(min, max): (f64, f64)| max - min;
+        let splittable = |side| length(side).is_finite() && length(side) > 0.;
+        // The first of the longest sides, going backwards since `max_by` keeps the last one.
+        let axis = [2, 1, 0]
+            .into_iter()
+            .max_by(|&a, &b| {
+                let (a, b) = (sides[a], sides[b]);
+                (splittable(a).cmp(&splittable(b))).then(length(a).total_cmp(&length(b)))
+            })
+            .unwrap();
+        let (low, high) = sides[axis];
+        let middle = if length(sides[axis]).is_finite() {
+            low + (high - low) / 2.
+        } else {
+            0f64.max(low).min(high)
+        };
+
+        let (mut left_max, mut right_min) = (self.max, self.min);
+        match axis {
+            0 => (left_max.x, right_min.x) = (middle, middle),
+            1 => (left_max.y, right_min.y) = (middle, middle),
+            _ => (left_max.z, right_min.z) = (middle, middle),
+        }
+
+        (
+            BoundingBox {
+                min: self.min,
+                max: left_max,
+            },
+            BoundingBox {
+                min: right_min,
+                max: self.max,
+            },
+        )
+    }
+
+    /// The smallest box containing both.
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
         BoundingBox {
             min: Tuple::point(
                 f64::min(self.min.x, other.min.x),
@@ -284,150 +907,121 @@ impl BoundingBox {
     }
 }
 
+/// What an object is when it isn't a group: a primitive, hit directly, or a shape made of
+/// other objects.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Shape {
-    Sphere,
-    Plane,
-    Cube,
-    Cylinder(Cylinder),
-    Cone(Cone),
-    Triangle(Triangle),
+    /// Spheres, planes, cubes, triangles and the like, see `Primitive`.
+    Primitive(Arc<dyn Primitive>),
     Csg(Csg),
+    Lod(Lod),
+    PointCloud(PointCloud),
 }
 
 impl Shape {
+    pub fn primitive(primitive: impl Primitive) -> Self {
+        Shape::Primitive(Arc::new(primitive))
+    }
+
     fn bounding_box(&self) -> BoundingBox {
         match self {
-            Shape::Sphere => BoundingBox {
-                min: Tuple::point(-(1. + EPSILON), -(1. + EPSILON), -(1. + EPSILON)),
-                max: Tuple::point(1. + EPSILON, 1. + EPSILON, 1. + EPSILON),
-            },
-
-            Shape::Cube => BoundingBox {
-                min: Tuple::point(-1., -1., -1.),
-                max: Tuple::point(1., 1., 1.),
-            },
-            Shape::Plane => BoundingBox {
-                min: Tuple::point(f64::NEG_INFINITY, 0., f64::NEG_INFINITY),
-                max: Tuple::point(f64::INFINITY, 0., f64::INFINITY),
-            },
-            Shape::Cylinder(Cylinder {
-                minimum: min_y,
-                maximum: max_y,
-                ..
-            }) => BoundingBox {
-                min: Tuple::point(-1., *min_y, -1.),
-                max: Tuple::point(1., *max_y, 1.),
-            },
-            Shape::Cone(Cone {
-                minimum: min_y,
-                maximum: max_y,
-                ..
-            }) => {
-                let max_x = f64::max(min_y.abs(), max_y.abs());
-                let max_z = max_x;
-
-                BoundingBox {
-                    min: Tuple::point(-max_x, *min_y, -max_z),
-                    max: Tuple::point(max_x, *max_y, max_z),
-                }
-            }
-            Shape::Triangle(triangle) => triangle.bounding_box(),
-            Shape::Csg(csg) => {
-                let left = csg.left.bounding_box();
-                let right = csg.right.bounding_box();
-
-                left.union(&right)
-            }
+            Shape::Primitive(primitive) => primitive.bounding_box(),
+            Shape::Csg(csg) => csg.left.bounding_box().union(&csg.right.bounding_box()),
+            Shape::Lod(lod) => lod.bounding_box(),
+            Shape::PointCloud(cloud) => cloud.bounding_box(),
         }
     }
 
-    pub(crate) fn local_normal_at(&self, intersection: Intersection, local_point: Tuple) -> Tuple {
+    /// The area of the surface in the space of the shape, for the shapes `sample_surface` can
+    /// pick points on.
+    pub fn area(&self) -> Option<f64> {
         match self {
-            Shape::Sphere => Sphere::local_normal_at(local_point),
-            Shape::Plane => Plane::local_normal_at(local_point),
-            Shape::Cube => Cube::local_normal_at(local_point),
-            Shape::Cylinder(cylinder) => cylinder.local_normal_at(local_point),
-            Shape::Cone(cone) => cone.local_normal_at(local_point),
-            Shape::Triangle(triangle) => {
-                let uvt = intersection.uvt().unwrap();
-
-                triangle.local_normal_at(&uvt)
-            }
-            Shape::Csg(_) => unreachable!(),
+            Shape::Primitive(primitive) => primitive.area(),
+            _ => None,
         }
     }
 
-    fn local_intersect(&self, local_ray: Ray) -> Vec<TorUVT> {
-        match self {
-            Shape::Sphere => Sphere::local_intersect(local_ray)
-                .into_iter()
-                .map(|t| TorUVT::JustT { t })
-                .collect(),
-            Shape::Plane => Plane::local_intersect(local_ray)
-                .into_iter()
-                .map(|t| TorUVT::JustT { t })
-                .collect(),
-            Shape::Cube => Cube::local_intersect(local_ray)
-                .into_iter()
-                .map(|t| TorUVT::JustT { t })
-                .collect(),
-            Shape::Cylinder(cylinder) => cylinder
-                .local_intersect(local_ray)
-                .into_iter()
-                .map(|t| TorUVT::JustT { t })
-                .collect(),
-            Shape::Cone(cone) => cone
-                .local_intersect(local_ray)
-                .into_iter()
-                .map(|t| TorUVT::JustT { t })
-                .collect(),
-            Shape::Triangle(triangle) => triangle
-                .local_intersect(local_ray)
-                .into_iter()
-                .map(|uvt| TorUVT::UVT { uvt })
-                .collect(),
-            Shape::Csg(_) => unreachable!(),
-        }
+    /// A point picked uniformly on the surface in the space of the shape, the normal there,
+    /// and the probability density of picking it per unit of area, which is 1 over the area.
+    ///
+    /// Only primitives have one, and of the built-in ones only spheres, cubes, discs, quads and
+    /// triangles: planes have no end, and the rest have no simple way to pick points evenly.
+    pub fn sample_surface(&self, rng: &mut Rng) -> Option<(Point3, Vec3, f64)> {
+        let Shape::Primitive(primitive) = self else {
+            return None;
+        };
+        let (point, normal) = primitive.sample_surface(rng)?;
+
+        Some((point, normal, 1. / primitive.area()?))
     }
 }
 
 impl<'a> SimpleObject<'a> {
-    pub(crate) fn from_object(object: &'a Object) -> Option<Self> {
-        match &object.shape {
-            ShapeOrGroup::Shape { material, shape } => Some(Self {
-                transform: object.transform,
-                material: *material,
-                shape: shape,
-            }),
-            ShapeOrGroup::Group(_) => None,
-        }
+    /// The object as a single primitive shape, `None` for groups and for shapes made of
+    /// other objects.
+    pub fn from_object(object: &'a Object) -> Option<Self> {
+        let ShapeOrGroup::Shape {
+            material,
+            shape: Shape::Primitive(primitive),
+        } = &object.shape
+        else {
+            return None;
+        };
+
+        Some(Self {
+            transform: object.transform,
+            root_transform: object.transform,
+            material: *material,
+            primitive: primitive.as_ref(),
+        })
     }
 
     pub fn transform(&self) -> Matrix4 {
-        self.transform
+        self.transform.matrix()
     }
 
     pub fn material(&self) -> Material {
         self.material
     }
 
-    pub fn normal_at(&self, intersection: Intersection, world_point: Tuple) -> Tuple {
-        let inverse_transform = self.transform().inverse().unwrap();
-        let local_point = inverse_transform * world_point;
-        let local_normal = self.shape.local_normal_at(intersection, local_point);
+    pub fn normal_at(&self, intersection: Intersection, world_point: Point3) -> Vec3 {
+        self.world_normal(world_point, |local_point| {
+            self.primitive.local_normal_at(intersection, local_point)
+        })
+    }
 
-        let mut world_normal = inverse_transform.transpose() * local_normal;
-        // TODO: Investigate what's up with setting the w = 0;
-        world_normal.w = 0.;
+    /// Like `normal_at`, for the normal of the surface itself, see
+    /// `Primitive::local_geometric_normal_at`.
+    pub fn geometric_normal_at(&self, intersection: Intersection, world_point: Point3) -> Vec3 {
+        self.world_normal(world_point, |local_point| {
+            self.primitive
+                .local_geometric_normal_at(intersection, local_point)
+        })
+    }
 
-        world_normal.normalize()
+    /// Takes a point in world space into the space of the shape, through every group it's in,
+    /// since `transform` already composes theirs.
+    pub fn world_to_object(&self, world_point: Point3) -> Point3 {
+        self.transform.inverse() * world_point
+    }
+
+    /// Takes a normal in the space of the shape out to world space, through every group it's in.
+    pub fn normal_to_world(&self, normal: Vec3) -> Vec3 {
+        (self.transform.inverse_transpose() * normal).normalize()
+    }
+
+    fn world_normal(&self, world_point: Point3, local_normal_at: impl Fn(Point3) -> Vec3) -> Vec3 {
+        let local_normal = local_normal_at(self.world_to_object(world_point));
+
+        self.normal_to_world(local_normal)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::math::tuple::Tuple;
+    use crate::misc::approx_equal;
+    use crate::shape::triangle::Triangle;
     use std::f64::consts::PI;
 
     use super::*;
@@ -447,10 +1041,10 @@ mod tests {
     impl<'a> SimpleObject<'a> {
         /// The maths assume the sphere is located in the origin,
         /// and it handles the general case by "unmoving" the ray with the opposite transform.
-        pub fn intersect(&self, ray: Ray) -> Vec<Intersection> {
-            let local_ray = ray.transform(self.transform().inverse().unwrap());
+        pub fn intersect(&self, ray: Ray) -> Vec<Intersection<'_>> {
+            let local_ray = ray.transform(self.transform.inverse());
 
-            self.shape
+            self.primitive
                 .local_intersect(local_ray)
                 .into_iter()
                 .map(|t_or_uvt| Intersection::new(&t_or_uvt, *self))
@@ -458,22 +1052,50 @@ mod tests {
         }
     }
 
+    /// Pearson's chi-square statistic for `counts` against the fraction of them each bin
+    /// should get, to compare with the critical value for one degree of freedom less than
+    /// the number of bins.
+    pub(crate) fn chi_square(counts: &[usize], fractions: &[f64]) -> f64 {
+        let total = counts.iter().sum::<usize>() as f64;
+
+        counts
+            .iter()
+            .zip(fractions)
+            .map(|(&count, fraction)| {
+                let expected = total * fraction;
+
+                (count as f64 - expected).powi(2) / expected
+            })
+            .sum()
+    }
+
     #[test]
     fn the_default_transformation() {
         let object = Object::sphere();
         let s = SimpleObject::from_object(&object).unwrap();
 
-        assert_eq!(s.transform, Matrix4::identity());
+        assert_eq!(s.transform(), Matrix4::identity());
     }
 
     #[test]
     fn assigning_a_transformation() {
         let mut object = Object::sphere();
         let t = Matrix4::translation(2., 3., 4.);
-        object.transform = t;
+        object.set_transform(t);
         let s = SimpleObject::from_object(&object).unwrap();
 
-        assert_eq!(s.transform, t);
+        assert_eq!(s.transform(), t);
+    }
+
+    #[test]
+    fn small_uniform_scales_are_valid_transforms() {
+        let mut object = Object::sphere();
+        object.set_transform(Matrix4::scaling(0.02, 0.02, 0.02));
+
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
+        let xs = object.intersect(r);
+        assert_eq!(xs.len(), 2);
+        assert!(approx_equal(xs[0].t, 4.98));
     }
 
     #[test]
@@ -497,8 +1119,8 @@ mod tests {
 
     // #[test]
     // fn intersecting_a_scaled_shape_with_a_ray() {
-    //     let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
-    //     let mut s = SimpleObject::new(Shape::Sphere);
+    //     let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
+    //     let mut s = SimpleObject::sphere();
     //     s.set_transform(Matrix4::scaling(2., 2., 2.));
 
     //     let xs = s.intersect(r);
@@ -510,8 +1132,8 @@ mod tests {
 
     // #[test]
     // fn intersecting_a_translated_shape_with_a_ray() {
-    //     let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
-    //     let mut s = SimpleObject::new(Shape::Sphere);
+    //     let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
+    //     let mut s = SimpleObject::sphere();
     //     s.set_transform(Matrix4::translation(5., 0., 0.));
 
     //     let xs = s.intersect(r);
@@ -522,26 +1144,139 @@ mod tests {
     // }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn computing_the_normal_on_a_translated_shape() {
         let mut object = Object::sphere();
-        object.transform = Matrix4::translation(0., 1., 0.);
+        object.set_transform(Matrix4::translation(0., 1., 0.));
         let s = SimpleObject::from_object(&object).unwrap();
 
         let i = Intersection::new_(0., s);
-        let n = s.normal_at(i, Tuple::point(0., 1.70711, -0.70711));
-        assert_eq!(n, Tuple::vector(0., 0.70711, -0.70711));
+        let n = s.normal_at(i, Point3::new(0., 1.70711, -0.70711));
+        assert_eq!(n, Vec3::new(0., 0.70711, -0.70711));
     }
 
     #[test]
     fn computing_the_normal_on_a_transformed_shape() {
         let mut object = Object::sphere();
         let transform = Matrix4::scaling(1., 0.5, 1.) * Matrix4::rotation_z(PI / 5.);
-        object.transform = transform;
+        object.set_transform(transform);
         let s = SimpleObject::from_object(&object).unwrap();
 
         let i = Intersection::new_(0., s);
-        let n = s.normal_at(i, Tuple::point(0., 2_f64.sqrt() / 2., -2_f64.sqrt() / 2.));
-        assert_eq!(n, Tuple::vector(0., 0.97014, -0.24254));
+        let n = s.normal_at(i, Point3::new(0., 2_f64.sqrt() / 2., -2_f64.sqrt() / 2.));
+        assert_eq!(n, Vec3::new(0., 0.97014, -0.24254));
+    }
+
+    #[test]
+    fn elliptical_cylinders_and_cones_are_like_scaled_ones() {
+        let shear = Matrix4::shearing(0.5, 0., 0., 0.3, 0., 0.) * Matrix4::rotation_z(0.4);
+        let squash = Matrix4::scaling(2., 1., 0.5);
+        let pairs = [
+            (
+                Shape::primitive(Cylinder::truncated(-1., 1., true).unwrap()),
+                Shape::primitive(
+                    Cylinder::truncated(-1., 1., true)
+                        .unwrap()
+                        .with_radii(2., 0.5)
+                        .unwrap(),
+                ),
+            ),
+            (
+                Shape::primitive(Cone::truncated(-1., 0.5, true).unwrap()),
+                Shape::primitive(
+                    Cone::truncated(-1., 0.5, true)
+                        .unwrap()
+                        .with_radii(2., 0.5)
+                        .unwrap(),
+                ),
+            ),
+        ];
+
+        for (unit, elliptical) in pairs {
+            let mut scaled = Object::new(unit);
+            scaled.set_transform(shear * squash);
+            let mut elliptical = Object::new(elliptical);
+            elliptical.set_transform(shear);
+
+            assert_eq!(elliptical.bounding_box(), scaled.bounding_box());
+            for i in 0..50 {
+                let origin = Point3::new(-6., (i % 7) as f64 / 3. - 1., (i / 7) as f64 / 3. - 1.);
+                let r = Ray::new(origin, Vec3::new(1., 0.1, 0.05).normalize());
+                let (xs, expected) = (elliptical.intersect(r), scaled.intersect(r));
+
+                assert_eq!(xs.len(), expected.len());
+                for (hit, expected) in xs.iter().zip(&expected) {
+                    assert!(approx_equal(hit.t, expected.t));
+                    let point = r.position(hit.t);
+                    assert_eq!(
+                        hit.object.normal_at(*hit, point),
+                        expected.object.normal_at(*expected, point)
+                    );
+                }
+            }
+        }
+    }
+
+    /// A sphere nested in two groups, handed to `test` as `ray` sees it.
+    fn nested_sphere(
+        outer: Matrix4,
+        inner: Matrix4,
+        sphere: Matrix4,
+        ray: Ray,
+        test: impl FnOnce(SimpleObject),
+    ) {
+        let mut s = Object::sphere();
+        s.set_transform(sphere);
+        let mut g2 = Object::group(vec![s]);
+        g2.set_transform(inner);
+        let mut g1 = Object::group(vec![g2]);
+        g1.set_transform(outer);
+
+        test(g1.intersect(ray)[0].object);
+    }
+
+    #[test]
+    fn converting_a_point_from_world_to_object_space() {
+        nested_sphere(
+            Matrix4::rotation_y(PI / 2.),
+            Matrix4::scaling(2., 2., 2.),
+            Matrix4::translation(5., 0., 0.),
+            Ray::new(Point3::new(0., 0., -20.), Vec3::new(0., 0., 1.)),
+            |s| {
+                let p = s.world_to_object(Point3::new(-2., 0., -10.));
+                assert_eq!(p, Point3::new(0., 0., -1.));
+            },
+        );
+    }
+
+    #[test]
+    fn converting_a_normal_from_object_to_world_space() {
+        let third = 3_f64.sqrt() / 3.;
+        nested_sphere(
+            Matrix4::rotation_y(PI / 2.),
+            Matrix4::scaling(1., 2., 3.),
+            Matrix4::translation(5., 0., 0.),
+            Ray::new(Point3::new(0., 0., -30.), Vec3::new(0., 0., 1.)),
+            |s| {
+                let n = s.normal_to_world(Vec3::new(third, third, third));
+                assert_eq!(n, Vec3::new(0.28571, 0.42857, -0.85714));
+            },
+        );
+    }
+
+    #[test]
+    fn finding_the_normal_on_a_child_object() {
+        nested_sphere(
+            Matrix4::rotation_y(PI / 2.),
+            Matrix4::scaling(1., 2., 3.),
+            Matrix4::translation(5., 0., 0.),
+            Ray::new(Point3::new(0., 0., -30.), Vec3::new(0., 0., 1.)),
+            |s| {
+                let i = Intersection::new_(0., s);
+                let n = s.normal_at(i, Point3::new(1.7321, 1.1547, -5.5774));
+                assert_eq!(n, Vec3::new(0.2857, 0.42854, -0.85716));
+            },
+        );
     }
 
     #[test]
@@ -549,8 +1284,311 @@ mod tests {
         let object = Object::glass_sphere();
         let s = SimpleObject::from_object(&object).unwrap();
 
-        assert_eq!(s.transform, Matrix4::identity());
+        assert_eq!(s.transform(), Matrix4::identity());
         assert_eq!(s.material.transparency, 1.0);
         assert_eq!(s.material.refractive_index, 1.5);
     }
+
+    #[test]
+    fn changing_the_transform_updates_what_is_derived_from_it() {
+        let mut s = Object::sphere();
+        let r = Ray::new(Point3::new(5., 0., -5.), Vec3::new(0., 0., 1.));
+        assert!(s.intersect(r).is_empty());
+
+        s.set_transform(Matrix4::translation(5., 0., 0.));
+
+        assert_eq!(s.bounding_box().min, Tuple::point(4., -1., -1.));
+        assert_eq!(s.intersect(r).len(), 2);
+    }
+
+    #[test]
+    fn the_bounds_of_a_group_follow_the_transforms_of_its_children() {
+        let mut child = Object::sphere();
+        child.set_transform(Matrix4::translation(5., 0., 0.));
+        let mut group = Object::group(vec![child]);
+        group.set_transform(Matrix4::scaling(2., 2., 2.));
+
+        let bounds = group.bounding_box();
+        assert_eq!(bounds.min, Tuple::point(8., -2., -2.));
+        assert_eq!(bounds.max, Tuple::point(12., 2., 2.));
+        assert!(Object::group(vec![])
+            .intersect(Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.)))
+            .is_empty());
+    }
+
+    #[test]
+    fn dividing_a_group_keeps_what_rays_hit() {
+        let spheres: Vec<Object> = (0..8)
+            .map(|i| {
+                let mut sphere = Object::sphere();
+                sphere.set_transform(Matrix4::translation(i as f64 * 3., 0., 0.));
+                sphere
+            })
+            .collect();
+        let group = Object::group(spheres);
+        let mut divided = group.clone();
+        divided.divide(2);
+
+        match divided.shape() {
+            ShapeOrGroup::Group(children) => assert_eq!(children.len(), 2),
+            _ => unreachable!(),
+        }
+        assert_eq!(divided.bounding_box(), group.bounding_box());
+        for x in [0., 9., 21.] {
+            let r = Ray::new(Point3::new(x, 0., -5.), Vec3::new(0., 0., 1.));
+            let ts =
+                |object: &Object| -> Vec<f64> { object.intersect(r).iter().map(|i| i.t).collect() };
+            assert_eq!(ts(&divided), ts(&group));
+        }
+    }
+
+    #[test]
+    fn discs_and_quads_are_bounded_inside_divided_groups() {
+        // Walls standing on their sides, and lights lying face down above them.
+        let panels: Vec<Object> = (0..8)
+            .map(|i| {
+                let x = i as f64 * 3.;
+                if i % 2 == 0 {
+                    let mut wall = Object::quad(2., 4.);
+                    wall.set_transform(
+                        Matrix4::translation(x, 0., 0.) * Matrix4::rotation_x(PI / 2.),
+                    );
+                    wall
+                } else {
+                    let mut light = Object::disc(1.);
+                    light.set_transform(Matrix4::translation(x, 1., 0.) * Matrix4::rotation_x(PI));
+                    light
+                }
+            })
+            .collect();
+        let group = Object::group(panels);
+        let mut divided = group.clone();
+        divided.divide(2);
+
+        let bounds = group.bounding_box();
+        assert!(bounds.is_finite());
+        assert_eq!(bounds.min, Tuple::point(-1., -2., -1.));
+        assert_eq!(bounds.max, Tuple::point(22., 2., 1.));
+        for x in [0., 0.9, 1.1, 3., 3.9, 9., 21.5] {
+            let r = Ray::new(Point3::new(x, 0.5, -5.), Vec3::new(0., 0., 1.));
+            let ts =
+                |object: &Object| -> Vec<f64> { object.intersect(r).iter().map(|i| i.t).collect() };
+            assert_eq!(ts(&divided), ts(&group));
+            assert_eq!(ts(&group).len(), usize::from(x % 6. < 1.));
+        }
+        let down = Ray::new(Point3::new(3.5, 5., 0.5), Vec3::new(0., -1., 0.));
+        let hits = divided.intersect(down);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].t, 4.);
+        assert_eq!(
+            hits[0].object.normal_at(hits[0], Point3::new(3.5, 1., 0.5)),
+            Vec3::new(0., -1., 0.)
+        );
+    }
+
+    #[test]
+    fn padding_grows_with_the_magnitude_of_the_box() {
+        let small =
+            BoundingBox::from_points(&[Tuple::point(0., 0., 0.), Tuple::point(0.5, 0.5, 0.5)]);
+        let large =
+            BoundingBox::from_points(&[Tuple::point(0., 0., 0.), Tuple::point(1000., 1., 1.)]);
+
+        assert_eq!(small.padded(0.1).max, Tuple::point(0.6, 0.6, 0.6));
+        assert_eq!(large.padded(0.1).min, Tuple::point(-100., -100., -100.));
+
+        // Infinite extents don't blow up the padding of the others.
+        let plane = Plane {}.bounding_box().padded(0.1);
+        assert_eq!(plane.min.y, -0.1);
+        assert_eq!(plane.max.x, f64::INFINITY);
+    }
+
+    #[test]
+    fn bounding_boxes_can_be_moved_split_and_compared() {
+        let unit = BoundingBox::new(Tuple::point(-1., -1., -1.), Tuple::point(1., 1., 1.));
+
+        let moved = unit.transform(Matrix4::translation(1., 0., 0.) * Matrix4::scaling(2., 1., 1.));
+        assert_eq!(moved.min(), Tuple::point(-1., -1., -1.));
+        assert_eq!(moved.max(), Tuple::point(3., 1., 1.));
+        assert!(moved.contains_box(&unit));
+        assert!(!unit.contains_box(&moved));
+        assert!(moved.contains_point(Tuple::point(3., 0., 1.)));
+        assert!(!moved.contains_point(Tuple::point(3.1, 0., 0.)));
+
+        let (left, right) = moved.split();
+        assert_eq!(left.max(), Tuple::point(1., 1., 1.));
+        assert_eq!(right.min(), Tuple::point(1., -1., -1.));
+        assert_eq!(left.union(&right), moved);
+
+        let cube = moved.to_object().unwrap();
+        assert_eq!(cube.bounding_box(), moved);
+        assert!(BoundingBox::empty().to_object().is_none());
+        assert!(Plane {}.bounding_box().to_object().is_none());
+    }
+
+    #[test]
+    fn infinite_bounding_boxes_split_without_nans() {
+        let (left, right) = BoundingBox::infinite().split();
+        assert_eq!((left.max.x, right.min.x), (0., 0.));
+        assert_eq!(
+            (left.min.x, right.max.x),
+            (f64::NEG_INFINITY, f64::INFINITY)
+        );
+
+        // Sides with a length are split before ones without end.
+        let (left, right) = Shape::primitive(Cylinder::new().with_radius(2.).unwrap())
+            .bounding_box()
+            .split();
+        assert_eq!((left.max.x, right.min.x), (0., 0.));
+        assert_eq!(left.max.y, f64::INFINITY);
+
+        // Flat boxes like the plane's are split across a side without end.
+        let (left, right) = Plane {}.bounding_box().split();
+        assert_eq!((left.max.x, right.min.x), (0., 0.));
+
+        let above = BoundingBox::new(
+            Tuple::point(f64::NEG_INFINITY, 5., f64::NEG_INFINITY),
+            Tuple::point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        );
+        let (left, right) = above.split();
+        for corner in [left.min, left.max, right.min, right.max] {
+            assert!(!corner.x.is_nan() && !corner.y.is_nan() && !corner.z.is_nan());
+        }
+        let union = left.union(&right);
+        for (corner, expected) in [(union.min, above.min), (union.max, above.max)] {
+            assert_eq!(
+                (corner.x, corner.y, corner.z),
+                (expected.x, expected.y, expected.z)
+            );
+        }
+    }
+
+    #[test]
+    fn rays_hitting_a_triangle_on_its_bounding_box_face_are_not_culled() {
+        for i in 0..200 {
+            let a = i as f64 * 0.37;
+            let mut triangle = Object::primitive(Triangle::new(
+                Tuple::point(0., 1., 0.),
+                Tuple::point(-1., 0., 0.),
+                Tuple::point(1., 0., 0.),
+            ));
+            triangle.set_transform(
+                Matrix4::translation(3.1 * a.sin(), 1.7, -0.3)
+                    * Matrix4::scaling(2.3 + a.sin(), 1.1, 0.7),
+            );
+
+            // The top vertex lies on the top face of the box.
+            let target = triangle.transform() * Point3::new(0., 1., 0.);
+            let origin = target + Vec3::new(0.2 * a.cos(), 0.5, -5.);
+            let ray = Ray::new(origin, (target - origin).normalize());
+
+            let local_ray = ray.transform(triangle.transform.inverse());
+            let local_hits = SimpleObject::from_object(&triangle)
+                .unwrap()
+                .primitive
+                .local_intersect(local_ray)
+                .len();
+
+            assert_eq!(triangle.intersect(ray).len(), local_hits, "case {}", i);
+        }
+    }
+
+    #[test]
+    fn sampling_a_mesh_is_uniform_over_its_area() {
+        // The ends have a tenth of the area each, the sides a fifth.
+        let mut cube = Object::cube();
+        cube.set_transform(Matrix4::scaling(2., 1., 1.));
+        let mesh = cube.tessellate(1).to_group();
+        let mut rng = Rng::new(3);
+        let mut counts = [0; 6];
+
+        for _ in 0..30_000 {
+            let (point, normal, density) = mesh.sample_surface(&mut rng).unwrap();
+            let face = [normal.x, normal.y, normal.z]
+                .iter()
+                .position(|coordinate| coordinate.abs() > 0.5)
+                .unwrap();
+
+            assert!(approx_equal(density, 1. / 40.));
+            assert!(mesh
+                .bounding_box()
+                .padded(EPSILON)
+                .contains_point(point.into()));
+            counts[face * 2 + usize::from(normal.x + normal.y + normal.z > 0.)] += 1;
+        }
+
+        let fractions = [0.1, 0.1, 0.2, 0.2, 0.2, 0.2];
+        // The critical value for 5 degrees of freedom at p = 0.001.
+        assert!(chi_square(&counts, &fractions) < 20.52);
+    }
+
+    #[test]
+    fn sampling_a_transformed_shape_gives_densities_in_the_space_of_its_parent() {
+        let mut rng = Rng::new(8);
+        let mut sphere = Object::sphere();
+        sphere.set_transform(Matrix4::translation(1., 2., 3.) * Matrix4::scaling(2., 2., 2.));
+
+        for _ in 0..100 {
+            let (point, normal, density) = sphere.sample_surface(&mut rng).unwrap();
+
+            assert!(approx_equal(
+                (point - Point3::new(1., 2., 3.)).magnitude(),
+                2.
+            ));
+            assert_eq!(normal, (point - Point3::new(1., 2., 3.)).normalize());
+            assert!(approx_equal(density, 1. / (16. * PI)));
+        }
+
+        // Picked unevenly, but 1 over the density still averages out to the area.
+        let mut cube = Object::cube();
+        cube.set_transform(Matrix4::scaling(2., 1., 1.));
+        let samples = 20_000;
+        let area = (0..samples)
+            .map(|_| 1. / cube.sample_surface(&mut rng).unwrap().2)
+            .sum::<f64>()
+            / samples as f64;
+
+        assert!((area - 40.).abs() < 0.5);
+    }
+
+    #[test]
+    fn sampling_discs_and_quads_stays_within_them() {
+        let mut rng = Rng::new(5);
+        let disc = Object::disc(2.);
+        let quad = Object::quad(3., 1.);
+        let mut near_the_middle = 0;
+
+        for _ in 0..10_000 {
+            let (point, normal, density) = disc.sample_surface(&mut rng).unwrap();
+            let distance = point.x.hypot(point.z);
+
+            assert!(distance <= 2. && point.y == 0.);
+            assert_eq!(normal, Vec3::new(0., 1., 0.));
+            assert!(approx_equal(density, 1. / (4. * PI)));
+            near_the_middle += usize::from(distance < 1.);
+
+            let (point, _, density) = quad.sample_surface(&mut rng).unwrap();
+            assert!(point.x.abs() <= 1.5 && point.z.abs() <= 0.5);
+            assert!(approx_equal(density, 1. / 3.));
+        }
+
+        // The inner half of the radius is only a quarter of the area.
+        assert!(chi_square(&[near_the_middle, 10_000 - near_the_middle], &[0.25, 0.75]) < 10.83);
+    }
+
+    #[test]
+    fn only_some_shapes_can_be_sampled() {
+        let mut rng = Rng::new(1);
+
+        assert!(Object::plane().sample_surface(&mut rng).is_none());
+        assert!(Object::cylinder().sample_surface(&mut rng).is_none());
+        assert!(Object::group(vec![]).sample_surface(&mut rng).is_none());
+        // Groups only pick children that can be sampled.
+        let group = Object::group(vec![Object::plane(), Object::sphere()]);
+        for _ in 0..100 {
+            let (point, _, density) = group.sample_surface(&mut rng).unwrap();
+
+            assert!(approx_equal((point - Point3::origin()).magnitude(), 1.));
+            assert!(approx_equal(density, 1. / (4. * PI)));
+        }
+    }
 }
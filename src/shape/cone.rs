@@ -1,43 +1,123 @@
-use std::f64::{INFINITY, NEG_INFINITY};
-
-use crate::{math::tuple::Tuple, misc::EPSILON, ray::Ray};
+use crate::{
+    error::{RayTracerError, Result},
+    intersection::{Intersection, TorUVT},
+    math::{point3::Point3, tuple::Tuple, vec3::Vec3},
+    mesh::{self, Mesh},
+    misc::{epsilon_at, EPSILON},
+    ray::Ray,
+    shape::{hits::Hits, primitive::Primitive, BoundingBox},
+};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Cone {
-    pub minimum: f64,
-    pub maximum: f64,
-    pub closed: bool,
+    minimum: f64,
+    maximum: f64,
+    closed: bool,
+    /// How far the surface is from the axis along x and z at a height of 1, the cross-section
+    /// being an ellipse unless they're the same.
+    radius_x: f64,
+    radius_z: f64,
 }
 
 impl Cone {
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
         Self {
-            minimum: NEG_INFINITY,
-            maximum: INFINITY,
+            minimum: f64::NEG_INFINITY,
+            maximum: f64::INFINITY,
             closed: false,
+            radius_x: 1.,
+            radius_z: 1.,
+        }
+    }
+
+    /// Cut off below `minimum` and above `maximum`, with caps on the ends if `closed`.
+    /// Bounds can be infinite, but `minimum` has to be below `maximum`.
+    pub fn truncated(minimum: f64, maximum: f64, closed: bool) -> Result<Self> {
+        if minimum.is_nan() || maximum.is_nan() || minimum >= maximum {
+            return Err(RayTracerError::InvalidShape(format!(
+                "the minimum of a cone must be below its maximum, got {} and {}",
+                minimum, maximum
+            )));
         }
+
+        Ok(Self {
+            minimum,
+            maximum,
+            closed,
+            ..Self::new()
+        })
+    }
+
+    /// As wide as `radius` all around instead of 1, see `with_radii`.
+    pub fn with_radius(self, radius: f64) -> Result<Self> {
+        self.with_radii(radius, radius)
+    }
+
+    /// An elliptical cone, widening out from the apex by `radius_x` along x and `radius_z`
+    /// along z for every unit of height, instead of scaling it, which only squashes it in
+    /// object space and leaves the normals to the inverse transpose of the transform.
+    /// Radii have to be positive.
+    pub fn with_radii(self, radius_x: f64, radius_z: f64) -> Result<Self> {
+        let valid = |radius: f64| radius > 0. && radius.is_finite();
+        if !valid(radius_x) || !valid(radius_z) {
+            return Err(RayTracerError::InvalidShape(format!(
+                "the radii of a cone must be positive, got {} and {}",
+                radius_x, radius_z
+            )));
+        }
+
+        Ok(Self {
+            radius_x,
+            radius_z,
+            ..self
+        })
+    }
+
+    pub fn minimum(&self) -> f64 {
+        self.minimum
     }
 
-    pub fn local_intersect(&self, ray: Ray) -> Vec<f64> {
+    pub fn maximum(&self) -> f64 {
+        self.maximum
+    }
+
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    pub fn radius_x(&self) -> f64 {
+        self.radius_x
+    }
+
+    pub fn radius_z(&self) -> f64 {
+        self.radius_z
+    }
+
+    pub fn local_intersect(&self, ray: Ray) -> Hits<f64> {
+        let ray = self.squeezed(ray);
         let a = ray.direction.x.powi(2) - ray.direction.y.powi(2) + ray.direction.z.powi(2);
         let b = 2. * ray.origin.x * ray.direction.x - 2. * ray.origin.y * ray.direction.y
             + 2. * ray.origin.z * ray.direction.z;
         let c = ray.origin.x.powi(2) - ray.origin.y.powi(2) + ray.origin.z.powi(2);
 
-        if a.abs() < EPSILON && b.abs() < EPSILON {
-            return vec![];
+        // Parallel to the side as a share of how long the direction is, which the transform
+        // stretches: large cones have short local directions that aren't parallel at all.
+        let length = ray.direction.magnitude();
+        let parallel = a.abs() < EPSILON * length * length;
+        if parallel && b.abs() < EPSILON * length {
+            return Hits::new();
         }
 
-        let mut xs = Vec::with_capacity(4);
+        let mut xs = Hits::new();
 
-        if a.abs() < EPSILON {
+        if parallel {
             xs.push(-c / (2. * b));
         }
 
         let disc = b.powi(2) - 4. * a * c;
 
         if disc < 0. {
-            return vec![];
+            return Hits::new();
         } else {
             let t0 = (-b - disc.sqrt()) / (2. * a);
             let t1 = (-b + disc.sqrt()) / (2. * a);
@@ -53,14 +133,15 @@ impl Cone {
                 xs.push(t1);
             }
 
-            xs.append(&mut self.intersect_caps(ray));
+            xs.extend(self.intersect_caps(ray));
         }
 
         xs
     }
 
-    pub fn local_normal_at(&self, local_point: Tuple) -> Tuple {
-        let dist = local_point.x.powi(2) + local_point.z.powi(2);
+    pub fn local_normal_at(&self, local_point: Point3) -> Vec3 {
+        let (x, z) = (local_point.x / self.radius_x, local_point.z / self.radius_z);
+        let dist = x.powi(2) + z.powi(2);
         let y_2 = local_point.y.powi(2);
         let y = if local_point.y > 0. {
             -dist.sqrt()
@@ -68,19 +149,40 @@ impl Cone {
             dist.sqrt()
         };
 
-        if dist < y_2 && local_point.y >= self.maximum - EPSILON {
-            Tuple::vector(0., 1., 0.)
-        } else if dist < y_2 && local_point.y <= self.minimum + EPSILON {
-            Tuple::vector(0., -1., 0.)
+        let epsilon = epsilon_at(local_point.y);
+
+        if dist < y_2 && local_point.y >= self.maximum - epsilon {
+            Vec3::new(0., 1., 0.)
+        } else if dist < y_2 && local_point.y <= self.minimum + epsilon {
+            Vec3::new(0., -1., 0.)
         } else {
-            Tuple::vector(local_point.x, y, local_point.z)
+            // Across the ellipse, squeezed by the radii once more.
+            Vec3::new(x / self.radius_x, y, z / self.radius_z)
+        }
+    }
+
+    /// The ray squeezed along x and z to where the cross-section is a unit circle, which
+    /// scales it without moving where along it it hits anything.
+    fn squeezed(&self, ray: Ray) -> Ray {
+        Ray {
+            origin: Point3::new(
+                ray.origin.x / self.radius_x,
+                ray.origin.y,
+                ray.origin.z / self.radius_z,
+            ),
+            direction: Vec3::new(
+                ray.direction.x / self.radius_x,
+                ray.direction.y,
+                ray.direction.z / self.radius_z,
+            ),
+            ..ray
         }
     }
 
-    fn intersect_caps(&self, ray: Ray) -> Vec<f64> {
-        let mut xs = Vec::with_capacity(2);
+    fn intersect_caps(&self, ray: Ray) -> Hits<f64> {
+        let mut xs = Hits::new();
 
-        if !self.closed || ray.direction.y.abs() < EPSILON {
+        if !self.closed || ray.direction.y.abs() < EPSILON * ray.direction.magnitude() {
             return xs;
         }
 
@@ -104,32 +206,71 @@ fn check_cap(ray: Ray, t: f64) -> bool {
     x.powi(2) + z.powi(2) <= y.powi(2)
 }
 
+impl Default for Cone {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PartialEq for Cone {
     fn eq(&self, other: &Self) -> bool {
         // TODO: Make sure this is fine: we don't really want == for f64s,
         // but I don't think we can use approx_equal because we have infinities involved
-        self.minimum == other.minimum && self.maximum == other.maximum
+        self.minimum == other.minimum
+            && self.maximum == other.maximum
+            && self.radius_x == other.radius_x
+            && self.radius_z == other.radius_z
+    }
+}
+
+impl Primitive for Cone {
+    fn local_intersect(&self, ray: Ray) -> Hits<TorUVT> {
+        Cone::local_intersect(self, ray).map(|t| TorUVT::JustT { t })
+    }
+
+    fn local_normal_at(&self, _: Intersection, point: Point3) -> Vec3 {
+        Cone::local_normal_at(self, point)
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        // As wide as it gets at whichever end is further from the apex.
+        let widest = f64::max(self.minimum.abs(), self.maximum.abs());
+        let (x, z) = (widest * self.radius_x, widest * self.radius_z);
+
+        BoundingBox::new(
+            Tuple::point(-x, self.minimum, -z),
+            Tuple::point(x, self.maximum, z),
+        )
+    }
+
+    /// Infinite ones have none.
+    fn tessellate(&self, resolution: usize) -> Mesh {
+        mesh::cone_mesh(*self, resolution)
+    }
+
+    fn same_as(&self, other: &dyn Primitive) -> bool {
+        other.downcast_ref() == Some(self)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{math::tuple::Tuple, misc::approx_equal, ray::Ray};
+    use crate::{misc::approx_equal, ray::Ray};
 
     #[test]
     fn intersecting_a_cone_with_a_ray() {
         let examples = vec![
-            (Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.), 5., 5.),
+            (Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.), 5., 5.),
             (
-                Tuple::point(0., 0., -5.),
-                Tuple::vector(1., 1., 1.),
+                Point3::new(0., 0., -5.),
+                Vec3::new(1., 1., 1.),
                 8.66025,
                 8.66025,
             ),
             (
-                Tuple::point(1., 1., -5.),
-                Tuple::vector(-0.5, -1., 1.),
+                Point3::new(1., 1., -5.),
+                Vec3::new(-0.5, -1., 1.),
                 4.55006,
                 49.44994,
             ),
@@ -156,9 +297,9 @@ mod tests {
         cone.closed = true;
 
         let examples = vec![
-            (Tuple::point(0., 0., -5.), Tuple::vector(0., 1., 0.), 0),
-            (Tuple::point(0., 0., -0.25), Tuple::vector(0., 1., 1.), 2),
-            (Tuple::point(0., 0., -0.25), Tuple::vector(0., 1., 0.), 4),
+            (Point3::new(0., 0., -5.), Vec3::new(0., 1., 0.), 0),
+            (Point3::new(0., 0., -0.25), Vec3::new(0., 1., 1.), 2),
+            (Point3::new(0., 0., -0.25), Vec3::new(0., 1., 0.), 4),
         ];
 
         for (origin, direction, count) in examples {
@@ -177,12 +318,9 @@ mod tests {
         // TODO: I believe these normals are incorrect as they're not normalized.
         // They, however, are how they appear in the book.
         let examples = vec![
-            (Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 0.)),
-            (
-                Tuple::point(1., 1., 1.),
-                Tuple::vector(1., -(2_f64.sqrt()), 1.),
-            ),
-            (Tuple::point(-1., -1., 0.), Tuple::vector(-1., 1., 0.)),
+            (Point3::new(0., 0., 0.), Vec3::new(0., 0., 0.)),
+            (Point3::new(1., 1., 1.), Vec3::new(1., -(2_f64.sqrt()), 1.)),
+            (Point3::new(-1., -1., 0.), Vec3::new(-1., 1., 0.)),
         ];
 
         for (point, normal) in examples {
@@ -191,4 +329,29 @@ mod tests {
             assert_eq!(n, normal);
         }
     }
+
+    #[test]
+    fn truncated_cones_need_their_minimum_below_their_maximum() {
+        assert!(Cone::truncated(-0.5, 0.5, true).is_ok());
+        assert!(Cone::truncated(0.5, -0.5, true).is_err());
+    }
+
+    #[test]
+    fn cones_can_be_elliptical() {
+        let cone = Cone::new().with_radii(2., 0.5).unwrap();
+
+        let along_x = Ray::new(Point3::new(-5., 1., 0.), Vec3::new(1., 0., 0.));
+        let along_z = Ray::new(Point3::new(0., 1., -5.), Vec3::new(0., 0., 1.));
+        assert_eq!(cone.local_intersect(along_x), [3., 7.]);
+        assert_eq!(cone.local_intersect(along_z), [4.5, 5.5]);
+
+        // The gradient of (x / 2)² + (z / 0.5)² - y² there.
+        let side = Point3::new(2_f64.sqrt(), 1., 0.5 / 2_f64.sqrt());
+        assert_eq!(
+            cone.local_normal_at(side).normalize(),
+            Vec3::new(2_f64.sqrt() / 4., -1., 2_f64.sqrt()).normalize()
+        );
+
+        assert!(Cone::new().with_radius(-1.).is_err());
+    }
 }
@@ -1,12 +1,31 @@
-use crate::{intersection::Intersection, ray::Ray};
+use crate::{intersection::Intersection, material::Material, ray::Ray};
 
-use super::{Object, SimpleObject};
+use super::{Object, Shape, ShapeOrGroup, SimpleObject};
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct Csg {
-    op: CsgOp,
+    pub(crate) op: CsgOp,
     pub(crate) left: Box<Object>,
     pub(crate) right: Box<Object>,
+    /// Material of the surfaces coming from the left operand.
+    pub left_material: CsgMaterial,
+    /// Material of the surfaces coming from the right operand,
+    /// which for a difference includes the newly exposed cut.
+    pub right_material: CsgMaterial,
+}
+
+/// Which material shades the surfaces coming from one of the operands of a `Csg`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum CsgMaterial {
+    /// The operand's own material.
+    #[default]
+    Own,
+    /// The material of the left operand.
+    Left,
+    /// The material of the right operand.
+    Right,
+    /// A material just for these surfaces, like the inside of a cut.
+    Custom(Box<Material>),
 }
 
 impl Csg {
@@ -15,6 +34,8 @@ impl Csg {
             op,
             left: Box::new(left),
             right: Box::new(right),
+            left_material: CsgMaterial::Own,
+            right_material: CsgMaterial::Own,
         }
     }
 
@@ -30,13 +51,13 @@ impl Csg {
         Self::new(CsgOp::Difference, left, right)
     }
 
-    pub(crate) fn local_intersect(&self, local_ray: Ray) -> Vec<Intersection> {
+    pub(crate) fn local_intersect(&self, local_ray: Ray) -> Vec<Intersection<'_>> {
         let left_intersections = self.left.intersect(local_ray);
         let right_intersections = self.right.intersect(local_ray);
 
         let mut xs = left_intersections
             .into_iter()
-            .chain(right_intersections.into_iter())
+            .chain(right_intersections)
             .collect::<Vec<_>>();
         xs.sort_by(|i1, i2| i1.t.partial_cmp(&i2.t).unwrap());
 
@@ -56,7 +77,7 @@ impl Csg {
             let left_hit = self.left.includes(i.object);
 
             if self.op.intersection_allowed(left_hit, inl, inr) {
-                result.push(i);
+                result.push(self.resolve_material(i, left_hit));
             }
 
             if left_hit {
@@ -72,6 +93,51 @@ impl Csg {
     pub(crate) fn includes(&self, object: SimpleObject) -> bool {
         self.left.includes(object) || self.right.includes(object)
     }
+
+    /// The materials of the surfaces coming from the left and the right operand,
+    /// the first ones found in operands with more than one.
+    pub(crate) fn surface_materials(&self) -> (Material, Material) {
+        let resolve = |choice: &CsgMaterial, own: &Object| match choice {
+            CsgMaterial::Own => operand_material(own),
+            CsgMaterial::Left => operand_material(&self.left),
+            CsgMaterial::Right => operand_material(&self.right),
+            CsgMaterial::Custom(material) => **material,
+        };
+
+        (
+            resolve(&self.left_material, &self.left),
+            resolve(&self.right_material, &self.right),
+        )
+    }
+
+    fn resolve_material<'a>(&self, mut i: Intersection<'a>, left_hit: bool) -> Intersection<'a> {
+        let choice = if left_hit {
+            &self.left_material
+        } else {
+            &self.right_material
+        };
+
+        match choice {
+            CsgMaterial::Own => {}
+            CsgMaterial::Left => i.object.material = operand_material(&self.left),
+            CsgMaterial::Right => i.object.material = operand_material(&self.right),
+            CsgMaterial::Custom(material) => i.object.material = **material,
+        }
+
+        i
+    }
+}
+
+/// The material of the first shape found in the operand.
+fn operand_material(object: &Object) -> Material {
+    match &object.shape {
+        ShapeOrGroup::Shape {
+            shape: Shape::Csg(csg),
+            ..
+        } => operand_material(&csg.left),
+        ShapeOrGroup::Shape { material, .. } => *material,
+        ShapeOrGroup::Group(group) => group.first().map(operand_material).unwrap_or_default(),
+    }
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -93,11 +159,8 @@ impl CsgOp {
 
 #[cfg(test)]
 mod tests {
-    use crate::{
-        math::{matrix4::Matrix4, tuple::Tuple},
-        misc::approx_equal,
-        shape::SimpleObject,
-    };
+    use crate::math::{point3::Point3, vec3::Vec3};
+    use crate::{color::Color, math::matrix4::Matrix4, misc::approx_equal, shape::SimpleObject};
 
     use super::*;
 
@@ -113,6 +176,7 @@ mod tests {
 
     #[rustfmt::skip]
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn evaluating_the_rules_for_csg_operations() {
         assert_eq!(CsgOp::Union.intersection_allowed(true, true, true),  false);
         assert_eq!(CsgOp::Union.intersection_allowed(true, true, false),  true);
@@ -172,7 +236,7 @@ mod tests {
     #[test]
     fn a_ray_misses_a_csg_object() {
         let c = Object::union(Object::sphere(), Object::cube());
-        let r = Ray::new(Tuple::point(0., 2., -5.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 2., -5.), Vec3::new(0., 0., 1.));
         let xs = c.intersect(r);
 
         assert!(xs.is_empty());
@@ -182,9 +246,9 @@ mod tests {
     fn a_ray_hits_a_csg_object() {
         let s1 = Object::sphere();
         let mut s2 = Object::sphere();
-        s2.transform = Matrix4::translation(0., 0., 0.5);
+        s2.set_transform(Matrix4::translation(0., 0., 0.5));
         let c = Object::union(s1.clone(), s2.clone());
-        let r = Ray::new(Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
         let xs = c.intersect(r);
 
         assert_eq!(xs.len(), 2);
@@ -194,4 +258,56 @@ mod tests {
         assert!(approx_equal(xs[1].t, 6.5));
         assert_eq!(xs[1].object, SimpleObject::from_object(&s2).unwrap());
     }
+
+    fn cut_sphere(right_material: CsgMaterial) -> Object {
+        let mut red = Material::new();
+        red.color = Color::red();
+        let mut sphere = Object::sphere();
+        sphere.set_material(red);
+        let mut blue = Material::new();
+        blue.color = Color::new(0., 0., 1.);
+        let mut cube = Object::cube();
+        cube.set_transform(Matrix4::translation(0., 0., -1.));
+        cube.set_material(blue);
+
+        let mut c = Object::difference(sphere, cube);
+        c.csg_mut().unwrap().right_material = right_material;
+
+        c
+    }
+
+    #[test]
+    fn the_cut_of_a_difference_uses_the_right_operand_material_by_default() {
+        let c = cut_sphere(CsgMaterial::Own);
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
+        let xs = c.intersect(r);
+
+        assert!(approx_equal(xs[0].t, 5.));
+        assert_eq!(xs[0].object.material.color, Color::new(0., 0., 1.));
+        assert_eq!(xs[1].object.material.color, Color::red());
+    }
+
+    #[test]
+    fn the_cut_of_a_difference_can_use_the_left_operand_material() {
+        let c = cut_sphere(CsgMaterial::Left);
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
+        let xs = c.intersect(r);
+
+        assert_eq!(xs[0].object.material.color, Color::red());
+        assert_eq!(xs[1].object.material.color, Color::red());
+    }
+
+    #[test]
+    fn the_cut_of_a_difference_can_use_its_own_material() {
+        let mut cut = Material::new();
+        cut.color = Color::green();
+        let c = cut_sphere(CsgMaterial::Custom(Box::new(cut)));
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
+        let xs = c.intersect(r);
+
+        assert_eq!(xs[0].object.material.color, Color::green());
+        assert_eq!(xs[1].object.material.color, Color::red());
+        // The shapes are still told apart, even with different materials.
+        assert_eq!(xs.len(), 2);
+    }
 }
@@ -1,11 +1,17 @@
-use std::f64::INFINITY;
-
-use crate::{math::tuple::Tuple, misc::EPSILON, ray::Ray};
-
+use crate::{
+    intersection::{Intersection, TorUVT},
+    math::{point3::Point3, tuple::Tuple, vec3::Vec3},
+    mesh::{self, Mesh},
+    ray::Ray,
+    sampler::Rng,
+    shape::{hits::Hits, primitive::Primitive, BoundingBox},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Cube;
 
 impl Cube {
-    pub fn local_intersect(local_ray: Ray) -> Vec<f64> {
+    pub fn local_intersect(local_ray: Ray) -> Hits<f64> {
         local_intersect(
             Tuple::point(-1., -1., -1.),
             Tuple::point(1., 1., 1.),
@@ -13,7 +19,7 @@ impl Cube {
         )
     }
 
-    pub fn local_normal_at(local_point: Tuple) -> Tuple {
+    pub fn local_normal_at(local_point: Point3) -> Vec3 {
         let max_abs = [local_point.x, local_point.y, local_point.z]
             .iter()
             .copied()
@@ -22,33 +28,46 @@ impl Cube {
             .unwrap();
 
         if max_abs == local_point.x.abs() {
-            Tuple::vector(local_point.x, 0., 0.)
+            Vec3::new(local_point.x, 0., 0.)
         } else if max_abs == local_point.y.abs() {
-            Tuple::vector(0., local_point.y, 0.)
+            Vec3::new(0., local_point.y, 0.)
         } else {
-            Tuple::vector(0., 0., local_point.z)
+            Vec3::new(0., 0., local_point.z)
         }
     }
+
+    pub const AREA: f64 = 24.;
+
+    /// A point picked uniformly on the cube, and the normal there.
+    pub(crate) fn sample_surface(rng: &mut Rng) -> (Point3, Vec3) {
+        // Every face has the same area.
+        let face = rng.below(6);
+        let side = if face.is_multiple_of(2) { 1. } else { -1. };
+        let (u, v) = (rng.range(-1., 1.), rng.range(-1., 1.));
+        let point = match face / 2 {
+            0 => Point3::new(side, u, v),
+            1 => Point3::new(u, side, v),
+            _ => Point3::new(u, v, side),
+        };
+
+        (point, Self::local_normal_at(point))
+    }
 }
 
-pub fn local_intersect(min: Tuple, max: Tuple, local_ray: Ray) -> Vec<f64> {
+pub fn local_intersect(min: Tuple, max: Tuple, local_ray: Ray) -> Hits<f64> {
     let (xt_min, xt_max) = check_axis(min.x, max.x, local_ray.origin.x, local_ray.direction.x);
     let (yt_min, yt_max) = check_axis(min.y, max.y, local_ray.origin.y, local_ray.direction.y);
     let (zt_min, zt_max) = check_axis(min.z, max.z, local_ray.origin.z, local_ray.direction.z);
 
-    let t_min = *[xt_min, yt_min, zt_min]
-        .iter()
-        .max_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap();
-    let t_max = *[xt_max, yt_max, zt_max]
-        .iter()
-        .min_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap();
+    // A ray lying on the plane of a face gives 0 / 0 = NaN for that axis,
+    // which `f64::max` and `f64::min` skip.
+    let t_min = xt_min.max(yt_min).max(zt_min);
+    let t_max = xt_max.min(yt_max).min(zt_max);
 
     if t_min > t_max {
-        vec![]
+        Hits::new()
     } else {
-        vec![t_min, t_max]
+        [t_min, t_max].into()
     }
 }
 
@@ -56,11 +75,9 @@ fn check_axis(min: f64, max: f64, origin: f64, direction: f64) -> (f64, f64) {
     let t_min_numerator = min - origin;
     let t_max_numerator = max - origin;
 
-    let (mut t_min, mut t_max) = if direction.abs() >= EPSILON {
-        (t_min_numerator / direction, t_max_numerator / direction)
-    } else {
-        (t_min_numerator * INFINITY, t_max_numerator * INFINITY)
-    };
+    // Dividing by a direction of 0 gives infinities, parallel rays never reaching that face.
+    let mut t_min = t_min_numerator / direction;
+    let mut t_max = t_max_numerator / direction;
 
     if t_min > t_max {
         std::mem::swap(&mut t_min, &mut t_max)
@@ -69,56 +86,52 @@ fn check_axis(min: f64, max: f64, origin: f64, direction: f64) -> (f64, f64) {
     (t_min, t_max)
 }
 
+impl Primitive for Cube {
+    fn local_intersect(&self, ray: Ray) -> Hits<TorUVT> {
+        Cube::local_intersect(ray).map(|t| TorUVT::JustT { t })
+    }
+
+    fn local_normal_at(&self, _: Intersection, point: Point3) -> Vec3 {
+        Cube::local_normal_at(point)
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::new(Tuple::point(-1., -1., -1.), Tuple::point(1., 1., 1.))
+    }
+
+    fn area(&self) -> Option<f64> {
+        Some(Cube::AREA)
+    }
+
+    fn sample_surface(&self, rng: &mut Rng) -> Option<(Point3, Vec3)> {
+        Some(Cube::sample_surface(rng))
+    }
+
+    fn tessellate(&self, resolution: usize) -> Mesh {
+        mesh::cube(resolution)
+    }
+
+    fn same_as(&self, other: &dyn Primitive) -> bool {
+        other.downcast_ref() == Some(self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::misc::approx_equal;
+    use crate::shape::tests::chi_square;
 
     #[test]
     fn a_ray_intersects_a_cube() {
         let examples = vec![
-            (
-                Tuple::point(5., 0.5, 0.),
-                Tuple::vector(-1., 0., 0.),
-                4.,
-                6.,
-            ),
-            (
-                Tuple::point(-5., 0.5, 0.),
-                Tuple::vector(1., 0., 0.),
-                4.,
-                6.,
-            ),
-            (
-                Tuple::point(0.5, 5., 0.),
-                Tuple::vector(0., -1., 0.),
-                4.,
-                6.,
-            ),
-            (
-                Tuple::point(0.5, -5., 0.),
-                Tuple::vector(0., 1., 0.),
-                4.,
-                6.,
-            ),
-            (
-                Tuple::point(0.5, 0., 5.),
-                Tuple::vector(0., 0., -1.),
-                4.,
-                6.,
-            ),
-            (
-                Tuple::point(0.5, 0., -5.),
-                Tuple::vector(0., 0., 1.),
-                4.,
-                6.,
-            ),
-            (
-                Tuple::point(0., 0.5, 0.),
-                Tuple::vector(0., 0., 1.),
-                -1.,
-                1.,
-            ),
+            (Point3::new(5., 0.5, 0.), Vec3::new(-1., 0., 0.), 4., 6.),
+            (Point3::new(-5., 0.5, 0.), Vec3::new(1., 0., 0.), 4., 6.),
+            (Point3::new(0.5, 5., 0.), Vec3::new(0., -1., 0.), 4., 6.),
+            (Point3::new(0.5, -5., 0.), Vec3::new(0., 1., 0.), 4., 6.),
+            (Point3::new(0.5, 0., 5.), Vec3::new(0., 0., -1.), 4., 6.),
+            (Point3::new(0.5, 0., -5.), Vec3::new(0., 0., 1.), 4., 6.),
+            (Point3::new(0., 0.5, 0.), Vec3::new(0., 0., 1.), -1., 1.),
         ];
 
         for (origin, direction, t1, t2) in examples {
@@ -131,24 +144,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn a_ray_intersects_a_cube_scaled_way_up() {
+        // What a ray across a cube a billion units wide turns into in its local space.
+        let r = Ray::new(Point3::new(0.5, 0.5, -5.), Vec3::new(0., 1e-10, 1e-9));
+        let xs = Cube::local_intersect(r);
+
+        assert_eq!(xs.len(), 2);
+        assert!(approx_equal(xs[0] * 1e-9, 4.));
+        assert!(approx_equal(xs[1] * 1e-9, 5.));
+    }
+
     #[test]
     fn a_ray_misses_a_cube() {
         let examples = vec![
-            (
-                Tuple::point(-2., 0., 0.),
-                Tuple::vector(0.2673, 0.5345, 0.8018),
-            ),
-            (
-                Tuple::point(0., -2., 0.),
-                Tuple::vector(0.8018, 0.2673, 0.5345),
-            ),
-            (
-                Tuple::point(0., 0., -2.),
-                Tuple::vector(0.5345, 0.8018, 0.2673),
-            ),
-            (Tuple::point(2., 0., 2.), Tuple::vector(0., 0., -1.)),
-            (Tuple::point(0., 2., 2.), Tuple::vector(0., -1., 0.)),
-            (Tuple::point(2., 2., 0.), Tuple::vector(-1., 0., 0.)),
+            (Point3::new(-2., 0., 0.), Vec3::new(0.2673, 0.5345, 0.8018)),
+            (Point3::new(0., -2., 0.), Vec3::new(0.8018, 0.2673, 0.5345)),
+            (Point3::new(0., 0., -2.), Vec3::new(0.5345, 0.8018, 0.2673)),
+            (Point3::new(2., 0., 2.), Vec3::new(0., 0., -1.)),
+            (Point3::new(0., 2., 2.), Vec3::new(0., -1., 0.)),
+            (Point3::new(2., 2., 0.), Vec3::new(-1., 0., 0.)),
         ];
         for (origin, direction) in examples {
             let ray = Ray::new(origin, direction);
@@ -158,17 +173,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn a_ray_lying_on_the_plane_of_a_face() {
+        let ray = Ray::new(Point3::new(0., 1., -5.), Vec3::new(0., 0., 1.));
+        let xs = Cube::local_intersect(ray);
+
+        assert_eq!(xs, vec![4., 6.]);
+    }
+
     #[test]
     fn the_normal_on_the_surface_of_a_cube() {
         let examples = vec![
-            (Tuple::point(1., 0.5, -0.8), Tuple::vector(1., 0., 0.)),
-            (Tuple::point(-1., -0.2, 0.9), Tuple::vector(-1., 0., 0.)),
-            (Tuple::point(-0.4, 1., -0.1), Tuple::vector(0., 1., 0.)),
-            (Tuple::point(0.3, -1., -0.7), Tuple::vector(0., -1., 0.)),
-            (Tuple::point(-0.6, 0.3, 1.), Tuple::vector(0., 0., 1.)),
-            (Tuple::point(0.4, 0.4, -1.), Tuple::vector(0., 0., -1.)),
-            (Tuple::point(1., 1., 1.), Tuple::vector(1., 0., 0.)),
-            (Tuple::point(-1., -1., -1.), Tuple::vector(-1., 0., 0.)),
+            (Point3::new(1., 0.5, -0.8), Vec3::new(1., 0., 0.)),
+            (Point3::new(-1., -0.2, 0.9), Vec3::new(-1., 0., 0.)),
+            (Point3::new(-0.4, 1., -0.1), Vec3::new(0., 1., 0.)),
+            (Point3::new(0.3, -1., -0.7), Vec3::new(0., -1., 0.)),
+            (Point3::new(-0.6, 0.3, 1.), Vec3::new(0., 0., 1.)),
+            (Point3::new(0.4, 0.4, -1.), Vec3::new(0., 0., -1.)),
+            (Point3::new(1., 1., 1.), Vec3::new(1., 0., 0.)),
+            (Point3::new(-1., -1., -1.), Vec3::new(-1., 0., 0.)),
         ];
         for (point, expected_normal) in examples {
             let normal = Cube::local_normal_at(point);
@@ -176,4 +199,32 @@ mod tests {
             assert_eq!(normal, expected_normal);
         }
     }
+
+    #[test]
+    fn points_are_sampled_uniformly_on_a_cube() {
+        let mut rng = Rng::new(6);
+        // Quarters of every face.
+        let mut counts = [0; 24];
+
+        for _ in 0..24_000 {
+            let (point, normal) = Cube::sample_surface(&mut rng);
+            let coordinates = [point.x, point.y, point.z];
+            let axis = coordinates
+                .iter()
+                .position(|coordinate| coordinate.abs() == 1.)
+                .unwrap();
+
+            assert_eq!(normal, Cube::local_normal_at(point));
+            let (u, v) = match axis {
+                0 => (point.y, point.z),
+                1 => (point.x, point.z),
+                _ => (point.x, point.y),
+            };
+            let face = axis * 2 + usize::from(coordinates[axis] > 0.);
+            counts[face * 4 + usize::from(u > 0.) * 2 + usize::from(v > 0.)] += 1;
+        }
+
+        // The critical value for 23 degrees of freedom at p = 0.001.
+        assert!(chi_square(&counts, &[1. / 24.; 24]) < 49.73);
+    }
 }
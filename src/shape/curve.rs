@@ -0,0 +1,440 @@
+use crate::{
+    math::{point3::Point3, tuple::Tuple, vec3::Vec3},
+    misc::EPSILON,
+    ray::Ray,
+};
+
+use super::{hits::Hits, primitive::Primitive, triangle::UVT, BoundingBox, BOUNDING_BOX_PADDING};
+use crate::intersection::{Intersection, TorUVT};
+
+/// How many times a curve can be split in half while looking for flat pieces.
+const MAX_DEPTH: usize = 8;
+/// A piece is considered flat if its control points deviate from its chord
+/// less than this fraction of the radius.
+const FLATNESS: f64 = 0.1;
+
+/// A cubic Bézier curve swept into a tube of constant radius,
+/// useful for wires, grass and hair strands.
+///
+/// Rays are intersected by adaptively splitting the curve until the pieces are flat,
+/// and intersecting each flat piece as a capsule around its chord.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Curve {
+    control_points: [Tuple; 4],
+    pub radius: f64,
+}
+
+impl Curve {
+    pub fn bezier(p0: Tuple, p1: Tuple, p2: Tuple, p3: Tuple, radius: f64) -> Self {
+        Self {
+            control_points: [p0, p1, p2, p3],
+            radius,
+        }
+    }
+
+    /// A uniform cubic B-spline segment, converted to its Bézier form.
+    /// Unlike Bézier curves, it doesn't pass through its first and last control points.
+    pub fn b_spline(p0: Tuple, p1: Tuple, p2: Tuple, p3: Tuple, radius: f64) -> Self {
+        let sixth = 1. / 6.;
+
+        Self::bezier(
+            (p0 + p1 * 4. + p2) * sixth,
+            (p1 * 4. + p2 * 2.) * sixth,
+            (p1 * 2. + p2 * 4.) * sixth,
+            (p1 + p2 * 4. + p3) * sixth,
+            radius,
+        )
+    }
+
+    pub fn control_points(&self) -> [Tuple; 4] {
+        self.control_points
+    }
+
+    /// The point along the center of the curve at parameter `u` (between 0 and 1).
+    pub fn point_at(&self, u: f64) -> Tuple {
+        let [p0, p1, p2, p3] = self.control_points;
+        let v = 1. - u;
+
+        p0 * v.powi(3) + p1 * (3. * v.powi(2) * u) + p2 * (3. * v * u.powi(2)) + p3 * u.powi(3)
+    }
+
+    pub(crate) fn bounding_box(&self) -> BoundingBox {
+        Segment::whole(self).bounding_box(self.radius)
+    }
+
+    pub(crate) fn local_intersect(&self, local_ray: Ray) -> Hits<UVT> {
+        let mut intervals = vec![];
+        let mut stack = vec![(Segment::whole(self), 0)];
+
+        while let Some((segment, depth)) = stack.pop() {
+            if !segment
+                .bounding_box(self.radius)
+                .padded(BOUNDING_BOX_PADDING)
+                .intersect(local_ray)
+            {
+                continue;
+            }
+
+            if depth == MAX_DEPTH || segment.is_flat(self.radius * FLATNESS) {
+                intervals.extend(segment.intersect_capsule(local_ray, self.radius));
+            } else {
+                let (left, right) = segment.split();
+
+                stack.push((left, depth + 1));
+                stack.push((right, depth + 1));
+            }
+        }
+
+        // The capsules of consecutive pieces overlap at their joints,
+        // only report the points where the ray enters or leaves the whole tube.
+        intervals.sort_by(|(enter1, _), (enter2, _)| enter1.t.partial_cmp(&enter2.t).unwrap());
+        let mut merged: Vec<(UVT, UVT)> = vec![];
+
+        for (enter, exit) in intervals {
+            match merged.last_mut() {
+                Some((_, last_exit)) if enter.t <= last_exit.t => {
+                    if exit.t > last_exit.t {
+                        *last_exit = exit;
+                    }
+                }
+                _ => merged.push((enter, exit)),
+            }
+        }
+
+        merged
+            .into_iter()
+            .flat_map(|(enter, exit)| [enter, exit])
+            .collect()
+    }
+
+    /// The normal points away from the center of the curve, where `u` says it was hit.
+    pub(crate) fn local_normal_at(&self, uvt: &UVT, local_point: Point3) -> Vec3 {
+        let center = self.point_at(uvt.u);
+
+        local_point - Point3::new(center.x, center.y, center.z)
+    }
+}
+
+/// A piece of a curve, covering the parameters from `u0` to `u1`.
+#[derive(Clone, Copy)]
+struct Segment {
+    points: [Tuple; 4],
+    u0: f64,
+    u1: f64,
+}
+
+impl Segment {
+    fn whole(curve: &Curve) -> Self {
+        Self {
+            points: curve.control_points,
+            u0: 0.,
+            u1: 1.,
+        }
+    }
+
+    fn bounding_box(&self, radius: f64) -> BoundingBox {
+        let padding = Tuple::vector(radius, radius, radius);
+        let BoundingBox { min, max } = BoundingBox::from_points(&self.points);
+
+        BoundingBox {
+            min: min - padding,
+            max: max + padding,
+        }
+    }
+
+    /// De Casteljau subdivision at the middle of the segment.
+    fn split(&self) -> (Self, Self) {
+        let [p0, p1, p2, p3] = self.points;
+        let middle = |a: Tuple, b: Tuple| (a + b) * 0.5;
+
+        let p01 = middle(p0, p1);
+        let p12 = middle(p1, p2);
+        let p23 = middle(p2, p3);
+        let p012 = middle(p01, p12);
+        let p123 = middle(p12, p23);
+        let p0123 = middle(p012, p123);
+        let u_middle = (self.u0 + self.u1) / 2.;
+
+        (
+            Self {
+                points: [p0, p01, p012, p0123],
+                u0: self.u0,
+                u1: u_middle,
+            },
+            Self {
+                points: [p0123, p123, p23, p3],
+                u0: u_middle,
+                u1: self.u1,
+            },
+        )
+    }
+
+    fn is_flat(&self, tolerance: f64) -> bool {
+        let [p0, p1, p2, p3] = self.points;
+
+        distance_to_segment(p1, p0, p3) < tolerance && distance_to_segment(p2, p0, p3) < tolerance
+    }
+
+    /// Intersects the capsule around the chord of the segment,
+    /// returning the points where the ray enters and leaves it.
+    fn intersect_capsule(&self, ray: Ray, radius: f64) -> Option<(UVT, UVT)> {
+        let start = self.points[0];
+        let end = self.points[3];
+
+        let intervals = [
+            intersect_cylinder(ray, start, end, radius),
+            intersect_sphere(ray, start, radius),
+            intersect_sphere(ray, end, radius),
+        ];
+
+        // The capsule is convex, so the union of its pieces is a single interval.
+        let (t_enter, t_exit) = intervals
+            .into_iter()
+            .flatten()
+            .reduce(|(enter1, exit1), (enter2, exit2)| (enter1.min(enter2), exit1.max(exit2)))?;
+
+        let uvt_at = |t: f64| {
+            let s = projection_onto_segment(ray.position(t).into(), start, end);
+
+            UVT {
+                t,
+                u: self.u0 + s * (self.u1 - self.u0),
+                v: 0.,
+            }
+        };
+
+        Some((uvt_at(t_enter), uvt_at(t_exit)))
+    }
+}
+
+/// Where `point` projects onto the segment from `start` to `end`, from 0 to 1.
+fn projection_onto_segment(point: Tuple, start: Tuple, end: Tuple) -> f64 {
+    let axis = end - start;
+    let length_squared = axis.magnitude_squared();
+
+    if length_squared < EPSILON {
+        0.
+    } else {
+        ((point - start).dot(axis) / length_squared).clamp(0., 1.)
+    }
+}
+
+fn distance_to_segment(point: Tuple, start: Tuple, end: Tuple) -> f64 {
+    let s = projection_onto_segment(point, start, end);
+    let closest = start + (end - start) * s;
+
+    (point - closest).magnitude()
+}
+
+fn intersect_sphere(ray: Ray, center: Tuple, radius: f64) -> Option<(f64, f64)> {
+    let center_to_ray = Tuple::from(ray.origin) - center;
+    let a = ray.direction.magnitude_squared();
+    let b = 2. * Tuple::from(ray.direction).dot(center_to_ray);
+    let c = center_to_ray.magnitude_squared() - radius.powi(2);
+
+    let discriminant = b.powi(2) - 4. * a * c;
+
+    if discriminant < 0. {
+        None
+    } else {
+        let t1 = (-b - discriminant.sqrt()) / (2. * a);
+        let t2 = (-b + discriminant.sqrt()) / (2. * a);
+
+        Some((t1, t2))
+    }
+}
+
+/// Intersects the open cylinder going from `start` to `end`.
+fn intersect_cylinder(ray: Ray, start: Tuple, end: Tuple, radius: f64) -> Option<(f64, f64)> {
+    let axis = end - start;
+    let length_squared = axis.magnitude_squared();
+
+    if length_squared < EPSILON {
+        return None;
+    }
+
+    // Remove the components along the axis, what's left is a circle in 2D.
+    let ray_direction = Tuple::from(ray.direction);
+    let start_to_origin = Tuple::from(ray.origin) - start;
+    let direction = ray_direction - axis * (ray_direction.dot(axis) / length_squared);
+    let origin = start_to_origin - axis * (start_to_origin.dot(axis) / length_squared);
+
+    let a = direction.magnitude_squared();
+    if a < EPSILON {
+        // Parallel to the axis, the spheres at the ends take care of it.
+        return None;
+    }
+    let b = 2. * origin.dot(direction);
+    let c = origin.magnitude_squared() - radius.powi(2);
+
+    let discriminant = b.powi(2) - 4. * a * c;
+    if discriminant < 0. {
+        return None;
+    }
+
+    let t1 = (-b - discriminant.sqrt()) / (2. * a);
+    let t2 = (-b + discriminant.sqrt()) / (2. * a);
+
+    // Clip against the slab between the two ends of the cylinder.
+    let axial_origin = start_to_origin.dot(axis) / length_squared;
+    let axial_direction = ray_direction.dot(axis) / length_squared;
+    let (slab1, slab2) = if axial_direction.abs() < EPSILON {
+        if (0. ..=1.).contains(&axial_origin) {
+            (f64::NEG_INFINITY, f64::INFINITY)
+        } else {
+            return None;
+        }
+    } else {
+        let s1 = -axial_origin / axial_direction;
+        let s2 = (1. - axial_origin) / axial_direction;
+
+        (s1.min(s2), s1.max(s2))
+    };
+
+    let t_enter = t1.max(slab1);
+    let t_exit = t2.min(slab2);
+
+    if t_enter > t_exit {
+        None
+    } else {
+        Some((t_enter, t_exit))
+    }
+}
+
+impl Primitive for Curve {
+    fn local_intersect(&self, ray: Ray) -> Hits<TorUVT> {
+        Curve::local_intersect(self, ray).map(|uvt| TorUVT::UVT { uvt })
+    }
+
+    fn local_normal_at(&self, intersection: Intersection, point: Point3) -> Vec3 {
+        let uvt = intersection.uvt().unwrap();
+
+        Curve::local_normal_at(self, &uvt, point)
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        Curve::bounding_box(self)
+    }
+
+    fn same_as(&self, other: &dyn Primitive) -> bool {
+        other.downcast_ref() == Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        intersection::{Intersection, TorUVT},
+        misc::approx_equal,
+        shape::{Object, SimpleObject},
+    };
+
+    fn straight_curve() -> Curve {
+        Curve::bezier(
+            Tuple::point(-2., 0., 0.),
+            Tuple::point(-1., 0., 0.),
+            Tuple::point(1., 0., 0.),
+            Tuple::point(2., 0., 0.),
+            0.5,
+        )
+    }
+
+    fn arch() -> Curve {
+        Curve::bezier(
+            Tuple::point(-2., 0., 0.),
+            Tuple::point(-2., 4., 0.),
+            Tuple::point(2., 4., 0.),
+            Tuple::point(2., 0., 0.),
+            0.25,
+        )
+    }
+
+    #[test]
+    fn a_bezier_curve_goes_through_its_end_points() {
+        let c = arch();
+
+        assert_eq!(c.point_at(0.), Tuple::point(-2., 0., 0.));
+        assert_eq!(c.point_at(1.), Tuple::point(2., 0., 0.));
+        assert_eq!(c.point_at(0.5), Tuple::point(0., 3., 0.));
+    }
+
+    #[test]
+    fn a_b_spline_starts_at_the_weighted_average_of_its_first_points() {
+        let c = Curve::b_spline(
+            Tuple::point(0., 0., 0.),
+            Tuple::point(6., 0., 0.),
+            Tuple::point(12., 0., 0.),
+            Tuple::point(18., 0., 0.),
+            1.,
+        );
+
+        assert_eq!(c.point_at(0.), Tuple::point(6., 0., 0.));
+        assert_eq!(c.point_at(1.), Tuple::point(12., 0., 0.));
+    }
+
+    #[test]
+    fn a_ray_strikes_a_straight_curve_like_a_cylinder() {
+        let c = straight_curve();
+        let r = Ray::new(Point3::new(0.5, 0., -5.), Vec3::new(0., 0., 1.));
+        let xs = c.local_intersect(r);
+
+        assert_eq!(xs.len(), 2);
+        assert!(approx_equal(xs[0].t, 4.5));
+        assert!(approx_equal(xs[1].t, 5.5));
+        assert!(approx_equal(xs[0].u, 0.625));
+    }
+
+    #[test]
+    fn a_ray_along_the_curve_enters_and_leaves_once() {
+        let c = arch();
+        let r = Ray::new(Point3::new(-5., 3., 0.), Vec3::new(1., 0., 0.));
+        let xs = c.local_intersect(r);
+
+        assert_eq!(xs.len(), 2);
+        assert!(xs[0].t < 5. && xs[1].t > 5.);
+
+        let r = Ray::new(Point3::new(-2., -5., 0.), Vec3::new(0., 1., 0.));
+        let xs = c.local_intersect(r);
+
+        assert_eq!(xs.len(), 2);
+        assert!(approx_equal(xs[0].t, 4.75));
+    }
+
+    #[test]
+    fn a_ray_misses_a_curve() {
+        let c = arch();
+        let rays = [
+            Ray::new(Point3::new(0., 1., -5.), Vec3::new(0., 0., 1.)),
+            Ray::new(Point3::new(0., 5., -5.), Vec3::new(0., 0., 1.)),
+            Ray::new(Point3::new(-5., 0.5, 0.), Vec3::new(0., 0., 1.)),
+        ];
+
+        for r in rays {
+            assert!(c.local_intersect(r).is_empty());
+        }
+    }
+
+    #[test]
+    fn the_normal_on_a_curve_points_away_from_its_center() {
+        let c = arch();
+        let object = Object::primitive(c);
+        let s = SimpleObject::from_object(&object).unwrap();
+        let r = Ray::new(Point3::new(0., 5., 0.), Vec3::new(0., -1., 0.));
+        let xs = c.local_intersect(r);
+        let i = Intersection::new(&TorUVT::UVT { uvt: xs[0] }, s);
+
+        let n = s.normal_at(i, r.position(xs[0].t));
+
+        assert_eq!(n, Vec3::new(0., 1., 0.));
+    }
+
+    #[test]
+    fn a_curve_has_a_bounding_box_padded_by_its_radius() {
+        let c = arch();
+        let bounds = c.bounding_box();
+
+        assert_eq!(bounds.min, Tuple::point(-2.25, -0.25, -0.25));
+        assert_eq!(bounds.max, Tuple::point(2.25, 4.25, 0.25));
+    }
+}
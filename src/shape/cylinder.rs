@@ -1,27 +1,114 @@
-use std::f64::{INFINITY, NEG_INFINITY};
-
-use crate::{math::tuple::Tuple, misc::EPSILON, ray::Ray};
+use crate::{
+    error::{RayTracerError, Result},
+    intersection::{Intersection, TorUVT},
+    math::{point3::Point3, tuple::Tuple, vec3::Vec3},
+    mesh::{self, Mesh},
+    misc::{epsilon_at, EPSILON},
+    ray::Ray,
+    shape::{hits::Hits, primitive::Primitive, BoundingBox},
+};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Cylinder {
-    pub minimum: f64,
-    pub maximum: f64,
-    pub closed: bool,
+    minimum: f64,
+    maximum: f64,
+    closed: bool,
+    /// How far the surface is from the axis along x and z, the same all the way up, the
+    /// cross-section being an ellipse unless they're the same.
+    radius_x: f64,
+    radius_z: f64,
 }
 
 impl Cylinder {
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
         Self {
-            minimum: NEG_INFINITY,
-            maximum: INFINITY,
+            minimum: f64::NEG_INFINITY,
+            maximum: f64::INFINITY,
             closed: false,
+            radius_x: 1.,
+            radius_z: 1.,
+        }
+    }
+
+    /// Cut off below `minimum` and above `maximum`, with caps on the ends if `closed`.
+    /// Bounds can be infinite, but `minimum` has to be below `maximum`.
+    pub fn truncated(minimum: f64, maximum: f64, closed: bool) -> Result<Self> {
+        if minimum.is_nan() || maximum.is_nan() || minimum >= maximum {
+            return Err(RayTracerError::InvalidShape(format!(
+                "the minimum of a cylinder must be below its maximum, got {} and {}",
+                minimum, maximum
+            )));
+        }
+
+        Ok(Self {
+            minimum,
+            maximum,
+            closed,
+            ..Self::new()
+        })
+    }
+
+    /// As wide as `radius` all around instead of 1, see `with_radii`.
+    pub fn with_radius(self, radius: f64) -> Result<Self> {
+        self.with_radii(radius, radius)
+    }
+
+    /// An elliptical cylinder, its side `radius_x` out from the axis along x and `radius_z`
+    /// along z at every height, with normals worked out across the ellipse itself rather than
+    /// left to the inverse transpose of a scaling transform. Radii have to be positive.
+    pub fn with_radii(self, radius_x: f64, radius_z: f64) -> Result<Self> {
+        let valid = |radius: f64| radius > 0. && radius.is_finite();
+        if !valid(radius_x) || !valid(radius_z) {
+            return Err(RayTracerError::InvalidShape(format!(
+                "the radii of a cylinder must be positive, got {} and {}",
+                radius_x, radius_z
+            )));
+        }
+
+        Ok(Self {
+            radius_x,
+            radius_z,
+            ..self
+        })
+    }
+
+    /// From 0 to 1 and closed, the cylinder most scenes start from.
+    pub const fn unit_capped() -> Self {
+        Self {
+            minimum: 0.,
+            maximum: 1.,
+            closed: true,
+            ..Self::new()
         }
     }
 
-    pub fn local_intersect(&self, ray: Ray) -> Vec<f64> {
+    pub fn minimum(&self) -> f64 {
+        self.minimum
+    }
+
+    pub fn maximum(&self) -> f64 {
+        self.maximum
+    }
+
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    pub fn radius_x(&self) -> f64 {
+        self.radius_x
+    }
+
+    pub fn radius_z(&self) -> f64 {
+        self.radius_z
+    }
+
+    pub fn local_intersect(&self, ray: Ray) -> Hits<f64> {
+        let ray = self.squeezed(ray);
         let a = ray.direction.x.powi(2) + ray.direction.z.powi(2);
 
-        if a.abs() < EPSILON {
+        // Parallel to the axis as a share of how long the direction is, which the transform
+        // stretches: large cylinders have short local directions that aren't parallel at all.
+        if a < EPSILON * ray.direction.magnitude_squared() {
             return self.intersect_caps(ray);
         }
 
@@ -31,14 +118,14 @@ impl Cylinder {
         let disc = b.powi(2) - 4. * a * c;
 
         if disc < 0. {
-            vec![]
+            Hits::new()
         } else {
             let t0 = (-b - disc.sqrt()) / (2. * a);
             let t1 = (-b + disc.sqrt()) / (2. * a);
 
             let y0 = ray.origin.y + t0 * ray.direction.y;
             let y1 = ray.origin.y + t1 * ray.direction.y;
-            let mut xs = Vec::with_capacity(2);
+            let mut xs = Hits::new();
 
             if self.minimum < y0 && y0 < self.maximum {
                 xs.push(t0);
@@ -48,27 +135,48 @@ impl Cylinder {
                 xs.push(t1);
             }
 
-            xs.append(&mut self.intersect_caps(ray));
+            xs.extend(self.intersect_caps(ray));
             xs
         }
     }
 
-    pub fn local_normal_at(&self, local_point: Tuple) -> Tuple {
-        let dist = local_point.x.powi(2) + local_point.z.powi(2);
+    pub fn local_normal_at(&self, local_point: Point3) -> Vec3 {
+        let (x, z) = (local_point.x / self.radius_x, local_point.z / self.radius_z);
+        let dist = x.powi(2) + z.powi(2);
+        let epsilon = epsilon_at(local_point.y);
 
-        if dist < 1. && local_point.y >= self.maximum - EPSILON {
-            Tuple::vector(0., 1., 0.)
-        } else if dist < 1. && local_point.y <= self.minimum + EPSILON {
-            Tuple::vector(0., -1., 0.)
+        if dist < 1. && local_point.y >= self.maximum - epsilon {
+            Vec3::new(0., 1., 0.)
+        } else if dist < 1. && local_point.y <= self.minimum + epsilon {
+            Vec3::new(0., -1., 0.)
         } else {
-            Tuple::vector(local_point.x, 0., local_point.z)
+            // Across the ellipse, squeezed by the radii once more.
+            Vec3::new(x / self.radius_x, 0., z / self.radius_z)
+        }
+    }
+
+    /// The ray squeezed along x and z to where the cross-section is a unit circle, which
+    /// scales it without moving where along it it hits anything.
+    fn squeezed(&self, ray: Ray) -> Ray {
+        Ray {
+            origin: Point3::new(
+                ray.origin.x / self.radius_x,
+                ray.origin.y,
+                ray.origin.z / self.radius_z,
+            ),
+            direction: Vec3::new(
+                ray.direction.x / self.radius_x,
+                ray.direction.y,
+                ray.direction.z / self.radius_z,
+            ),
+            ..ray
         }
     }
 
-    fn intersect_caps(&self, ray: Ray) -> Vec<f64> {
-        let mut xs = Vec::with_capacity(2);
+    fn intersect_caps(&self, ray: Ray) -> Hits<f64> {
+        let mut xs = Hits::new();
 
-        if !self.closed || ray.direction.y.abs() < EPSILON {
+        if !self.closed || ray.direction.y.abs() < EPSILON * ray.direction.magnitude() {
             return xs;
         }
 
@@ -92,25 +200,63 @@ fn check_cap(ray: Ray, t: f64) -> bool {
     x.powi(2) + z.powi(2) <= r
 }
 
+impl Default for Cylinder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PartialEq for Cylinder {
     fn eq(&self, other: &Self) -> bool {
         // TODO: Make sure this is fine: we don't really want == for f64s,
         // but I don't think we can use approx_equal because we have infinities involved
-        self.minimum == other.minimum && self.maximum == other.maximum
+        self.minimum == other.minimum
+            && self.maximum == other.maximum
+            && self.radius_x == other.radius_x
+            && self.radius_z == other.radius_z
+    }
+}
+
+impl Primitive for Cylinder {
+    fn local_intersect(&self, ray: Ray) -> Hits<TorUVT> {
+        Cylinder::local_intersect(self, ray).map(|t| TorUVT::JustT { t })
+    }
+
+    fn local_normal_at(&self, _: Intersection, point: Point3) -> Vec3 {
+        Cylinder::local_normal_at(self, point)
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let (x, z) = (self.radius_x, self.radius_z);
+
+        BoundingBox::new(
+            Tuple::point(-x, self.minimum, -z),
+            Tuple::point(x, self.maximum, z),
+        )
+    }
+
+    /// Infinite ones have none.
+    fn tessellate(&self, resolution: usize) -> Mesh {
+        mesh::cylinder_mesh(*self, resolution)
+    }
+
+    fn same_as(&self, other: &dyn Primitive) -> bool {
+        other.downcast_ref() == Some(self)
     }
 }
 
 #[cfg(test)]
 mod tests {
+
     use super::*;
     use crate::misc::approx_equal;
 
     #[test]
     fn a_ray_misses_a_cylinder() {
         let examples = vec![
-            (Tuple::point(1., 0., 0.), Tuple::vector(0., 1., 0.)),
-            (Tuple::point(0., 0., 0.), Tuple::vector(0., 1., 0.)),
-            (Tuple::point(0., 0., -5.), Tuple::vector(1., 1., 1.)),
+            (Point3::new(1., 0., 0.), Vec3::new(0., 1., 0.)),
+            (Point3::new(0., 0., 0.), Vec3::new(0., 1., 0.)),
+            (Point3::new(0., 0., -5.), Vec3::new(1., 1., 1.)),
         ];
         let cyl = Cylinder::new();
 
@@ -126,11 +272,11 @@ mod tests {
     #[test]
     fn a_ray_strikes_a_cylinder() {
         let examples = vec![
-            (Tuple::point(1., 0., -5.), Tuple::vector(0., 0., 1.), 5., 5.),
-            (Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.), 4., 6.),
+            (Point3::new(1., 0., -5.), Vec3::new(0., 0., 1.), 5., 5.),
+            (Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.), 4., 6.),
             (
-                Tuple::point(0.5, 0., -5.),
-                Tuple::vector(0.1, 1., 1.),
+                Point3::new(0.5, 0., -5.),
+                Vec3::new(0.1, 1., 1.),
                 6.80798,
                 7.08872,
             ),
@@ -148,13 +294,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn a_ray_strikes_a_cylinder_scaled_way_up() {
+        // What a ray across a cylinder a million units wide turns into in its local space.
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1e-6));
+        let xs = Cylinder::new().local_intersect(r);
+
+        assert_eq!(xs.len(), 2);
+        assert!(approx_equal(xs[0] * 1e-6, 4.));
+        assert!(approx_equal(xs[1] * 1e-6, 6.));
+    }
+
     #[test]
     fn the_normal_on_the_surface_of_a_cube() {
         let examples = vec![
-            (Tuple::point(1., 0., 0.), Tuple::vector(1., 0., 0.)),
-            (Tuple::point(0., 5., -1.), Tuple::vector(0., 0., -1.)),
-            (Tuple::point(0., -2., 1.), Tuple::vector(0., 0., 1.)),
-            (Tuple::point(-1., 1., 0.), Tuple::vector(-1., 0., 0.)),
+            (Point3::new(1., 0., 0.), Vec3::new(1., 0., 0.)),
+            (Point3::new(0., 5., -1.), Vec3::new(0., 0., -1.)),
+            (Point3::new(0., -2., 1.), Vec3::new(0., 0., 1.)),
+            (Point3::new(-1., 1., 0.), Vec3::new(-1., 0., 0.)),
         ];
 
         let cyl = Cylinder::new();
@@ -169,19 +326,19 @@ mod tests {
     fn the_default_minimum_and_maximum_for_a_cylinder() {
         let cyl = Cylinder::new();
 
-        assert_eq!(cyl.minimum, NEG_INFINITY);
-        assert_eq!(cyl.maximum, INFINITY);
+        assert_eq!(cyl.minimum, f64::NEG_INFINITY);
+        assert_eq!(cyl.maximum, f64::INFINITY);
     }
 
     #[test]
     fn intersecting_a_constrained_cylinder() {
         let examples = vec![
-            (Tuple::point(0., 1.5, 0.), Tuple::vector(0.1, 1., 0.), 0),
-            (Tuple::point(0., 3., -5.), Tuple::vector(0., 0., 1.), 0),
-            (Tuple::point(0., 0., -5.), Tuple::vector(0., 0., 1.), 0),
-            (Tuple::point(0., 2., -5.), Tuple::vector(0., 0., 1.), 0),
-            (Tuple::point(0., 1., -5.), Tuple::vector(0., 0., 1.), 0),
-            (Tuple::point(0., 1.5, -2.), Tuple::vector(0., 0., 1.), 2),
+            (Point3::new(0., 1.5, 0.), Vec3::new(0.1, 1., 0.), 0),
+            (Point3::new(0., 3., -5.), Vec3::new(0., 0., 1.), 0),
+            (Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.), 0),
+            (Point3::new(0., 2., -5.), Vec3::new(0., 0., 1.), 0),
+            (Point3::new(0., 1., -5.), Vec3::new(0., 0., 1.), 0),
+            (Point3::new(0., 1.5, -2.), Vec3::new(0., 0., 1.), 2),
         ];
 
         let mut cyl = Cylinder::new();
@@ -203,6 +360,27 @@ mod tests {
         assert!(!cyl.closed);
     }
 
+    #[test]
+    fn truncated_cylinders_need_their_minimum_below_their_maximum() {
+        let cyl = Cylinder::truncated(1., 2., true).unwrap();
+        assert_eq!((cyl.minimum(), cyl.maximum(), cyl.closed()), (1., 2., true));
+        assert!(Cylinder::truncated(f64::NEG_INFINITY, 0., false).is_ok());
+
+        for (minimum, maximum) in [(2., 1.), (1., 1.), (f64::NAN, 1.)] {
+            assert!(matches!(
+                Cylinder::truncated(minimum, maximum, false),
+                Err(RayTracerError::InvalidShape(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn a_unit_capped_cylinder() {
+        let cyl = Cylinder::unit_capped();
+
+        assert_eq!((cyl.minimum(), cyl.maximum(), cyl.closed()), (0., 1., true));
+    }
+
     #[test]
     fn intersecting_the_caps_of_a_closed_cylinder() {
         let mut cyl = Cylinder::new();
@@ -211,11 +389,11 @@ mod tests {
         cyl.closed = true;
 
         let examples = vec![
-            (Tuple::point(0., 3., 0.), Tuple::vector(0., -1., 0.), 2),
-            (Tuple::point(0., 3., -2.), Tuple::vector(0., -1., 2.), 2),
-            (Tuple::point(0., 4., -2.), Tuple::vector(0., -1., 1.), 2),
-            (Tuple::point(0., 0., -2.), Tuple::vector(0., 1., 2.), 2),
-            (Tuple::point(0., -1., -2.), Tuple::vector(0., 1., 1.), 2),
+            (Point3::new(0., 3., 0.), Vec3::new(0., -1., 0.), 2),
+            (Point3::new(0., 3., -2.), Vec3::new(0., -1., 2.), 2),
+            (Point3::new(0., 4., -2.), Vec3::new(0., -1., 1.), 2),
+            (Point3::new(0., 0., -2.), Vec3::new(0., 1., 2.), 2),
+            (Point3::new(0., -1., -2.), Vec3::new(0., 1., 1.), 2),
         ];
 
         for (point, direction, count) in examples {
@@ -235,12 +413,12 @@ mod tests {
         cyl.closed = true;
 
         let examples = vec![
-            (Tuple::point(0., 1., 0.), Tuple::vector(0., -1., 0.)),
-            (Tuple::point(0.5, 1., 0.), Tuple::vector(0., -1., 0.)),
-            (Tuple::point(0., 1., 0.5), Tuple::vector(0., -1., 0.)),
-            (Tuple::point(0., 2., 0.), Tuple::vector(0., 1., 0.)),
-            (Tuple::point(0.5, 2., 0.), Tuple::vector(0., 1., 0.)),
-            (Tuple::point(0., 2., 0.5), Tuple::vector(0., 1., 0.)),
+            (Point3::new(0., 1., 0.), Vec3::new(0., -1., 0.)),
+            (Point3::new(0.5, 1., 0.), Vec3::new(0., -1., 0.)),
+            (Point3::new(0., 1., 0.5), Vec3::new(0., -1., 0.)),
+            (Point3::new(0., 2., 0.), Vec3::new(0., 1., 0.)),
+            (Point3::new(0.5, 2., 0.), Vec3::new(0., 1., 0.)),
+            (Point3::new(0., 2., 0.5), Vec3::new(0., 1., 0.)),
         ];
 
         for (point, normal) in examples {
@@ -249,4 +427,32 @@ mod tests {
             assert_eq!(n, normal)
         }
     }
+
+    #[test]
+    fn cylinders_can_be_elliptical() {
+        let cyl = Cylinder::truncated(0., 1., true)
+            .unwrap()
+            .with_radii(2., 0.5)
+            .unwrap();
+
+        let along_x = Ray::new(Point3::new(-5., 0.5, 0.), Vec3::new(1., 0., 0.));
+        let along_z = Ray::new(Point3::new(0., 0.5, -5.), Vec3::new(0., 0., 1.));
+        let down = Ray::new(Point3::new(1.9, 2., 0.), Vec3::new(0., -1., 0.));
+        assert_eq!(cyl.local_intersect(along_x), [3., 7.]);
+        assert_eq!(cyl.local_intersect(along_z), [4.5, 5.5]);
+        assert_eq!(cyl.local_intersect(down), [2., 1.]);
+
+        let side = Point3::new(2_f64.sqrt(), 0.5, 0.5 / 2_f64.sqrt());
+        assert_eq!(
+            cyl.local_normal_at(side).normalize(),
+            Vec3::new(1., 0., 4.).normalize()
+        );
+        assert_eq!(
+            cyl.local_normal_at(Point3::new(1.9, 1., 0.)),
+            Vec3::new(0., 1., 0.)
+        );
+
+        assert!(Cylinder::new().with_radius(0.).is_err());
+        assert!(Cylinder::new().with_radii(1., f64::INFINITY).is_err());
+    }
 }
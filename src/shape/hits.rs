@@ -0,0 +1,169 @@
+use std::{fmt, mem::MaybeUninit, ops::Deref};
+
+/// How many hits `Hits` keeps without allocating, as many as any primitive but curves has.
+const INLINE: usize = 4;
+
+/// Where a ray hits a shape, kept inline for the handful of hits nearly every shape has
+/// so intersecting doesn't allocate, and spilling over into a `Vec` for the rest.
+#[derive(Clone)]
+pub struct Hits<T: Copy> {
+    /// The first `len` are hits, and the rest not set yet.
+    inline: [MaybeUninit<T>; INLINE],
+    /// How many of `inline` are hits, 0 once they spilled over into `spilled`.
+    len: usize,
+    spilled: Vec<T>,
+}
+
+impl<T: Copy> Hits<T> {
+    pub fn new() -> Self {
+        Self {
+            inline: [MaybeUninit::uninit(); INLINE],
+            len: 0,
+            spilled: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, hit: T) {
+        if self.spilled.is_empty() && self.len < INLINE {
+            self.inline[self.len] = MaybeUninit::new(hit);
+            self.len += 1;
+        } else {
+            if self.spilled.is_empty() {
+                self.spilled = self.inline().to_vec();
+                self.len = 0;
+            }
+            self.spilled.push(hit);
+        }
+    }
+
+    /// The hits kept inline.
+    fn inline(&self) -> &[T] {
+        // SAFETY: the first `len` items of `inline` are set, and `MaybeUninit<T>` has the
+        // same size and alignment as `T`.
+        unsafe { std::slice::from_raw_parts(self.inline.as_ptr().cast(), self.len) }
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        if self.spilled.is_empty() {
+            self.inline()
+        } else {
+            &self.spilled
+        }
+    }
+
+    /// The hits changed by `f`, in the same order.
+    pub fn map<U: Copy>(self, f: impl FnMut(T) -> U) -> Hits<U> {
+        self.into_iter().map(f).collect()
+    }
+}
+
+impl<T: Copy> Default for Hits<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy> Deref for Hits<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T: Copy> FromIterator<T> for Hits<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut hits = Self::new();
+        hits.extend(iter);
+
+        hits
+    }
+}
+
+impl<T: Copy> Extend<T> for Hits<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for hit in iter {
+            self.push(hit);
+        }
+    }
+}
+
+impl<T: Copy> IntoIterator for Hits<T> {
+    type Item = T;
+    type IntoIter = std::iter::Chain<
+        std::iter::Map<
+            std::iter::Take<std::array::IntoIter<MaybeUninit<T>, INLINE>>,
+            fn(MaybeUninit<T>) -> T,
+        >,
+        std::vec::IntoIter<T>,
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        // SAFETY: only the first `len` items of `inline` are taken, and those are set.
+        let hit: fn(MaybeUninit<T>) -> T = |hit| unsafe { hit.assume_init() };
+
+        self.inline
+            .into_iter()
+            .take(self.len)
+            .map(hit)
+            .chain(self.spilled)
+    }
+}
+
+impl<'a, T: Copy> IntoIterator for &'a Hits<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+
+impl<T: Copy, const N: usize> From<[T; N]> for Hits<T> {
+    fn from(hits: [T; N]) -> Self {
+        hits.into_iter().collect()
+    }
+}
+
+impl<T: Copy + fmt::Debug> fmt::Debug for Hits<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl<T: Copy + PartialEq, U: AsRef<[T]>> PartialEq<U> for Hits<T> {
+    fn eq(&self, other: &U) -> bool {
+        self.as_slice() == other.as_ref()
+    }
+}
+
+impl<T: Copy> AsRef<[T]> for Hits<T> {
+    fn as_ref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hits_spill_over_past_what_fits_inline() {
+        let mut hits: Hits<f64> = [1., 2., 3.].into();
+        assert!(hits.spilled.is_empty());
+        assert_eq!(
+            hits.clone().into_iter().collect::<Vec<_>>(),
+            vec![1., 2., 3.]
+        );
+
+        hits.extend([4., 5.]);
+
+        assert_eq!(hits, vec![1., 2., 3., 4., 5.]);
+        assert_eq!(hits.len(), 5);
+        assert_eq!(
+            hits.map(|t| t * 2.).into_iter().collect::<Vec<_>>(),
+            vec![2., 4., 6., 8., 10.]
+        );
+        assert!(Hits::<f64>::new().is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{math::tuple::Tuple, ray::Ray};
+
+use super::{BoundingBox, Object, SimpleObject};
+
+/// Versions of the same model in less and less detail, each one used by rays starting
+/// further away than its switch distance, so models far in the background stay cheap.
+///
+/// Distances are measured from the center of the model's bounds in the space of the object,
+/// so they scale along with it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Lod {
+    /// Sorted by switch distance, the first one being 0.
+    pub(crate) levels: Vec<(f64, Object)>,
+}
+
+impl Lod {
+    /// `detailed` is used up close, then each of `levels` from its switch distance on.
+    pub fn new(detailed: Object, levels: Vec<(f64, Object)>) -> Self {
+        let mut levels: Vec<(f64, Object)> = std::iter::once((0., detailed))
+            .chain(
+                levels
+                    .into_iter()
+                    .map(|(distance, level)| (distance.max(0.), level)),
+            )
+            .collect();
+        levels.sort_by(|(d1, _), (d2, _)| d1.total_cmp(d2));
+
+        Self { levels }
+    }
+
+    /// The level seen by rays starting at `origin`.
+    pub(crate) fn level_for(&self, local_ray: Ray) -> &Object {
+        let distance = (Tuple::from(local_ray.origin) - self.center()).magnitude();
+
+        self.levels
+            .iter()
+            .rev()
+            .find(|(switch_distance, _)| distance >= *switch_distance)
+            .map(|(_, level)| level)
+            .unwrap_or(&self.levels[0].1)
+    }
+
+    fn center(&self) -> Tuple {
+        let bounds = self.levels[0].1.bounding_box();
+
+        if bounds.is_finite() {
+            bounds.min + (bounds.max - bounds.min) * 0.5
+        } else {
+            Tuple::point(0., 0., 0.)
+        }
+    }
+
+    pub(crate) fn bounding_box(&self) -> BoundingBox {
+        self.levels
+            .iter()
+            .map(|(_, level)| level.bounding_box())
+            .reduce(|box1, box2| box1.union(&box2))
+            .unwrap_or_else(BoundingBox::empty)
+    }
+
+    pub(crate) fn includes(&self, object: SimpleObject) -> bool {
+        self.levels.iter().any(|(_, level)| level.includes(object))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{point3::Point3, vec3::Vec3};
+    use crate::{
+        math::matrix4::Matrix4,
+        shape::{cube::Cube, sphere::Sphere, Object},
+    };
+
+    fn lod() -> Object {
+        let detailed = Object::sphere();
+        let mut coarse = Object::cube();
+        coarse.set_transform(Matrix4::scaling(0.5, 0.5, 0.5));
+
+        Object::lod(detailed, vec![(10., coarse)])
+    }
+
+    #[test]
+    fn rays_see_the_level_for_how_far_they_start() {
+        let lod = lod();
+        let near = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
+        let far = Ray::new(Point3::new(0., 0., -20.), Vec3::new(0., 0., 1.));
+
+        let xs = lod.intersect(near);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 4.);
+        assert!(xs[0].object.primitive.downcast_ref::<Sphere>().is_some());
+
+        let xs = lod.intersect(far);
+        assert_eq!(xs[0].t, 19.5);
+        assert!(xs[0].object.primitive.downcast_ref::<Cube>().is_some());
+    }
+
+    #[test]
+    fn levels_follow_the_transform_of_the_object() {
+        let mut lod = lod();
+        lod.set_transform(Matrix4::translation(0., 0., 20.));
+        let r = Ray::new(Point3::new(0., 0., 5.), Vec3::new(0., 0., 1.));
+
+        let xs = lod.intersect(r);
+
+        // 15 units away from the center, far enough for the cube.
+        assert_eq!(xs[0].t, 14.5);
+        assert_eq!(
+            xs[0].object.transform(),
+            lod.transform() * Matrix4::scaling(0.5, 0.5, 0.5)
+        );
+    }
+}
@@ -1,45 +1,198 @@
+use crate::intersection::{Intersection, TorUVT};
+use crate::math::point3::Point3;
 use crate::math::tuple::Tuple;
+use crate::math::vec3::Vec3;
+use crate::mesh::{self, Mesh};
 use crate::misc::EPSILON;
 use crate::ray::Ray;
+use crate::sampler::Rng;
+use crate::shape::hits::Hits;
+use crate::shape::{primitive::Primitive, BoundingBox};
+use std::f64::consts::PI;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Plane {}
 
 impl Plane {
-    pub fn local_intersect(local_ray: Ray) -> Vec<f64> {
-        if local_ray.direction.y.abs() < EPSILON {
-            vec![]
+    pub fn local_intersect(local_ray: Ray) -> Hits<f64> {
+        if local_ray.direction.y.abs() < EPSILON * local_ray.direction.magnitude() {
+            Hits::new()
         } else {
             let t = -local_ray.origin.y / local_ray.direction.y;
 
-            vec![t]
+            [t].into()
         }
     }
 
-    pub fn local_normal_at(_: Tuple) -> Tuple {
-        Tuple::vector(0., 1., 0.)
+    /// Where the ray meets the plane, if it does inside the disc of `radius` around the origin.
+    pub fn local_intersect_disc(local_ray: Ray, radius: f64) -> Hits<f64> {
+        Self::local_intersect_within(local_ray, |x, z| x.hypot(z) <= radius)
+    }
+
+    /// Where the ray meets the plane, if it does inside the `width` by `depth` rectangle
+    /// centred on the origin, `width` along x and `depth` along z.
+    pub fn local_intersect_quad(local_ray: Ray, width: f64, depth: f64) -> Hits<f64> {
+        Self::local_intersect_within(local_ray, |x, z| {
+            x.abs() <= width / 2. && z.abs() <= depth / 2.
+        })
+    }
+
+    fn local_intersect_within(local_ray: Ray, inside: impl Fn(f64, f64) -> bool) -> Hits<f64> {
+        Self::local_intersect(local_ray)
+            .into_iter()
+            .filter(|&t| {
+                let point = local_ray.position(t);
+
+                inside(point.x, point.z)
+            })
+            .collect()
+    }
+
+    pub fn local_normal_at(_: Point3) -> Vec3 {
+        Vec3::new(0., 1., 0.)
+    }
+
+    pub(crate) fn sample_disc(radius: f64, rng: &mut Rng) -> (Point3, Vec3) {
+        // Rings grow with their distance to the centre, and so should the odds of landing on them.
+        let distance = radius * rng.next_f64().sqrt();
+        let angle = rng.range(0., 2. * PI);
+        let point = Point3::new(distance * angle.cos(), 0., distance * angle.sin());
+
+        (point, Self::local_normal_at(point))
+    }
+
+    pub(crate) fn sample_quad(width: f64, depth: f64, rng: &mut Rng) -> (Point3, Vec3) {
+        let point = Point3::new(
+            rng.range(-width / 2., width / 2.),
+            0.,
+            rng.range(-depth / 2., depth / 2.),
+        );
+
+        (point, Self::local_normal_at(point))
+    }
+}
+
+/// The part of the plane within `radius` of the origin.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Disc {
+    pub radius: f64,
+}
+
+/// The `width` by `depth` rectangle of the plane centred on the origin, `width` along x.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quad {
+    pub width: f64,
+    pub depth: f64,
+}
+
+impl Primitive for Plane {
+    fn local_intersect(&self, ray: Ray) -> Hits<TorUVT> {
+        Plane::local_intersect(ray).map(|t| TorUVT::JustT { t })
+    }
+
+    fn local_normal_at(&self, _: Intersection, point: Point3) -> Vec3 {
+        Plane::local_normal_at(point)
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::new(
+            Tuple::point(f64::NEG_INFINITY, 0., f64::NEG_INFINITY),
+            Tuple::point(f64::INFINITY, 0., f64::INFINITY),
+        )
+    }
+
+    fn same_as(&self, other: &dyn Primitive) -> bool {
+        other.downcast_ref() == Some(self)
+    }
+}
+
+impl Primitive for Disc {
+    fn local_intersect(&self, ray: Ray) -> Hits<TorUVT> {
+        Plane::local_intersect_disc(ray, self.radius).map(|t| TorUVT::JustT { t })
+    }
+
+    fn local_normal_at(&self, _: Intersection, point: Point3) -> Vec3 {
+        Plane::local_normal_at(point)
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let radius = self.radius;
+
+        BoundingBox::new(
+            Tuple::point(-radius, 0., -radius),
+            Tuple::point(radius, 0., radius),
+        )
+    }
+
+    fn area(&self) -> Option<f64> {
+        Some(PI * self.radius * self.radius)
+    }
+
+    fn sample_surface(&self, rng: &mut Rng) -> Option<(Point3, Vec3)> {
+        Some(Plane::sample_disc(self.radius, rng))
+    }
+
+    fn tessellate(&self, resolution: usize) -> Mesh {
+        mesh::disc(self.radius, resolution)
+    }
+
+    fn same_as(&self, other: &dyn Primitive) -> bool {
+        other.downcast_ref() == Some(self)
+    }
+}
+
+impl Primitive for Quad {
+    fn local_intersect(&self, ray: Ray) -> Hits<TorUVT> {
+        Plane::local_intersect_quad(ray, self.width, self.depth).map(|t| TorUVT::JustT { t })
+    }
+
+    fn local_normal_at(&self, _: Intersection, point: Point3) -> Vec3 {
+        Plane::local_normal_at(point)
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let (x, z) = (self.width / 2., self.depth / 2.);
+
+        BoundingBox::new(Tuple::point(-x, 0., -z), Tuple::point(x, 0., z))
+    }
+
+    fn area(&self) -> Option<f64> {
+        Some(self.width * self.depth)
+    }
+
+    fn sample_surface(&self, rng: &mut Rng) -> Option<(Point3, Vec3)> {
+        Some(Plane::sample_quad(self.width, self.depth, rng))
+    }
+
+    fn tessellate(&self, _resolution: usize) -> Mesh {
+        mesh::quad_mesh(self.width, self.depth)
+    }
+
+    fn same_as(&self, other: &dyn Primitive) -> bool {
+        other.downcast_ref() == Some(self)
     }
 }
 
 #[cfg(test)]
 mod tests {
+
     use super::*;
     use crate::shape::{Object, SimpleObject};
 
     #[test]
     fn the_normal_of_a_plane_is_constant_everywhere() {
-        let n1 = Plane::local_normal_at(Tuple::point(0., 0., 0.));
-        let n2 = Plane::local_normal_at(Tuple::point(10., 0., -10.));
-        let n3 = Plane::local_normal_at(Tuple::point(-5., 0., 150.));
+        let n1 = Plane::local_normal_at(Point3::new(0., 0., 0.));
+        let n2 = Plane::local_normal_at(Point3::new(10., 0., -10.));
+        let n3 = Plane::local_normal_at(Point3::new(-5., 0., 150.));
 
-        assert_eq!(n1, Tuple::vector(0., 1., 0.));
-        assert_eq!(n2, Tuple::vector(0., 1., 0.));
-        assert_eq!(n3, Tuple::vector(0., 1., 0.));
+        assert_eq!(n1, Vec3::new(0., 1., 0.));
+        assert_eq!(n2, Vec3::new(0., 1., 0.));
+        assert_eq!(n3, Vec3::new(0., 1., 0.));
     }
 
     #[test]
     fn intersect_with_a_ray_parallel_to_the_plane() {
-        let r = Ray::new(Tuple::point(0., 10., 0.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 10., 0.), Vec3::new(0., 0., 1.));
         let xs = Plane::local_intersect(r);
 
         assert!(xs.is_empty());
@@ -47,17 +200,42 @@ mod tests {
 
     #[test]
     fn intersect_with_a_coplanar_ray() {
-        let r = Ray::new(Tuple::point(0., 0., 0.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., 1.));
         let xs = Plane::local_intersect(r);
 
         assert!(xs.is_empty());
     }
 
+    #[test]
+    fn discs_are_only_hit_within_their_radius() {
+        let down = Vec3::new(0., -1., 0.);
+        let hit =
+            |x: f64, z: f64| Plane::local_intersect_disc(Ray::new(Point3::new(x, 1., z), down), 2.);
+
+        assert_eq!(hit(0., 0.), vec![1.]);
+        assert_eq!(hit(1.2, 1.5), vec![1.]);
+        assert!(hit(1.5, 1.5).is_empty());
+        assert!(hit(0., -2.1).is_empty());
+    }
+
+    #[test]
+    fn quads_are_only_hit_within_their_sides() {
+        let down = Vec3::new(0., -1., 0.);
+        let hit = |x: f64, z: f64| {
+            Plane::local_intersect_quad(Ray::new(Point3::new(x, 1., z), down), 4., 2.)
+        };
+
+        assert_eq!(hit(0., 0.), vec![1.]);
+        assert_eq!(hit(1.9, -0.9), vec![1.]);
+        assert!(hit(2.1, 0.).is_empty());
+        assert!(hit(0., 1.1).is_empty());
+    }
+
     #[test]
     fn a_ray_intersecting_a_plane_from_above() {
         let object = Object::plane();
         let p = SimpleObject::from_object(&object).unwrap();
-        let local_ray = Ray::new(Tuple::point(0., 1., 0.), Tuple::vector(0., -1., 0.));
+        let local_ray = Ray::new(Point3::new(0., 1., 0.), Vec3::new(0., -1., 0.));
         let world_ray = local_ray.transform(p.transform());
         let xs = p.intersect(world_ray);
 
@@ -69,7 +247,7 @@ mod tests {
     fn a_ray_intersecting_a_plane_from_below() {
         let object = Object::plane();
         let p = SimpleObject::from_object(&object).unwrap();
-        let local_ray = Ray::new(Tuple::point(0., -1., 0.), Tuple::vector(0., 1., 0.));
+        let local_ray = Ray::new(Point3::new(0., -1., 0.), Vec3::new(0., 1., 0.));
         let world_ray = local_ray.transform(p.transform());
         let xs = p.intersect(world_ray);
 
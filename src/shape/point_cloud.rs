@@ -0,0 +1,345 @@
+use crate::{
+    color::Color, material::Material, math::transform::Transform, math::tuple::Tuple, ray::Ray,
+};
+
+use super::{sphere::Sphere, BoundingBox, SimpleObject, BOUNDING_BOX_PADDING};
+
+/// Maximum amount of points stored in a single leaf of the hierarchy.
+const LEAF_SIZE: usize = 4;
+
+/// Every point is intersected as this sphere, moved and scaled into place.
+pub(crate) static UNIT_SPHERE: Sphere = Sphere {};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CloudPoint {
+    pub position: Tuple,
+    pub radius: f64,
+    /// Overrides the color of the point cloud's material, if present.
+    pub color: Option<Color>,
+}
+
+impl CloudPoint {
+    pub fn new(position: Tuple, radius: f64) -> Self {
+        Self {
+            position,
+            radius,
+            color: None,
+        }
+    }
+
+    pub(crate) fn transform(&self) -> Transform {
+        let Tuple { x, y, z, .. } = self.position;
+
+        Transform::translation(x, y, z) * Transform::scaling(self.radius, self.radius, self.radius)
+    }
+
+    pub(crate) fn material(&self, mut material: Material) -> Material {
+        if let Some(color) = self.color {
+            material.color = color;
+        }
+
+        material
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let extent = Tuple::vector(self.radius, self.radius, self.radius);
+
+        BoundingBox {
+            min: self.position - extent,
+            max: self.position + extent,
+        }
+    }
+
+    /// Same maths as `Sphere::local_intersect`, without going through a transform.
+    fn intersect(&self, ray: Ray) -> Option<(f64, f64)> {
+        let sphere_to_ray = Tuple::from(ray.origin) - self.position;
+        let a = ray.direction.magnitude_squared();
+        let b = 2. * Tuple::from(ray.direction).dot(sphere_to_ray);
+        let c = sphere_to_ray.magnitude_squared() - self.radius.powi(2);
+
+        let discriminant = b.powi(2) - 4. * a * c;
+
+        if discriminant < 0. {
+            None
+        } else {
+            let t1 = (-b - discriminant.sqrt()) / (2. * a);
+            let t2 = (-b + discriminant.sqrt()) / (2. * a);
+
+            Some((t1, t2))
+        }
+    }
+}
+
+/// A set of spheres with their own position, radius and color,
+/// intersected through a bounding volume hierarchy built once on creation
+/// instead of going through one `Object` per point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointCloud {
+    points: Vec<CloudPoint>,
+    nodes: Vec<Node>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Leaf {
+        bounds: BoundingBox,
+        start: usize,
+        end: usize,
+    },
+    Interior {
+        bounds: BoundingBox,
+        left: usize,
+        right: usize,
+    },
+}
+
+impl Node {
+    fn bounds(&self) -> &BoundingBox {
+        match self {
+            Node::Leaf { bounds, .. } | Node::Interior { bounds, .. } => bounds,
+        }
+    }
+}
+
+impl PointCloud {
+    pub fn new(mut points: Vec<CloudPoint>) -> Self {
+        let mut nodes = vec![];
+
+        if !points.is_empty() {
+            let len = points.len();
+            build(&mut points, 0, len, &mut nodes);
+        }
+
+        Self { points, nodes }
+    }
+
+    pub fn points(&self) -> &[CloudPoint] {
+        &self.points
+    }
+
+    pub(crate) fn bounding_box(&self) -> BoundingBox {
+        match self.nodes.first() {
+            Some(root) => root.bounds().clone(),
+            None => BoundingBox::from_points(&[Tuple::point(0., 0., 0.)]),
+        }
+    }
+
+    /// Returns every hit as a (t, point) pair.
+    pub(crate) fn local_intersect(&self, local_ray: Ray) -> Vec<(f64, &CloudPoint)> {
+        let mut xs = vec![];
+        let mut stack = vec![];
+
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+
+            if !node
+                .bounds()
+                .padded(BOUNDING_BOX_PADDING)
+                .intersect(local_ray)
+            {
+                continue;
+            }
+
+            match *node {
+                Node::Leaf { start, end, .. } => {
+                    for point in &self.points[start..end] {
+                        if let Some((t1, t2)) = point.intersect(local_ray) {
+                            xs.push((t1, point));
+                            xs.push((t2, point));
+                        }
+                    }
+                }
+                Node::Interior { left, right, .. } => {
+                    stack.push(left);
+                    stack.push(right);
+                }
+            }
+        }
+
+        xs
+    }
+
+    pub(crate) fn includes(&self, transform: Transform, object: SimpleObject) -> bool {
+        object.primitive.downcast_ref() == Some(&UNIT_SPHERE)
+            && self
+                .points
+                .iter()
+                .any(|point| transform * point.transform() == object.transform)
+    }
+}
+
+/// Builds the hierarchy for `points[start..end]`, returning the index of its root node.
+fn build(points: &mut [CloudPoint], start: usize, end: usize, nodes: &mut Vec<Node>) -> usize {
+    let bounds = points[start..end]
+        .iter()
+        .map(CloudPoint::bounding_box)
+        .reduce(|box1, box2| box1.union(&box2))
+        .unwrap();
+
+    let index = nodes.len();
+
+    if end - start <= LEAF_SIZE {
+        nodes.push(Node::Leaf { bounds, start, end });
+        return index;
+    }
+
+    // Split along the longest axis, at the median point.
+    let extent = bounds.max - bounds.min;
+    let axis = |point: &CloudPoint| {
+        if extent.x >= extent.y && extent.x >= extent.z {
+            point.position.x
+        } else if extent.y >= extent.z {
+            point.position.y
+        } else {
+            point.position.z
+        }
+    };
+    points[start..end].sort_by(|p1, p2| axis(p1).total_cmp(&axis(p2)));
+    let middle = start + (end - start) / 2;
+
+    // Reserve our slot so that children come after their parent.
+    nodes.push(Node::Leaf { bounds, start, end });
+    let left = build(points, start, middle, nodes);
+    let right = build(points, middle, end, nodes);
+    let bounds = nodes[index].bounds().clone();
+    nodes[index] = Node::Interior {
+        bounds,
+        left,
+        right,
+    };
+
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{point3::Point3, vec3::Vec3};
+    use crate::{
+        intersection::Intersection,
+        math::matrix4::Matrix4,
+        misc::approx_equal,
+        shape::{Object, ShapeOrGroup},
+    };
+
+    fn grid(size: usize) -> Vec<CloudPoint> {
+        let mut points = vec![];
+
+        for x in 0..size {
+            for y in 0..size {
+                for z in 0..size {
+                    let position = Tuple::point(x as f64, y as f64, z as f64);
+                    points.push(CloudPoint::new(position, 0.1 + 0.05 * (x % 3) as f64));
+                }
+            }
+        }
+
+        points
+    }
+
+    #[test]
+    fn the_hierarchy_finds_the_same_hits_as_brute_force() {
+        let points = grid(6);
+        let cloud = PointCloud::new(points.clone());
+        let rays = [
+            Ray::new(Point3::new(-1., 0., 0.), Vec3::new(1., 0., 0.)),
+            Ray::new(Point3::new(2.05, 10., 3.), Vec3::new(0., -1., 0.)),
+            Ray::new(Point3::new(-2., -2., -2.), Vec3::new(1., 1., 1.)),
+            Ray::new(Point3::new(-2., 0.5, 0.5), Vec3::new(1., 0., 0.)),
+        ];
+
+        for ray in rays {
+            let mut expected: Vec<f64> = points
+                .iter()
+                .filter_map(|point| point.intersect(ray))
+                .flat_map(|(t1, t2)| [t1, t2])
+                .collect();
+            let mut actual: Vec<f64> = cloud
+                .local_intersect(ray)
+                .into_iter()
+                .map(|(t, _)| t)
+                .collect();
+            expected.sort_by(|t1, t2| t1.partial_cmp(t2).unwrap());
+            actual.sort_by(|t1, t2| t1.partial_cmp(t2).unwrap());
+
+            assert_eq!(expected.len(), actual.len());
+            assert!(expected
+                .iter()
+                .zip(actual.iter())
+                .all(|(t1, t2)| approx_equal(*t1, *t2)));
+        }
+    }
+
+    #[test]
+    fn points_that_are_not_numbers_dont_stop_the_cloud_from_being_built() {
+        let mut points = grid(3);
+        points[4].position.x = f64::NAN;
+        points[9].position.y = f64::NAN;
+        let cloud = PointCloud::new(points);
+        let r = Ray::new(Point3::new(-1., 2., 2.), Vec3::new(1., 0., 0.));
+
+        assert_eq!(cloud.local_intersect(r).len(), 6);
+    }
+
+    #[test]
+    fn a_point_cloud_has_a_bounding_box_covering_all_its_radii() {
+        let cloud = PointCloud::new(vec![
+            CloudPoint::new(Tuple::point(0., 0., 0.), 1.),
+            CloudPoint::new(Tuple::point(5., -2., 1.), 0.5),
+        ]);
+        let bounds = cloud.bounding_box();
+
+        assert_eq!(bounds.min, Tuple::point(-1., -2.5, -1.));
+        assert_eq!(bounds.max, Tuple::point(5.5, 1., 1.5));
+    }
+
+    #[test]
+    fn intersecting_a_point_cloud_object_yields_transformed_spheres() {
+        let mut red_point = CloudPoint::new(Tuple::point(0., 0., 5.), 2.);
+        red_point.color = Some(Color::red());
+        let mut object = Object::point_cloud(vec![red_point]);
+        object.set_transform(Matrix4::translation(1., 0., 0.));
+        let r = Ray::new(Point3::new(1., 0., -5.), Vec3::new(0., 0., 1.));
+
+        let xs = object.intersect(r);
+
+        assert_eq!(xs.len(), 2);
+        assert!(approx_equal(xs[0].t, 8.));
+        assert!(approx_equal(xs[1].t, 12.));
+        assert_eq!(xs[0].object.material.color, Color::red());
+        assert_eq!(
+            xs[0].object.transform(),
+            Matrix4::translation(1., 0., 5.) * Matrix4::scaling(2., 2., 2.)
+        );
+
+        let n = xs[0].object.normal_at(xs[0], r.position(xs[0].t));
+        assert_eq!(n, Vec3::new(0., 0., -1.));
+    }
+
+    #[test]
+    fn points_without_a_color_use_the_cloud_material() {
+        let mut object = Object::point_cloud(vec![CloudPoint::new(Tuple::point(0., 0., 0.), 1.)]);
+        let mut material = Material::new();
+        material.color = Color::green();
+        object.set_material(material);
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
+
+        let xs = object.intersect(r);
+        let hit = Intersection::hit(&xs).unwrap();
+
+        assert_eq!(hit.object.material.color, Color::green());
+        assert!(matches!(object.shape, ShapeOrGroup::Shape { .. }));
+    }
+
+    #[test]
+    fn an_empty_point_cloud_is_never_hit() {
+        let cloud = PointCloud::new(vec![]);
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.));
+
+        assert!(cloud.local_intersect(r).is_empty());
+    }
+}
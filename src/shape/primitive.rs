@@ -0,0 +1,149 @@
+use std::{any::Any, fmt, ptr};
+
+use crate::{
+    intersection::{Intersection, TorUVT},
+    math::{point3::Point3, vec3::Vec3},
+    mesh::Mesh,
+    ray::Ray,
+    sampler::Rng,
+    shape::{hits::Hits, BoundingBox},
+};
+
+/// A shape rays hit directly, which is every built-in one but CSGs, levels of detail and point
+/// clouds, made of other objects. Objects hold them all the same way, so built-in shapes and
+/// ones defined outside of the crate are made into objects with `Object::primitive`, and a new
+/// one only needs this trait implemented.
+///
+/// Everything is in the space of the shape, objects taking rays into it and normals out of
+/// it through their transforms. Primitives are shared between the threads rendering, and
+/// between the copies of objects that hold them.
+pub trait Primitive: Any + fmt::Debug + Send + Sync {
+    /// Where a ray hits the shape, in no particular order, with where on the surface for
+    /// shapes that have coordinates.
+    fn local_intersect(&self, ray: Ray) -> Hits<TorUVT>;
+
+    /// The normal at `point`, where `intersection` hit the shape. It doesn't have to be
+    /// normalized, and which side it's on doesn't matter.
+    fn local_normal_at(&self, intersection: Intersection, point: Point3) -> Vec3;
+
+    /// The normal of the surface itself, for shapes that smooth or perturb the one
+    /// `local_normal_at` shades with.
+    fn local_geometric_normal_at(&self, intersection: Intersection, point: Point3) -> Vec3 {
+        self.local_normal_at(intersection, point)
+    }
+
+    /// Bounds of everything `local_intersect` can hit, infinite ones included.
+    fn bounding_box(&self) -> BoundingBox;
+
+    /// The area of the surface, for the primitives `sample_surface` can pick points on.
+    fn area(&self) -> Option<f64> {
+        None
+    }
+
+    /// A point picked uniformly on the surface and the normal there, for area lights and the
+    /// like. `None` for primitives without an end, or with no simple way to pick points evenly.
+    fn sample_surface(&self, _rng: &mut Rng) -> Option<(Point3, Vec3)> {
+        None
+    }
+
+    /// Triangles approximating the surface, with curves split in `resolution` segments all the
+    /// way around, see `Object::tessellate`. Primitives without an end have none.
+    fn tessellate(&self, _resolution: usize) -> Mesh {
+        Mesh::default()
+    }
+
+    /// Whether `other` is the same shape, which is what objects are compared by.
+    ///
+    /// By default a primitive is only the same as itself, there being no telling what makes
+    /// two of them alike. Built-in ones compare their parameters, through `downcast_ref`.
+    fn same_as(&self, other: &dyn Primitive) -> bool {
+        ptr::addr_eq(self, other)
+    }
+}
+
+impl dyn Primitive {
+    /// The primitive as the shape it is, `None` if it's some other one.
+    pub fn downcast_ref<T: Primitive>(&self) -> Option<&T> {
+        (self as &dyn Any).downcast_ref()
+    }
+}
+
+/// See `Primitive::same_as`.
+impl PartialEq for dyn Primitive {
+    fn eq(&self, other: &Self) -> bool {
+        self.same_as(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::tuple::Tuple;
+    use crate::{
+        color::Color,
+        light::Light,
+        math::matrix4::Matrix4,
+        shape::{sphere::Sphere, Object},
+        world::World,
+    };
+
+    /// Spheres all over again, defined the way shapes outside the crate would be.
+    #[derive(Debug)]
+    struct Ball;
+
+    impl Primitive for Ball {
+        fn local_intersect(&self, ray: Ray) -> Hits<TorUVT> {
+            Sphere::local_intersect(ray).map(|t| TorUVT::JustT { t })
+        }
+
+        fn local_normal_at(&self, _intersection: Intersection, point: Point3) -> Vec3 {
+            Sphere::local_normal_at(point)
+        }
+
+        fn bounding_box(&self) -> BoundingBox {
+            BoundingBox::new(Tuple::point(-1., -1., -1.), Tuple::point(1., 1., 1.))
+        }
+    }
+
+    #[test]
+    fn primitives_render_like_the_shapes_they_copy() {
+        let transform = Matrix4::translation(0.5, 0., 1.) * Matrix4::scaling(1., 2., 1.);
+        let world_of = |mut object: Object| {
+            object.set_transform(transform);
+            let mut world = World::new();
+            world.objects = vec![Object::group(vec![object])];
+            world.lights = vec![Light::point_light(
+                Point3::new(-10., 10., -10.),
+                Color::white(),
+            )];
+
+            world
+        };
+        let ball = world_of(Object::primitive(Ball));
+        let sphere = world_of(Object::sphere());
+
+        assert_eq!(
+            ball.objects[0].bounding_box(),
+            sphere.objects[0].bounding_box()
+        );
+        for x in [-1., 0., 0.4, 1.2] {
+            let r = Ray::new(Point3::new(x, 0.5, -5.), Vec3::new(0., 0., 1.));
+            assert_eq!(ball.color_at(r), sphere.color_at(r));
+        }
+    }
+
+    #[test]
+    fn built_in_primitives_are_equal_to_alike_ones_and_others_only_to_themselves() {
+        assert_eq!(Object::disc(2.), Object::disc(2.));
+        assert_ne!(Object::disc(2.), Object::disc(1.));
+        assert_ne!(Object::sphere(), Object::primitive(Ball));
+
+        let ball = Object::primitive(Ball);
+        assert_eq!(ball.clone(), ball);
+        assert_ne!(Object::primitive(Ball), ball);
+
+        let sphere: &dyn Primitive = &Sphere {};
+        assert!(sphere.downcast_ref::<Sphere>().is_some());
+        assert!(sphere.downcast_ref::<Ball>().is_none());
+    }
+}
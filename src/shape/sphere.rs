@@ -1,12 +1,20 @@
+use crate::intersection::{Intersection, TorUVT};
+use crate::math::point3::Point3;
 use crate::math::tuple::Tuple;
+use crate::math::vec3::Vec3;
+use crate::mesh::{self, Mesh};
 use crate::ray::Ray;
+use crate::sampler::Rng;
+use crate::shape::hits::Hits;
+use crate::shape::{primitive::Primitive, BoundingBox};
+use std::f64::consts::PI;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Sphere {}
 
 impl Sphere {
-    pub fn local_intersect(local_ray: Ray) -> Vec<f64> {
-        let sphere_to_ray = local_ray.origin - Tuple::point(0., 0., 0.);
+    pub fn local_intersect(local_ray: Ray) -> Hits<f64> {
+        let sphere_to_ray = local_ray.origin - Point3::origin();
         let a = local_ray.direction.magnitude_squared();
         let b = 2. * local_ray.direction.dot(sphere_to_ray);
         let c = sphere_to_ray.magnitude_squared() - 1.;
@@ -14,27 +22,71 @@ impl Sphere {
         let discriminant = b.powi(2) - 4. * a * c;
 
         if discriminant < 0. {
-            vec![]
+            Hits::new()
         } else {
             let t1 = (-b - discriminant.sqrt()) / (2. * a);
             let t2 = (-b + discriminant.sqrt()) / (2. * a);
 
-            vec![t1, t2]
+            [t1, t2].into()
         }
     }
 
-    pub fn local_normal_at(local_point: Tuple) -> Tuple {
-        // Warning: do not remove this (consider the w!)
-        local_point - Tuple::point(0., 0., 0.)
+    pub fn local_normal_at(local_point: Point3) -> Vec3 {
+        local_point - Point3::origin()
+    }
+
+    pub const AREA: f64 = 4. * PI;
+
+    /// A point picked uniformly on the sphere, and the normal there.
+    pub(crate) fn sample_surface(rng: &mut Rng) -> (Point3, Vec3) {
+        // Slices of a sphere as thick as each other all have the same area,
+        // so the height is as likely to be anything.
+        let z = rng.range(-1., 1.);
+        let angle = rng.range(0., 2. * PI);
+        let radius = (1. - z * z).sqrt();
+        let point = Point3::new(radius * angle.cos(), radius * angle.sin(), z);
+
+        (point, Self::local_normal_at(point))
+    }
+}
+
+impl Primitive for Sphere {
+    fn local_intersect(&self, ray: Ray) -> Hits<TorUVT> {
+        Sphere::local_intersect(ray).map(|t| TorUVT::JustT { t })
+    }
+
+    fn local_normal_at(&self, _: Intersection, point: Point3) -> Vec3 {
+        Sphere::local_normal_at(point)
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::new(Tuple::point(-1., -1., -1.), Tuple::point(1., 1., 1.))
+    }
+
+    fn area(&self) -> Option<f64> {
+        Some(Sphere::AREA)
+    }
+
+    fn sample_surface(&self, rng: &mut Rng) -> Option<(Point3, Vec3)> {
+        Some(Sphere::sample_surface(rng))
+    }
+
+    fn tessellate(&self, resolution: usize) -> Mesh {
+        mesh::sphere(resolution)
+    }
+
+    fn same_as(&self, other: &dyn Primitive) -> bool {
+        other.downcast_ref() == Some(self)
     }
 }
 
 #[cfg(test)]
 mod tests {
+
     use super::*;
     use crate::{
         intersection::Intersection,
-        shape::{Object, SimpleObject},
+        shape::{tests::chi_square, Object, SimpleObject},
     };
 
     #[test]
@@ -42,8 +94,8 @@ mod tests {
         let object = Object::sphere();
         let s = SimpleObject::from_object(&object).unwrap();
         let i = Intersection::new_(0., s);
-        let n = s.normal_at(i, Tuple::point(1., 0., 0.));
-        assert_eq!(n, Tuple::vector(1., 0., 0.));
+        let n = s.normal_at(i, Point3::new(1., 0., 0.));
+        assert_eq!(n, Vec3::new(1., 0., 0.));
     }
 
     #[test]
@@ -51,8 +103,8 @@ mod tests {
         let object = Object::sphere();
         let s = SimpleObject::from_object(&object).unwrap();
         let i = Intersection::new_(0., s);
-        let n = s.normal_at(i, Tuple::point(0., 1., 0.));
-        assert_eq!(n, Tuple::vector(0., 1., 0.));
+        let n = s.normal_at(i, Point3::new(0., 1., 0.));
+        assert_eq!(n, Vec3::new(0., 1., 0.));
     }
 
     #[test]
@@ -60,8 +112,8 @@ mod tests {
         let object = Object::sphere();
         let s = SimpleObject::from_object(&object).unwrap();
         let i = Intersection::new_(0., s);
-        let n = s.normal_at(i, Tuple::point(0., 0., 1.));
-        assert_eq!(n, Tuple::vector(0., 0., 1.));
+        let n = s.normal_at(i, Point3::new(0., 0., 1.));
+        assert_eq!(n, Vec3::new(0., 0., 1.));
     }
 
     #[test]
@@ -71,11 +123,31 @@ mod tests {
         let i = Intersection::new_(0., s);
         let n = s.normal_at(
             i,
-            Tuple::point(3_f64.sqrt() / 3., 3_f64.sqrt() / 3., 3_f64.sqrt() / 3.),
+            Point3::new(3_f64.sqrt() / 3., 3_f64.sqrt() / 3., 3_f64.sqrt() / 3.),
         );
         assert_eq!(
             n,
-            Tuple::vector(3_f64.sqrt() / 3., 3_f64.sqrt() / 3., 3_f64.sqrt() / 3.)
+            Vec3::new(3_f64.sqrt() / 3., 3_f64.sqrt() / 3., 3_f64.sqrt() / 3.)
         );
     }
+
+    #[test]
+    fn points_are_sampled_uniformly_on_a_sphere() {
+        let mut rng = Rng::new(5);
+        // Bands of the same height and slices of the same angle, which all have the same area.
+        let mut counts = [0; 64];
+
+        for _ in 0..64_000 {
+            let (point, normal) = Sphere::sample_surface(&mut rng);
+
+            assert!(((point - Point3::origin()).magnitude() - 1.).abs() < 1e-9);
+            assert_eq!(normal, point - Point3::origin());
+            let band = (((point.z + 1.) / 2.) * 8.).min(7.) as usize;
+            let slice = ((point.y.atan2(point.x) + PI) / (2. * PI) * 8.).min(7.) as usize;
+            counts[band * 8 + slice] += 1;
+        }
+
+        // The critical value for 63 degrees of freedom at p = 0.001.
+        assert!(chi_square(&counts, &[1. / 64.; 64]) < 103.44);
+    }
 }
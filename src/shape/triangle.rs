@@ -1,11 +1,21 @@
-use crate::{math::tuple::Tuple, misc::EPSILON, ray::Ray, shape::BoundingBox};
+use crate::{
+    intersection::{Intersection, TorUVT},
+    math::{point3::Point3, tuple::Tuple, vec3::Vec3},
+    mesh::Mesh,
+    misc::EPSILON,
+    ray::Ray,
+    sampler::Rng,
+    shape::{hits::Hits, primitive::Primitive, BoundingBox},
+};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Triangle {
     pub(crate) p1: Tuple,
     pub(crate) p2: Tuple,
     pub(crate) p3: Tuple,
-    kind: TriangleKind,
+    pub(crate) kind: TriangleKind,
+    /// Texture coordinates at `p1`, `p2` and `p3`, for meshes that come with them.
+    pub(crate) texture: Option<[(f64, f64); 3]>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -21,6 +31,7 @@ impl Triangle {
             p2,
             p3,
             kind: TriangleKind::Flat,
+            texture: None,
         }
     }
 
@@ -31,6 +42,42 @@ impl Triangle {
             p2,
             p3,
             kind: TriangleKind::Smooth { n1, n2, n3 },
+            texture: None,
+        }
+    }
+
+    /// The same triangle with texture coordinates at each of its corners.
+    pub(crate) fn with_texture(self, texture: [(f64, f64); 3]) -> Self {
+        Self {
+            texture: Some(texture),
+            ..self
+        }
+    }
+
+    /// The texture coordinates at the hit, blended from the ones at the corners like
+    /// smooth normals are.
+    #[allow(dead_code)]
+    pub(crate) fn texture_at(&self, uvt: &UVT) -> Option<(f64, f64)> {
+        let [(u1, v1), (u2, v2), (u3, v3)] = self.texture?;
+        let UVT { u, v, .. } = *uvt;
+        let w = 1. - u - v;
+
+        Some((u1 * w + u2 * u + u3 * v, v1 * w + v2 * u + v3 * v))
+    }
+
+    /// The same triangle wound the other way, so its normal points the other way.
+    pub(crate) fn flipped(self) -> Self {
+        let kind = match self.kind {
+            TriangleKind::Flat => TriangleKind::Flat,
+            TriangleKind::Smooth { n1, n2, n3 } => TriangleKind::Smooth { n1, n2: n3, n3: n2 },
+        };
+
+        Self {
+            p2: self.p3,
+            p3: self.p2,
+            kind,
+            texture: self.texture.map(|[t1, t2, t3]| [t1, t3, t2]),
+            ..self
         }
     }
 
@@ -42,44 +89,68 @@ impl Triangle {
         self.p3 - self.p1
     }
 
-    fn normal(&self) -> Tuple {
-        self.edge2().cross(self.edge1()).normalize()
+    /// The normal of the plane of the triangle, which smooth ones only use to tell sides apart.
+    pub(crate) fn normal(&self) -> Vec3 {
+        vector(self.edge2().cross(self.edge1())).normalize()
+    }
+
+    pub(crate) fn area(&self) -> f64 {
+        self.edge1().cross(self.edge2()).magnitude() / 2.
+    }
+
+    /// A point picked uniformly on the triangle, and the normal of its plane.
+    pub(crate) fn sample_surface(&self, rng: &mut Rng) -> (Point3, Vec3) {
+        // Along `v` from one side to the other of a slice `u` of the way from `p1`,
+        // where slices get longer the further they are.
+        let (u, v) = (rng.next_f64().sqrt(), rng.next_f64());
+        let point = self.p1 + self.edge1() * (u * (1. - v)) + self.edge2() * (u * v);
+
+        (Point3::new(point.x, point.y, point.z), self.normal())
     }
 
-    pub(crate) fn local_normal_at(&self, uvt: &UVT) -> Tuple {
+    pub(crate) fn local_normal_at(&self, uvt: &UVT) -> Vec3 {
         let UVT { u, v, .. } = uvt;
 
         match self.kind {
             TriangleKind::Flat => self.normal(),
             TriangleKind::Smooth { n1, n2, n3 } => {
-                (n2 * *u + n3 * *v + n1 * (1. - *u - *v)).normalize()
+                vector(n2 * *u + n3 * *v + n1 * (1. - *u - *v)).normalize()
             }
         }
     }
 
-    pub(crate) fn local_intersect(&self, local_ray: Ray) -> Vec<UVT> {
-        let dir_cross_edge2 = local_ray.direction.cross(self.edge2());
+    pub(crate) fn local_intersect(&self, local_ray: Ray) -> Hits<UVT> {
+        let (origin, direction) = (
+            Tuple::from(local_ray.origin),
+            Tuple::from(local_ray.direction),
+        );
+        let dir_cross_edge2 = direction.cross(self.edge2());
         let det = self.edge1().dot(dir_cross_edge2);
 
-        if det.abs() < EPSILON {
-            return vec![];
+        // Parallel to the triangle as a share of how long the direction and the edges are,
+        // so tiny triangles and the short directions of scaled up meshes still get hit.
+        let scale = direction.dot(direction)
+            * self.edge1().dot(self.edge1())
+            * self.edge2().dot(self.edge2());
+        if det * det < EPSILON * EPSILON * scale {
+            return Hits::new();
         }
 
         let f = 1.0 / det;
-        let p1_to_origin = local_ray.origin - self.p1;
+        let p1_to_origin = origin - self.p1;
         let u = f * p1_to_origin.dot(dir_cross_edge2);
-        if u < 0. || u > 1. {
-            return vec![];
+        if !(0. ..=1.).contains(&u) {
+            return Hits::new();
         }
 
         let origin_cross_e1 = p1_to_origin.cross(self.edge1());
-        let v = f * local_ray.direction.dot(origin_cross_e1);
+        let v = f * direction.dot(origin_cross_e1);
         if v < 0. || (u + v) > 1. {
-            return vec![];
+            return Hits::new();
         }
 
         let t = f * self.edge2().dot(origin_cross_e1);
-        vec![UVT { u, v, t }]
+        [UVT { u, v, t }].into()
     }
 
     pub(crate) fn bounding_box(&self) -> BoundingBox {
@@ -87,11 +158,58 @@ impl Triangle {
     }
 }
 
-#[derive(Clone, Copy)]
-pub(crate) struct UVT {
-    pub(crate) t: f64,
-    pub(crate) u: f64,
-    pub(crate) v: f64,
+/// The corners and normals of triangles are kept as tuples, like the rest of the geometry
+/// of meshes, and only become vectors where they meet rays and shading.
+fn vector(tuple: Tuple) -> Vec3 {
+    Vec3::new(tuple.x, tuple.y, tuple.z)
+}
+
+/// A hit `t` along a ray, at `u` and `v` on the surface, the barycentric coordinates of
+/// the hit for triangles.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+pub struct UVT {
+    pub t: f64,
+    pub u: f64,
+    pub v: f64,
+}
+
+impl Primitive for Triangle {
+    fn local_intersect(&self, ray: Ray) -> Hits<TorUVT> {
+        Triangle::local_intersect(self, ray).map(|uvt| TorUVT::UVT { uvt })
+    }
+
+    /// Smooth triangles blend the normals of their vertices by where the intersection is.
+    fn local_normal_at(&self, intersection: Intersection, _: Point3) -> Vec3 {
+        let uvt = intersection.uvt().unwrap();
+
+        Triangle::local_normal_at(self, &uvt)
+    }
+
+    fn local_geometric_normal_at(&self, _: Intersection, _: Point3) -> Vec3 {
+        self.normal()
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        Triangle::bounding_box(self)
+    }
+
+    fn area(&self) -> Option<f64> {
+        Some(Triangle::area(self))
+    }
+
+    fn sample_surface(&self, rng: &mut Rng) -> Option<(Point3, Vec3)> {
+        Some(Triangle::sample_surface(self, rng))
+    }
+
+    /// Itself, flat, since meshes don't keep normals.
+    fn tessellate(&self, _resolution: usize) -> Mesh {
+        Mesh::new(vec![Triangle::new(self.p1, self.p2, self.p3)])
+    }
+
+    fn same_as(&self, other: &dyn Primitive) -> bool {
+        other.downcast_ref() == Some(self)
+    }
 }
 
 #[cfg(test)]
@@ -99,15 +217,17 @@ mod tests {
     use crate::{
         intersection::{Intersection, TorUVT},
         misc::approx_equal,
-        shape::{Object, Shape, SimpleObject},
+        shape::{tests::chi_square, Object, Shape, SimpleObject},
     };
 
     use super::*;
 
     impl Triangle {
         pub(crate) fn normals(&self) -> (Tuple, Tuple, Tuple) {
+            let normal = Tuple::from(self.normal());
+
             match self.kind {
-                TriangleKind::Flat => (self.normal(), self.normal(), self.normal()),
+                TriangleKind::Flat => (normal, normal, normal),
                 TriangleKind::Smooth { n1, n2, n3 } => (n1, n2, n3),
             }
         }
@@ -136,7 +256,7 @@ mod tests {
         assert_eq!(t.p3, p3);
         assert_eq!(t.edge1(), Tuple::vector(-1., -1., 0.));
         assert_eq!(t.edge2(), Tuple::vector(1., -1., 0.));
-        assert_eq!(t.normal(), Tuple::vector(0., 0., -1.));
+        assert_eq!(t.normal(), Vec3::new(0., 0., -1.));
     }
 
     #[test]
@@ -177,7 +297,7 @@ mod tests {
             Tuple::point(-1., 0., 0.),
             Tuple::point(1., 0., 0.),
         );
-        let r = Ray::new(Tuple::point(0., -1., -2.), Tuple::vector(0., 1., 0.));
+        let r = Ray::new(Point3::new(0., -1., -2.), Vec3::new(0., 1., 0.));
         let xs = t.local_intersect(r);
 
         assert!(xs.is_empty());
@@ -190,7 +310,7 @@ mod tests {
             Tuple::point(-1., 0., 0.),
             Tuple::point(1., 0., 0.),
         );
-        let r = Ray::new(Tuple::point(1., 1., -2.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(1., 1., -2.), Vec3::new(0., 0., 1.));
         let xs = t.local_intersect(r);
 
         assert!(xs.is_empty());
@@ -203,7 +323,7 @@ mod tests {
             Tuple::point(-1., 0., 0.),
             Tuple::point(1., 0., 0.),
         );
-        let r = Ray::new(Tuple::point(-1., 1., -2.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(-1., 1., -2.), Vec3::new(0., 0., 1.));
         let xs = t.local_intersect(r);
 
         assert!(xs.is_empty());
@@ -216,7 +336,7 @@ mod tests {
             Tuple::point(-1., 0., 0.),
             Tuple::point(1., 0., 0.),
         );
-        let r = Ray::new(Tuple::point(0., -1., -2.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., -1., -2.), Vec3::new(0., 0., 1.));
         let xs = t.local_intersect(r);
         assert!(xs.is_empty());
     }
@@ -228,7 +348,21 @@ mod tests {
             Tuple::point(-1., 0., 0.),
             Tuple::point(1., 0., 0.),
         );
-        let r = Ray::new(Tuple::point(0., 0.5, -2.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(0., 0.5, -2.), Vec3::new(0., 0., 1.));
+        let xs = t.local_intersect(r);
+
+        assert_eq!(xs.len(), 1);
+        assert!(approx_equal(xs[0].t, 2.));
+    }
+
+    #[test]
+    fn a_ray_strikes_a_tiny_triangle() {
+        let t = Triangle::new(
+            Tuple::point(0., 1e-5, 0.),
+            Tuple::point(-1e-5, 0., 0.),
+            Tuple::point(1e-5, 0., 0.),
+        );
+        let r = Ray::new(Point3::new(0., 0.5e-5, -2.), Vec3::new(0., 0., 1.));
         let xs = t.local_intersect(r);
 
         assert_eq!(xs.len(), 1);
@@ -245,7 +379,7 @@ mod tests {
         let tri = test_smooth_tri();
         let n = tri.local_normal_at(&i);
 
-        assert_eq!(n, Tuple::vector(-0.5547, 0.83205, 0.));
+        assert_eq!(n, Vec3::new(-0.5547, 0.83205, 0.));
     }
 
     #[test]
@@ -255,14 +389,76 @@ mod tests {
             u: 0.45,
             v: 0.25,
         };
-        let r = Ray::new(Tuple::point(-0.2, 0.3, -2.), Tuple::vector(0., 0., 1.));
+        let r = Ray::new(Point3::new(-0.2, 0.3, -2.), Vec3::new(0., 0., 1.));
         let tri = test_smooth_tri();
-        let shape = Shape::Triangle(tri);
+        let shape = Shape::primitive(tri);
         let object = Object::new(shape);
         let shape = SimpleObject::from_object(&object).unwrap();
         let i = Intersection::new(&TorUVT::UVT { uvt }, shape);
         let comps = i.prepare_computations(r, &[i]);
 
-        assert_eq!(comps.normal_vector, Tuple::vector(-0.5547, 0.83205, 0.));
+        assert_eq!(comps.normal_vector, Vec3::new(-0.5547, 0.83205, 0.));
+    }
+
+    #[test]
+    fn smooth_triangles_offset_points_along_their_flat_normal() {
+        let uvt = UVT {
+            t: 2.,
+            u: 0.45,
+            v: 0.25,
+        };
+        let r = Ray::new(Point3::new(-0.2, 0.3, -2.), Vec3::new(0., 0., 1.));
+        let object = Object::primitive(test_smooth_tri());
+        let shape = SimpleObject::from_object(&object).unwrap();
+        let i = Intersection::new(&TorUVT::UVT { uvt }, shape);
+        let comps = i.prepare_computations(r, &[i]);
+
+        assert_eq!(comps.geometric_normal, Vec3::new(0., 0., -1.));
+        assert_eq!(comps.normal_vector, Vec3::new(-0.5547, 0.83205, 0.));
+        assert!(comps.over_point.z < -EPSILON / 2.);
+        assert!(approx_equal(comps.over_point.y, 0.3));
+        assert!(comps.under_point.z > EPSILON / 2.);
+    }
+
+    #[test]
+    fn texture_coordinates_are_blended_across_the_triangle() {
+        let t = test_smooth_tri().with_texture([(0., 0.), (1., 0.), (0., 1.)]);
+        let uvt = UVT {
+            u: 0.45,
+            v: 0.25,
+            t: 1.,
+        };
+
+        assert_eq!(t.texture_at(&uvt), Some((0.45, 0.25)));
+        assert_eq!(t.flipped().texture_at(&uvt), Some((0.25, 0.45)));
+        assert_eq!(test_smooth_tri().texture_at(&uvt), None);
+    }
+
+    #[test]
+    fn points_are_sampled_uniformly_on_a_triangle() {
+        let triangle = Triangle::new(
+            Tuple::point(0., 2., 0.),
+            Tuple::point(-1., 0., 1.),
+            Tuple::point(3., 0., 0.),
+        );
+        let mut rng = Rng::new(4);
+        // The corners and the middle of the triangle split at the midpoints of its sides.
+        let mut counts = [0; 4];
+
+        for _ in 0..20_000 {
+            let (point, normal) = triangle.sample_surface(&mut rng);
+            let ray = Ray::new(point + normal, -normal);
+            let uvt = triangle.local_intersect(ray)[0];
+            let weights = [1. - uvt.u - uvt.v, uvt.u, uvt.v];
+
+            assert!(approx_equal(uvt.t, 1.));
+            assert_eq!(normal, triangle.normal());
+            let corner = weights.iter().position(|&weight| weight > 0.5);
+            counts[corner.unwrap_or(3)] += 1;
+        }
+
+        assert!(approx_equal(triangle.area(), 77_f64.sqrt() / 2.));
+        // The critical value for 3 degrees of freedom at p = 0.001.
+        assert!(chi_square(&counts, &[0.25; 4]) < 16.27);
     }
 }
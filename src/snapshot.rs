@@ -0,0 +1,1114 @@
+//! Binary snapshots of built worlds, to load baked scenes without parsing and building them again.
+//!
+//! Everything a world is made of goes in the snapshot as it is, so meshes come back with the
+//! hierarchies they were divided into, without dividing them again.
+
+use crate::error::{RayTracerError, Result};
+
+use crate::{
+    bytes::{invalid_data, Reader},
+    canvas::Canvas,
+    color::Color,
+    cube_map::{CubeMap, Face},
+    environment::Environment,
+    fog::Fog,
+    light::{Dome, Fixture, Light, LightKind, Sky, Spot},
+    material::{Bump, Material, Portal},
+    math::{matrix4::Matrix4, point3::Point3, tuple::Tuple, vec3::Vec3},
+    pattern::{
+        Fill, Gradient, GradientBlend, GradientShape, ImageFilter, Pattern, PatternMotion,
+        PatternSpace, PatternType, UvMapping,
+    },
+    shape::{
+        cone::Cone,
+        csg::{Csg, CsgMaterial, CsgOp},
+        cube::Cube,
+        curve::Curve,
+        cylinder::Cylinder,
+        lod::Lod,
+        plane::{Disc, Plane, Quad},
+        point_cloud::{CloudPoint, PointCloud},
+        primitive::Primitive,
+        sphere::Sphere,
+        triangle::{Triangle, TriangleKind},
+        Object, Shape, ShapeOrGroup,
+    },
+    water::Waves,
+    world::World,
+};
+
+const MAGIC: &[u8; 8] = b"RTSNAP\x00\x0c";
+/// How deep objects can nest in groups, CSGs and levels of detail, deeper ones are
+/// taken as corrupt rather than read by recursing that deep.
+const MAX_DEPTH: usize = 48;
+
+const FACES: [Face; 6] = [
+    Face::Right,
+    Face::Left,
+    Face::Up,
+    Face::Down,
+    Face::Front,
+    Face::Back,
+];
+
+/// Writes everything in the world, objects, lights and environment, to bytes `load` reads back.
+/// Fails with `Unsupported` on shapes defined outside the crate, see `Primitive`.
+pub fn save(world: &World) -> Result<Vec<u8>> {
+    let mut writer = Writer(MAGIC.to_vec());
+
+    writer.u32(world.objects.len());
+    for object in &world.objects {
+        writer.object(object)?;
+    }
+    writer.u32(world.lights.len());
+    for light in &world.lights {
+        writer.light(light);
+    }
+    writer.u32(world.attached_lights.len());
+    for attached in &world.attached_lights {
+        writer.u32(attached.object);
+        writer.light(&attached.light);
+    }
+    match &world.environment {
+        None => writer.u8(0),
+        Some(Environment::CubeMap(cube_map)) => {
+            writer.u8(1);
+            for face in FACES {
+                writer.canvas(cube_map.face(face));
+            }
+        }
+        Some(Environment::Gradient {
+            zenith,
+            horizon,
+            ground,
+        }) => {
+            writer.u8(2);
+            writer.color(*zenith);
+            writer.color(*horizon);
+            writer.color(*ground);
+        }
+    }
+    writer.optional(world.fog, |writer, fog| {
+        writer.f64(fog.density);
+        writer.color(fog.color);
+        writer.u32(fog.steps);
+    });
+
+    Ok(writer.0)
+}
+
+/// Reads a world written by `save`, failing with `InvalidData` on anything else.
+pub fn load(bytes: &[u8]) -> Result<World> {
+    let mut reader = Reader::new(bytes);
+
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(invalid_data("not a world snapshot"));
+    }
+
+    let mut world = World::new();
+    // Every object takes at least a transform.
+    for _ in 0..reader.count(16 * 8)? {
+        world.add_object(read_object(&mut reader, MAX_DEPTH)?);
+    }
+    // And a position, an intensity and whether it has a fixture and which kind it is.
+    for _ in 0..reader.count(7 * 8 + 2)? {
+        world.add_light(read_light(&mut reader)?);
+    }
+    // The same, after the index of the object the light is attached to.
+    for _ in 0..reader.count(4 + 7 * 8 + 2)? {
+        let object = reader.u32()? as usize;
+        let light = read_light(&mut reader)?;
+        if object >= world.objects.len() {
+            return Err(invalid_data("light attached to a missing object"));
+        }
+        world.attach_light(object, light);
+    }
+    world.environment = match reader.u8()? {
+        0 => None,
+        1 => {
+            let mut faces = vec![];
+            for _ in FACES {
+                faces.push(read_canvas(&mut reader)?);
+            }
+            let faces: [Canvas; 6] = faces.try_into().ok().expect("there are six faces");
+
+            Some(CubeMap::new(faces).into())
+        }
+        2 => Some(Environment::Gradient {
+            zenith: read_color(&mut reader)?,
+            horizon: read_color(&mut reader)?,
+            ground: read_color(&mut reader)?,
+        }),
+        _ => return Err(invalid_data("unknown environment")),
+    };
+    world.fog = read_optional(&mut reader, |reader| {
+        Ok(Fog {
+            density: reader.f64()?,
+            color: read_color(reader)?,
+            steps: reader.u32()? as usize,
+        })
+    })?;
+
+    if reader.remaining() != 0 {
+        return Err(invalid_data("trailing bytes after the world"));
+    }
+
+    Ok(world)
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn u32(&mut self, value: usize) {
+        self.0.extend_from_slice(&(value as u32).to_le_bytes());
+    }
+
+    fn f64(&mut self, value: f64) {
+        self.0.extend_from_slice(&value.to_bits().to_le_bytes());
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    fn tuple(&mut self, tuple: Tuple) {
+        for value in [tuple.x, tuple.y, tuple.z, tuple.w] {
+            self.f64(value);
+        }
+    }
+
+    fn color(&mut self, color: Color) {
+        for value in [color.red, color.green, color.blue] {
+            self.f64(value);
+        }
+    }
+
+    fn matrix(&mut self, matrix: Matrix4) {
+        for row in matrix.rows() {
+            for value in row {
+                self.f64(*value);
+            }
+        }
+    }
+
+    fn object(&mut self, object: &Object) -> Result<()> {
+        self.matrix(object.transform());
+        self.optional(object.name.as_deref(), |writer, name| {
+            writer.u32(name.len());
+            writer.0.extend_from_slice(name.as_bytes());
+        });
+
+        match object.shape() {
+            ShapeOrGroup::Group(children) => {
+                self.u8(0);
+                self.u32(children.len());
+                for child in children {
+                    self.object(child)?;
+                }
+            }
+            ShapeOrGroup::Shape { material, shape } => {
+                self.u8(1);
+                self.material(material);
+                self.shape(shape)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn shape(&mut self, shape: &Shape) -> Result<()> {
+        match shape {
+            Shape::Primitive(primitive) => self.primitive(primitive.as_ref())?,
+            Shape::Csg(csg) => {
+                self.u8(6);
+                self.u8(match csg.op {
+                    CsgOp::Union => 0,
+                    CsgOp::Intersection => 1,
+                    CsgOp::Difference => 2,
+                });
+                self.object(&csg.left)?;
+                self.object(&csg.right)?;
+                self.csg_material(&csg.left_material);
+                self.csg_material(&csg.right_material);
+            }
+            Shape::PointCloud(cloud) => {
+                self.u8(7);
+                self.u32(cloud.points().len());
+                for point in cloud.points() {
+                    self.tuple(point.position);
+                    self.f64(point.radius);
+                    self.optional(point.color, Self::color);
+                }
+            }
+            Shape::Lod(lod) => {
+                self.u8(9);
+                self.u32(lod.levels.len());
+                for (distance, level) in &lod.levels {
+                    self.f64(*distance);
+                    self.object(level)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn primitive(&mut self, primitive: &dyn Primitive) -> Result<()> {
+        if primitive.downcast_ref::<Sphere>().is_some() {
+            self.u8(0);
+        } else if primitive.downcast_ref::<Plane>().is_some() {
+            self.u8(1);
+        } else if primitive.downcast_ref::<Cube>().is_some() {
+            self.u8(2);
+        } else if let Some(cylinder) = primitive.downcast_ref::<Cylinder>() {
+            self.u8(3);
+            self.f64(cylinder.minimum());
+            self.f64(cylinder.maximum());
+            self.bool(cylinder.closed());
+            self.f64(cylinder.radius_x());
+            self.f64(cylinder.radius_z());
+        } else if let Some(cone) = primitive.downcast_ref::<Cone>() {
+            self.u8(4);
+            self.f64(cone.minimum());
+            self.f64(cone.maximum());
+            self.bool(cone.closed());
+            self.f64(cone.radius_x());
+            self.f64(cone.radius_z());
+        } else if let Some(triangle) = primitive.downcast_ref::<Triangle>() {
+            self.u8(5);
+            for point in [triangle.p1, triangle.p2, triangle.p3] {
+                self.tuple(point);
+            }
+            match triangle.kind {
+                TriangleKind::Flat => self.u8(0),
+                TriangleKind::Smooth { n1, n2, n3 } => {
+                    self.u8(1);
+                    for normal in [n1, n2, n3] {
+                        self.tuple(normal);
+                    }
+                }
+            }
+            self.optional(triangle.texture, |writer, texture| {
+                for (u, v) in texture {
+                    writer.f64(u);
+                    writer.f64(v);
+                }
+            });
+        } else if let Some(curve) = primitive.downcast_ref::<Curve>() {
+            self.u8(8);
+            for point in curve.control_points() {
+                self.tuple(point);
+            }
+            self.f64(curve.radius);
+        } else if let Some(Disc { radius }) = primitive.downcast_ref() {
+            self.u8(10);
+            self.f64(*radius);
+        } else if let Some(Quad { width, depth }) = primitive.downcast_ref() {
+            self.u8(11);
+            self.f64(*width);
+            self.f64(*depth);
+        } else {
+            return Err(RayTracerError::Unsupported(
+                "snapshots can't hold shapes defined outside the crate".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn csg_material(&mut self, material: &CsgMaterial) {
+        match material {
+            CsgMaterial::Own => self.u8(0),
+            CsgMaterial::Left => self.u8(1),
+            CsgMaterial::Right => self.u8(2),
+            CsgMaterial::Custom(material) => {
+                self.u8(3);
+                self.material(material);
+            }
+        }
+    }
+
+    fn material(&mut self, material: &Material) {
+        self.color(material.color);
+        for value in [
+            material.ambient,
+            material.diffuse,
+            material.specular,
+            material.shininess,
+            material.reflective,
+            material.transparency,
+            material.refractive_index,
+        ] {
+            self.f64(value);
+        }
+        self.color(material.absorption);
+        self.bool(material.casts_shadows);
+        self.bool(material.shadow_catcher);
+        self.optional(material.pattern, Self::pattern);
+        self.optional(material.waves, |writer, waves| {
+            for value in [
+                waves.amplitude,
+                waves.wavelength,
+                waves.speed,
+                waves.direction,
+            ] {
+                writer.f64(value);
+            }
+            writer.u32(waves.octaves as usize);
+        });
+        self.optional(material.portal, |writer, portal| writer.matrix(portal.exit));
+        self.optional(material.bump, |writer, bump| {
+            writer.pattern(bump.pattern);
+            writer.f64(bump.strength);
+        });
+        self.optional(material.ramp, Self::pattern);
+    }
+
+    fn pattern(&mut self, pattern: Pattern) {
+        self.matrix(pattern.transform);
+        self.u8(match pattern.space {
+            PatternSpace::Object => 0,
+            PatternSpace::World => 1,
+            PatternSpace::GroupRoot => 2,
+        });
+        self.tuple(pattern.motion.velocity);
+        self.f64(pattern.motion.angular_velocity);
+
+        let (tag, a, b) = match pattern.pattern_type {
+            PatternType::Image(image) => {
+                self.u8(4);
+                self.canvas(image.canvas);
+                self.u8(match image.mapping {
+                    UvMapping::Spherical => 0,
+                    UvMapping::Planar => 1,
+                    UvMapping::Cylindrical => 2,
+                });
+                self.u8(match image.filter {
+                    ImageFilter::Nearest => 0,
+                    ImageFilter::Bilinear => 1,
+                });
+                return;
+            }
+            PatternType::Stops(pattern) => {
+                self.u8(5);
+                self.u32(pattern.stops.len());
+                for &(position, color) in pattern.stops {
+                    self.f64(position);
+                    self.color(color);
+                }
+                self.u8(match pattern.blend {
+                    GradientBlend::Linear => 0,
+                    GradientBlend::Smooth => 1,
+                    GradientBlend::Steps => 2,
+                });
+                self.u8(match pattern.shape {
+                    GradientShape::Axial => 0,
+                    GradientShape::Radial => 1,
+                    GradientShape::Spherical => 2,
+                });
+                self.bool(pattern.repeating);
+                return;
+            }
+            PatternType::Blend(pattern) => (6, pattern.a, pattern.b),
+            PatternType::Striped(pattern) => (0, pattern.a, pattern.b),
+            PatternType::Gradient(pattern) => (1, pattern.a, pattern.b),
+            PatternType::Ring(pattern) => (2, pattern.a, pattern.b),
+            PatternType::Checkered(pattern) => (3, pattern.a, pattern.b),
+            #[cfg(test)]
+            PatternType::TestPattern => panic!("test patterns can't be saved"),
+        };
+        self.u8(tag);
+        self.fill(a);
+        self.fill(b);
+    }
+
+    fn fill(&mut self, fill: Fill) {
+        match fill {
+            Fill::Color(color) => {
+                self.u8(0);
+                self.color(color);
+            }
+            Fill::Pattern(pattern) => {
+                self.u8(1);
+                self.pattern(*pattern);
+            }
+        }
+    }
+
+    fn light(&mut self, light: &Light) {
+        self.tuple(light.position.into());
+        self.color(light.intensity);
+        self.optional(light.fixture, |writer, fixture| match fixture {
+            Fixture::Sphere { radius } => {
+                writer.u8(0);
+                writer.f64(radius);
+            }
+            Fixture::Quad { size } => {
+                writer.u8(1);
+                writer.f64(size);
+            }
+        });
+        match light.kind {
+            LightKind::Point => self.u8(0),
+            LightKind::Spot(spot) => {
+                self.u8(2);
+                self.tuple(spot.direction.into());
+                self.f64(spot.cone_angle);
+                self.f64(spot.fade_angle);
+            }
+            LightKind::Dome(dome) => {
+                self.u8(1);
+                self.u8(match dome.sky {
+                    Sky::Uniform => 0,
+                    Sky::Environment => 1,
+                });
+                self.u32(dome.samples);
+            }
+        }
+    }
+
+    fn canvas(&mut self, canvas: &Canvas) {
+        self.u32(canvas.width());
+        self.u32(canvas.height());
+        for y in 0..canvas.height() {
+            for x in 0..canvas.width() {
+                self.color(canvas.pixel_at(x as i32, y as i32));
+            }
+        }
+    }
+
+    fn optional<T>(&mut self, value: Option<T>, write: impl FnOnce(&mut Self, T)) {
+        match value {
+            None => self.u8(0),
+            Some(value) => {
+                self.u8(1);
+                write(self, value);
+            }
+        }
+    }
+}
+
+fn read_bool(reader: &mut Reader) -> Result<bool> {
+    match reader.u8()? {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(invalid_data("booleans are 0 or 1")),
+    }
+}
+
+fn read_tuple(reader: &mut Reader) -> Result<Tuple> {
+    Ok(Tuple::new(
+        reader.f64()?,
+        reader.f64()?,
+        reader.f64()?,
+        reader.f64()?,
+    ))
+}
+
+fn read_point(reader: &mut Reader) -> Result<Point3> {
+    Point3::try_from(read_tuple(reader)?).map_err(|_| invalid_data("expected a point"))
+}
+
+fn read_vector(reader: &mut Reader) -> Result<Vec3> {
+    Vec3::try_from(read_tuple(reader)?).map_err(|_| invalid_data("expected a vector"))
+}
+
+fn read_color(reader: &mut Reader) -> Result<Color> {
+    Ok(Color::new(reader.f64()?, reader.f64()?, reader.f64()?))
+}
+
+fn read_matrix(reader: &mut Reader) -> Result<Matrix4> {
+    let mut rows = [[0.; 4]; 4];
+    for row in rows.iter_mut() {
+        for value in row.iter_mut() {
+            *value = reader.f64()?;
+        }
+    }
+
+    Ok(Matrix4::from_rows(rows))
+}
+
+fn read_invertible(reader: &mut Reader) -> Result<Matrix4> {
+    let matrix = read_matrix(reader)?;
+
+    match matrix.inverse() {
+        Some(_) => Ok(matrix),
+        None => Err(invalid_data("transforms must be invertible")),
+    }
+}
+
+fn read_optional<'a, T>(
+    reader: &mut Reader<'a>,
+    read: impl FnOnce(&mut Reader<'a>) -> Result<T>,
+) -> Result<Option<T>> {
+    match reader.u8()? {
+        0 => Ok(None),
+        1 => Ok(Some(read(reader)?)),
+        _ => Err(invalid_data("unknown optional value")),
+    }
+}
+
+/// Reads an object nesting at most `depth` objects deep, counting itself.
+fn read_object(reader: &mut Reader, depth: usize) -> Result<Object> {
+    let depth = depth
+        .checked_sub(1)
+        .ok_or_else(|| invalid_data("objects nested too deeply"))?;
+    let transform = read_invertible(reader)?;
+    let name = read_optional(reader, |reader| {
+        let length = reader.count(1)?;
+
+        String::from_utf8(reader.take(length)?.to_vec())
+            .map_err(|_| invalid_data("object names must be UTF-8"))
+    })?;
+
+    let shape = match reader.u8()? {
+        0 => {
+            let children = (0..reader.count(16 * 8)?)
+                .map(|_| read_object(reader, depth))
+                .collect::<Result<_>>()?;
+
+            ShapeOrGroup::Group(children)
+        }
+        1 => ShapeOrGroup::Shape {
+            material: read_material(reader)?,
+            shape: read_shape(reader, depth)?,
+        },
+        _ => return Err(invalid_data("unknown object")),
+    };
+    let mut object = Object::from_parts(transform, shape);
+    object.name = name;
+
+    Ok(object)
+}
+
+fn read_size(reader: &mut Reader) -> Result<f64> {
+    let size = reader.f64()?;
+
+    if size > 0. && size.is_finite() {
+        Ok(size)
+    } else {
+        Err(invalid_data("sizes must be positive"))
+    }
+}
+
+/// Reads a shape whose objects nest at most `depth` objects deep.
+fn read_shape(reader: &mut Reader, depth: usize) -> Result<Shape> {
+    // The bigger shapes are read by functions of their own, keeping this one's stack frame
+    // small for the objects nested through it.
+    Ok(match reader.u8()? {
+        0 => Shape::primitive(Sphere {}),
+        1 => Shape::primitive(Plane {}),
+        2 => Shape::primitive(Cube),
+        3 => Shape::primitive(
+            Cylinder::truncated(reader.f64()?, reader.f64()?, read_bool(reader)?)
+                .map_err(|_| invalid_data("invalid cylinder bounds"))?
+                .with_radii(reader.f64()?, reader.f64()?)
+                .map_err(|_| invalid_data("invalid cylinder radii"))?,
+        ),
+        4 => Shape::primitive(
+            Cone::truncated(reader.f64()?, reader.f64()?, read_bool(reader)?)
+                .map_err(|_| invalid_data("invalid cone bounds"))?
+                .with_radii(reader.f64()?, reader.f64()?)
+                .map_err(|_| invalid_data("invalid cone radii"))?,
+        ),
+        5 => Shape::primitive(read_triangle(reader)?),
+        6 => Shape::Csg(read_csg(reader, depth)?),
+        7 => Shape::PointCloud(read_point_cloud(reader)?),
+        8 => {
+            let (p0, p1, p2, p3) = (
+                read_tuple(reader)?,
+                read_tuple(reader)?,
+                read_tuple(reader)?,
+                read_tuple(reader)?,
+            );
+
+            Shape::primitive(Curve::bezier(p0, p1, p2, p3, reader.f64()?))
+        }
+        9 => Shape::Lod(read_lod(reader, depth)?),
+        10 => Shape::primitive(Disc {
+            radius: read_size(reader)?,
+        }),
+        11 => Shape::primitive(Quad {
+            width: read_size(reader)?,
+            depth: read_size(reader)?,
+        }),
+        _ => return Err(invalid_data("unknown shape")),
+    })
+}
+
+fn read_triangle(reader: &mut Reader) -> Result<Triangle> {
+    let (p1, p2, p3) = (
+        read_tuple(reader)?,
+        read_tuple(reader)?,
+        read_tuple(reader)?,
+    );
+
+    let mut triangle = match reader.u8()? {
+        0 => Triangle::new(p1, p2, p3),
+        1 => Triangle::smooth(
+            p1,
+            p2,
+            p3,
+            read_tuple(reader)?,
+            read_tuple(reader)?,
+            read_tuple(reader)?,
+        ),
+        _ => return Err(invalid_data("unknown triangle")),
+    };
+    triangle.texture = read_optional(reader, |reader| {
+        let mut corner = || -> Result<(f64, f64)> { Ok((reader.f64()?, reader.f64()?)) };
+
+        Ok([corner()?, corner()?, corner()?])
+    })?;
+
+    Ok(triangle)
+}
+
+fn read_csg(reader: &mut Reader, depth: usize) -> Result<Csg> {
+    let op = match reader.u8()? {
+        0 => CsgOp::Union,
+        1 => CsgOp::Intersection,
+        2 => CsgOp::Difference,
+        _ => return Err(invalid_data("unknown csg operation")),
+    };
+
+    Ok(Csg {
+        op,
+        left: Box::new(read_object(reader, depth)?),
+        right: Box::new(read_object(reader, depth)?),
+        left_material: read_csg_material(reader)?,
+        right_material: read_csg_material(reader)?,
+    })
+}
+
+fn read_point_cloud(reader: &mut Reader) -> Result<PointCloud> {
+    // Every point takes at least a position, a radius and whether it has a color.
+    let points = (0..reader.count(5 * 8 + 1)?)
+        .map(|_| {
+            Ok(CloudPoint {
+                position: read_tuple(reader)?,
+                radius: reader.f64()?,
+                color: read_optional(reader, read_color)?,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(PointCloud::new(points))
+}
+
+fn read_lod(reader: &mut Reader, depth: usize) -> Result<Lod> {
+    // Every level takes at least its distance and a transform.
+    let mut levels = (0..reader.count(17 * 8)?)
+        .map(|_| Ok((reader.f64()?, read_object(reader, depth)?)))
+        .collect::<Result<Vec<_>>>()?;
+    if levels.is_empty() {
+        return Err(invalid_data("levels of detail need at least a level"));
+    }
+    let (_, detailed) = levels.remove(0);
+
+    Ok(Lod::new(detailed, levels))
+}
+
+fn read_csg_material(reader: &mut Reader) -> Result<CsgMaterial> {
+    Ok(match reader.u8()? {
+        0 => CsgMaterial::Own,
+        1 => CsgMaterial::Left,
+        2 => CsgMaterial::Right,
+        3 => CsgMaterial::Custom(Box::new(read_material(reader)?)),
+        _ => return Err(invalid_data("unknown csg material")),
+    })
+}
+
+fn read_material(reader: &mut Reader) -> Result<Material> {
+    let mut material = Material::new();
+
+    material.color = read_color(reader)?;
+    material.ambient = reader.f64()?;
+    material.diffuse = reader.f64()?;
+    material.specular = reader.f64()?;
+    material.shininess = reader.f64()?;
+    material.reflective = reader.f64()?;
+    material.transparency = reader.f64()?;
+    material.refractive_index = reader.f64()?;
+    material.absorption = read_color(reader)?;
+    material.casts_shadows = read_bool(reader)?;
+    material.shadow_catcher = read_bool(reader)?;
+    material.pattern = read_optional(reader, read_pattern)?;
+    material.waves = read_optional(reader, |reader| {
+        Ok(Waves {
+            amplitude: reader.f64()?,
+            wavelength: reader.f64()?,
+            speed: reader.f64()?,
+            direction: reader.f64()?,
+            octaves: reader.u32()?,
+        })
+    })?;
+    material.portal = read_optional(reader, |reader| {
+        Ok(Portal {
+            exit: read_invertible(reader)?,
+        })
+    })?;
+    material.bump = read_optional(reader, |reader| {
+        Ok(Bump {
+            pattern: read_pattern(reader)?,
+            strength: reader.f64()?,
+        })
+    })?;
+    material.ramp = read_optional(reader, read_pattern)?;
+
+    Ok(material)
+}
+
+fn read_pattern(reader: &mut Reader) -> Result<Pattern> {
+    let transform = read_invertible(reader)?;
+    let space = match reader.u8()? {
+        0 => PatternSpace::Object,
+        1 => PatternSpace::World,
+        2 => PatternSpace::GroupRoot,
+        _ => return Err(invalid_data("unknown pattern space")),
+    };
+    let motion = PatternMotion {
+        velocity: read_tuple(reader)?,
+        angular_velocity: reader.f64()?,
+    };
+    let mut pattern = match reader.u8()? {
+        4 => return read_image_pattern(reader, transform, space, motion),
+        5 => Pattern::gradient_with_stops(read_gradient(reader)?),
+        tag @ (0..=3 | 6) => {
+            let (a, b) = (read_fill(reader)?, read_fill(reader)?);
+
+            match tag {
+                0 => Pattern::striped(a, b),
+                1 => Pattern::gradient(a, b),
+                2 => Pattern::ring(a, b),
+                3 => Pattern::checkered(a, b),
+                _ => Pattern::blend(a, b),
+            }
+        }
+        _ => return Err(invalid_data("unknown pattern")),
+    };
+
+    pattern.transform = transform;
+    pattern.space = space;
+    pattern.motion = motion;
+
+    Ok(pattern)
+}
+
+fn read_fill(reader: &mut Reader) -> Result<Fill> {
+    match reader.u8()? {
+        0 => Ok(Fill::Color(read_color(reader)?)),
+        1 => Ok(read_pattern(reader)?.into()),
+        _ => Err(invalid_data("unknown fill")),
+    }
+}
+
+fn read_gradient(reader: &mut Reader) -> Result<Gradient> {
+    let count = reader.u32()? as usize;
+    // Every stop takes four numbers.
+    if count == 0 || count > reader.remaining() / (4 * 8) {
+        return Err(invalid_data("gradients need at least a stop"));
+    }
+    let stops = (0..count)
+        .map(|_| Ok((reader.f64()?, read_color(reader)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let blend = match reader.u8()? {
+        0 => GradientBlend::Linear,
+        1 => GradientBlend::Smooth,
+        2 => GradientBlend::Steps,
+        _ => return Err(invalid_data("unknown gradient blend")),
+    };
+    let shape = match reader.u8()? {
+        0 => GradientShape::Axial,
+        1 => GradientShape::Radial,
+        2 => GradientShape::Spherical,
+        _ => return Err(invalid_data("unknown gradient shape")),
+    };
+
+    Ok(Gradient {
+        stops,
+        blend,
+        shape,
+        repeating: read_bool(reader)?,
+    })
+}
+
+fn read_image_pattern(
+    reader: &mut Reader,
+    transform: Matrix4,
+    space: PatternSpace,
+    motion: PatternMotion,
+) -> Result<Pattern> {
+    let canvas = read_canvas(reader)?;
+    let mapping = match reader.u8()? {
+        0 => UvMapping::Spherical,
+        1 => UvMapping::Planar,
+        2 => UvMapping::Cylindrical,
+        _ => return Err(invalid_data("unknown uv mapping")),
+    };
+    let filter = match reader.u8()? {
+        0 => ImageFilter::Nearest,
+        1 => ImageFilter::Bilinear,
+        _ => return Err(invalid_data("unknown image filter")),
+    };
+    if canvas.width() == 0 || canvas.height() == 0 {
+        return Err(invalid_data("images need at least a pixel"));
+    }
+
+    let mut pattern = Pattern::image(canvas, mapping, filter);
+    pattern.transform = transform;
+    pattern.space = space;
+    pattern.motion = motion;
+
+    Ok(pattern)
+}
+
+fn read_light(reader: &mut Reader) -> Result<Light> {
+    let mut light = Light::point_light(read_point(reader)?, read_color(reader)?);
+
+    light.fixture = read_optional(reader, |reader| {
+        Ok(match reader.u8()? {
+            0 => Fixture::Sphere {
+                radius: reader.f64()?,
+            },
+            1 => Fixture::Quad {
+                size: reader.f64()?,
+            },
+            _ => return Err(invalid_data("unknown fixture")),
+        })
+    })?;
+    light.kind = match reader.u8()? {
+        0 => LightKind::Point,
+        1 => {
+            let sky = match reader.u8()? {
+                0 => Sky::Uniform,
+                1 => Sky::Environment,
+                _ => return Err(invalid_data("unknown sky")),
+            };
+
+            LightKind::Dome(Dome {
+                sky,
+                samples: reader.u32()? as usize,
+            })
+        }
+        2 => LightKind::Spot(Spot {
+            direction: read_vector(reader)?,
+            cone_angle: reader.f64()?,
+            fade_angle: reader.f64()?,
+        }),
+        _ => return Err(invalid_data("unknown light")),
+    };
+
+    Ok(light)
+}
+
+fn read_canvas(reader: &mut Reader) -> Result<Canvas> {
+    let (width, height) = (reader.u32()? as usize, reader.u32()? as usize);
+    // Every pixel takes three numbers.
+    if width.saturating_mul(height) > reader.remaining() / (3 * 8) {
+        return Err(invalid_data("the file is truncated"));
+    }
+
+    let mut canvas = Canvas::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            canvas.write_pixel(x as i32, y as i32, read_color(reader)?);
+        }
+    }
+
+    Ok(canvas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        camera::Camera,
+        math::transformations::view_transform,
+        obj::WavefrontObj,
+        shape::{csg::CsgMaterial, Object},
+    };
+    use std::f64::consts::PI;
+
+    fn render(world: &World) -> String {
+        let mut camera = Camera::new(20, 15, PI / 3.);
+        camera.transform = view_transform(
+            Tuple::point(0., 2., -8.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        );
+
+        camera.render_with_progress(world, |_, _| {}).to_ppm()
+    }
+
+    fn baked_world() -> World {
+        let mut world = World::new();
+        world.add_light(Light::point_light(
+            Point3::new(-10., 10., -10.),
+            Color::white(),
+        ));
+        world.add_light(Light::dome(Sky::Uniform, Color::new(0.2, 0.2, 0.3)));
+        world.add_light(Light::spot_light(
+            Point3::new(0., 6., 0.),
+            Vec3::new(0., -1., 0.),
+            0.3,
+            0.1,
+            Color::new(0.5, 0.4, 0.3),
+        ));
+
+        let mut floor = Object::plane();
+        floor.set_material(Material::with_pattern(Pattern::checkered(
+            Color::white(),
+            Pattern::blend(
+                Pattern::striped(Color::new(0.2, 0.3, 0.4), Color::black()),
+                Color::new(0.5, 0.1, 0.1),
+            ),
+        )));
+        world.add_object(floor);
+
+        let mut lens = Object::difference(Object::sphere(), Object::cube());
+        lens.csg_mut().unwrap().right_material = CsgMaterial::Left;
+        lens.set_transform(Matrix4::translation(-2., 1., 0.));
+        world.add_object(lens);
+
+        let mut globe = Object::sphere();
+        let mut map = Canvas::new(4, 2);
+        map.write_pixel(1, 0, Color::new(0.2, 0.4, 0.9));
+        map.write_pixel(2, 1, Color::new(0.3, 0.8, 0.2));
+        globe.set_material(Material::with_pattern(Pattern::image(
+            map,
+            UvMapping::Spherical,
+            ImageFilter::Bilinear,
+        )));
+        globe.set_transform(Matrix4::translation(0., 1., -2.) * Matrix4::scaling(0.5, 0.5, 0.5));
+        world.add_object(globe);
+
+        let mut post = Object::primitive(Cylinder::truncated(0., 2., true).unwrap());
+        let mut rings = Gradient::new(vec![
+            (0., Color::new(0.6, 0.4, 0.2)),
+            (0.5, Color::new(0.3, 0.2, 0.1)),
+        ]);
+        rings.shape = GradientShape::Radial;
+        rings.blend = GradientBlend::Smooth;
+        rings.repeating = true;
+        let mut wood = Material::with_pattern(Pattern::gradient_with_stops(rings));
+        wood.bump = Some(Bump::new(
+            Pattern::ring(Color::black(), Color::white()),
+            0.05,
+        ));
+        let mut toon = Gradient::new(vec![(0., Color::new(0.3, 0.3, 0.3)), (0.5, Color::white())]);
+        toon.blend = GradientBlend::Steps;
+        wood.ramp = Some(Pattern::gradient_with_stops(toon));
+        post.set_material(wood);
+        post.set_transform(Matrix4::translation(-3., 0., 2.) * Matrix4::scaling(0.5, 1., 0.5));
+        world.add_object(post);
+
+        let mut model = Object::lod(Object::sphere(), vec![(10., Object::cube())]);
+        model.set_transform(Matrix4::translation(3., 0.5, -1.) * Matrix4::scaling(0.5, 0.5, 0.5));
+        world.add_object(model);
+
+        let mut cylinder = Object::primitive(Cylinder::truncated(0., 2., true).unwrap());
+        cylinder.set_transform(Matrix4::translation(2., 0., 1.));
+        world.add_object(cylinder);
+
+        let mut wall = Object::quad(4., 2.);
+        wall.set_transform(Matrix4::translation(0., 1., 3.) * Matrix4::rotation_x(PI / 2.));
+        world.add_object(wall);
+
+        let mut lamp = Object::disc(0.5);
+        lamp.set_transform(Matrix4::translation(1., 3., 0.) * Matrix4::rotation_x(PI));
+        world.add_object(lamp);
+
+        let mut obj = String::from("v 0 0 0\nvt 0 0\nvt 1 0.5\n");
+        for i in 0..8 {
+            obj += &format!(
+                "v {} 1 0\nv {} 1 0\n",
+                i as f64 * 0.5 - 2.,
+                i as f64 * 0.5 - 1.5
+            );
+        }
+        for i in 0..8 {
+            obj += &format!("f 1/1 {}/2 {}/-1\n", 2 * i + 2, 2 * i + 3);
+        }
+        let mut mesh = WavefrontObj::from_file_contents(&obj).unwrap().to_group();
+        mesh.divide(2);
+        mesh.set_transform(Matrix4::translation(0., 1., 2.));
+        world.add_object(mesh);
+        world.attach_light(
+            2,
+            Light::point_light(Point3::new(0., 3., 0.), Color::new(0.3, 0.2, 0.1)),
+        );
+
+        world
+    }
+
+    #[test]
+    fn loaded_worlds_render_like_the_saved_ones() {
+        let world = baked_world();
+        let loaded = load(&save(&world).unwrap()).unwrap();
+
+        // Planes never compare equal, their infinite bounds aren't approximately equal.
+        assert_eq!(loaded.objects[1..], world.objects[1..]);
+        assert_eq!(loaded.attached_lights, world.attached_lights);
+        assert_eq!(render(&loaded), render(&world));
+    }
+
+    #[test]
+    fn snapshots_keep_the_environment() {
+        let mut world = World::new();
+        let mut faces = [0; 6].map(|_| Canvas::new(2, 1));
+        faces[Face::Up as usize].write_pixel(1, 0, Color::new(0.1, 0.2, 0.3));
+        world.environment = Some(CubeMap::new(faces).into());
+
+        let loaded = load(&save(&world).unwrap()).unwrap();
+
+        let Some(Environment::CubeMap(cube_map)) = loaded.environment else {
+            panic!("the environment should be a cube map");
+        };
+        assert_eq!(
+            cube_map.face(Face::Up).pixel_at(1, 0),
+            Color::new(0.1, 0.2, 0.3)
+        );
+
+        world.environment = Some(Environment::sky(Color::new(0., 0., 1.), Color::white()));
+        let loaded = load(&save(&world).unwrap()).unwrap();
+        assert_eq!(
+            loaded.environment.unwrap().color_at(Vec3::new(0., 1., 0.)),
+            Color::new(0., 0., 1.)
+        );
+
+        world.fog = Some(Fog::new(0.2, Color::new(0.5, 0.6, 0.7)));
+        assert_eq!(load(&save(&world).unwrap()).unwrap().fog, world.fog);
+    }
+
+    #[test]
+    fn only_whole_snapshots_are_loaded() {
+        let bytes = save(&baked_world()).unwrap();
+
+        assert!(load(&bytes[..bytes.len() - 1]).is_err());
+        assert!(load(&[bytes.as_slice(), &[0]].concat()).is_err());
+        assert!(load(b"RTSNAP\x00\x02").is_err());
+        assert!(load(&[]).is_err());
+    }
+
+    #[test]
+    fn snapshots_nested_too_deeply_are_rejected() {
+        let nested = |depth: usize| {
+            let mut object = Object::sphere();
+            for _ in 1..depth {
+                object = Object::new(Shape::Csg(Csg::union(object, Object::cube())));
+            }
+            let mut world = World::new();
+            world.add_object(object);
+
+            save(&world).unwrap()
+        };
+
+        assert!(load(&nested(MAX_DEPTH)).is_ok());
+        assert!(load(&nested(MAX_DEPTH + 1)).is_err());
+    }
+}
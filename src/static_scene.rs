@@ -0,0 +1,126 @@
+//! Scenes written as constant tables, known at compile time, instead of built by code at startup.
+//!
+//! Only what can be made in constants goes in them: simple shapes, and transforms made of
+//! translations, scalings and shearings, put together with `Matrix4::multiply`.
+
+use crate::{
+    light::Light,
+    material::Material,
+    math::matrix4::Matrix4,
+    shape::{
+        cone::Cone, cube::Cube, cylinder::Cylinder, plane::Plane, sphere::Sphere, Object, Shape,
+    },
+    world::World,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub enum StaticShape {
+    Sphere,
+    Plane,
+    Cube,
+    Cylinder(Cylinder),
+    Cone(Cone),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct StaticObject {
+    pub shape: StaticShape,
+    pub transform: Matrix4,
+    pub material: Material,
+}
+
+impl StaticObject {
+    pub const fn new(shape: StaticShape) -> Self {
+        Self {
+            shape,
+            transform: Matrix4::identity(),
+            material: Material::new(),
+        }
+    }
+
+    pub const fn with_transform(self, transform: Matrix4) -> Self {
+        Self { transform, ..self }
+    }
+
+    pub const fn with_material(self, material: Material) -> Self {
+        Self { material, ..self }
+    }
+
+    pub fn to_object(&self) -> Object {
+        let mut object = Object::new(match self.shape {
+            StaticShape::Sphere => Shape::primitive(Sphere {}),
+            StaticShape::Plane => Shape::primitive(Plane {}),
+            StaticShape::Cube => Shape::primitive(Cube),
+            StaticShape::Cylinder(cylinder) => Shape::primitive(cylinder),
+            StaticShape::Cone(cone) => Shape::primitive(cone),
+        });
+        object.set_transform(self.transform);
+        object.set_material(self.material);
+
+        object
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct StaticScene {
+    pub objects: &'static [StaticObject],
+    pub lights: &'static [Light],
+}
+
+impl StaticScene {
+    pub fn world(&self) -> World {
+        let mut world = World::new();
+        for object in self.objects {
+            world.add_object(object.to_object());
+        }
+        for light in self.lights {
+            world.add_light(*light);
+        }
+
+        world
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::Color,
+        math::{point3::Point3, vec3::Vec3},
+        misc::approx_equal,
+        ray::Ray,
+    };
+
+    const SCENE: StaticScene = StaticScene {
+        objects: &[
+            StaticObject::new(StaticShape::Plane),
+            StaticObject::new(StaticShape::Sphere)
+                .with_transform(
+                    Matrix4::translation(0., 1., 0.).multiply(Matrix4::scaling(0.5, 0.5, 0.5)),
+                )
+                .with_material(Material {
+                    color: Color::new(0.1, 1., 0.5),
+                    ..Material::new()
+                }),
+        ],
+        lights: &[Light::point_light(
+            Point3::new(-10., 10., -10.),
+            Color::white(),
+        )],
+    };
+
+    #[test]
+    fn worlds_from_constant_tables() {
+        let world = SCENE.world();
+        let r = Ray::new(Point3::new(0., 5., 0.), Vec3::new(0., -1., 0.));
+        let xs = world.intersect(r);
+
+        assert_eq!(world.objects.len(), 2);
+        assert_eq!(
+            world.objects[1].transform(),
+            Matrix4::translation(0., 1., 0.) * Matrix4::scaling(0.5, 0.5, 0.5)
+        );
+        assert!(approx_equal(xs[0].t, 3.5));
+        assert_eq!(xs[0].object.material.color, Color::new(0.1, 1., 0.5));
+    }
+}
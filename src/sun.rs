@@ -0,0 +1,101 @@
+//! Sunlight and a sky to go with it for any time of day, see `World::set_time_of_day`.
+
+use crate::{color::Color, math::vec3::Vec3};
+
+/// Towards the sun at `hour` of solar time (noon being 12) on an equinox,
+/// seen from `latitude` degrees north of the equator. +x is east, +y up and +z north.
+pub fn sun_direction(hour: f64, latitude: f64) -> Vec3 {
+    let hour_angle = (hour - 12.) * 15_f64.to_radians();
+    let latitude = latitude.to_radians();
+
+    Vec3::new(
+        -hour_angle.sin(),
+        latitude.cos() * hour_angle.cos(),
+        -latitude.sin() * hour_angle.cos(),
+    )
+}
+
+/// White high up, redder towards the horizon, and gone once the sun sets.
+pub(crate) fn sunlight(direction: Vec3) -> Color {
+    let elevation = direction.y;
+    if elevation <= 0. {
+        return Color::black();
+    }
+
+    let warmth = (elevation / 0.3).min(1.);
+    let strength = (elevation / 0.05).min(1.);
+
+    mix(
+        Color::new(1., 0.45, 0.15),
+        Color::new(1., 0.97, 0.92),
+        warmth,
+    ) * strength
+}
+
+/// The sky looking towards `direction` with the sun towards `sun`: blue by day,
+/// orange along the horizon around sunrise and sunset, dark at night, and dim below the horizon.
+pub(crate) fn sky_color(direction: Vec3, sun: Vec3) -> Color {
+    let day = ((sun.y + 0.1) / 0.3).clamp(0., 1.);
+    let twilight = (1. - sun.y.abs() / 0.3).clamp(0., 1.);
+
+    let zenith = mix(
+        Color::new(0.01, 0.01, 0.03),
+        Color::new(0.2, 0.4, 0.85),
+        day,
+    );
+    let horizon = mix(
+        Color::new(0.02, 0.02, 0.04),
+        Color::new(0.75, 0.85, 0.95),
+        day,
+    );
+    let horizon = mix(horizon, Color::new(0.95, 0.55, 0.3), twilight * day);
+
+    if direction.y >= 0. {
+        mix(horizon, zenith, direction.y.sqrt())
+    } else {
+        horizon * 0.3
+    }
+}
+
+fn mix(a: Color, b: Color, fraction: f64) -> Color {
+    a * (1. - fraction) + b * fraction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::misc::approx_equal;
+
+    #[test]
+    fn the_sun_rises_in_the_east_and_peaks_at_noon() {
+        assert_eq!(sun_direction(6., 0.), Vec3::new(1., 0., 0.));
+        assert_eq!(sun_direction(12., 0.), Vec3::new(0., 1., 0.));
+        assert_eq!(sun_direction(18., 0.), Vec3::new(-1., 0., 0.));
+
+        // Further north, the sun stays lower and to the south.
+        let noon = sun_direction(12., 45.);
+        assert!(approx_equal(noon.y, 2_f64.sqrt() / 2.));
+        assert!(noon.z < 0.);
+        assert!(sun_direction(0., 45.).y < 0.);
+    }
+
+    #[test]
+    fn sunlight_reddens_towards_the_horizon() {
+        let noon = sunlight(sun_direction(12., 30.));
+        let evening = sunlight(sun_direction(17.5, 30.));
+
+        assert_eq!(sunlight(sun_direction(22., 30.)), Color::black());
+        assert!(noon.blue > 0.9);
+        assert!(evening.blue < evening.red / 2.);
+    }
+
+    #[test]
+    fn the_sky_is_blue_by_day_and_dark_at_night() {
+        let up = Vec3::new(0., 1., 0.);
+        let day = sky_color(up, sun_direction(12., 30.));
+        let night = sky_color(up, sun_direction(0., 30.));
+
+        assert!(day.blue > day.red);
+        assert!(night.blue < 0.05);
+    }
+}
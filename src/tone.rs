@@ -0,0 +1,116 @@
+//! Tone mapping, bringing the light renders add up to, which goes well over 1 around
+//! highlights and lights, into the range 8 bit images can show, applied when exporting
+//! them with `Canvas::tone_mapped`.
+
+use crate::color::Color;
+
+/// How colors over 1 are squeezed into what images can show.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToneOperator {
+    /// Cutting every channel off at 1, which blows highlights out to flat white.
+    #[default]
+    Clamp,
+    /// `c / (1 + c)` on every channel, which never quite reaches white.
+    Reinhard,
+    /// A fit of the filmic curve of the Academy Color Encoding System, with some contrast
+    /// in the darks and highlights rolling off into white.
+    Aces,
+}
+
+/// The names `ToneOperator::named` knows.
+pub const TONE_OPERATORS: [&str; 3] = ["clamp", "reinhard", "aces"];
+
+impl ToneOperator {
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "clamp" => Some(Self::Clamp),
+            "reinhard" => Some(Self::Reinhard),
+            "aces" => Some(Self::Aces),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Clamp => "clamp",
+            Self::Reinhard => "reinhard",
+            Self::Aces => "aces",
+        }
+    }
+
+    fn map(self, value: f64) -> f64 {
+        let value = value.max(0.);
+
+        match self {
+            Self::Clamp => value.min(1.),
+            Self::Reinhard => value / (1. + value),
+            Self::Aces => {
+                (value * (2.51 * value + 0.03) / (value * (2.43 * value + 0.59) + 0.14)).min(1.)
+            }
+        }
+    }
+}
+
+/// Exposure and a tone operator, the default leaving colors from 0 to 1 as they are.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ToneMap {
+    pub operator: ToneOperator,
+    /// In stops: every one doubles the light before it's mapped, negative ones halve it.
+    pub exposure: f64,
+}
+
+impl ToneMap {
+    pub fn new(operator: ToneOperator, exposure: f64) -> Self {
+        Self { operator, exposure }
+    }
+
+    pub fn apply(self, color: Color) -> Color {
+        let color = color * 2_f64.powf(self.exposure);
+
+        Color::new(
+            self.operator.map(color.red),
+            self.operator.map(color.green),
+            self.operator.map(color.blue),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operators_bring_bright_colors_under_one() {
+        let bright = Color::new(0.5, 1., 4.);
+
+        assert_eq!(
+            ToneMap::new(ToneOperator::Clamp, 0.).apply(bright),
+            Color::new(0.5, 1., 1.)
+        );
+        assert_eq!(
+            ToneMap::new(ToneOperator::Reinhard, 0.).apply(bright),
+            Color::new(1. / 3., 0.5, 0.8)
+        );
+        let aces = ToneMap::new(ToneOperator::Aces, 0.).apply(bright);
+        assert!(aces.red < aces.green && aces.green < aces.blue && aces.blue <= 1.);
+        assert_eq!(
+            ToneMap::new(ToneOperator::Aces, 0.).apply(Color::black()),
+            Color::black()
+        );
+    }
+
+    #[test]
+    fn every_stop_of_exposure_doubles_the_light() {
+        let color = Color::new(0.1, 0.2, 0.4);
+
+        assert_eq!(ToneMap::default().apply(color), color);
+        assert_eq!(
+            ToneMap::new(ToneOperator::Clamp, 1.).apply(color),
+            Color::new(0.2, 0.4, 0.8)
+        );
+        assert_eq!(
+            ToneMap::new(ToneOperator::Clamp, -2.).apply(color),
+            Color::new(0.025, 0.05, 0.1)
+        );
+    }
+}
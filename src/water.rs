@@ -0,0 +1,170 @@
+use std::f64::consts::PI;
+
+use crate::{
+    color::Color,
+    material::Material,
+    math::{point3::Point3, vec3::Vec3},
+};
+
+/// Ripples that bend the normals of a horizontal surface without moving it,
+/// made of `octaves` sine waves, each shorter, smaller and turned away from the last.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Waves {
+    /// Height of the biggest wave.
+    pub amplitude: f64,
+    /// Distance between the crests of the biggest wave.
+    pub wavelength: f64,
+    /// How fast the crests travel, in units per unit of time.
+    pub speed: f64,
+    /// Angle around the y axis the biggest wave travels along, 0 being the x axis.
+    pub direction: f64,
+    pub octaves: u32,
+}
+
+// Roughly the golden angle, so that no two octaves travel the same way.
+const OCTAVE_TURN: f64 = 2.4;
+const OCTAVE_AMPLITUDE: f64 = 0.5;
+const OCTAVE_WAVELENGTH: f64 = 0.6;
+
+impl Waves {
+    pub fn new() -> Self {
+        Self {
+            amplitude: 0.02,
+            wavelength: 1.,
+            speed: 0.5,
+            direction: 0.,
+            octaves: 4,
+        }
+    }
+
+    /// The slope of the waves at the point, along x and along z.
+    fn slope(self, point: Point3, time: f64) -> (f64, f64) {
+        let mut amplitude = self.amplitude;
+        let mut wavelength = self.wavelength;
+        let mut direction = self.direction;
+        let (mut dx, mut dz) = (0., 0.);
+
+        for _ in 0..self.octaves {
+            let k = 2. * PI / wavelength;
+            let (sin, cos) = direction.sin_cos();
+            let phase = k * (cos * point.x + sin * point.z - self.speed * time);
+            let slope = amplitude * k * phase.cos();
+
+            dx += slope * cos;
+            dz += slope * sin;
+
+            amplitude *= OCTAVE_AMPLITUDE;
+            wavelength *= OCTAVE_WAVELENGTH;
+            direction += OCTAVE_TURN;
+        }
+
+        (dx, dz)
+    }
+
+    /// Tilts `normal` as if the surface at `point` were rippled at `time`.
+    pub(crate) fn perturb(self, normal: Vec3, point: Point3, time: f64) -> Vec3 {
+        let (dx, dz) = self.slope(point, time);
+
+        (normal - Vec3::new(dx, 0., dz)).normalize()
+    }
+}
+
+impl Default for Waves {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A clear, reflective material that ripples with `waves`.
+pub fn water_material(waves: Waves) -> Material {
+    let mut material = Material::new();
+    material.color = Color::new(0.02, 0.05, 0.08);
+    material.ambient = 0.;
+    material.diffuse = 0.1;
+    material.specular = 1.;
+    material.shininess = 300.;
+    material.reflective = 0.9;
+    material.transparency = 0.9;
+    material.refractive_index = 1.333;
+    material.casts_shadows = false;
+    material.waves = Some(waves);
+
+    material
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{misc::approx_equal, ray::Ray, shape::Object};
+
+    #[test]
+    fn calm_water_does_not_bend_normals() {
+        let waves = Waves {
+            amplitude: 0.,
+            ..Waves::new()
+        };
+        let normal = Vec3::new(0., 1., 0.);
+
+        assert_eq!(waves.perturb(normal, Point3::new(0.3, 0., 1.7), 2.), normal);
+    }
+
+    #[test]
+    fn a_single_wave_tilts_normals_by_its_slope() {
+        let waves = Waves {
+            amplitude: 0.1,
+            wavelength: 2.,
+            speed: 0.,
+            direction: 0.,
+            octaves: 1,
+        };
+        let k = PI;
+        let n = waves.perturb(Vec3::new(0., 1., 0.), Point3::new(0., 0., 5.), 0.);
+
+        assert_eq!(n, Vec3::new(-0.1 * k, 1., 0.).normalize());
+    }
+
+    #[test]
+    fn waves_travel_with_time() {
+        let waves = Waves::new();
+        let normal = Vec3::new(0., 1., 0.);
+        let point = Point3::new(0.25, 0., 0.5);
+
+        let before = waves.perturb(normal, point, 0.);
+        let after = waves.perturb(normal, point, 0.3);
+
+        assert_ne!(before, after);
+        assert!(approx_equal(after.magnitude(), 1.));
+    }
+
+    #[test]
+    fn a_single_wave_repeats_once_it_travels_a_wavelength() {
+        let waves = Waves {
+            octaves: 1,
+            ..Waves::new()
+        };
+        let normal = Vec3::new(0., 1., 0.);
+        let point = Point3::new(0.25, 0., 0.5);
+        let period = waves.wavelength / waves.speed;
+
+        assert_eq!(
+            waves.perturb(normal, point, 0.),
+            waves.perturb(normal, point, period)
+        );
+    }
+
+    #[test]
+    fn the_normals_of_a_water_plane_follow_the_time_of_the_ray() {
+        let water = Object::water(Waves::new());
+        let ray = Ray::new(Point3::new(0.3, 1., 0.2), Vec3::new(0., -1., 0.));
+
+        let normal_at = |time| {
+            let ray = ray.at_time(time);
+            let xs = water.intersect(ray);
+            xs[0].prepare_computations(ray, &xs).normal_vector
+        };
+
+        assert_ne!(normal_at(0.), Vec3::new(0., 1., 0.));
+        assert_ne!(normal_at(0.), normal_at(0.5));
+    }
+}
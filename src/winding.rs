@@ -0,0 +1,179 @@
+//! Making the windings of imported meshes agree, since many files mix clockwise and
+//! counterclockwise triangles, which throws smooth shading off.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::{math::tuple::Tuple, shape::triangle::Triangle};
+
+type VertexKey = [u64; 3];
+
+/// Which triangles to flip for every triangle to go around the edges it shares with its
+/// neighbors the other way from them, as consistently wound meshes do.
+///
+/// The first triangle of each connected piece keeps its winding, unless `outward` is set:
+/// then pieces whose normals (see `Triangle::normal`) point inside are flipped as a whole,
+/// judging by the sign of the volume they enclose.
+pub(crate) fn triangles_to_flip(triangles: &[Triangle], outward: bool) -> Vec<bool> {
+    let mut edges: HashMap<(VertexKey, VertexKey), Vec<(usize, bool)>> = HashMap::new();
+    for (index, triangle) in triangles.iter().enumerate() {
+        for (from, to) in triangle_edges(triangle) {
+            let forward = from < to;
+            let edge = if forward { (from, to) } else { (to, from) };
+
+            edges.entry(edge).or_default().push((index, forward));
+        }
+    }
+
+    let mut flip = vec![false; triangles.len()];
+    let mut visited = vec![false; triangles.len()];
+
+    for start in 0..triangles.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        let mut piece = vec![start];
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(index) = queue.pop_front() {
+            for (from, to) in triangle_edges(&triangles[index]) {
+                let forward = (from < to) != flip[index];
+                let edge = if from < to { (from, to) } else { (to, from) };
+
+                for &(neighbor, neighbor_forward) in &edges[&edge] {
+                    if visited[neighbor] {
+                        continue;
+                    }
+                    visited[neighbor] = true;
+                    // Neighbors wound the same way go along the edge in opposite directions.
+                    flip[neighbor] = neighbor_forward == forward;
+                    piece.push(neighbor);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        if outward {
+            let volume: f64 = piece
+                .iter()
+                .map(|&index| {
+                    let Triangle { p1, p2, p3, .. } = triangles[index];
+                    let volume = (p3 - p1).cross(p2 - p1).dot(p1 - Tuple::point(0., 0., 0.));
+
+                    if flip[index] {
+                        -volume
+                    } else {
+                        volume
+                    }
+                })
+                .sum();
+
+            if volume < 0. {
+                for index in piece {
+                    flip[index] = !flip[index];
+                }
+            }
+        }
+    }
+
+    flip
+}
+
+fn triangle_edges(triangle: &Triangle) -> [(VertexKey, VertexKey); 3] {
+    let [p1, p2, p3] = [triangle.p1, triangle.p2, triangle.p3].map(vertex_key);
+
+    [(p1, p2), (p2, p3), (p3, p1)]
+}
+
+/// Vertices are shared when they're exactly the same, as in meshes that index them.
+fn vertex_key(point: Tuple) -> VertexKey {
+    // Adding 0 turns -0 into 0.
+    [point.x, point.y, point.z].map(|coordinate| (coordinate + 0.).to_bits())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tetrahedron with its faces wound every which way.
+    fn tetrahedron() -> Vec<Triangle> {
+        let a = Tuple::point(0., 0., 0.);
+        let b = Tuple::point(1., 0., 0.);
+        let c = Tuple::point(0., 1., 0.);
+        let d = Tuple::point(0., 0., 1.);
+
+        vec![
+            Triangle::new(a, b, c),
+            Triangle::new(a, b, d),
+            Triangle::new(a, d, c),
+            Triangle::new(b, c, d),
+        ]
+    }
+
+    fn oriented(triangles: &[Triangle], flip: &[bool]) -> Vec<Triangle> {
+        triangles
+            .iter()
+            .zip(flip)
+            .map(|(triangle, flip)| if *flip { triangle.flipped() } else { *triangle })
+            .collect()
+    }
+
+    fn is_consistent(triangles: &[Triangle]) -> bool {
+        let mut directed: Vec<(VertexKey, VertexKey)> =
+            triangles.iter().flat_map(triangle_edges).collect();
+        directed.sort();
+        let len = directed.len();
+        directed.dedup();
+
+        directed.len() == len
+    }
+
+    #[test]
+    fn windings_are_made_to_agree_across_shared_edges() {
+        let triangles = tetrahedron();
+        assert!(!is_consistent(&triangles));
+
+        let flip = triangles_to_flip(&triangles, false);
+        let oriented = oriented(&triangles, &flip);
+
+        assert!(!flip[0]);
+        assert!(is_consistent(&oriented));
+    }
+
+    fn points_outward(triangles: &[Triangle], center: Tuple) -> bool {
+        triangles
+            .iter()
+            .all(|triangle| Tuple::from(triangle.normal()).dot(triangle.p1 - center) > 0.)
+    }
+
+    #[test]
+    fn closed_meshes_can_be_turned_outward() {
+        let triangles = tetrahedron();
+        let oriented = oriented(&triangles, &triangles_to_flip(&triangles, true));
+
+        assert!(is_consistent(&oriented));
+        assert!(points_outward(&oriented, Tuple::point(0.25, 0.25, 0.25)));
+    }
+
+    #[test]
+    fn separate_pieces_are_oriented_on_their_own() {
+        let offset = Tuple::vector(5., 0., 0.);
+        let mut triangles = tetrahedron();
+        triangles.extend(tetrahedron().iter().map(|triangle| {
+            let Triangle { p1, p2, p3, .. } = triangle.flipped();
+
+            Triangle::new(p1 + offset, p2 + offset, p3 + offset)
+        }));
+
+        let oriented = oriented(&triangles, &triangles_to_flip(&triangles, true));
+
+        assert!(points_outward(
+            &oriented[..4],
+            Tuple::point(0.25, 0.25, 0.25)
+        ));
+        assert!(points_outward(
+            &oriented[4..],
+            Tuple::point(5.25, 0.25, 0.25)
+        ));
+    }
+}
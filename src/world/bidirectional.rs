@@ -0,0 +1,741 @@
+//! Bidirectional path tracing, see `Integrator::Bidirectional`.
+//!
+//! Paths are traced from the points camera rays hit and from the lights, bouncing off surfaces
+//! diffusely and going on through mirrors, glass and portals, and every point of one is joined
+//! to every point of the other with a shadow ray. A path of light can be made that way at any of
+//! its straight stretches between diffuse bounces, so each way of making it counts for as much
+//! of it as the others. Light going through glass straight onto what the camera sees has no such
+//! stretch, so it's traced from the lights all the way to the camera instead, by `caustics`.
+//!
+//! Lights are as bright at any distance, as with the other integrators, which real lights
+//! would be if they got brighter with the square of the distance to what they light. So paths
+//! from them carry that much more light the further they go before their first diffuse bounce.
+
+use std::{
+    f64::consts::PI,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+use super::{cosine_weighted, point_rng, refracted_ray, World};
+use crate::{
+    camera::Camera,
+    color::Color,
+    intersection::{ComputedIntersection, Intersection},
+    light::{Light, LightKind},
+    material::Material,
+    math::{point3::Point3, vec3::Vec3},
+    misc::EPSILON,
+    ray::Ray,
+    sampler::Rng,
+    shape::{BoundingBox, Object},
+};
+
+/// The most mirrors, glass and portals a path goes on through between two diffuse bounces.
+const MAX_SPECULAR_BOUNCES: usize = 8;
+
+/// How many paths from the lights each job of `caustics` traces, with its own random numbers,
+/// so the result doesn't depend on how many threads there are.
+const CAUSTIC_PATHS_PER_JOB: usize = 4096;
+
+/// A point a path bounces off diffusely.
+#[derive(Clone, Copy, Debug)]
+struct Vertex {
+    /// Just off the surface, on the side the path comes from.
+    point: Point3,
+    /// Facing the side the path comes from.
+    normal: Vec3,
+    /// How much of the light falling on the surface it sends back out.
+    reflectance: Color,
+    /// For paths from the camera, how much of the light leaving the point makes it back along
+    /// the path. For paths from lights, how much light makes it to the point along the path.
+    /// Both divided by how likely the path was to be picked.
+    throughput: Color,
+    /// How many of the stretches of the path up to the point are straight lines, rather than
+    /// bent by mirrors, glass or portals, which is how many ways there are to join it up.
+    straight_stretches: usize,
+}
+
+/// Where a ray going on through mirrors, glass and portals ends up.
+enum Step {
+    /// On a surface it bounces off diffusely, after going `length` in all.
+    Surface {
+        vertex: Vertex,
+        straight: bool,
+        length: f64,
+    },
+    /// Out of the world, along the ray, with the throughput it carries.
+    Escaped { ray: Ray, throughput: Color },
+    /// Absorbed, or gone on through too many mirrors and glass.
+    Lost,
+}
+
+/// Directions paths from lights start in: uniformly all around, or towards spheres, to spend
+/// the paths on what they can reach.
+struct Emitter {
+    all_around: bool,
+    /// Centers and radii.
+    spheres: Vec<(Point3, f64)>,
+}
+
+impl Emitter {
+    /// Towards the finite boxes, and all around too if any box isn't.
+    fn towards(boxes: &[BoundingBox]) -> Self {
+        let spheres = boxes
+            .iter()
+            .filter(|bounds| bounds.is_finite())
+            .map(BoundingBox::bounding_sphere)
+            .collect();
+
+        Self {
+            all_around: boxes.iter().any(|bounds| !bounds.is_finite()),
+            spheres,
+        }
+    }
+
+    fn choices(&self) -> usize {
+        self.spheres.len() + usize::from(self.all_around)
+    }
+
+    /// A direction from `origin` and how likely it was to be picked, per unit of solid angle.
+    fn sample(&self, origin: Point3, rng: &mut Rng) -> Option<(Vec3, f64)> {
+        if self.choices() == 0 {
+            return None;
+        }
+        let choice = rng.below(self.choices());
+        let (u, v) = (rng.next_f64(), rng.next_f64());
+        let direction = match self.spheres.get(choice) {
+            Some(&sphere) => match cone_towards(origin, sphere) {
+                Some((axis, cos_max)) => in_cone(axis, 1. - u * (1. - cos_max), v),
+                None => in_cone(Vec3::new(0., 1., 0.), 1. - 2. * u, v),
+            },
+            None => in_cone(Vec3::new(0., 1., 0.), 1. - 2. * u, v),
+        };
+
+        Some((direction, self.density(origin, direction)))
+    }
+
+    fn density(&self, origin: Point3, direction: Vec3) -> f64 {
+        let all_around = 1. / (4. * PI);
+        let towards_spheres: f64 = self
+            .spheres
+            .iter()
+            .map(|&sphere| match cone_towards(origin, sphere) {
+                Some((axis, cos_max)) if direction.dot(axis) >= cos_max => {
+                    1. / (2. * PI * (1. - cos_max))
+                }
+                Some(_) => 0.,
+                None => all_around,
+            })
+            .sum();
+        let all_around = if self.all_around { all_around } else { 0. };
+
+        (towards_spheres + all_around) / self.choices() as f64
+    }
+}
+
+/// The axis of the cone of directions from `origin` to the sphere and the cosine of its half
+/// angle, `None` if `origin` is inside the sphere, which is all around it.
+fn cone_towards(origin: Point3, (center, radius): (Point3, f64)) -> Option<(Vec3, f64)> {
+    let to_center = center - origin;
+    let distance = to_center.magnitude();
+    if distance <= radius {
+        return None;
+    }
+
+    Some((
+        to_center / distance,
+        (1. - (radius / distance).powi(2)).sqrt(),
+    ))
+}
+
+/// The direction at an angle of cosine `cos_theta` from `axis`, turned `turn` of the way around it.
+fn in_cone(axis: Vec3, cos_theta: f64, turn: f64) -> Vec3 {
+    let helper = if axis.x.abs() > 0.9 {
+        Vec3::new(0., 1., 0.)
+    } else {
+        Vec3::new(1., 0., 0.)
+    };
+    let tangent = helper.cross(axis).normalize();
+    let bitangent = axis.cross(tangent);
+    let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+    let angle = 2. * PI * turn;
+
+    tangent * (sin_theta * angle.cos()) + bitangent * (sin_theta * angle.sin()) + axis * cos_theta
+}
+
+fn mean(color: Color) -> f64 {
+    (color.red + color.green + color.blue) / 3.
+}
+
+impl World {
+    /// Light leaving the point off the diffuse color of its material, for
+    /// `Integrator::Bidirectional`, averaged over `samples` paths from it and the lights.
+    pub(super) fn bidirectional_light(
+        &self,
+        material: Material,
+        comps: &ComputedIntersection,
+        lights: &[Light],
+        samples: usize,
+        bounces: usize,
+    ) -> Color {
+        let mut rng = point_rng(comps.over_point, self.seed);
+        let emitter = Emitter::towards(&[self.bounds()]);
+        let start = Vertex {
+            point: comps.over_point,
+            normal: comps.normal_vector,
+            reflectance: material.color * material.diffuse,
+            throughput: Color::white(),
+            straight_stretches: 0,
+        };
+        let samples = samples.max(1);
+        let mut total = Color::black();
+
+        for _ in 0..samples {
+            let (from_camera, escaped) = self.subpath(start, bounces, comps.time, &mut rng);
+            total = total + escaped;
+
+            for light in lights {
+                if let LightKind::Dome(_) = light.kind {
+                    continue;
+                }
+                let first = match bounces {
+                    0 => None,
+                    _ => self.light_vertex(*light, &emitter, comps.time, &mut rng),
+                };
+                let from_light = match first {
+                    Some(first) => self.subpath(first, bounces - 1, comps.time, &mut rng).0,
+                    None => vec![],
+                };
+
+                total = total + self.join(&from_camera, &from_light, *light, bounces, comps.time);
+            }
+        }
+
+        // The sky is gathered the same as with `Integrator::Direct`.
+        let sky = lights
+            .iter()
+            .filter_map(|light| match light.kind {
+                LightKind::Dome(dome) => Some(self.dome_lighting(material, comps, *light, dome)),
+                _ => None,
+            })
+            .fold(Color::black(), |c1, c2| c1 + c2);
+
+        total * (1. / samples as f64) + sky
+    }
+
+    /// Light from the lights going through glass, mirrors and portals onto surfaces the camera
+    /// sees straight, which paths from the camera can't find, for each pixel of its image.
+    /// `paths` paths are traced from each light, spread over what they can go through.
+    ///
+    /// `None` if it's cancelled before it's done.
+    pub(crate) fn caustics(
+        &self,
+        camera: Camera,
+        paths: usize,
+        threads: usize,
+        cancelled: impl Fn() -> bool + Sync,
+    ) -> Option<Vec<Color>> {
+        let (width, height) = (camera.hsize.max(0) as usize, camera.vsize.max(0) as usize);
+        let mut image = vec![Color::black(); width * height];
+        let emitter = Emitter::towards(&self.bouncing_bounds());
+        let lights: Vec<Light> = self
+            .all_lights()
+            .iter()
+            .copied()
+            .filter(|light| !matches!(light.kind, LightKind::Dome(_)))
+            .collect();
+        if emitter.choices() == 0 || lights.is_empty() || paths == 0 {
+            return Some(image);
+        }
+
+        let eye = camera.transform.inverse()? * Point3::origin();
+        let jobs_per_light = paths.div_ceil(CAUSTIC_PATHS_PER_JOB);
+        let jobs = lights.len() * jobs_per_light;
+        let next_job = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel();
+
+        let mut done: Vec<Option<Vec<(usize, Color)>>> = vec![None; jobs];
+        thread::scope(|scope| {
+            for _ in 0..threads.clamp(1, jobs) {
+                let (next_job, lights, emitter, cancelled, sender) =
+                    (&next_job, &lights, &emitter, &cancelled, sender.clone());
+
+                scope.spawn(move || loop {
+                    let job = next_job.fetch_add(1, Ordering::Relaxed);
+                    if job >= jobs || cancelled() {
+                        break;
+                    }
+                    let (light, chunk) = (lights[job / jobs_per_light], job % jobs_per_light);
+                    let count = CAUSTIC_PATHS_PER_JOB.min(paths - chunk * CAUSTIC_PATHS_PER_JOB);
+                    let mut rng = Rng::new(self.seed ^ ((job as u64) << 20));
+                    let splats: Vec<(usize, Color)> = (0..count)
+                        .filter_map(|_| {
+                            let vertex =
+                                self.light_vertex(light, emitter, camera.time, &mut rng)?;
+                            let (x, y, importance) = self.towards_camera(vertex, camera, eye)?;
+
+                            Some((y * width + x, vertex.reflectance * importance))
+                        })
+                        .collect();
+                    if sender.send((job, splats)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(sender);
+
+            for (job, splats) in receiver {
+                done[job] = Some(splats);
+            }
+        });
+
+        // Added up in the same order whatever thread traced them.
+        for splats in done {
+            for (index, light) in splats? {
+                image[index] = image[index] + light * (1. / (PI * paths as f64));
+            }
+        }
+
+        Some(image)
+    }
+
+    /// The pixel the light the vertex sends towards the camera shows up in, and how much of
+    /// it, if the vertex is lit only through glass, mirrors or portals and the camera sees it.
+    fn towards_camera(
+        &self,
+        vertex: Vertex,
+        camera: Camera,
+        eye: Point3,
+    ) -> Option<(usize, usize, Color)> {
+        // Lit straight, so paths from the camera find it.
+        if vertex.straight_stretches > 0 {
+            return None;
+        }
+        let (x, y, importance) = camera.pixel_showing(vertex.point)?;
+        let to_eye = eye - vertex.point;
+        let distance = to_eye.magnitude();
+        let cosine = vertex.normal.dot(to_eye / distance);
+        if cosine <= 0. || !self.unblocked(vertex.point, eye, camera.time, None) {
+            return None;
+        }
+
+        Some((
+            x,
+            y,
+            vertex.throughput * (cosine * importance / distance.powi(2)),
+        ))
+    }
+
+    /// Bounds of everything in the world.
+    fn bounds(&self) -> BoundingBox {
+        self.objects
+            .iter()
+            .map(Object::bounding_box)
+            .reduce(|box1, box2| box1.union(&box2))
+            .unwrap_or_else(BoundingBox::empty)
+    }
+
+    /// The first point a path from the light bounces off diffusely, if it does.
+    fn light_vertex(
+        &self,
+        light: Light,
+        emitter: &Emitter,
+        time: f64,
+        rng: &mut Rng,
+    ) -> Option<Vertex> {
+        let (direction, density) = emitter.sample(light.position, rng)?;
+        let falloff = light.spot_falloff(light.position + direction);
+        if falloff == 0. {
+            return None;
+        }
+        let own_geometry = light.attached_to.map(|index| &self.objects[index]);
+        let ray = Ray::new(light.position, direction).at_time(time);
+        let throughput = light.intensity * (PI * falloff / density);
+
+        match self.scatter(ray, throughput, rng, own_geometry) {
+            Step::Surface {
+                vertex,
+                straight,
+                length,
+            } => Some(Vertex {
+                throughput: vertex.throughput * length.powi(2),
+                straight_stretches: usize::from(straight),
+                ..vertex
+            }),
+            _ => None,
+        }
+    }
+
+    /// The path from `start` on, bouncing off surfaces diffusely up to `bounces` more times,
+    /// and the light of the background where it leaves the world, if it does.
+    fn subpath(
+        &self,
+        start: Vertex,
+        bounces: usize,
+        time: f64,
+        rng: &mut Rng,
+    ) -> (Vec<Vertex>, Color) {
+        let mut vertices = vec![start];
+
+        while vertices.len() <= bounces {
+            let last = vertices[vertices.len() - 1];
+            let direction = cosine_weighted(last.normal, (rng.next_f64(), rng.next_f64()));
+            let ray = Ray::new(last.point, direction).at_time(time);
+
+            match self.scatter(ray, last.throughput * last.reflectance, rng, None) {
+                Step::Surface {
+                    vertex, straight, ..
+                } => vertices.push(Vertex {
+                    straight_stretches: last.straight_stretches + usize::from(straight),
+                    ..vertex
+                }),
+                Step::Escaped { ray, throughput } => {
+                    return (vertices, throughput * self.background(ray));
+                }
+                Step::Lost => break,
+            }
+        }
+
+        (vertices, Color::black())
+    }
+
+    /// Follows the ray through mirrors, glass and portals up to the first surface it bounces
+    /// off diffusely, picking what happens at each one by how much light goes each way.
+    fn scatter(
+        &self,
+        mut ray: Ray,
+        mut throughput: Color,
+        rng: &mut Rng,
+        ignored: Option<&Object>,
+    ) -> Step {
+        let mut length = 0.;
+
+        for bounce in 0..=MAX_SPECULAR_BOUNCES {
+            let mut intersections = self.intersect_unsorted(ray);
+            if let Some(ignored) = ignored {
+                intersections.retain(|i| !ignored.includes(i.object));
+            }
+            let Some(hit) = Intersection::hit(&intersections).copied() else {
+                return Step::Escaped { ray, throughput };
+            };
+            let comps = Self::prepare_computations(hit, ray, intersections);
+            length += hit.t * ray.direction.magnitude();
+
+            if let Some(through) = self.continuation(&comps) {
+                ray = through;
+                continue;
+            }
+
+            let material =
+                comps
+                    .object
+                    .material()
+                    .at_time(comps.object, comps.over_point, comps.time);
+            let refracted = refracted_ray(&comps).filter(|_| material.transparency > 0.);
+            let (reflective, transparency) = match refracted {
+                Some(_) if material.reflective > 0. => {
+                    let reflectance = comps.schlick();
+
+                    (
+                        material.reflective * reflectance,
+                        material.transparency * (1. - reflectance),
+                    )
+                }
+                Some(_) => (material.reflective, material.transparency),
+                None => (material.reflective, 0.),
+            };
+            let diffuse = material.diffuse * mean(material.color);
+            let total = diffuse + reflective + transparency;
+            if total <= 0. {
+                return Step::Lost;
+            }
+
+            let pick = rng.next_f64() * total;
+            if pick < diffuse {
+                return Step::Surface {
+                    vertex: Vertex {
+                        point: comps.over_point,
+                        normal: comps.normal_vector,
+                        reflectance: material.color * material.diffuse,
+                        throughput: throughput * (total / diffuse),
+                        straight_stretches: 0,
+                    },
+                    straight: bounce == 0,
+                    length,
+                };
+            }
+
+            throughput = throughput * total;
+            ray = match refracted {
+                Some(refracted) if pick >= diffuse + reflective => {
+                    throughput = throughput * self.kept_inside(&comps, refracted);
+                    refracted
+                }
+                _ => Ray::new(comps.over_point, comps.reflect_vector).at_time(comps.time),
+            };
+        }
+
+        Step::Lost
+    }
+
+    /// Light making it along every way of joining a point of the path from the camera to the
+    /// light itself or to a point of the path from it, at most `bounces` bounces long in all.
+    fn join(
+        &self,
+        from_camera: &[Vertex],
+        from_light: &[Vertex],
+        light: Light,
+        bounces: usize,
+        time: f64,
+    ) -> Color {
+        let own_geometry = light.attached_to.map(|index| &self.objects[index]);
+        let mut total = Color::black();
+
+        for (bounced, eye) in from_camera.iter().enumerate() {
+            let to_light = light.position - eye.point;
+            let cosine = eye.normal.dot(to_light.normalize());
+            if cosine > 0. && self.unblocked(eye.point, light.position, time, own_geometry) {
+                let ways = (eye.straight_stretches + 1) as f64;
+
+                total = total
+                    + eye.throughput
+                        * eye.reflectance
+                        * light.intensity_at(eye.point)
+                        * (cosine / ways);
+            }
+
+            for lit in from_light.iter().take(bounces.saturating_sub(bounced)) {
+                let between = lit.point - eye.point;
+                let distance_squared = between.magnitude_squared();
+                if distance_squared < EPSILON {
+                    continue;
+                }
+                let direction = between / distance_squared.sqrt();
+                let (eye_cosine, lit_cosine) =
+                    (eye.normal.dot(direction), -lit.normal.dot(direction));
+                if eye_cosine <= 0.
+                    || lit_cosine <= 0.
+                    || !self.unblocked(eye.point, lit.point, time, None)
+                {
+                    continue;
+                }
+                let ways = (eye.straight_stretches + lit.straight_stretches + 1) as f64;
+                let geometry = eye_cosine * lit_cosine / distance_squared;
+
+                total = total
+                    + eye.throughput
+                        * eye.reflectance
+                        * lit.reflectance
+                        * lit.throughput
+                        * (geometry / (PI * PI * ways));
+            }
+        }
+
+        total
+    }
+
+    /// Whether nothing casting shadows, but `ignored`, is between the two points.
+    fn unblocked(&self, from: Point3, to: Point3, time: f64, ignored: Option<&Object>) -> bool {
+        let vector = to - from;
+        let distance = vector.magnitude();
+        if distance < EPSILON {
+            return true;
+        }
+        let ray = Ray::new(from, vector / distance).at_time(time);
+
+        !self.intersect_unsorted(ray).iter().any(|i| {
+            i.t >= 0.
+                && i.t < distance
+                && i.object.material.casts_shadows
+                && !ignored.is_some_and(|ignored| ignored.includes(i.object))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::tuple::Tuple;
+    use crate::{
+        integrator::Integrator, math::matrix4::Matrix4, math::transformations::view_transform,
+        render_settings::RenderSettings,
+    };
+
+    /// Lit only diffusely, so every integrator shades it the same way.
+    fn matte(color: Color) -> Material {
+        let mut material = Material::new();
+        material.color = color;
+        material.ambient = 0.;
+        material.specular = 0.;
+
+        material
+    }
+
+    fn close(a: Color, b: Color, tolerance: f64) -> bool {
+        [(a.red, b.red), (a.green, b.green), (a.blue, b.blue)]
+            .iter()
+            .all(|(a, b)| (a - b).abs() <= tolerance * b.abs().max(1e-3))
+    }
+
+    fn floor() -> Object {
+        let mut floor = Object::plane();
+        floor.set_material(matte(Color::new(0.8, 0.6, 0.4)));
+
+        floor
+    }
+
+    #[test]
+    fn light_straight_from_the_lights_is_the_same_as_with_direct_lighting() {
+        let down = Ray::new(Point3::new(0.5, 3., 0.2), Vec3::new(0., -1., 0.));
+
+        for height in [1., 3.] {
+            let mut w = World::new();
+            w.add_object(floor());
+            w.add_light(Light::point_light(
+                Point3::new(0., height, 0.),
+                Color::new(1., 0.9, 0.8),
+            ));
+            let direct = w.color_at(down);
+            w.integrator = Integrator::Bidirectional {
+                samples: 4,
+                bounces: 3,
+            };
+
+            assert!(close(w.color_at(down), direct, 1e-9));
+        }
+    }
+
+    #[test]
+    fn bounced_light_is_the_same_as_path_tracing_it() {
+        let mut w = World::new();
+        w.add_object(floor());
+        let mut wall = Object::plane();
+        wall.set_transform(Matrix4::translation(0., 0., 3.) * Matrix4::rotation_x(PI / 2.));
+        wall.set_material(matte(Color::new(0.2, 0.9, 0.3)));
+        w.add_object(wall);
+        // Shades the middle of the floor.
+        let mut blocker = Object::cube();
+        blocker.set_transform(Matrix4::translation(0., 1.5, 0.5) * Matrix4::scaling(0.4, 0.1, 0.4));
+        blocker.set_material(matte(Color::white()));
+        w.add_object(blocker);
+        w.add_light(Light::point_light(Point3::new(0., 3., 1.), Color::white()));
+        let down = Ray::new(Point3::new(0., 5., 0.), Vec3::new(0., -1., 0.));
+
+        w.integrator = Integrator::Bidirectional {
+            samples: 4,
+            bounces: 0,
+        };
+        assert_eq!(w.color_at(down), Color::black());
+
+        w.integrator = Integrator::PathTrace {
+            samples: 8_000,
+            bounces: 1,
+        };
+        let path_traced = w.color_at(down);
+        w.integrator = Integrator::Bidirectional {
+            samples: 8_000,
+            bounces: 1,
+        };
+        let bidirectional = w.color_at(down);
+
+        assert!(path_traced.green > 0.01);
+        assert!(close(bidirectional, path_traced, 0.05));
+    }
+
+    /// A glass ball over a floor, lit from right above it.
+    fn lens() -> (World, Camera) {
+        let mut w = World::new();
+        w.add_object(floor());
+        let mut ball = Object::sphere();
+        ball.set_transform(Matrix4::translation(0., 2., 0.));
+        ball.set_material({
+            let mut material = matte(Color::white());
+            material.diffuse = 0.;
+            material.transparency = 1.;
+            material.refractive_index = 1.5;
+            material
+        });
+        w.add_object(ball);
+        w.add_light(Light::point_light(Point3::new(0., 5., 0.), Color::white()));
+        w.integrator = Integrator::Bidirectional {
+            samples: 4,
+            bounces: 0,
+        };
+        let mut camera = Camera::new(16, 16, PI / 3.);
+        camera.transform = view_transform(
+            Tuple::point(0., 3., -6.),
+            Tuple::point(0., 0., 0.),
+            Tuple::vector(0., 1., 0.),
+        );
+
+        (w, camera)
+    }
+
+    #[test]
+    fn glass_focuses_light_onto_what_is_behind_it() {
+        let (w, camera) = lens();
+        let settings = RenderSettings {
+            threads: Some(1),
+            ..Default::default()
+        };
+        let image = camera.render_with_settings(&w, &settings, |_, _| {});
+        let brightness = |point: Point3| {
+            let (x, y, _) = camera.pixel_showing(point).unwrap();
+
+            mean(image.pixel_at(x as i32, y as i32))
+        };
+
+        let lit_straight = brightness(Point3::new(2.5, 0., 0.));
+        assert!(lit_straight > 0.1);
+        // In the shadow of the ball but for the light it focuses.
+        assert!(brightness(Point3::new(0., 0., 0.)) > 2. * lit_straight);
+        assert!(brightness(Point3::new(1.2, 0., 0.)) < lit_straight);
+
+        let threaded = camera.render_with_settings(
+            &w,
+            &RenderSettings {
+                threads: Some(3),
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+        assert_eq!(threaded.to_ppm(), image.to_ppm());
+    }
+
+    #[test]
+    fn paths_from_lights_start_towards_what_they_can_reach() {
+        let mut rng = Rng::new(2);
+        let sphere =
+            BoundingBox::from_points(&[Tuple::point(-1., -1., -1.), Tuple::point(1., 1., 1.)]);
+        let origin = Point3::new(0., 10., 0.);
+        let emitter = Emitter::towards(std::slice::from_ref(&sphere));
+
+        for _ in 0..1000 {
+            let (direction, density) = emitter.sample(origin, &mut rng).unwrap();
+            let (axis, cos_max) = cone_towards(origin, sphere.bounding_sphere()).unwrap();
+
+            assert!(direction.dot(axis) >= cos_max - 1e-9);
+            assert!((density - 1. / (2. * PI * (1. - cos_max))).abs() < 1e-6);
+        }
+
+        // Densities add up to 1 over every direction.
+        let emitter = Emitter {
+            all_around: true,
+            spheres: vec![sphere.bounding_sphere()],
+        };
+        let samples = 200_000;
+        let total = (0..samples)
+            .map(|_| {
+                let direction = in_cone(Vec3::new(0., 1., 0.), rng.range(-1., 1.), rng.next_f64());
+
+                emitter.density(origin, direction) * 4. * PI
+            })
+            .sum::<f64>()
+            / samples as f64;
+        assert!((total - 1.).abs() < 0.02);
+    }
+}
@@ -0,0 +1,577 @@
+use crate::error::{RayTracerError, Result};
+
+/// How deep collections can nest, block and flow ones together, before a document is
+/// rejected rather than parsed by recursing that deep.
+const MAX_DEPTH: usize = 128;
+
+/// A parsed YAML document, supporting the subset used by scene files:
+/// block and flow collections, plain and quoted scalars, and comments.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Yaml {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Sequence(Vec<Yaml>),
+    /// Entries are kept in the order they were written.
+    Mapping(Vec<(String, Yaml)>),
+}
+
+impl Yaml {
+    pub fn parse(source: &str) -> Result<Yaml> {
+        let mut lines: Vec<Line> = source
+            .lines()
+            .enumerate()
+            .filter_map(|(index, line)| Line::new(index + 1, line))
+            .collect();
+
+        if lines.is_empty() {
+            return Ok(Yaml::Null);
+        }
+
+        let mut position = 0;
+        let indent = lines[0].indent;
+        let document = parse_block(&mut lines, &mut position, indent, 0)?;
+
+        match lines.get(position) {
+            Some(line) => Err(invalid_data(line.number, "unexpected indentation")),
+            None => Ok(document),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Yaml> {
+        self.as_mapping()?
+            .iter()
+            .find(|(entry_key, _)| entry_key == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Yaml::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Yaml::Bool(boolean) => Some(*boolean),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Yaml::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    pub fn as_sequence(&self) -> Option<&[Yaml]> {
+        match self {
+            Yaml::Sequence(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_mapping(&self) -> Option<&[(String, Yaml)]> {
+        match self {
+            Yaml::Mapping(entries) => Some(entries),
+            _ => None,
+        }
+    }
+}
+
+struct Line {
+    number: usize,
+    indent: usize,
+    content: String,
+}
+
+impl Line {
+    /// Returns `None` for lines without content.
+    fn new(number: usize, line: &str) -> Option<Self> {
+        let content = strip_comment(line).trim_end();
+        let trimmed = content.trim_start();
+
+        if trimmed.is_empty() || trimmed == "---" {
+            None
+        } else {
+            Some(Self {
+                number,
+                indent: content.len() - trimmed.len(),
+                content: trimmed.to_string(),
+            })
+        }
+    }
+
+    fn is_sequence_item(&self) -> bool {
+        self.content == "-" || self.content.starts_with("- ")
+    }
+}
+
+fn parse_block(
+    lines: &mut [Line],
+    position: &mut usize,
+    indent: usize,
+    depth: usize,
+) -> Result<Yaml> {
+    let line = &lines[*position];
+
+    if line.is_sequence_item() {
+        parse_sequence(lines, position, indent, depth)
+    } else if line.content.starts_with(['[', '{', '"', '\'']) || split_key(&line.content).is_none()
+    {
+        let number = line.number;
+        let value = parse_inline(&line.content, number, depth)?;
+        *position += 1;
+
+        Ok(value)
+    } else {
+        parse_mapping(lines, position, indent, depth)
+    }
+}
+
+fn parse_sequence(
+    lines: &mut [Line],
+    position: &mut usize,
+    indent: usize,
+    depth: usize,
+) -> Result<Yaml> {
+    check_depth(&lines[*position], depth)?;
+    let mut items = vec![];
+
+    while let Some(line) = lines.get(*position) {
+        if line.indent != indent || !line.is_sequence_item() {
+            break;
+        }
+
+        let rest = line.content[1..].trim_start().to_string();
+
+        if rest.is_empty() {
+            *position += 1;
+            items.push(parse_nested(lines, position, indent, depth + 1)?);
+        } else if split_key(&rest).is_some() && !rest.starts_with(['[', '{', '"', '\'']) {
+            // A mapping starting on the same line as the dash,
+            // continue as if it had been written on its own line.
+            let line = &mut lines[*position];
+            line.indent += line.content.len() - rest.len();
+            line.content = rest;
+            let item_indent = line.indent;
+            items.push(parse_mapping(lines, position, item_indent, depth + 1)?);
+        } else {
+            let number = line.number;
+            *position += 1;
+            items.push(parse_inline(&rest, number, depth + 1)?);
+        }
+    }
+
+    Ok(Yaml::Sequence(items))
+}
+
+fn parse_mapping(
+    lines: &mut [Line],
+    position: &mut usize,
+    indent: usize,
+    depth: usize,
+) -> Result<Yaml> {
+    check_depth(&lines[*position], depth)?;
+    let mut entries = vec![];
+
+    while let Some(line) = lines.get(*position) {
+        if line.indent != indent || line.is_sequence_item() {
+            break;
+        }
+
+        let number = line.number;
+        let (key, value) = split_key(&line.content)
+            .ok_or_else(|| invalid_data(number, "expected a `key: value` pair"))?;
+        let key = unquote(key);
+        let value = value.to_string();
+        *position += 1;
+
+        let value = if value.is_empty() {
+            match lines.get(*position) {
+                // Sequences are allowed at the same indentation as their key.
+                Some(next) if next.indent == indent && next.is_sequence_item() => {
+                    parse_sequence(lines, position, indent, depth + 1)?
+                }
+                _ => parse_nested(lines, position, indent, depth + 1)?,
+            }
+        } else {
+            parse_inline(&value, number, depth + 1)?
+        };
+
+        entries.push((key, value));
+    }
+
+    Ok(Yaml::Mapping(entries))
+}
+
+fn check_depth(line: &Line, depth: usize) -> Result<()> {
+    if depth < MAX_DEPTH {
+        Ok(())
+    } else {
+        Err(invalid_data(line.number, "collections nested too deeply"))
+    }
+}
+
+/// Parses the block indented under the current one, if any.
+fn parse_nested(
+    lines: &mut [Line],
+    position: &mut usize,
+    indent: usize,
+    depth: usize,
+) -> Result<Yaml> {
+    match lines.get(*position) {
+        Some(next) if next.indent > indent => {
+            let nested_indent = next.indent;
+            parse_block(lines, position, nested_indent, depth)
+        }
+        _ => Ok(Yaml::Null),
+    }
+}
+
+fn parse_inline(source: &str, line: usize, depth: usize) -> Result<Yaml> {
+    let mut parser = FlowParser {
+        chars: source.chars().collect(),
+        position: 0,
+        line,
+        depth,
+    };
+
+    let value = parser.value()?;
+    parser.skip_whitespace();
+
+    if parser.position < parser.chars.len() {
+        Err(invalid_data(line, "unexpected characters after value"))
+    } else {
+        Ok(value)
+    }
+}
+
+struct FlowParser {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+    /// How deep the collection being parsed is.
+    depth: usize,
+}
+
+impl FlowParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        self.skip_whitespace();
+
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(invalid_data(self.line, &format!("expected `{}`", expected)))
+        }
+    }
+
+    fn value(&mut self) -> Result<Yaml> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('[') | Some('{') if self.depth >= MAX_DEPTH => {
+                Err(invalid_data(self.line, "collections nested too deeply"))
+            }
+            Some('[') => self.nested(Self::sequence),
+            Some('{') => self.nested(Self::mapping),
+            Some('"') | Some('\'') => self.quoted().map(Yaml::String),
+            _ => Ok(scalar(&self.plain())),
+        }
+    }
+
+    fn nested(&mut self, collection: fn(&mut Self) -> Result<Yaml>) -> Result<Yaml> {
+        self.depth += 1;
+        let value = collection(self);
+        self.depth -= 1;
+
+        value
+    }
+
+    fn sequence(&mut self) -> Result<Yaml> {
+        self.expect('[')?;
+        let mut items = vec![];
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(']') {
+                self.position += 1;
+                return Ok(Yaml::Sequence(items));
+            }
+
+            items.push(self.value()?);
+            self.skip_whitespace();
+
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(']') => {}
+                _ => return Err(invalid_data(self.line, "expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn mapping(&mut self) -> Result<Yaml> {
+        self.expect('{')?;
+        let mut entries = vec![];
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some('}') {
+                self.position += 1;
+                return Ok(Yaml::Mapping(entries));
+            }
+
+            let key = match self.peek() {
+                Some('"') | Some('\'') => self.quoted()?,
+                _ => self.plain(),
+            };
+            self.expect(':')?;
+            entries.push((key, self.value()?));
+            self.skip_whitespace();
+
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some('}') => {}
+                _ => return Err(invalid_data(self.line, "expected `,` or `}`")),
+            }
+        }
+    }
+
+    fn quoted(&mut self) -> Result<String> {
+        let quote = self.chars[self.position];
+        self.position += 1;
+        let mut string = String::new();
+
+        while let Some(c) = self.peek() {
+            self.position += 1;
+
+            match c {
+                c if c == quote => return Ok(string),
+                '\\' if quote == '"' => {
+                    let escaped = self.peek().unwrap_or('\\');
+                    self.position += 1;
+                    string.push(match escaped {
+                        'n' => '\n',
+                        't' => '\t',
+                        other => other,
+                    });
+                }
+                c => string.push(c),
+            }
+        }
+
+        Err(invalid_data(self.line, "unterminated string"))
+    }
+
+    /// A plain scalar, ending at the end of the line or a flow delimiter.
+    fn plain(&mut self) -> String {
+        let start = self.position;
+
+        while let Some(c) = self.peek() {
+            if matches!(c, ',' | ']' | '}') {
+                break;
+            }
+            // Inside flow mappings, `key: value` ends the key at the colon.
+            if c == ':'
+                && self
+                    .chars
+                    .get(self.position + 1)
+                    .is_none_or(|next| next.is_whitespace())
+            {
+                break;
+            }
+            self.position += 1;
+        }
+
+        self.chars[start..self.position]
+            .iter()
+            .collect::<String>()
+            .trim()
+            .to_string()
+    }
+}
+
+fn scalar(plain: &str) -> Yaml {
+    match plain {
+        "" | "~" | "null" => Yaml::Null,
+        "true" => Yaml::Bool(true),
+        "false" => Yaml::Bool(false),
+        _ => match plain.parse() {
+            Ok(number) => Yaml::Number(number),
+            Err(_) => Yaml::String(plain.to_string()),
+        },
+    }
+}
+
+/// Splits `key: value` (or `key:`) at the first colon outside of quotes and brackets.
+fn split_key(content: &str) -> Option<(&str, &str)> {
+    let mut depth = 0;
+    let mut quote = None;
+
+    for (index, c) in content.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '[') | (None, '{') => depth += 1,
+            (None, ']') | (None, '}') => depth -= 1,
+            (None, ':') if depth == 0 => {
+                let rest = &content[index + 1..];
+
+                if rest.is_empty() || rest.starts_with(' ') {
+                    return Some((content[..index].trim(), rest.trim()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn unquote(key: &str) -> String {
+    let quoted = key.len() >= 2
+        && ((key.starts_with('"') && key.ends_with('"'))
+            || (key.starts_with('\'') && key.ends_with('\'')));
+
+    if quoted {
+        key[1..key.len() - 1].to_string()
+    } else {
+        key.to_string()
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '#') if previous.is_whitespace() => return &line[..index],
+            _ => {}
+        }
+        previous = c;
+    }
+
+    line
+}
+
+fn invalid_data(line: usize, message: &str) -> RayTracerError {
+    RayTracerError::Parse(format!("line {}: {}", line, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_scalars() {
+        assert_eq!(Yaml::parse("3.5").unwrap(), Yaml::Number(3.5));
+        assert_eq!(Yaml::parse("-2").unwrap(), Yaml::Number(-2.));
+        assert_eq!(Yaml::parse("true").unwrap(), Yaml::Bool(true));
+        assert_eq!(Yaml::parse("~").unwrap(), Yaml::Null);
+        assert_eq!(
+            Yaml::parse("white-material").unwrap(),
+            Yaml::String("white-material".to_string())
+        );
+        assert_eq!(
+            Yaml::parse("\"a # b\"").unwrap(),
+            Yaml::String("a # b".to_string())
+        );
+    }
+
+    #[test]
+    fn parsing_flow_collections() {
+        let yaml = Yaml::parse("[ translate, 1, -0.5, [2, 3] ] # comment").unwrap();
+
+        assert_eq!(
+            yaml,
+            Yaml::Sequence(vec![
+                Yaml::String("translate".to_string()),
+                Yaml::Number(1.),
+                Yaml::Number(-0.5),
+                Yaml::Sequence(vec![Yaml::Number(2.), Yaml::Number(3.)]),
+            ])
+        );
+
+        let yaml = Yaml::parse("{ color: [1, 0, 0], diffuse: 0.7 }").unwrap();
+        assert_eq!(yaml.get("diffuse"), Some(&Yaml::Number(0.7)));
+        assert_eq!(yaml.get("color").unwrap().as_sequence().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn parsing_a_scene_like_document() {
+        let source = "
+# A comment
+- add: camera
+  width: 100
+  from: [ -6, 6, -10 ]
+
+- define: standard-transform
+  value:
+    - [ translate, 1, -1, 1 ]
+    - [ scale, 0.5, 0.5, 0.5 ]
+
+- add: sphere
+  material:
+    color: [ 1, 0.2, 1 ]
+    diffuse: 0.7
+  transform:
+  - standard-transform
+";
+        let yaml = Yaml::parse(source).unwrap();
+        let items = yaml.as_sequence().unwrap();
+
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].get("add").unwrap().as_str(), Some("camera"));
+        assert_eq!(items[0].get("width").unwrap().as_f64(), Some(100.));
+        assert_eq!(
+            items[1].get("value").unwrap().as_sequence().unwrap().len(),
+            2
+        );
+        let material = items[2].get("material").unwrap();
+        assert_eq!(material.get("diffuse").unwrap().as_f64(), Some(0.7));
+        assert_eq!(
+            items[2].get("transform").unwrap(),
+            &Yaml::Sequence(vec![Yaml::String("standard-transform".to_string())])
+        );
+    }
+
+    #[test]
+    fn malformed_documents_are_rejected() {
+        assert!(Yaml::parse("[1, 2").is_err());
+        assert!(Yaml::parse("key: value\nno colon here").is_err());
+        assert!(Yaml::parse("a: 1\n    b: 2").is_err());
+    }
+
+    #[test]
+    fn deeply_nested_documents_are_rejected_instead_of_overflowing_the_stack() {
+        let flow = "[".repeat(200_000);
+        let block = (0..200)
+            .map(|depth| format!("{}- ", " ".repeat(2 * depth)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let nested_but_fine = format!("{}{}", "[".repeat(100), "]".repeat(100));
+
+        assert!(matches!(Yaml::parse(&flow), Err(RayTracerError::Parse(_))));
+        assert!(matches!(Yaml::parse(&block), Err(RayTracerError::Parse(_))));
+        assert!(Yaml::parse(&nested_but_fine).is_ok());
+    }
+}